@@ -1,6 +1,6 @@
 use std::{env, fmt};
 
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use rpki::ca::idexchange;
 
@@ -8,7 +8,7 @@ use crate::{
     cli::{
         options::{
             BulkCaCommand, CaCommand, Command, KrillInitDetails, Options,
-            PubServerCommand,
+            PubServerCommand, RrdpArchiveExport, TokenCommand,
         },
         report::{ApiResponse, ReportError},
     },
@@ -16,11 +16,12 @@ use crate::{
         api::{
             AllCertAuthIssues, ApiRepositoryContact, AspaDefinitionUpdates,
             BgpSecDefinitionUpdates, CaRepoDetails, CertAuthIssues,
-            ChildCaInfo, ChildrenConnectionStats, ParentCaContact,
-            ParentStatuses, PublisherDetails, PublisherList, RepoStatus,
-            Token,
+            ChangeRequest, ChildCaInfo, ChildrenConnectionStats,
+            ConfiguredRoas, ParentCaContact, ParentStatuses,
+            PublisherDetails, PublisherList, RepoStatus, RoaImportResult,
+            RoaImportStatus, RrdpArchiveQuery, RrdpArchiveState, Token,
         },
-        bgp::BgpAnalysisAdvice,
+        bgp::{BgpAnalysisAdvice, BgpAnalysisReport},
         error::KrillIoError,
         util::{file, httpclient},
     },
@@ -96,6 +97,34 @@ async fn post_json_with_opt_response<T: DeserializeOwned>(
         .map_err(Error::HttpClientError)
 }
 
+/// Performs a POST of route or ASPA updates. These are applied directly,
+/// unless change approval is required, in which case the server responds
+/// with the change request that was submitted for them.
+async fn post_json_change(
+    server: &idexchange::ServiceUri,
+    token: &Token,
+    path: &str,
+    data: impl Serialize,
+) -> Result<ApiResponse, Error> {
+    let request: Option<ChangeRequest> =
+        post_json_with_opt_response(server, token, path, data).await?;
+    match request {
+        None => Ok(ApiResponse::Empty),
+        Some(request) => Ok(ApiResponse::ChangeRequest(request)),
+    }
+}
+
+/// The response to trying a route update: advice if the update would
+/// result in invalids, or the change request if it was submitted for
+/// approval.
+#[derive(Deserialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+enum TryUpdateResponse {
+    ChangeRequest(ChangeRequest),
+    Advice(BgpAnalysisAdvice),
+}
+
 async fn delete(
     server: &idexchange::ServiceUri,
     token: &Token,
@@ -123,6 +152,13 @@ impl KrillClient {
         if let Some(string) = res.report(format)? {
             println!("{}", string)
         }
+
+        // A rejected import is reported, but it is still a failure.
+        if let ApiResponse::RoaImport(result) = &res {
+            if result.status() == RoaImportStatus::Rejected {
+                return Err(Error::RoaImportRejected);
+            }
+        }
         Ok(())
     }
 
@@ -149,6 +185,7 @@ impl KrillClient {
             Command::Bulk(cmd) => client.bulk(cmd).await,
             Command::CertAuth(cmd) => client.certauth(cmd).await,
             Command::PubServer(cmd) => client.publishers(cmd).await,
+            Command::Tokens(cmd) => client.tokens(cmd).await,
             Command::Init(details) => client.init_config(details),
             #[cfg(feature = "multi-user")]
             Command::User(cmd) => client.user(cmd),
@@ -174,6 +211,35 @@ impl KrillClient {
         Ok(ApiResponse::Info(info))
     }
 
+    async fn tokens(
+        &self,
+        command: TokenCommand,
+    ) -> Result<ApiResponse, Error> {
+        match command {
+            TokenCommand::List => {
+                let list =
+                    get_json(&self.server, &self.token, "api/v1/tokens")
+                        .await?;
+                Ok(ApiResponse::ApiTokens(list))
+            }
+            TokenCommand::Add(request) => {
+                let issued = post_json_with_response(
+                    &self.server,
+                    &self.token,
+                    "api/v1/tokens",
+                    request,
+                )
+                .await?;
+                Ok(ApiResponse::ApiTokenIssued(issued))
+            }
+            TokenCommand::Revoke(name) => {
+                let uri = format!("api/v1/tokens/{}", name);
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+        }
+    }
+
     async fn bulk(
         &self,
         command: BulkCaCommand,
@@ -412,13 +478,108 @@ impl KrillClient {
 
             CaCommand::RouteAuthorizationsUpdate(handle, updates) => {
                 let uri = format!("api/v1/cas/{}/routes", handle);
-                post_json(&self.server, &self.token, &uri, updates).await?;
+                post_json_change(&self.server, &self.token, &uri, updates)
+                    .await
+            }
+
+            CaCommand::RouteAuthorizationsExport(handle, format) => {
+                let uri = format!("api/v1/cas/{}/routes", handle);
+                let roas: ConfiguredRoas =
+                    get_json(&self.server, &self.token, &uri).await?;
+                let roas: Vec<_> = roas
+                    .unpack()
+                    .into_iter()
+                    .map(|roa| roa.roa_configuration().clone())
+                    .collect();
+                Ok(ApiResponse::GenericBody(format.export(&roas)))
+            }
+
+            CaCommand::RouteAuthorizationsImport(handle, import) => {
+                let uri = format!("api/v1/cas/{}/routes", handle);
+                let current: ConfiguredRoas =
+                    get_json(&self.server, &self.token, &uri).await?;
+                let current: Vec<_> = current
+                    .unpack()
+                    .into_iter()
+                    .map(|roa| roa.roa_configuration().clone())
+                    .collect();
+
+                let updates = import
+                    .updates(&current)
+                    .map_err(|e| Error::InputError(e.to_string()))?;
+                if updates.is_empty() {
+                    return Ok(ApiResponse::RoaImport(
+                        RoaImportResult::unchanged(),
+                    ));
+                }
+
+                // Always check the effect of the updates first, so that
+                // they are not applied if they would result in invalids.
+                let dry_run_uri =
+                    format!("api/v1/cas/{}/routes/analysis/dryrun", handle);
+                let effect: BgpAnalysisReport = post_json_with_response(
+                    &self.server,
+                    &self.token,
+                    &dry_run_uri,
+                    &updates,
+                )
+                .await?;
+
+                let status = if import.dry_run() {
+                    RoaImportStatus::DryRun
+                } else if effect.contains_invalids() && !import.force() {
+                    RoaImportStatus::Rejected
+                } else {
+                    // If change approval is required, the updates are
+                    // submitted as a new change request instead.
+                    let request: Option<ChangeRequest> =
+                        post_json_with_opt_response(
+                            &self.server,
+                            &self.token,
+                            &uri,
+                            &updates,
+                        )
+                        .await?;
+                    if let Some(request) = request {
+                        return Ok(ApiResponse::RoaImport(
+                            RoaImportResult::submitted(
+                                request.id(),
+                                updates,
+                                effect,
+                            ),
+                        ));
+                    }
+                    RoaImportStatus::Applied
+                };
+
+                Ok(ApiResponse::RoaImport(RoaImportResult::new(
+                    status, updates, effect,
+                )))
+            }
+
+            CaCommand::RouteAuthorizationsSchedule(handle, request) => {
+                let uri = format!("api/v1/cas/{}/routes/scheduled", handle);
+                post_json_change(&self.server, &self.token, &uri, request)
+                    .await
+            }
+
+            CaCommand::RouteAuthorizationsScheduledList(handle) => {
+                let uri = format!("api/v1/cas/{}/routes/scheduled", handle);
+                let scheduled =
+                    get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RouteAuthorizationsScheduled(scheduled))
+            }
+
+            CaCommand::RouteAuthorizationsScheduleCancel(handle, id) => {
+                let uri =
+                    format!("api/v1/cas/{}/routes/scheduled/{}", handle, id);
+                delete(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::Empty)
             }
 
             CaCommand::RouteAuthorizationsTryUpdate(handle, updates) => {
                 let uri = format!("api/v1/cas/{}/routes/try", handle);
-                let advice_opt: Option<BgpAnalysisAdvice> =
+                let response: Option<TryUpdateResponse> =
                     post_json_with_opt_response(
                         &self.server,
                         &self.token,
@@ -426,9 +587,12 @@ impl KrillClient {
                         updates,
                     )
                     .await?;
-                match advice_opt {
+                match response {
                     None => Ok(ApiResponse::Empty),
-                    Some(advice) => {
+                    Some(TryUpdateResponse::ChangeRequest(request)) => {
+                        Ok(ApiResponse::ChangeRequest(request))
+                    }
+                    Some(TryUpdateResponse::Advice(advice)) => {
                         Ok(ApiResponse::BgpAnalysisAdvice(advice))
                     }
                 }
@@ -506,22 +670,67 @@ impl KrillClient {
             CaCommand::AspasAddOrReplace(handle, aspa) => {
                 let uri = format!("api/v1/cas/{}/aspas", handle);
                 let updates = AspaDefinitionUpdates::new(vec![aspa], vec![]);
-                post_json(&self.server, &self.token, &uri, updates).await?;
-                Ok(ApiResponse::Empty)
+                post_json_change(&self.server, &self.token, &uri, updates)
+                    .await
             }
 
             CaCommand::AspasRemove(handle, customer) => {
                 let uri = format!("api/v1/cas/{}/aspas", handle);
                 let updates =
                     AspaDefinitionUpdates::new(vec![], vec![customer]);
-                post_json(&self.server, &self.token, &uri, updates).await?;
-                Ok(ApiResponse::Empty)
+                post_json_change(&self.server, &self.token, &uri, updates)
+                    .await
             }
 
             CaCommand::AspasUpdate(handle, customer, update) => {
                 let uri =
                     format!("api/v1/cas/{}/aspas/as/{}", handle, customer);
-                post_json(&self.server, &self.token, &uri, update).await?;
+                post_json_change(&self.server, &self.token, &uri, update)
+                    .await
+            }
+
+            CaCommand::AspasAnalyze(handle, paths) => {
+                let uri = format!("api/v1/cas/{}/aspas/analysis", handle);
+                let report = post_json_with_response(
+                    &self.server,
+                    &self.token,
+                    &uri,
+                    paths,
+                )
+                .await?;
+                Ok(ApiResponse::AspaAnalysis(report))
+            }
+
+            CaCommand::GhostbustersList(handle) => {
+                let uri = format!("api/v1/cas/{}/ghostbusters", handle);
+                let records =
+                    get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::GhostbusterRecords(records))
+            }
+
+            CaCommand::GhostbustersUpdate(handle, updates) => {
+                let uri = format!("api/v1/cas/{}/ghostbusters", handle);
+                post_json(&self.server, &self.token, &uri, updates).await?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::ChangeRequestsList(handle) => {
+                let uri = format!("api/v1/cas/{}/changes", handle);
+                let requests =
+                    get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ChangeRequests(requests))
+            }
+
+            CaCommand::ChangeRequestApprove(handle, id) => {
+                let uri =
+                    format!("api/v1/cas/{}/changes/{}/approve", handle, id);
+                post_empty(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::ChangeRequestReject(handle, id) => {
+                let uri = format!("api/v1/cas/{}/changes/{}", handle, id);
+                delete(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::Empty)
             }
 
@@ -572,6 +781,43 @@ impl KrillClient {
                 }
             },
 
+            CaCommand::RscList(ca) => {
+                let uri = format!("api/v1/cas/{}/rsc/", ca);
+                let list = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RscList(list))
+            }
+
+            CaCommand::RscShow(ca, name, out) => {
+                let uri = format!("api/v1/cas/{}/rsc/{}", ca, name);
+                let rsc = get_json(&self.server, &self.token, &uri).await?;
+
+                match out {
+                    None => Ok(ApiResponse::Rsc(rsc)),
+                    Some(out) => {
+                        file::save(rsc.as_ref(), &out)?;
+                        Ok(ApiResponse::Empty)
+                    }
+                }
+            }
+
+            CaCommand::RscSign(ca, name, request) => {
+                let uri = format!("api/v1/cas/{}/rsc/{}/sign", ca, name);
+                post_json(&self.server, &self.token, &uri, request).await?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RscVerify(ca, request) => {
+                let uri = format!("api/v1/cas/{}/rsc/verify", ca);
+                let verification = post_json_with_response(
+                    &self.server,
+                    &self.token,
+                    &uri,
+                    request,
+                )
+                .await?;
+                Ok(ApiResponse::RscVerification(verification))
+            }
+
             CaCommand::RtaList(ca) => {
                 let uri = format!("api/v1/cas/{}/rta/", ca);
                 let list = get_json(&self.server, &self.token, &uri).await?;
@@ -694,6 +940,18 @@ impl KrillClient {
                     get_json(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::PublisherDetails(details))
             }
+            PubServerCommand::PublisherObjects(handle) => {
+                let uri =
+                    format!("api/v1/pubd/publishers/{}/objects", handle);
+                let objects =
+                    get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::PublisherObjects(objects))
+            }
+            PubServerCommand::PublisherObjectsDiff(handle) => {
+                let uri = format!("api/v1/pubd/publishers/{}/diff", handle);
+                let diff = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::PublisherObjectsDiff(diff))
+            }
             PubServerCommand::RepositoryResponse(handle) => {
                 let uri = format!(
                     "api/v1/pubd/publishers/{}/response.json",
@@ -702,6 +960,66 @@ impl KrillClient {
                 let res = get_json(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::Rfc8183RepositoryResponse(res))
             }
+            PubServerCommand::ArchiveIndex => {
+                let index = get_json(
+                    &self.server,
+                    &self.token,
+                    "api/v1/pubd/archive",
+                )
+                .await?;
+                Ok(ApiResponse::RrdpArchiveIndex(index))
+            }
+            PubServerCommand::ArchiveExport(query, export) => {
+                let uri = match query {
+                    RrdpArchiveQuery::Serial(serial, None) => {
+                        format!("api/v1/pubd/archive/serial/{}", serial)
+                    }
+                    RrdpArchiveQuery::Serial(serial, Some(session)) => {
+                        format!(
+                            "api/v1/pubd/archive/serial/{}/{}",
+                            serial, session
+                        )
+                    }
+                    RrdpArchiveQuery::Time(time) => format!(
+                        "api/v1/pubd/archive/time/{}",
+                        i64::from(time)
+                    ),
+                };
+                let state: RrdpArchiveState =
+                    get_json(&self.server, &self.token, &uri).await?;
+
+                match export {
+                    None => Ok(ApiResponse::RrdpArchiveState(state)),
+                    Some(RrdpArchiveExport::Dir(dir)) => {
+                        for object in state.objects() {
+                            file::save_with_rsync_uri(
+                                &object.base64().to_bytes(),
+                                &dir,
+                                object.uri(),
+                            )?;
+                        }
+                        Ok(ApiResponse::Empty)
+                    }
+                    Some(RrdpArchiveExport::Tarball(path)) => {
+                        let files: Vec<_> = state
+                            .objects()
+                            .iter()
+                            .map(|object| {
+                                (
+                                    object.uri().clone(),
+                                    object.base64().to_bytes(),
+                                )
+                            })
+                            .collect();
+                        file::save_tar_gz_with_rsync_uris(
+                            &files,
+                            state.time().into(),
+                            &path,
+                        )?;
+                        Ok(ApiResponse::Empty)
+                    }
+                }
+            }
         }
     }
 
@@ -861,6 +1179,7 @@ pub enum Error {
     Rfc8183(idexchange::Error),
     InitError(String),
     InputError(String),
+    RoaImportRejected,
 }
 
 impl fmt::Display for Error {
@@ -881,6 +1200,10 @@ impl fmt::Display for Error {
             Error::Rfc8183(e) => e.fmt(f),
             Error::InitError(s) => s.fmt(f),
             Error::InputError(s) => s.fmt(f),
+            Error::RoaImportRejected => write!(
+                f,
+                "ROA import rejected, it would result in invalid announcements"
+            ),
         }
     }
 }
@@ -8,7 +8,7 @@ use std::{
 };
 
 use bytes::Bytes;
-use clap::{App, Arg, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgGroup, ArgMatches, SubCommand};
 
 use rpki::{
     ca::{
@@ -16,6 +16,7 @@ use rpki::{
         idcert::IdCert,
         idexchange,
         idexchange::{CaHandle, ChildHandle, ParentHandle, PublisherHandle},
+        provisioning::ResourceClassName,
     },
     crypto::KeyIdentifier,
     repository::{
@@ -30,21 +31,27 @@ use crate::{
     cli::report::{ReportError, ReportFormat},
     commons::{
         api::{
-            self, import::ImportChild, AddChildRequest, AspaDefinition,
-            AspaDefinitionFormatError, AspaProvidersUpdate,
-            AuthorizationFmtError, BgpSecAsnKey, BgpSecDefinition,
-            CertAuthInit, CustomerAsn, ParentCaReq, ProviderAsn,
+            self, import::ImportChild, rrdp::RrdpSession, AddChildRequest,
+            ApiTokenRequest, AspaDefinition, AspaDefinitionFormatError,
+            AspaProvidersUpdate, AuthorizationFmtError, BgpSecAsnKey,
+            BgpSecDefinition, CertAuthInit, CustomerAsn, GhostbusterRecord,
+            GhostbusterRecordUpdates, ParentCaReq, ProviderAsn,
             PublicationServerUris, RepoFileDeleteCriteria, RoaConfiguration,
-            RoaConfigurationUpdates, RoaPayload, RtaName, Token,
-            UpdateChildRequest,
+            RoaConfigurationUpdates, RoaFileError, RoaFileFormat, RoaImport,
+            RoaImportMode, RoaPayload, RrdpArchiveQuery, RscName, RtaName,
+            ScheduleRoaUpdatesRequest, Timestamp, Token, UpdateChildRequest,
+            VCard,
         },
+        bgp::{AsPathError, AsPaths},
         crypto::SignSupport,
         error::KrillIoError,
         util::file,
     },
     constants::*,
     daemon::ca::{
-        ResourceTaggedAttestation, RtaContentRequest, RtaPrepareRequest,
+        ChecklistEntry, ResourceTaggedAttestation, RpkiSignedChecklist,
+        RscContentRequest, RscVerifyRequest, RtaContentRequest,
+        RtaPrepareRequest,
     },
 };
 
@@ -58,6 +65,20 @@ pub struct GeneralArgs {
 
 impl GeneralArgs {
     pub fn add_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        Self::add_connection_args(app).arg(
+            Arg::with_name(KRILL_CLI_FORMAT_ARG)
+                .short("f")
+                .long(KRILL_CLI_FORMAT_ARG)
+                .value_name("type")
+                .help("Report format: none|json|text (default). Or set env: KRILL_CLI_FORMAT")
+                .required(false),
+        )
+    }
+
+    /// Adds the arguments for connecting to the server, but not the report
+    /// format. Used by subcommands which have their own --format argument,
+    /// for these the report format can only be set using the env variable.
+    pub fn add_connection_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         app.arg(
             Arg::with_name(KRILL_CLI_SERVER_ARG)
                 .short("s")
@@ -74,14 +95,6 @@ impl GeneralArgs {
                 .help("The secret token for the Krill server. Or set env: KRILL_CLI_TOKEN")
                 .required(false),
         )
-        .arg(
-            Arg::with_name(KRILL_CLI_FORMAT_ARG)
-                .short("f")
-                .long(KRILL_CLI_FORMAT_ARG)
-                .value_name("type")
-                .help("Report format: none|json|text (default). Or set env: KRILL_CLI_FORMAT")
-                .required(false),
-        )
         .arg(
             Arg::with_name(KRILL_CLI_API_ARG)
                 .long(KRILL_CLI_API_ARG)
@@ -751,6 +764,139 @@ impl Options {
                 .required(false),
         );
 
+        sub = sub.arg(
+            Arg::with_name("expires_at")
+                .long("expires-at")
+                .help("Withdraw the added ROAs automatically at date/time in RFC 3339 format, e.g. 2020-04-09T19:37:02Z")
+                .value_name("<RFC 3339 DateTime>")
+                .required(false),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("not_before")
+                .long("not-before")
+                .help("Schedule the update to be applied at date/time in RFC 3339 format, e.g. 2020-04-09T02:00:00Z")
+                .value_name("<RFC 3339 DateTime>")
+                .required(false),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_export_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("export")
+            .about("Export authorizations to a file");
+
+        sub = GeneralArgs::add_connection_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("roa_file_format")
+                .short("f")
+                .long("format")
+                .help("File format: csv|slurm|json (default)")
+                .value_name("type")
+                .required(false),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_import_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("import")
+            .about("Import authorizations from a file");
+
+        sub = GeneralArgs::add_connection_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub
+            .arg(
+                Arg::with_name("file")
+                    .long("file")
+                    .help("The file containing the authorizations")
+                    .value_name("<file>")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("roa_file_format")
+                    .short("f")
+                    .long("format")
+                    .help("File format: csv|slurm|json, or leave out to detect it")
+                    .value_name("type")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("replace")
+                    .long("replace")
+                    .help("Replace all current authorizations with the imported authorizations")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("merge")
+                    .long("merge")
+                    .help("Add the imported authorizations to the current authorizations")
+                    .required(false),
+            )
+            .group(
+                ArgGroup::with_name("import_mode")
+                    .args(&["replace", "merge"])
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("dryrun")
+                    .long("dryrun")
+                    .help("Show the updates and their effect on known announcements without applying them")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("force")
+                    .long("force")
+                    .help("Apply the updates even if they would result in invalid announcements")
+                    .required(false),
+            );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_scheduled_list_sc<'a, 'b>(
+        app: App<'a, 'b>,
+    ) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list")
+            .about("Show scheduled authorization updates");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_scheduled_cancel_sc<'a, 'b>(
+        app: App<'a, 'b>,
+    ) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("cancel")
+            .about("Cancel scheduled authorization updates");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("id")
+                .long("id")
+                .help("The id of the scheduled updates")
+                .value_name("<id>")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_scheduled_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("scheduled")
+            .about("Manage scheduled authorization updates");
+
+        sub = Self::make_cas_routes_scheduled_list_sc(sub);
+        sub = Self::make_cas_routes_scheduled_cancel_sc(sub);
+
         app.subcommand(sub)
     }
 
@@ -810,6 +956,9 @@ impl Options {
 
         sub = Self::make_cas_routes_list_sc(sub);
         sub = Self::make_cas_routes_update_sc(sub);
+        sub = Self::make_cas_routes_export_sc(sub);
+        sub = Self::make_cas_routes_import_sc(sub);
+        sub = Self::make_cas_routes_scheduled_sc(sub);
         sub = Self::make_cas_routes_bgp_sc(sub);
 
         app.subcommand(sub)
@@ -890,6 +1039,73 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn add_ghostbusters_resource_class_arg<'a, 'b>(
+        app: App<'a, 'b>,
+    ) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("resource_class")
+                .long("resource-class")
+                .help("The resource class, or leave out for all resource classes")
+                .value_name("name")
+                .required(false),
+        )
+    }
+
+    fn make_cas_ghostbusters_list_sc<'a, 'b>(
+        app: App<'a, 'b>,
+    ) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list")
+            .about("Show current Ghostbuster records");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_ghostbusters_set_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("set")
+            .about("Add or replace a Ghostbuster record");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_ghostbusters_resource_class_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("vcard")
+                .long("vcard")
+                .help("File containing the contact details as a vCard 4.0")
+                .value_name("<file>")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_ghostbusters_remove_sc<'a, 'b>(
+        app: App<'a, 'b>,
+    ) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("remove")
+            .about("Remove a Ghostbuster record");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_ghostbusters_resource_class_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_ghostbusters_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("ghostbusters")
+            .about("Manage Ghostbuster records (RFC 6493) for a CA");
+
+        sub = Self::make_cas_ghostbusters_list_sc(sub);
+        sub = Self::make_cas_ghostbusters_set_sc(sub);
+        sub = Self::make_cas_ghostbusters_remove_sc(sub);
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_aspas_add_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("add")
             .about("Add or replace an ASPA configuration");
@@ -972,6 +1188,23 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_aspas_analyze_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("analyze")
+            .about("Analyze current ASPA configurations against AS paths");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("paths")
+                .long("paths")
+                .help("File with AS paths, one per line, or 'bgpdump -m' output")
+                .value_name("<file>")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_aspas_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("aspas")
             .about("Manage ASPAs for a CA (experimental)");
@@ -980,6 +1213,60 @@ impl Options {
         sub = Self::make_cas_aspas_remove_sc(sub);
         sub = Self::make_cas_aspas_update_sc(sub);
         sub = Self::make_cas_aspas_list_sc(sub);
+        sub = Self::make_cas_aspas_analyze_sc(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_changes_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list")
+            .about("Show route and ASPA changes pending approval");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn add_change_request_id_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("id")
+                .long("id")
+                .help("The id of the change request")
+                .value_name("<id>")
+                .required(true),
+        )
+    }
+
+    fn make_cas_changes_approve_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("approve")
+            .about("Approve and apply a pending change");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_change_request_id_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_changes_reject_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub =
+            SubCommand::with_name("reject").about("Reject a pending change");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_change_request_id_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_changes_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("changes")
+            .about("Manage route and ASPA changes which need approval");
+
+        sub = Self::make_cas_changes_list_sc(sub);
+        sub = Self::make_cas_changes_approve_sc(sub);
+        sub = Self::make_cas_changes_reject_sc(sub);
 
         app.subcommand(sub)
     }
@@ -1054,9 +1341,8 @@ impl Options {
         app.subcommand(sub)
     }
 
-    #[cfg(feature = "rta")]
-    fn make_cas_rta_list<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("list").about("List RTAs");
+    fn make_cas_rsc_list<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list").about("List RSCs");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
@@ -1064,9 +1350,8 @@ impl Options {
         app.subcommand(sub)
     }
 
-    #[cfg(feature = "rta")]
-    fn make_cas_rta_show<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("show").about("Show RTA");
+    fn make_cas_rsc_show<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("show").about("Show RSC");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
@@ -1076,7 +1361,7 @@ impl Options {
                 .long("name")
                 .short("n")
                 .value_name("string")
-                .help("Your local name for this RTA")
+                .help("Your local name for this RSC")
                 .required(true),
         );
 
@@ -1085,17 +1370,16 @@ impl Options {
                 .long("out")
                 .short("o")
                 .value_name("path")
-                .help("File to write RTA to")
+                .help("File to write the DER encoded RSC to")
                 .required(true),
         );
 
         app.subcommand(sub)
     }
 
-    #[cfg(feature = "rta")]
-    fn make_cas_rta_sign_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    fn make_cas_rsc_sign_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("sign")
-            .about("Create an RTA signed by a CA");
+            .about("Create an RSC for one or more files signed by a CA");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
@@ -1107,7 +1391,7 @@ impl Options {
                 .long("days")
                 .short("d")
                 .value_name("number of days")
-                .help("Validity time of the RTA in days")
+                .help("Validity time of the RSC in days")
                 .required(true),
         );
 
@@ -1116,7 +1400,8 @@ impl Options {
                 .long("in")
                 .short("i")
                 .value_name("path")
-                .help("Content which needs to be signed")
+                .multiple(true)
+                .help("File(s) to include in the checklist")
                 .required(true),
         );
 
@@ -1125,58 +1410,65 @@ impl Options {
                 .long("name")
                 .short("n")
                 .value_name("string")
-                .help("Your local name for this RTA")
+                .help("Your local name for this RSC")
                 .required(true),
         );
 
-        sub = sub.arg(
-            Arg::with_name("keys")
-                .long("keys")
-                .short("k")
-                .value_name("hex encoded key identifiers")
-                .multiple(true)
-                .help("Optional additional keys to include in this RTA")
-                .required(false),
-        );
-
         app.subcommand(sub)
     }
 
-    #[cfg(feature = "rta")]
-    fn make_cas_rta_multi_prep_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("prep")
-            .about("Prepare keys for multi-signed RTA");
+    fn make_cas_rsc_verify_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("verify")
+            .about("Verify files against an RSC signed by a CA");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
 
-        sub = Self::add_resource_args(sub);
-
         sub = sub.arg(
-            Arg::with_name("days")
-                .long("days")
-                .short("d")
-                .value_name("number of days")
-                .help("Validity time of the RTA in days")
+            Arg::with_name("rsc")
+                .long("rsc")
+                .short("r")
+                .value_name("path")
+                .help("The DER encoded RSC")
                 .required(true),
         );
 
         sub = sub.arg(
-            Arg::with_name("name")
-                .long("name")
-                .short("n")
-                .value_name("string")
-                .help("Your local name for this RTA")
+            Arg::with_name("in")
+                .long("in")
+                .short("i")
+                .value_name("path")
+                .multiple(true)
+                .help("File(s) to verify against the checklist")
                 .required(true),
         );
 
         app.subcommand(sub)
     }
 
+    fn make_cas_rsc_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("rsc")
+            .about("Manage RPKI Signed Checklists (RFC 9323)");
+        sub = Self::make_cas_rsc_list(sub);
+        sub = Self::make_cas_rsc_show(sub);
+        sub = Self::make_cas_rsc_sign_sc(sub);
+        sub = Self::make_cas_rsc_verify_sc(sub);
+        app.subcommand(sub)
+    }
+
     #[cfg(feature = "rta")]
-    fn make_cas_rta_multi_sign_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("cosign")
-            .about("Co-sign an existing (prepared) RTA");
+    fn make_cas_rta_list<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list").about("List RTAs");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    #[cfg(feature = "rta")]
+    fn make_cas_rta_show<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("show").about("Show RTA");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
@@ -1191,11 +1483,121 @@ impl Options {
         );
 
         sub = sub.arg(
-            Arg::with_name("in")
-                .long("in")
-                .short("i")
-                .value_name("path")
-                .help("RTA which needs to be co-signed")
+            Arg::with_name("out")
+                .long("out")
+                .short("o")
+                .value_name("path")
+                .help("File to write RTA to")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
+    #[cfg(feature = "rta")]
+    fn make_cas_rta_sign_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("sign")
+            .about("Create an RTA signed by a CA");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = Self::add_resource_args(sub);
+
+        sub = sub.arg(
+            Arg::with_name("days")
+                .long("days")
+                .short("d")
+                .value_name("number of days")
+                .help("Validity time of the RTA in days")
+                .required(true),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("in")
+                .long("in")
+                .short("i")
+                .value_name("path")
+                .help("Content which needs to be signed")
+                .required(true),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("name")
+                .long("name")
+                .short("n")
+                .value_name("string")
+                .help("Your local name for this RTA")
+                .required(true),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("keys")
+                .long("keys")
+                .short("k")
+                .value_name("hex encoded key identifiers")
+                .multiple(true)
+                .help("Optional additional keys to include in this RTA")
+                .required(false),
+        );
+
+        app.subcommand(sub)
+    }
+
+    #[cfg(feature = "rta")]
+    fn make_cas_rta_multi_prep_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("prep")
+            .about("Prepare keys for multi-signed RTA");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = Self::add_resource_args(sub);
+
+        sub = sub.arg(
+            Arg::with_name("days")
+                .long("days")
+                .short("d")
+                .value_name("number of days")
+                .help("Validity time of the RTA in days")
+                .required(true),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("name")
+                .long("name")
+                .short("n")
+                .value_name("string")
+                .help("Your local name for this RTA")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
+    #[cfg(feature = "rta")]
+    fn make_cas_rta_multi_sign_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("cosign")
+            .about("Co-sign an existing (prepared) RTA");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("name")
+                .long("name")
+                .short("n")
+                .value_name("string")
+                .help("Your local name for this RTA")
+                .required(true),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("in")
+                .long("in")
+                .short("i")
+                .value_name("path")
+                .help("RTA which needs to be co-signed")
                 .required(true),
         );
 
@@ -1249,6 +1651,68 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_tokens_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("tokens")
+            .about("Manage named API tokens (requires multi-user support)");
+
+        let mut list =
+            SubCommand::with_name("list").about("List issued API tokens");
+        list = GeneralArgs::add_args(list);
+
+        let mut add = SubCommand::with_name("add").about(
+            "Issue a new API token, limited to permissions and (optionally) CAs",
+        );
+        add = GeneralArgs::add_args(add);
+        add = add
+            .arg(
+                Arg::with_name("name")
+                    .long("name")
+                    .value_name("name")
+                    .help("The name of the token")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("permission")
+                    .long("permission")
+                    .value_name("permission")
+                    .help("Permission for the token, e.g. ROUTES_UPDATE (multiple allowed)")
+                    .required(true)
+                    .multiple(true)
+                    .number_of_values(1),
+            )
+            .arg(
+                Arg::with_name("ca_access")
+                    .long("ca-access")
+                    .value_name("CA handle")
+                    .help("Limit the token to this CA (multiple allowed). Default: all CAs")
+                    .required(false)
+                    .multiple(true)
+                    .number_of_values(1),
+            )
+            .arg(
+                Arg::with_name("expires_at")
+                    .long("expires-at")
+                    .value_name("<RFC 3339 DateTime>")
+                    .help("Expire the token at date/time in RFC 3339 format, e.g. 2020-04-09T19:37:02Z")
+                    .required(true),
+            );
+
+        let mut revoke =
+            SubCommand::with_name("revoke").about("Revoke an API token");
+        revoke = GeneralArgs::add_args(revoke);
+        revoke = revoke.arg(
+            Arg::with_name("name")
+                .long("name")
+                .value_name("name")
+                .help("The name of the token")
+                .required(true),
+        );
+
+        sub = sub.subcommand(list).subcommand(add).subcommand(revoke);
+
+        app.subcommand(sub)
+    }
+
     fn make_health_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let health = SubCommand::with_name("health")
             .about("Perform an authenticated health check");
@@ -1367,6 +1831,23 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_publishers_objects_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("objects")
+            .about("Show all objects of a publisher");
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_publisher_arg(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_publishers_diff_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("diff").about(
+            "Compare the objects of a publisher with the objects of the CA with the same handle",
+        );
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_publisher_arg(sub);
+        app.subcommand(sub)
+    }
+
     fn make_publication_server_stats_sc<'a, 'b>(
         app: App<'a, 'b>,
     ) -> App<'a, 'b> {
@@ -1428,6 +1909,8 @@ impl Options {
         sub = Self::make_publishers_remove_sc(sub);
         sub = Self::make_publishers_show_sc(sub);
         sub = Self::make_publishers_response_sc(sub);
+        sub = Self::make_publishers_objects_sc(sub);
+        sub = Self::make_publishers_diff_sc(sub);
 
         app.subcommand(sub)
     }
@@ -1449,6 +1932,71 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_pubserver_archive_list_sc<'a, 'b>(
+        app: App<'a, 'b>,
+    ) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list")
+            .about("List the RRDP serials for which files are kept on disk");
+        sub = GeneralArgs::add_args(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_pubserver_archive_export_sc<'a, 'b>(
+        app: App<'a, 'b>,
+    ) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("export").about(
+            "Reconstruct the repository content at an earlier RRDP serial or time",
+        );
+        sub = GeneralArgs::add_args(sub);
+
+        sub = sub
+            .arg(
+                Arg::with_name("serial")
+                    .long("serial")
+                    .value_name("number")
+                    .help("The RRDP serial to reconstruct")
+                    .required_unless("time")
+                    .conflicts_with("time"),
+            )
+            .arg(
+                Arg::with_name("session")
+                    .long("session")
+                    .value_name("uuid")
+                    .help("The RRDP session of the serial, defaults to the most recent session with this serial")
+                    .requires("serial"),
+            )
+            .arg(
+                Arg::with_name("time")
+                    .long("time")
+                    .value_name("<RFC 3339 DateTime>")
+                    .help("Reconstruct the content as it was published at date/time in RFC 3339 format, e.g. 2020-04-09T14:03:00Z")
+                    .required_unless("serial"),
+            )
+            .arg(
+                Arg::with_name("dir")
+                    .long("dir")
+                    .value_name("path")
+                    .help("Write the files to this directory, using the rsync layout")
+                    .conflicts_with("tarball"),
+            )
+            .arg(
+                Arg::with_name("tarball")
+                    .long("tarball")
+                    .value_name("path")
+                    .help("Write the files to this gzipped tar file, using the rsync layout"),
+            );
+
+        app.subcommand(sub)
+    }
+
+    fn make_pubserver_archive_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("archive")
+            .about("Inspect the RRDP files kept by the Publication Server");
+        sub = Self::make_pubserver_archive_list_sc(sub);
+        sub = Self::make_pubserver_archive_export_sc(sub);
+        app.subcommand(sub)
+    }
+
     fn make_pubserver_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("pubserver")
             .about("Manage your Publication Server (only needed if you run your own)");
@@ -1456,6 +2004,7 @@ impl Options {
         sub = Self::make_publishers_sc(sub);
         sub = Self::make_pubserver_delete_sc(sub);
         sub = Self::make_publication_server_sc(sub);
+        sub = Self::make_pubserver_archive_sc(sub);
 
         app.subcommand(sub)
     }
@@ -1478,6 +2027,9 @@ impl Options {
         app = Self::make_cas_issues_sc(app);
         app = Self::make_pubserver_sc(app);
         app = Self::make_cas_aspas_sc(app);
+        app = Self::make_cas_ghostbusters_sc(app);
+        app = Self::make_cas_changes_sc(app);
+        app = Self::make_cas_rsc_sc(app);
 
         #[cfg(feature = "rta")]
         {
@@ -1490,6 +2042,8 @@ impl Options {
 
         app = Self::make_bulk_sc(app);
 
+        app = Self::make_tokens_sc(app);
+
         app.get_matches()
     }
 
@@ -2075,13 +2629,50 @@ impl Options {
             RoaConfigurationUpdates::new(added, removed)
         };
 
+        let updates = match matches.value_of("expires_at") {
+            Some(expires_at) => {
+                let time = Time::from_str(expires_at).map_err(|e| {
+                    Error::general(&format!("invalid date format: {}", e))
+                })?;
+                let (added, removed) = updates.unpack();
+                let added = added
+                    .into_iter()
+                    .map(|roa| {
+                        roa.with_expires_at(Some(Timestamp::from(time)))
+                    })
+                    .collect();
+                RoaConfigurationUpdates::new(added, removed)
+            }
+            None => updates,
+        };
+
         if matches.is_present("dryrun") && matches.is_present("try") {
             return Err(Error::general(
                 "You cannot use both --dryrun and --try",
             ));
         }
 
-        let command = if matches.is_present("dryrun") {
+        let not_before = match matches.value_of("not_before") {
+            Some(not_before) => {
+                if matches.is_present("dryrun") || matches.is_present("try") {
+                    return Err(Error::general(
+                        "You cannot use --not-before with --dryrun or --try",
+                    ));
+                }
+                let time = Time::from_str(not_before).map_err(|e| {
+                    Error::general(&format!("invalid date format: {}", e))
+                })?;
+                Some(Timestamp::from(time))
+            }
+            None => None,
+        };
+
+        let command = if let Some(not_before) = not_before {
+            Command::CertAuth(CaCommand::RouteAuthorizationsSchedule(
+                my_ca,
+                ScheduleRoaUpdatesRequest::new(not_before, updates),
+            ))
+        } else if matches.is_present("dryrun") {
             Command::CertAuth(CaCommand::RouteAuthorizationsDryRunUpdate(
                 my_ca, updates,
             ))
@@ -2098,74 +2689,178 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
-    fn parse_matches_cas_routes_bgp_full(
+    fn parse_matches_cas_routes_export(
         matches: &ArgMatches,
     ) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
-        Ok(Options::make(
-            general_args,
-            Command::CertAuth(CaCommand::BgpAnalysisFull(my_ca)),
-        ))
+
+        let format = match matches.value_of("roa_file_format") {
+            Some(format) => RoaFileFormat::from_str(format)?,
+            None => RoaFileFormat::Json,
+        };
+
+        let command = Command::CertAuth(
+            CaCommand::RouteAuthorizationsExport(my_ca, format),
+        );
+
+        Ok(Options::make(general_args, command))
     }
 
-    fn parse_matches_cas_routes_bgp_suggest(
+    fn parse_matches_cas_routes_import(
         matches: &ArgMatches,
     ) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
 
-        let v4 = matches.value_of("ipv4").unwrap_or("");
-        let v6 = matches.value_of("ipv6").unwrap_or("");
-
-        let resources = ResourceSet::from_strs("", v4, v6).map_err(|e| {
-            Error::GeneralArgumentError(format!(
-                "Could not parse IP resources: {}",
-                e
-            ))
+        let path = matches.value_of("file").unwrap();
+        let bytes = Self::read_file_arg(path)?;
+        let content = std::str::from_utf8(&bytes).map_err(|_| {
+            Error::general("The ROA file must be UTF-8 encoded")
         })?;
 
-        let resources = if resources.is_empty() {
-            None
+        let format = match matches.value_of("roa_file_format") {
+            Some(format) => RoaFileFormat::from_str(format)?,
+            None => RoaFileFormat::detect(content),
+        };
+        let configurations = format.parse(content)?;
+
+        let mode = if matches.is_present("replace") {
+            RoaImportMode::Replace
         } else {
-            Some(resources)
+            RoaImportMode::Merge
         };
 
-        Ok(Options::make(
-            general_args,
-            Command::CertAuth(CaCommand::BgpAnalysisSuggest(
-                my_ca, resources,
-            )),
-        ))
-    }
+        let import = RoaImport::new(
+            configurations,
+            mode,
+            matches.is_present("dryrun"),
+            matches.is_present("force"),
+        );
 
-    fn parse_matches_cas_routes_bgp(
-        matches: &ArgMatches,
-    ) -> Result<Options, Error> {
-        if let Some(m) = matches.subcommand_matches("analyze") {
-            Self::parse_matches_cas_routes_bgp_full(m)
-        } else if let Some(m) = matches.subcommand_matches("suggest") {
-            Self::parse_matches_cas_routes_bgp_suggest(m)
-        } else {
-            Err(Error::UnrecognizedSubCommand)
-        }
-    }
+        let command = Command::CertAuth(
+            CaCommand::RouteAuthorizationsImport(my_ca, import),
+        );
 
-    fn parse_matches_cas_routes(
-        matches: &ArgMatches,
-    ) -> Result<Options, Error> {
-        if let Some(m) = matches.subcommand_matches("list") {
-            Self::parse_matches_cas_routes_list(m)
-        } else if let Some(m) = matches.subcommand_matches("update") {
-            Self::parse_matches_cas_routes_update(m)
-        } else if let Some(m) = matches.subcommand_matches("bgp") {
-            Self::parse_matches_cas_routes_bgp(m)
-        } else {
-            Err(Error::UnrecognizedSubCommand)
-        }
+        Ok(Options::make(general_args, command))
     }
 
-    fn parse_matches_cas_bgpsec_list(
+    fn parse_matches_cas_routes_scheduled_list(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(
+            CaCommand::RouteAuthorizationsScheduledList(my_ca),
+        );
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_routes_scheduled_cancel(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let id = matches.value_of("id").unwrap();
+        let id = u64::from_str(id)
+            .map_err(|_| Error::general(&format!("invalid id: {}", id)))?;
+
+        let command = Command::CertAuth(
+            CaCommand::RouteAuthorizationsScheduleCancel(my_ca, id),
+        );
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_routes_scheduled(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            Self::parse_matches_cas_routes_scheduled_list(m)
+        } else if let Some(m) = matches.subcommand_matches("cancel") {
+            Self::parse_matches_cas_routes_scheduled_cancel(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_routes_bgp_full(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        Ok(Options::make(
+            general_args,
+            Command::CertAuth(CaCommand::BgpAnalysisFull(my_ca)),
+        ))
+    }
+
+    fn parse_matches_cas_routes_bgp_suggest(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let v4 = matches.value_of("ipv4").unwrap_or("");
+        let v6 = matches.value_of("ipv6").unwrap_or("");
+
+        let resources = ResourceSet::from_strs("", v4, v6).map_err(|e| {
+            Error::GeneralArgumentError(format!(
+                "Could not parse IP resources: {}",
+                e
+            ))
+        })?;
+
+        let resources = if resources.is_empty() {
+            None
+        } else {
+            Some(resources)
+        };
+
+        Ok(Options::make(
+            general_args,
+            Command::CertAuth(CaCommand::BgpAnalysisSuggest(
+                my_ca, resources,
+            )),
+        ))
+    }
+
+    fn parse_matches_cas_routes_bgp(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("analyze") {
+            Self::parse_matches_cas_routes_bgp_full(m)
+        } else if let Some(m) = matches.subcommand_matches("suggest") {
+            Self::parse_matches_cas_routes_bgp_suggest(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_routes(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            Self::parse_matches_cas_routes_list(m)
+        } else if let Some(m) = matches.subcommand_matches("update") {
+            Self::parse_matches_cas_routes_update(m)
+        } else if let Some(m) = matches.subcommand_matches("export") {
+            Self::parse_matches_cas_routes_export(m)
+        } else if let Some(m) = matches.subcommand_matches("import") {
+            Self::parse_matches_cas_routes_import(m)
+        } else if let Some(m) = matches.subcommand_matches("scheduled") {
+            Self::parse_matches_cas_routes_scheduled(m)
+        } else if let Some(m) = matches.subcommand_matches("bgp") {
+            Self::parse_matches_cas_routes_bgp(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_bgpsec_list(
         matches: &ArgMatches,
     ) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
@@ -2355,6 +3050,25 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_aspas_analyze(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let path = matches.value_of("paths").unwrap(); // required argument
+        let bytes = Self::read_file_arg(path)?;
+        let text = std::str::from_utf8(&bytes).map_err(|_| {
+            Error::general("AS paths file is not valid UTF-8")
+        })?;
+        let paths = AsPaths::parse(text)?;
+
+        let command =
+            Command::CertAuth(CaCommand::AspasAnalyze(my_ca, paths));
+
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_cas_aspas(
         matches: &ArgMatches,
     ) -> Result<Options, Error> {
@@ -2366,6 +3080,137 @@ impl Options {
             Self::parse_matches_cas_aspas_update(m)
         } else if let Some(m) = matches.subcommand_matches("list") {
             Self::parse_matches_cas_aspas_list(m)
+        } else if let Some(m) = matches.subcommand_matches("analyze") {
+            Self::parse_matches_cas_aspas_analyze(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_ghostbusters_resource_class(
+        matches: &ArgMatches,
+    ) -> Option<ResourceClassName> {
+        matches
+            .value_of("resource_class")
+            .map(ResourceClassName::from)
+    }
+
+    fn parse_matches_cas_ghostbusters_list(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::GhostbustersList(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_ghostbusters_set(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let resource_class = Self::parse_ghostbusters_resource_class(matches);
+
+        let path = matches.value_of("vcard").unwrap(); // required argument
+        let bytes = Self::read_file_arg(path)?;
+        let text = std::str::from_utf8(&bytes)
+            .map_err(|_| Error::general("vCard file is not valid UTF-8"))?;
+        let vcard = VCard::from_str(text)
+            .map_err(|e| Error::general(&format!("Invalid vCard: {}", e)))?;
+
+        let updates = GhostbusterRecordUpdates::new(
+            vec![GhostbusterRecord::new(resource_class, vcard)],
+            vec![],
+        );
+        let command =
+            Command::CertAuth(CaCommand::GhostbustersUpdate(my_ca, updates));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_ghostbusters_remove(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let resource_class = Self::parse_ghostbusters_resource_class(matches);
+
+        let updates =
+            GhostbusterRecordUpdates::new(vec![], vec![resource_class]);
+        let command =
+            Command::CertAuth(CaCommand::GhostbustersUpdate(my_ca, updates));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_ghostbusters(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            Self::parse_matches_cas_ghostbusters_list(m)
+        } else if let Some(m) = matches.subcommand_matches("set") {
+            Self::parse_matches_cas_ghostbusters_set(m)
+        } else if let Some(m) = matches.subcommand_matches("remove") {
+            Self::parse_matches_cas_ghostbusters_remove(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_changes_list(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::ChangeRequestsList(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_change_request_id(matches: &ArgMatches) -> Result<u64, Error> {
+        let id = matches.value_of("id").unwrap();
+        u64::from_str(id)
+            .map_err(|_| Error::general(&format!("invalid id: {}", id)))
+    }
+
+    fn parse_matches_cas_changes_approve(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let id = Self::parse_change_request_id(matches)?;
+
+        let command =
+            Command::CertAuth(CaCommand::ChangeRequestApprove(my_ca, id));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_changes_reject(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let id = Self::parse_change_request_id(matches)?;
+
+        let command =
+            Command::CertAuth(CaCommand::ChangeRequestReject(my_ca, id));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_changes(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            Self::parse_matches_cas_changes_list(m)
+        } else if let Some(m) = matches.subcommand_matches("approve") {
+            Self::parse_matches_cas_changes_approve(m)
+        } else if let Some(m) = matches.subcommand_matches("reject") {
+            Self::parse_matches_cas_changes_reject(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -2449,6 +3294,146 @@ impl Options {
         Ok(Options::make(general, command))
     }
 
+    fn parse_matches_cas_rsc_list(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let ca = Self::parse_my_ca(matches)?;
+        let command = Command::CertAuth(CaCommand::RscList(ca));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_rsc_show(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let ca = Self::parse_my_ca(matches)?;
+        let name = matches.value_of("name").unwrap().to_string();
+
+        let out_file = matches.value_of("out").unwrap();
+        let out_file = PathBuf::from_str(out_file).map_err(|_| {
+            Error::GeneralArgumentError(format!(
+                "Invalid filename: {}",
+                out_file
+            ))
+        })?;
+
+        file::save(&[], &out_file).map_err(|e| {
+            Error::GeneralArgumentError(format!(
+                "Cannot save to file: {}, error: {}",
+                out_file.to_string_lossy(),
+                e
+            ))
+        })?;
+
+        let command =
+            Command::CertAuth(CaCommand::RscShow(ca, name, Some(out_file)));
+        Ok(Options::make(general_args, command))
+    }
+
+    /// Reads the given '--in' files and returns a checklist entry for
+    /// each, using the base name of the file.
+    fn parse_rsc_files(
+        matches: &ArgMatches,
+    ) -> Result<Vec<ChecklistEntry>, Error> {
+        let mut entries = vec![];
+        for in_file in matches.values_of("in").unwrap() {
+            let path = PathBuf::from_str(in_file).map_err(|_| {
+                Error::GeneralArgumentError(format!(
+                    "Invalid filename: {}",
+                    in_file
+                ))
+            })?;
+
+            let content = file::read(&path).map_err(|e| {
+                Error::GeneralArgumentError(format!(
+                    "Can't read file '{}', error: {}",
+                    path.to_string_lossy(),
+                    e,
+                ))
+            })?;
+
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string());
+
+            entries.push(ChecklistEntry::for_file(file_name, &content));
+        }
+        Ok(entries)
+    }
+
+    fn parse_matches_cas_rsc_sign(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let ca = Self::parse_my_ca(matches)?;
+
+        let days = matches.value_of("days").unwrap();
+        let days = i64::from_str(days).map_err(|e| {
+            Error::GeneralArgumentError(format!(
+                "Invalid number of days: {}",
+                e
+            ))
+        })?;
+
+        let checklist = Self::parse_rsc_files(matches)?;
+        let name = matches.value_of("name").unwrap().to_string();
+
+        let validity = SignSupport::sign_validity_days(days);
+
+        let resources = Self::parse_resource_args(matches)?
+            .ok_or_else(|| Error::general("You must specify at least one of --ipv4, --ipv6 or --asn"))?;
+
+        let request = RscContentRequest::new(resources, validity, checklist);
+        let command =
+            Command::CertAuth(CaCommand::RscSign(ca, name, request));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_rsc_verify(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let ca = Self::parse_my_ca(matches)?;
+
+        let rsc_file = matches.value_of("rsc").unwrap();
+        let rsc_file = PathBuf::from_str(rsc_file).map_err(|_| {
+            Error::GeneralArgumentError(format!(
+                "Invalid filename: {}",
+                rsc_file
+            ))
+        })?;
+
+        let rsc = file::read(&rsc_file).map_err(|e| {
+            Error::GeneralArgumentError(format!(
+                "Can't read file '{}', error: {}",
+                rsc_file.to_string_lossy(),
+                e,
+            ))
+        })?;
+        let rsc = RpkiSignedChecklist::new(rsc);
+
+        let files = Self::parse_rsc_files(matches)?;
+
+        let request = RscVerifyRequest::new(rsc, files);
+        let command = Command::CertAuth(CaCommand::RscVerify(ca, request));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_rsc(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            Self::parse_matches_cas_rsc_list(m)
+        } else if let Some(m) = matches.subcommand_matches("show") {
+            Self::parse_matches_cas_rsc_show(m)
+        } else if let Some(m) = matches.subcommand_matches("sign") {
+            Self::parse_matches_cas_rsc_sign(m)
+        } else if let Some(m) = matches.subcommand_matches("verify") {
+            Self::parse_matches_cas_rsc_verify(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
     fn parse_matches_cas_rta_list(
         matches: &ArgMatches,
     ) -> Result<Options, Error> {
@@ -2642,6 +3627,61 @@ impl Options {
         }
     }
 
+    fn parse_matches_tokens_add(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+
+        let name = matches.value_of("name").unwrap().to_string();
+        let permissions = matches
+            .values_of("permission")
+            .unwrap()
+            .map(|p| p.to_string())
+            .collect();
+
+        let mut cas = vec![];
+        if let Some(values) = matches.values_of("ca_access") {
+            for ca in values {
+                cas.push(
+                    CaHandle::from_str(ca)
+                        .map_err(|_| Error::InvalidHandle)?,
+                );
+            }
+        }
+
+        let expires_at = matches.value_of("expires_at").unwrap();
+        let expires = Time::from_str(expires_at).map_err(|e| {
+            Error::general(&format!("invalid date format: {}", e))
+        })?;
+
+        let request = ApiTokenRequest::new(
+            name,
+            permissions,
+            cas,
+            Timestamp::from(expires),
+        );
+
+        let command = Command::Tokens(TokenCommand::Add(request));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_tokens(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Tokens(TokenCommand::List);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("add") {
+            Self::parse_matches_tokens_add(m)
+        } else if let Some(m) = matches.subcommand_matches("revoke") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let name = m.value_of("name").unwrap().to_string();
+            let command = Command::Tokens(TokenCommand::Revoke(name));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
     fn parse_matches_health(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let command = Command::Health;
@@ -2743,6 +3783,27 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_publishers_objects(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let publisher = Self::parse_publisher_arg(matches)?;
+        let command =
+            Command::PubServer(PubServerCommand::PublisherObjects(publisher));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_publishers_diff(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let publisher = Self::parse_publisher_arg(matches)?;
+        let command = Command::PubServer(
+            PubServerCommand::PublisherObjectsDiff(publisher),
+        );
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_publication_server_stats(
         matches: &ArgMatches,
     ) -> Result<Options, Error> {
@@ -2830,6 +3891,10 @@ impl Options {
             Self::parse_matches_publishers_show(m)
         } else if let Some(m) = matches.subcommand_matches("response") {
             Self::parse_matches_publishers_repo_response(m)
+        } else if let Some(m) = matches.subcommand_matches("objects") {
+            Self::parse_matches_publishers_objects(m)
+        } else if let Some(m) = matches.subcommand_matches("diff") {
+            Self::parse_matches_publishers_diff(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -2849,6 +3914,62 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_pubserver_archive_export(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+
+        let query = match matches.value_of("serial") {
+            Some(serial) => {
+                let serial = u64::from_str(serial)
+                    .map_err(|_| Error::general("serial must be a number"))?;
+                let session = match matches.value_of("session") {
+                    Some(session) => {
+                        Some(RrdpSession::from_str(session).map_err(|e| {
+                            Error::general(&format!("invalid session: {}", e))
+                        })?)
+                    }
+                    None => None,
+                };
+                RrdpArchiveQuery::Serial(serial, session)
+            }
+            None => {
+                let time = Time::from_str(matches.value_of("time").unwrap())
+                    .map_err(|e| {
+                        Error::general(&format!("invalid date format: {}", e))
+                    })?;
+                RrdpArchiveQuery::Time(Timestamp::from(time))
+            }
+        };
+
+        let export = if let Some(dir) = matches.value_of("dir") {
+            Some(RrdpArchiveExport::Dir(PathBuf::from(dir)))
+        } else {
+            matches.value_of("tarball").map(|tarball| {
+                RrdpArchiveExport::Tarball(PathBuf::from(tarball))
+            })
+        };
+
+        let command = Command::PubServer(PubServerCommand::ArchiveExport(
+            query, export,
+        ));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_pubserver_archive(
+        matches: &ArgMatches,
+    ) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::PubServer(PubServerCommand::ArchiveIndex);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("export") {
+            Self::parse_matches_pubserver_archive_export(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
     fn parse_matches_pubserver(
         matches: &ArgMatches,
     ) -> Result<Options, Error> {
@@ -2858,6 +3979,8 @@ impl Options {
             Self::parse_matches_delete(m)
         } else if let Some(m) = matches.subcommand_matches("server") {
             Self::parse_matches_publication_server(m)
+        } else if let Some(m) = matches.subcommand_matches("archive") {
+            Self::parse_matches_pubserver_archive(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -2888,10 +4011,16 @@ impl Options {
             Self::parse_matches_cas_bgpsec(m)
         } else if let Some(m) = matches.subcommand_matches("aspas") {
             Self::parse_matches_cas_aspas(m)
+        } else if let Some(m) = matches.subcommand_matches("ghostbusters") {
+            Self::parse_matches_cas_ghostbusters(m)
+        } else if let Some(m) = matches.subcommand_matches("changes") {
+            Self::parse_matches_cas_changes(m)
         } else if let Some(m) = matches.subcommand_matches("repo") {
             Self::parse_matches_cas_repo(m)
         } else if let Some(m) = matches.subcommand_matches("issues") {
             Self::parse_matches_cas_issues(m)
+        } else if let Some(m) = matches.subcommand_matches("rsc") {
+            Self::parse_matches_cas_rsc(m)
         } else if let Some(m) = matches.subcommand_matches("rta") {
             Self::parse_matches_cas_rta(m)
         } else if let Some(m) = matches.subcommand_matches("bulk") {
@@ -2902,6 +4031,8 @@ impl Options {
             Self::parse_matches_info(m)
         } else if let Some(m) = matches.subcommand_matches("pubserver") {
             Self::parse_matches_pubserver(m)
+        } else if let Some(m) = matches.subcommand_matches("tokens") {
+            Self::parse_matches_tokens(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -2922,6 +4053,7 @@ pub enum Command {
     Bulk(BulkCaCommand),
     CertAuth(CaCommand),
     PubServer(PubServerCommand),
+    Tokens(TokenCommand),
     Init(KrillInitDetails),
     #[cfg(feature = "multi-user")]
     User(KrillUserDetails),
@@ -2967,8 +4099,13 @@ pub enum CaCommand {
     // Authorizations
     RouteAuthorizationsList(CaHandle),
     RouteAuthorizationsUpdate(CaHandle, RoaConfigurationUpdates),
+    RouteAuthorizationsExport(CaHandle, RoaFileFormat),
+    RouteAuthorizationsImport(CaHandle, RoaImport),
     RouteAuthorizationsTryUpdate(CaHandle, RoaConfigurationUpdates),
     RouteAuthorizationsDryRunUpdate(CaHandle, RoaConfigurationUpdates),
+    RouteAuthorizationsSchedule(CaHandle, ScheduleRoaUpdatesRequest),
+    RouteAuthorizationsScheduledList(CaHandle),
+    RouteAuthorizationsScheduleCancel(CaHandle, u64),
     BgpAnalysisFull(CaHandle),
     BgpAnalysisSuggest(CaHandle, Option<ResourceSet>),
 
@@ -2977,6 +4114,16 @@ pub enum CaCommand {
     AspasAddOrReplace(CaHandle, AspaDefinition),
     AspasUpdate(CaHandle, CustomerAsn, AspaProvidersUpdate),
     AspasRemove(CaHandle, CustomerAsn),
+    AspasAnalyze(CaHandle, AsPaths),
+
+    // Ghostbuster records
+    GhostbustersList(CaHandle),
+    GhostbustersUpdate(CaHandle, GhostbusterRecordUpdates),
+
+    // Change requests
+    ChangeRequestsList(CaHandle),
+    ChangeRequestApprove(CaHandle, u64),
+    ChangeRequestReject(CaHandle, u64),
 
     // BGPSec
     BgpSecList(CaHandle),
@@ -2989,6 +4136,12 @@ pub enum CaCommand {
     ShowHistoryDetails(CaHandle, String),
     Issues(Option<CaHandle>),
 
+    // RSC
+    RscList(CaHandle),
+    RscShow(CaHandle, RscName, Option<PathBuf>),
+    RscSign(CaHandle, RscName, RscContentRequest),
+    RscVerify(CaHandle, RscVerifyRequest),
+
     // RTA
     RtaList(CaHandle),
     RtaShow(CaHandle, RtaName, Option<PathBuf>),
@@ -3053,6 +4206,13 @@ pub enum BulkCaCommand {
     Import(api::import::Structure),
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TokenCommand {
+    List,
+    Add(ApiTokenRequest),
+    Revoke(String),
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct KrillInitDetails {
     data_dir: Option<String>,
@@ -3128,12 +4288,23 @@ pub enum PubServerCommand {
     RemovePublisher(PublisherHandle),
     DeleteFiles(RepoFileDeleteCriteria),
     RepositoryResponse(PublisherHandle),
+    PublisherObjects(PublisherHandle),
+    PublisherObjectsDiff(PublisherHandle),
     StalePublishers(i64),
     PublisherList,
     RepositoryStats,
     RepositoryInit(PublicationServerUris),
     RepositoryClear,
     RepositorySessionReset,
+    ArchiveIndex,
+    ArchiveExport(RrdpArchiveQuery, Option<RrdpArchiveExport>),
+}
+
+/// Where to write the files of a reconstructed repository state.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RrdpArchiveExport {
+    Dir(PathBuf),
+    Tarball(PathBuf),
 }
 
 //------------ Error ---------------------------------------------------------
@@ -3146,9 +4317,11 @@ pub enum Error {
     Rfc8183(idexchange::Error),
     ResourceSetError(String),
     InvalidRouteDelta(AuthorizationFmtError),
+    InvalidRoaFile(RoaFileError),
     InvalidAsn(String),
     DuplicateAspaProvider(DuplicateProviderAs),
     InvalidAspaConfig(AspaDefinitionFormatError),
+    InvalidAsPaths(AsPathError),
     InvalidHandle,
     InvalidSeconds,
     MissingArgWithEnv(String, String),
@@ -3173,9 +4346,11 @@ impl fmt::Display for Error {
             Error::Rfc8183(e) => write!(f, "Invalid RFC 8183 XML: {}", e),
             Error::ResourceSetError(e) => write!(f, "Invalid resources requested: {}", e),
             Error::InvalidRouteDelta(e) => e.fmt(f),
+            Error::InvalidRoaFile(e) => e.fmt(f),
             Error::InvalidAsn(s) => write!(f, "Invalid ASN format. Expected 'AS#', got: {}", s),
             Error::DuplicateAspaProvider(e) => e.fmt(f),
             Error::InvalidAspaConfig(e) => e.fmt(f),
+            Error::InvalidAsPaths(e) => e.fmt(f),
             Error::InvalidHandle => write!(
                 f,
                 "The publisher handle may only contain -_A-Za-z0-9, (\\ /) see issue #83"
@@ -3234,12 +4409,24 @@ impl From<AuthorizationFmtError> for Error {
     }
 }
 
+impl From<RoaFileError> for Error {
+    fn from(e: RoaFileError) -> Self {
+        Error::InvalidRoaFile(e)
+    }
+}
+
 impl From<AspaDefinitionFormatError> for Error {
     fn from(e: AspaDefinitionFormatError) -> Self {
         Error::InvalidAspaConfig(e)
     }
 }
 
+impl From<AsPathError> for Error {
+    fn from(e: AsPathError) -> Self {
+        Error::InvalidAsPaths(e)
+    }
+}
+
 impl From<DuplicateProviderAs> for Error {
     fn from(e: DuplicateProviderAs) -> Self {
         Error::DuplicateAspaProvider(e)
@@ -8,21 +8,30 @@ use rpki::ca::idexchange;
 use crate::{
     commons::{
         api::{
-            import::ExportChild, AllCertAuthIssues, AspaDefinitionList,
-            BgpSecCsrInfoList, CaCommandDetails, CaRepoDetails, CertAuthInfo,
-            CertAuthIssues, CertAuthList, ChildCaInfo,
+            import::ExportChild, AllCertAuthIssues, ApiTokenIssued,
+            ApiTokenList, AspaDefinitionList, BgpSecCsrInfoList,
+            CaCommandDetails, CaRepoDetails, CertAuthInfo, CertAuthIssues,
+            CertAuthList, ChangeRequest, ChangeRequestList, ChildCaInfo,
             ChildrenConnectionStats, CommandHistory, ConfiguredRoas,
-            IdCertInfo, ParentCaContact, ParentStatuses, PublisherDetails,
-            PublisherList, RepoStatus, RepositoryContact, RtaList,
-            RtaPrepResponse, ServerInfo,
+            GhostbusterRecords, IdCertInfo, ParentCaContact, ParentStatuses,
+            PublisherDetails, PublisherList, PublisherObjects,
+            PublisherObjectsDiff, RepoStatus, RepositoryContact,
+            RoaImportResult, RrdpArchiveIndex, RrdpArchiveState, RscList,
+            RtaList, RtaPrepResponse, ScheduledRoaUpdatesList, ServerInfo,
         },
-        bgp::{BgpAnalysisAdvice, BgpAnalysisReport, BgpAnalysisSuggestion},
+        bgp::{
+            AspaAnalysisReport, BgpAnalysisAdvice, BgpAnalysisReport,
+            BgpAnalysisSuggestion,
+        },
+    },
+    daemon::ca::{
+        ResourceTaggedAttestation, RpkiSignedChecklist, RscVerification,
     },
-    daemon::ca::ResourceTaggedAttestation,
     pubd::RepoStats,
     ta::{
-        TrustAnchorProxySignerExchanges, TrustAnchorSignedRequest,
-        TrustAnchorSignedResponse, TrustAnchorSignerInfo,
+        TaKeyRollStatus, TaStagedKey, TrustAnchorProxySignerExchanges,
+        TrustAnchorSignedRequest, TrustAnchorSignedResponse,
+        TrustAnchorSignerInfo,
     },
 };
 
@@ -42,12 +51,22 @@ pub enum ApiResponse {
 
     // ROA related
     RouteAuthorizations(ConfiguredRoas),
+    RouteAuthorizationsScheduled(ScheduledRoaUpdatesList),
     BgpAnalysisAdvice(BgpAnalysisAdvice),
     BgpAnalysisFull(BgpAnalysisReport),
     BgpAnalysisSuggestions(BgpAnalysisSuggestion),
+    RoaImport(RoaImportResult),
 
     // ASPA related
     AspaDefinitions(AspaDefinitionList),
+    AspaAnalysis(AspaAnalysisReport),
+
+    // Ghostbuster records
+    GhostbusterRecords(GhostbusterRecords),
+
+    // Change requests
+    ChangeRequest(ChangeRequest),
+    ChangeRequests(ChangeRequestList),
 
     // BGPSec related
     BgpSecDefinitions(BgpSecCsrInfoList),
@@ -61,7 +80,11 @@ pub enum ApiResponse {
 
     PublisherDetails(PublisherDetails),
     PublisherList(PublisherList),
+    PublisherObjects(PublisherObjects),
+    PublisherObjectsDiff(PublisherObjectsDiff),
     RepoStats(RepoStats),
+    RrdpArchiveIndex(RrdpArchiveIndex),
+    RrdpArchiveState(RrdpArchiveState),
 
     Rfc8183ParentResponse(idexchange::ParentResponse),
     Rfc8183RepositoryResponse(idexchange::RepositoryResponse),
@@ -74,10 +97,17 @@ pub enum ApiResponse {
     CertAuthIssues(CertAuthIssues),
     AllCertAuthIssues(AllCertAuthIssues),
 
+    RscList(RscList),
+    Rsc(RpkiSignedChecklist),
+    RscVerification(RscVerification),
+
     RtaList(RtaList),
     RtaMultiPrep(RtaPrepResponse),
     Rta(ResourceTaggedAttestation),
 
+    ApiTokens(ApiTokenList),
+    ApiTokenIssued(ApiTokenIssued),
+
     Empty, // Typically a successful post just gets an empty 200 response
     GenericBody(String), /* For when the server echos Json to a
             * successful post */
@@ -113,6 +143,9 @@ impl ApiResponse {
                 ApiResponse::RouteAuthorizations(definitions) => {
                     Ok(Some(definitions.report(fmt)?))
                 }
+                ApiResponse::RouteAuthorizationsScheduled(scheduled) => {
+                    Ok(Some(scheduled.report(fmt)?))
+                }
                 ApiResponse::BgpAnalysisAdvice(analysis) => {
                     Ok(Some(analysis.report(fmt)?))
                 }
@@ -122,9 +155,24 @@ impl ApiResponse {
                 ApiResponse::BgpAnalysisSuggestions(suggestions) => {
                     Ok(Some(suggestions.report(fmt)?))
                 }
+                ApiResponse::RoaImport(result) => {
+                    Ok(Some(result.report(fmt)?))
+                }
                 ApiResponse::AspaDefinitions(definitions) => {
                     Ok(Some(definitions.report(fmt)?))
                 }
+                ApiResponse::AspaAnalysis(report) => {
+                    Ok(Some(report.report(fmt)?))
+                }
+                ApiResponse::GhostbusterRecords(records) => {
+                    Ok(Some(records.report(fmt)?))
+                }
+                ApiResponse::ChangeRequest(request) => {
+                    Ok(Some(request.report(fmt)?))
+                }
+                ApiResponse::ChangeRequests(requests) => {
+                    Ok(Some(requests.report(fmt)?))
+                }
                 ApiResponse::BgpSecDefinitions(definitions) => {
                     Ok(Some(definitions.report(fmt)?))
                 }
@@ -147,7 +195,19 @@ impl ApiResponse {
                 ApiResponse::PublisherDetails(details) => {
                     Ok(Some(details.report(fmt)?))
                 }
+                ApiResponse::PublisherObjects(objects) => {
+                    Ok(Some(objects.report(fmt)?))
+                }
+                ApiResponse::PublisherObjectsDiff(diff) => {
+                    Ok(Some(diff.report(fmt)?))
+                }
                 ApiResponse::RepoStats(stats) => Ok(Some(stats.report(fmt)?)),
+                ApiResponse::RrdpArchiveIndex(index) => {
+                    Ok(Some(index.report(fmt)?))
+                }
+                ApiResponse::RrdpArchiveState(state) => {
+                    Ok(Some(state.report(fmt)?))
+                }
                 ApiResponse::Rfc8183ParentResponse(res) => {
                     Ok(Some(res.report(fmt)?))
                 }
@@ -166,9 +226,18 @@ impl ApiResponse {
                 ApiResponse::RepoStatus(status) => {
                     Ok(Some(status.report(fmt)?))
                 }
+                ApiResponse::Rsc(rsc) => Ok(Some(rsc.report(fmt)?)),
+                ApiResponse::RscList(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::RscVerification(res) => {
+                    Ok(Some(res.report(fmt)?))
+                }
                 ApiResponse::Rta(rta) => Ok(Some(rta.report(fmt)?)),
                 ApiResponse::RtaList(list) => Ok(Some(list.report(fmt)?)),
                 ApiResponse::RtaMultiPrep(res) => Ok(Some(res.report(fmt)?)),
+                ApiResponse::ApiTokens(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::ApiTokenIssued(issued) => {
+                    Ok(Some(issued.report(fmt)?))
+                }
                 ApiResponse::GenericBody(body) => Ok(Some(body.clone())),
                 ApiResponse::Empty => Ok(None),
             }
@@ -263,6 +332,11 @@ impl Report for RepoStats {}
 impl Report for ChildrenConnectionStats {}
 
 impl Report for PublisherDetails {}
+impl Report for PublisherObjects {}
+impl Report for PublisherObjectsDiff {}
+
+impl Report for RrdpArchiveIndex {}
+impl Report for RrdpArchiveState {}
 
 impl Report for idexchange::RepositoryResponse {
     fn text(&self) -> Result<String, ReportError> {
@@ -289,12 +363,20 @@ impl Report for idexchange::PublisherRequest {
 }
 
 impl Report for ConfiguredRoas {}
+impl Report for ScheduledRoaUpdatesList {}
 
 impl Report for BgpAnalysisAdvice {}
 impl Report for BgpAnalysisReport {}
 impl Report for BgpAnalysisSuggestion {}
+impl Report for RoaImportResult {}
 
 impl Report for AspaDefinitionList {}
+impl Report for AspaAnalysisReport {}
+
+impl Report for GhostbusterRecords {}
+
+impl Report for ChangeRequest {}
+impl Report for ChangeRequestList {}
 
 impl Report for BgpSecCsrInfoList {}
 
@@ -307,11 +389,20 @@ impl Report for AllCertAuthIssues {}
 
 impl Report for ServerInfo {}
 
+impl Report for RpkiSignedChecklist {}
+impl Report for RscList {}
+impl Report for RscVerification {}
+
 impl Report for ResourceTaggedAttestation {}
 impl Report for RtaList {}
 impl Report for RtaPrepResponse {}
 
+impl Report for ApiTokenList {}
+impl Report for ApiTokenIssued {}
+
 impl Report for TrustAnchorSignerInfo {}
 impl Report for TrustAnchorSignedRequest {}
 impl Report for TrustAnchorSignedResponse {}
 impl Report for TrustAnchorProxySignerExchanges {}
+impl Report for TaKeyRollStatus {}
+impl Report for TaStagedKey {}
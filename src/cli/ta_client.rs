@@ -18,7 +18,7 @@ use crate::{
         actor::Actor,
         api::{
             AddChildRequest, ApiRepositoryContact, CertAuthInfo, IdCertInfo,
-            RepositoryContact, Token,
+            RepositoryContact, Token, UpdateChildRequest,
         },
         crypto::KrillSigner,
         error::Error as KrillError,
@@ -32,7 +32,8 @@ use crate::{
         KRILL_VERSION,
     },
     ta::{
-        self, Config, TrustAnchorHandle, TrustAnchorProxySignerExchanges,
+        self, Config, TaKeyRollRequest, TaKeyRollStatus, TaStagedKey,
+        TrustAnchorHandle, TrustAnchorProxySignerExchanges,
         TrustAnchorSignedRequest, TrustAnchorSignedResponse,
         TrustAnchorSigner, TrustAnchorSignerCommand, TrustAnchorSignerInfo,
         TrustAnchorSignerInitCommand, TrustAnchorSignerInitCommandDetails,
@@ -156,7 +157,11 @@ pub enum ProxyCommandDetails {
     SignerMakeRequest,
     SignerShowRequest,
     SignerProcessResponse(TrustAnchorSignedResponse),
+    KeyRollPlan(TaKeyRollRequest),
+    KeyRollShow,
     ChildAdd(AddChildRequest),
+    ChildUpdate(ChildHandle, UpdateChildRequest),
+    ChildRemove(ChildHandle),
     ChildResponse(ChildHandle),
 }
 
@@ -178,6 +183,7 @@ pub enum SignerCommandDetails {
     },
     ShowLastResponse,
     ShowExchanges,
+    ShowStagedKey,
 }
 
 #[derive(Debug)]
@@ -218,6 +224,7 @@ impl TrustAnchorClientCommand {
         sub = Self::make_proxy_id_sc(sub);
         sub = Self::make_proxy_repo_sc(sub);
         sub = Self::make_proxy_signer_sc(sub);
+        sub = Self::make_proxy_key_roll_sc(sub);
         sub = Self::make_proxy_children_sc(sub);
 
         app.subcommand(sub)
@@ -341,10 +348,49 @@ impl TrustAnchorClientCommand {
         app.subcommand(sub)
     }
 
+    fn make_proxy_key_roll_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("key-roll")
+            .about("Manage a planned Trust Anchor key roll");
+        sub = Self::make_proxy_key_roll_initiate_sc(sub);
+        sub = Self::make_proxy_key_roll_activate_sc(sub);
+        sub = Self::make_proxy_key_roll_show_sc(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_proxy_key_roll_initiate_sc<'a, 'b>(
+        app: App<'a, 'b>,
+    ) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("initiate").about(
+            "Plan that the signer stages a new key with a new TAL in the next signing session.",
+        );
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_tal_args(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_proxy_key_roll_activate_sc<'a, 'b>(
+        app: App<'a, 'b>,
+    ) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("activate").about(
+            "Plan that the signer activates the staged key in the next signing session.",
+        );
+        sub = GeneralArgs::add_args(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_proxy_key_roll_show_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("show")
+            .about("Show the planned key roll step and staged key, if any.");
+        sub = GeneralArgs::add_args(sub);
+        app.subcommand(sub)
+    }
+
     fn make_proxy_children_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("children")
             .about("Manage children under the TA proxy");
         sub = Self::make_proxy_children_add_sc(sub);
+        sub = Self::make_proxy_children_update_sc(sub);
+        sub = Self::make_proxy_children_remove_sc(sub);
         sub = Self::make_proxy_children_response_sc(sub);
         app.subcommand(sub)
     }
@@ -387,6 +433,48 @@ impl TrustAnchorClientCommand {
         app.subcommand(sub)
     }
 
+    fn make_proxy_children_update_sc<'a, 'b>(
+        app: App<'a, 'b>,
+    ) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("update").about("Update the resources of a child. The child will request a new certificate, which is signed in the next signer exchange. Resource types which are not specified are removed.");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_child_arg(sub);
+        sub = sub
+            .arg(
+                Arg::with_name("asn")
+                    .value_name("asn resources")
+                    .long("asn")
+                    .help("The ASN resources for the child")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("ipv4")
+                    .value_name("IPv4 resources")
+                    .long("ipv4")
+                    .help("The IPv4 resources for the child")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("ipv6")
+                    .value_name("IPv6 resources")
+                    .long("ipv6")
+                    .help("The IPv6 resources for the child")
+                    .required(false),
+            );
+
+        app.subcommand(sub)
+    }
+
+    fn make_proxy_children_remove_sc<'a, 'b>(
+        app: App<'a, 'b>,
+    ) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("remove").about("Remove a child. Its certificate is revoked in the next signer exchange.");
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_child_arg(sub);
+        app.subcommand(sub)
+    }
+
     fn make_proxy_children_response_sc<'a, 'b>(
         app: App<'a, 'b>,
     ) -> App<'a, 'b> {
@@ -408,6 +496,7 @@ impl TrustAnchorClientCommand {
         sub = Self::make_signer_process_sc(sub);
         sub = Self::make_signer_last_sc(sub);
         sub = Self::make_signer_exchanges_sc(sub);
+        sub = Self::make_signer_key_roll_sc(sub);
 
         app.subcommand(sub)
     }
@@ -433,21 +522,6 @@ impl TrustAnchorClientCommand {
                     .help("Path to Proxy ID JSON")
                     .required(true),
             )
-            .arg(
-                Arg::with_name("tal_rsync")
-                    .long("tal_rsync")
-                    .value_name("Rsync URI")
-                    .help("Used for TA certificate on TAL and AIA")
-                    .required(true),
-            )
-            .arg(
-                Arg::with_name("tal_https")
-                    .long("tal_https")
-                    .value_name("HTTPS URI")
-                    .help("Used for TAL. Multiple allowed.")
-                    .multiple(true)
-                    .required(true),
-            )
             .arg(
                 Arg::with_name("private_key_pem")
                     .long("private_key_pem")
@@ -462,6 +536,7 @@ impl TrustAnchorClientCommand {
                     .help("[OPTIONAL] Override the initial manifest number (defaults to 1)")
                     .required(false),
             );
+        sub = Self::add_tal_args(sub);
 
         app.subcommand(sub)
     }
@@ -515,8 +590,35 @@ impl TrustAnchorClientCommand {
         app.subcommand(sub)
     }
 
+    fn make_signer_key_roll_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("key-roll").about(
+            "Show the staged key and its TAL, if a key roll is in progress.",
+        );
+        sub = Self::add_config_arg(sub);
+        sub = Self::add_format_arg(sub);
+        app.subcommand(sub)
+    }
+
     //-- Arguments
 
+    fn add_tal_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("tal_rsync")
+                .long("tal_rsync")
+                .value_name("Rsync URI")
+                .help("Used for TA certificate on TAL and AIA")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("tal_https")
+                .long("tal_https")
+                .value_name("HTTPS URI")
+                .help("Used for TAL. Multiple allowed.")
+                .multiple(true)
+                .required(true),
+        )
+    }
+
     fn add_config_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         app.arg(
             Arg::with_name("config")
@@ -574,6 +676,8 @@ impl TrustAnchorClientCommand {
             Self::parse_matches_proxy_repo(m)
         } else if let Some(m) = matches.subcommand_matches("signer") {
             Self::parse_matches_proxy_signer(m)
+        } else if let Some(m) = matches.subcommand_matches("key-roll") {
+            Self::parse_matches_proxy_key_roll(m)
         } else if let Some(m) = matches.subcommand_matches("children") {
             Self::parse_matches_proxy_children(m)
         } else {
@@ -742,11 +846,74 @@ impl TrustAnchorClientCommand {
         }))
     }
 
+    fn parse_matches_proxy_key_roll(
+        matches: &ArgMatches,
+    ) -> Result<Self, TaClientError> {
+        if let Some(m) = matches.subcommand_matches("initiate") {
+            Self::parse_matches_proxy_key_roll_initiate(m)
+        } else if let Some(m) = matches.subcommand_matches("activate") {
+            Self::parse_matches_proxy_key_roll_activate(m)
+        } else if let Some(m) = matches.subcommand_matches("show") {
+            Self::parse_matches_proxy_key_roll_show(m)
+        } else {
+            Err(TaClientError::UnrecognizedMatch)
+        }
+    }
+
+    fn parse_matches_proxy_key_roll_initiate(
+        matches: &ArgMatches,
+    ) -> Result<Self, TaClientError> {
+        let general = GeneralArgs::from_matches(matches)
+            .map_err(|e| TaClientError::Other(e.to_string()))?;
+        let tal_https = Self::parse_tal_https_arg(matches)?;
+        let tal_rsync = Self::parse_tal_rsync_arg(matches)?;
+
+        Ok(TrustAnchorClientCommand::Proxy(ProxyCommand {
+            general,
+            details: ProxyCommandDetails::KeyRollPlan(
+                TaKeyRollRequest::Initiate {
+                    tal_https,
+                    tal_rsync,
+                },
+            ),
+        }))
+    }
+
+    fn parse_matches_proxy_key_roll_activate(
+        matches: &ArgMatches,
+    ) -> Result<Self, TaClientError> {
+        let general = GeneralArgs::from_matches(matches)
+            .map_err(|e| TaClientError::Other(e.to_string()))?;
+
+        Ok(TrustAnchorClientCommand::Proxy(ProxyCommand {
+            general,
+            details: ProxyCommandDetails::KeyRollPlan(
+                TaKeyRollRequest::Activate,
+            ),
+        }))
+    }
+
+    fn parse_matches_proxy_key_roll_show(
+        matches: &ArgMatches,
+    ) -> Result<Self, TaClientError> {
+        let general = GeneralArgs::from_matches(matches)
+            .map_err(|e| TaClientError::Other(e.to_string()))?;
+
+        Ok(TrustAnchorClientCommand::Proxy(ProxyCommand {
+            general,
+            details: ProxyCommandDetails::KeyRollShow,
+        }))
+    }
+
     fn parse_matches_proxy_children(
         matches: &ArgMatches,
     ) -> Result<Self, TaClientError> {
         if let Some(m) = matches.subcommand_matches("add") {
             Self::parse_matches_proxy_children_add(m)
+        } else if let Some(m) = matches.subcommand_matches("update") {
+            Self::parse_matches_proxy_children_update(m)
+        } else if let Some(m) = matches.subcommand_matches("remove") {
+            Self::parse_matches_proxy_children_remove(m)
         } else if let Some(m) = matches.subcommand_matches("response") {
             Self::parse_matches_proxy_children_response(m)
         } else {
@@ -781,6 +948,54 @@ impl TrustAnchorClientCommand {
         }))
     }
 
+    fn parse_matches_proxy_children_update(
+        matches: &ArgMatches,
+    ) -> Result<Self, TaClientError> {
+        let general = GeneralArgs::from_matches(matches)
+            .map_err(|e| TaClientError::Other(e.to_string()))?;
+        let child = Self::parse_child_arg(matches)?;
+
+        let asn = matches.value_of("asn");
+        let ipv4 = matches.value_of("ipv4");
+        let ipv6 = matches.value_of("ipv6");
+
+        if asn.is_none() && ipv4.is_none() && ipv6.is_none() {
+            return Err(TaClientError::Other(
+                "Specify at least one of: asn, ipv4, ipv6".to_string(),
+            ));
+        }
+
+        let resources = ResourceSet::from_strs(
+            asn.unwrap_or(""),
+            ipv4.unwrap_or(""),
+            ipv6.unwrap_or(""),
+        )
+        .map_err(|e| {
+            TaClientError::Other(format!("Cannot parse resources: {}", e))
+        })?;
+
+        Ok(TrustAnchorClientCommand::Proxy(ProxyCommand {
+            general,
+            details: ProxyCommandDetails::ChildUpdate(
+                child,
+                UpdateChildRequest::resources(resources),
+            ),
+        }))
+    }
+
+    fn parse_matches_proxy_children_remove(
+        matches: &ArgMatches,
+    ) -> Result<Self, TaClientError> {
+        let general = GeneralArgs::from_matches(matches)
+            .map_err(|e| TaClientError::Other(e.to_string()))?;
+        let child = Self::parse_child_arg(matches)?;
+
+        Ok(TrustAnchorClientCommand::Proxy(ProxyCommand {
+            general,
+            details: ProxyCommandDetails::ChildRemove(child),
+        }))
+    }
+
     fn parse_matches_proxy_children_response(
         matches: &ArgMatches,
     ) -> Result<Self, TaClientError> {
@@ -803,6 +1018,31 @@ impl TrustAnchorClientCommand {
         })
     }
 
+    fn parse_tal_https_arg(
+        matches: &ArgMatches,
+    ) -> Result<Vec<uri::Https>, TaClientError> {
+        let uri_strs = matches.values_of("tal_https").unwrap();
+        let mut uris = vec![];
+        for uri_str in uri_strs {
+            uris.push(uri::Https::from_str(uri_str).map_err(|_| {
+                TaClientError::Other(format!(
+                    "Invalid HTTPS URI: {}",
+                    uri_str
+                ))
+            })?);
+        }
+        Ok(uris)
+    }
+
+    fn parse_tal_rsync_arg(
+        matches: &ArgMatches,
+    ) -> Result<uri::Rsync, TaClientError> {
+        let rsync_str = matches.value_of("tal_rsync").unwrap();
+        uri::Rsync::from_str(rsync_str).map_err(|_| {
+            TaClientError::Other(format!("Invalid rsync uri: {}", rsync_str))
+        })
+    }
+
     fn read_file_arg(path_str: &str) -> Result<Bytes, TaClientError> {
         let path = PathBuf::from(path_str);
         file::read(&path).map_err(|e| {
@@ -841,6 +1081,8 @@ impl TrustAnchorClientCommand {
             Self::parse_matches_signer_last_response(m)
         } else if let Some(m) = matches.subcommand_matches("exchanges") {
             Self::parse_matches_signer_exchanges(m)
+        } else if let Some(m) = matches.subcommand_matches("key-roll") {
+            Self::parse_matches_signer_key_roll(m)
         } else {
             Err(TaClientError::UnrecognizedMatch)
         }
@@ -862,29 +1104,8 @@ impl TrustAnchorClientCommand {
             repo_contact.into()
         };
 
-        let tal_https = {
-            let uri_strs = matches.values_of("tal_https").unwrap();
-            let mut uris = vec![];
-            for uri_str in uri_strs {
-                uris.push(uri::Https::from_str(uri_str).map_err(|_| {
-                    TaClientError::Other(format!(
-                        "Invalid HTTPS URI: {}",
-                        uri_str
-                    ))
-                })?);
-            }
-            uris
-        };
-
-        let tal_rsync = {
-            let rsync_str = matches.value_of("tal_rsync").unwrap();
-            uri::Rsync::from_str(rsync_str).map_err(|_| {
-                TaClientError::Other(format!(
-                    "Invalid rsync uri: {}",
-                    rsync_str
-                ))
-            })?
-        };
+        let tal_https = Self::parse_tal_https_arg(matches)?;
+        let tal_rsync = Self::parse_tal_rsync_arg(matches)?;
 
         let ta_mft_nr_override = if let Some(number) =
             matches.value_of("initial_manifest_number")
@@ -997,6 +1218,19 @@ impl TrustAnchorClientCommand {
         }))
     }
 
+    fn parse_matches_signer_key_roll(
+        matches: &ArgMatches,
+    ) -> Result<Self, TaClientError> {
+        let config = Self::parse_config(matches)?;
+        let format = Self::parse_format(matches)?;
+
+        Ok(TrustAnchorClientCommand::Signer(SignerCommand {
+            config,
+            format,
+            details: SignerCommandDetails::ShowStagedKey,
+        }))
+    }
+
     fn parse_config(matches: &ArgMatches) -> Result<Config, TaClientError> {
         let config_path = matches.value_of("config").unwrap_or(CONFIG_PATH);
         Config::parse(config_path).map_err(TaClientError::ConfigError)
@@ -1091,6 +1325,19 @@ impl TrustAnchorClient {
                             )
                             .await
                     }
+                    ProxyCommandDetails::KeyRollPlan(key_roll) => {
+                        client
+                            .post_json("api/v1/ta/proxy/key_roll", key_roll)
+                            .await
+                    }
+                    ProxyCommandDetails::KeyRollShow => {
+                        let status = client
+                            .get_json("api/v1/ta/proxy/key_roll")
+                            .await?;
+                        Ok(TrustAnchorClientApiResponse::KeyRollStatus(
+                            status,
+                        ))
+                    }
                     ProxyCommandDetails::ChildAdd(child) => {
                         let response = client
                             .post_json_with_response(
@@ -1102,6 +1349,16 @@ impl TrustAnchorClient {
                             response,
                         ))
                     }
+                    ProxyCommandDetails::ChildUpdate(child, update) => {
+                        let uri_path =
+                            format!("api/v1/ta/proxy/children/{}", child);
+                        client.post_json(&uri_path, update).await
+                    }
+                    ProxyCommandDetails::ChildRemove(child) => {
+                        let uri_path =
+                            format!("api/v1/ta/proxy/children/{}", child);
+                        client.delete(&uri_path).await
+                    }
                     ProxyCommandDetails::ChildResponse(child) => {
                         let uri_path = format!("api/v1/ta/proxy/children/{}/parent_response.json", child);
                         let response = client.get_json(&uri_path).await?;
@@ -1131,6 +1388,9 @@ impl TrustAnchorClient {
                     SignerCommandDetails::ShowExchanges => {
                         signer_manager.show_exchanges()
                     }
+                    SignerCommandDetails::ShowStagedKey => {
+                        signer_manager.show_staged_key()
+                    }
                 }
             }
         }
@@ -1147,6 +1407,8 @@ pub enum TrustAnchorClientApiResponse {
     SignerRequest(TrustAnchorSignedRequest),
     SignerResponse(TrustAnchorSignedResponse),
     ProxySignerExchanges(TrustAnchorProxySignerExchanges),
+    KeyRollStatus(TaKeyRollStatus),
+    StagedKey(TaStagedKey),
     Empty,
 }
 
@@ -1183,6 +1445,12 @@ impl TrustAnchorClientApiResponse {
                 TrustAnchorClientApiResponse::ProxySignerExchanges(
                     exchanges,
                 ) => exchanges.report(fmt).map(Some),
+                TrustAnchorClientApiResponse::KeyRollStatus(status) => {
+                    status.report(fmt).map(Some)
+                }
+                TrustAnchorClientApiResponse::StagedKey(staged) => {
+                    staged.report(fmt).map(Some)
+                }
                 TrustAnchorClientApiResponse::Empty => Ok(None),
             }
         }
@@ -1257,6 +1525,17 @@ impl ProxyClient {
             .map_err(TaClientError::HttpClientError)
     }
 
+    async fn delete(
+        &self,
+        path: &str,
+    ) -> Result<TrustAnchorClientApiResponse, TaClientError> {
+        let uri = self.resolve_uri(path);
+        httpclient::delete(&uri, Some(&self.token))
+            .await
+            .map(|_| TrustAnchorClientApiResponse::Empty)
+            .map_err(TaClientError::HttpClientError)
+    }
+
     async fn get_json<T: DeserializeOwned>(
         &self,
         path: &str,
@@ -1393,6 +1672,17 @@ impl TrustAnchorSignerManager {
         ))
     }
 
+    fn show_staged_key(
+        &self,
+    ) -> Result<TrustAnchorClientApiResponse, TaClientError> {
+        self.get_signer()?
+            .get_staged_key()
+            .map(|staged| {
+                TrustAnchorClientApiResponse::StagedKey(staged.clone())
+            })
+            .ok_or_else(|| TaClientError::other("No key is staged."))
+    }
+
     fn get_signer(&self) -> Result<Arc<TrustAnchorSigner>, TaClientError> {
         if self.store.has(&self.ta_handle)? {
             self.store
@@ -112,6 +112,36 @@ impl ActorDef {
         }
     }
 
+    /// Defines an actor for a named API token. The name is prefixed with
+    /// "token:" so that it cannot be confused with a user.
+    pub fn api_token(
+        name: &str,
+        attributes: HashMap<String, String>,
+    ) -> ActorDef {
+        ActorDef {
+            name: ActorName::AsString(format!("token:{}", name)),
+            is_user: false,
+            attributes: Attributes::UserDefined(attributes),
+            new_auth: None,
+            auth_error: None,
+        }
+    }
+
+    /// Defines an actor for a client which authenticated using a TLS
+    /// client certificate. The name is prefixed with "cert:".
+    pub fn client_cert(
+        name: &str,
+        attributes: HashMap<String, String>,
+    ) -> ActorDef {
+        ActorDef {
+            name: ActorName::AsString(format!("cert:{}", name)),
+            is_user: false,
+            attributes: Attributes::UserDefined(attributes),
+            new_auth: None,
+            auth_error: None,
+        }
+    }
+
     // Takes either a ApiAuthError or a commons::error::Error
     pub fn with_auth_error(mut self, api_error: Error) -> Self {
         self.auth_error = Some(api_error.into());
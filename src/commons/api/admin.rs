@@ -14,12 +14,16 @@ use rpki::{
         provisioning::ResourceClassName,
     },
     crypto::PublicKey,
-    repository::resources::ResourceSet,
+    repository::{resources::ResourceSet, x509::Time},
+    rrdp::Hash,
     uri,
 };
 
 use crate::commons::{
-    api::{rrdp::PublishElement, IdCertInfo, Timestamp},
+    api::{
+        rrdp::{DeltaElements, PublishElement, RrdpSession},
+        IdCertInfo, Timestamp,
+    },
     error::Error,
     KrillResult,
 };
@@ -53,6 +57,214 @@ impl fmt::Display for Token {
     }
 }
 
+//------------ ApiTokenRequest -----------------------------------------------
+
+/// Request to issue a named API token. The token will be limited to the
+/// given permissions, and - unless the list of CAs is empty - to the given
+/// CAs.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenRequest {
+    name: String,
+    permissions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cas: Vec<CaHandle>,
+    expires: Timestamp,
+}
+
+impl ApiTokenRequest {
+    pub fn new(
+        name: String,
+        permissions: Vec<String>,
+        cas: Vec<CaHandle>,
+        expires: Timestamp,
+    ) -> Self {
+        ApiTokenRequest {
+            name,
+            permissions,
+            cas,
+            expires,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn permissions(&self) -> &Vec<String> {
+        &self.permissions
+    }
+
+    pub fn cas(&self) -> &Vec<CaHandle> {
+        &self.cas
+    }
+
+    pub fn expires(&self) -> Timestamp {
+        self.expires
+    }
+}
+
+impl fmt::Display for ApiTokenRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "name: {} permissions: {}",
+            self.name,
+            self.permissions.join(",")
+        )?;
+        if !self.cas.is_empty() {
+            let cas: Vec<String> =
+                self.cas.iter().map(|ca| ca.to_string()).collect();
+            write!(f, " cas: {}", cas.join(","))?;
+        }
+        write!(f, " expires: {}", self.expires.to_rfc3339())
+    }
+}
+
+//------------ ApiTokenIssued ------------------------------------------------
+
+/// The response to issuing an API token. This is the only time that the
+/// actual token is disclosed, Krill only keeps a hash of it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenIssued {
+    name: String,
+    token: Token,
+    expires: Timestamp,
+}
+
+impl ApiTokenIssued {
+    pub fn new(name: String, token: Token, expires: Timestamp) -> Self {
+        ApiTokenIssued {
+            name,
+            token,
+            expires,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    pub fn expires(&self) -> Timestamp {
+        self.expires
+    }
+}
+
+impl fmt::Display for ApiTokenIssued {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.token)
+    }
+}
+
+//------------ ApiTokenInfo --------------------------------------------------
+
+/// Describes an issued API token, without disclosing the token itself.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenInfo {
+    name: String,
+    permissions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cas: Vec<CaHandle>,
+    issued: Timestamp,
+    expires: Timestamp,
+}
+
+impl ApiTokenInfo {
+    pub fn new(
+        name: String,
+        permissions: Vec<String>,
+        cas: Vec<CaHandle>,
+        issued: Timestamp,
+        expires: Timestamp,
+    ) -> Self {
+        ApiTokenInfo {
+            name,
+            permissions,
+            cas,
+            issued,
+            expires,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn permissions(&self) -> &Vec<String> {
+        &self.permissions
+    }
+
+    pub fn cas(&self) -> &Vec<CaHandle> {
+        &self.cas
+    }
+
+    pub fn issued(&self) -> Timestamp {
+        self.issued
+    }
+
+    pub fn expires(&self) -> Timestamp {
+        self.expires
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires < Timestamp::now()
+    }
+}
+
+impl fmt::Display for ApiTokenInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} permissions: {}",
+            self.name,
+            self.permissions.join(",")
+        )?;
+        if !self.cas.is_empty() {
+            let cas: Vec<String> =
+                self.cas.iter().map(|ca| ca.to_string()).collect();
+            write!(f, " cas: {}", cas.join(","))?;
+        }
+        write!(f, " expires: {}", self.expires.to_rfc3339())?;
+        if self.is_expired() {
+            write!(f, " (expired)")?;
+        }
+        Ok(())
+    }
+}
+
+//------------ ApiTokenList --------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenList {
+    tokens: Vec<ApiTokenInfo>,
+}
+
+impl ApiTokenList {
+    pub fn new(tokens: Vec<ApiTokenInfo>) -> Self {
+        ApiTokenList { tokens }
+    }
+
+    pub fn tokens(&self) -> &Vec<ApiTokenInfo> {
+        &self.tokens
+    }
+}
+
+impl fmt::Display for ApiTokenList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.tokens.is_empty() {
+            writeln!(f, "No API tokens issued.")
+        } else {
+            for token in &self.tokens {
+                writeln!(f, "{}", token)?;
+            }
+            Ok(())
+        }
+    }
+}
+
 //------------ PublicationServerUris -----------------------------------------
 
 /// Contains the information needed to initialize a new Publication Server
@@ -151,6 +363,10 @@ pub struct PublisherDetails {
     id_cert: IdCertInfo,
     base_uri: uri::Rsync,
     current_files: Vec<PublishElement>,
+    #[serde(default)]
+    size: usize,
+    #[serde(default)]
+    quota: PublisherQuota,
 }
 
 impl PublisherDetails {
@@ -159,12 +375,16 @@ impl PublisherDetails {
         id_cert: IdCertInfo,
         base_uri: uri::Rsync,
         current_files: Vec<PublishElement>,
+        quota: PublisherQuota,
     ) -> Self {
+        let size = current_files.iter().map(|f| f.size_approx()).sum();
         PublisherDetails {
             handle: handle.clone(),
             id_cert,
             base_uri,
             current_files,
+            size,
+            quota,
         }
     }
 
@@ -180,6 +400,15 @@ impl PublisherDetails {
     pub fn current_files(&self) -> &Vec<PublishElement> {
         &self.current_files
     }
+
+    /// Returns the approximate total size of the current files in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn quota(&self) -> PublisherQuota {
+        self.quota
+    }
 }
 
 impl fmt::Display for PublisherDetails {
@@ -187,6 +416,13 @@ impl fmt::Display for PublisherDetails {
         writeln!(f, "handle: {}", self.handle())?;
         writeln!(f, "id: {}", self.id_cert.public_key().key_identifier())?;
         writeln!(f, "base uri: {}", self.base_uri())?;
+        writeln!(
+            f,
+            "usage: {} objects, {} bytes",
+            self.current_files.len(),
+            self.size
+        )?;
+        writeln!(f, "quota: {}", self.quota)?;
         writeln!(f, "objects:")?;
         for e in &self.current_files {
             writeln!(f, "  {}", e.uri())?;
@@ -196,6 +432,314 @@ impl fmt::Display for PublisherDetails {
     }
 }
 
+//------------ PublisherQuota ------------------------------------------------
+
+/// Limits for the content of a publisher in the publication server.
+///
+/// Limits which are not set do not apply. Sizes are in bytes.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+pub struct PublisherQuota {
+    /// The maximum number of objects.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_objects: Option<usize>,
+
+    /// The maximum total size of all objects.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<usize>,
+
+    /// The maximum size of a single object.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_object_size: Option<usize>,
+}
+
+impl PublisherQuota {
+    /// Returns this quota, using the limits of the given quota for any
+    /// limits which are not set.
+    pub fn or(self, other: PublisherQuota) -> Self {
+        PublisherQuota {
+            max_objects: self.max_objects.or(other.max_objects),
+            max_size: self.max_size.or(other.max_size),
+            max_object_size: self.max_object_size.or(other.max_object_size),
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.max_objects.is_none()
+            && self.max_size.is_none()
+            && self.max_object_size.is_none()
+    }
+}
+
+impl fmt::Display for PublisherQuota {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_unlimited() {
+            return write!(f, "none");
+        }
+
+        let mut limits = vec![];
+        if let Some(max) = self.max_objects {
+            limits.push(format!("max {} objects", max));
+        }
+        if let Some(max) = self.max_size {
+            limits.push(format!("max {} bytes", max));
+        }
+        if let Some(max) = self.max_object_size {
+            limits.push(format!("max {} bytes per object", max));
+        }
+        write!(f, "{}", limits.join(", "))
+    }
+}
+
+//------------ PublisherObjects ----------------------------------------------
+
+/// This type defines the objects of a publisher in the publication server:
+/// /api/v1/pubd/publishers/{handle}/objects
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherObjects {
+    publisher: PublisherHandle,
+    objects: Vec<PublisherObject>,
+}
+
+impl PublisherObjects {
+    pub fn new(
+        publisher: PublisherHandle,
+        objects: Vec<PublisherObject>,
+    ) -> Self {
+        PublisherObjects { publisher, objects }
+    }
+
+    pub fn publisher(&self) -> &PublisherHandle {
+        &self.publisher
+    }
+
+    pub fn objects(&self) -> &Vec<PublisherObject> {
+        &self.objects
+    }
+}
+
+impl fmt::Display for PublisherObjects {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "URI, Type, Size, Expires, Hash")?;
+        for object in &self.objects {
+            writeln!(
+                f,
+                "{}, {}, {}, {}, {}",
+                object.uri,
+                object.object_type,
+                object.size,
+                object
+                    .expires
+                    .map(|time| time.to_rfc3339())
+                    .unwrap_or_else(|| "-".to_string()),
+                object.hash
+            )?;
+        }
+        Ok(())
+    }
+}
+
+//------------ PublisherObject -----------------------------------------------
+
+/// An object in the publication server. The expiry is the end of the
+/// validity of the certificate, or of the EE certificate of a signed object.
+/// It is not set for CRLs, or for objects that cannot be decoded.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherObject {
+    uri: uri::Rsync,
+    hash: Hash,
+    size: usize,
+    object_type: PublisherObjectType,
+    expires: Option<Time>,
+}
+
+impl PublisherObject {
+    pub fn new(
+        uri: uri::Rsync,
+        hash: Hash,
+        size: usize,
+        object_type: PublisherObjectType,
+        expires: Option<Time>,
+    ) -> Self {
+        PublisherObject {
+            uri,
+            hash,
+            size,
+            object_type,
+            expires,
+        }
+    }
+
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+
+    pub fn hash(&self) -> Hash {
+        self.hash
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn object_type(&self) -> PublisherObjectType {
+        self.object_type
+    }
+
+    pub fn expires(&self) -> Option<Time> {
+        self.expires
+    }
+}
+
+//------------ PublisherObjectType -------------------------------------------
+
+/// The type of a published object, based on its file extension.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublisherObjectType {
+    Certificate,
+    Crl,
+    Manifest,
+    Roa,
+    Aspa,
+    Ghostbusters,
+
+    /// An object with an unknown extension, or which cannot be decoded
+    /// as the type indicated by its extension.
+    Unknown,
+}
+
+impl fmt::Display for PublisherObjectType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PublisherObjectType::Certificate => write!(f, "certificate"),
+            PublisherObjectType::Crl => write!(f, "crl"),
+            PublisherObjectType::Manifest => write!(f, "manifest"),
+            PublisherObjectType::Roa => write!(f, "roa"),
+            PublisherObjectType::Aspa => write!(f, "aspa"),
+            PublisherObjectType::Ghostbusters => write!(f, "ghostbusters"),
+            PublisherObjectType::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+//------------ PublisherObjectsDiff ------------------------------------------
+
+/// This type defines the differences between the objects of a publisher in
+/// the publication server, and the objects that the CA with the same handle
+/// in this Krill instance believes it has published:
+/// /api/v1/pubd/publishers/{handle}/diff
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherObjectsDiff {
+    publisher: PublisherHandle,
+    differences: Vec<PublisherObjectDifference>,
+}
+
+impl PublisherObjectsDiff {
+    /// Creates the diff from the delta that would turn the objects in the
+    /// publication server into the objects of the CA.
+    pub fn new(publisher: PublisherHandle, delta: &DeltaElements) -> Self {
+        let mut differences: Vec<_> = delta
+            .publishes()
+            .iter()
+            .map(|p| PublisherObjectDifference {
+                uri: p.uri().clone(),
+                repository: None,
+                ca: Some(p.base64().to_hash()),
+            })
+            .chain(delta.updates().iter().map(|u| {
+                PublisherObjectDifference {
+                    uri: u.uri().clone(),
+                    repository: Some(u.hash()),
+                    ca: Some(u.base64().to_hash()),
+                }
+            }))
+            .chain(delta.withdraws().iter().map(|w| {
+                PublisherObjectDifference {
+                    uri: w.uri().clone(),
+                    repository: Some(w.hash()),
+                    ca: None,
+                }
+            }))
+            .collect();
+        differences.sort_by_key(|d| d.uri.to_string());
+
+        PublisherObjectsDiff {
+            publisher,
+            differences,
+        }
+    }
+
+    pub fn publisher(&self) -> &PublisherHandle {
+        &self.publisher
+    }
+
+    pub fn differences(&self) -> &Vec<PublisherObjectDifference> {
+        &self.differences
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+impl fmt::Display for PublisherObjectsDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.differences.is_empty() {
+            return writeln!(
+                f,
+                "Publication server and CA '{}' have the same objects",
+                self.publisher
+            );
+        }
+
+        let hash = |hash: Option<Hash>| {
+            hash.map(|h| h.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        };
+
+        writeln!(f, "URI, Publication Server, CA")?;
+        for difference in &self.differences {
+            writeln!(
+                f,
+                "{}, {}, {}",
+                difference.uri,
+                hash(difference.repository),
+                hash(difference.ca)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+//------------ PublisherObjectDifference -------------------------------------
+
+/// An object which is only found in the publication server, only in the CA,
+/// or which has different content in each.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherObjectDifference {
+    uri: uri::Rsync,
+    repository: Option<Hash>,
+    ca: Option<Hash>,
+}
+
+impl PublisherObjectDifference {
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+
+    /// The hash of the object in the publication server, if present.
+    pub fn repository(&self) -> Option<Hash> {
+        self.repository
+    }
+
+    /// The hash of the object that the CA published, if present.
+    pub fn ca(&self) -> Option<Hash> {
+        self.ca
+    }
+}
+
 //------------ PublicationServerInfo -----------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -741,6 +1285,166 @@ impl From<RepoFileDeleteCriteria> for uri::Rsync {
     }
 }
 
+//------------ RrdpArchiveIndex ----------------------------------------------
+
+/// This type defines the RRDP serials of the publication server for which
+/// snapshot and/or delta files are still available, either in the current
+/// RRDP directory or in the archive. See:
+/// /api/v1/pubd/archive
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RrdpArchiveIndex {
+    serials: Vec<RrdpArchiveSerial>,
+}
+
+impl RrdpArchiveIndex {
+    pub fn new(serials: Vec<RrdpArchiveSerial>) -> Self {
+        RrdpArchiveIndex { serials }
+    }
+
+    pub fn serials(&self) -> &Vec<RrdpArchiveSerial> {
+        &self.serials
+    }
+}
+
+impl fmt::Display for RrdpArchiveIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Session, Serial, Time, Snapshot, Delta")?;
+        for serial in &self.serials {
+            writeln!(
+                f,
+                "{}, {}, {}, {}, {}",
+                serial.session,
+                serial.serial,
+                serial.time.to_rfc3339(),
+                serial.snapshot,
+                serial.delta
+            )?;
+        }
+        Ok(())
+    }
+}
+
+//------------ RrdpArchiveSerial ---------------------------------------------
+
+/// The files available for an RRDP serial. The time is when the files were
+/// written, i.e. when the serial was published.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RrdpArchiveSerial {
+    session: RrdpSession,
+    serial: u64,
+    time: Timestamp,
+    snapshot: bool,
+    delta: bool,
+}
+
+impl RrdpArchiveSerial {
+    pub fn new(
+        session: RrdpSession,
+        serial: u64,
+        time: Timestamp,
+        snapshot: bool,
+        delta: bool,
+    ) -> Self {
+        RrdpArchiveSerial {
+            session,
+            serial,
+            time,
+            snapshot,
+            delta,
+        }
+    }
+
+    pub fn session(&self) -> RrdpSession {
+        self.session
+    }
+
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    pub fn time(&self) -> Timestamp {
+        self.time
+    }
+
+    pub fn snapshot(&self) -> bool {
+        self.snapshot
+    }
+
+    pub fn delta(&self) -> bool {
+        self.delta
+    }
+}
+
+//------------ RrdpArchiveQuery ----------------------------------------------
+
+/// Selects the RRDP serial for which the repository state is reconstructed
+/// from the archive.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RrdpArchiveQuery {
+    /// The given serial. If no session is given, then the most recent
+    /// session which has the serial is used.
+    Serial(u64, Option<RrdpSession>),
+
+    /// The serial which was current at the given time.
+    Time(Timestamp),
+}
+
+//------------ RrdpArchiveState ----------------------------------------------
+
+/// The content of the repository at an archived RRDP serial.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RrdpArchiveState {
+    session: RrdpSession,
+    serial: u64,
+    time: Timestamp,
+    objects: Vec<PublishElement>,
+}
+
+impl RrdpArchiveState {
+    pub fn new(
+        session: RrdpSession,
+        serial: u64,
+        time: Timestamp,
+        objects: Vec<PublishElement>,
+    ) -> Self {
+        RrdpArchiveState {
+            session,
+            serial,
+            time,
+            objects,
+        }
+    }
+
+    pub fn session(&self) -> RrdpSession {
+        self.session
+    }
+
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    pub fn time(&self) -> Timestamp {
+        self.time
+    }
+
+    pub fn objects(&self) -> &Vec<PublishElement> {
+        &self.objects
+    }
+}
+
+impl fmt::Display for RrdpArchiveState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "session: {}", self.session)?;
+        writeln!(f, "serial: {}", self.serial)?;
+        writeln!(f, "time: {}", self.time.to_rfc3339())?;
+        writeln!(f, "objects:")?;
+        for object in &self.objects {
+            writeln!(f, "  {}", object.uri())?;
+        }
+        Ok(())
+    }
+}
+
 //------------ Tests ---------------------------------------------------------
 
 #[cfg(test)]
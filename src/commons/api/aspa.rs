@@ -63,6 +63,10 @@ impl AspaDefinitionList {
     pub fn new(definitions: Vec<AspaDefinition>) -> Self {
         AspaDefinitionList(definitions)
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AspaDefinition> {
+        self.0.iter()
+    }
 }
 
 impl fmt::Display for AspaDefinitionList {
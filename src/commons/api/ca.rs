@@ -48,8 +48,10 @@ use crate::daemon::ca::BgpSecCertInfo;
 use crate::{
     commons::{
         api::{
-            rrdp::PublishElement, AspaDefinition, ErrorResponse,
-            ParentCaContact, RepositoryContact, RoaAggregateKey, RoaPayload,
+            rrdp::PublishElement, AspaDefinition, AspaDefinitionUpdates,
+            AspaProvidersUpdate, CustomerAsn, ErrorResponse, ParentCaContact,
+            RepositoryContact, RoaAggregateKey, RoaConfigurationUpdates,
+            RoaPayload, ScheduleRoaUpdatesRequest,
         },
         util::KrillVersion,
     },
@@ -607,6 +609,12 @@ impl ObjectName {
             format!("ROUTER-{:08X}-{}.cer", asn.into_u32(), key).into(),
         )
     }
+
+    /// There is at most one Ghostbuster record in each resource class, so
+    /// we can use a fixed name.
+    pub fn ghostbusters() -> Self {
+        ObjectName("ghostbusters.gbr".into())
+    }
 }
 
 impl From<&Cert> for ObjectName {
@@ -1360,6 +1368,72 @@ impl fmt::Display for RepoStatus {
     }
 }
 
+//------------ RepoValidationStatus ------------------------------------------
+
+/// The outcome of the last validation of the content that a CA published
+/// in its repository, as seen by relying parties.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepoValidationStatus {
+    timestamp: Timestamp,
+    objects: usize,
+    issues: Vec<RepoValidationIssue>,
+}
+
+impl RepoValidationStatus {
+    pub fn new(objects: usize, issues: Vec<RepoValidationIssue>) -> Self {
+        RepoValidationStatus {
+            timestamp: Timestamp::now(),
+            objects,
+            issues,
+        }
+    }
+
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    /// The number of objects found in the repository for the CA.
+    pub fn objects(&self) -> usize {
+        self.objects
+    }
+
+    pub fn issues(&self) -> &Vec<RepoValidationIssue> {
+        &self.issues
+    }
+}
+
+/// An issue found for a URI when validating the published content of a
+/// CA. The URI is usually the rsync URI of an object, but it can also be
+/// the RRDP notification URI if the content could not be retrieved.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepoValidationIssue {
+    uri: String,
+    issue: String,
+}
+
+impl RepoValidationIssue {
+    pub fn new(uri: impl fmt::Display, issue: impl fmt::Display) -> Self {
+        RepoValidationIssue {
+            uri: uri.to_string(),
+            issue: issue.to_string(),
+        }
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn issue(&self) -> &str {
+        &self.issue
+    }
+}
+
+impl fmt::Display for RepoValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.uri, self.issue)
+    }
+}
+
 //------------ ParentExchange ------------------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -1632,7 +1706,16 @@ impl ChildExchange {
 /// A wrapper for unix timestamps with second precision, with some convenient
 /// stuff.
 #[derive(
-    Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize,
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
 )]
 pub struct Timestamp(i64);
 
@@ -2128,6 +2211,9 @@ impl fmt::Display for AllCertAuthIssues {
                         )?;
                     }
                 }
+                for issue in issues.repo_content_issues() {
+                    writeln!(f, "   Published Content Issue: {}", issue)?;
+                }
             }
         }
         Ok(())
@@ -2140,6 +2226,8 @@ impl fmt::Display for AllCertAuthIssues {
 pub struct CertAuthIssues {
     repo_issue: Option<ErrorResponse>,
     parent_issues: Vec<CertAuthParentIssue>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    repo_content_issues: Vec<ErrorResponse>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -2170,8 +2258,19 @@ impl CertAuthIssues {
         &self.parent_issues
     }
 
+    /// Adds an issue found when validating the published content.
+    pub fn add_repo_content_issue(&mut self, issue: ErrorResponse) {
+        self.repo_content_issues.push(issue);
+    }
+
+    pub fn repo_content_issues(&self) -> &Vec<ErrorResponse> {
+        &self.repo_content_issues
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.repo_issue.is_none() && self.parent_issues.is_empty()
+        self.repo_issue.is_none()
+            && self.parent_issues.is_empty()
+            && self.repo_content_issues.is_empty()
     }
 }
 
@@ -2193,6 +2292,9 @@ impl fmt::Display for CertAuthIssues {
                     )?;
                 }
             }
+            for issue in self.repo_content_issues() {
+                writeln!(f, "Published Content Issue: {}", issue)?;
+            }
         }
         Ok(())
     }
@@ -2341,6 +2443,161 @@ impl fmt::Display for RtaPrepResponse {
     }
 }
 
+pub type RscName = String;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RscList(Vec<RscName>);
+
+impl RscList {
+    pub fn new(list: Vec<RscName>) -> Self {
+        RscList(list)
+    }
+}
+
+impl fmt::Display for RscList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in &self.0 {
+            writeln!(f, "{}", name)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ ChangeRequestUpdates ------------------------------------------
+
+/// The updates in a change request which needs approval.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeRequestUpdates {
+    Routes(RoaConfigurationUpdates),
+    ScheduledRoutes(ScheduleRoaUpdatesRequest),
+    Aspas(AspaDefinitionUpdates),
+    AspaProviders {
+        customer: CustomerAsn,
+        update: AspaProvidersUpdate,
+    },
+}
+
+impl fmt::Display for ChangeRequestUpdates {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChangeRequestUpdates::Routes(updates) => {
+                writeln!(f, "Update ROA definitions:")?;
+                write!(f, "{}", updates)
+            }
+            ChangeRequestUpdates::ScheduledRoutes(request) => {
+                writeln!(
+                    f,
+                    "Schedule ROA definition updates not before {}:",
+                    request.not_before().to_rfc3339()
+                )?;
+                write!(f, "{}", request.updates())
+            }
+            ChangeRequestUpdates::Aspas(updates) => {
+                writeln!(f, "{}", updates)
+            }
+            ChangeRequestUpdates::AspaProviders { customer, update } => {
+                writeln!(
+                    f,
+                    "Update ASPA for customer {}: {}",
+                    customer, update
+                )
+            }
+        }
+    }
+}
+
+//------------ ChangeRequest -------------------------------------------------
+
+/// Route or ASPA updates which were submitted by a user, and which will
+/// only be applied when they are approved by a different user.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChangeRequest {
+    id: u64,
+    requested_by: String,
+    requested_at: Timestamp,
+    updates: ChangeRequestUpdates,
+}
+
+impl ChangeRequest {
+    pub fn new(
+        id: u64,
+        requested_by: String,
+        requested_at: Timestamp,
+        updates: ChangeRequestUpdates,
+    ) -> Self {
+        ChangeRequest {
+            id,
+            requested_by,
+            requested_at,
+            updates,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn requested_by(&self) -> &str {
+        &self.requested_by
+    }
+
+    pub fn requested_at(&self) -> Timestamp {
+        self.requested_at
+    }
+
+    pub fn updates(&self) -> &ChangeRequestUpdates {
+        &self.updates
+    }
+}
+
+impl fmt::Display for ChangeRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "id: {} requested by: {} at: {}",
+            self.id,
+            self.requested_by,
+            self.requested_at.to_rfc3339()
+        )?;
+        write!(f, "{}", self.updates)
+    }
+}
+
+//------------ ChangeRequestList ---------------------------------------------
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChangeRequestList(Vec<ChangeRequest>);
+
+impl ChangeRequestList {
+    pub fn new(requests: Vec<ChangeRequest>) -> Self {
+        ChangeRequestList(requests)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ChangeRequest> {
+        self.0.iter()
+    }
+}
+
+impl fmt::Display for ChangeRequestList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return writeln!(f, "No pending change requests.");
+        }
+        for (nr, request) in self.0.iter().enumerate() {
+            if nr > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", request)?;
+        }
+        Ok(())
+    }
+}
+
 //============ Tests =========================================================
 
 #[cfg(test)]
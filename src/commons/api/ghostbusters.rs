@@ -0,0 +1,341 @@
+//! Ghostbuster records
+//!
+//! A Ghostbuster record is a signed object which contains a vCard with the
+//! contact details of the operator of a CA, so that relying parties know
+//! who to contact when they have problems with the objects published by
+//! the CA. See: https://www.rfc-editor.org/rfc/rfc6493.html
+
+use std::{fmt, str::FromStr};
+
+use bytes::Bytes;
+use rpki::ca::provisioning::ResourceClassName;
+
+//------------ VCard -------------------------------------------------------
+
+/// The vCard contained in a Ghostbuster record.
+///
+/// RFC 6493 restricts vCards to version 4.0, and to a small set of
+/// properties. A vCard parsed from a string is checked against this
+/// profile, and its lines are terminated by CRLF as required by RFC 6350.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VCard(String);
+
+impl VCard {
+    /// The properties allowed by section 5 of RFC 6493.
+    const ALLOWED_PROPERTIES: [&'static str; 9] = [
+        "BEGIN", "VERSION", "FN", "N", "ORG", "ADR", "TEL", "EMAIL", "END",
+    ];
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn to_bytes(&self) -> Bytes {
+        Bytes::copy_from_slice(self.0.as_bytes())
+    }
+
+    /// Returns the formatted name (FN) of the contact, if present.
+    pub fn full_name(&self) -> Option<String> {
+        self.properties()
+            .into_iter()
+            .find(|(name, _)| name == "FN")
+            .map(|(_, value)| value)
+    }
+
+    /// Verifies that this vCard conforms to the profile in RFC 6493.
+    ///
+    /// Note that we need to check this separately from parsing, because
+    /// vCards received through the API are deserialized as is.
+    pub fn validate(&self) -> Result<(), String> {
+        let properties = self.properties();
+
+        for (name, _) in &properties {
+            if name.is_empty() {
+                return Err("every line must contain a property".to_string());
+            }
+            if !Self::ALLOWED_PROPERTIES.contains(&name.as_str()) {
+                return Err(format!("property '{}' is not allowed", name));
+            }
+        }
+
+        let value_of = |index: usize| {
+            properties
+                .get(index)
+                .map(|(name, value)| (name.as_str(), value.to_uppercase()))
+        };
+
+        if value_of(0) != Some(("BEGIN", "VCARD".to_string())) {
+            return Err("must start with BEGIN:VCARD".to_string());
+        }
+        if value_of(1) != Some(("VERSION", "4.0".to_string())) {
+            return Err("must have VERSION:4.0 after BEGIN".to_string());
+        }
+        if value_of(properties.len() - 1)
+            != Some(("END", "VCARD".to_string()))
+        {
+            return Err("must end with END:VCARD".to_string());
+        }
+
+        let count = |wanted: &str| {
+            properties.iter().filter(|(name, _)| name == wanted).count()
+        };
+
+        if count("BEGIN") != 1 || count("VERSION") != 1 || count("END") != 1 {
+            return Err("must contain exactly one vCard".to_string());
+        }
+        if count("FN") != 1 {
+            return Err("must contain exactly one FN property".to_string());
+        }
+        if count("ADR") + count("TEL") + count("EMAIL") == 0 {
+            return Err(
+                "must contain at least one ADR, TEL or EMAIL property"
+                    .to_string(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the (unfolded) properties as pairs of the upper case
+    /// property name without parameters, and the value. Lines which do
+    /// not contain a property result in an empty name.
+    fn properties(&self) -> Vec<(String, String)> {
+        let mut lines: Vec<String> = vec![];
+        for line in self.0.split("\r\n").filter(|line| !line.is_empty()) {
+            match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+                (Some(continuation), Some(last)) => {
+                    last.push_str(continuation)
+                }
+                _ => lines.push(line.to_string()),
+            }
+        }
+
+        lines
+            .into_iter()
+            .map(|line| match line.split_once(':') {
+                Some((name_and_params, value)) => {
+                    let name = name_and_params
+                        .split(';')
+                        .next()
+                        .unwrap_or_default()
+                        .to_uppercase();
+                    (name, value.to_string())
+                }
+                None => (String::new(), String::new()),
+            })
+            .collect()
+    }
+}
+
+impl FromStr for VCard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut vcard = String::new();
+        for line in s.trim().lines() {
+            vcard.push_str(line.trim_end_matches('\r'));
+            vcard.push_str("\r\n");
+        }
+
+        let vcard = VCard(vcard);
+        vcard.validate()?;
+        Ok(vcard)
+    }
+}
+
+impl fmt::Display for VCard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in self.0.lines() {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ GhostbusterRecord -------------------------------------------
+
+/// A Ghostbuster record defined for a CA. If no resource class is set, then
+/// the record is published in each resource class which does not have a
+/// record of its own.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GhostbusterRecord {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    resource_class: Option<ResourceClassName>,
+    vcard: VCard,
+}
+
+impl GhostbusterRecord {
+    pub fn new(
+        resource_class: Option<ResourceClassName>,
+        vcard: VCard,
+    ) -> Self {
+        GhostbusterRecord {
+            resource_class,
+            vcard,
+        }
+    }
+
+    pub fn resource_class(&self) -> Option<&ResourceClassName> {
+        self.resource_class.as_ref()
+    }
+
+    pub fn vcard(&self) -> &VCard {
+        &self.vcard
+    }
+
+    pub fn unpack(self) -> (Option<ResourceClassName>, VCard) {
+        (self.resource_class, self.vcard)
+    }
+}
+
+impl fmt::Display for GhostbusterRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.resource_class {
+            Some(rcn) => write!(f, "resource class '{}'", rcn)?,
+            None => write!(f, "all resource classes")?,
+        }
+        if let Some(name) = self.vcard.full_name() {
+            write!(f, " contact: {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ GhostbusterRecords ------------------------------------------
+
+/// The Ghostbuster records defined for a CA, as shown through the API.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GhostbusterRecords(Vec<GhostbusterRecord>);
+
+impl GhostbusterRecords {
+    pub fn new(records: Vec<GhostbusterRecord>) -> Self {
+        GhostbusterRecords(records)
+    }
+
+    pub fn records(&self) -> &Vec<GhostbusterRecord> {
+        &self.0
+    }
+}
+
+impl fmt::Display for GhostbusterRecords {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return writeln!(f, "No Ghostbuster records defined");
+        }
+        for record in &self.0 {
+            match record.resource_class() {
+                Some(rcn) => writeln!(f, "resource class: {}", rcn)?,
+                None => writeln!(f, "resource class: all")?,
+            }
+            writeln!(f, "{}", record.vcard())?;
+        }
+        Ok(())
+    }
+}
+
+//------------ GhostbusterRecordUpdates ------------------------------------
+
+/// Contains Ghostbuster record updates sent to the API. Records are
+/// identified by their resource class, where no resource class stands for
+/// the record that applies to all resource classes.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GhostbusterRecordUpdates {
+    add_or_replace: Vec<GhostbusterRecord>,
+    remove: Vec<Option<ResourceClassName>>,
+}
+
+impl GhostbusterRecordUpdates {
+    pub fn new(
+        add_or_replace: Vec<GhostbusterRecord>,
+        remove: Vec<Option<ResourceClassName>>,
+    ) -> Self {
+        GhostbusterRecordUpdates {
+            add_or_replace,
+            remove,
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn unpack(
+        self,
+    ) -> (Vec<GhostbusterRecord>, Vec<Option<ResourceClassName>>) {
+        (self.add_or_replace, self.remove)
+    }
+}
+
+impl fmt::Display for GhostbusterRecordUpdates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Update Ghostbuster records: ")?;
+        if !self.add_or_replace.is_empty() {
+            write!(f, " add or replace:")?;
+            for record in &self.add_or_replace {
+                write!(f, " {}", record)?;
+            }
+        }
+        if !self.remove.is_empty() {
+            write!(f, " remove for:")?;
+            for resource_class in &self.remove {
+                match resource_class {
+                    Some(rcn) => write!(f, " resource class '{}'", rcn)?,
+                    None => write!(f, " all resource classes")?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vcard() {
+        let vcard = VCard::from_str(
+            "BEGIN:VCARD\nVERSION:4.0\nFN:NOC\nORG:Example\n\
+             EMAIL;TYPE=work:noc@example.com\nEND:VCARD\n",
+        )
+        .unwrap();
+        assert_eq!(
+            vcard.as_str(),
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:NOC\r\nORG:Example\r\n\
+             EMAIL;TYPE=work:noc@example.com\r\nEND:VCARD\r\n"
+        );
+        assert_eq!(vcard.full_name().as_deref(), Some("NOC"));
+    }
+
+    #[test]
+    fn reject_vcard_outside_profile() {
+        let invalid = |s: &str| VCard::from_str(s).is_err();
+
+        // not a vCard
+        assert!(invalid("NOC, noc@example.com"));
+
+        // wrong version
+        assert!(invalid(
+            "BEGIN:VCARD\nVERSION:3.0\nFN:NOC\nEMAIL:noc@example.com\nEND:VCARD"
+        ));
+
+        // missing FN
+        assert!(invalid(
+            "BEGIN:VCARD\nVERSION:4.0\nEMAIL:noc@example.com\nEND:VCARD"
+        ));
+
+        // no ADR, TEL or EMAIL
+        assert!(invalid("BEGIN:VCARD\nVERSION:4.0\nFN:NOC\nEND:VCARD"));
+
+        // property not allowed by RFC 6493
+        assert!(invalid(
+            "BEGIN:VCARD\nVERSION:4.0\nFN:NOC\nEMAIL:noc@example.com\n\
+             URL:https://example.com\nEND:VCARD"
+        ));
+
+        // missing END
+        assert!(invalid(
+            "BEGIN:VCARD\nVERSION:4.0\nFN:NOC\nEMAIL:noc@example.com"
+        ));
+    }
+}
@@ -19,8 +19,9 @@ use rpki::{
 use crate::{
     commons::{
         api::{
-            ArgKey, ArgVal, AspaProvidersUpdate, CustomerAsn, Message,
-            RoaConfigurationUpdates, RtaName, StorableParentContact,
+            ArgKey, ArgVal, AspaProvidersUpdate, ChangeRequestUpdates,
+            CustomerAsn, Message, RoaConfigurationUpdates, RscName, RtaName,
+            StorableParentContact, Timestamp,
         },
         eventsourcing::{
             Event, InitEvent, StoredCommand, StoredEffect,
@@ -31,7 +32,8 @@ use crate::{
 };
 
 use super::{
-    AspaDefinitionUpdates, ResourceClassNameMapping, ResourceSetSummary,
+    AspaDefinitionUpdates, GhostbusterRecordUpdates,
+    ResourceClassNameMapping, ResourceSetSummary,
 };
 
 //------------ CommandHistory ------------------------------------------------
@@ -235,6 +237,22 @@ impl CommandSummary {
     pub fn with_rta_name(self, name: &str) -> Self {
         self.with_arg("rta_name", name)
     }
+
+    pub fn with_rsc_name(self, name: &str) -> Self {
+        self.with_arg("rsc_name", name)
+    }
+
+    pub fn with_not_before(self, not_before: Timestamp) -> Self {
+        self.with_arg("not_before", not_before.to_rfc3339())
+    }
+
+    pub fn with_scheduled_id(self, id: u64) -> Self {
+        self.with_arg("id", id)
+    }
+
+    pub fn with_change_request_id(self, id: u64) -> Self {
+        self.with_arg("id", id)
+    }
 }
 
 //------------ CommandHistoryCriteria ----------------------------------------
@@ -470,6 +488,17 @@ pub enum CertAuthStorableCommand {
     RoaDefinitionUpdates {
         updates: RoaConfigurationUpdates,
     },
+    RoaDefinitionsExpired,
+    RoaDefinitionUpdatesScheduled {
+        not_before: Timestamp,
+        updates: RoaConfigurationUpdates,
+    },
+    RoaDefinitionUpdatesScheduleCancelled {
+        id: u64,
+    },
+    RoaDefinitionUpdatesScheduleActivated {
+        id: u64,
+    },
     ReissueBeforeExpiring,
     ForceReissue,
     AspasUpdate {
@@ -482,6 +511,18 @@ pub enum CertAuthStorableCommand {
     AspaRemove {
         customer: CustomerAsn,
     },
+    GhostbustersUpdate {
+        updates: GhostbusterRecordUpdates,
+    },
+    ChangeRequestSubmitted {
+        updates: ChangeRequestUpdates,
+    },
+    ChangeRequestApproved {
+        id: u64,
+    },
+    ChangeRequestRejected {
+        id: u64,
+    },
     BgpSecDefinitionUpdates, // details in events
     RepoUpdate {
         service_uri: ServiceUri,
@@ -495,6 +536,9 @@ pub enum CertAuthStorableCommand {
     RtaCoSign {
         name: RtaName,
     },
+    RscSign {
+        name: RscName,
+    },
     Deactivate,
 }
 
@@ -605,6 +649,19 @@ impl WithStorableDetails for CertAuthStorableCommand {
                     .with_added(updates.added().len())
                     .with_removed(updates.removed().len())
             }
+            CertAuthStorableCommand::RoaDefinitionsExpired => CommandSummary::new("cmd-ca-roas-expired", self),
+            CertAuthStorableCommand::RoaDefinitionUpdatesScheduled { not_before, updates } => {
+                CommandSummary::new("cmd-ca-roas-scheduled", self)
+                    .with_not_before(*not_before)
+                    .with_added(updates.added().len())
+                    .with_removed(updates.removed().len())
+            }
+            CertAuthStorableCommand::RoaDefinitionUpdatesScheduleCancelled { id } => {
+                CommandSummary::new("cmd-ca-roas-scheduled-cancel", self).with_scheduled_id(*id)
+            }
+            CertAuthStorableCommand::RoaDefinitionUpdatesScheduleActivated { id } => {
+                CommandSummary::new("cmd-ca-roas-scheduled-activate", self).with_scheduled_id(*id)
+            }
 
             // ASPA
             CertAuthStorableCommand::AspasUpdate { .. } => CommandSummary::new("cmd-ca-aspas-update", self),
@@ -613,6 +670,22 @@ impl WithStorableDetails for CertAuthStorableCommand {
             }
             CertAuthStorableCommand::AspaRemove { .. } => CommandSummary::new("cmd-ca-aspas-remove", self),
 
+            // Ghostbuster records
+            CertAuthStorableCommand::GhostbustersUpdate { .. } => {
+                CommandSummary::new("cmd-ca-ghostbusters-update", self)
+            }
+
+            // Change Requests
+            CertAuthStorableCommand::ChangeRequestSubmitted { .. } => {
+                CommandSummary::new("cmd-ca-change-request-submit", self)
+            }
+            CertAuthStorableCommand::ChangeRequestApproved { id } => {
+                CommandSummary::new("cmd-ca-change-request-approve", self).with_change_request_id(*id)
+            }
+            CertAuthStorableCommand::ChangeRequestRejected { id } => {
+                CommandSummary::new("cmd-ca-change-request-reject", self).with_change_request_id(*id)
+            }
+
             // BGPSec
             CertAuthStorableCommand::BgpSecDefinitionUpdates => CommandSummary::new("cmd-bgpsec-update", self),
 
@@ -637,6 +710,11 @@ impl WithStorableDetails for CertAuthStorableCommand {
                 CommandSummary::new("cmd-ca-rta-cosign", self).with_rta_name(name)
             }
 
+            // RSC
+            CertAuthStorableCommand::RscSign { name } => {
+                CommandSummary::new("cmd-ca-rsc-sign", self).with_rsc_name(name)
+            }
+
             // Deactivation
             CertAuthStorableCommand::Deactivate => CommandSummary::new("cmd-ca-deactivate", self),
         }
@@ -791,6 +869,31 @@ impl fmt::Display for CertAuthStorableCommand {
                 }
                 Ok(())
             }
+            CertAuthStorableCommand::RoaDefinitionsExpired => {
+                write!(f, "Remove expired ROAs")
+            }
+            CertAuthStorableCommand::RoaDefinitionUpdatesScheduled { not_before, updates } => {
+                write!(f, "Schedule ROA updates not before {}", not_before.to_rfc3339())?;
+                if !updates.added().is_empty() {
+                    write!(f, "  ADD:",)?;
+                    for addition in updates.added() {
+                        write!(f, " {}", addition)?;
+                    }
+                }
+                if !updates.removed().is_empty() {
+                    write!(f, "  REMOVE:",)?;
+                    for rem in updates.removed() {
+                        write!(f, " {}", rem)?;
+                    }
+                }
+                Ok(())
+            }
+            CertAuthStorableCommand::RoaDefinitionUpdatesScheduleCancelled { id } => {
+                write!(f, "Cancel scheduled ROA updates with id '{}'", id)
+            }
+            CertAuthStorableCommand::RoaDefinitionUpdatesScheduleActivated { id } => {
+                write!(f, "Activate scheduled ROA updates with id '{}'", id)
+            }
             CertAuthStorableCommand::ReissueBeforeExpiring => {
                 write!(f, "Automatically re-issue objects before they would expire")
             }
@@ -811,6 +914,54 @@ impl fmt::Display for CertAuthStorableCommand {
                 write!(f, "Remove ASPA for customer AS: {}", customer)
             }
 
+            // ------------------------------------------------------------
+            // Ghostbuster records
+            // ------------------------------------------------------------
+            CertAuthStorableCommand::GhostbustersUpdate { updates } => {
+                write!(f, "{}", updates)
+            }
+
+            // ------------------------------------------------------------
+            // Change Requests
+            // ------------------------------------------------------------
+            CertAuthStorableCommand::ChangeRequestSubmitted { updates } => match updates {
+                ChangeRequestUpdates::Routes(updates) => {
+                    write!(f, "Request approval for ROA updates")?;
+                    if !updates.added().is_empty() {
+                        write!(f, "  ADD:",)?;
+                        for addition in updates.added() {
+                            write!(f, " {}", addition)?;
+                        }
+                    }
+                    if !updates.removed().is_empty() {
+                        write!(f, "  REMOVE:",)?;
+                        for rem in updates.removed() {
+                            write!(f, " {}", rem)?;
+                        }
+                    }
+                    Ok(())
+                }
+                ChangeRequestUpdates::ScheduledRoutes(request) => {
+                    write!(
+                        f,
+                        "Request approval for ROA updates scheduled not before {}",
+                        request.not_before().to_rfc3339()
+                    )
+                }
+                ChangeRequestUpdates::Aspas(updates) => {
+                    write!(f, "Request approval: {}", updates)
+                }
+                ChangeRequestUpdates::AspaProviders { customer, update } => {
+                    write!(f, "Request approval: update ASPA for customer AS: {} {}", customer, update)
+                }
+            },
+            CertAuthStorableCommand::ChangeRequestApproved { id } => {
+                write!(f, "Approve change request with id '{}'", id)
+            }
+            CertAuthStorableCommand::ChangeRequestRejected { id } => {
+                write!(f, "Reject change request with id '{}'", id)
+            }
+
             // ------------------------------------------------------------
             // BGPSec Support
             // ------------------------------------------------------------
@@ -830,6 +981,11 @@ impl fmt::Display for CertAuthStorableCommand {
             CertAuthStorableCommand::RtaSign { name } => write!(f, "RTA Sign {}", name),
             CertAuthStorableCommand::RtaCoSign { name } => write!(f, "RTA Co-Sign {}", name),
 
+            // ------------------------------------------------------------
+            // RSC
+            // ------------------------------------------------------------
+            CertAuthStorableCommand::RscSign { name } => write!(f, "RSC Sign {}", name),
+
             // ------------------------------------------------------------
             // Deactivate
             // ------------------------------------------------------------
@@ -12,6 +12,9 @@ pub use self::bgpsec::*;
 mod ca;
 pub use self::ca::*;
 
+mod ghostbusters;
+pub use self::ghostbusters::*;
+
 mod history;
 pub use self::history::*;
 
@@ -20,6 +23,9 @@ pub mod import;
 mod roas;
 pub use self::roas::*;
 
+mod roas_bulk;
+pub use self::roas_bulk::*;
+
 pub mod rrdp;
 
 use std::{collections::HashMap, fmt};
@@ -90,10 +96,22 @@ impl ErrorResponse {
         self.with_arg("uri", uri)
     }
 
+    pub fn with_path(self, path: &str) -> Self {
+        self.with_arg("path", path)
+    }
+
     pub fn with_base_uri(self, base_uri: impl fmt::Display) -> Self {
         self.with_arg("base_uri", base_uri)
     }
 
+    pub fn with_leader(self, leader: &str) -> Self {
+        self.with_arg("leader", leader)
+    }
+
+    pub fn with_token_name(self, name: &str) -> Self {
+        self.with_arg("token", name)
+    }
+
     pub fn with_ca(self, ca: &CaHandle) -> Self {
         self.with_arg("ca", ca)
     }
@@ -127,6 +145,14 @@ impl ErrorResponse {
         self.with_arg("bgpsec_csr", base64)
     }
 
+    pub fn with_scheduled_id(self, id: u64) -> Self {
+        self.with_arg("id", id)
+    }
+
+    pub fn with_change_request_id(self, id: u64) -> Self {
+        self.with_arg("id", id)
+    }
+
     pub fn with_roa_delta_error(
         mut self,
         roa_delta_error: &RoaDeltaError,
@@ -9,7 +9,7 @@ use rpki::repository::{
     roa::RoaIpAddress,
 };
 
-use crate::daemon::ca::RoaInfo;
+use crate::{commons::api::Timestamp, daemon::ca::RoaInfo};
 
 //------------ RoaAggregateKey ---------------------------------------------
 
@@ -369,11 +369,22 @@ pub struct RoaConfiguration {
     payload: RoaPayload,
     #[serde(default)] // missing is same as no comment
     comment: Option<String>,
+    // Time-limited configurations are removed once they expire.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<Timestamp>,
 }
 
 impl RoaConfiguration {
     pub fn new(payload: RoaPayload, comment: Option<String>) -> Self {
-        RoaConfiguration { payload, comment }
+        RoaConfiguration {
+            payload,
+            comment,
+            expires_at: None,
+        }
+    }
+
+    pub fn with_expires_at(self, expires_at: Option<Timestamp>) -> Self {
+        RoaConfiguration { expires_at, ..self }
     }
 
     pub fn unpack(self) -> (RoaPayload, Option<String>) {
@@ -388,11 +399,15 @@ impl RoaConfiguration {
         self.comment.as_ref()
     }
 
+    pub fn expires_at(&self) -> Option<Timestamp> {
+        self.expires_at
+    }
+
     /// Ensures that the payload uses an explicit max length
     pub fn into_explicit_max_length(self) -> Self {
         RoaConfiguration {
             payload: self.payload.into_explicit_max_length(),
-            comment: self.comment,
+            ..self
         }
     }
 }
@@ -420,7 +435,7 @@ impl FromStr for RoaConfiguration {
         let payload = RoaPayload::from_str(payload_part)?;
         let comment = parts.next().map(|s| s.trim().to_string());
 
-        Ok(RoaConfiguration { payload, comment })
+        Ok(RoaConfiguration::new(payload, comment))
     }
 }
 
@@ -438,10 +453,7 @@ impl PartialOrd for RoaConfiguration {
 
 impl From<RoaPayload> for RoaConfiguration {
     fn from(payload: RoaPayload) -> Self {
-        RoaConfiguration {
-            payload,
-            comment: None,
-        }
+        RoaConfiguration::new(payload, None)
     }
 }
 
@@ -512,7 +524,18 @@ impl AsRef<RoaConfiguration> for ConfiguredRoa {
 
 impl fmt::Display for ConfiguredRoa {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.roa_configuration)
+        // Highlight time-limited configurations. Note that we cannot
+        // simply extend the Display of RoaConfiguration, because that
+        // must remain parsable.
+        let config = &self.roa_configuration;
+        write!(f, "{}", config.payload)?;
+        if let Some(expires_at) = config.expires_at {
+            write!(f, " [expires: {}]", expires_at.to_rfc3339())?;
+        }
+        if let Some(comment) = &config.comment {
+            write!(f, " # {}", comment)?;
+        }
+        Ok(())
     }
 }
 
@@ -675,6 +698,123 @@ impl FromStr for RoaConfigurationUpdates {
     }
 }
 
+//------------ ScheduleRoaUpdatesRequest -----------------------------------
+
+/// Requests that ROA configuration updates are applied once the `not_before`
+/// time has passed, e.g. at the time of a planned prefix migration.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScheduleRoaUpdatesRequest {
+    not_before: Timestamp,
+    updates: RoaConfigurationUpdates,
+}
+
+impl ScheduleRoaUpdatesRequest {
+    pub fn new(
+        not_before: Timestamp,
+        updates: RoaConfigurationUpdates,
+    ) -> Self {
+        ScheduleRoaUpdatesRequest {
+            not_before,
+            updates,
+        }
+    }
+
+    pub fn not_before(&self) -> Timestamp {
+        self.not_before
+    }
+
+    pub fn updates(&self) -> &RoaConfigurationUpdates {
+        &self.updates
+    }
+
+    pub fn unpack(self) -> (Timestamp, RoaConfigurationUpdates) {
+        (self.not_before, self.updates)
+    }
+}
+
+//------------ ScheduledRoaUpdates -----------------------------------------
+
+/// ROA configuration updates which are pending until their `not_before`
+/// time. The id is unique within a CA.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScheduledRoaUpdates {
+    id: u64,
+    not_before: Timestamp,
+    updates: RoaConfigurationUpdates,
+}
+
+impl ScheduledRoaUpdates {
+    pub fn new(
+        id: u64,
+        not_before: Timestamp,
+        updates: RoaConfigurationUpdates,
+    ) -> Self {
+        ScheduledRoaUpdates {
+            id,
+            not_before,
+            updates,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn not_before(&self) -> Timestamp {
+        self.not_before
+    }
+
+    pub fn updates(&self) -> &RoaConfigurationUpdates {
+        &self.updates
+    }
+}
+
+impl fmt::Display for ScheduledRoaUpdates {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "id: {} not before: {}",
+            self.id,
+            self.not_before.to_rfc3339()
+        )?;
+        write!(f, "{}", self.updates)
+    }
+}
+
+//------------ ScheduledRoaUpdatesList -------------------------------------
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScheduledRoaUpdatesList(Vec<ScheduledRoaUpdates>);
+
+impl ScheduledRoaUpdatesList {
+    pub fn new(scheduled: Vec<ScheduledRoaUpdates>) -> Self {
+        ScheduledRoaUpdatesList(scheduled)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ScheduledRoaUpdates> {
+        self.0.iter()
+    }
+}
+
+impl fmt::Display for ScheduledRoaUpdatesList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return writeln!(f, "No scheduled ROA updates.");
+        }
+        for (nr, scheduled) in self.0.iter().enumerate() {
+            if nr > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", scheduled)?;
+        }
+        Ok(())
+    }
+}
+
 //------------ TypedPrefix -------------------------------------------------
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub enum TypedPrefix {
@@ -1050,6 +1190,26 @@ mod tests {
         parse_ser_de_print_configuration("2001:db8::/32-48 => 64496");
     }
 
+    #[test]
+    fn serde_roa_configuration_expires_at() {
+        let def = roa_configuration("192.168.0.0/16 => 64496 # scrubbing")
+            .with_expires_at(Some(Timestamp::new(1700000000)));
+
+        let json = serde_json::to_string(&def).unwrap();
+        assert_eq!(
+            json,
+            "{\"asn\":64496,\"prefix\":\"192.168.0.0/16\",\"comment\":\"scrubbing\",\"expires_at\":1700000000}"
+        );
+        let de: RoaConfiguration = serde_json::from_str(&json).unwrap();
+        assert_eq!(def, de);
+
+        let configured = ConfiguredRoa::new(def, vec![]);
+        assert_eq!(
+            configured.to_string(),
+            "192.168.0.0/16 => 64496 [expires: 2023-11-14T22:13:20+00:00] # scrubbing"
+        );
+    }
+
     #[test]
     fn serde_roa_payload() {
         fn parse_ser_de_print_payload(s: &str) {
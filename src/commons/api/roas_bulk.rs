@@ -0,0 +1,716 @@
+//! Bulk import and export of ROA configurations.
+//!
+//! ROA configurations can be exported to, and imported from, CSV files,
+//! SLURM (RFC 8416) files with locally added assertions, and JSON files
+//! using Krill's own format. An import is turned into the minimal
+//! [`RoaConfigurationUpdates`] needed to get from the current to the
+//! imported configurations.
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commons::{
+    api::{
+        AsNumber, RoaConfiguration, RoaConfigurationUpdates, RoaPayload,
+        TypedPrefix,
+    },
+    bgp::{BgpAnalysisReport, BgpAnalysisState},
+};
+
+const CSV_HEADER: &str = "ASN,IP Prefix,Max Length,Comment";
+
+//------------ RoaFileFormat -------------------------------------------------
+
+/// The file formats supported for bulk import and export.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RoaFileFormat {
+    Csv,
+    Slurm,
+    Json,
+}
+
+impl RoaFileFormat {
+    /// Guesses the format of a file from its content.
+    pub fn detect(content: &str) -> Self {
+        let content = content.trim_start();
+        if content.starts_with('{') && content.contains("slurmVersion") {
+            RoaFileFormat::Slurm
+        } else if content.starts_with('[') {
+            RoaFileFormat::Json
+        } else {
+            RoaFileFormat::Csv
+        }
+    }
+
+    pub fn export(self, roas: &[RoaConfiguration]) -> String {
+        match self {
+            RoaFileFormat::Csv => {
+                let mut res = format!("{}\n", CSV_HEADER);
+                for roa in roas {
+                    let payload = roa.payload();
+                    res.push_str(&format!(
+                        "AS{},{},{},{}\n",
+                        payload.asn(),
+                        payload.prefix(),
+                        payload.effective_max_length(),
+                        csv_field(roa.comment().map_or("", |c| c.as_str()))
+                    ));
+                }
+                res
+            }
+            RoaFileFormat::Slurm => {
+                let assertions = roas
+                    .iter()
+                    .map(|roa| SlurmPrefixAssertion {
+                        asn: roa.payload().asn(),
+                        prefix: roa.payload().prefix(),
+                        max_prefix_length: roa.payload().max_length(),
+                        comment: roa.comment().cloned(),
+                    })
+                    .collect();
+                let slurm = SlurmFile {
+                    slurm_version: 1,
+                    validation_output_filters: SlurmFilters::default(),
+                    locally_added_assertions: SlurmAssertions {
+                        prefix_assertions: assertions,
+                        bgpsec_assertions: vec![],
+                    },
+                };
+                serde_json::to_string_pretty(&slurm).unwrap()
+            }
+            RoaFileFormat::Json => {
+                serde_json::to_string_pretty(roas).unwrap()
+            }
+        }
+    }
+
+    pub fn parse(
+        self,
+        content: &str,
+    ) -> Result<Vec<RoaConfiguration>, RoaFileError> {
+        match self {
+            RoaFileFormat::Csv => Self::parse_csv(content),
+            RoaFileFormat::Slurm => {
+                let slurm: SlurmFile = serde_json::from_str(content)
+                    .map_err(RoaFileError::new)?;
+                if slurm.slurm_version != 1 {
+                    return Err(RoaFileError::new(format!(
+                        "unsupported SLURM version: {}",
+                        slurm.slurm_version
+                    )));
+                }
+
+                // Filters only make sense for a validator, they are ignored
+                Ok(slurm
+                    .locally_added_assertions
+                    .prefix_assertions
+                    .into_iter()
+                    .map(|assertion| {
+                        RoaConfiguration::new(
+                            RoaPayload::new(
+                                assertion.asn,
+                                assertion.prefix,
+                                assertion.max_prefix_length,
+                            ),
+                            assertion.comment,
+                        )
+                    })
+                    .collect())
+            }
+            RoaFileFormat::Json => {
+                serde_json::from_str(content).map_err(RoaFileError::new)
+            }
+        }
+    }
+
+    /// Parses CSV with the columns: ASN, prefix, max length and comment.
+    /// The max length and comment are optional. A header line, empty lines
+    /// and lines starting with '#' are skipped.
+    fn parse_csv(
+        content: &str,
+    ) -> Result<Vec<RoaConfiguration>, RoaFileError> {
+        let mut res = vec![];
+        for (idx, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |msg: &str| {
+                RoaFileError::new(format!("line {}: {}", idx + 1, msg))
+            };
+
+            let fields =
+                csv_split(line).ok_or_else(|| err("unclosed quote"))?;
+            let asn_str = fields[0].trim();
+            if res.is_empty() && asn_str.eq_ignore_ascii_case("ASN") {
+                continue; // header
+            }
+            if fields.len() < 2 {
+                return Err(err("expected at least an ASN and a prefix"));
+            }
+
+            let asn_str = asn_str
+                .strip_prefix("AS")
+                .or_else(|| asn_str.strip_prefix("as"))
+                .unwrap_or(asn_str);
+            let asn = AsNumber::from_str(asn_str)
+                .map_err(|_| err("invalid ASN"))?;
+            let prefix = TypedPrefix::from_str(fields[1].trim())
+                .map_err(|_| err("invalid prefix"))?;
+            let max_length = match fields.get(2).map(|s| s.trim()) {
+                None | Some("") => None,
+                Some(len) => Some(
+                    u8::from_str(len)
+                        .map_err(|_| err("invalid max length"))?,
+                ),
+            };
+            let comment = fields
+                .get(3)
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string());
+
+            res.push(RoaConfiguration::new(
+                RoaPayload::new(asn, prefix, max_length),
+                comment,
+            ));
+        }
+        Ok(res)
+    }
+}
+
+impl FromStr for RoaFileFormat {
+    type Err = RoaFileError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(RoaFileFormat::Csv),
+            "slurm" => Ok(RoaFileFormat::Slurm),
+            "json" => Ok(RoaFileFormat::Json),
+            _ => Err(RoaFileError::new(format!(
+                "unsupported format '{}', expected csv, slurm or json",
+                s
+            ))),
+        }
+    }
+}
+
+/// Quotes a CSV field if needed.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Splits a CSV line into its fields, returns None if a quote is not
+/// closed.
+fn csv_split(line: &str) -> Option<Vec<String>> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+//------------ Slurm ---------------------------------------------------------
+
+/// A SLURM file as defined in RFC 8416. Only the prefix assertions are used
+/// for ROA configurations, the other members are kept as they are.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmFile {
+    slurm_version: u8,
+    validation_output_filters: SlurmFilters,
+    locally_added_assertions: SlurmAssertions,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmFilters {
+    prefix_filters: Vec<serde_json::Value>,
+    bgpsec_filters: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmAssertions {
+    prefix_assertions: Vec<SlurmPrefixAssertion>,
+    bgpsec_assertions: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmPrefixAssertion {
+    asn: AsNumber,
+    prefix: TypedPrefix,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_prefix_length: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+//------------ RoaImportMode -------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RoaImportMode {
+    /// The imported configurations replace all current configurations.
+    Replace,
+
+    /// The imported configurations are added to the current
+    /// configurations.
+    Merge,
+}
+
+//------------ RoaImport -----------------------------------------------------
+
+/// Imports ROA configurations into a CA. The updates are always checked
+/// using a dry run first.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoaImport {
+    configurations: Vec<RoaConfiguration>,
+    mode: RoaImportMode,
+    dry_run: bool,
+    force: bool,
+}
+
+impl RoaImport {
+    pub fn new(
+        configurations: Vec<RoaConfiguration>,
+        mode: RoaImportMode,
+        dry_run: bool,
+        force: bool,
+    ) -> Self {
+        RoaImport {
+            configurations,
+            mode,
+            dry_run,
+            force,
+        }
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn force(&self) -> bool {
+        self.force
+    }
+
+    /// Returns the minimal updates needed to get from the current to the
+    /// imported configurations.
+    ///
+    /// Imported configurations without a comment or expiry time keep those
+    /// of a current configuration for the same payload, so that comments
+    /// added in Krill are not lost when importing a file without them.
+    pub fn updates(
+        &self,
+        current: &[RoaConfiguration],
+    ) -> Result<RoaConfigurationUpdates, RoaFileError> {
+        let current: HashMap<RoaPayload, &RoaConfiguration> = current
+            .iter()
+            .map(|roa| (roa.payload().into_explicit_max_length(), roa))
+            .collect();
+
+        let mut updates = RoaConfigurationUpdates::empty();
+        let mut imported: HashMap<RoaPayload, &RoaConfiguration> =
+            HashMap::new();
+
+        for roa in &self.configurations {
+            let payload = roa.payload().into_explicit_max_length();
+            if !payload.max_length_valid() {
+                return Err(RoaFileError::new(format!(
+                    "invalid max length: {}",
+                    roa.payload()
+                )));
+            }
+            if imported.insert(payload, roa).is_some() {
+                return Err(RoaFileError::new(format!(
+                    "duplicate entry: {}",
+                    roa.payload()
+                )));
+            }
+
+            match current.get(&payload) {
+                None => updates.add(roa.clone()),
+                Some(existing) => {
+                    let comment =
+                        roa.comment().or(existing.comment()).cloned();
+                    let expires_at =
+                        roa.expires_at().or(existing.expires_at());
+                    if comment.as_ref() != existing.comment()
+                        || expires_at != existing.expires_at()
+                    {
+                        updates.add(
+                            RoaConfiguration::new(payload, comment)
+                                .with_expires_at(expires_at),
+                        );
+                    }
+                }
+            }
+        }
+
+        if self.mode == RoaImportMode::Replace {
+            let mut removed: Vec<_> = current
+                .keys()
+                .filter(|payload| !imported.contains_key(payload))
+                .copied()
+                .collect();
+            removed.sort();
+            for payload in removed {
+                updates.remove(payload);
+            }
+        }
+
+        Ok(updates)
+    }
+}
+
+//------------ RoaImportResult -----------------------------------------------
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoaImportStatus {
+    /// The current configurations already match the import.
+    Unchanged,
+
+    /// The updates were applied.
+    Applied,
+
+    /// The updates were only checked.
+    DryRun,
+
+    /// The updates were submitted as a change request, and will only be
+    /// applied when it is approved.
+    Submitted,
+
+    /// The updates were not applied because they would leave invalid
+    /// announcements.
+    Rejected,
+}
+
+/// The updates for an import, and their effect on known announcements.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaImportResult {
+    status: RoaImportStatus,
+    updates: RoaConfigurationUpdates,
+    effect: BgpAnalysisReport,
+
+    /// The id of the change request, if the updates were submitted for
+    /// approval.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    change_request: Option<u64>,
+}
+
+impl RoaImportResult {
+    pub fn new(
+        status: RoaImportStatus,
+        updates: RoaConfigurationUpdates,
+        effect: BgpAnalysisReport,
+    ) -> Self {
+        RoaImportResult {
+            status,
+            updates,
+            effect,
+            change_request: None,
+        }
+    }
+
+    pub fn unchanged() -> Self {
+        RoaImportResult::new(
+            RoaImportStatus::Unchanged,
+            RoaConfigurationUpdates::empty(),
+            BgpAnalysisReport::new(vec![]),
+        )
+    }
+
+    /// Creates a result for updates which were submitted as the change
+    /// request with the given id.
+    pub fn submitted(
+        id: u64,
+        updates: RoaConfigurationUpdates,
+        effect: BgpAnalysisReport,
+    ) -> Self {
+        RoaImportResult {
+            status: RoaImportStatus::Submitted,
+            updates,
+            effect,
+            change_request: Some(id),
+        }
+    }
+
+    pub fn status(&self) -> RoaImportStatus {
+        self.status
+    }
+
+    pub fn updates(&self) -> &RoaConfigurationUpdates {
+        &self.updates
+    }
+
+    pub fn effect(&self) -> &BgpAnalysisReport {
+        &self.effect
+    }
+
+    pub fn change_request(&self) -> Option<u64> {
+        self.change_request
+    }
+}
+
+impl fmt::Display for RoaImportResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.status == RoaImportStatus::Unchanged {
+            return writeln!(f, "ROA configurations are up-to-date");
+        }
+
+        writeln!(f, "Updates:")?;
+        write!(f, "{}", self.updates)?;
+
+        let invalids: Vec<_> = [
+            BgpAnalysisState::AnnouncementInvalidAsn,
+            BgpAnalysisState::AnnouncementInvalidLength,
+        ]
+        .into_iter()
+        .flat_map(|state| self.effect.matching_announcements(state))
+        .collect();
+        if !invalids.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "These updates would leave the following invalids:")?;
+            for invalid in invalids {
+                writeln!(f, "  {}", invalid)?;
+            }
+        }
+
+        writeln!(f)?;
+        match self.status {
+            RoaImportStatus::Applied => writeln!(f, "The updates were applied"),
+            RoaImportStatus::DryRun => {
+                writeln!(f, "Dry run, the updates were not applied")
+            }
+            RoaImportStatus::Submitted => match self.change_request {
+                Some(id) => writeln!(
+                    f,
+                    "The updates were submitted for approval as change \
+                     request {}",
+                    id
+                ),
+                None => writeln!(f, "The updates were submitted for approval"),
+            },
+            RoaImportStatus::Rejected => writeln!(
+                f,
+                "The updates were not applied, use --force to apply them anyway"
+            ),
+            RoaImportStatus::Unchanged => Ok(()),
+        }
+    }
+}
+
+//------------ RoaFileError --------------------------------------------------
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoaFileError(String);
+
+impl RoaFileError {
+    fn new(msg: impl fmt::Display) -> Self {
+        RoaFileError(msg.to_string())
+    }
+}
+
+impl fmt::Display for RoaFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid ROA file: {}", self.0)
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test::roa_configuration;
+
+    fn current() -> Vec<RoaConfiguration> {
+        vec![
+            roa_configuration("10.0.0.0/24-24 => 64496 # office"),
+            roa_configuration("10.0.1.0/24-24 => 64496"),
+            roa_configuration("10.0.2.0/24-24 => 64497 # old"),
+        ]
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let roas = vec![
+            roa_configuration("10.0.0.0/22-24 => 64496 # office, \"main\""),
+            roa_configuration("2001:db8::/32-48 => 64497"),
+        ];
+
+        let csv = RoaFileFormat::Csv.export(&roas);
+        assert_eq!(
+            csv,
+            "ASN,IP Prefix,Max Length,Comment\n\
+             AS64496,10.0.0.0/22,24,\"office, \"\"main\"\"\"\n\
+             AS64497,2001:db8::/32,48,\n"
+        );
+        assert_eq!(RoaFileFormat::detect(&csv), RoaFileFormat::Csv);
+        assert_eq!(RoaFileFormat::Csv.parse(&csv).unwrap(), roas);
+    }
+
+    #[test]
+    fn parse_csv_without_header() {
+        let csv = "# exported from IPAM\n64496, 10.0.0.0/24\n\nAS64497,10.0.1.0/24,,\n";
+        assert_eq!(
+            RoaFileFormat::Csv.parse(csv).unwrap(),
+            vec![
+                roa_configuration("10.0.0.0/24 => 64496"),
+                roa_configuration("10.0.1.0/24 => 64497"),
+            ]
+        );
+
+        assert!(RoaFileFormat::Csv.parse("64496").is_err());
+        assert!(RoaFileFormat::Csv.parse("AS-X,10.0.0.0/24").is_err());
+        assert!(RoaFileFormat::Csv.parse("64496,10.0.0.0/24,x").is_err());
+        assert!(RoaFileFormat::Csv
+            .parse("64496,10.0.0.0/24,24,\"x")
+            .is_err());
+    }
+
+    #[test]
+    fn slurm_round_trip() {
+        let roas = vec![
+            roa_configuration("10.0.0.0/22-24 => 64496 # office"),
+            roa_configuration("2001:db8::/32 => 64497"),
+        ];
+
+        let slurm = RoaFileFormat::Slurm.export(&roas);
+        assert_eq!(RoaFileFormat::detect(&slurm), RoaFileFormat::Slurm);
+        assert_eq!(RoaFileFormat::Slurm.parse(&slurm).unwrap(), roas);
+
+        // Example from RFC 8416, section 3.5
+        let rfc_example = r#"{
+          "slurmVersion": 1,
+          "validationOutputFilters": {
+            "prefixFilters": [
+              { "prefix": "192.0.2.0/24", "comment": "All VRPs encompassed by prefix" }
+            ],
+            "bgpsecFilters": []
+          },
+          "locallyAddedAssertions": {
+            "prefixAssertions": [
+              {
+                "asn": 64496,
+                "prefix": "198.51.100.0/24",
+                "comment": "My other important route"
+              },
+              {
+                "asn": 64496,
+                "prefix": "2001:DB8::/32",
+                "maxPrefixLength": 48,
+                "comment": "My other important de-aggregated routes"
+              }
+            ],
+            "bgpsecAssertions": []
+          }
+        }"#;
+        assert_eq!(
+            RoaFileFormat::Slurm.parse(rfc_example).unwrap(),
+            vec![
+                roa_configuration(
+                    "198.51.100.0/24 => 64496 # My other important route"
+                ),
+                roa_configuration(
+                    "2001:db8::/32-48 => 64496 # My other important de-aggregated routes"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let roas = current();
+        let json = RoaFileFormat::Json.export(&roas);
+        assert_eq!(RoaFileFormat::detect(&json), RoaFileFormat::Json);
+        assert_eq!(RoaFileFormat::Json.parse(&json).unwrap(), roas);
+    }
+
+    #[test]
+    fn import_updates() {
+        let imported = vec![
+            roa_configuration("10.0.0.0/24 => 64496"),
+            roa_configuration("10.0.1.0/24 => 64496 # lab"),
+            roa_configuration("10.0.3.0/24 => 64497"),
+        ];
+
+        // The comment for 10.0.0.0/24 is kept, the new comment for
+        // 10.0.1.0/24 is set.
+        let merge = RoaImport::new(
+            imported.clone(),
+            RoaImportMode::Merge,
+            false,
+            false,
+        );
+        assert_eq!(
+            merge.updates(&current()).unwrap(),
+            RoaConfigurationUpdates::new(
+                vec![
+                    roa_configuration("10.0.1.0/24-24 => 64496 # lab"),
+                    roa_configuration("10.0.3.0/24 => 64497"),
+                ],
+                vec![]
+            )
+        );
+
+        let replace =
+            RoaImport::new(imported, RoaImportMode::Replace, false, false);
+        assert_eq!(
+            replace.updates(&current()).unwrap(),
+            RoaConfigurationUpdates::new(
+                vec![
+                    roa_configuration("10.0.1.0/24-24 => 64496 # lab"),
+                    roa_configuration("10.0.3.0/24 => 64497"),
+                ],
+                vec![RoaPayload::from_str("10.0.2.0/24-24 => 64497").unwrap()]
+            )
+        );
+
+        // Importing the current configurations results in no updates
+        let same =
+            RoaImport::new(current(), RoaImportMode::Replace, false, false);
+        assert!(same.updates(&current()).unwrap().is_empty());
+
+        let duplicate = RoaImport::new(
+            vec![
+                roa_configuration("10.0.3.0/24 => 64497"),
+                roa_configuration("10.0.3.0/24-24 => 64497"),
+            ],
+            RoaImportMode::Merge,
+            false,
+            false,
+        );
+        assert!(duplicate.updates(&current()).is_err());
+    }
+}
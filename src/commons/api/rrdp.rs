@@ -5,6 +5,7 @@ use std::{
     fmt, io,
     ops::{Add, AddAssign, Deref},
     path::PathBuf,
+    str::FromStr,
     sync::Arc,
     {collections::HashMap, path::Path},
 };
@@ -16,13 +17,17 @@ use uuid::Uuid;
 use rpki::{
     ca::publication,
     ca::{idexchange::PublisherHandle, publication::Base64},
-    repository::x509::Time,
+    repository::{
+        aspa::Aspa, sigobj::SignedObject, x509::Time, Cert, Crl, Manifest,
+        Roa,
+    },
     rrdp::Hash,
     uri,
     xml::decode::Name,
 };
 
 use crate::commons::{
+    api::{PublisherObject, PublisherObjectType, PublisherQuota},
     error::{Error, KrillIoError},
     util::file,
     KrillResult,
@@ -85,6 +90,14 @@ impl<'de> Deserialize<'de> for RrdpSession {
     }
 }
 
+impl FromStr for RrdpSession {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(RrdpSession)
+    }
+}
+
 impl fmt::Display for RrdpSession {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.0.hyphenated())
@@ -395,6 +408,34 @@ impl CurrentObjects {
         Ok(elements)
     }
 
+    /// Returns the details of all objects, ordered by URI.
+    pub fn to_publisher_objects(&self) -> KrillResult<Vec<PublisherObject>> {
+        let mut objects = vec![];
+
+        for (uri_key, base64) in self.0.iter() {
+            let uri = uri_key.try_into()?;
+            let bytes = base64.to_bytes();
+            let (object_type, expires) =
+                match DecodedObject::decode(&uri, bytes.as_ref()) {
+                    Ok(object) => (
+                        object.object_type(),
+                        object.cert().map(|cert| cert.validity().not_after()),
+                    ),
+                    Err(_) => (PublisherObjectType::Unknown, None),
+                };
+            objects.push(PublisherObject::new(
+                uri,
+                base64.to_hash(),
+                bytes.len(),
+                object_type,
+                expires,
+            ));
+        }
+
+        objects.sort_by_key(|object| object.uri().to_string());
+        Ok(objects)
+    }
+
     pub fn to_withdraw_elements(&self) -> KrillResult<Vec<WithdrawElement>> {
         let mut elements = vec![];
 
@@ -452,6 +493,175 @@ impl CurrentObjects {
         Ok(())
     }
 
+    /// Verifies that applying a delta keeps the objects within the given
+    /// quota.
+    ///
+    /// Updates which do not increase the number of objects or their total
+    /// size are allowed, even if the current objects already exceed the
+    /// quota, so that publishers can always clean up.
+    ///
+    /// Assumes that the delta was checked using [`verify_delta`].
+    pub fn verify_quota(
+        &self,
+        delta: &DeltaElements,
+        quota: PublisherQuota,
+    ) -> Result<(), PublicationDeltaError> {
+        if let Some(max) = quota.max_object_size {
+            let sizes = delta
+                .publishes()
+                .iter()
+                .map(|p| (p.uri(), p.size_approx()))
+                .chain(
+                    delta
+                        .updates()
+                        .iter()
+                        .map(|u| (u.uri(), u.size_approx())),
+                );
+
+            for (uri, size) in sizes {
+                if size > max {
+                    return Err(PublicationDeltaError::too_large(uri, max));
+                }
+            }
+        }
+
+        if let Some(max) = quota.max_objects {
+            let count = (self.len() + delta.publishes().len())
+                .saturating_sub(delta.withdraws().len());
+            if count > max && count > self.len() {
+                return Err(PublicationDeltaError::QuotaExceeded(format!(
+                    "{} objects exceeds the maximum of {}",
+                    count, max
+                )));
+            }
+        }
+
+        if let Some(max) = quota.max_size {
+            let current = self.size_approx();
+
+            // Updated and withdrawn objects replace, or remove, the
+            // current objects for their URIs.
+            let replaced: usize = delta
+                .updates()
+                .iter()
+                .map(|u| u.uri())
+                .chain(delta.withdraws().iter().map(|w| w.uri()))
+                .filter_map(|uri| self.0.get(&CurrentObjectUri::from(uri)))
+                .map(|base64| base64.size_approx())
+                .sum();
+
+            let size =
+                (current + delta.size_approx()).saturating_sub(replaced);
+            if size > max && size > current {
+                return Err(PublicationDeltaError::QuotaExceeded(format!(
+                    "{} bytes exceeds the maximum of {} bytes",
+                    size, max
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that the objects which are published or updated by a delta
+    /// are valid RPKI objects.
+    ///
+    /// Objects are decoded based on their file extension, and objects with
+    /// an unknown extension are rejected. Signed objects are rejected if
+    /// their EE certificate has expired. Manifests are rejected if any of
+    /// the listed objects that would be published does not match the hash
+    /// on the manifest.
+    ///
+    /// Assumes that the delta was checked using [`verify_delta`].
+    pub fn verify_objects(
+        &self,
+        delta: &DeltaElements,
+    ) -> Result<(), PublicationDeltaError> {
+        // Objects as they would be after applying the delta, for those
+        // objects which are changed by it. Withdrawn objects map to None.
+        // Other objects are looked up in the current objects.
+        let mut changed: HashMap<CurrentObjectUri, Option<&Base64>> =
+            HashMap::new();
+        for p in delta.publishes() {
+            changed.insert(p.uri().into(), Some(p.base64()));
+        }
+        for u in delta.updates() {
+            changed.insert(u.uri().into(), Some(u.base64()));
+        }
+        for w in delta.withdraws() {
+            changed.insert(w.uri().into(), None);
+        }
+
+        let objects = delta
+            .publishes()
+            .iter()
+            .map(|p| (p.uri(), p.base64()))
+            .chain(delta.updates().iter().map(|u| (u.uri(), u.base64())));
+
+        for (uri, base64) in objects {
+            self.verify_object(&changed, uri, base64).map_err(|msg| {
+                PublicationDeltaError::InvalidObject(uri.clone(), msg)
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn verify_object(
+        &self,
+        changed: &HashMap<CurrentObjectUri, Option<&Base64>>,
+        uri: &uri::Rsync,
+        base64: &Base64,
+    ) -> Result<(), String> {
+        let object = DecodedObject::decode(uri, base64.to_bytes().as_ref())
+            .map_err(|e| e.to_string())?;
+
+        if let DecodedObject::Manifest(mft) = &object {
+            self.verify_manifest_hashes(changed, uri, mft)?;
+        }
+
+        if let Some(ee_cert) = object.ee_cert() {
+            let not_after = ee_cert.validity().not_after();
+            if not_after < Time::now() {
+                return Err(format!(
+                    "EE certificate expired at {}",
+                    not_after.to_rfc3339()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verify_manifest_hashes(
+        &self,
+        changed: &HashMap<CurrentObjectUri, Option<&Base64>>,
+        mft_uri: &uri::Rsync,
+        mft: &Manifest,
+    ) -> Result<(), String> {
+        let base = mft_uri
+            .parent()
+            .ok_or_else(|| "manifest uri has no parent".to_string())?;
+
+        for (uri, hash) in mft.iter_uris(&base) {
+            let key = CurrentObjectUri::from(&uri);
+            let base64 = match changed.get(&key) {
+                Some(changed) => *changed,
+                None => self.0.get(&key),
+            };
+            if let Some(base64) = base64 {
+                if hash.verify(base64.to_bytes()).is_err() {
+                    return Err(format!(
+                        "hash on manifest does not match object: {}",
+                        uri
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Applies a delta to CurrentObjects.
     ///
     /// Assumes that the delta was checked using [`verify_delta`].
@@ -526,6 +736,110 @@ impl CurrentObjects {
     }
 }
 
+//------------ DecodedObject -------------------------------------------------
+
+/// A published object, decoded as the type indicated by its file extension.
+pub enum DecodedObject {
+    Certificate(Cert),
+    Crl(Crl),
+    Manifest(Manifest),
+    Roa(Roa),
+    Aspa(Aspa),
+    Ghostbusters(SignedObject),
+}
+
+impl DecodedObject {
+    /// Decodes an object based on the file extension of its URI.
+    pub fn decode(
+        uri: &uri::Rsync,
+        bytes: &[u8],
+    ) -> Result<Self, DecodeObjectError> {
+        let extension = uri.path().rsplit_once('.').map(|(_, ext)| ext);
+
+        match extension {
+            Some("cer") => Cert::decode(bytes)
+                .map(DecodedObject::Certificate)
+                .map_err(DecodeObjectError::invalid),
+            Some("crl") => Crl::decode(bytes)
+                .map(DecodedObject::Crl)
+                .map_err(DecodeObjectError::invalid),
+            Some("mft") => Manifest::decode(bytes, false)
+                .map(DecodedObject::Manifest)
+                .map_err(DecodeObjectError::invalid),
+            Some("roa") => Roa::decode(bytes, false)
+                .map(DecodedObject::Roa)
+                .map_err(DecodeObjectError::invalid),
+            Some("asa") => Aspa::decode(bytes, false)
+                .map(DecodedObject::Aspa)
+                .map_err(DecodeObjectError::invalid),
+            Some("gbr") => SignedObject::decode(bytes, false)
+                .map(DecodedObject::Ghostbusters)
+                .map_err(DecodeObjectError::invalid),
+            _ => Err(DecodeObjectError::UnsupportedType),
+        }
+    }
+
+    pub fn object_type(&self) -> PublisherObjectType {
+        match self {
+            DecodedObject::Certificate(_) => PublisherObjectType::Certificate,
+            DecodedObject::Crl(_) => PublisherObjectType::Crl,
+            DecodedObject::Manifest(_) => PublisherObjectType::Manifest,
+            DecodedObject::Roa(_) => PublisherObjectType::Roa,
+            DecodedObject::Aspa(_) => PublisherObjectType::Aspa,
+            DecodedObject::Ghostbusters(_) => {
+                PublisherObjectType::Ghostbusters
+            }
+        }
+    }
+
+    /// Returns the EE certificate of a signed object.
+    pub fn ee_cert(&self) -> Option<&Cert> {
+        match self {
+            DecodedObject::Certificate(_) | DecodedObject::Crl(_) => None,
+            DecodedObject::Manifest(mft) => Some(mft.cert()),
+            DecodedObject::Roa(roa) => Some(roa.cert()),
+            DecodedObject::Aspa(aspa) => Some(aspa.cert()),
+            DecodedObject::Ghostbusters(gbr) => Some(gbr.cert()),
+        }
+    }
+
+    /// Returns the certificate itself, or the EE certificate of a signed
+    /// object. CRLs have no certificate.
+    pub fn cert(&self) -> Option<&Cert> {
+        match self {
+            DecodedObject::Certificate(cert) => Some(cert),
+            _ => self.ee_cert(),
+        }
+    }
+}
+
+//------------ DecodeObjectError ---------------------------------------------
+
+#[derive(Clone, Debug)]
+pub enum DecodeObjectError {
+    UnsupportedType,
+    Invalid(String),
+}
+
+impl DecodeObjectError {
+    fn invalid(e: impl fmt::Display) -> Self {
+        DecodeObjectError::Invalid(e.to_string())
+    }
+}
+
+impl fmt::Display for DecodeObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeObjectError::UnsupportedType => {
+                write!(f, "unsupported object type")
+            }
+            DecodeObjectError::Invalid(e) => {
+                write!(f, "cannot decode: {}", e)
+            }
+        }
+    }
+}
+
 //------------ PublicationDeltaError
 //------------ ---------------------------------------------
 
@@ -535,6 +849,9 @@ pub enum PublicationDeltaError {
     UriOutsideJail(uri::Rsync, uri::Rsync),
     ObjectAlreadyPresent(uri::Rsync),
     NoObjectForHashAndOrUri(uri::Rsync),
+    ObjectTooLarge(uri::Rsync, usize),
+    QuotaExceeded(String),
+    InvalidObject(uri::Rsync, String),
 }
 
 impl fmt::Display for PublicationDeltaError {
@@ -553,6 +870,19 @@ impl fmt::Display for PublicationDeltaError {
             PublicationDeltaError::NoObjectForHashAndOrUri(uri) => {
                 write!(f, "File does not match hash at uri: {}", uri)
             }
+            PublicationDeltaError::ObjectTooLarge(uri, max) => {
+                write!(
+                    f,
+                    "File exceeds the maximum size of {} bytes: {}",
+                    max, uri
+                )
+            }
+            PublicationDeltaError::QuotaExceeded(msg) => {
+                write!(f, "Publisher quota exceeded: {}", msg)
+            }
+            PublicationDeltaError::InvalidObject(uri, msg) => {
+                write!(f, "Invalid object at uri: {}: {}", uri, msg)
+            }
         }
     }
 }
@@ -569,6 +899,10 @@ impl PublicationDeltaError {
     fn no_match(uri: &uri::Rsync) -> Self {
         PublicationDeltaError::NoObjectForHashAndOrUri(uri.clone())
     }
+
+    fn too_large(uri: &uri::Rsync, max: usize) -> Self {
+        PublicationDeltaError::ObjectTooLarge(uri.clone(), max)
+    }
 }
 
 //------------ RrdpFileRandom ------------------------------------------------
@@ -1172,6 +1506,245 @@ mod tests {
         assert!(objects.verify_delta(&withdraw_file1_updated, &jail).is_ok());
     }
 
+    #[test]
+    fn current_objects_verify_objects() {
+        let base = "rsync://localhost/repo/NLnetLabs/0/";
+        let mft_uri = rsync(&format!(
+            "{}B2F9D7A5A5A9D791BE2BB641C9AF19505D100C2F.mft",
+            base
+        ));
+        let crl_uri = rsync(&format!(
+            "{}B2F9D7A5A5A9D791BE2BB641C9AF19505D100C2F.crl",
+            base
+        ));
+
+        let mft = Base64::from_content(include_bytes!(
+            "../../../test-resources/migrations/v0_13_1_pubserver/repo/rsync/current/NLnetLabs/0/B2F9D7A5A5A9D791BE2BB641C9AF19505D100C2F.mft"
+        ));
+        let crl = Base64::from_content(include_bytes!(
+            "../../../test-resources/migrations/v0_13_1_pubserver/repo/rsync/current/NLnetLabs/0/B2F9D7A5A5A9D791BE2BB641C9AF19505D100C2F.crl"
+        ));
+        let other_crl = Base64::from_content(include_bytes!(
+            "../../../test-resources/migrations/v0_13_1_pubserver/repo/rsync/current/7B5FE8F755E6B83E280D0865A45EC2EAE3AD64BE.crl"
+        ));
+
+        fn publish(uri: &uri::Rsync, base64: &Base64) -> DeltaElements {
+            DeltaElements {
+                publishes: vec![PublishElement::new(
+                    base64.clone(),
+                    uri.clone(),
+                )],
+                updates: vec![],
+                withdraws: vec![],
+            }
+        }
+
+        fn rejected(
+            objects: &CurrentObjects,
+            delta: &DeltaElements,
+        ) -> String {
+            match objects.verify_objects(delta) {
+                Err(PublicationDeltaError::InvalidObject(_, msg)) => msg,
+                _ => panic!("Expected invalid object"),
+            }
+        }
+
+        let objects = CurrentObjects::default();
+
+        // A valid CRL is accepted
+        assert!(objects.verify_objects(&publish(&crl_uri, &crl)).is_ok());
+
+        // Objects which cannot be decoded are rejected
+        let garbage = Base64::from_content(&[1, 2, 3]);
+        rejected(&objects, &publish(&crl_uri, &garbage));
+        rejected(&objects, &publish(&mft_uri, &garbage));
+
+        // Objects with an unknown extension are rejected
+        let txt_uri = rsync(&format!("{}file.txt", base));
+        rejected(&objects, &publish(&txt_uri, &garbage));
+
+        // A manifest must match the objects that it lists
+        let mut objects = CurrentObjects::default();
+        objects.apply_delta(publish(&crl_uri, &other_crl));
+        let msg = rejected(&objects, &publish(&mft_uri, &mft));
+        assert!(msg.starts_with("hash on manifest does not match"));
+
+        // This manifest matches, but its EE certificate expired long ago
+        let mut objects = CurrentObjects::default();
+        objects.apply_delta(publish(&crl_uri, &crl));
+        let msg = rejected(&objects, &publish(&mft_uri, &mft));
+        assert!(msg.starts_with("EE certificate expired"));
+    }
+
+    #[test]
+    fn current_objects_verify_quota() {
+        let uri =
+            |name: &str| rsync(&format!("rsync://localhost/repo/{}", name));
+        let content = Base64::from_content(&[0; 300]);
+        let larger = Base64::from_content(&[0; 600]);
+        let size = content.size_approx();
+
+        let mut objects = CurrentObjects::default();
+        objects.apply_delta(DeltaElements {
+            publishes: vec![
+                PublishElement::new(content.clone(), uri("a.cer")),
+                PublishElement::new(content.clone(), uri("b.cer")),
+            ],
+            updates: vec![],
+            withdraws: vec![],
+        });
+
+        fn quota(
+            max_objects: Option<usize>,
+            max_size: Option<usize>,
+        ) -> PublisherQuota {
+            PublisherQuota {
+                max_objects,
+                max_size,
+                max_object_size: None,
+            }
+        }
+
+        let publish_c = DeltaElements {
+            publishes: vec![PublishElement::new(
+                content.clone(),
+                uri("c.cer"),
+            )],
+            updates: vec![],
+            withdraws: vec![],
+        };
+        let update_a = |base64: &Base64| DeltaElements {
+            publishes: vec![],
+            updates: vec![UpdateElement::new(
+                uri("a.cer"),
+                content.to_hash(),
+                base64.clone(),
+            )],
+            withdraws: vec![],
+        };
+
+        // A new object must fit in the number of objects and total size
+        assert!(objects
+            .verify_quota(&publish_c, quota(Some(3), Some(3 * size)))
+            .is_ok());
+        assert!(objects
+            .verify_quota(&publish_c, quota(Some(2), None))
+            .is_err());
+        assert!(objects
+            .verify_quota(&publish_c, quota(None, Some(3 * size - 1)))
+            .is_err());
+
+        // Withdrawn objects no longer count
+        let mut replace_a = publish_c.clone();
+        replace_a.withdraws =
+            vec![WithdrawElement::new(uri("a.cer"), content.to_hash())];
+        assert!(objects
+            .verify_quota(&replace_a, quota(Some(2), Some(2 * size)))
+            .is_ok());
+
+        // Updated objects count with their new size
+        let max_size = size + larger.size_approx();
+        assert!(objects
+            .verify_quota(&update_a(&larger), quota(None, Some(max_size)))
+            .is_ok());
+        assert!(objects
+            .verify_quota(&update_a(&larger), quota(None, Some(max_size - 1)))
+            .is_err());
+
+        // Updates which do not grow the objects are allowed over quota
+        assert!(objects
+            .verify_quota(&update_a(&content), quota(Some(1), Some(size)))
+            .is_ok());
+
+        // A single object may not exceed the maximum object size
+        let max_object_size = PublisherQuota {
+            max_object_size: Some(size),
+            ..Default::default()
+        };
+        assert!(matches!(
+            objects.verify_quota(&update_a(&larger), max_object_size),
+            Err(PublicationDeltaError::ObjectTooLarge(_, _))
+        ));
+    }
+
+    #[test]
+    fn current_objects_to_publisher_objects() {
+        let base = "rsync://localhost/repo/";
+        let mft_bytes = include_bytes!(
+            "../../../test-resources/migrations/v0_13_1_pubserver/repo/rsync/current/NLnetLabs/0/B2F9D7A5A5A9D791BE2BB641C9AF19505D100C2F.mft"
+        );
+        let crl_bytes = include_bytes!(
+            "../../../test-resources/migrations/v0_13_1_pubserver/repo/rsync/current/NLnetLabs/0/B2F9D7A5A5A9D791BE2BB641C9AF19505D100C2F.crl"
+        );
+        let cer_bytes = include_bytes!(
+            "../../../test-resources/migrations/v0_13_1_pubserver/repo/rsync/current/1B73D6E56F851983B02CD2ED642B6A94BF368AEF.cer"
+        );
+
+        let mut objects = CurrentObjects::default();
+        objects.apply_delta(DeltaElements {
+            publishes: vec![
+                PublishElement::new(
+                    Base64::from_content(mft_bytes),
+                    rsync(&format!("{}a.mft", base)),
+                ),
+                PublishElement::new(
+                    Base64::from_content(crl_bytes),
+                    rsync(&format!("{}a.crl", base)),
+                ),
+                PublishElement::new(
+                    Base64::from_content(cer_bytes),
+                    rsync(&format!("{}b.cer", base)),
+                ),
+                PublishElement::new(
+                    Base64::from_content(&[1, 2, 3]),
+                    rsync(&format!("{}c.roa", base)),
+                ),
+            ],
+            updates: vec![],
+            withdraws: vec![],
+        });
+
+        let details = objects.to_publisher_objects().unwrap();
+        let summary: Vec<_> = details
+            .iter()
+            .map(|o| (o.uri().to_string(), o.object_type(), o.size()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    format!("{}a.crl", base),
+                    PublisherObjectType::Crl,
+                    crl_bytes.len()
+                ),
+                (
+                    format!("{}a.mft", base),
+                    PublisherObjectType::Manifest,
+                    mft_bytes.len()
+                ),
+                (
+                    format!("{}b.cer", base),
+                    PublisherObjectType::Certificate,
+                    cer_bytes.len()
+                ),
+                (format!("{}c.roa", base), PublisherObjectType::Unknown, 3),
+            ]
+        );
+
+        let manifest = Manifest::decode(mft_bytes.as_ref(), false).unwrap();
+        assert_eq!(details[0].expires(), None);
+        assert_eq!(
+            details[1].expires(),
+            Some(manifest.cert().validity().not_after())
+        );
+        assert!(details[2].expires().is_some());
+        assert_eq!(details[3].expires(), None);
+        assert_eq!(
+            details[1].hash(),
+            Base64::from_content(mft_bytes).to_hash()
+        );
+    }
+
     #[test]
     fn current_objects_deltas() {
         fn file_rsync_uri(name: &str) -> uri::Rsync {
@@ -12,66 +12,109 @@ use crate::{
             make_roa_tree, make_validated_announcement_tree, Announcement,
             AnnouncementValidity, Announcements, BgpAnalysisEntry,
             BgpAnalysisReport, BgpAnalysisState, BgpAnalysisSuggestion,
-            IpRange, RisDumpError, RisDumpLoader, ValidatedAnnouncement,
+            BgpSource, BgpSourceError, IpRange, IrrSource, RisDumpLoader,
+            RoaTree, ValidatedAnnouncement,
         },
     },
     constants::{test_announcements_enabled, BGP_RIS_REFRESH_MINUTES},
+    daemon::config::{BgpSourceConfig, IrrSourceConfig},
 };
 
 //------------ BgpAnalyser -------------------------------------------------
 
-/// This type helps analyse ROAs vs BGP and vice versa.
+/// This type helps analyse ROAs vs BGP and vice versa. If IRR sources are
+/// configured, then ROAs are also compared with the registered route
+/// objects.
 pub struct BgpAnalyser {
-    dump_loader: Option<RisDumpLoader>,
+    sources: Vec<BgpSource>,
     seen: RwLock<Announcements>,
+    irr_sources: Vec<IrrSource>,
+    registered: RwLock<Announcements>,
 }
 
 impl BgpAnalyser {
+    /// Creates a new analyser using the RIS dumps, if enabled, and any other
+    /// configured sources. Note that BMP sources start listening for
+    /// connections immediately.
     pub fn new(
         ris_enabled: bool,
         ris_v4_uri: &str,
         ris_v6_uri: &str,
+        other_sources: &[BgpSourceConfig],
+        irr_sources: &[IrrSourceConfig],
     ) -> Self {
         if test_announcements_enabled() {
             Self::with_test_announcements()
         } else {
-            let dump_loader = if ris_enabled {
-                Some(RisDumpLoader::new(ris_v4_uri, ris_v6_uri))
-            } else {
-                None
-            };
+            let mut sources = vec![];
+            if ris_enabled {
+                sources.push(BgpSource::RisDumps(RisDumpLoader::new(
+                    ris_v4_uri, ris_v6_uri,
+                )));
+            }
+            sources.extend(other_sources.iter().map(BgpSource::from_config));
+
             BgpAnalyser {
-                dump_loader,
+                sources,
                 seen: RwLock::new(Announcements::default()),
+                irr_sources: irr_sources
+                    .iter()
+                    .map(IrrSource::from_config)
+                    .collect(),
+                registered: RwLock::new(Announcements::default()),
             }
         }
     }
 
+    /// Updates the announcements seen and the route objects registered in
+    /// the IRR. Returns true if either changed.
     pub async fn update(&self) -> Result<bool, BgpAnalyserError> {
-        let loader = match self.dump_loader.as_ref() {
-            Some(loader) => loader,
-            None => return Ok(false),
-        };
+        // Try to update both, even if one of them fails.
+        let seen = self.update_seen().await;
+        let registered = self.update_registered().await;
+        Ok(seen? | registered?)
+    }
+
+    /// Updates the announcements seen, using all sources. The result is
+    /// only applied if all sources could be loaded, so that we do not
+    /// report on partial information.
+    async fn update_seen(&self) -> Result<bool, BgpAnalyserError> {
+        if self.sources.is_empty() {
+            return Ok(false);
+        }
         if let Some(last_time) = self.seen.read().await.last_checked() {
             if (last_time + Duration::minutes(BGP_RIS_REFRESH_MINUTES))
                 > Time::now()
             {
                 trace!(
-                    "Will not check BGP RIS dumps until the \
+                    "Will not check BGP announcement sources until the \
                     refresh interval has passed"
                 );
                 return Ok(false); // no need to update yet
             }
         }
-        let announcements = loader.download_updates().await?;
+
+        let mut announcements = vec![];
+        for source in &self.sources {
+            let mut loaded = source.load().await.map_err(|e| {
+                BgpAnalyserError::Source(source.to_string(), e)
+            })?;
+            debug!("Loaded {} announcements from {}", loaded.len(), source);
+            announcements.append(&mut loaded);
+        }
+        if self.sources.len() > 1 {
+            announcements.sort_by_key(|ann| (*ann.prefix(), *ann.asn()));
+            announcements.dedup();
+        }
+
         let mut seen = self.seen.write().await;
         if seen.equivalent(&announcements) {
-            debug!("BGP Ris Dumps unchanged");
+            debug!("BGP announcements unchanged");
             seen.update_checked();
             Ok(false)
         } else {
             info!(
-                "Updated announcements ({}) based on BGP Ris Dumps",
+                "Updated announcements ({}) based on BGP announcement sources",
                 announcements.len()
             );
             seen.update(announcements);
@@ -79,6 +122,48 @@ impl BgpAnalyser {
         }
     }
 
+    /// Updates the registered route objects, using all IRR sources. As with
+    /// announcements, the result is only applied if all sources could be
+    /// loaded.
+    async fn update_registered(&self) -> Result<bool, BgpAnalyserError> {
+        if self.irr_sources.is_empty() {
+            return Ok(false);
+        }
+        if let Some(last_time) = self.registered.read().await.last_checked() {
+            if (last_time + Duration::minutes(BGP_RIS_REFRESH_MINUTES))
+                > Time::now()
+            {
+                return Ok(false);
+            }
+        }
+
+        let mut route_objects = vec![];
+        for source in &self.irr_sources {
+            let mut loaded = source.load().await.map_err(|e| {
+                BgpAnalyserError::Source(source.to_string(), e)
+            })?;
+            debug!("Loaded {} route objects from {}", loaded.len(), source);
+            route_objects.append(&mut loaded);
+        }
+        // The same route object is often found in more than one IRR
+        route_objects.sort_by_key(|ann| (*ann.prefix(), *ann.asn()));
+        route_objects.dedup();
+
+        let mut registered = self.registered.write().await;
+        if registered.equivalent(&route_objects) {
+            debug!("IRR route objects unchanged");
+            registered.update_checked();
+            Ok(false)
+        } else {
+            info!(
+                "Updated route objects ({}) based on IRR sources",
+                route_objects.len()
+            );
+            registered.update(route_objects);
+            Ok(true)
+        }
+    }
+
     pub async fn analyse(
         &self,
         roas: &[ConfiguredRoa],
@@ -108,6 +193,33 @@ impl BgpAnalyser {
             entries.push(BgpAnalysisEntry::roa_not_held(not_held));
         }
 
+        let scope = match &limited_scope {
+            Some(limit) => limit,
+            None => resources_held,
+        };
+        let (v4_scope, v6_scope) = IpRange::for_resource_set(scope);
+
+        let roa_payloads: Vec<_> = roas_held
+            .iter()
+            .map(|configured| configured.payload())
+            .collect();
+        let roa_tree = make_roa_tree(&roa_payloads);
+
+        let registered = self.registered.read().await;
+        if registered.last_checked().is_some() {
+            let route_objects: Vec<_> = v4_scope
+                .iter()
+                .chain(v6_scope.iter())
+                .flat_map(|block| registered.contained_by(block.clone()))
+                .collect();
+            entries.append(&mut Self::analyse_route_objects(
+                &route_objects,
+                &registered,
+                &roas_held,
+                &roa_tree,
+            ));
+        }
+
         if seen.last_checked().is_none() {
             // nothing to analyse, just push all ROAs as 'no announcement
             // info'
@@ -115,13 +227,6 @@ impl BgpAnalyser {
                 entries.push(BgpAnalysisEntry::roa_no_announcement_info(roa));
             }
         } else {
-            let scope = match &limited_scope {
-                Some(limit) => limit,
-                None => resources_held,
-            };
-
-            let (v4_scope, v6_scope) = IpRange::for_resource_set(scope);
-
             let mut scoped_announcements = vec![];
 
             for block in v4_scope.into_iter() {
@@ -132,11 +237,6 @@ impl BgpAnalyser {
                 scoped_announcements.append(&mut seen.contained_by(block));
             }
 
-            let roa_payloads: Vec<_> = roas_held
-                .iter()
-                .map(|configured| configured.payload())
-                .collect();
-            let roa_tree = make_roa_tree(&roa_payloads);
             let validated: Vec<ValidatedAnnouncement> = scoped_announcements
                 .into_iter()
                 .map(|a| a.validate(&roa_tree))
@@ -302,6 +402,57 @@ impl BgpAnalyser {
         BgpAnalysisReport::new(entries)
     }
 
+    /// Compares the registered route objects in scope with the ROAs held.
+    ///
+    /// Route objects which are not valid under the ROAs are reported,
+    /// unless they are disallowed by AS0 ROAs on purpose. ROAs for real
+    /// ASNs are reported if no route object exists which they authorize.
+    fn analyse_route_objects(
+        route_objects: &[&Announcement],
+        registered: &Announcements,
+        roas_held: &[ConfiguredRoa],
+        roa_tree: &RoaTree,
+    ) -> Vec<BgpAnalysisEntry> {
+        let mut entries = vec![];
+
+        for route_object in route_objects {
+            let (route_object, validity, _, invalidating_roas) =
+                route_object.validate(roa_tree).unpack();
+            match validity {
+                AnnouncementValidity::Valid
+                | AnnouncementValidity::Disallowed => {}
+                AnnouncementValidity::InvalidLength
+                | AnnouncementValidity::InvalidAsn
+                | AnnouncementValidity::NotFound => {
+                    entries.push(BgpAnalysisEntry::route_object_no_roa(
+                        route_object,
+                        invalidating_roas,
+                    ))
+                }
+            }
+        }
+
+        for roa in roas_held {
+            if roa.asn() == AsNumber::zero() {
+                continue;
+            }
+            let has_route_object = registered
+                .contained_by(roa.prefix())
+                .into_iter()
+                .any(|route_object| {
+                    route_object.asn() == &roa.asn()
+                        && route_object.prefix().addr_len()
+                            <= roa.effective_max_length()
+                });
+            if !has_route_object {
+                entries
+                    .push(BgpAnalysisEntry::roa_no_route_object(roa.clone()));
+            }
+        }
+
+        entries
+    }
+
     pub async fn suggest(
         &self,
         roas: &[ConfiguredRoa],
@@ -309,6 +460,7 @@ impl BgpAnalyser {
         limited_scope: Option<ResourceSet>,
     ) -> BgpAnalysisSuggestion {
         let mut suggestion = BgpAnalysisSuggestion::default();
+        let irr_known = self.registered.read().await.last_checked().is_some();
 
         // perform analysis
         let entries = self
@@ -318,7 +470,20 @@ impl BgpAnalyser {
         for entry in &entries {
             match entry.state() {
                 BgpAnalysisState::RoaUnseen => {
-                    suggestion.add_stale(entry.configured_roa())
+                    // Keep unseen ROAs if a route object shows that the
+                    // announcement is intended.
+                    let has_route_object = irr_known
+                        && !entries.iter().any(|other| {
+                            other.state()
+                                == BgpAnalysisState::RoaNoRouteObject
+                                && other.configured_roa()
+                                    == entry.configured_roa()
+                        });
+                    if has_route_object {
+                        suggestion.add_keep(entry.configured_roa())
+                    } else {
+                        suggestion.add_stale(entry.configured_roa())
+                    }
                 }
                 BgpAnalysisState::RoaTooPermissive => {
                     let replace_with = entry
@@ -369,6 +534,12 @@ impl BgpAnalyser {
                 BgpAnalysisState::RoaNoAnnouncementInfo => {
                     suggestion.add_keep(entry.configured_roa())
                 }
+                BgpAnalysisState::RoaNoRouteObject => {
+                    suggestion.add_no_route_object(entry.configured_roa())
+                }
+                BgpAnalysisState::RouteObjectNoRoa => {
+                    suggestion.add_route_object_no_roa(entry.announcement())
+                }
             }
         }
 
@@ -395,8 +566,10 @@ impl BgpAnalyser {
         let mut announcements = Announcements::default();
         announcements.update(Self::test_announcements());
         BgpAnalyser {
-            dump_loader: None,
+            sources: vec![],
             seen: RwLock::new(announcements),
+            irr_sources: vec![],
+            registered: RwLock::new(Announcements::default()),
         }
     }
 }
@@ -405,32 +578,28 @@ impl BgpAnalyser {
 
 #[derive(Debug)]
 pub enum BgpAnalyserError {
-    RisDump(RisDumpError),
+    Source(String, BgpSourceError),
 }
 
 impl fmt::Display for BgpAnalyserError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            BgpAnalyserError::RisDump(e) => {
-                write!(f, "BGP RIS update error: {}", e)
+            BgpAnalyserError::Source(source, e) => {
+                write!(f, "Could not load {}: {}", source, e)
             }
         }
     }
 }
 
-impl From<RisDumpError> for BgpAnalyserError {
-    fn from(e: RisDumpError) -> Self {
-        BgpAnalyserError::RisDump(e)
-    }
-}
-
 //------------ Tests --------------------------------------------------------
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        commons::{api::RoaConfigurationUpdates, bgp::BgpAnalysisState},
-        test::{announcement, configured_roa},
+        commons::{
+            api::RoaConfigurationUpdates, bgp::BgpAnalysisState, util::file,
+        },
+        test::{announcement, configured_roa, tmp_dir},
     };
 
     use super::*;
@@ -442,6 +611,8 @@ mod tests {
             true,
             "http://www.ris.ripe.net/dumps/riswhoisdump.IPv4.gz",
             "http://www.ris.ripe.net/dumps/riswhoisdump.IPv6.gz",
+            &[],
+            &[],
         );
 
         assert!(analyser.seen.read().await.is_empty());
@@ -451,6 +622,48 @@ mod tests {
         assert!(analyser.seen.read().await.last_checked().is_some());
     }
 
+    #[tokio::test]
+    async fn update_from_local_sources() {
+        let (dir, cleanup) = tmp_dir();
+        let v4_path = dir.join("v4.txt");
+        let v6_path = dir.join("v6.txt");
+        file::save(b"64496 10.0.0.0/22\n64497 10.0.4.0/24\n", &v4_path)
+            .unwrap();
+        file::save(b"64496 2001:db8::/32\n64496 10.0.0.0/22\n", &v6_path)
+            .unwrap();
+
+        let analyser = BgpAnalyser {
+            sources: [v4_path, v6_path]
+                .into_iter()
+                .map(|path| {
+                    BgpSource::from_config(&BgpSourceConfig::File { path })
+                })
+                .collect(),
+            seen: RwLock::new(Announcements::default()),
+            irr_sources: vec![],
+            registered: RwLock::new(Announcements::default()),
+        };
+
+        assert!(analyser.update().await.unwrap());
+        let seen = analyser.seen.read().await;
+        let mut all: Vec<_> = seen.all().into_iter().copied().collect();
+        all.sort_by_key(|ann| (*ann.prefix(), *ann.asn()));
+        assert_eq!(
+            all,
+            vec![
+                announcement("10.0.0.0/22 => 64496"),
+                announcement("10.0.4.0/24 => 64497"),
+                announcement("2001:db8::/32 => 64496"),
+            ]
+        );
+        drop(seen);
+
+        // within the refresh interval the sources are not checked again
+        assert!(!analyser.update().await.unwrap());
+
+        cleanup();
+    }
+
     #[tokio::test]
     async fn analyse_bgp() {
         let roa_too_permissive = configured_roa("10.0.0.0/22-23 => 64496");
@@ -549,7 +762,7 @@ mod tests {
         let resources_held =
             ResourceSet::from_strs("", "10.0.0.0/16", "").unwrap();
 
-        let analyser = BgpAnalyser::new(false, "", "");
+        let analyser = BgpAnalyser::new(false, "", "", &[], &[]);
         let table = analyser.analyse(&roas, &resources_held, None).await;
         let table_entries = table.entries();
         assert_eq!(3, table_entries.len());
@@ -563,6 +776,58 @@ mod tests {
         assert_eq!(roas_no_info, roas);
     }
 
+    #[tokio::test]
+    async fn analyse_irr_route_objects() {
+        let roa_seen = configured_roa("192.168.1.0/24 => 64497");
+        let roa_unseen_registered = configured_roa("10.0.3.0/24 => 64497");
+        let roa_as0 = configured_roa("10.0.4.0/24 => 0");
+        let roas =
+            &[roa_seen.clone(), roa_unseen_registered.clone(), roa_as0];
+
+        let not_authorized = announcement("10.0.5.0/24 => 64499");
+        let invalid_asn = announcement("10.0.3.0/24 => 64500");
+
+        let analyser = BgpAnalyser::with_test_announcements();
+        analyser.registered.write().await.update(vec![
+            announcement("10.0.3.0/24 => 64497"),
+            invalid_asn,
+            // disallowed by the AS0 ROA, so not reported
+            announcement("10.0.4.0/24 => 64501"),
+            not_authorized,
+        ]);
+
+        let resources_held =
+            ResourceSet::from_strs("", "10.0.0.0/16, 192.168.0.0/16", "")
+                .unwrap();
+        let report = analyser.analyse(roas, &resources_held, None).await;
+
+        assert_eq!(
+            report.matching_announcements(BgpAnalysisState::RouteObjectNoRoa),
+            vec![invalid_asn, not_authorized]
+        );
+
+        let roas_no_route_object: Vec<_> = report
+            .matching_entries(BgpAnalysisState::RoaNoRouteObject)
+            .into_iter()
+            .map(|e| e.configured_roa().clone())
+            .collect();
+        assert_eq!(roas_no_route_object, vec![roa_seen]);
+
+        // The unseen ROA is kept because it has a route object, and ROAs
+        // are suggested for the route objects which are not authorized.
+        let suggestion = analyser.suggest(roas, &resources_held, None).await;
+        let updates = RoaConfigurationUpdates::from(suggestion);
+        assert!(!updates
+            .removed()
+            .contains(&roa_unseen_registered.payload()));
+        for route_object in [invalid_asn, not_authorized] {
+            assert!(updates
+                .added()
+                .iter()
+                .any(|roa| roa.payload() == RoaPayload::from(route_object)));
+        }
+    }
+
     #[tokio::test]
     async fn make_bgp_analysis_suggestion() {
         let roa_too_permissive = configured_roa("10.0.0.0/22-23 => 64496");
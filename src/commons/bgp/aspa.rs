@@ -0,0 +1,506 @@
+//! Support for analysing ASPA definitions against observed AS paths.
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use rpki::repository::resources::Asn;
+
+use crate::commons::api::{
+    AspaDefinition, AspaDefinitionList, AspaProvidersUpdate, CustomerAsn,
+    ProviderAsn,
+};
+
+//------------ AsPath --------------------------------------------------------
+
+/// An observed AS path. The first ASN is the one nearest to the observer,
+/// the last ASN is the origin of the route.
+///
+/// Consecutive duplicate ASNs, resulting from AS path prepending, are
+/// collapsed when the path is parsed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AsPath(Vec<Asn>);
+
+impl AsPath {
+    pub fn new(mut asns: Vec<Asn>) -> Result<Self, AsPathError> {
+        asns.dedup();
+        if asns.is_empty() {
+            Err(AsPathError::Empty)
+        } else {
+            Ok(AsPath(asns))
+        }
+    }
+
+    pub fn asns(&self) -> &[Asn] {
+        &self.0
+    }
+
+    pub fn origin(&self) -> Asn {
+        // cannot panic, paths are never empty
+        *self.0.last().unwrap()
+    }
+
+    /// Returns the ASNs to which the given ASN propagated this route. I.e.
+    /// the ASNs which are found directly before the given ASN in this path.
+    pub fn upstreams_of(&self, asn: Asn) -> impl Iterator<Item = Asn> + '_ {
+        self.0
+            .windows(2)
+            .filter(move |pair| pair[1] == asn)
+            .map(|pair| pair[0])
+    }
+}
+
+impl FromStr for AsPath {
+    type Err = AsPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains(['{', '}']) {
+            return Err(AsPathError::AsSet);
+        }
+
+        let mut asns = vec![];
+        for asn_str in s.split_whitespace() {
+            let asn = Asn::from_str(asn_str)
+                .map_err(|_| AsPathError::InvalidAsn(asn_str.to_string()))?;
+            asns.push(asn);
+        }
+
+        AsPath::new(asns)
+    }
+}
+
+impl fmt::Display for AsPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, asn) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", asn)?;
+        }
+        Ok(())
+    }
+}
+
+impl serde::Serialize for AsPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.to_string().serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for AsPath {
+    fn deserialize<D>(deserializer: D) -> Result<AsPath, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        AsPath::from_str(&string).map_err(serde::de::Error::custom)
+    }
+}
+
+//------------ AsPaths -------------------------------------------------------
+
+/// A collection of observed AS paths, used as input for ASPA analysis.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AsPaths(Vec<AsPath>);
+
+impl AsPaths {
+    pub fn new(paths: Vec<AsPath>) -> Self {
+        AsPaths(paths)
+    }
+
+    /// Parses AS paths from text. Every line is expected to contain either
+    /// a single AS path, i.e. whitespace separated ASNs, or a route in the
+    /// format used by 'bgpdump -m', in which case the AS path is taken from
+    /// the 7th field.
+    ///
+    /// Empty lines and lines starting with '#' are ignored. Paths containing
+    /// an AS_SET are ignored as well, because they cannot be verified using
+    /// ASPA. The same goes for 'bgpdump -m' lines which contain no AS path,
+    /// such as withdrawals.
+    pub fn parse(text: &str) -> Result<Self, AsPathError> {
+        let mut paths = vec![];
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let path_str = if line.contains('|') {
+                match line.split('|').nth(6) {
+                    Some(path_str) => path_str,
+                    None => continue,
+                }
+            } else {
+                line
+            };
+
+            match AsPath::from_str(path_str) {
+                Ok(path) => paths.push(path),
+                Err(AsPathError::AsSet) => {}
+                Err(e) => {
+                    return Err(AsPathError::InvalidLine(
+                        idx + 1,
+                        e.to_string(),
+                    ))
+                }
+            }
+        }
+
+        Ok(AsPaths(paths))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AsPath> {
+        self.0.iter()
+    }
+}
+
+//------------ AspaAnalysisReport --------------------------------------------
+
+/// Reports how the ASPA definitions of a CA relate to observed AS paths.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AspaAnalysisReport {
+    paths: usize,
+    entries: Vec<AspaAnalysisEntry>,
+}
+
+impl AspaAnalysisReport {
+    /// Analyses the given ASPA definitions against the observed AS paths.
+    ///
+    /// For each customer ASN we look at all ASNs to which it propagated
+    /// routes, i.e. its upstreams in the observed paths. Upstreams which are
+    /// not authorized as providers would make these routes ASPA-invalid under
+    /// upstream path verification. Note that an unauthorized upstream may in
+    /// fact be a lateral peer, which is why providers are only *suggested*.
+    pub fn new(definitions: &AspaDefinitionList, paths: &AsPaths) -> Self {
+        let mut entries: Vec<AspaAnalysisEntry> = definitions
+            .iter()
+            .map(|definition| AspaAnalysisEntry::new(definition, paths))
+            .collect();
+        entries.sort_by_key(|entry| entry.customer);
+
+        AspaAnalysisReport {
+            paths: paths.len(),
+            entries,
+        }
+    }
+
+    pub fn paths(&self) -> usize {
+        self.paths
+    }
+
+    pub fn entries(&self) -> &Vec<AspaAnalysisEntry> {
+        &self.entries
+    }
+
+    /// Returns true if any observed upstream is not authorized.
+    pub fn contains_invalids(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| !entry.suggestion.is_empty())
+    }
+}
+
+impl fmt::Display for AspaAnalysisReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Analysed {} AS paths.", self.paths)?;
+
+        for entry in &self.entries {
+            writeln!(f)?;
+            write!(f, "{}", entry)?;
+        }
+
+        Ok(())
+    }
+}
+
+//------------ AspaAnalysisEntry ---------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AspaAnalysisEntry {
+    customer: CustomerAsn,
+    providers: Vec<ProviderAsn>,
+    upstreams: Vec<AspaObservedUpstream>,
+    unseen_providers: Vec<ProviderAsn>,
+    suggestion: AspaProvidersUpdate,
+}
+
+impl AspaAnalysisEntry {
+    fn new(definition: &AspaDefinition, paths: &AsPaths) -> Self {
+        let customer = definition.customer();
+        let providers = definition.providers().clone();
+
+        let mut observed: HashMap<Asn, AspaObservedUpstream> = HashMap::new();
+        for path in paths.iter() {
+            for upstream in path.upstreams_of(customer) {
+                observed
+                    .entry(upstream)
+                    .or_insert_with(|| AspaObservedUpstream {
+                        asn: upstream,
+                        authorized: providers.contains(&upstream),
+                        paths: 0,
+                        example: path.clone(),
+                    })
+                    .paths += 1;
+            }
+        }
+
+        let mut upstreams: Vec<AspaObservedUpstream> =
+            observed.into_values().collect();
+        upstreams.sort_by_key(|upstream| upstream.asn);
+
+        let unseen_providers = providers
+            .iter()
+            .filter(|provider| {
+                !upstreams.iter().any(|upstream| upstream.asn == **provider)
+            })
+            .copied()
+            .collect();
+
+        let missing = upstreams
+            .iter()
+            .filter(|upstream| !upstream.authorized)
+            .map(|upstream| upstream.asn)
+            .collect();
+
+        AspaAnalysisEntry {
+            customer,
+            providers,
+            upstreams,
+            unseen_providers,
+            suggestion: AspaProvidersUpdate::new(missing, vec![]),
+        }
+    }
+
+    pub fn customer(&self) -> CustomerAsn {
+        self.customer
+    }
+
+    pub fn upstreams(&self) -> &Vec<AspaObservedUpstream> {
+        &self.upstreams
+    }
+
+    pub fn unseen_providers(&self) -> &Vec<ProviderAsn> {
+        &self.unseen_providers
+    }
+
+    /// Returns the suggested update for the providers of this customer
+    /// ASN. I.e. the observed upstreams which are not yet authorized.
+    pub fn suggestion(&self) -> &AspaProvidersUpdate {
+        &self.suggestion
+    }
+}
+
+impl fmt::Display for AspaAnalysisEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Customer {}", self.customer)?;
+
+        if self.upstreams.is_empty() {
+            writeln!(f, "  Not seen propagating routes to any upstream.")?;
+        } else {
+            writeln!(f, "  Observed upstreams:")?;
+            for upstream in &self.upstreams {
+                writeln!(f, "    {}", upstream)?;
+            }
+        }
+
+        if !self.unseen_providers.is_empty() {
+            write!(f, "  Providers not seen in any path:")?;
+            for provider in &self.unseen_providers {
+                write!(f, " {}", provider)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.suggestion.is_empty() {
+            write!(f, "  Suggested providers to add:")?;
+            for provider in self.suggestion.added() {
+                write!(f, " {}", provider)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+//------------ AspaObservedUpstream -----------------------------------------
+
+/// An ASN to which a customer ASN was seen propagating routes.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AspaObservedUpstream {
+    asn: Asn,
+    authorized: bool,
+    paths: usize,
+    example: AsPath,
+}
+
+impl AspaObservedUpstream {
+    pub fn asn(&self) -> Asn {
+        self.asn
+    }
+
+    pub fn authorized(&self) -> bool {
+        self.authorized
+    }
+
+    pub fn paths(&self) -> usize {
+        self.paths
+    }
+
+    pub fn example(&self) -> &AsPath {
+        &self.example
+    }
+}
+
+impl fmt::Display for AspaObservedUpstream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.authorized {
+            write!(f, "{} in {} paths, authorized", self.asn, self.paths)
+        } else {
+            write!(
+                f,
+                "{} in {} paths, NOT authorized, e.g.: {}",
+                self.asn, self.paths, self.example
+            )
+        }
+    }
+}
+
+//------------ AsPathError ---------------------------------------------------
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AsPathError {
+    Empty,
+    AsSet,
+    InvalidAsn(String),
+    InvalidLine(usize, String),
+}
+
+impl fmt::Display for AsPathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsPathError::Empty => write!(f, "AS path is empty"),
+            AsPathError::AsSet => {
+                write!(f, "AS path contains an AS_SET")
+            }
+            AsPathError::InvalidAsn(s) => {
+                write!(f, "Invalid ASN in AS path: {}", s)
+            }
+            AsPathError::InvalidLine(nr, e) => {
+                write!(f, "Cannot parse AS path on line {}: {}", nr, e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AsPathError {}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asn(nr: u32) -> Asn {
+        Asn::from_u32(nr)
+    }
+
+    #[test]
+    fn parse_as_paths() {
+        let text = "\
+# plain paths, prepending is collapsed
+64496 64497 65000 65000 65000
+
+TABLE_DUMP2|1700000000|B|192.0.2.1|64496|10.0.0.0/24|64496 64498 65000|IGP|192.0.2.1|0|0||NAG||
+BGP4MP|1700000000|W|192.0.2.1|64496|10.0.0.0/24
+64496 {64510,64511}
+";
+        let paths = AsPaths::parse(text).unwrap();
+        assert_eq!(paths.len(), 2);
+
+        let first = paths.iter().next().unwrap();
+        assert_eq!(first.asns(), &[asn(64496), asn(64497), asn(65000)]);
+        assert_eq!(first.origin(), asn(65000));
+        assert_eq!(first.to_string(), "AS64496 AS64497 AS65000");
+
+        assert_eq!(
+            AsPaths::parse("64496\n64496 bogus\n"),
+            Err(AsPathError::InvalidLine(
+                2,
+                AsPathError::InvalidAsn("bogus".to_string()).to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn as_paths_json() {
+        let paths = AsPaths::parse("64496 AS64497 65000").unwrap();
+        let json = serde_json::to_string(&paths).unwrap();
+        assert_eq!(json, r#"["AS64496 AS64497 AS65000"]"#);
+        let parsed: AsPaths = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, paths);
+    }
+
+    #[test]
+    fn analyse_aspas() {
+        let paths = AsPaths::parse(
+            "\
+64496 64497 65000
+64496 64497 65000
+64496 64498 65000
+64499 65000 65001
+64496 65002
+",
+        )
+        .unwrap();
+
+        let definitions = AspaDefinitionList::new(vec![
+            AspaDefinition::from_str("AS65000 => AS64497, AS64510").unwrap(),
+            AspaDefinition::from_str("AS65001 => AS65000").unwrap(),
+            AspaDefinition::from_str("AS65003 => AS64496").unwrap(),
+        ]);
+
+        let report = AspaAnalysisReport::new(&definitions, &paths);
+        assert_eq!(report.paths(), 5);
+        assert!(report.contains_invalids());
+
+        let entries = report.entries();
+        assert_eq!(entries.len(), 3);
+
+        // AS65000 uses AS64498 and AS64499 which are not authorized, and
+        // AS64510 is never seen.
+        let entry = &entries[0];
+        assert_eq!(entry.customer(), asn(65000));
+        let upstreams: Vec<_> = entry
+            .upstreams()
+            .iter()
+            .map(|upstream| (upstream.asn(), upstream.paths()))
+            .collect();
+        assert_eq!(
+            upstreams,
+            vec![(asn(64497), 2), (asn(64498), 1), (asn(64499), 1)]
+        );
+        assert_eq!(entry.unseen_providers(), &vec![asn(64510)]);
+        assert_eq!(entry.suggestion().added(), &vec![asn(64498), asn(64499)]);
+
+        // AS65001 only uses its authorized provider
+        let entry = &entries[1];
+        assert_eq!(entry.customer(), asn(65001));
+        assert!(entry.suggestion().is_empty());
+        assert!(entry.unseen_providers().is_empty());
+
+        // AS65003 is not seen at all
+        let entry = &entries[2];
+        assert!(entry.upstreams().is_empty());
+        assert_eq!(entry.unseen_providers(), &vec![asn(64496)]);
+    }
+}
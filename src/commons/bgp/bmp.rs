@@ -0,0 +1,396 @@
+//! Support receiving announcements from routers using the BGP Monitoring
+//! Protocol (BMP), see RFC 7854.
+//!
+//! Krill acts as a BMP station: routers connect to the configured address
+//! and send their routes. Krill keeps the routes per monitored peer in
+//! memory, and uses the origin ASN and prefix of all current routes as the
+//! announcements seen.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::Arc,
+};
+
+use tokio::{
+    io::AsyncReadExt,
+    net::{TcpListener, TcpStream},
+    sync::RwLock,
+};
+
+use crate::commons::{
+    api::{AsNumber, TypedPrefix},
+    bgp::{
+        wire::{AddressFamily, PathAttributes, WireReader},
+        Announcement, BgpSourceError,
+    },
+};
+
+const BMP_VERSION: u8 = 3;
+const BMP_COMMON_HEADER_LEN: usize = 6;
+const BMP_PER_PEER_HEADER_LEN: usize = 42;
+const BMP_MAX_MESSAGE_LEN: usize = 1024 * 1024;
+
+const BMP_MSG_ROUTE_MONITORING: u8 = 0;
+const BMP_MSG_PEER_DOWN: u8 = 2;
+const BMP_MSG_TERMINATION: u8 = 5;
+
+const BMP_PEER_FLAG_POST_POLICY: u8 = 0x40;
+const BMP_PEER_FLAG_LEGACY_AS_PATH: u8 = 0x20;
+
+const BGP_MARKER_LEN: usize = 16;
+const BGP_MSG_UPDATE: u8 = 2;
+
+//------------ BmpFeed -------------------------------------------------------
+
+/// Listens for BMP connections and keeps track of the routes received.
+pub struct BmpFeed {
+    listen: SocketAddr,
+    ribs: Arc<RwLock<BmpRibs>>,
+}
+
+impl BmpFeed {
+    /// Creates a new feed and starts listening for BMP connections on the
+    /// given address in the background. This must be called from within
+    /// the tokio runtime.
+    pub fn start(listen: SocketAddr) -> Self {
+        let ribs = Arc::new(RwLock::new(BmpRibs::default()));
+
+        let listener_ribs = ribs.clone();
+        tokio::spawn(async move {
+            let listener = match TcpListener::bind(listen).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    error!("Could not listen for BMP on {}: {}", listen, e);
+                    return;
+                }
+            };
+            info!("Listening for BMP connections on {}", listen);
+
+            loop {
+                match listener.accept().await {
+                    Ok((stream, remote)) => {
+                        info!("Accepted BMP connection from {}", remote);
+                        let ribs = listener_ribs.clone();
+                        tokio::spawn(async move {
+                            Self::handle_connection(stream, remote, ribs)
+                                .await
+                        });
+                    }
+                    Err(e) => {
+                        warn!("Could not accept BMP connection: {}", e);
+                    }
+                }
+            }
+        });
+
+        BmpFeed { listen, ribs }
+    }
+
+    pub fn listen(&self) -> SocketAddr {
+        self.listen
+    }
+
+    /// Returns the announcements for all routes currently known.
+    pub async fn announcements(&self) -> Vec<Announcement> {
+        self.ribs.read().await.announcements()
+    }
+
+    async fn handle_connection(
+        mut stream: TcpStream,
+        remote: SocketAddr,
+        ribs: Arc<RwLock<BmpRibs>>,
+    ) {
+        let mut peers = HashSet::new();
+
+        if let Err(e) =
+            Self::read_messages(&mut stream, &ribs, &mut peers).await
+        {
+            warn!("Closing BMP connection from {}: {}", remote, e);
+        } else {
+            info!("BMP connection from {} was closed", remote);
+        }
+
+        // Routes are only valid for as long as the router is connected.
+        let mut ribs = ribs.write().await;
+        for peer in peers {
+            ribs.remove_peer(&peer);
+        }
+    }
+
+    async fn read_messages(
+        stream: &mut TcpStream,
+        ribs: &RwLock<BmpRibs>,
+        peers: &mut HashSet<BmpPeer>,
+    ) -> Result<(), BgpSourceError> {
+        loop {
+            let mut header = [0_u8; BMP_COMMON_HEADER_LEN];
+            match stream.read_exact(&mut header).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    return Ok(());
+                }
+                Err(e) => return Err(BgpSourceError::format(e)),
+            }
+
+            let mut reader = WireReader::new(&header);
+            let version = reader.u8()?;
+            let len = reader.u32()? as usize;
+            let msg_type = reader.u8()?;
+
+            if version != BMP_VERSION {
+                return Err(BgpSourceError::format(format!(
+                    "unsupported BMP version: {}",
+                    version
+                )));
+            }
+            if !(BMP_COMMON_HEADER_LEN..=BMP_MAX_MESSAGE_LEN).contains(&len) {
+                return Err(BgpSourceError::format(format!(
+                    "invalid BMP message length: {}",
+                    len
+                )));
+            }
+
+            let mut body = vec![0_u8; len - BMP_COMMON_HEADER_LEN];
+            stream
+                .read_exact(&mut body)
+                .await
+                .map_err(BgpSourceError::format)?;
+
+            if msg_type == BMP_MSG_TERMINATION {
+                return Ok(());
+            }
+
+            ribs.write().await.process(msg_type, &body, peers)?;
+        }
+    }
+}
+
+//------------ BmpPeer -------------------------------------------------------
+
+/// Identifies a monitored peer: the peer type, whether the routes are
+/// pre- or post-policy, the peer distinguisher and the peer address.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BmpPeer([u8; 26]);
+
+impl BmpPeer {
+    fn from_header(header: &[u8]) -> Self {
+        let mut peer = [0_u8; 26];
+        peer[0] = header[0];
+        peer[1] = header[1] & BMP_PEER_FLAG_POST_POLICY;
+        peer[2..].copy_from_slice(&header[2..26]);
+        BmpPeer(peer)
+    }
+}
+
+//------------ BmpRibs -------------------------------------------------------
+
+/// The routes per monitored peer, with the origin ASN for each prefix.
+#[derive(Debug, Default)]
+pub struct BmpRibs {
+    peers: HashMap<BmpPeer, HashMap<TypedPrefix, AsNumber>>,
+}
+
+impl BmpRibs {
+    pub fn announcements(&self) -> Vec<Announcement> {
+        let announcements: HashSet<Announcement> = self
+            .peers
+            .values()
+            .flat_map(|rib| rib.iter())
+            .map(|(prefix, asn)| Announcement::new(*asn, *prefix))
+            .collect();
+        announcements.into_iter().collect()
+    }
+
+    fn remove_peer(&mut self, peer: &BmpPeer) {
+        self.peers.remove(peer);
+    }
+
+    /// Processes the body of a BMP message, i.e. without the common
+    /// header. Peers for which routes are received are added to 'peers'.
+    fn process(
+        &mut self,
+        msg_type: u8,
+        body: &[u8],
+        peers: &mut HashSet<BmpPeer>,
+    ) -> Result<(), BgpSourceError> {
+        match msg_type {
+            BMP_MSG_ROUTE_MONITORING => {
+                let mut reader = WireReader::new(body);
+                let header = reader.take(BMP_PER_PEER_HEADER_LEN)?;
+                let peer = BmpPeer::from_header(header);
+                let legacy_as_path =
+                    header[1] & BMP_PEER_FLAG_LEGACY_AS_PATH != 0;
+
+                peers.insert(peer);
+                let rib = self.peers.entry(peer).or_default();
+                Self::apply_update(rib, &mut reader, legacy_as_path)
+            }
+            BMP_MSG_PEER_DOWN => {
+                let mut reader = WireReader::new(body);
+                let header = reader.take(BMP_PER_PEER_HEADER_LEN)?;
+                let peer = BmpPeer::from_header(header);
+                self.remove_peer(&peer);
+                Ok(())
+            }
+            _ => Ok(()), // initiation, peer up, statistics, etc.
+        }
+    }
+
+    fn apply_update(
+        rib: &mut HashMap<TypedPrefix, AsNumber>,
+        reader: &mut WireReader,
+        legacy_as_path: bool,
+    ) -> Result<(), BgpSourceError> {
+        reader.take(BGP_MARKER_LEN)?;
+        let _len = reader.u16()?;
+        if reader.u8()? != BGP_MSG_UPDATE {
+            return Ok(());
+        }
+
+        let withdrawn_len = reader.u16()? as usize;
+        let mut withdrawn = WireReader::new(reader.take(withdrawn_len)?);
+        while !withdrawn.is_empty() {
+            rib.remove(&withdrawn.prefix(AddressFamily::Ipv4)?);
+        }
+
+        let attributes_len = reader.u16()? as usize;
+        let attributes = PathAttributes::parse(
+            reader.take(attributes_len)?,
+            legacy_as_path,
+            true,
+        )?;
+
+        for prefix in attributes.unreachable() {
+            rib.remove(prefix);
+        }
+
+        let mut reachable = attributes.reachable().to_vec();
+        while !reader.is_empty() {
+            reachable.push(reader.prefix(AddressFamily::Ipv4)?);
+        }
+
+        for prefix in reachable {
+            match attributes.origin() {
+                Some(asn) => {
+                    rib.insert(prefix, asn);
+                }
+                None => {
+                    // The new route replaces any previous route for this
+                    // prefix, but we cannot determine its origin.
+                    rib.remove(&prefix);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::commons::bgp::wire::tests::as_path_attribute;
+
+    fn per_peer_header(address: u8) -> Vec<u8> {
+        let mut header = vec![0_u8; BMP_PER_PEER_HEADER_LEN];
+        header[25] = address; // last octet of the peer address
+        header
+    }
+
+    fn route_monitoring(
+        address: u8,
+        withdrawn: &[u8],
+        attributes: &[u8],
+        nlri: &[u8],
+    ) -> Vec<u8> {
+        let mut update = vec![];
+        update.extend_from_slice(&(withdrawn.len() as u16).to_be_bytes());
+        update.extend_from_slice(withdrawn);
+        update.extend_from_slice(&(attributes.len() as u16).to_be_bytes());
+        update.extend_from_slice(attributes);
+        update.extend_from_slice(nlri);
+
+        let mut res = per_peer_header(address);
+        res.extend_from_slice(&[0xff; BGP_MARKER_LEN]);
+        res.extend_from_slice(&(19 + update.len() as u16).to_be_bytes());
+        res.push(BGP_MSG_UPDATE);
+        res.extend_from_slice(&update);
+        res
+    }
+
+    fn announcement(asn: u32, prefix: &str) -> Announcement {
+        Announcement::new(
+            AsNumber::new(asn),
+            TypedPrefix::from_str(prefix).unwrap(),
+        )
+    }
+
+    fn sorted(mut announcements: Vec<Announcement>) -> Vec<Announcement> {
+        announcements.sort_by_key(|ann| (*ann.prefix(), *ann.asn()));
+        announcements
+    }
+
+    #[test]
+    fn process_bmp_messages() {
+        let mut ribs = BmpRibs::default();
+        let mut peers = HashSet::new();
+
+        // peer 1 announces 192.0.2.0/24 from AS65000
+        let msg = route_monitoring(
+            1,
+            &[],
+            &as_path_attribute(&[64496, 65000]),
+            &[24, 192, 0, 2],
+        );
+        ribs.process(BMP_MSG_ROUTE_MONITORING, &msg, &mut peers)
+            .unwrap();
+
+        // peer 2 announces 2001:db8::/32 from AS65001 using MP_REACH_NLRI
+        let mut attributes = as_path_attribute(&[64497, 65001]);
+        let mp_reach = [
+            0, 2, 1, // AFI IPv6, SAFI unicast
+            16, 0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            1, // next hop
+            0, // reserved
+            32, 0x20, 0x01, 0x0d, 0xb8, // NLRI
+        ];
+        attributes.extend_from_slice(&[0x80, 14, mp_reach.len() as u8]);
+        attributes.extend_from_slice(&mp_reach);
+        let msg = route_monitoring(2, &[], &attributes, &[]);
+        ribs.process(BMP_MSG_ROUTE_MONITORING, &msg, &mut peers)
+            .unwrap();
+
+        assert_eq!(peers.len(), 2);
+        assert_eq!(
+            sorted(ribs.announcements()),
+            vec![
+                announcement(65001, "2001:db8::/32"),
+                announcement(65000, "192.0.2.0/24")
+            ]
+        );
+
+        // peer 1 withdraws 192.0.2.0/24
+        let msg = route_monitoring(1, &[24, 192, 0, 2], &[], &[]);
+        ribs.process(BMP_MSG_ROUTE_MONITORING, &msg, &mut peers)
+            .unwrap();
+        assert_eq!(
+            ribs.announcements(),
+            vec![announcement(65001, "2001:db8::/32")]
+        );
+
+        // peer 2 goes down
+        ribs.process(BMP_MSG_PEER_DOWN, &per_peer_header(2), &mut peers)
+            .unwrap();
+        assert!(ribs.announcements().is_empty());
+
+        // truncated messages result in an error
+        assert!(ribs
+            .process(BMP_MSG_ROUTE_MONITORING, &msg[..50], &mut peers)
+            .is_err());
+    }
+}
@@ -0,0 +1,272 @@
+//! Route objects registered in an Internet Routing Registry (IRR).
+//!
+//! Route objects are represented as announcements, so that they can be
+//! validated against ROAs in the same way as announcements seen in BGP.
+
+use std::{fmt, io, path::PathBuf, str::FromStr, time::Duration};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::{
+    commons::{
+        api::{AsNumber, TypedPrefix},
+        bgp::{read_maybe_gzipped, Announcement, BgpSourceError},
+        error::KrillIoError,
+    },
+    daemon::config::IrrSourceConfig,
+};
+
+/// The time allowed for a complete whois query.
+const WHOIS_TIMEOUT_SECS: u64 = 120;
+
+/// The maximum size of a whois response. Queries are expected to select
+/// the route objects for the prefixes of the CAs, rather than a full dump.
+const WHOIS_MAX_RESPONSE_BYTES: u64 = 64 * 1024 * 1024;
+
+//------------ IrrSource -----------------------------------------------------
+
+/// A source of IRR route objects.
+pub enum IrrSource {
+    /// A local RPSL dump.
+    File(RpslFile),
+
+    /// A query to a whois server.
+    Whois(WhoisQuery),
+}
+
+impl IrrSource {
+    pub fn from_config(config: &IrrSourceConfig) -> Self {
+        match config {
+            IrrSourceConfig::File { path } => {
+                IrrSource::File(RpslFile { path: path.clone() })
+            }
+            IrrSourceConfig::Whois { server, query } => {
+                IrrSource::Whois(WhoisQuery {
+                    server: server.clone(),
+                    query: query.clone(),
+                })
+            }
+        }
+    }
+
+    /// Loads the current route objects from this source.
+    pub async fn load(&self) -> Result<Vec<Announcement>, BgpSourceError> {
+        match self {
+            IrrSource::File(file) => file.load(),
+            IrrSource::Whois(query) => query.load().await,
+        }
+    }
+}
+
+impl fmt::Display for IrrSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IrrSource::File(file) => {
+                write!(f, "RPSL file {}", file.path.display())
+            }
+            IrrSource::Whois(query) => {
+                write!(f, "whois query '{}' to {}", query.query, query.server)
+            }
+        }
+    }
+}
+
+//------------ RpslFile ------------------------------------------------------
+
+/// A local file with RPSL objects, such as the split database dumps
+/// published by most IRRs. The file may be gzipped.
+pub struct RpslFile {
+    path: PathBuf,
+}
+
+impl RpslFile {
+    pub fn load(&self) -> Result<Vec<Announcement>, BgpSourceError> {
+        let bytes = read_maybe_gzipped(&self.path)?;
+
+        // Dumps may contain some latin-1 in free text attributes, which
+        // we do not care about.
+        Ok(parse_rpsl(&String::from_utf8_lossy(&bytes)))
+    }
+}
+
+//------------ WhoisQuery ----------------------------------------------------
+
+/// A query sent to a whois server, e.g. an IRRd mirror, which responds with
+/// RPSL objects. The query should select the route and route6 objects of
+/// interest, for example: "-K -T route,route6 -i mnt-by MAINT-EXAMPLE".
+pub struct WhoisQuery {
+    server: String,
+    query: String,
+}
+
+impl WhoisQuery {
+    pub async fn load(&self) -> Result<Vec<Announcement>, BgpSourceError> {
+        let response = tokio::time::timeout(
+            Duration::from_secs(WHOIS_TIMEOUT_SECS),
+            self.query(),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            Err(io::Error::new(io::ErrorKind::TimedOut, "query timed out"))
+        })
+        .map_err(|e| {
+            KrillIoError::new(
+                format!("Could not query whois server {}", self.server),
+                e,
+            )
+        })?;
+
+        Ok(parse_rpsl(&String::from_utf8_lossy(&response)))
+    }
+
+    async fn query(&self) -> Result<Vec<u8>, io::Error> {
+        let mut stream = TcpStream::connect(&self.server).await?;
+        stream
+            .write_all(format!("{}\r\n", self.query).as_bytes())
+            .await?;
+
+        // Read one byte more than allowed, so that we can tell whether the
+        // response was too large, rather than silently truncate it.
+        let mut response = vec![];
+        stream
+            .take(WHOIS_MAX_RESPONSE_BYTES + 1)
+            .read_to_end(&mut response)
+            .await?;
+
+        if response.len() as u64 > WHOIS_MAX_RESPONSE_BYTES {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "response exceeds {} bytes",
+                    WHOIS_MAX_RESPONSE_BYTES
+                ),
+            ))
+        } else {
+            Ok(response)
+        }
+    }
+}
+
+//------------ Parsing -------------------------------------------------------
+
+/// Parses the route and route6 objects from RPSL text. Other objects are
+/// ignored, and so are route objects which cannot be parsed, as IRR data
+/// is known to contain some of those.
+fn parse_rpsl(text: &str) -> Vec<Announcement> {
+    let mut res = vec![];
+    let mut skipped = 0;
+
+    let mut route: Option<&str> = None;
+    let mut origin: Option<&str> = None;
+    let mut first = true;
+
+    // Objects are separated by empty lines, adding one at the end ensures
+    // that the last object is included.
+    for line in text.lines().chain(std::iter::once("")) {
+        if line.trim().is_empty() {
+            if let Some(route) = route.take() {
+                match parse_route(route, origin.take()) {
+                    Some(announcement) => res.push(announcement),
+                    None => skipped += 1,
+                }
+            }
+            origin = None;
+            first = true;
+            continue;
+        }
+
+        // Comments, and continuation lines of attribute values which are
+        // never needed here.
+        if line.starts_with(['%', '#', ' ', '\t', '+']) {
+            continue;
+        }
+
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name.trim(), value),
+            None => continue,
+        };
+        let value = value.split('#').next().unwrap_or_default().trim();
+
+        // The first attribute determines the class of an object
+        if first {
+            first = false;
+            if name.eq_ignore_ascii_case("route")
+                || name.eq_ignore_ascii_case("route6")
+            {
+                route = Some(value);
+            }
+        } else if route.is_some() && name.eq_ignore_ascii_case("origin") {
+            origin = Some(value);
+        }
+    }
+
+    if skipped > 0 {
+        debug!(
+            "Skipped {} route objects which could not be parsed",
+            skipped
+        );
+    }
+
+    res
+}
+
+fn parse_route(route: &str, origin: Option<&str>) -> Option<Announcement> {
+    let origin = origin?;
+    let origin = origin
+        .strip_prefix("AS")
+        .or_else(|| origin.strip_prefix("as"))?;
+
+    let asn = AsNumber::from_str(origin).ok()?;
+    let prefix = TypedPrefix::from_str(route).ok()?;
+    Some(Announcement::new(asn, prefix))
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rpsl_route_objects() {
+        let text = "\
+% This is the RIPE Database query service.
+
+route:          192.0.2.0/24
+descr:          Example
+                continued description
+origin:         AS64496 # primary
+mnt-by:         MAINT-EXAMPLE
+source:         RIPE
+
+aut-num:        AS64496
+as-name:        EXAMPLE
+
+route6:         2001:db8::/32
+origin:         as64497
+source:         RADB
+
+route:          198.51.100.0/24
+descr:          no origin
+
+route:          198.51.100.0/33
+origin:         AS64498
+";
+        assert_eq!(
+            parse_rpsl(text),
+            vec![
+                Announcement::new(
+                    AsNumber::new(64496),
+                    TypedPrefix::from_str("192.0.2.0/24").unwrap()
+                ),
+                Announcement::new(
+                    AsNumber::new(64497),
+                    TypedPrefix::from_str("2001:db8::/32").unwrap()
+                ),
+            ]
+        );
+    }
+}
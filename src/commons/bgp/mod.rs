@@ -1,14 +1,31 @@
 mod analyser;
 pub use self::analyser::*;
 
+mod aspa;
+pub use self::aspa::*;
+
 mod announcements;
 pub use self::announcements::*;
 
 mod iptree;
 pub use self::iptree::*;
 
+mod bmp;
+pub use self::bmp::*;
+
+mod mrt;
+pub use self::mrt::*;
+
 mod risdumps;
 pub use self::risdumps::*;
 
+mod sources;
+pub use self::sources::*;
+
+mod irr;
+pub use self::irr::*;
+
 mod report;
 pub use self::report::*;
+
+mod wire;
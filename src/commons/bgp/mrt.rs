@@ -0,0 +1,178 @@
+//! Support loading announcements from MRT RIB dumps
+//!
+//! See RFC 6396 (and RFC 8050 for the ADD-PATH variants). Only the
+//! TABLE_DUMP_V2 type is supported, as produced by most route collectors
+//! for their periodic RIB snapshots. Other record types are skipped.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use crate::commons::bgp::{
+    read_maybe_gzipped,
+    wire::{AddressFamily, PathAttributes, WireReader},
+    Announcement, BgpSourceError,
+};
+
+const MRT_TYPE_TABLE_DUMP_V2: u16 = 13;
+
+const RIB_IPV4_UNICAST: u16 = 2;
+const RIB_IPV6_UNICAST: u16 = 4;
+const RIB_IPV4_UNICAST_ADDPATH: u16 = 8;
+const RIB_IPV6_UNICAST_ADDPATH: u16 = 10;
+
+//------------ MrtRibDump ----------------------------------------------------
+
+/// A local MRT file with a RIB dump. The file may be gzipped.
+pub struct MrtRibDump {
+    path: PathBuf,
+}
+
+impl MrtRibDump {
+    pub fn new(path: PathBuf) -> Self {
+        MrtRibDump { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn load(&self) -> Result<Vec<Announcement>, BgpSourceError> {
+        let bytes = read_maybe_gzipped(&self.path)?;
+        Self::parse(&bytes)
+    }
+
+    /// Parses the RIB entries in an MRT dump. Every distinct origin ASN
+    /// seen for a prefix, in the routes of any peer, results in an
+    /// announcement.
+    pub fn parse(bytes: &[u8]) -> Result<Vec<Announcement>, BgpSourceError> {
+        let mut announcements = HashSet::new();
+        let mut reader = WireReader::new(bytes);
+
+        while !reader.is_empty() {
+            let _timestamp = reader.u32()?;
+            let mrt_type = reader.u16()?;
+            let subtype = reader.u16()?;
+            let len = reader.u32()? as usize;
+            let body = reader.take(len)?;
+
+            if mrt_type != MRT_TYPE_TABLE_DUMP_V2 {
+                continue;
+            }
+
+            let (family, add_path) = match subtype {
+                RIB_IPV4_UNICAST => (AddressFamily::Ipv4, false),
+                RIB_IPV6_UNICAST => (AddressFamily::Ipv6, false),
+                RIB_IPV4_UNICAST_ADDPATH => (AddressFamily::Ipv4, true),
+                RIB_IPV6_UNICAST_ADDPATH => (AddressFamily::Ipv6, true),
+                _ => continue, // peer index table, multicast, etc.
+            };
+
+            let mut rib = WireReader::new(body);
+            let _sequence = rib.u32()?;
+            let prefix = rib.prefix(family)?;
+            let entries = rib.u16()?;
+
+            for _ in 0..entries {
+                let _peer_index = rib.u16()?;
+                let _originated = rib.u32()?;
+                if add_path {
+                    let _path_id = rib.u32()?;
+                }
+                let attributes_len = rib.u16()? as usize;
+                let attributes = rib.take(attributes_len)?;
+
+                // TABLE_DUMP_V2 always uses 4 octet ASNs in AS_PATH
+                let attributes =
+                    PathAttributes::parse(attributes, false, false)?;
+                if let Some(asn) = attributes.origin() {
+                    announcements.insert(Announcement::new(asn, prefix));
+                }
+            }
+        }
+
+        Ok(announcements.into_iter().collect())
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::commons::{
+        api::{AsNumber, TypedPrefix},
+        bgp::wire::tests::as_path_attribute,
+    };
+
+    fn mrt_record(mrt_type: u16, subtype: u16, body: &[u8]) -> Vec<u8> {
+        let mut res = vec![];
+        res.extend_from_slice(&1_700_000_000_u32.to_be_bytes());
+        res.extend_from_slice(&mrt_type.to_be_bytes());
+        res.extend_from_slice(&subtype.to_be_bytes());
+        res.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        res.extend_from_slice(body);
+        res
+    }
+
+    fn rib_body(prefix: &[u8], origins: &[u32]) -> Vec<u8> {
+        let mut res = vec![];
+        res.extend_from_slice(&7_u32.to_be_bytes()); // sequence
+        res.extend_from_slice(prefix);
+        res.extend_from_slice(&(origins.len() as u16).to_be_bytes());
+        for (idx, origin) in origins.iter().enumerate() {
+            res.extend_from_slice(&(idx as u16).to_be_bytes()); // peer
+            res.extend_from_slice(&1_700_000_000_u32.to_be_bytes());
+            let attributes = as_path_attribute(&[64496, *origin]);
+            res.extend_from_slice(&(attributes.len() as u16).to_be_bytes());
+            res.extend_from_slice(&attributes);
+        }
+        res
+    }
+
+    #[test]
+    fn parse_table_dump_v2() {
+        let mut dump = vec![];
+        // peer index table, which we skip
+        dump.append(&mut mrt_record(13, 1, &[0, 0, 0, 0, 0, 0, 0, 0]));
+        // 192.0.2.0/24 seen from two peers with the same origin
+        dump.append(&mut mrt_record(
+            13,
+            RIB_IPV4_UNICAST,
+            &rib_body(&[24, 192, 0, 2], &[65000, 65000]),
+        ));
+        // 2001:db8::/32 seen with two different origins
+        dump.append(&mut mrt_record(
+            13,
+            RIB_IPV6_UNICAST,
+            &rib_body(&[32, 0x20, 0x01, 0x0d, 0xb8], &[65001, 65002]),
+        ));
+        // BGP4MP records are skipped
+        dump.append(&mut mrt_record(16, 4, &[1, 2, 3]));
+
+        let mut announcements = MrtRibDump::parse(&dump).unwrap();
+        announcements.sort_by_key(|ann| (*ann.prefix(), *ann.asn()));
+
+        let expected: Vec<_> = [
+            (65001, "2001:db8::/32"),
+            (65002, "2001:db8::/32"),
+            (65000, "192.0.2.0/24"),
+        ]
+        .iter()
+        .map(|(asn, prefix)| {
+            Announcement::new(
+                AsNumber::new(*asn),
+                TypedPrefix::from_str(prefix).unwrap(),
+            )
+        })
+        .collect();
+
+        assert_eq!(announcements, expected);
+
+        // truncated input results in an error rather than a panic
+        assert!(MrtRibDump::parse(&dump[..dump.len() - 10]).is_err());
+    }
+}
@@ -105,6 +105,12 @@ pub struct BgpAnalysisSuggestion {
 
     #[serde(skip_serializing_if = "Vec::is_empty", default = "Vec::new")]
     keep_disallowing: Vec<Announcement>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Vec::new")]
+    route_object_no_roa: Vec<Announcement>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Vec::new")]
+    no_route_object: Vec<ConfiguredRoa>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -123,6 +129,7 @@ impl From<BgpAnalysisSuggestion> for RoaConfigurationUpdates {
             too_permissive,
             as0_redundant,
             redundant,
+            route_object_no_roa,
         ) = (
             suggestion.stale,
             suggestion.not_found,
@@ -131,6 +138,7 @@ impl From<BgpAnalysisSuggestion> for RoaConfigurationUpdates {
             suggestion.too_permissive,
             suggestion.as0_redundant,
             suggestion.redundant,
+            suggestion.route_object_no_roa,
         );
 
         let mut added: Vec<RoaConfiguration> = vec![];
@@ -144,6 +152,14 @@ impl From<BgpAnalysisSuggestion> for RoaConfigurationUpdates {
             added.push(RoaConfiguration::new(announcement.into(), None));
         }
 
+        // Route objects may also have been seen in BGP
+        for route_object in route_object_no_roa.into_iter() {
+            let payload = RoaPayload::from(route_object);
+            if !added.iter().any(|roa| roa.payload() == payload) {
+                added.push(RoaConfiguration::new(payload, None));
+            }
+        }
+
         for stale in stale.into_iter() {
             removed.push(stale.payload());
         }
@@ -219,6 +235,14 @@ impl BgpAnalysisSuggestion {
     pub fn add_keep_disallowing(&mut self, announcement: Announcement) {
         self.keep_disallowing.push(announcement);
     }
+
+    pub fn add_route_object_no_roa(&mut self, route_object: Announcement) {
+        self.route_object_no_roa.push(route_object);
+    }
+
+    pub fn add_no_route_object(&mut self, configured: &ConfiguredRoa) {
+        self.no_route_object.push(configured.clone());
+    }
 }
 
 #[allow(clippy::cognitive_complexity)]
@@ -314,6 +338,28 @@ impl fmt::Display for BgpAnalysisSuggestion {
             writeln!(f)?;
         }
 
+        if !self.route_object_no_roa.is_empty() {
+            writeln!(
+                f,
+                "Authorize these route objects which are registered in the IRR:"
+            )?;
+            for route_object in &self.route_object_no_roa {
+                writeln!(f, "  {}", route_object)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.no_route_object.is_empty() {
+            writeln!(
+                f,
+                "Consider registering route objects in the IRR for these authorizations:"
+            )?;
+            for auth in &self.no_route_object {
+                writeln!(f, "  {}", auth)?;
+            }
+            writeln!(f)?;
+        }
+
         Ok(())
     }
 }
@@ -404,6 +450,9 @@ impl From<BgpAnalysisReport> for BgpStats {
                 BgpAnalysisState::RoaAs0
                 | BgpAnalysisState::RoaNoAnnouncementInfo
                 | BgpAnalysisState::RoaSeen => stats.increment_roas_total(),
+                // ROAs are counted in their BGP state already
+                BgpAnalysisState::RoaNoRouteObject
+                | BgpAnalysisState::RouteObjectNoRoa => {}
             }
         }
         stats
@@ -424,7 +473,13 @@ impl fmt::Display for BgpAnalysisReport {
         }
 
         if entry_map.contains_key(&BgpAnalysisState::RoaNoAnnouncementInfo) {
-            write!(f, "no BGP announcements known")
+            write!(f, "no BGP announcements known")?;
+            if entry_map.contains_key(&BgpAnalysisState::RoaNoRouteObject)
+                || entry_map.contains_key(&BgpAnalysisState::RouteObjectNoRoa)
+            {
+                writeln!(f)?;
+                writeln!(f)?;
+            }
         } else {
             if let Some(authorizing) =
                 entry_map.get(&BgpAnalysisState::RoaSeen)
@@ -667,9 +722,48 @@ impl fmt::Display for BgpAnalysisReport {
                 }
                 writeln!(f)?;
             }
+        }
+
+        if let Some(no_roa) =
+            entry_map.get(&BgpAnalysisState::RouteObjectNoRoa)
+        {
+            writeln!(
+                f,
+                "Route objects registered in the IRR which are not authorized by your ROA configurations:"
+            )?;
+            for route_object in no_roa {
+                writeln!(f)?;
+                writeln!(
+                    f,
+                    "\tRoute object: {}",
+                    route_object.announcement()
+                )?;
+                if !route_object.disallowed_by.is_empty() {
+                    writeln!(f)?;
+                    writeln!(f, "\t\tDisallowed by ROA configuration(s):")?;
+                    for roa in route_object.disallowed_by.iter() {
+                        writeln!(f, "\t\t{}", roa)?;
+                    }
+                }
+            }
+            writeln!(f)?;
+        }
 
-            Ok(())
+        if let Some(no_route_object) =
+            entry_map.get(&BgpAnalysisState::RoaNoRouteObject)
+        {
+            writeln!(
+                f,
+                "ROA configurations for which no route object is registered in the IRR:"
+            )?;
+            writeln!(f)?;
+            for roa in no_route_object {
+                writeln!(f, "\tConfiguration: {}", roa.configured_roa())?;
+            }
+            writeln!(f)?;
         }
+
+        Ok(())
     }
 }
 
@@ -1002,6 +1096,33 @@ impl BgpAnalysisEntry {
         }
     }
 
+    pub fn roa_no_route_object(configured_roa: ConfiguredRoa) -> Self {
+        BgpAnalysisEntry {
+            roa_or_announcement: ConfiguredRoaOrAnnouncement::Roa(
+                configured_roa,
+            ),
+            state: BgpAnalysisState::RoaNoRouteObject,
+            allowed_by: None,
+            disallowed_by: vec![],
+            made_redundant_by: vec![],
+            authorizes: vec![],
+            disallows: vec![],
+        }
+    }
+
+    /// A route object is represented as an announcement, disallowed_by
+    /// lists the ROAs which make it invalid, if any.
+    pub fn route_object_no_roa(
+        route_object: Announcement,
+        disallowed_by: Vec<RoaPayload>,
+    ) -> Self {
+        Self::announcement_invalid(
+            route_object,
+            BgpAnalysisState::RouteObjectNoRoa,
+            disallowed_by,
+        )
+    }
+
     pub fn announcement_not_found(announcement: Announcement) -> Self {
         BgpAnalysisEntry {
             roa_or_announcement: ConfiguredRoaOrAnnouncement::Announcement(
@@ -1064,6 +1185,8 @@ pub enum BgpAnalysisState {
     AnnouncementDisallowed,
     AnnouncementNotFound,
     RoaNoAnnouncementInfo,
+    RoaNoRouteObject,
+    RouteObjectNoRoa,
 }
 
 impl BgpAnalysisState {
@@ -0,0 +1,247 @@
+//! Sources of BGP announcements used by the BgpAnalyser.
+
+use std::{
+    fmt,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use libflate::gzip::Decoder;
+
+use crate::{
+    commons::{
+        api::{AsNumber, TypedPrefix},
+        bgp::{
+            Announcement, BmpFeed, MrtRibDump, RisDumpError, RisDumpLoader,
+        },
+        error::KrillIoError,
+        util::file,
+    },
+    daemon::config::BgpSourceConfig,
+};
+
+//------------ BgpSource -----------------------------------------------------
+
+/// A source of BGP announcements.
+pub enum BgpSource {
+    /// The RIPE RIS whois dumps, downloaded over HTTP.
+    RisDumps(RisDumpLoader),
+
+    /// A local text file with announcements.
+    File(AnnouncementsFile),
+
+    /// A local MRT RIB dump (TABLE_DUMP_V2).
+    Mrt(MrtRibDump),
+
+    /// Routes received from routers over BMP.
+    Bmp(BmpFeed),
+}
+
+impl BgpSource {
+    pub fn from_config(config: &BgpSourceConfig) -> Self {
+        match config {
+            BgpSourceConfig::File { path } => {
+                BgpSource::File(AnnouncementsFile::new(path.clone()))
+            }
+            BgpSourceConfig::Mrt { path } => {
+                BgpSource::Mrt(MrtRibDump::new(path.clone()))
+            }
+            BgpSourceConfig::Bmp { listen } => {
+                BgpSource::Bmp(BmpFeed::start(*listen))
+            }
+        }
+    }
+
+    /// Loads the current announcements from this source.
+    pub async fn load(&self) -> Result<Vec<Announcement>, BgpSourceError> {
+        match self {
+            BgpSource::RisDumps(loader) => loader
+                .download_updates()
+                .await
+                .map_err(BgpSourceError::from),
+            BgpSource::File(file) => file.load(),
+            BgpSource::Mrt(dump) => dump.load(),
+            BgpSource::Bmp(feed) => Ok(feed.announcements().await),
+        }
+    }
+}
+
+impl fmt::Display for BgpSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BgpSource::RisDumps(_) => write!(f, "BGP RIS dumps"),
+            BgpSource::File(file) => {
+                write!(f, "announcements file {}", file.path.display())
+            }
+            BgpSource::Mrt(dump) => {
+                write!(f, "MRT RIB dump {}", dump.path().display())
+            }
+            BgpSource::Bmp(feed) => {
+                write!(f, "BMP feed on {}", feed.listen())
+            }
+        }
+    }
+}
+
+//------------ AnnouncementsFile ---------------------------------------------
+
+/// A local text file with one announcement per line: the origin ASN and
+/// the prefix separated by whitespace, optionally followed by other
+/// columns which are ignored. This is compatible with the RIS whois dump
+/// format. Lines that are empty or start with '%' or '#' are skipped.
+///
+/// The file may be gzipped.
+pub struct AnnouncementsFile {
+    path: PathBuf,
+}
+
+impl AnnouncementsFile {
+    pub fn new(path: PathBuf) -> Self {
+        AnnouncementsFile { path }
+    }
+
+    pub fn load(&self) -> Result<Vec<Announcement>, BgpSourceError> {
+        let bytes = read_maybe_gzipped(&self.path)?;
+        let text =
+            String::from_utf8(bytes).map_err(BgpSourceError::format)?;
+        Self::parse(&text)
+    }
+
+    fn parse(text: &str) -> Result<Vec<Announcement>, BgpSourceError> {
+        let mut res = vec![];
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty()
+                || line.starts_with('%')
+                || line.starts_with('#')
+            {
+                continue;
+            }
+
+            let mut values = line.split_whitespace();
+            let (asn_str, prefix_str) = match (values.next(), values.next()) {
+                (Some(asn), Some(prefix)) => (asn, prefix),
+                _ => {
+                    return Err(BgpSourceError::format(format!(
+                        "missing column on line {}",
+                        idx + 1
+                    )))
+                }
+            };
+
+            if asn_str.contains('{') {
+                continue; // AS_SET origins are not supported
+            }
+
+            let asn_str = asn_str
+                .strip_prefix("AS")
+                .or_else(|| asn_str.strip_prefix("as"))
+                .unwrap_or(asn_str);
+            let asn = AsNumber::from_str(asn_str).map_err(|e| {
+                BgpSourceError::format(format!("line {}: {}", idx + 1, e))
+            })?;
+            let prefix = TypedPrefix::from_str(prefix_str).map_err(|e| {
+                BgpSourceError::format(format!("line {}: {}", idx + 1, e))
+            })?;
+
+            res.push(Announcement::new(asn, prefix));
+        }
+        Ok(res)
+    }
+}
+
+/// Reads a file, and gunzips its content if it starts with the gzip magic
+/// bytes.
+pub(super) fn read_maybe_gzipped(
+    path: &Path,
+) -> Result<Vec<u8>, BgpSourceError> {
+    let bytes = file::read(path)?;
+
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut gunzipped: Vec<u8> = vec![];
+        let mut decoder = Decoder::new(bytes.as_ref()).map_err(|e| {
+            BgpSourceError::format(format!("Could not unzip file: {}", e))
+        })?;
+        decoder.read_to_end(&mut gunzipped).map_err(|e| {
+            BgpSourceError::format(format!("Could not unzip file: {}", e))
+        })?;
+        Ok(gunzipped)
+    } else {
+        Ok(bytes.to_vec())
+    }
+}
+
+//------------ BgpSourceError ------------------------------------------------
+
+#[derive(Debug)]
+pub enum BgpSourceError {
+    RisDump(RisDumpError),
+    IoError(KrillIoError),
+    FormatError(String),
+}
+
+impl BgpSourceError {
+    pub fn format(e: impl fmt::Display) -> Self {
+        BgpSourceError::FormatError(e.to_string())
+    }
+}
+
+impl fmt::Display for BgpSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BgpSourceError::RisDump(e) => e.fmt(f),
+            BgpSourceError::IoError(e) => write!(f, "IO error: {}", e),
+            BgpSourceError::FormatError(s) => {
+                write!(f, "Invalid input: {}", s)
+            }
+        }
+    }
+}
+
+impl From<RisDumpError> for BgpSourceError {
+    fn from(e: RisDumpError) -> Self {
+        BgpSourceError::RisDump(e)
+    }
+}
+
+impl From<KrillIoError> for BgpSourceError {
+    fn from(e: KrillIoError) -> Self {
+        BgpSourceError::IoError(e)
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_announcements_file() {
+        let text = "\
+% RIS whois style comment
+# our own collector
+AS64496   192.0.2.0/24    12
+64497     2001:db8::/32
+{64498,64499} 198.51.100.0/24
+";
+        let announcements = AnnouncementsFile::parse(text).unwrap();
+        assert_eq!(
+            announcements,
+            vec![
+                Announcement::new(
+                    AsNumber::new(64496),
+                    TypedPrefix::from_str("192.0.2.0/24").unwrap()
+                ),
+                Announcement::new(
+                    AsNumber::new(64497),
+                    TypedPrefix::from_str("2001:db8::/32").unwrap()
+                ),
+            ]
+        );
+
+        assert!(AnnouncementsFile::parse("64496").is_err());
+        assert!(AnnouncementsFile::parse("64496 192.0.2.0/33").is_err());
+    }
+}
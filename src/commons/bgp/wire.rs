@@ -0,0 +1,311 @@
+//! Helpers for decoding the BGP wire format, as used in MRT and BMP.
+//!
+//! See RFC 4271 for BGP messages and path attributes, and RFC 4760 for the
+//! multiprotocol extensions used for IPv6.
+
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+use crate::commons::{
+    api::{AsNumber, TypedPrefix},
+    bgp::BgpSourceError,
+};
+
+const ATTR_FLAG_EXTENDED_LENGTH: u8 = 0x10;
+
+const ATTR_TYPE_AS_PATH: u8 = 2;
+const ATTR_TYPE_MP_REACH_NLRI: u8 = 14;
+const ATTR_TYPE_MP_UNREACH_NLRI: u8 = 15;
+const ATTR_TYPE_AS4_PATH: u8 = 17;
+
+const AS_PATH_SEGMENT_SEQUENCE: u8 = 2;
+
+const AFI_IPV4: u16 = 1;
+const AFI_IPV6: u16 = 2;
+const SAFI_UNICAST: u8 = 1;
+
+//------------ WireReader ----------------------------------------------------
+
+/// Reads big-endian values from a byte slice, returning errors rather
+/// than panicking if the input is truncated.
+pub(super) struct WireReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> WireReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        WireReader { bytes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], BgpSourceError> {
+        if len > self.bytes.len() {
+            Err(BgpSourceError::format("unexpected end of input"))
+        } else {
+            let (taken, rest) = self.bytes.split_at(len);
+            self.bytes = rest;
+            Ok(taken)
+        }
+    }
+
+    pub fn u8(&mut self) -> Result<u8, BgpSourceError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, BgpSourceError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, BgpSourceError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Reads a prefix encoded as a length in bits followed by the minimum
+    /// number of octets needed to hold it.
+    pub fn prefix(
+        &mut self,
+        family: AddressFamily,
+    ) -> Result<TypedPrefix, BgpSourceError> {
+        let len = self.u8()?;
+        let max_len = match family {
+            AddressFamily::Ipv4 => 32,
+            AddressFamily::Ipv6 => 128,
+        };
+        if len > max_len {
+            return Err(BgpSourceError::format(format!(
+                "invalid prefix length: {}",
+                len
+            )));
+        }
+
+        let mut addr = [0_u8; 16];
+        let octets = self.take((len as usize + 7) / 8)?;
+        addr[..octets.len()].copy_from_slice(octets);
+
+        // clear any bits beyond the prefix length
+        if len % 8 != 0 {
+            let last = octets.len() - 1;
+            addr[last] &= 0xff_u8 << (8 - len % 8);
+        }
+
+        let prefix_str = match family {
+            AddressFamily::Ipv4 => {
+                let v4 = Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]);
+                format!("{}/{}", v4, len)
+            }
+            AddressFamily::Ipv6 => {
+                format!("{}/{}", Ipv6Addr::from(addr), len)
+            }
+        };
+
+        TypedPrefix::from_str(&prefix_str).map_err(BgpSourceError::format)
+    }
+}
+
+//------------ AddressFamily -------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum AddressFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    /// Returns the address family for an AFI/SAFI combination, or None if
+    /// it is not IPv4 or IPv6 unicast.
+    fn unicast(afi: u16, safi: u8) -> Option<Self> {
+        match (afi, safi) {
+            (AFI_IPV4, SAFI_UNICAST) => Some(AddressFamily::Ipv4),
+            (AFI_IPV6, SAFI_UNICAST) => Some(AddressFamily::Ipv6),
+            _ => None,
+        }
+    }
+}
+
+//------------ PathAttributes ------------------------------------------------
+
+/// The parts of the BGP path attributes that we need to find announcements.
+#[derive(Debug, Default)]
+pub(super) struct PathAttributes {
+    origin: Option<AsNumber>,
+    as4_origin: Option<AsNumber>,
+    reachable: Vec<TypedPrefix>,
+    unreachable: Vec<TypedPrefix>,
+}
+
+impl PathAttributes {
+    /// Parses path attributes. AS_PATH segments use 4 octet ASNs, unless
+    /// 'legacy_as_path' is set. In that case 2 octet ASNs are used, and the
+    /// origin from the AS4_PATH attribute is preferred if present.
+    ///
+    /// MP_REACH_NLRI and MP_UNREACH_NLRI are only parsed if 'parse_mp' is
+    /// set. MRT RIB entries use an abbreviated form for these, but they
+    /// are not needed there because the prefix is part of the entry.
+    pub fn parse(
+        bytes: &[u8],
+        legacy_as_path: bool,
+        parse_mp: bool,
+    ) -> Result<Self, BgpSourceError> {
+        let mut attributes = PathAttributes::default();
+        let mut reader = WireReader::new(bytes);
+
+        while !reader.is_empty() {
+            let flags = reader.u8()?;
+            let attr_type = reader.u8()?;
+            let len = if flags & ATTR_FLAG_EXTENDED_LENGTH != 0 {
+                reader.u16()? as usize
+            } else {
+                reader.u8()? as usize
+            };
+            let value = reader.take(len)?;
+
+            match attr_type {
+                ATTR_TYPE_AS_PATH => {
+                    let asn_size = if legacy_as_path { 2 } else { 4 };
+                    attributes.origin = Self::path_origin(value, asn_size)?;
+                }
+                ATTR_TYPE_AS4_PATH => {
+                    attributes.as4_origin = Self::path_origin(value, 4)?;
+                }
+                ATTR_TYPE_MP_REACH_NLRI if parse_mp => {
+                    let mut mp = WireReader::new(value);
+                    let afi = mp.u16()?;
+                    let safi = mp.u8()?;
+                    let next_hop_len = mp.u8()? as usize;
+                    mp.take(next_hop_len)?;
+                    mp.u8()?; // reserved
+                    if let Some(family) = AddressFamily::unicast(afi, safi) {
+                        while !mp.is_empty() {
+                            attributes.reachable.push(mp.prefix(family)?);
+                        }
+                    }
+                }
+                ATTR_TYPE_MP_UNREACH_NLRI if parse_mp => {
+                    let mut mp = WireReader::new(value);
+                    let afi = mp.u16()?;
+                    let safi = mp.u8()?;
+                    if let Some(family) = AddressFamily::unicast(afi, safi) {
+                        while !mp.is_empty() {
+                            attributes.unreachable.push(mp.prefix(family)?);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if legacy_as_path && attributes.as4_origin.is_some() {
+            attributes.origin = attributes.as4_origin;
+        }
+
+        Ok(attributes)
+    }
+
+    /// Returns the origin ASN, if it can be determined. This is the last
+    /// ASN of the path, provided that it is part of an AS_SEQUENCE. Paths
+    /// ending in an AS_SET are not supported, and empty paths have no
+    /// origin in this sense.
+    pub fn origin(&self) -> Option<AsNumber> {
+        self.origin
+    }
+
+    /// Returns the IPv6 (or IPv4) prefixes from MP_REACH_NLRI.
+    pub fn reachable(&self) -> &[TypedPrefix] {
+        &self.reachable
+    }
+
+    /// Returns the IPv6 (or IPv4) prefixes from MP_UNREACH_NLRI.
+    pub fn unreachable(&self) -> &[TypedPrefix] {
+        &self.unreachable
+    }
+
+    fn path_origin(
+        value: &[u8],
+        asn_size: usize,
+    ) -> Result<Option<AsNumber>, BgpSourceError> {
+        let mut origin = None;
+        let mut reader = WireReader::new(value);
+
+        while !reader.is_empty() {
+            let segment_type = reader.u8()?;
+            let count = reader.u8()? as usize;
+            let mut last = None;
+            for _ in 0..count {
+                last = Some(if asn_size == 2 {
+                    reader.u16()? as u32
+                } else {
+                    reader.u32()?
+                });
+            }
+            if count > 0 {
+                origin = if segment_type == AS_PATH_SEGMENT_SEQUENCE {
+                    last.map(AsNumber::new)
+                } else {
+                    None
+                };
+            }
+        }
+
+        Ok(origin)
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// Encodes an AS_PATH attribute with a single AS_SEQUENCE.
+    pub fn as_path_attribute(asns: &[u32]) -> Vec<u8> {
+        let mut res =
+            vec![0x40, ATTR_TYPE_AS_PATH, (2 + asns.len() * 4) as u8];
+        res.push(AS_PATH_SEGMENT_SEQUENCE);
+        res.push(asns.len() as u8);
+        for asn in asns {
+            res.extend_from_slice(&asn.to_be_bytes());
+        }
+        res
+    }
+
+    #[test]
+    fn read_prefixes() {
+        let bytes = [24, 192, 0, 2, 20, 0x20, 0x01, 0x0d, 0xbf];
+        let mut reader = WireReader::new(&bytes);
+        assert_eq!(
+            reader.prefix(AddressFamily::Ipv4).unwrap(),
+            TypedPrefix::from_str("192.0.2.0/24").unwrap()
+        );
+        // host bits beyond the length are cleared
+        assert_eq!(
+            reader.prefix(AddressFamily::Ipv6).unwrap(),
+            TypedPrefix::from_str("2001::/20").unwrap()
+        );
+        assert!(reader.prefix(AddressFamily::Ipv4).is_err());
+    }
+
+    #[test]
+    fn origin_from_as_path() {
+        let bytes = as_path_attribute(&[64496, 64497, 65000]);
+        let attributes = PathAttributes::parse(&bytes, false, false).unwrap();
+        assert_eq!(attributes.origin(), Some(AsNumber::new(65000)));
+
+        // AS_SET at the end means no usable origin
+        let bytes = [0x40, ATTR_TYPE_AS_PATH, 6, 1, 1, 0, 0, 0xfd, 0xe8];
+        let attributes = PathAttributes::parse(&bytes, false, false).unwrap();
+        assert_eq!(attributes.origin(), None);
+
+        // legacy 2 octet AS_PATH
+        let bytes =
+            [0x40, ATTR_TYPE_AS_PATH, 6, 2, 2, 0xfb, 0xf0, 0xfd, 0xe8];
+        let attributes = PathAttributes::parse(&bytes, true, false).unwrap();
+        assert_eq!(attributes.origin(), Some(AsNumber::new(65000)));
+    }
+}
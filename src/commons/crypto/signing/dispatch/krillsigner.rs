@@ -1,5 +1,6 @@
 use std::{sync::Arc, time::Duration};
 
+use bcder::Oid;
 use bytes::Bytes;
 use rpki::{
     ca::{
@@ -20,7 +21,7 @@ use rpki::{
         manifest::ManifestContent,
         roa::RoaBuilder,
         rta,
-        sigobj::SignedObjectBuilder,
+        sigobj::{SignedObject, SignedObjectBuilder},
         x509::{Serial, Time, Validity},
         Cert, Crl, Manifest, Roa,
     },
@@ -43,7 +44,10 @@ use crate::{
         KrillResult,
     },
     constants::ID_CERTIFICATE_VALIDITY_YEARS,
-    daemon::config::{SignerConfig, SignerType},
+    daemon::{
+        ca::{RpkiSignedChecklist, RscBuilder},
+        config::{SignerConfig, SignerType},
+    },
 };
 
 #[cfg(feature = "hsm")]
@@ -55,6 +59,10 @@ use crate::commons::crypto::{
     SignerHandle,
 };
 
+/// The content type of Ghostbuster records: 1.2.840.113549.1.9.16.1.35
+const CT_RPKI_GHOSTBUSTERS: [u8; 11] =
+    [42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 35];
+
 /// High level signing interface between Krill and the [SignerRouter].
 ///
 /// KrillSigner:
@@ -365,6 +373,25 @@ impl KrillSigner {
             .map_err(crypto::Error::signing)
     }
 
+    /// Signs a Ghostbuster record (RFC 6493). The rpki crate has no
+    /// dedicated type for these, so we use the generic signed object with
+    /// the vCard as its content.
+    pub fn sign_ghostbusters(
+        &self,
+        vcard: Bytes,
+        object_builder: SignedObjectBuilder,
+        key_id: &KeyIdentifier,
+    ) -> CryptoResult<SignedObject> {
+        object_builder
+            .finalize(
+                Oid(Bytes::from_static(&CT_RPKI_GHOSTBUSTERS)),
+                vcard,
+                &self.router,
+                key_id,
+            )
+            .map_err(crypto::Error::signing)
+    }
+
     pub fn sign_rta(
         &self,
         rta_builder: &mut rta::RtaBuilder,
@@ -377,6 +404,17 @@ impl KrillSigner {
             .map_err(crypto::Error::signing)
     }
 
+    pub fn sign_rsc(
+        &self,
+        rsc_builder: RscBuilder,
+        ee: Cert,
+    ) -> CryptoResult<RpkiSignedChecklist> {
+        let key = ee.subject_key_identifier();
+        rsc_builder
+            .sign(&self.router, &key, ee)
+            .map_err(crypto::Error::signing)
+    }
+
     pub fn create_rfc6492_cms(
         &self,
         message: provisioning::Message,
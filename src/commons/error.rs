@@ -200,6 +200,7 @@ pub enum Error {
     HttpClientError(httpclient::Error),
     ConfigError(String),
     UpgradeError(UpgradeError),
+    HaStandby(String),
 
     //-----------------------------------------------------------------
     // General API Client Issues
@@ -218,6 +219,9 @@ pub enum Error {
     ApiAuthTransientError(String),
     ApiAuthSessionExpired(String),
     ApiInsufficientRights(String),
+    ApiTokenUnknown(String),
+    ApiTokenDuplicate(String),
+    ApiTokenInvalid(String),
 
     //-----------------------------------------------------------------
     // Repository Issues
@@ -238,6 +242,8 @@ pub enum Error {
     RepositoryServerNotInitialized,
     RepositoryServerHasPublishers,
     RepositoryServerAlreadyInitialized,
+    RepositoryMirrorInvalidPath(String),
+    RepositoryArchiveUnavailable(String),
 
     //-----------------------------------------------------------------
     // Publishing
@@ -259,6 +265,7 @@ pub enum Error {
     CaRepoIssue(CaHandle, String),
     CaRepoResponseInvalid(CaHandle, String),
     CaRepoResponseWrongXml(CaHandle),
+    CaRepoContentIssue(CaHandle, String, String),
 
     // CA Parent Issues
     CaParentDuplicateName(CaHandle, ParentHandle),
@@ -299,6 +306,7 @@ pub enum Error {
     CaAuthorizationInvalidMaxLength(CaHandle, RoaPayloadJsonMapKey),
     CaAuthorizationNotEntitled(CaHandle, RoaPayloadJsonMapKey),
     RoaDeltaError(CaHandle, RoaDeltaError),
+    CaRoaScheduledUnknown(CaHandle, u64),
 
     //-----------------------------------------------------------------
     // Autonomous System Provider Authorization - ASPA
@@ -310,6 +318,18 @@ pub enum Error {
     AspaProvidersDuplicates(CaHandle, CustomerAsn),
     AspaProvidersEmpty(CaHandle, CustomerAsn),
 
+    //-----------------------------------------------------------------
+    // Ghostbuster records
+    //-----------------------------------------------------------------
+    GhostbusterRecordUnknown(CaHandle, Option<ResourceClassName>),
+    GhostbusterRecordInvalid(CaHandle, String),
+
+    //-----------------------------------------------------------------
+    // Change Requests (routes and ASPAs needing approval)
+    //-----------------------------------------------------------------
+    CaChangeRequestUnknown(CaHandle, u64),
+    CaChangeRequestSelfApproval(CaHandle, u64),
+
     //-----------------------------------------------------------------
     // BGP Sec
     //-----------------------------------------------------------------
@@ -350,12 +370,20 @@ pub enum Error {
     TaProxyHasNoRequest,
     TaProxyHasRequest,
     TaProxyRequestNonceMismatch(ta::Nonce, ta::Nonce),
+    TaKeyRollInProgress,
+    TaKeyRollNotStaged,
 
     //-----------------------------------------------------------------
     // Resource Tagged Attestation issues
     //-----------------------------------------------------------------
     RtaResourcesNotHeld,
 
+    //-----------------------------------------------------------------
+    // RPKI Signed Checklist issues
+    //-----------------------------------------------------------------
+    RscResourcesNotHeld,
+    RscInvalid(String),
+
     //-----------------------------------------------------------------
     // If we really don't know any more..
     //-----------------------------------------------------------------
@@ -378,6 +406,7 @@ impl fmt::Display for Error {
             Error::HttpClientError(e) => write!(f, "HTTP client error: {}", e),
             Error::ConfigError(e) => write!(f, "Configuration error: {}", e),
             Error::UpgradeError(e) => write!(f, "Could not upgrade Krill: {}", e),
+            Error::HaStandby(leader) => write!(f, "This Krill node is a standby, changes can only be made on the leader: {}", leader),
 
             //-----------------------------------------------------------------
             // General API Client Issues
@@ -396,6 +425,9 @@ impl fmt::Display for Error {
             Error::ApiAuthTransientError(e) => write!(f, "Transient authentication error: {}", e),
             Error::ApiAuthSessionExpired(e) => write!(f, "Session expired: {}", e),
             Error::ApiInsufficientRights(e) => write!(f, "Insufficient rights: {}", e),
+            Error::ApiTokenUnknown(name) => write!(f, "Unknown API token '{}'", name),
+            Error::ApiTokenDuplicate(name) => write!(f, "Duplicate API token '{}'", name),
+            Error::ApiTokenInvalid(e) => write!(f, "Invalid API token request: {}", e),
 
             //-----------------------------------------------------------------
             // Repository Issues
@@ -417,6 +449,8 @@ impl fmt::Display for Error {
             Error::RepositoryServerNotInitialized => write!(f, "Publication Server not initialized, see 'krillc pubserver server init --help'"),
             Error::RepositoryServerHasPublishers => write!(f, "Publication Server cannot be removed, still has publishers"),
             Error::RepositoryServerAlreadyInitialized => write!(f, "Publication Server already initialized"),
+            Error::RepositoryMirrorInvalidPath(path) => write!(f, "Invalid path for mirrored repository file '{}'", path),
+            Error::RepositoryArchiveUnavailable(e) => write!(f, "Cannot reconstruct archived repository state: {}", e),
 
             //-----------------------------------------------------------------
             // RFC 8181 (publishing)
@@ -440,6 +474,7 @@ impl fmt::Display for Error {
             re-install of Krill you will need to send XML to all other parties again: parent(s), children, and repository", ca,        e),
             Error::CaRepoResponseInvalid(ca, e) => write!(f, "CA '{}' got invalid repository response: {}", ca, e),
             Error::CaRepoResponseWrongXml(ca) => write!(f, "CA '{}' got parent instead of repository response", ca),
+            Error::CaRepoContentIssue(ca, uri, e) => write!(f, "CA '{}' published content at '{}' is invalid: {}", ca, uri, e),
 
             // CA Parent Issues
             Error::CaParentDuplicateName(ca, parent) => write!(f, "CA '{}' already has a parent named '{}'", ca, parent),
@@ -489,6 +524,7 @@ impl fmt::Display for Error {
             Error::CaAuthorizationInvalidMaxLength(_ca, roa) => write!(f, "Invalid max length in ROA: '{}'", roa),
             Error::CaAuthorizationNotEntitled(_ca, roa) => write!(f, "Prefix in ROA '{}' not held by you", roa),
             Error::RoaDeltaError(_ca, e) => write!(f, "ROA delta rejected:\n\n'{}' ", e),
+            Error::CaRoaScheduledUnknown(_ca, id) => write!(f, "No pending scheduled ROA updates with id '{}'", id),
 
             //-----------------------------------------------------------------
             // Autonomous System Provider Authorization - ASPAs
@@ -500,6 +536,19 @@ impl fmt::Display for Error {
             Error::AspaProvidersDuplicates(_ca, asn) => write!(f, "ASPA for customer AS '{}' cannot have duplicate providers", asn),
             Error::AspaCustomerUnknown(_ca, asn) => write!(f, "No current ASPA exists for customer AS '{}'", asn),
 
+            //-----------------------------------------------------------------
+            // Ghostbuster records
+            //-----------------------------------------------------------------
+            Error::GhostbusterRecordUnknown(_ca, Some(rcn)) => write!(f, "No Ghostbuster record exists for resource class '{}'", rcn),
+            Error::GhostbusterRecordUnknown(_ca, None) => write!(f, "No Ghostbuster record exists for all resource classes"),
+            Error::GhostbusterRecordInvalid(_ca, msg) => write!(f, "Invalid vCard for Ghostbuster record: {}", msg),
+
+            //-----------------------------------------------------------------
+            // Change Requests
+            //-----------------------------------------------------------------
+            Error::CaChangeRequestUnknown(_ca, id) => write!(f, "No pending change request with id '{}'", id),
+            Error::CaChangeRequestSelfApproval(_ca, id) => write!(f, "Change request with id '{}' must be approved by a different user", id),
+
             //-----------------------------------------------------------------
             // BGPSec
             //-----------------------------------------------------------------
@@ -542,12 +591,20 @@ impl fmt::Display for Error {
             Error::TaProxyHasNoRequest => write!(f, "Trust Anchor Proxy has no signer request"),
             Error::TaProxyHasRequest => write!(f, "Trust Anchor Proxy already has signer request"),
             Error::TaProxyRequestNonceMismatch(rcvd, expected) => write!(f, "Trust Anchor Response nonce '{}' does not match open Request nonce '{}'", rcvd, expected),
+            Error::TaKeyRollInProgress => write!(f, "Trust Anchor already has a staged key for a key roll"),
+            Error::TaKeyRollNotStaged => write!(f, "Trust Anchor has no staged key to activate"),
 
             //-----------------------------------------------------------------
             // Resource Tagged Attestation issues
             //-----------------------------------------------------------------
             Error::RtaResourcesNotHeld => write!(f, "Your CA does not hold the requested resources"),
 
+            //-----------------------------------------------------------------
+            // RPKI Signed Checklist issues
+            //-----------------------------------------------------------------
+            Error::RscResourcesNotHeld => write!(f, "Your CA does not hold the requested resources under a single parent"),
+            Error::RscInvalid(msg) => write!(f, "Invalid RSC: {}", msg),
+
             //-----------------------------------------------------------------
             // If we really don't know any more..
             //-----------------------------------------------------------------
@@ -705,6 +762,7 @@ impl Error {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
             Error::PublisherUnknown(_)
+            | Error::ApiTokenUnknown(_)
             | Error::CaUnknown(_)
             | Error::CaChildUnknown(_, _)
             | Error::CaParentUnknown(_, _)
@@ -717,6 +775,8 @@ impl Error {
             | Error::ApiLoginError(_) => StatusCode::UNAUTHORIZED,
             Error::ApiInsufficientRights(_) => StatusCode::FORBIDDEN,
 
+            Error::HaStandby(_) => StatusCode::SERVICE_UNAVAILABLE,
+
             _ => StatusCode::BAD_REQUEST,
         }
     }
@@ -772,6 +832,11 @@ impl Error {
                 ErrorResponse::new("sys-upgrade", self).with_cause(e)
             }
 
+            // service unavailable
+            Error::HaStandby(leader) => {
+                ErrorResponse::new("sys-ha-standby", self).with_leader(leader)
+            }
+
             //-----------------------------------------------------------------
             // General API Client Issues (label: api-*)
             //-----------------------------------------------------------------
@@ -837,6 +902,20 @@ impl Error {
                     .with_cause(e)
             }
 
+            Error::ApiTokenUnknown(name) => {
+                ErrorResponse::new("api-token-unknown", self)
+                    .with_token_name(name)
+            }
+
+            Error::ApiTokenDuplicate(name) => {
+                ErrorResponse::new("api-token-duplicate", self)
+                    .with_token_name(name)
+            }
+
+            Error::ApiTokenInvalid(e) => {
+                ErrorResponse::new("api-token-invalid", self).with_cause(e)
+            }
+
             //-----------------------------------------------------------------
             // Repository Issues (label: repo-*)
             //-----------------------------------------------------------------
@@ -877,6 +956,14 @@ impl Error {
             Error::RepositoryServerAlreadyInitialized => {
                 ErrorResponse::new("pub-repo-initialized", self)
             }
+            Error::RepositoryMirrorInvalidPath(path) => {
+                ErrorResponse::new("pub-mirror-invalid-path", self)
+                    .with_path(path)
+            }
+            Error::RepositoryArchiveUnavailable(e) => {
+                ErrorResponse::new("pub-archive-unavailable", self)
+                    .with_cause(e)
+            }
 
             //-----------------------------------------------------------------
             // Publishing
@@ -932,6 +1019,13 @@ impl Error {
                     .with_ca(ca)
             }
 
+            Error::CaRepoContentIssue(ca, uri, err) => {
+                ErrorResponse::new("ca-repo-content-issue", self)
+                    .with_ca(ca)
+                    .with_uri(uri)
+                    .with_cause(err)
+            }
+
             Error::CaParentDuplicateName(ca, parent) => {
                 ErrorResponse::new("ca-parent-duplicate", self)
                     .with_ca(ca)
@@ -1058,6 +1152,11 @@ impl Error {
                     .with_ca(ca)
                     .with_roa_delta_error(roa_delta_error)
             }
+            Error::CaRoaScheduledUnknown(ca, id) => {
+                ErrorResponse::new("ca-roa-scheduled-unknown", self)
+                    .with_ca(ca)
+                    .with_scheduled_id(*id)
+            }
 
             //-----------------------------------------------------------------
             // Autonomous System Provider Authorization - ASPA
@@ -1093,6 +1192,38 @@ impl Error {
                     .with_asn(*asn)
             }
 
+            //-----------------------------------------------------------------
+            // Ghostbuster records
+            //-----------------------------------------------------------------
+            Error::GhostbusterRecordUnknown(ca, rcn) => {
+                let response =
+                    ErrorResponse::new("ca-ghostbuster-unknown", self)
+                        .with_ca(ca);
+                match rcn {
+                    Some(rcn) => response.with_resource_class(rcn),
+                    None => response,
+                }
+            }
+            Error::GhostbusterRecordInvalid(ca, msg) => {
+                ErrorResponse::new("ca-ghostbuster-invalid", self)
+                    .with_ca(ca)
+                    .with_cause(msg)
+            }
+
+            //-----------------------------------------------------------------
+            // Change Requests
+            //-----------------------------------------------------------------
+            Error::CaChangeRequestUnknown(ca, id) => {
+                ErrorResponse::new("ca-change-request-unknown", self)
+                    .with_ca(ca)
+                    .with_change_request_id(*id)
+            }
+            Error::CaChangeRequestSelfApproval(ca, id) => {
+                ErrorResponse::new("ca-change-request-self-approval", self)
+                    .with_ca(ca)
+                    .with_change_request_id(*id)
+            }
+
             //-----------------------------------------------------------------
             // BGP Sec
             //-----------------------------------------------------------------
@@ -1191,6 +1322,12 @@ impl Error {
             Error::TaProxyRequestNonceMismatch(_rcvd, _expected) => {
                 ErrorResponse::new("ta-proxy-response-nonce", self)
             }
+            Error::TaKeyRollInProgress => {
+                ErrorResponse::new("ta-key-roll-in-progress", self)
+            }
+            Error::TaKeyRollNotStaged => {
+                ErrorResponse::new("ta-key-roll-not-staged", self)
+            }
 
             //-----------------------------------------------------------------
             // Resource Tagged Attestation issues
@@ -1199,6 +1336,16 @@ impl Error {
                 ErrorResponse::new("rta-resources-not-held", self)
             }
 
+            //-----------------------------------------------------------------
+            // RPKI Signed Checklist issues
+            //-----------------------------------------------------------------
+            Error::RscResourcesNotHeld => {
+                ErrorResponse::new("rsc-resources-not-held", self)
+            }
+            Error::RscInvalid(msg) => {
+                ErrorResponse::new("rsc-invalid", self).with_cause(msg)
+            }
+
             //-----------------------------------------------------------------
             // If we really don't know any more..
             //-----------------------------------------------------------------
@@ -1225,6 +1372,11 @@ impl Error {
                 PublicationDeltaError::ObjectAlreadyPresent(_) => {
                     publication::ReportErrorCode::ObjectAlreadyPresent
                 }
+                PublicationDeltaError::ObjectTooLarge(_, _)
+                | PublicationDeltaError::QuotaExceeded(_)
+                | PublicationDeltaError::InvalidObject(_, _) => {
+                    publication::ReportErrorCode::OtherError
+                }
             },
             _ => publication::ReportErrorCode::OtherError,
         }
@@ -1324,6 +1476,10 @@ mod tests {
                 "https://example.com/",
             )),
         );
+        verify(
+            include_str!("../../test-resources/errors/sys-ha-standby.json"),
+            Error::HaStandby("krill-a".to_string()),
+        );
 
         //-----------------------------------------------------------------
         // General API Client Issues
@@ -1348,6 +1504,24 @@ mod tests {
             ),
             Error::ApiUnknownResource,
         );
+        verify(
+            include_str!(
+                "../../test-resources/errors/api-token-unknown.json"
+            ),
+            Error::ApiTokenUnknown("ci".to_string()),
+        );
+        verify(
+            include_str!(
+                "../../test-resources/errors/api-token-duplicate.json"
+            ),
+            Error::ApiTokenDuplicate("ci".to_string()),
+        );
+        verify(
+            include_str!(
+                "../../test-resources/errors/api-token-invalid.json"
+            ),
+            Error::ApiTokenInvalid("no permissions".to_string()),
+        );
 
         //-----------------------------------------------------------------
         // Repository Issues
@@ -1381,6 +1555,22 @@ mod tests {
                 "rsync://host/module/folder".to_string(),
             ),
         );
+        verify(
+            include_str!(
+                "../../test-resources/errors/pub-mirror-invalid-path.json"
+            ),
+            Error::RepositoryMirrorInvalidPath(
+                "rrdp/../../etc/passwd".to_string(),
+            ),
+        );
+        verify(
+            include_str!(
+                "../../test-resources/errors/pub-archive-unavailable.json"
+            ),
+            Error::RepositoryArchiveUnavailable(
+                "missing delta for serial 12".to_string(),
+            ),
+        );
 
         //-----------------------------------------------------------------
         // RFC 8181
@@ -1432,6 +1622,16 @@ mod tests {
             ),
             Error::CaRepoResponseWrongXml(ca.clone()),
         );
+        verify(
+            include_str!(
+                "../../test-resources/errors/ca-repo-content-issue.json"
+            ),
+            Error::CaRepoContentIssue(
+                ca.clone(),
+                "rsync://localhost/repo/ca/0/key.mft".to_string(),
+                "manifest is stale".to_string(),
+            ),
+        );
 
         verify(
             include_str!(
@@ -1504,6 +1704,12 @@ mod tests {
             include_str!("../../test-resources/errors/ca-roa-unknown.json"),
             Error::CaAuthorizationUnknown(ca.clone(), auth),
         );
+        verify(
+            include_str!(
+                "../../test-resources/errors/ca-roa-scheduled-unknown.json"
+            ),
+            Error::CaRoaScheduledUnknown(ca.clone(), 12),
+        );
         verify(
             include_str!("../../test-resources/errors/ca-roa-duplicate.json"),
             Error::CaAuthorizationDuplicate(ca.clone(), auth),
@@ -1519,7 +1725,39 @@ mod tests {
             include_str!(
                 "../../test-resources/errors/ca-roa-not-entitled.json"
             ),
-            Error::CaAuthorizationNotEntitled(ca, auth),
+            Error::CaAuthorizationNotEntitled(ca.clone(), auth),
+        );
+
+        verify(
+            include_str!(
+                "../../test-resources/errors/ca-change-request-unknown.json"
+            ),
+            Error::CaChangeRequestUnknown(ca.clone(), 12),
+        );
+        verify(
+            include_str!(
+                "../../test-resources/errors/ca-change-request-self-approval.json"
+            ),
+            Error::CaChangeRequestSelfApproval(ca.clone(), 12),
+        );
+
+        verify(
+            include_str!(
+                "../../test-resources/errors/ca-ghostbuster-unknown.json"
+            ),
+            Error::GhostbusterRecordUnknown(
+                ca.clone(),
+                Some(ResourceClassName::from("RC0")),
+            ),
+        );
+        verify(
+            include_str!(
+                "../../test-resources/errors/ca-ghostbuster-invalid.json"
+            ),
+            Error::GhostbusterRecordInvalid(
+                ca,
+                "must contain exactly one FN property".to_string(),
+            ),
         );
 
         verify(
@@ -1574,6 +1812,17 @@ mod tests {
             Error::TaAlreadyInitialized,
         );
 
+        verify(
+            include_str!(
+                "../../test-resources/errors/rsc-resources-not-held.json"
+            ),
+            Error::RscResourcesNotHeld,
+        );
+        verify(
+            include_str!("../../test-resources/errors/rsc-invalid.json"),
+            Error::RscInvalid("message digest mismatch".to_string()),
+        );
+
         verify(
             include_str!("../../test-resources/errors/general-error.json"),
             Error::custom("some unlikely corner case"),
@@ -3,11 +3,13 @@ use std::{fmt, str::FromStr};
 pub use kvx::{
     namespace, segment, Key, Namespace, Scope, Segment, SegmentBuf,
 };
-use kvx::{KeyValueStoreBackend, NamespaceBuf, ReadStore, WriteStore};
+use kvx::{KeyValueStoreBackend, NamespaceBuf, PubKeyValueStoreBackend};
 use serde::{de::DeserializeOwned, Serialize};
 use url::Url;
 
 use crate::commons::error::KrillIoError;
+#[cfg(feature = "postgres")]
+use crate::commons::eventsourcing::pgstore::PostgresStore;
 
 pub trait SegmentExt {
     fn parse_lossy(value: &str) -> SegmentBuf;
@@ -46,19 +48,44 @@ impl SegmentExt for Segment {
 
 #[derive(Debug)]
 pub struct KeyValueStore {
-    inner: kvx::KeyValueStore,
+    inner: Box<dyn PubKeyValueStoreBackend>,
 }
 
 // # Construct and high level functions.
 impl KeyValueStore {
     /// Creates a new KeyValueStore.
+    ///
+    /// PostgreSQL databases are handled by our own backend, which shares
+    /// its connections between namespaces. All other storage types are
+    /// handled by kvx.
     pub fn create(
         storage_uri: &Url,
         namespace: &Namespace,
     ) -> Result<Self, KeyValueError> {
-        kvx::KeyValueStore::new(storage_uri, namespace)
-            .map(|inner| KeyValueStore { inner })
-            .map_err(KeyValueError::Inner)
+        Self::create_with_namespace(storage_uri, namespace.into())
+    }
+
+    fn create_with_namespace(
+        storage_uri: &Url,
+        namespace: NamespaceBuf,
+    ) -> Result<Self, KeyValueError> {
+        #[cfg(feature = "postgres")]
+        if Self::is_postgres(storage_uri) {
+            let inner = PostgresStore::new(storage_uri, namespace)?;
+            return Ok(KeyValueStore {
+                inner: Box::new(inner),
+            });
+        }
+
+        let inner = kvx::KeyValueStore::new(storage_uri, namespace)?;
+        Ok(KeyValueStore {
+            inner: Box::new(inner),
+        })
+    }
+
+    /// Returns whether the storage URI refers to a PostgreSQL database.
+    pub fn is_postgres(storage_uri: &Url) -> bool {
+        storage_uri.scheme() == "postgres"
     }
 
     /// Returns true if this KeyValueStore (with this namespace) has any
@@ -92,12 +119,17 @@ impl KeyValueStore {
     pub fn execute<F, T>(
         &self,
         scope: &Scope,
-        op: F,
+        mut op: F,
     ) -> Result<T, KeyValueError>
     where
         F: FnMut(&dyn KeyValueStoreBackend) -> Result<T, kvx::Error>,
     {
-        self.inner.execute(scope, op).map_err(KeyValueError::Inner)
+        let mut res = None;
+        self.inner.transaction(scope, &mut |kv| {
+            res = Some(op(kv)?);
+            Ok(())
+        })?;
+        res.ok_or(KeyValueError::Inner(kvx::Error::Unknown))
     }
 }
 
@@ -210,10 +242,7 @@ impl KeyValueStore {
         namespace: &Namespace,
     ) -> Result<Self, KeyValueError> {
         let namespace = Self::prefixed_namespace(namespace, "upgrade")?;
-
-        kvx::KeyValueStore::new(storage_uri, namespace)
-            .map(|inner| KeyValueStore { inner })
-            .map_err(KeyValueError::Inner)
+        Self::create_with_namespace(storage_uri, namespace)
     }
 
     fn prefixed_namespace(
@@ -24,6 +24,18 @@ pub use self::kv::{
     Segment, SegmentBuf, SegmentExt,
 };
 
+#[cfg(feature = "postgres")]
+mod pgstore;
+#[cfg(feature = "postgres")]
+pub use self::pgstore::blocking;
+
+/// Runs a store operation. Only the PostgreSQL backend blocks, so without
+/// it the operation is simply run.
+#[cfg(not(feature = "postgres"))]
+pub fn blocking<T>(op: impl FnOnce() -> T) -> T {
+    op()
+}
+
 //------------ Tests ---------------------------------------------------------
 
 #[cfg(test)]
@@ -0,0 +1,618 @@
+//! PostgreSQL backend for the KeyValueStore.
+//!
+//! All namespaces are kept in a single 'store' table, which is created when
+//! it does not yet exist. Stores for the same database share a connection
+//! pool, so the number of connections used by Krill does not depend on the
+//! number of namespaces it uses.
+//!
+//! The synchronous postgres client blocks on a runtime of its own. This is
+//! not allowed inside a tokio runtime, so all calls to the client are run
+//! using [`blocking`].
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt, panic,
+    sync::{Mutex, OnceLock},
+    thread,
+};
+
+use kvx::{
+    Key, KeyValueStoreBackend, NamespaceBuf, ReadStore, Scope, SegmentBuf,
+    WriteStore,
+};
+use postgres::{error::SqlState, types::ToSql, NoTls, Row, Transaction};
+use r2d2_postgres::{r2d2::Pool, PostgresConnectionManager};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use url::Url;
+
+type PgPool = Pool<PostgresConnectionManager<NoTls>>;
+
+type PgResult<T> = Result<T, kvx::Error>;
+
+/// The maximum number of connections in the pool for a database.
+const POOL_MAX_SIZE: u32 = 10;
+
+/// The number of times a transaction is retried if it was aborted because
+/// of a deadlock.
+const TRANSACTION_RETRIES: usize = 10;
+
+const CREATE_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS store (
+    namespace text NOT NULL,
+    scope text[] NOT NULL,
+    key text NOT NULL,
+    value jsonb NOT NULL,
+    PRIMARY KEY (namespace, scope, key)
+)";
+
+/// Runs a blocking database operation.
+///
+/// On the multi-threaded runtime, as used by the Krill binaries, we use
+/// `block_in_place`. This is not available on the current thread runtime,
+/// e.g. in tests, so there the operation is run on a separate thread which
+/// is not part of the runtime.
+///
+/// This is also used for the task queue, which uses the postgres backend
+/// from kvx directly.
+pub fn blocking<T: Send>(op: impl FnOnce() -> T + Send) -> T {
+    match Handle::try_current() {
+        Ok(handle)
+            if handle.runtime_flavor() == RuntimeFlavor::MultiThread =>
+        {
+            tokio::task::block_in_place(op)
+        }
+        Ok(_) => thread::scope(|scope| {
+            scope
+                .spawn(op)
+                .join()
+                .unwrap_or_else(|e| panic::resume_unwind(e))
+        }),
+        Err(_) => op(),
+    }
+}
+
+/// Returns the pool for the database in the URI, connecting to it and
+/// creating the schema if needed.
+fn pool(storage_uri: &Url) -> PgResult<PgPool> {
+    static POOLS: OnceLock<Mutex<HashMap<Url, PgPool>>> = OnceLock::new();
+
+    let mut pools = POOLS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .map_err(|e| kvx::Error::MutexLock(e.to_string()))?;
+
+    if let Some(pool) = pools.get(storage_uri) {
+        return Ok(pool.clone());
+    }
+
+    let manager =
+        PostgresConnectionManager::new(storage_uri.as_str().parse()?, NoTls);
+    let pool = Pool::builder()
+        .max_size(POOL_MAX_SIZE)
+        .min_idle(Some(1))
+        .build(manager)?;
+    pool.get()?.batch_execute(CREATE_SCHEMA)?;
+
+    pools.insert(storage_uri.clone(), pool.clone());
+    Ok(pool)
+}
+
+//------------ PostgresStore -------------------------------------------------
+
+/// A key value store for a namespace in a PostgreSQL database.
+pub struct PostgresStore {
+    namespace: NamespaceBuf,
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    pub fn new(storage_uri: &Url, namespace: NamespaceBuf) -> PgResult<Self> {
+        blocking(|| pool(storage_uri))
+            .map(|pool| PostgresStore { namespace, pool })
+    }
+
+    /// Runs the operation in a new transaction, and returns its result.
+    fn with_transaction<T>(
+        &self,
+        scope: &Scope,
+        mut op: impl FnMut(&dyn KeyValueStoreBackend) -> PgResult<T>,
+    ) -> PgResult<T> {
+        let mut res = None;
+        self.transaction(scope, &mut |kv| {
+            res = Some(op(kv)?);
+            Ok(())
+        })?;
+        res.ok_or(kvx::Error::Unknown)
+    }
+
+    fn run_transaction(
+        &self,
+        lock: &str,
+        callback: &mut dyn FnMut(&dyn KeyValueStoreBackend) -> PgResult<()>,
+    ) -> PgResult<()> {
+        for attempt in 0..=TRANSACTION_RETRIES {
+            let mut client = blocking(|| self.pool.get())?;
+            let transaction = blocking(|| client.transaction())?;
+
+            let store = PostgresTransaction {
+                namespace: &self.namespace,
+                transaction: RefCell::new(transaction),
+            };
+
+            let res = store
+                .execute(
+                    "SELECT pg_advisory_xact_lock(hashtextextended($1, 0))",
+                    &[&lock],
+                )
+                .and_then(|_| callback(&store));
+
+            // Both commit and rollback, when the transaction is dropped,
+            // block on the client.
+            let transaction = store.transaction.into_inner();
+            let res = match res {
+                Ok(()) => blocking(|| Ok(transaction.commit()?)),
+                Err(e) => {
+                    blocking(|| drop(transaction));
+                    Err(e)
+                }
+            };
+
+            match res {
+                Err(e)
+                    if attempt < TRANSACTION_RETRIES
+                        && Self::is_deadlock(&e) =>
+                {
+                    debug!("Retrying transaction for {}: {}", lock, e);
+                }
+                res => return res,
+            }
+        }
+
+        Ok(()) // not reached, the last attempt always returns
+    }
+
+    fn move_namespace(&self, to: &NamespaceBuf) -> PgResult<()> {
+        let mut client = self.pool.get()?;
+        let mut transaction = client.transaction()?;
+
+        if transaction
+            .query_opt(
+                "SELECT 1 FROM store WHERE namespace = $1 LIMIT 1",
+                &[to],
+            )?
+            .is_some()
+        {
+            return Err(kvx::Error::NamespaceMigration(format!(
+                "target namespace {} already exists in database",
+                to
+            )));
+        }
+
+        transaction.execute(
+            "UPDATE store SET namespace = $2 WHERE namespace = $1",
+            &[&self.namespace, to],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn is_deadlock(e: &kvx::Error) -> bool {
+        match e {
+            kvx::Error::Postgres(e) => {
+                e.code() == Some(&SqlState::T_R_DEADLOCK_DETECTED)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl KeyValueStoreBackend for PostgresStore {
+    /// Runs the callback in a transaction. Transactions for the same scope
+    /// are executed one at a time, using an advisory lock, like the scope
+    /// locks used for disk based stores.
+    ///
+    /// Note that we do not use serializable transactions. Krill may open a
+    /// transaction for another namespace, e.g. for keys, while one is in
+    /// progress and these would always conflict with each other.
+    fn transaction(
+        &self,
+        scope: &Scope,
+        callback: &mut dyn FnMut(&dyn KeyValueStoreBackend) -> PgResult<()>,
+    ) -> PgResult<()> {
+        let lock = format!("{}/{}", self.namespace, scope);
+        self.run_transaction(&lock, callback)
+    }
+}
+
+impl ReadStore for PostgresStore {
+    fn is_empty(&self) -> PgResult<bool> {
+        self.with_transaction(&Scope::global(), |kv| kv.is_empty())
+    }
+
+    fn has(&self, key: &Key) -> PgResult<bool> {
+        self.with_transaction(key.scope(), |kv| kv.has(key))
+    }
+
+    fn has_scope(&self, scope: &Scope) -> PgResult<bool> {
+        self.with_transaction(scope, |kv| kv.has_scope(scope))
+    }
+
+    fn get(&self, key: &Key) -> PgResult<Option<serde_json::Value>> {
+        self.with_transaction(key.scope(), |kv| kv.get(key))
+    }
+
+    fn list_keys(&self, scope: &Scope) -> PgResult<Vec<Key>> {
+        self.with_transaction(scope, |kv| kv.list_keys(scope))
+    }
+
+    fn list_scopes(&self) -> PgResult<Vec<Scope>> {
+        self.with_transaction(&Scope::global(), |kv| kv.list_scopes())
+    }
+}
+
+impl WriteStore for PostgresStore {
+    fn store(&self, key: &Key, value: serde_json::Value) -> PgResult<()> {
+        self.with_transaction(key.scope(), |kv| kv.store(key, value.clone()))
+    }
+
+    fn move_value(&self, from: &Key, to: &Key) -> PgResult<()> {
+        self.with_transaction(from.scope(), |kv| kv.move_value(from, to))
+    }
+
+    fn move_scope(&self, from: &Scope, to: &Scope) -> PgResult<()> {
+        self.with_transaction(from, |kv| kv.move_scope(from, to))
+    }
+
+    fn delete(&self, key: &Key) -> PgResult<()> {
+        self.with_transaction(key.scope(), |kv| kv.delete(key))
+    }
+
+    fn delete_scope(&self, scope: &Scope) -> PgResult<()> {
+        self.with_transaction(scope, |kv| kv.delete_scope(scope))
+    }
+
+    fn clear(&self) -> PgResult<()> {
+        self.with_transaction(&Scope::global(), |kv| kv.clear())
+    }
+
+    /// Moves all entries to the given namespace. Fails if there already are
+    /// entries in that namespace.
+    fn migrate_namespace(&mut self, to: NamespaceBuf) -> PgResult<()> {
+        blocking(|| self.move_namespace(&to))?;
+        self.namespace = to;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PostgresStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PostgresStore({})", self.namespace)
+    }
+}
+
+impl fmt::Display for PostgresStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "KeyValueStore::Postgres({})", self.namespace)
+    }
+}
+
+//------------ PostgresTransaction -------------------------------------------
+
+/// The view on a namespace used inside a transaction.
+struct PostgresTransaction<'a> {
+    namespace: &'a NamespaceBuf,
+    transaction: RefCell<Transaction<'a>>,
+}
+
+impl PostgresTransaction<'_> {
+    fn query(
+        &self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> PgResult<Vec<Row>> {
+        let transaction = &mut *self.transaction.borrow_mut();
+        Ok(blocking(|| transaction.query(query, params))?)
+    }
+
+    fn query_opt(
+        &self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> PgResult<Option<Row>> {
+        let transaction = &mut *self.transaction.borrow_mut();
+        Ok(blocking(|| transaction.query_opt(query, params))?)
+    }
+
+    fn execute(
+        &self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> PgResult<u64> {
+        let transaction = &mut *self.transaction.borrow_mut();
+        Ok(blocking(|| transaction.execute(query, params))?)
+    }
+}
+
+impl KeyValueStoreBackend for PostgresTransaction<'_> {
+    /// Nested transactions are part of the current transaction.
+    fn transaction(
+        &self,
+        _scope: &Scope,
+        callback: &mut dyn FnMut(&dyn KeyValueStoreBackend) -> PgResult<()>,
+    ) -> PgResult<()> {
+        callback(self)
+    }
+}
+
+impl ReadStore for PostgresTransaction<'_> {
+    fn is_empty(&self) -> PgResult<bool> {
+        Ok(self
+            .query_opt(
+                "SELECT 1 FROM store WHERE namespace = $1 LIMIT 1",
+                &[self.namespace],
+            )?
+            .is_none())
+    }
+
+    fn has(&self, key: &Key) -> PgResult<bool> {
+        Ok(self
+            .query_opt(
+                "SELECT 1 FROM store WHERE namespace = $1 AND scope = $2 AND key = $3",
+                &[self.namespace, key.scope().as_vec(), &key.name()],
+            )?
+            .is_some())
+    }
+
+    fn has_scope(&self, scope: &Scope) -> PgResult<bool> {
+        Ok(self
+            .query_opt(
+                "SELECT 1 FROM store WHERE namespace = $1 AND scope[:$3] = $2 LIMIT 1",
+                &[self.namespace, scope.as_vec(), &scope.len()],
+            )?
+            .is_some())
+    }
+
+    fn get(&self, key: &Key) -> PgResult<Option<serde_json::Value>> {
+        Ok(self
+            .query_opt(
+                "SELECT value FROM store WHERE namespace = $1 AND scope = $2 AND key = $3",
+                &[self.namespace, key.scope().as_vec(), &key.name()],
+            )?
+            .map(|row| row.get(0)))
+    }
+
+    fn list_keys(&self, scope: &Scope) -> PgResult<Vec<Key>> {
+        Ok(self
+            .query(
+                "SELECT scope, key FROM store WHERE namespace = $1 AND scope[:$3] = $2",
+                &[self.namespace, scope.as_vec(), &scope.len()],
+            )?
+            .into_iter()
+            .map(|row| {
+                let name: SegmentBuf = row.get(1);
+                Key::new_scoped(Scope::new(row.get(0)), name)
+            })
+            .collect())
+    }
+
+    fn list_scopes(&self) -> PgResult<Vec<Scope>> {
+        let mut scopes: Vec<Scope> = self
+            .query(
+                "SELECT DISTINCT scope FROM store WHERE namespace = $1",
+                &[self.namespace],
+            )?
+            .into_iter()
+            .flat_map(|row| Scope::new(row.get(0)).sub_scopes())
+            .collect();
+        scopes.sort();
+        scopes.dedup();
+        Ok(scopes)
+    }
+}
+
+impl WriteStore for PostgresTransaction<'_> {
+    fn store(&self, key: &Key, value: serde_json::Value) -> PgResult<()> {
+        self.execute(
+            "INSERT INTO store (namespace, scope, key, value) VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (namespace, scope, key) DO UPDATE SET value = $4",
+            &[self.namespace, key.scope().as_vec(), &key.name(), &value],
+        )?;
+        Ok(())
+    }
+
+    fn move_value(&self, from: &Key, to: &Key) -> PgResult<()> {
+        let moved = self.execute(
+            "UPDATE store SET scope = $4, key = $5 WHERE namespace = $1 AND scope = $2 AND key = $3",
+            &[
+                self.namespace,
+                from.scope().as_vec(),
+                &from.name(),
+                to.scope().as_vec(),
+                &to.name(),
+            ],
+        )?;
+        if moved == 0 {
+            Err(kvx::Error::UnknownKey)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Moves all values in the scope, including those in sub-scopes.
+    fn move_scope(&self, from: &Scope, to: &Scope) -> PgResult<()> {
+        self.execute(
+            "UPDATE store SET scope = $3 || scope[$4 + 1:] \
+             WHERE namespace = $1 AND scope[:$4] = $2",
+            &[self.namespace, from.as_vec(), to.as_vec(), &from.len()],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &Key) -> PgResult<()> {
+        self.execute(
+            "DELETE FROM store WHERE namespace = $1 AND scope = $2 AND key = $3",
+            &[self.namespace, key.scope().as_vec(), &key.name()],
+        )?;
+        Ok(())
+    }
+
+    /// Deletes all values in the scope, including those in sub-scopes.
+    fn delete_scope(&self, scope: &Scope) -> PgResult<()> {
+        self.execute(
+            "DELETE FROM store WHERE namespace = $1 AND scope[:$3] = $2",
+            &[self.namespace, scope.as_vec(), &scope.len()],
+        )?;
+        Ok(())
+    }
+
+    fn clear(&self) -> PgResult<()> {
+        self.execute(
+            "DELETE FROM store WHERE namespace = $1",
+            &[self.namespace],
+        )?;
+        Ok(())
+    }
+
+    fn migrate_namespace(&mut self, _to: NamespaceBuf) -> PgResult<()> {
+        Err(kvx::Error::NamespaceMigration(
+            "cannot migrate a namespace inside a transaction".to_string(),
+        ))
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+/// These tests need a PostgreSQL database, which is set using the
+/// KRILL_KV_STORAGE_URL environment variable. They are skipped if that is
+/// not a `postgres://` URI.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, sync::Arc};
+
+    use rand::{distributions::Alphanumeric, Rng};
+
+    use crate::commons::eventsourcing::KeyValueStore;
+
+    fn test_store() -> Option<PostgresStore> {
+        let storage_uri = env::var("KRILL_KV_STORAGE_URL")
+            .ok()
+            .and_then(|s| Url::parse(&s).ok())
+            .filter(KeyValueStore::is_postgres)?;
+
+        let namespace = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(8)
+            .map(char::from)
+            .collect::<String>()
+            .parse()
+            .unwrap();
+
+        Some(PostgresStore::new(&storage_uri, namespace).unwrap())
+    }
+
+    fn key(scope: &str, name: &str) -> Key {
+        Key::new_scoped(
+            Scope::from_segment(scope.parse::<SegmentBuf>().unwrap()),
+            name.parse::<SegmentBuf>().unwrap(),
+        )
+    }
+
+    #[test]
+    fn append_and_read() {
+        let store = match test_store() {
+            Some(store) => store,
+            None => return,
+        };
+        assert!(store.is_empty().unwrap());
+
+        // Values are appended to a scope, the way events are
+        for version in 0..3 {
+            let key = key("ca", &format!("delta-{}.json", version));
+            store.store(&key, serde_json::json!(version)).unwrap();
+        }
+
+        let scope = key("ca", "x").scope().clone();
+        assert!(!store.is_empty().unwrap());
+        assert!(store.has_scope(&scope).unwrap());
+        assert_eq!(store.list_keys(&scope).unwrap().len(), 3);
+        assert_eq!(store.list_scopes().unwrap(), vec![scope]);
+
+        let key = key("ca", "delta-1.json");
+        assert!(store.has(&key).unwrap());
+        assert_eq!(store.get(&key).unwrap(), Some(serde_json::json!(1)));
+
+        // Replacing a value does not add a key
+        store.store(&key, serde_json::json!("one")).unwrap();
+        assert_eq!(store.get(&key).unwrap(), Some(serde_json::json!("one")));
+        assert_eq!(store.list_keys(key.scope()).unwrap().len(), 3);
+
+        store.clear().unwrap();
+        assert!(store.is_empty().unwrap());
+    }
+
+    #[test]
+    fn failed_transaction_is_rolled_back() {
+        let store = match test_store() {
+            Some(store) => store,
+            None => return,
+        };
+        let key = key("ca", "delta-0.json");
+
+        let res = store.transaction(key.scope(), &mut |kv| {
+            kv.store(&key, serde_json::json!(0))?;
+            Err(kvx::Error::Unknown)
+        });
+        assert!(res.is_err());
+        assert!(!store.has(&key).unwrap());
+    }
+
+    #[test]
+    fn transactions_for_a_scope_are_locked() {
+        let store = match test_store() {
+            Some(store) => store,
+            None => return,
+        };
+        let store = Arc::new(store);
+        let counter = key("ca", "counter.json");
+        store.store(&counter, serde_json::json!(0)).unwrap();
+
+        // Without the lock, concurrent increments would get lost.
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let store = store.clone();
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..10 {
+                        store
+                            .transaction(counter.scope(), &mut |kv| {
+                                let value = kv.get(&counter)?.unwrap();
+                                let value = value.as_u64().unwrap() + 1;
+                                kv.store(&counter, serde_json::json!(value))
+                            })
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(store.get(&counter).unwrap(), Some(serde_json::json!(40)));
+        store.clear().unwrap();
+    }
+
+    #[tokio::test]
+    async fn current_thread_runtime() {
+        let store = match test_store() {
+            Some(store) => store,
+            None => return,
+        };
+        let key = key("ca", "delta-0.json");
+
+        store.store(&key, serde_json::json!(0)).unwrap();
+        assert_eq!(store.get(&key).unwrap(), Some(serde_json::json!(0)));
+        store.clear().unwrap();
+    }
+}
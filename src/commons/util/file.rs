@@ -9,6 +9,7 @@ use std::{
 };
 
 use bytes::Bytes;
+use libflate::gzip::Encoder;
 use serde::{de::DeserializeOwned, Serialize};
 
 use rpki::{
@@ -150,6 +151,46 @@ pub fn save_with_rsync_uri(
     save(content, &path)
 }
 
+/// Saves content for rsync URIs in a gzipped tar file, using the same
+/// relative paths as `save_with_rsync_uri`. All entries get the given
+/// modification time.
+pub fn save_tar_gz_with_rsync_uris(
+    files: &[(uri::Rsync, Bytes)],
+    mtime: i64,
+    full_path: &Path,
+) -> Result<(), KrillIoError> {
+    let cannot_write = |e| {
+        KrillIoError::new(
+            format!("Could not write to: {}", full_path.to_string_lossy()),
+            e,
+        )
+    };
+
+    let f = create_file_with_path(full_path)?;
+    let encoder = Encoder::new(f).map_err(cannot_write)?;
+    let mut builder = tar::Builder::new(encoder);
+
+    for (uri, content) in files {
+        let name = path_with_rsync(Path::new(""), uri);
+
+        let mut header = tar::Header::new_ustar();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_mode(0o644);
+        header.set_size(content.len() as u64);
+        header.set_mtime(mtime.max(0) as u64);
+
+        builder
+            .append_data(&mut header, name, content.as_ref())
+            .map_err(cannot_write)?;
+    }
+
+    let encoder = builder.into_inner().map_err(cannot_write)?;
+    encoder.finish().into_result().map_err(cannot_write)?;
+
+    trace!("Saved file: {}", full_path.to_string_lossy());
+    Ok(())
+}
+
 /// Reads a file to Bytes
 pub fn read(path: &Path) -> Result<Bytes, KrillIoError> {
     let mut f = File::open(path).map_err(|e| {
@@ -204,6 +245,55 @@ pub fn delete_file(full_path: &Path) -> Result<(), KrillIoError> {
     })
 }
 
+/// Removes the file and any **empty** directories on the path after removing
+/// it, up to but excluding the base path.
+pub fn clean_file_and_path_in(
+    base_path: &Path,
+    path: &Path,
+) -> Result<(), KrillIoError> {
+    if path.exists() {
+        delete_file(path)?;
+
+        let mut parent_opt = path.parent();
+
+        while let Some(parent) = parent_opt {
+            if !parent.starts_with(base_path) || parent == base_path {
+                break;
+            }
+            let is_empty = parent
+                .read_dir()
+                .map_err(|e| {
+                    KrillIoError::new(
+                        format!(
+                            "Could not read directory: '{}'",
+                            parent.to_string_lossy()
+                        ),
+                        e,
+                    )
+                })?
+                .next()
+                .is_none();
+
+            if !is_empty {
+                break;
+            }
+
+            fs::remove_dir(parent).map_err(|e| {
+                KrillIoError::new(
+                    format!(
+                        "Could not remove dir: {}",
+                        parent.to_string_lossy()
+                    ),
+                    e,
+                )
+            })?;
+
+            parent_opt = parent.parent();
+        }
+    }
+    Ok(())
+}
+
 /// Removes the file and any **empty** directories on the path after removing
 /// it.
 pub fn clean_file_and_path(path: &Path) -> Result<(), KrillIoError> {
@@ -600,4 +690,58 @@ mod tests {
             assert!(files.contains(&file_4));
         });
     }
+
+    #[test]
+    fn should_save_tar_gz() {
+        test::test_under_tmp(|base_dir| {
+            let long_path =
+                format!("rsync://host/module/{}/file.txt", "a".repeat(90));
+            let files = vec![
+                (
+                    test::rsync("rsync://host/module/alice/file1.txt"),
+                    Bytes::from("content 1"),
+                ),
+                (test::rsync(&long_path), Bytes::from(vec![1; 600])),
+            ];
+
+            let path = base_dir.join("repo.tar.gz");
+            save_tar_gz_with_rsync_uris(&files, 1_600_000_000, &path)
+                .unwrap();
+
+            let bytes = read(&path).unwrap();
+            let decoder =
+                libflate::gzip::Decoder::new(bytes.as_ref()).unwrap();
+            let mut archive = tar::Archive::new(decoder);
+
+            let entries: Vec<_> = archive
+                .entries()
+                .unwrap()
+                .map(|entry| {
+                    let mut entry = entry.unwrap();
+                    let header = entry.header();
+                    assert_eq!(header.mtime().unwrap(), 1_600_000_000);
+                    assert_eq!(header.mode().unwrap(), 0o644);
+
+                    let name = entry.path().unwrap().to_path_buf();
+                    let mut content = vec![];
+                    entry.read_to_end(&mut content).unwrap();
+                    (name, content)
+                })
+                .collect();
+
+            assert_eq!(
+                entries,
+                vec![
+                    (
+                        PathBuf::from("host/module/alice/file1.txt"),
+                        b"content 1".to_vec()
+                    ),
+                    (
+                        PathBuf::from(&long_path["rsync://".len()..]),
+                        vec![1; 600]
+                    ),
+                ]
+            );
+        })
+    }
 }
@@ -303,6 +303,83 @@ pub async fn post_binary_with_full_ua(
     }
 }
 
+/// Posts a JSON body with additional headers, e.g. a signature, and
+/// expects any successful (2xx) status. The response body is ignored.
+/// Intended for notifying webhooks.
+pub async fn post_json_body_with_headers(
+    uri: &str,
+    body: String,
+    extra_headers: HeaderMap,
+) -> Result<(), Error> {
+    let mut headers = headers(uri, Some(JSON_CONTENT), None)?;
+    headers.extend(extra_headers);
+
+    let res = client(uri)?
+        .post(uri)
+        .headers(headers)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| Error::execute(uri, e))?;
+
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(Error::from_res(uri, res).await)
+    }
+}
+
+/// Puts binary data with additional headers, e.g. for authentication, and
+/// expects any successful (2xx) status. The response body is ignored.
+/// Intended for mirroring repository files.
+pub async fn put_binary_with_headers(
+    uri: &str,
+    data: Bytes,
+    token: Option<&Token>,
+    extra_headers: HeaderMap,
+) -> Result<(), Error> {
+    let mut headers = headers(uri, Some("application/octet-stream"), token)?;
+    headers.extend(extra_headers);
+
+    let res = client(uri)?
+        .put(uri)
+        .headers(headers)
+        .body(data)
+        .send()
+        .await
+        .map_err(|e| Error::execute(uri, e))?;
+
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(Error::from_res(uri, res).await)
+    }
+}
+
+/// Sends a delete request with additional headers, and expects any
+/// successful (2xx) status. Intended for mirroring repository files.
+pub async fn delete_with_headers(
+    uri: &str,
+    token: Option<&Token>,
+    extra_headers: HeaderMap,
+) -> Result<(), Error> {
+    let mut headers = headers(uri, None, token)?;
+    headers.extend(extra_headers);
+
+    let res = client(uri)?
+        .delete(uri)
+        .headers(headers)
+        .send()
+        .await
+        .map_err(|e| Error::execute(uri, e))?;
+
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(Error::from_res(uri, res).await)
+    }
+}
+
 /// Sends a delete request to the specified url.
 pub async fn delete(uri: &str, token: Option<&Token>) -> Result<(), Error> {
     report_delete(uri, None, token);
@@ -444,16 +521,18 @@ async fn opt_text_response(
     res: Response,
 ) -> Result<Option<String>, Error> {
     match res.status() {
-        StatusCode::OK => match res.text().await.ok() {
-            None => Ok(None),
-            Some(s) => {
-                if s.is_empty() {
-                    Ok(None)
-                } else {
-                    Ok(Some(s))
+        StatusCode::OK | StatusCode::ACCEPTED => {
+            match res.text().await.ok() {
+                None => Ok(None),
+                Some(s) => {
+                    if s.is_empty() {
+                        Ok(None)
+                    } else {
+                        Ok(Some(s))
+                    }
                 }
             }
-        },
+        }
         StatusCode::FORBIDDEN => Err(Error::Forbidden(uri.to_string())),
         _ => Err(Error::from_res(uri, res).await),
     }
@@ -47,11 +47,14 @@ pub fn test_announcements_enabled() -> bool {
 // to be able to expand the segment macro at compile time, while running the
 // expanded code, which actually makes it a Segment, at runtime
 pub const TASK_QUEUE_NS: &Namespace = namespace!("tasks");
+pub const API_TOKENS_NS: &Namespace = namespace!("api_tokens");
 pub const CASERVER_NS: &Namespace = namespace!("cas");
 pub const CA_OBJECTS_NS: &Namespace = namespace!("ca_objects");
 pub const KEYS_NS: &Namespace = namespace!("keys");
 pub const PUBSERVER_CONTENT_NS: &Namespace = namespace!("pubd_objects");
 pub const PUBSERVER_NS: &Namespace = namespace!("pubd");
+pub const REPOSITORY_MIRRORS_NS: &Namespace =
+    namespace!("repository_mirrors");
 pub const PROPERTIES_NS: &Namespace = namespace!("properties");
 pub const SIGNERS_NS: &Namespace = namespace!("signers");
 pub const STATUS_NS: &Namespace = namespace!("status");
@@ -59,6 +62,7 @@ pub const TA_PROXY_SERVER_NS: &Namespace = namespace!("ta_proxy");
 pub const TA_SIGNER_SERVER_NS: &Namespace = namespace!("ta_signer");
 
 pub const PROPERTIES_DFLT_NAME: &str = "main";
+pub const API_TOKENS_DFLT_NAME: &str = "main";
 
 pub const PUBSERVER_DFLT: &str = "0";
 pub const PUBSERVER_BACKUP_DIR: &str = "pubd_bk";
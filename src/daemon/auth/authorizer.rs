@@ -1,6 +1,12 @@
 //! Authorization for the API
 
-use std::{any::Any, collections::HashMap, fmt, str::FromStr, sync::Arc};
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
 
 use rpki::ca::idexchange::{InvalidHandle, MyHandle};
 
@@ -15,16 +21,18 @@ use crate::{
     daemon::{
         auth::{
             common::permissions::Permission, policy::AuthPolicy,
-            providers::AdminTokenAuthProvider,
+            providers::AdminTokenAuthProvider, tokens::ApiTokenManager,
         },
         config::Config,
         http::{HttpResponse, HyperRequest},
     },
 };
 
+#[cfg(feature = "multi-user")]
+use crate::daemon::auth::client_certs::ClientCertAuthenticator;
 #[cfg(feature = "multi-user")]
 use crate::daemon::auth::providers::{
-    ConfigFileAuthProvider, OpenIDConnectAuthProvider,
+    ConfigFileAuthProvider, LdapAuthProvider, OpenIDConnectAuthProvider,
 };
 
 //------------ Authorizer ----------------------------------------------------
@@ -51,6 +59,9 @@ pub enum AuthProvider {
 
     #[cfg(feature = "multi-user")]
     OpenIdConnect(OpenIDConnectAuthProvider),
+
+    #[cfg(feature = "multi-user")]
+    Ldap(LdapAuthProvider),
 }
 
 impl From<AdminTokenAuthProvider> for AuthProvider {
@@ -73,6 +84,13 @@ impl From<OpenIDConnectAuthProvider> for AuthProvider {
     }
 }
 
+#[cfg(feature = "multi-user")]
+impl From<LdapAuthProvider> for AuthProvider {
+    fn from(provider: LdapAuthProvider) -> Self {
+        AuthProvider::Ldap(provider)
+    }
+}
+
 impl AuthProvider {
     pub async fn authenticate(
         &self,
@@ -88,6 +106,8 @@ impl AuthProvider {
             AuthProvider::OpenIdConnect(provider) => {
                 provider.authenticate(request).await
             }
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.authenticate(request),
         }
     }
 
@@ -100,6 +120,8 @@ impl AuthProvider {
             AuthProvider::OpenIdConnect(provider) => {
                 provider.get_login_url().await
             }
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.get_login_url(),
         }
     }
 
@@ -115,6 +137,8 @@ impl AuthProvider {
             AuthProvider::OpenIdConnect(provider) => {
                 provider.login(request).await
             }
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.login(request).await,
         }
     }
 
@@ -130,6 +154,8 @@ impl AuthProvider {
             AuthProvider::OpenIdConnect(provider) => {
                 provider.logout(request).await
             }
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.logout(request),
         }
     }
 }
@@ -139,7 +165,10 @@ impl AuthProvider {
 pub struct Authorizer {
     primary_provider: AuthProvider,
     legacy_provider: Option<AdminTokenAuthProvider>,
-    policy: AuthPolicy,
+    api_tokens: ApiTokenManager,
+    #[cfg(feature = "multi-user")]
+    client_certs: Option<ClientCertAuthenticator>,
+    policy: RwLock<AuthPolicy>,
     private_attributes: Vec<String>,
 }
 
@@ -183,10 +212,24 @@ impl Authorizer {
         #[cfg(not(feature = "multi-user"))]
         let private_attributes = vec!["role".to_string()];
 
+        let api_tokens = ApiTokenManager::create(
+            &config.storage_uri,
+            config.use_history_cache,
+        )?;
+
+        #[cfg(feature = "multi-user")]
+        let client_certs = config
+            .auth_client_certs
+            .as_ref()
+            .map(ClientCertAuthenticator::new);
+
         Ok(Authorizer {
             primary_provider,
             legacy_provider,
-            policy: AuthPolicy::new(config)?,
+            api_tokens,
+            #[cfg(feature = "multi-user")]
+            client_certs,
+            policy: RwLock::new(AuthPolicy::new(config)?),
             private_attributes,
         })
     }
@@ -207,6 +250,32 @@ impl Authorizer {
             _ => self.primary_provider.authenticate(request).await,
         };
 
+        // Try named API tokens last. These can only be restricted to
+        // specific permissions and CAs by the policy, so they are only
+        // supported in multi-user mode.
+        #[cfg(feature = "multi-user")]
+        {
+            authenticate_res = match authenticate_res {
+                Ok(Some(res)) => Ok(Some(res)),
+                _ => match self.api_tokens.authenticate(request) {
+                    Ok(None) => authenticate_res,
+                    res => res,
+                },
+            };
+        }
+
+        // Finally, try the TLS client certificate, if any.
+        #[cfg(feature = "multi-user")]
+        if let Some(client_certs) = &self.client_certs {
+            authenticate_res = match authenticate_res {
+                Ok(Some(res)) => Ok(Some(res)),
+                _ => match client_certs.authenticate(request) {
+                    Ok(None) => authenticate_res,
+                    res => res,
+                },
+            };
+        }
+
         // Create an actor based on the authentication result
         let actor = match authenticate_res {
             // authentication success
@@ -229,7 +298,29 @@ impl Authorizer {
     }
 
     pub fn actor_from_def(&self, def: ActorDef) -> Actor {
-        Actor::new(def, self.policy.clone())
+        Actor::new(def, self.policy.read().unwrap().clone())
+    }
+
+    /// Reloads the authorization policies, and the users if they are
+    /// defined in the config file, from the given config.
+    ///
+    /// Nothing is changed if the new config cannot be loaded. Requests
+    /// which are already being processed keep using the old policies.
+    pub fn reload(&self, config: Arc<Config>) -> KrillResult<()> {
+        let policy = AuthPolicy::new(config.clone())?;
+
+        #[cfg(feature = "multi-user")]
+        if let AuthProvider::ConfigFile(provider) = &self.primary_provider {
+            provider.reload(&config)?;
+        }
+
+        *self.policy.write().unwrap() = policy;
+        Ok(())
+    }
+
+    /// Returns the manager for named API tokens.
+    pub fn api_tokens(&self) -> &ApiTokenManager {
+        &self.api_tokens
     }
 
     /// Return the URL at which an end-user should be directed to login with
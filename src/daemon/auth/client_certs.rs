@@ -0,0 +1,215 @@
+//! Authentication of API clients using TLS client certificates.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use openssl::x509::X509;
+
+use crate::{
+    commons::{actor::ActorDef, error::Error, KrillResult},
+    daemon::http::{tls::ClientCertificate, HyperRequest},
+};
+
+//------------ ConfigAuthClientCerts -----------------------------------------
+
+/// Configures authentication using TLS client certificates.
+///
+/// Example:
+///
+///   [auth_client_certs]
+///   ca_certs = "/etc/krill/client-ca.pem"
+///
+///   [[auth_client_certs.clients]]
+///   name = "puppet"
+///   subject = "CN=puppet,O=Example,C=NL"
+///   attributes = { role = "readwrite" }
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigAuthClientCerts {
+    /// A PEM file with the CA certificates used to verify the certificates
+    /// presented by clients.
+    pub ca_certs: PathBuf,
+
+    #[serde(default)]
+    pub clients: Vec<ConfigClientCert>,
+}
+
+/// Maps a client certificate to a Krill actor.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigClientCert {
+    /// The name of the actor, as shown in the history of CAs.
+    pub name: String,
+
+    /// Matches the subject of the certificate, in RFC 4514 string form.
+    #[serde(default)]
+    pub subject: Option<String>,
+
+    /// Matches a DNS name, email address or URI in the subject alternative
+    /// names of the certificate.
+    #[serde(default)]
+    pub san: Option<String>,
+
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+}
+
+impl ConfigClientCert {
+    fn matches(&self, cert: &ClientCertDetails) -> bool {
+        let subject_matches = match &self.subject {
+            Some(subject) => {
+                normalize_dn(subject).eq_ignore_ascii_case(&cert.subject)
+            }
+            None => true,
+        };
+        let san_matches = match &self.san {
+            Some(san) => {
+                cert.sans.iter().any(|s| s.eq_ignore_ascii_case(san))
+            }
+            None => true,
+        };
+
+        // A client without any criteria should never match
+        (self.subject.is_some() || self.san.is_some())
+            && subject_matches
+            && san_matches
+    }
+}
+
+/// Removes whitespace around the separators of an RFC 4514 string, so that
+/// "CN=puppet, O=Example" matches "CN=puppet,O=Example".
+fn normalize_dn(dn: &str) -> String {
+    dn.split(',').map(str::trim).collect::<Vec<_>>().join(",")
+}
+
+//------------ ClientCertDetails ---------------------------------------------
+
+/// The details of a client certificate used to find its actor.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct ClientCertDetails {
+    subject: String,
+    sans: Vec<String>,
+}
+
+impl ClientCertDetails {
+    fn from_der(der: &[u8]) -> KrillResult<Self> {
+        let cert = X509::from_der(der).map_err(|e| {
+            Error::ApiInvalidCredentials(format!(
+                "Cannot parse client certificate: {}",
+                e
+            ))
+        })?;
+
+        // RFC 4514 lists the most significant RDN last, i.e. in the
+        // reverse order of the encoding.
+        let mut rdns = vec![];
+        for entry in cert.subject_name().entries() {
+            let key = entry.object().nid().short_name().unwrap_or("UNDEF");
+            let value = entry
+                .data()
+                .as_utf8()
+                .map(|s| s.to_string())
+                .unwrap_or_default();
+            rdns.push(format!("{}={}", key, value));
+        }
+        rdns.reverse();
+        let subject = rdns.join(",");
+
+        let mut sans = vec![];
+        if let Some(names) = cert.subject_alt_names() {
+            for name in names.iter() {
+                if let Some(value) =
+                    name.dnsname().or(name.email()).or(name.uri())
+                {
+                    sans.push(value.to_string());
+                }
+            }
+        }
+
+        Ok(ClientCertDetails { subject, sans })
+    }
+}
+
+//------------ ClientCertAuthenticator ---------------------------------------
+
+/// Finds the actor for the verified client certificate of a request.
+pub struct ClientCertAuthenticator {
+    clients: Vec<ConfigClientCert>,
+}
+
+impl ClientCertAuthenticator {
+    pub fn new(config: &ConfigAuthClientCerts) -> Self {
+        ClientCertAuthenticator {
+            clients: config.clients.clone(),
+        }
+    }
+
+    /// Returns the actor for the client certificate of the request, or
+    /// `None` if the client did not present a certificate.
+    ///
+    /// The certificate was already verified against the configured CA
+    /// certificates during the TLS handshake. Certificates which do not
+    /// match any of the configured clients are rejected.
+    pub fn authenticate(
+        &self,
+        request: &HyperRequest,
+    ) -> KrillResult<Option<ActorDef>> {
+        let cert = match request.extensions().get::<ClientCertificate>() {
+            Some(cert) => cert,
+            None => return Ok(None),
+        };
+
+        let details = ClientCertDetails::from_der(cert.as_ref())?;
+        trace!("Client certificate: {:?}", details);
+
+        match self.clients.iter().find(|client| client.matches(&details)) {
+            Some(client) => Ok(Some(ActorDef::client_cert(
+                &client.name,
+                client.attributes.clone(),
+            ))),
+            None => Err(Error::ApiInvalidCredentials(format!(
+                "Unknown client certificate '{}'",
+                details.subject
+            ))),
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(subject: Option<&str>, san: Option<&str>) -> ConfigClientCert {
+        ConfigClientCert {
+            name: "client".to_string(),
+            subject: subject.map(|s| s.to_string()),
+            san: san.map(|s| s.to_string()),
+            attributes: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn match_client_cert() {
+        let der =
+            include_bytes!("../../../test-resources/client-certs/puppet.der");
+        let details = ClientCertDetails::from_der(der).unwrap();
+        assert_eq!(details.subject, "CN=puppet,O=Example,C=NL");
+        assert_eq!(details.sans, vec!["puppet.example.org".to_string()]);
+
+        assert!(client(Some("CN=puppet, O=Example, C=NL"), None)
+            .matches(&details));
+        assert!(client(None, Some("PUPPET.example.org")).matches(&details));
+        assert!(client(
+            Some("CN=puppet,O=Example,C=NL"),
+            Some("puppet.example.org")
+        )
+        .matches(&details));
+
+        assert!(!client(Some("CN=puppet,O=Example"), None).matches(&details));
+        assert!(!client(
+            Some("CN=puppet,O=Example,C=NL"),
+            Some("other.example.org")
+        )
+        .matches(&details));
+        assert!(!client(None, None).matches(&details));
+    }
+}
@@ -69,13 +69,17 @@ iterable_enum! {
         ROUTES_READ,
         ROUTES_UPDATE,
         ROUTES_ANALYSIS,
+        ROUTES_APPROVE,
         ASPAS_READ,
         ASPAS_UPDATE,
         ASPAS_ANALYSIS,
         BGPSEC_READ,
         BGPSEC_UPDATE,
+        // The RTA permissions also govern RPKI Signed Checklists (RSCs).
         RTA_LIST,
         RTA_READ,
-        RTA_UPDATE
+        RTA_UPDATE,
+        TOKENS_ADMIN,
+        CONFIG_RELOAD
     }
 }
@@ -2,6 +2,10 @@ pub mod authorizer;
 pub mod providers;
 
 pub mod common;
+pub mod tokens;
+
+#[cfg(feature = "multi-user")]
+pub mod client_certs;
 
 #[cfg(feature = "multi-user")]
 pub mod policy;
@@ -290,6 +290,7 @@ impl PolarClass for Permission {
             .set_equality_check(|left: &Permission, right: &Permission| {
                 *left == *right
             })
+            .add_attribute_getter("name", |instance| instance.to_string())
             .build()
     }
 
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
 use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
 use base64::engine::Engine as _;
@@ -64,7 +67,7 @@ fn get_checked_config_user(
 }
 
 pub struct ConfigFileAuthProvider {
-    users: HashMap<String, UserDetails>,
+    users: RwLock<HashMap<String, UserDetails>>,
     session_key: CryptState,
     session_cache: Arc<LoginSessionCache>,
     fake_password_hash: String,
@@ -76,22 +79,28 @@ impl ConfigFileAuthProvider {
         config: Arc<Config>,
         session_cache: Arc<LoginSessionCache>,
     ) -> KrillResult<Self> {
+        let users = Self::read_users(&config)?;
+        let session_key = Self::init_session_key(&config)?;
+
+        Ok(ConfigFileAuthProvider {
+            users: RwLock::new(users),
+            session_key,
+            session_cache,
+            fake_password_hash: hex::encode("fake password hash"),
+            fake_salt: hex::encode("fake salt"),
+        })
+    }
+
+    fn read_users(
+        config: &Config,
+    ) -> KrillResult<HashMap<String, UserDetails>> {
         match &config.auth_users {
             Some(auth_users) => {
                 let mut users = HashMap::new();
                 for (k, v) in auth_users.iter() {
                     users.insert(k.clone(), get_checked_config_user(k, v)?);
                 }
-
-                let session_key = Self::init_session_key(&config)?;
-
-                Ok(ConfigFileAuthProvider {
-                    users,
-                    session_key,
-                    session_cache,
-                    fake_password_hash: hex::encode("fake password hash"),
-                    fake_salt: hex::encode("fake salt"),
-                })
+                Ok(users)
             }
             None => Err(Error::ConfigError(
                 "Missing [auth_users] config section!".into(),
@@ -99,6 +108,16 @@ impl ConfigFileAuthProvider {
         }
     }
 
+    /// Replaces the users with those in the given config.
+    ///
+    /// The current users are kept if the new config is invalid. Existing
+    /// login sessions stay valid until they expire.
+    pub fn reload(&self, config: &Config) -> KrillResult<()> {
+        let users = Self::read_users(config)?;
+        *self.users.write().unwrap() = users;
+        Ok(())
+    }
+
     fn init_session_key(config: &Config) -> KrillResult<CryptState> {
         debug!("Initializing login session encryption key");
         crypt::crypt_init(config)
@@ -172,16 +191,15 @@ impl ConfigFileAuthProvider {
             // unknown user path would return very quickly
             // compared to the known user path and timing differences can aid
             // attackers.
-            let (user_password_hash, user_salt) =
-                match self.users.get(&username) {
-                    Some(user) => {
-                        (user.password_hash.to_string(), user.salt.clone())
-                    }
-                    None => (
-                        self.fake_password_hash.clone(),
-                        self.fake_salt.clone(),
-                    ),
-                };
+            let users = self.users.read().unwrap();
+            let (user_password_hash, user_salt) = match users.get(&username) {
+                Some(user) => {
+                    (user.password_hash.to_string(), user.salt.clone())
+                }
+                None => {
+                    (self.fake_password_hash.clone(), self.fake_salt.clone())
+                }
+            };
 
             let username = username.trim().nfkc().collect::<String>();
             let password = password.trim().nfkc().collect::<String>();
@@ -225,7 +243,7 @@ impl ConfigFileAuthProvider {
                 // and don't result in an obvious timing difference between
                 // the two scenarios which could potentially
                 // be used to discover user names.
-                if let Some(user) = self.users.get(&username) {
+                if let Some(user) = users.get(&username) {
                     let api_token = self.session_cache.encode(
                         &username,
                         &user.attributes,
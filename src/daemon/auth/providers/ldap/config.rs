@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigAuthLdap {
+    /// The URL of the LDAP server, e.g. "ldaps://ldap.example.org".
+    pub url: String,
+
+    /// Upgrade "ldap://" connections to TLS using StartTLS.
+    #[serde(default)]
+    pub starttls: bool,
+
+    /// Do not verify the certificate of the LDAP server.
+    #[serde(default)]
+    pub insecure: bool,
+
+    /// The DN to bind as. Any "{username}" in this template is replaced
+    /// with the user name entered on the login form, e.g.
+    /// "uid={username},ou=people,dc=example,dc=org" or, for Active
+    /// Directory, "{username}@example.org".
+    pub bind_dn: String,
+
+    /// The base DN under which the entry for the user is searched after
+    /// binding, to find the groups that the user is a member of.
+    pub search_base: String,
+
+    /// The filter used to find the entry for the user, e.g. "(uid={username})"
+    /// or, for Active Directory, "(sAMAccountName={username})".
+    #[serde(default = "default_search_filter")]
+    pub search_filter: String,
+
+    /// The attribute of the user entry which lists the DNs of the groups
+    /// that the user is a member of.
+    #[serde(default = "default_group_attribute")]
+    pub group_attribute: String,
+
+    /// The Krill attributes for members of LDAP groups. If a user is a
+    /// member of multiple groups which set the same attribute, then the
+    /// group listed first wins.
+    #[serde(default)]
+    pub groups: Vec<ConfigAuthLdapGroup>,
+}
+
+fn default_search_filter() -> String {
+    "(uid={username})".to_string()
+}
+
+fn default_group_attribute() -> String {
+    "memberOf".to_string()
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigAuthLdapGroup {
+    /// The DN of the group, this is compared case-insensitively.
+    pub dn: String,
+
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+}
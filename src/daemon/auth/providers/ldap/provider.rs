@@ -0,0 +1,287 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
+use base64::engine::Engine as _;
+use ldap3::{
+    dn_escape, ldap_escape, LdapConnAsync, LdapConnSettings, LdapError,
+    Scope, SearchEntry,
+};
+
+use crate::daemon::http::{HttpResponse, HyperRequest};
+use crate::{
+    commons::{actor::ActorDef, error::Error, util::httpclient, KrillResult},
+    daemon::{
+        auth::common::{
+            crypt::{self, CryptState},
+            session::*,
+        },
+        auth::providers::ldap::config::ConfigAuthLdap,
+        auth::{Auth, LoggedInUser},
+        config::Config,
+    },
+};
+
+const UI_LOGIN_ROUTE_PATH: &str = "/login?withId=true";
+
+/// The LDAP result code for a failed bind, see RFC 4511 section 4.1.9.
+const LDAP_INVALID_CREDENTIALS: u32 = 49;
+
+const LDAP_CONN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Authenticates users by binding to an LDAP directory, such as Active
+/// Directory, with the credentials entered on the login form.
+///
+/// The Krill attributes of the user, e.g. its role, are determined by the
+/// groups that the user is a member of in the directory. Like the config
+/// file provider, a successful login results in a login session token
+/// which is used to authenticate further requests.
+pub struct LdapAuthProvider {
+    ldap_conf: ConfigAuthLdap,
+    session_key: CryptState,
+    session_cache: Arc<LoginSessionCache>,
+}
+
+impl LdapAuthProvider {
+    pub fn new(
+        config: Arc<Config>,
+        session_cache: Arc<LoginSessionCache>,
+    ) -> KrillResult<Self> {
+        match &config.auth_ldap {
+            Some(ldap_conf) => {
+                let session_key = Self::init_session_key(&config)?;
+
+                Ok(LdapAuthProvider {
+                    ldap_conf: ldap_conf.clone(),
+                    session_key,
+                    session_cache,
+                })
+            }
+            None => Err(Error::ConfigError(
+                "Missing [auth_ldap] config section!".into(),
+            )),
+        }
+    }
+
+    fn init_session_key(config: &Config) -> KrillResult<CryptState> {
+        debug!("Initializing login session encryption key");
+        crypt::crypt_init(config)
+    }
+
+    /// Parse HTTP Basic Authorization header
+    fn get_auth(&self, request: &HyperRequest) -> Option<Auth> {
+        let header =
+            request.headers().get(hyper::http::header::AUTHORIZATION)?;
+        let auth = header.to_str().ok()?.strip_prefix("Basic ")?;
+        let auth = BASE64_ENGINE.decode(auth).ok()?;
+        let auth = String::from_utf8(auth).ok()?;
+        let (username, password) = auth.split_once(':')?;
+
+        Some(Auth::UsernameAndPassword {
+            username: username.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    /// Log and convert the given error such that the details are logged
+    /// and only a high level statement is passed back to the caller.
+    fn internal_error(msg: &str, err: LdapError) -> Error {
+        warn!("{} [additional info: {}]", msg, err);
+        Error::ApiLoginError(msg.to_string())
+    }
+
+    /// Binds to the directory as the given user and returns the DNs of the
+    /// groups that the user is a member of.
+    async fn user_groups(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> KrillResult<Vec<String>> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(LDAP_CONN_TIMEOUT)
+            .set_starttls(self.ldap_conf.starttls)
+            .set_no_tls_verify(self.ldap_conf.insecure);
+
+        let (conn, mut ldap) =
+            LdapConnAsync::with_settings(settings, &self.ldap_conf.url)
+                .await
+                .map_err(|e| {
+                    Self::internal_error("Cannot connect to LDAP server", e)
+                })?;
+        ldap3::drive!(conn);
+
+        let bind_dn = self
+            .ldap_conf
+            .bind_dn
+            .replace("{username}", &dn_escape(username));
+
+        match ldap.simple_bind(&bind_dn, password).await {
+            Ok(res) if res.rc == 0 => {}
+            Ok(res) if res.rc == LDAP_INVALID_CREDENTIALS => {
+                trace!("LDAP bind failed for {}", bind_dn);
+                return Err(Error::ApiInvalidCredentials(
+                    "Incorrect credentials".to_string(),
+                ));
+            }
+            Ok(res) => {
+                return Err(Self::internal_error(
+                    "LDAP bind failed",
+                    LdapError::from(res),
+                ))
+            }
+            Err(e) => {
+                return Err(Self::internal_error("LDAP bind failed", e))
+            }
+        }
+
+        let filter = self
+            .ldap_conf
+            .search_filter
+            .replace("{username}", &ldap_escape(username));
+
+        let (entries, _) = ldap
+            .search(
+                &self.ldap_conf.search_base,
+                Scope::Subtree,
+                &filter,
+                vec![self.ldap_conf.group_attribute.as_str()],
+            )
+            .await
+            .and_then(|res| res.success())
+            .map_err(|e| Self::internal_error("LDAP search failed", e))?;
+
+        let _ = ldap.unbind().await;
+
+        let mut groups = vec![];
+        for entry in entries {
+            let mut entry = SearchEntry::construct(entry);
+            if let Some(values) =
+                entry.attrs.remove(&self.ldap_conf.group_attribute)
+            {
+                groups.extend(values);
+            }
+        }
+
+        Ok(groups)
+    }
+
+    /// Returns the Krill attributes for a member of the given groups.
+    fn group_attributes(&self, groups: &[String]) -> HashMap<String, String> {
+        let mut attributes = HashMap::new();
+        for group in &self.ldap_conf.groups {
+            if groups.iter().any(|dn| dn.eq_ignore_ascii_case(&group.dn)) {
+                for (k, v) in &group.attributes {
+                    attributes.entry(k.clone()).or_insert_with(|| v.clone());
+                }
+            }
+        }
+        attributes
+    }
+}
+
+impl LdapAuthProvider {
+    pub fn authenticate(
+        &self,
+        request: &HyperRequest,
+    ) -> KrillResult<Option<ActorDef>> {
+        if log_enabled!(log::Level::Trace) {
+            trace!("Attempting to authenticate the request..");
+        }
+
+        let res = match httpclient::get_bearer_token(request) {
+            Some(token) => {
+                let session = self.session_cache.decode(
+                    token,
+                    &self.session_key,
+                    true,
+                )?;
+
+                trace!(
+                    "id={}, attributes={:?}",
+                    &session.id,
+                    &session.attributes
+                );
+
+                Ok(Some(ActorDef::user(session.id, session.attributes, None)))
+            }
+            _ => Ok(None),
+        };
+
+        if log_enabled!(log::Level::Trace) {
+            trace!("Authentication result: {:?}", res);
+        }
+
+        res
+    }
+
+    pub fn get_login_url(&self) -> KrillResult<HttpResponse> {
+        // Direct Lagosta to show the user the Lagosta API token login form
+        Ok(HttpResponse::text_no_cache(UI_LOGIN_ROUTE_PATH.into()))
+    }
+
+    pub async fn login(
+        &self,
+        request: &HyperRequest,
+    ) -> KrillResult<LoggedInUser> {
+        match self.get_auth(request) {
+            Some(Auth::UsernameAndPassword { username, password })
+                if !username.trim().is_empty() && !password.is_empty() =>
+            {
+                // Note that an empty password must never be passed on, as
+                // LDAP servers treat this as an anonymous bind which
+                // succeeds regardless of the DN.
+                let username = username.trim().to_string();
+                let groups = self.user_groups(&username, &password).await?;
+                let attributes = self.group_attributes(&groups);
+
+                trace!(
+                    "LDAP user {} is a member of {:?}, attributes={:?}",
+                    username,
+                    groups,
+                    attributes
+                );
+
+                let api_token = self.session_cache.encode(
+                    &username,
+                    &attributes,
+                    HashMap::new(),
+                    &self.session_key,
+                    None,
+                )?;
+
+                Ok(LoggedInUser {
+                    token: api_token,
+                    id: username,
+                    attributes,
+                })
+            }
+            _ => {
+                trace!("Missing or incomplete credentials for login attempt");
+                Err(Error::ApiInvalidCredentials(
+                    "Missing credentials".to_string(),
+                ))
+            }
+        }
+    }
+
+    pub fn logout(
+        &self,
+        request: &HyperRequest,
+    ) -> KrillResult<HttpResponse> {
+        match httpclient::get_bearer_token(request) {
+            Some(token) => {
+                self.session_cache.remove(&token);
+
+                if let Ok(Some(actor)) = self.authenticate(request) {
+                    info!("User logged out: {}", actor.name.as_str());
+                }
+            }
+            _ => {
+                warn!("Unexpectedly received a logout request without a session token.");
+            }
+        }
+
+        // Logout is complete, direct Lagosta to show the user the Lagosta
+        // index page
+        Ok(HttpResponse::text_no_cache("/".into()))
+    }
+}
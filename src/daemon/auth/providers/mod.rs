@@ -3,6 +3,8 @@ pub mod admin_token;
 #[cfg(feature = "multi-user")]
 pub mod config_file;
 #[cfg(feature = "multi-user")]
+pub mod ldap;
+#[cfg(feature = "multi-user")]
 pub mod openid_connect;
 
 pub use admin_token::AdminTokenAuthProvider;
@@ -10,4 +12,6 @@ pub use admin_token::AdminTokenAuthProvider;
 #[cfg(feature = "multi-user")]
 pub use config_file::provider::ConfigFileAuthProvider;
 #[cfg(feature = "multi-user")]
+pub use ldap::provider::LdapAuthProvider;
+#[cfg(feature = "multi-user")]
 pub use openid_connect::provider::OpenIDConnectAuthProvider;
@@ -0,0 +1,711 @@
+//! Named API tokens
+//!
+//! API tokens are long-lived bearer tokens, meant to be used by automation
+//! rather than by people. Each token is limited to a set of permissions, and
+//! optionally to a set of CAs, and it expires at a given time. Tokens can
+//! be revoked before then.
+//!
+//! Krill does not keep the tokens themselves. Only a SHA256 hash of each
+//! token is kept in the (event-sourced) store, so that the tokens can be
+//! shared between nodes, and an audit trail of issued and revoked tokens is
+//! kept.
+//!
+//! Authenticated tokens are turned into actors with the "api-token" role,
+//! and the "permissions" and "inc_cas" attributes. The Oso policy only
+//! allows these permissions on these CAs for this role. Tokens can only be
+//! issued for permissions and CAs which the issuer has access to.
+
+use std::{collections::HashMap, fmt, str::FromStr, sync::Arc};
+
+use rpki::ca::idexchange::{CaHandle, MyHandle};
+use url::Url;
+
+use crate::{
+    commons::{
+        actor::{Actor, ActorDef},
+        api::{
+            ApiTokenInfo, ApiTokenIssued, ApiTokenList, ApiTokenRequest,
+            CommandSummary, Timestamp, Token,
+        },
+        error::Error,
+        eventsourcing::{
+            self, Aggregate, AggregateStore, Event, InitCommandDetails,
+            InitEvent, SentCommand, SentInitCommand, WithStorableDetails,
+        },
+        util::{httpclient, sha256},
+        KrillResult,
+    },
+    constants::{API_TOKENS_DFLT_NAME, API_TOKENS_NS, NO_RESOURCE},
+    daemon::{
+        auth::{common::permissions::Permission, Handle},
+        http::HyperRequest,
+    },
+};
+
+//------------ ApiTokensInitCommand ----------------------------------------
+pub type ApiTokensInitCommand = SentInitCommand<ApiTokensInitCommandDetails>;
+
+//------------ ApiTokensInitCommandDetails ---------------------------------
+#[derive(Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokensInitCommandDetails;
+
+impl fmt::Display for ApiTokensInitCommandDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.store().fmt(f)
+    }
+}
+
+impl InitCommandDetails for ApiTokensInitCommandDetails {
+    type StorableDetails = StorableApiTokensCommand;
+
+    fn store(&self) -> Self::StorableDetails {
+        StorableApiTokensCommand::make_init()
+    }
+}
+
+//------------ ApiTokensCommand --------------------------------------------
+pub type ApiTokensCommand = SentCommand<ApiTokensCommandDetails>;
+
+//------------ ApiTokensCommandDetails -------------------------------------
+#[derive(Clone, Debug)]
+pub enum ApiTokensCommandDetails {
+    Issue {
+        request: ApiTokenRequest,
+        hash: String,
+    },
+    Revoke {
+        name: String,
+    },
+}
+
+impl fmt::Display for ApiTokensCommandDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        StorableApiTokensCommand::from(self).fmt(f)
+    }
+}
+
+//------------ StorableApiTokensCommand ------------------------------------
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "type")]
+pub enum StorableApiTokensCommand {
+    Init,
+    Issue { request: ApiTokenRequest },
+    Revoke { name: String },
+}
+
+impl fmt::Display for StorableApiTokensCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Init => write!(f, "initialise API tokens"),
+            Self::Issue { request } => {
+                write!(f, "issue API token {}", request)
+            }
+            Self::Revoke { name } => write!(f, "revoke API token '{}'", name),
+        }
+    }
+}
+
+impl eventsourcing::CommandDetails for ApiTokensCommandDetails {
+    type Event = ApiTokensEvent;
+
+    type StorableDetails = StorableApiTokensCommand;
+
+    fn store(&self) -> Self::StorableDetails {
+        self.into()
+    }
+}
+
+impl From<&ApiTokensCommandDetails> for StorableApiTokensCommand {
+    fn from(details: &ApiTokensCommandDetails) -> Self {
+        // Note that the hash of the token is not part of the stored
+        // command. It is only kept in the resulting event.
+        match details {
+            ApiTokensCommandDetails::Issue { request, .. } => {
+                StorableApiTokensCommand::Issue {
+                    request: request.clone(),
+                }
+            }
+            ApiTokensCommandDetails::Revoke { name } => {
+                StorableApiTokensCommand::Revoke { name: name.clone() }
+            }
+        }
+    }
+}
+
+impl eventsourcing::WithStorableDetails for StorableApiTokensCommand {
+    fn summary(&self) -> CommandSummary {
+        match self {
+            StorableApiTokensCommand::Init => {
+                CommandSummary::new("cmd-api-tokens-init", self)
+            }
+            StorableApiTokensCommand::Issue { request } => {
+                CommandSummary::new("cmd-api-tokens-issue", self)
+                    .with_arg("name", request.name())
+            }
+            StorableApiTokensCommand::Revoke { name } => {
+                CommandSummary::new("cmd-api-tokens-revoke", self)
+                    .with_arg("name", name)
+            }
+        }
+    }
+
+    fn make_init() -> Self {
+        Self::Init
+    }
+}
+
+//------------ ApiTokensEvent ----------------------------------------------
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "type")]
+pub enum ApiTokensEvent {
+    Issued { name: String, token: StoredApiToken },
+    Revoked { name: String },
+}
+
+impl Event for ApiTokensEvent {}
+
+impl fmt::Display for ApiTokensEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiTokensEvent::Issued { name, token } => write!(
+                f,
+                "issued API token '{}' expiring at {}",
+                name,
+                token.expires.to_rfc3339()
+            ),
+            ApiTokensEvent::Revoked { name } => {
+                write!(f, "revoked API token '{}'", name)
+            }
+        }
+    }
+}
+
+//------------ ApiTokensInitEvent ------------------------------------------
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokensInitEvent;
+
+impl InitEvent for ApiTokensInitEvent {}
+
+impl fmt::Display for ApiTokensInitEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "initialised API tokens")
+    }
+}
+
+//------------ StoredApiToken ----------------------------------------------
+
+/// An issued API token. Only the (hex encoded) SHA256 hash of the token is
+/// kept.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StoredApiToken {
+    hash: String,
+    permissions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cas: Vec<CaHandle>,
+    issued: Timestamp,
+    expires: Timestamp,
+}
+
+impl StoredApiToken {
+    fn info(&self, name: &str) -> ApiTokenInfo {
+        ApiTokenInfo::new(
+            name.to_string(),
+            self.permissions.clone(),
+            self.cas.clone(),
+            self.issued,
+            self.expires,
+        )
+    }
+
+    fn actor_def(&self, name: &str) -> ActorDef {
+        // The "api-token" role has no rights of its own. The policy only
+        // allows the permissions in the "permissions" attribute.
+        let mut attributes = HashMap::new();
+        attributes.insert("role".to_string(), "api-token".to_string());
+        attributes
+            .insert("permissions".to_string(), self.permissions.join(","));
+        if !self.cas.is_empty() {
+            let cas: Vec<String> =
+                self.cas.iter().map(|ca| ca.to_string()).collect();
+            attributes.insert("inc_cas".to_string(), cas.join(","));
+        }
+
+        ActorDef::api_token(name, attributes)
+    }
+}
+
+//------------ ApiTokens ---------------------------------------------------
+
+/// All API tokens which were issued, and not revoked.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ApiTokens {
+    handle: MyHandle,
+    version: u64,
+
+    tokens: HashMap<String, StoredApiToken>,
+}
+
+impl Aggregate for ApiTokens {
+    type Command = ApiTokensCommand;
+    type StorableCommandDetails = StorableApiTokensCommand;
+    type Event = ApiTokensEvent;
+
+    type InitCommand = ApiTokensInitCommand;
+    type InitEvent = ApiTokensInitEvent;
+
+    type Error = Error;
+
+    fn init(handle: MyHandle, _event: ApiTokensInitEvent) -> Self {
+        ApiTokens {
+            handle,
+            version: 1, // init for 0 was applied
+            tokens: HashMap::new(),
+        }
+    }
+
+    fn process_init_command(
+        _command: ApiTokensInitCommand,
+    ) -> Result<Self::InitEvent, Self::Error> {
+        Ok(ApiTokensInitEvent)
+    }
+
+    fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn increment_version(&mut self) {
+        self.version += 1;
+    }
+
+    fn apply(&mut self, event: Self::Event) {
+        match event {
+            ApiTokensEvent::Issued { name, token } => {
+                self.tokens.insert(name, token);
+            }
+            ApiTokensEvent::Revoked { name } => {
+                self.tokens.remove(&name);
+            }
+        }
+    }
+
+    fn process_command(
+        &self,
+        command: Self::Command,
+    ) -> Result<Vec<Self::Event>, Self::Error> {
+        if log_enabled!(log::Level::Trace) {
+            trace!(
+                "Sending command to API tokens '{}', version: {}: {}",
+                self.handle,
+                self.version,
+                command
+            );
+        }
+
+        match command.into_details() {
+            ApiTokensCommandDetails::Issue { request, hash } => {
+                self.issue(request, hash)
+            }
+            ApiTokensCommandDetails::Revoke { name } => {
+                if self.tokens.contains_key(&name) {
+                    Ok(vec![ApiTokensEvent::Revoked { name }])
+                } else {
+                    Err(Error::ApiTokenUnknown(name))
+                }
+            }
+        }
+    }
+}
+
+impl ApiTokens {
+    fn issue(
+        &self,
+        request: ApiTokenRequest,
+        hash: String,
+    ) -> KrillResult<Vec<ApiTokensEvent>> {
+        let name = request.name();
+
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::ApiTokenInvalid(format!(
+                "name '{}' must consist of letters, digits, '-' and '_'",
+                name
+            )));
+        }
+
+        if self.tokens.contains_key(name) {
+            return Err(Error::ApiTokenDuplicate(name.to_string()));
+        }
+
+        if request.permissions().is_empty() {
+            return Err(Error::ApiTokenInvalid("no permissions".to_string()));
+        }
+
+        for permission in request.permissions() {
+            Permission::from_str(permission)
+                .map_err(Error::ApiTokenInvalid)?;
+        }
+
+        let issued = Timestamp::now();
+        if request.expires() <= issued {
+            return Err(Error::ApiTokenInvalid(
+                "expiry time is in the past".to_string(),
+            ));
+        }
+
+        Ok(vec![ApiTokensEvent::Issued {
+            name: name.to_string(),
+            token: StoredApiToken {
+                hash,
+                permissions: request.permissions().clone(),
+                cas: request.cas().clone(),
+                issued,
+                expires: request.expires(),
+            },
+        }])
+    }
+
+    fn find(&self, hash: &str) -> Option<(&String, &StoredApiToken)> {
+        self.tokens.iter().find(|(_, token)| token.hash == hash)
+    }
+}
+
+//------------ ApiTokenManager ---------------------------------------------
+
+/// Issues, revokes and authenticates API tokens.
+pub struct ApiTokenManager {
+    store: AggregateStore<ApiTokens>,
+    main_key: MyHandle,
+}
+
+impl ApiTokenManager {
+    pub fn create(
+        storage_uri: &Url,
+        use_history_cache: bool,
+    ) -> KrillResult<Self> {
+        let main_key = MyHandle::from_str(API_TOKENS_DFLT_NAME).unwrap();
+        let store = AggregateStore::create(
+            storage_uri,
+            API_TOKENS_NS,
+            use_history_cache,
+        )
+        .map_err(Error::AggregateStoreError)?;
+
+        if !store.has(&main_key)? {
+            let cmd = ApiTokensInitCommand::new(
+                &main_key,
+                ApiTokensInitCommandDetails,
+                &Actor::system_actor(),
+            );
+            store.add(cmd)?;
+        }
+
+        Ok(ApiTokenManager { store, main_key })
+    }
+
+    /// Issues a new API token. The returned token cannot be retrieved
+    /// again later.
+    pub fn issue(
+        &self,
+        request: ApiTokenRequest,
+        actor: &Actor,
+    ) -> KrillResult<ApiTokenIssued> {
+        Self::verify_issuer(&request, actor)?;
+
+        let mut bytes = [0; 32];
+        openssl::rand::rand_bytes(&mut bytes).map_err(|e| {
+            Error::Custom(format!("Could not generate API token: {}", e))
+        })?;
+        let token = Token::from(hex::encode(bytes));
+
+        let name = request.name().to_string();
+        let expires = request.expires();
+
+        let cmd = ApiTokensCommand::new(
+            &self.main_key,
+            None,
+            ApiTokensCommandDetails::Issue {
+                request,
+                hash: Self::hash(&token),
+            },
+            actor,
+        );
+        self.store.command(cmd)?;
+
+        Ok(ApiTokenIssued::new(name, token, expires))
+    }
+
+    /// Verifies that the issuer has all the permissions requested for the
+    /// token, on all the CAs requested. Tokens for all CAs can only be
+    /// issued by actors who are not restricted to specific CAs themselves.
+    fn verify_issuer(
+        request: &ApiTokenRequest,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let insufficient = |what: String| {
+            Error::ApiInsufficientRights(format!(
+                "'{}' cannot issue API tokens for {}",
+                actor.name(),
+                what
+            ))
+        };
+
+        if request.cas().is_empty()
+            && (actor.attribute("inc_cas".to_string()).is_some()
+                || actor.attribute("exc_cas".to_string()).is_some())
+        {
+            return Err(insufficient("all CAs".to_string()));
+        }
+
+        for permission in request.permissions() {
+            let permission = Permission::from_str(permission)
+                .map_err(Error::ApiTokenInvalid)?;
+
+            if request.cas().is_empty() {
+                if !actor.is_allowed(permission.clone(), NO_RESOURCE)? {
+                    return Err(insufficient(permission.to_string()));
+                }
+            } else {
+                for ca in request.cas() {
+                    if !actor
+                        .is_allowed(permission.clone(), Handle::from(ca))?
+                    {
+                        return Err(insufficient(format!(
+                            "{} on CA '{}'",
+                            permission, ca
+                        )));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Revokes the API token with the given name.
+    pub fn revoke(&self, name: String, actor: &Actor) -> KrillResult<()> {
+        let cmd = ApiTokensCommand::new(
+            &self.main_key,
+            None,
+            ApiTokensCommandDetails::Revoke { name },
+            actor,
+        );
+        self.store.command(cmd)?;
+        Ok(())
+    }
+
+    /// Lists all API tokens, including expired tokens.
+    pub fn list(&self) -> KrillResult<ApiTokenList> {
+        let tokens = self.tokens()?;
+        let mut list: Vec<ApiTokenInfo> = tokens
+            .tokens
+            .iter()
+            .map(|(name, token)| token.info(name))
+            .collect();
+        list.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok(ApiTokenList::new(list))
+    }
+
+    /// Returns the definition of the actor for the API token used as the
+    /// bearer token in the request, if any. Returns None if there is no
+    /// bearer token, or if it is not a known API token, so that other
+    /// providers can be tried.
+    pub fn authenticate(
+        &self,
+        request: &HyperRequest,
+    ) -> KrillResult<Option<ActorDef>> {
+        match httpclient::get_bearer_token(request) {
+            Some(token) => self.actor_def(&token),
+            None => Ok(None),
+        }
+    }
+
+    fn actor_def(&self, token: &Token) -> KrillResult<Option<ActorDef>> {
+        let tokens = self.tokens()?;
+        match tokens.find(&Self::hash(token)) {
+            Some((name, token)) => {
+                if token.expires < Timestamp::now() {
+                    Err(Error::ApiInvalidCredentials(format!(
+                        "API token '{}' has expired",
+                        name
+                    )))
+                } else {
+                    Ok(Some(token.actor_def(name)))
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn hash(token: &Token) -> String {
+        hex::encode(sha256(token.as_ref().as_bytes()))
+    }
+
+    fn tokens(&self) -> KrillResult<Arc<ApiTokens>> {
+        self.store.get_latest(&self.main_key)
+    }
+}
+
+//--------- Tests
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::{
+        daemon::{auth::policy::AuthPolicy, config::Config},
+        test,
+    };
+
+    fn request(
+        name: &str,
+        permissions: &[&str],
+        hours: i64,
+    ) -> ApiTokenRequest {
+        ApiTokenRequest::new(
+            name.to_string(),
+            permissions.iter().map(|p| p.to_string()).collect(),
+            vec![CaHandle::from_str("ca1").unwrap()],
+            Timestamp::now_plus_hours(hours),
+        )
+    }
+
+    fn user(storage_uri: &Url, attributes: &[(&str, &str)]) -> Actor {
+        let (data_dir, cleanup) = test::tmp_dir();
+        let config = Config::test(
+            storage_uri,
+            Some(&data_dir),
+            false,
+            false,
+            false,
+            false,
+        );
+        let policy = AuthPolicy::new(Arc::new(config)).unwrap();
+        cleanup();
+
+        let attributes = attributes
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Actor::new(
+            ActorDef::user("user".to_string(), attributes, None),
+            policy,
+        )
+    }
+
+    #[test]
+    fn issue_authenticate_revoke() {
+        test::test_in_memory(|storage_uri| {
+            let manager =
+                ApiTokenManager::create(storage_uri, false).unwrap();
+            let actor = user(storage_uri, &[("role", "admin")]);
+
+            let issued = manager
+                .issue(
+                    request("ci", &["CA_READ", "ROUTES_UPDATE"], 1),
+                    &actor,
+                )
+                .unwrap();
+
+            // The token maps to an actor with restricted attributes
+            let def = manager.actor_def(issued.token()).unwrap().unwrap();
+            assert_eq!(def.name.as_str(), "token:ci");
+            let attributes = def.attributes.as_map();
+            assert_eq!(attributes.get("role").unwrap(), "api-token");
+            assert_eq!(
+                attributes.get("permissions").unwrap(),
+                "CA_READ,ROUTES_UPDATE"
+            );
+            assert_eq!(attributes.get("inc_cas").unwrap(), "ca1");
+
+            // Other tokens are left for other providers
+            assert!(manager
+                .actor_def(&Token::from("secret"))
+                .unwrap()
+                .is_none());
+
+            // Names must be unique, permissions must be known and
+            // tokens must not be expired on arrival
+            assert!(matches!(
+                manager.issue(request("ci", &["CA_READ"], 1), &actor),
+                Err(Error::ApiTokenDuplicate(_))
+            ));
+            assert!(matches!(
+                manager.issue(request("ci2", &["CA_FLY"], 1), &actor),
+                Err(Error::ApiTokenInvalid(_))
+            ));
+            assert!(matches!(
+                manager.issue(request("ci2", &["CA_READ"], -1), &actor),
+                Err(Error::ApiTokenInvalid(_))
+            ));
+            assert_eq!(manager.list().unwrap().tokens().len(), 1);
+
+            // The token itself is not stored
+            let json =
+                serde_json::to_string(&*manager.tokens().unwrap()).unwrap();
+            assert!(!json.contains(issued.token().as_ref()));
+
+            // Revoked tokens are no longer accepted
+            manager.revoke("ci".to_string(), &actor).unwrap();
+            assert!(manager.actor_def(issued.token()).unwrap().is_none());
+            assert!(matches!(
+                manager.revoke("ci".to_string(), &actor),
+                Err(Error::ApiTokenUnknown(_))
+            ));
+        })
+    }
+
+    #[test]
+    #[cfg(feature = "multi-user")]
+    fn issue_requires_issuer_rights() {
+        test::test_in_memory(|storage_uri| {
+            let manager =
+                ApiTokenManager::create(storage_uri, false).unwrap();
+            let actor = user(
+                storage_uri,
+                &[("role", "readonly"), ("inc_cas", "ca1")],
+            );
+
+            // The issuer must have the permissions on the CAs
+            manager
+                .issue(request("read", &["CA_READ"], 1), &actor)
+                .unwrap();
+            assert!(matches!(
+                manager.issue(request("update", &["CA_UPDATE"], 1), &actor),
+                Err(Error::ApiInsufficientRights(_))
+            ));
+
+            let other_ca = ApiTokenRequest::new(
+                "ca2".to_string(),
+                vec!["CA_READ".to_string()],
+                vec![CaHandle::from_str("ca2").unwrap()],
+                Timestamp::now_plus_hours(1),
+            );
+            assert!(matches!(
+                manager.issue(other_ca, &actor),
+                Err(Error::ApiInsufficientRights(_))
+            ));
+
+            // Tokens for all CAs need access to all CAs
+            let all_cas = ApiTokenRequest::new(
+                "all".to_string(),
+                vec!["CA_READ".to_string()],
+                vec![],
+                Timestamp::now_plus_hours(1),
+            );
+            assert!(matches!(
+                manager.issue(all_cas.clone(), &actor),
+                Err(Error::ApiInsufficientRights(_))
+            ));
+            manager
+                .issue(all_cas, &user(storage_uri, &[("role", "admin")]))
+                .unwrap();
+
+            assert_eq!(manager.list().unwrap().tokens().len(), 2);
+        })
+    }
+}
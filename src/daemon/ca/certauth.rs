@@ -31,14 +31,18 @@ use crate::{
             AspaDefinition, AspaDefinitionList, AspaDefinitionUpdates,
             AspaProvidersUpdate, BgpSecAsnKey, BgpSecCsrInfoList,
             BgpSecDefinitionUpdates, CertAuthInfo, CertAuthStorableCommand,
-            ConfiguredRoa, CustomerAsn, IdCertInfo, ObjectName,
-            ParentCaContact, ReceivedCert, RepositoryContact,
-            ResourceClassNameMapping, Revocation, RoaConfiguration,
-            RoaConfigurationUpdates, RtaList, RtaName, RtaPrepResponse,
+            ChangeRequest, ChangeRequestList, ChangeRequestUpdates,
+            ConfiguredRoa, CustomerAsn, GhostbusterRecordUpdates,
+            GhostbusterRecords, IdCertInfo, ObjectName, ParentCaContact,
+            ReceivedCert, RepositoryContact, ResourceClassNameMapping,
+            Revocation, RoaConfiguration, RoaConfigurationUpdates,
+            RoaPayload, RscList, RscName, RtaList, RtaName, RtaPrepResponse,
+            ScheduleRoaUpdatesRequest, ScheduledRoaUpdates,
+            ScheduledRoaUpdatesList, Timestamp,
         },
         crypto::{CsrInfo, KrillSigner},
         error::{Error, RoaDeltaError},
-        eventsourcing::Aggregate,
+        eventsourcing::{Aggregate, Command},
         KrillResult,
     },
     constants::test_mode_enabled,
@@ -47,9 +51,12 @@ use crate::{
             events::ChildCertificateUpdates, AspaDefinitions,
             BgpSecDefinitions, CertAuthCommand, CertAuthCommandDetails,
             CertAuthEvent, CertAuthInitEvent, ChildDetails, DropReason,
-            PreparedRta, ResourceClass, ResourceTaggedAttestation, Rfc8183Id,
-            RoaInfo, RoaPayloadJsonMapKey, Routes, RtaContentRequest,
-            RtaPrepareRequest, Rtas, SignedRta, StoredBgpSecCsr,
+            GhostbusterDefinitions, PreparedRta, ResourceClass,
+            ResourceTaggedAttestation, Rfc8183Id, RoaInfo,
+            RoaPayloadJsonMapKey, Routes, RpkiSignedChecklist,
+            RscContentRequest, RscVerification, RscVerifyRequest, Rscs,
+            RtaContentRequest, RtaPrepareRequest, Rtas, SignedRsc, SignedRta,
+            StoredBgpSecCsr,
         },
         config::{Config, IssuanceTimingConfig},
     },
@@ -88,11 +95,26 @@ pub struct CertAuth {
     #[serde(skip_serializing_if = "Rtas::is_empty", default)]
     rtas: Rtas,
 
+    #[serde(skip_serializing_if = "Rscs::is_empty", default)]
+    rscs: Rscs,
+
     #[serde(skip_serializing_if = "AspaDefinitions::is_empty", default)]
     aspas: AspaDefinitions,
 
     #[serde(skip_serializing_if = "BgpSecDefinitions::is_empty", default)]
     bgpsec_defs: BgpSecDefinitions,
+
+    #[serde(
+        skip_serializing_if = "GhostbusterDefinitions::is_empty",
+        default
+    )]
+    ghostbusters: GhostbusterDefinitions,
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    scheduled_routes: Vec<ScheduledRoaUpdates>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    change_requests: Vec<ChangeRequest>,
 }
 
 impl Aggregate for CertAuth {
@@ -118,8 +140,12 @@ impl Aggregate for CertAuth {
 
         let routes = Routes::default();
         let rtas = Rtas::default();
+        let rscs = Rscs::default();
         let aspas = AspaDefinitions::default();
         let bgpsec_defs = BgpSecDefinitions::default();
+        let ghostbusters = GhostbusterDefinitions::default();
+        let scheduled_routes = vec![];
+        let change_requests = vec![];
 
         CertAuth {
             handle,
@@ -137,8 +163,12 @@ impl Aggregate for CertAuth {
 
             routes,
             rtas,
+            rscs,
             aspas,
             bgpsec_defs,
+            ghostbusters,
+            scheduled_routes,
+            change_requests,
         }
     }
 
@@ -382,6 +412,9 @@ impl Aggregate for CertAuth {
             CertAuthEvent::RouteAuthorizationComment { auth, comment } => {
                 self.routes.comment(&auth, comment)
             }
+            CertAuthEvent::RouteAuthorizationExpiry { auth, expires_at } => {
+                self.routes.expires(&auth, expires_at)
+            }
             CertAuthEvent::RouteAuthorizationRemoved { auth } => {
                 self.routes.remove(&auth);
             }
@@ -395,6 +428,13 @@ impl Aggregate for CertAuth {
                 .unwrap()
                 .roas_updated(updates),
 
+            CertAuthEvent::RouteAuthorizationsScheduled { scheduled } => {
+                self.scheduled_routes.push(scheduled)
+            }
+            CertAuthEvent::RouteAuthorizationsScheduleRemoved { id } => {
+                self.scheduled_routes.retain(|s| s.id() != id)
+            }
+
             //-----------------------------------------------------------------------
             // Autonomous System Provider Authorization
             //-----------------------------------------------------------------------
@@ -416,6 +456,34 @@ impl Aggregate for CertAuth {
                 .unwrap()
                 .aspa_objects_updated(updates),
 
+            //-----------------------------------------------------------------------
+            // Ghostbuster records
+            //-----------------------------------------------------------------------
+            CertAuthEvent::GhostbusterRecordAdded { record } => {
+                self.ghostbusters.add_or_replace(record)
+            }
+            CertAuthEvent::GhostbusterRecordRemoved { resource_class } => {
+                self.ghostbusters.remove(resource_class.as_ref());
+            }
+            CertAuthEvent::GhostbusterObjectUpdated {
+                resource_class_name,
+                update,
+            } => self
+                .resources
+                .get_mut(&resource_class_name)
+                .unwrap()
+                .ghostbuster_updated(update),
+
+            //-----------------------------------------------------------------------
+            // Change Requests
+            //-----------------------------------------------------------------------
+            CertAuthEvent::ChangeRequestSubmitted { request } => {
+                self.change_requests.push(request)
+            }
+            CertAuthEvent::ChangeRequestRemoved { id } => {
+                self.change_requests.retain(|r| r.id() != id)
+            }
+
             //-----------------------------------------------------------------------
             // BGPSec
             //-----------------------------------------------------------------------
@@ -458,6 +526,13 @@ impl Aggregate for CertAuth {
             CertAuthEvent::RtaSigned { name, rta } => {
                 self.rtas.add_signed(name, rta);
             }
+
+            //-----------------------------------------------------------------------
+            // RPKI Signed Checklists
+            //-----------------------------------------------------------------------
+            CertAuthEvent::RscSigned { name, rsc } => {
+                self.rscs.add_signed(name, rsc);
+            }
         }
     }
 
@@ -474,6 +549,8 @@ impl Aggregate for CertAuth {
             );
         }
 
+        let actor = command.actor().to_string();
+
         match command.into_details() {
             // being a parent
             CertAuthCommandDetails::ChildAdd(child, id_cert, resources) => {
@@ -573,6 +650,22 @@ impl Aggregate for CertAuth {
                 config,
                 signer,
             ) => self.route_authorizations_renew(true, &config, &signer),
+            CertAuthCommandDetails::RouteAuthorizationsRemoveExpired(
+                config,
+                signer,
+            ) => self.route_authorizations_remove_expired(&config, signer),
+            CertAuthCommandDetails::RouteAuthorizationsSchedule(request) => {
+                self.route_authorizations_schedule(request)
+            }
+            CertAuthCommandDetails::RouteAuthorizationsScheduleCancel(id) => {
+                self.route_authorizations_schedule_cancel(id)
+            }
+            CertAuthCommandDetails::RouteAuthorizationsScheduleActivate(
+                id,
+                config,
+                signer,
+            ) => self
+                .route_authorizations_schedule_activate(id, &config, signer),
 
             // ASPA
             CertAuthCommandDetails::AspasUpdate(updates, config, signer) => {
@@ -588,6 +681,31 @@ impl Aggregate for CertAuth {
                 self.aspas_renew(&config, &signer)
             }
 
+            // Ghostbuster records
+            CertAuthCommandDetails::GhostbustersUpdate(
+                updates,
+                config,
+                signer,
+            ) => self.ghostbusters_update(updates, &config, &signer),
+            CertAuthCommandDetails::GhostbustersRenew(config, signer) => {
+                self.ghostbusters_renew(&config, &signer)
+            }
+
+            // Change Requests
+            CertAuthCommandDetails::ChangeRequestSubmit(
+                updates,
+                config,
+                signer,
+            ) => self.change_request_submit(updates, actor, &config, signer),
+            CertAuthCommandDetails::ChangeRequestApprove(
+                id,
+                config,
+                signer,
+            ) => self.change_request_approve(id, &actor, &config, signer),
+            CertAuthCommandDetails::ChangeRequestReject(id) => {
+                self.change_request_reject(id)
+            }
+
             // BGPSec
             CertAuthCommandDetails::BgpSecUpdateDefinitions(
                 updates,
@@ -615,6 +733,11 @@ impl Aggregate for CertAuth {
             CertAuthCommandDetails::RtaSign(name, request, signer) => {
                 self.rta_sign(name, request, signer.deref())
             }
+
+            // RPKI Signed Checklists
+            CertAuthCommandDetails::RscSign(name, request, signer) => {
+                self.rsc_sign(name, request, signer.deref())
+            }
         }
     }
 
@@ -1855,6 +1978,7 @@ impl CertAuth {
             &self.routes,
             &self.aspas,
             &self.bgpsec_defs,
+            &self.ghostbusters,
             config,
             signer.deref(),
         )
@@ -2069,6 +2193,132 @@ impl CertAuth {
         Ok(events)
     }
 
+    /// Returns the time when the first time-limited route authorization
+    /// expires, if there is any.
+    pub fn route_authorizations_next_expiry(&self) -> Option<Timestamp> {
+        self.routes.next_expiry()
+    }
+
+    /// Removes time-limited route authorizations which have expired, and
+    /// updates ROAs accordingly. Note that this command is intended to be
+    /// sent by the scheduler, and will only be stored if any authorizations
+    /// expired.
+    fn route_authorizations_remove_expired(
+        &self,
+        config: &Config,
+        signer: Arc<KrillSigner>,
+    ) -> KrillResult<Vec<CertAuthEvent>> {
+        let expired = self.routes.expired(Timestamp::now());
+        if expired.is_empty() {
+            return Ok(vec![]);
+        }
+
+        for auth in &expired {
+            info!("CA '{}' removes expired ROA: '{}'", self.handle, auth);
+        }
+
+        let updates = RoaConfigurationUpdates::new(
+            vec![],
+            expired.into_iter().map(RoaPayload::from).collect(),
+        );
+        self.route_authorizations_update(updates, config, signer)
+    }
+
+    /// Returns the ROA configuration updates which are scheduled to be
+    /// applied later.
+    pub fn route_authorizations_scheduled(&self) -> ScheduledRoaUpdatesList {
+        ScheduledRoaUpdatesList::new(self.scheduled_routes.clone())
+    }
+
+    /// Schedules ROA configuration updates to be applied once their
+    /// 'not before' time has passed. The updates are fully verified when
+    /// they are activated, because the configured ROAs and resources may
+    /// change before then. Here we only reject additions which can never
+    /// be valid, or which are not held by this CA now.
+    fn route_authorizations_schedule(
+        &self,
+        request: ScheduleRoaUpdatesRequest,
+    ) -> KrillResult<Vec<CertAuthEvent>> {
+        let (not_before, updates) = request.unpack();
+        let updates = updates.into_explicit_max_length();
+
+        let mut delta_errors = RoaDeltaError::default();
+        let all_resources = self.all_resources();
+        for roa_configuration in updates.added() {
+            let roa_payload = roa_configuration.payload();
+            if !roa_payload.max_length_valid() {
+                delta_errors.add_invalid_length(roa_configuration.clone());
+            } else if !all_resources
+                .contains_roa_address(&roa_payload.as_roa_ip_address())
+            {
+                delta_errors.add_notheld(roa_configuration.clone());
+            }
+        }
+
+        if !delta_errors.is_empty() {
+            return Err(Error::RoaDeltaError(
+                self.handle().clone(),
+                delta_errors,
+            ));
+        }
+
+        // The version of this CA is unique for each command, so we can
+        // use it as the id for these scheduled updates.
+        let scheduled =
+            ScheduledRoaUpdates::new(self.version, not_before, updates);
+
+        info!(
+            "CA '{}' scheduled ROA updates with id '{}' not before {}",
+            self.handle,
+            scheduled.id(),
+            not_before.to_rfc3339()
+        );
+
+        Ok(vec![CertAuthEvent::RouteAuthorizationsScheduled {
+            scheduled,
+        }])
+    }
+
+    /// Cancels scheduled ROA configuration updates.
+    fn route_authorizations_schedule_cancel(
+        &self,
+        id: u64,
+    ) -> KrillResult<Vec<CertAuthEvent>> {
+        if self.scheduled_routes.iter().any(|s| s.id() == id) {
+            Ok(vec![CertAuthEvent::RouteAuthorizationsScheduleRemoved {
+                id,
+            }])
+        } else {
+            Err(Error::CaRoaScheduledUnknown(self.handle.clone(), id))
+        }
+    }
+
+    /// Applies scheduled ROA configuration updates, and removes them from
+    /// the pending updates.
+    fn route_authorizations_schedule_activate(
+        &self,
+        id: u64,
+        config: &Config,
+        signer: Arc<KrillSigner>,
+    ) -> KrillResult<Vec<CertAuthEvent>> {
+        let scheduled = self
+            .scheduled_routes
+            .iter()
+            .find(|s| s.id() == id)
+            .ok_or_else(|| {
+            Error::CaRoaScheduledUnknown(self.handle.clone(), id)
+        })?;
+
+        let mut events = self.route_authorizations_update(
+            scheduled.updates().clone(),
+            config,
+            signer,
+        )?;
+        events.push(CertAuthEvent::RouteAuthorizationsScheduleRemoved { id });
+
+        Ok(events)
+    }
+
     /// Renew existing ROA objects if needed.
     pub fn route_authorizations_renew(
         &self,
@@ -2142,6 +2392,7 @@ impl CertAuth {
         for roa_configuration in updates.added() {
             let roa_payload = roa_configuration.payload();
             let comment = roa_configuration.comment();
+            let expires_at = roa_configuration.expires_at();
 
             let auth = RoaPayloadJsonMapKey::from(roa_payload);
 
@@ -2155,13 +2406,27 @@ impl CertAuth {
                 delta_errors.add_notheld(roa_configuration.clone());
             } else if let Some(info) = desired_routes.info(&auth) {
                 // We have an existing info for this payload, this may be an
-                // attempt to update the comment.
-                if info.comment() != comment {
+                // attempt to update the comment or the expiry time.
+                let comment_changed = info.comment() != comment;
+                let expiry_changed = info.expires_at() != expires_at;
+
+                if comment_changed {
                     // Update comment
                     res.push(CertAuthEvent::RouteAuthorizationComment {
                         auth,
                         comment: comment.cloned(),
                     });
+                }
+                if expiry_changed {
+                    // Update expiry time
+                    res.push(CertAuthEvent::RouteAuthorizationExpiry {
+                        auth,
+                        expires_at,
+                    });
+                }
+                if comment_changed || expiry_changed {
+                    desired_routes.comment(&auth, comment.cloned());
+                    desired_routes.expires(&auth, expires_at);
                 } else {
                     // Duplicate entry. We could be idempotent, but perhaps
                     // it's best to return an error
@@ -2181,6 +2446,14 @@ impl CertAuth {
                         comment: comment.cloned(),
                     });
                 }
+
+                if expires_at.is_some() {
+                    desired_routes.expires(&auth, expires_at);
+                    res.push(CertAuthEvent::RouteAuthorizationExpiry {
+                        auth,
+                        expires_at,
+                    });
+                }
             }
         }
 
@@ -2422,6 +2695,219 @@ impl CertAuth {
     }
 }
 
+/// # Ghostbuster records
+impl CertAuth {
+    /// Show the current Ghostbuster records
+    pub fn ghostbusters_show(&self) -> GhostbusterRecords {
+        self.ghostbusters.records()
+    }
+
+    /// Process GhostbusterRecordUpdates:
+    /// - remove records to be removed
+    /// - add new records, or replace existing
+    ///
+    /// And (re-)issue or remove the Ghostbuster record objects in the
+    /// resource classes affected by this.
+    pub fn ghostbusters_update(
+        &self,
+        updates: GhostbusterRecordUpdates,
+        config: &Config,
+        signer: &KrillSigner,
+    ) -> KrillResult<Vec<CertAuthEvent>> {
+        let mut events = vec![];
+
+        let (add_or_replace, remove) = updates.unpack();
+
+        // Keep track of a copy of the definitions so we can use it to
+        // update the objects in each resource class
+        let mut all_ghostbusters = self.ghostbusters.clone();
+
+        for resource_class in remove {
+            if !all_ghostbusters.remove(resource_class.as_ref()) {
+                return Err(Error::GhostbusterRecordUnknown(
+                    self.handle.clone(),
+                    resource_class,
+                ));
+            }
+            events.push(CertAuthEvent::GhostbusterRecordRemoved {
+                resource_class,
+            });
+        }
+
+        for record in add_or_replace {
+            record.vcard().validate().map_err(|msg| {
+                Error::GhostbusterRecordInvalid(self.handle.clone(), msg)
+            })?;
+
+            if let Some(rcn) = record.resource_class() {
+                if !self.resources.contains_key(rcn) {
+                    return Err(Error::ResourceClassUnknown(rcn.clone()));
+                }
+            }
+
+            // Nothing to do if the record is unchanged
+            if all_ghostbusters.get(record.resource_class())
+                == Some(record.vcard())
+            {
+                continue;
+            }
+
+            all_ghostbusters.add_or_replace(record.clone());
+            events.push(CertAuthEvent::GhostbusterRecordAdded { record });
+        }
+
+        for (rcn, rc) in self.resources.iter() {
+            if let Some(update) =
+                rc.update_ghostbuster(&all_ghostbusters, config, signer)?
+            {
+                events.push(CertAuthEvent::GhostbusterObjectUpdated {
+                    resource_class_name: rcn.clone(),
+                    update,
+                });
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Renew existing Ghostbuster record objects if needed.
+    pub fn ghostbusters_renew(
+        &self,
+        config: &Config,
+        signer: &KrillSigner,
+    ) -> KrillResult<Vec<CertAuthEvent>> {
+        let mut events = vec![];
+
+        for (rcn, rc) in self.resources.iter() {
+            if let Some(update) =
+                rc.renew_ghostbuster(&config.issuance_timing, signer)?
+            {
+                info!(
+                    "CA '{}' reissued Ghostbuster record under RC '{}' before it would expire",
+                    self.handle, rcn
+                );
+
+                events.push(CertAuthEvent::GhostbusterObjectUpdated {
+                    resource_class_name: rcn.clone(),
+                    update,
+                });
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+/// # Change Requests
+impl CertAuth {
+    /// Returns the route and ASPA change requests which are pending
+    /// approval.
+    pub fn change_requests(&self) -> ChangeRequestList {
+        ChangeRequestList::new(self.change_requests.clone())
+    }
+
+    /// Submits route or ASPA updates which need to be approved by a
+    /// different user. The updates are verified now so that requests
+    /// which would be rejected are not stored. They are verified again
+    /// when they are approved, because the CA may have changed by then.
+    fn change_request_submit(
+        &self,
+        updates: ChangeRequestUpdates,
+        requested_by: String,
+        config: &Config,
+        signer: Arc<KrillSigner>,
+    ) -> KrillResult<Vec<CertAuthEvent>> {
+        self.change_request_updates_apply(updates.clone(), config, signer)?;
+
+        // The version of this CA is unique for each command, so we can
+        // use it as the id for this request.
+        let request = ChangeRequest::new(
+            self.version,
+            requested_by,
+            Timestamp::now(),
+            updates,
+        );
+
+        info!(
+            "CA '{}' received change request with id '{}' from '{}'",
+            self.handle,
+            request.id(),
+            request.requested_by()
+        );
+
+        Ok(vec![CertAuthEvent::ChangeRequestSubmitted { request }])
+    }
+
+    /// Approves a change request, applies its updates, and removes it
+    /// from the pending requests. Requests cannot be approved by the
+    /// user who submitted them.
+    fn change_request_approve(
+        &self,
+        id: u64,
+        approved_by: &str,
+        config: &Config,
+        signer: Arc<KrillSigner>,
+    ) -> KrillResult<Vec<CertAuthEvent>> {
+        let request = self
+            .change_requests
+            .iter()
+            .find(|r| r.id() == id)
+            .ok_or_else(|| {
+                Error::CaChangeRequestUnknown(self.handle.clone(), id)
+            })?;
+
+        if request.requested_by() == approved_by {
+            return Err(Error::CaChangeRequestSelfApproval(
+                self.handle.clone(),
+                id,
+            ));
+        }
+
+        let mut events = self.change_request_updates_apply(
+            request.updates().clone(),
+            config,
+            signer,
+        )?;
+        events.push(CertAuthEvent::ChangeRequestRemoved { id });
+
+        Ok(events)
+    }
+
+    /// Rejects a change request, without applying its updates.
+    fn change_request_reject(
+        &self,
+        id: u64,
+    ) -> KrillResult<Vec<CertAuthEvent>> {
+        if self.change_requests.iter().any(|r| r.id() == id) {
+            Ok(vec![CertAuthEvent::ChangeRequestRemoved { id }])
+        } else {
+            Err(Error::CaChangeRequestUnknown(self.handle.clone(), id))
+        }
+    }
+
+    fn change_request_updates_apply(
+        &self,
+        updates: ChangeRequestUpdates,
+        config: &Config,
+        signer: Arc<KrillSigner>,
+    ) -> KrillResult<Vec<CertAuthEvent>> {
+        match updates {
+            ChangeRequestUpdates::Routes(updates) => {
+                self.route_authorizations_update(updates, config, signer)
+            }
+            ChangeRequestUpdates::ScheduledRoutes(request) => {
+                self.route_authorizations_schedule(request)
+            }
+            ChangeRequestUpdates::Aspas(updates) => {
+                self.aspas_definitions_update(updates, config, &signer)
+            }
+            ChangeRequestUpdates::AspaProviders { customer, update } => {
+                self.aspas_update(customer, update, config, &signer)
+            }
+        }
+    }
+}
+
 /// # BGPSec
 impl CertAuth {
     pub fn bgpsec_definitions_show(&self) -> BgpSecCsrInfoList {
@@ -2786,6 +3272,100 @@ impl CertAuth {
     }
 }
 
+/// # RPKI Signed Checklists
+impl CertAuth {
+    pub fn rsc_list(&self) -> RscList {
+        self.rscs.list()
+    }
+
+    pub fn rsc_show(&self, name: &str) -> KrillResult<RpkiSignedChecklist> {
+        self.rscs.signed_rsc(name)
+    }
+
+    /// Sign a new RSC
+    fn rsc_sign(
+        &self,
+        name: RscName,
+        request: RscContentRequest,
+        signer: &KrillSigner,
+    ) -> KrillResult<Vec<CertAuthEvent>> {
+        let (resources, validity, checklist) = request.unpack();
+
+        if self.rscs.has(&name) {
+            return Err(Error::Custom(format!(
+                "RSC with name '{}' already exists",
+                name
+            )));
+        }
+
+        // An RSC is signed using a single EE certificate, so all its
+        // resources must be held in one resource class.
+        let (rcn, rc) = self
+            .resources
+            .iter()
+            .find(|(_, rc)| {
+                rc.current_resources()
+                    .map(|held| held.contains(&resources))
+                    .unwrap_or(false)
+            })
+            .ok_or(Error::RscResourcesNotHeld)?;
+
+        let builder =
+            RpkiSignedChecklist::rsc_builder(&resources, checklist)?;
+
+        // Like RTAs, RSCs use an EE certificate without a signed object
+        // SIA, and with explicit resources.
+        let key = signer.create_key()?;
+        let signed = rc
+            .create_rta_ee(&resources, validity, key, signer)
+            .and_then(|ee| {
+                let revocation = Revocation::from(&ee);
+                let rsc = signer.sign_rsc(builder, ee)?;
+                Ok((revocation, rsc))
+            });
+
+        // The one-off key is not needed anymore, also if signing failed.
+        signer.destroy_key(&key)?;
+        let (revocation, rsc) = signed?;
+
+        let rsc = SignedRsc::new(resources, rcn.clone(), revocation, rsc);
+
+        info!("CA '{}' signed an RSC object named '{}'", self.handle, name);
+
+        Ok(vec![CertAuthEvent::RscSigned { name, rsc }])
+    }
+
+    /// Verify that an RSC was signed by the current key of one of the
+    /// resource classes of this CA, and that the given files are on its
+    /// checklist.
+    pub fn rsc_verify(
+        &self,
+        request: RscVerifyRequest,
+    ) -> KrillResult<RscVerification> {
+        let (rsc, files) = request.unpack();
+
+        let object = rsc.decode()?;
+        let issuer_key = object.issuer_key()?;
+
+        let (rcn, key) = self
+            .resources
+            .iter()
+            .find_map(|(rcn, rc)| {
+                rc.current_key()
+                    .filter(|key| key.key_id() == &issuer_key)
+                    .map(|key| (rcn, key))
+            })
+            .ok_or_else(|| {
+                Error::RscInvalid(format!(
+                    "not signed by a current key of CA '{}'",
+                    self.handle
+                ))
+            })?;
+
+        object.validate(rcn.clone(), key.incoming_cert(), files)
+    }
+}
+
 /// # Deactivate
 impl CertAuth {
     pub fn revoke_under_parent(
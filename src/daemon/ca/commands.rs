@@ -18,10 +18,11 @@ use crate::{
         actor::Actor,
         api::{
             import::ImportChild, AspaDefinitionUpdates, AspaProvidersUpdate,
-            BgpSecDefinitionUpdates, CertAuthStorableCommand, CustomerAsn,
+            BgpSecDefinitionUpdates, CertAuthStorableCommand,
+            ChangeRequestUpdates, CustomerAsn, GhostbusterRecordUpdates,
             IdCertInfo, ParentCaContact, ReceivedCert, RepositoryContact,
-            ResourceClassNameMapping, RoaConfigurationUpdates, RtaName,
-            StorableRcEntitlement,
+            ResourceClassNameMapping, RoaConfigurationUpdates, RscName,
+            RtaName, ScheduleRoaUpdatesRequest, StorableRcEntitlement,
         },
         crypto::KrillSigner,
         eventsourcing::{
@@ -31,8 +32,8 @@ use crate::{
     },
     daemon::{
         ca::{
-            CertAuthEvent, ResourceTaggedAttestation, RtaContentRequest,
-            RtaPrepareRequest,
+            CertAuthEvent, ResourceTaggedAttestation, RscContentRequest,
+            RtaContentRequest, RtaPrepareRequest,
         },
         config::Config,
     },
@@ -217,6 +218,22 @@ pub enum CertAuthCommandDetails {
     // Re-issue all ROA objects regardless of their expiration time.
     RouteAuthorizationsForceRenew(Arc<Config>, Arc<KrillSigner>),
 
+    // Remove time-limited authorizations which have expired. Note that
+    // this command is intended to be sent by the scheduler, and will only
+    // be stored if any authorizations expired.
+    RouteAuthorizationsRemoveExpired(Arc<Config>, Arc<KrillSigner>),
+
+    // Schedule updates to the authorizations, to be applied by the
+    // scheduler once their 'not before' time has passed.
+    RouteAuthorizationsSchedule(ScheduleRoaUpdatesRequest),
+
+    // Cancel scheduled updates to the authorizations.
+    RouteAuthorizationsScheduleCancel(u64),
+
+    // Apply scheduled updates to the authorizations. Note that this
+    // command is intended to be sent by the scheduler.
+    RouteAuthorizationsScheduleActivate(u64, Arc<Config>, Arc<KrillSigner>),
+
     // ------------------------------------------------------------
     // ASPA Support
     // ------------------------------------------------------------
@@ -239,6 +256,37 @@ pub enum CertAuthCommandDetails {
     // will only be stored if there are any updates to be done.
     AspasRenew(Arc<Config>, Arc<KrillSigner>),
 
+    // ------------------------------------------------------------
+    // Ghostbuster records
+    // ------------------------------------------------------------
+
+    // Update the Ghostbuster records, adding new, replacing existing, or
+    // removing records.
+    GhostbustersUpdate(
+        GhostbusterRecordUpdates,
+        Arc<Config>,
+        Arc<KrillSigner>,
+    ),
+
+    // Re-issue Ghostbuster record objects which would otherwise expire in
+    // some time (default 4 weeks, configurable). Like the other renew
+    // commands this is intended to be sent by the scheduler.
+    GhostbustersRenew(Arc<Config>, Arc<KrillSigner>),
+
+    // ------------------------------------------------------------
+    // Change Requests
+    // ------------------------------------------------------------
+
+    // Submit route or ASPA updates which need to be approved by a
+    // different user before they are applied.
+    ChangeRequestSubmit(ChangeRequestUpdates, Arc<Config>, Arc<KrillSigner>),
+
+    // Approve and apply the updates in a pending change request.
+    ChangeRequestApprove(u64, Arc<Config>, Arc<KrillSigner>),
+
+    // Reject a pending change request.
+    ChangeRequestReject(u64),
+
     // ------------------------------------------------------------
     // BGPSec Support
     // ------------------------------------------------------------
@@ -273,6 +321,13 @@ pub enum CertAuthCommandDetails {
 
     // Co-sign an existing multi-signed RTA
     RtaCoSign(RtaName, ResourceTaggedAttestation, Arc<KrillSigner>),
+
+    // ------------------------------------------------------------
+    // RPKI Signed Checklists
+    // ------------------------------------------------------------
+
+    // Sign a new RSC
+    RscSign(RscName, RscContentRequest, Arc<KrillSigner>),
 }
 
 impl eventsourcing::CommandDetails for CertAuthCommandDetails {
@@ -449,6 +504,30 @@ impl From<CertAuthCommandDetails> for CertAuthStorableCommand {
             CertAuthCommandDetails::RouteAuthorizationsForceRenew(_, _) => {
                 CertAuthStorableCommand::ForceReissue
             }
+            CertAuthCommandDetails::RouteAuthorizationsRemoveExpired(_, _) => {
+                CertAuthStorableCommand::RoaDefinitionsExpired
+            }
+            CertAuthCommandDetails::RouteAuthorizationsSchedule(request) => {
+                let (not_before, updates) = request.unpack();
+                CertAuthStorableCommand::RoaDefinitionUpdatesScheduled {
+                    not_before,
+                    updates,
+                }
+            }
+            CertAuthCommandDetails::RouteAuthorizationsScheduleCancel(id) => {
+                CertAuthStorableCommand::RoaDefinitionUpdatesScheduleCancelled {
+                    id,
+                }
+            }
+            CertAuthCommandDetails::RouteAuthorizationsScheduleActivate(
+                id,
+                _,
+                _,
+            ) => {
+                CertAuthStorableCommand::RoaDefinitionUpdatesScheduleActivated {
+                    id,
+                }
+            }
 
             // ------------------------------------------------------------
             // ASPA Support
@@ -469,6 +548,29 @@ impl From<CertAuthCommandDetails> for CertAuthStorableCommand {
                 CertAuthStorableCommand::ReissueBeforeExpiring
             }
 
+            // ------------------------------------------------------------
+            // Ghostbuster records
+            // ------------------------------------------------------------
+            CertAuthCommandDetails::GhostbustersUpdate(updates, _, _) => {
+                CertAuthStorableCommand::GhostbustersUpdate { updates }
+            }
+            CertAuthCommandDetails::GhostbustersRenew(_, _) => {
+                CertAuthStorableCommand::ReissueBeforeExpiring
+            }
+
+            // ------------------------------------------------------------
+            // Change Requests
+            // ------------------------------------------------------------
+            CertAuthCommandDetails::ChangeRequestSubmit(updates, _, _) => {
+                CertAuthStorableCommand::ChangeRequestSubmitted { updates }
+            }
+            CertAuthCommandDetails::ChangeRequestApprove(id, _, _) => {
+                CertAuthStorableCommand::ChangeRequestApproved { id }
+            }
+            CertAuthCommandDetails::ChangeRequestReject(id) => {
+                CertAuthStorableCommand::ChangeRequestRejected { id }
+            }
+
             // ------------------------------------------------------------
             // BGPSec Support
             // ------------------------------------------------------------
@@ -500,6 +602,13 @@ impl From<CertAuthCommandDetails> for CertAuthStorableCommand {
             CertAuthCommandDetails::RtaCoSign(name, _, _) => {
                 CertAuthStorableCommand::RtaCoSign { name }
             }
+
+            // ------------------------------------------------------------
+            // RPKI Signed Checklists
+            // ------------------------------------------------------------
+            CertAuthCommandDetails::RscSign(name, _, _) => {
+                CertAuthStorableCommand::RscSign { name }
+            }
         }
     }
 }
@@ -855,6 +964,49 @@ impl CertAuthCommandDetails {
         )
     }
 
+    pub fn route_authorizations_schedule(
+        handle: &CaHandle,
+        request: ScheduleRoaUpdatesRequest,
+        actor: &Actor,
+    ) -> CertAuthCommand {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CertAuthCommandDetails::RouteAuthorizationsSchedule(request),
+            actor,
+        )
+    }
+
+    pub fn route_authorizations_schedule_cancel(
+        handle: &CaHandle,
+        id: u64,
+        actor: &Actor,
+    ) -> CertAuthCommand {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CertAuthCommandDetails::RouteAuthorizationsScheduleCancel(id),
+            actor,
+        )
+    }
+
+    pub fn route_authorizations_schedule_activate(
+        handle: &CaHandle,
+        id: u64,
+        config: Arc<Config>,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> CertAuthCommand {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CertAuthCommandDetails::RouteAuthorizationsScheduleActivate(
+                id, config, signer,
+            ),
+            actor,
+        )
+    }
+
     //-------------------------------------------------------------------------------
     // Autonomous System Provider Authorization
     //-------------------------------------------------------------------------------
@@ -891,6 +1043,74 @@ impl CertAuthCommandDetails {
         )
     }
 
+    //-------------------------------------------------------------------------------
+    // Ghostbuster records
+    //-------------------------------------------------------------------------------
+    pub fn ghostbusters_update(
+        ca: &CaHandle,
+        updates: GhostbusterRecordUpdates,
+        config: Arc<Config>,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> CertAuthCommand {
+        eventsourcing::SentCommand::new(
+            ca,
+            None,
+            CertAuthCommandDetails::GhostbustersUpdate(
+                updates, config, signer,
+            ),
+            actor,
+        )
+    }
+
+    //-------------------------------------------------------------------------------
+    // Change Requests
+    //-------------------------------------------------------------------------------
+    pub fn change_request_submit(
+        ca: &CaHandle,
+        updates: ChangeRequestUpdates,
+        config: Arc<Config>,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> CertAuthCommand {
+        eventsourcing::SentCommand::new(
+            ca,
+            None,
+            CertAuthCommandDetails::ChangeRequestSubmit(
+                updates, config, signer,
+            ),
+            actor,
+        )
+    }
+
+    pub fn change_request_approve(
+        ca: &CaHandle,
+        id: u64,
+        config: Arc<Config>,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> CertAuthCommand {
+        eventsourcing::SentCommand::new(
+            ca,
+            None,
+            CertAuthCommandDetails::ChangeRequestApprove(id, config, signer),
+            actor,
+        )
+    }
+
+    pub fn change_request_reject(
+        ca: &CaHandle,
+        id: u64,
+        actor: &Actor,
+    ) -> CertAuthCommand {
+        eventsourcing::SentCommand::new(
+            ca,
+            None,
+            CertAuthCommandDetails::ChangeRequestReject(id),
+            actor,
+        )
+    }
+
     //-------------------------------------------------------------------------------
     // BGPSec
     //-------------------------------------------------------------------------------
@@ -958,4 +1178,22 @@ impl CertAuthCommandDetails {
             actor,
         )
     }
+
+    //-------------------------------------------------------------------------------
+    // RPKI Signed Checklists
+    //-------------------------------------------------------------------------------
+    pub fn rsc_sign(
+        handle: &CaHandle,
+        name: RscName,
+        request: RscContentRequest,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> CertAuthCommand {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CertAuthCommandDetails::RscSign(name, request, signer),
+            actor,
+        )
+    }
 }
@@ -15,18 +15,19 @@ use rpki::{
 use crate::{
     commons::{
         api::{
-            AspaDefinition, AspaProvidersUpdate, BgpSecAsnKey, CustomerAsn,
-            IdCertInfo, IssuedCertificate, ObjectName, ParentCaContact,
-            ReceivedCert, RepositoryContact, ResourceClassNameMapping,
-            RoaAggregateKey, RtaName, SuspendedCert, UnsuspendedCert,
+            AspaDefinition, AspaProvidersUpdate, BgpSecAsnKey, ChangeRequest,
+            CustomerAsn, GhostbusterRecord, IdCertInfo, IssuedCertificate,
+            ObjectName, ParentCaContact, ReceivedCert, RepositoryContact,
+            ResourceClassNameMapping, RoaAggregateKey, RscName, RtaName,
+            ScheduledRoaUpdates, SuspendedCert, Timestamp, UnsuspendedCert,
         },
         crypto::KrillSigner,
         eventsourcing::{Event, InitEvent},
         KrillResult,
     },
     daemon::ca::{
-        AspaInfo, CertifiedKey, PreparedRta, RoaInfo, RoaPayloadJsonMapKey,
-        SignedRta,
+        AspaInfo, CertifiedKey, GhostbusterInfo, PreparedRta, RoaInfo,
+        RoaPayloadJsonMapKey, SignedRsc, SignedRta,
     },
 };
 
@@ -293,6 +294,17 @@ impl AspaObjectsUpdates {
     }
 }
 
+//------------ GhostbusterObjectUpdate -------------------------------------
+
+/// Tracks the (re-)issuance or removal of the Ghostbuster record object in
+/// a resource class.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GhostbusterObjectUpdate {
+    Issued(GhostbusterInfo),
+    Removed,
+}
+
 //------------ BgpSecCertificateUpdates ------------------------------------
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct BgpSecCertificateUpdates {
@@ -601,6 +613,12 @@ pub enum CertAuthEvent {
         auth: RoaPayloadJsonMapKey,
         comment: Option<String>,
     },
+    RouteAuthorizationExpiry {
+        // Time-limited authorizations are removed automatically, using a
+        // RouteAuthorizationRemoved event, once they expire.
+        auth: RoaPayloadJsonMapKey,
+        expires_at: Option<Timestamp>,
+    },
     RouteAuthorizationRemoved {
         // Tracks a single authorization (VRP) which is removed. See remark
         // for RouteAuthorizationAdded.
@@ -611,6 +629,16 @@ pub enum CertAuthEvent {
         resource_class_name: ResourceClassName,
         updates: RoaUpdates,
     },
+    RouteAuthorizationsScheduled {
+        // Tracks ROA configuration updates which will be applied once
+        // their 'not before' time has passed.
+        scheduled: ScheduledRoaUpdates,
+    },
+    RouteAuthorizationsScheduleRemoved {
+        // Scheduled updates are removed when they are cancelled, or when
+        // they are activated.
+        id: u64,
+    },
 
     // ASPA
     AspaConfigAdded {
@@ -629,6 +657,34 @@ pub enum CertAuthEvent {
         updates: AspaObjectsUpdates,
     },
 
+    // Ghostbuster records
+    GhostbusterRecordAdded {
+        // Adds or replaces the record for a resource class, or for all
+        // resource classes if it has no resource class.
+        record: GhostbusterRecord,
+    },
+    GhostbusterRecordRemoved {
+        resource_class: Option<ResourceClassName>,
+    },
+    GhostbusterObjectUpdated {
+        // Tracks the Ghostbuster record *object* which is (re-)issued or
+        // removed in a resource class.
+        resource_class_name: ResourceClassName,
+        update: GhostbusterObjectUpdate,
+    },
+
+    // Change Requests
+    ChangeRequestSubmitted {
+        // Tracks route or ASPA updates which need to be approved by a
+        // different user before they are applied.
+        request: ChangeRequest,
+    },
+    ChangeRequestRemoved {
+        // Change requests are removed when they are approved, or when
+        // they are rejected.
+        id: u64,
+    },
+
     // BGPSec
     BgpSecDefinitionAdded {
         key: BgpSecAsnKey,
@@ -672,6 +728,13 @@ pub enum CertAuthEvent {
         name: RtaName,
         prepared: PreparedRta,
     },
+
+    // Rsc
+    RscSigned {
+        // Adds a signed RPKI Signed Checklist.
+        name: RscName,
+        rsc: SignedRsc,
+    },
 }
 
 impl Event for CertAuthEvent {}
@@ -988,7 +1051,23 @@ impl fmt::Display for CertAuthEvent {
                     write!(f, "removed comment from ROA: '{}'", auth)
                 }
             }
+            CertAuthEvent::RouteAuthorizationExpiry { auth, expires_at } => {
+                if let Some(expires_at) = expires_at {
+                    write!(f, "ROA: '{}' expires at {}", auth, expires_at.to_rfc3339())
+                } else {
+                    write!(f, "removed expiry from ROA: '{}'", auth)
+                }
+            }
             CertAuthEvent::RouteAuthorizationRemoved { auth } => write!(f, "removed ROA: '{}'", auth),
+            CertAuthEvent::RouteAuthorizationsScheduled { scheduled } => write!(
+                f,
+                "scheduled ROA updates with id '{}' not before {}",
+                scheduled.id(),
+                scheduled.not_before().to_rfc3339()
+            ),
+            CertAuthEvent::RouteAuthorizationsScheduleRemoved { id } => {
+                write!(f, "removed scheduled ROA updates with id '{}'", id)
+            }
             CertAuthEvent::RoasUpdated {
                 resource_class_name,
                 updates,
@@ -1043,6 +1122,43 @@ impl fmt::Display for CertAuthEvent {
                 Ok(())
             }
 
+            // Ghostbuster records
+            CertAuthEvent::GhostbusterRecordAdded { record } => {
+                write!(f, "added Ghostbuster record for {}", record)
+            }
+            CertAuthEvent::GhostbusterRecordRemoved { resource_class } => match resource_class {
+                Some(rcn) => write!(f, "removed Ghostbuster record for resource class '{}'", rcn),
+                None => write!(f, "removed Ghostbuster record for all resource classes"),
+            },
+            CertAuthEvent::GhostbusterObjectUpdated {
+                resource_class_name,
+                update,
+            } => match update {
+                GhostbusterObjectUpdate::Issued(_) => write!(
+                    f,
+                    "issued {} under resource class '{}'",
+                    ObjectName::ghostbusters(),
+                    resource_class_name
+                ),
+                GhostbusterObjectUpdate::Removed => write!(
+                    f,
+                    "removed {} under resource class '{}'",
+                    ObjectName::ghostbusters(),
+                    resource_class_name
+                ),
+            },
+
+            // Change Requests
+            CertAuthEvent::ChangeRequestSubmitted { request } => write!(
+                f,
+                "change request with id '{}' submitted by '{}'",
+                request.id(),
+                request.requested_by()
+            ),
+            CertAuthEvent::ChangeRequestRemoved { id } => {
+                write!(f, "removed change request with id '{}'", id)
+            }
+
             // BGPSec
             CertAuthEvent::BgpSecDefinitionAdded { key, .. } => {
                 write!(
@@ -1110,6 +1226,11 @@ impl fmt::Display for CertAuthEvent {
             CertAuthEvent::RtaSigned { name, rta } => {
                 write!(f, "Signed RTA '{}' for resources: {}", name, rta.resources())
             }
+
+            // Rsc
+            CertAuthEvent::RscSigned { name, rsc } => {
+                write!(f, "Signed RSC '{}' for resources: {}", name, rsc.resources())
+            }
         }
     }
 }
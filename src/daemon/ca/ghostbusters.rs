@@ -0,0 +1,309 @@
+//! Ghostbuster records
+//!
+//! See: https://www.rfc-editor.org/rfc/rfc6493.html
+
+use std::collections::HashMap;
+
+use bcder::{encode::Values, Mode};
+use rpki::{
+    ca::{provisioning::ResourceClassName, publication::Base64},
+    repository::{
+        sigobj::{SignedObject, SignedObjectBuilder},
+        x509::{Serial, Time, Validity},
+    },
+    rrdp::Hash,
+    uri,
+};
+
+use crate::{
+    commons::{
+        api::{GhostbusterRecord, GhostbusterRecords, ObjectName, VCard},
+        crypto::KrillSigner,
+        KrillResult,
+    },
+    daemon::{
+        ca::{CertifiedKey, GhostbusterObjectUpdate},
+        config::IssuanceTimingConfig,
+    },
+};
+
+pub fn make_ghostbuster_object(
+    vcard: &VCard,
+    certified_key: &CertifiedKey,
+    validity: Validity,
+    signer: &KrillSigner,
+) -> KrillResult<SignedObject> {
+    let object_builder = {
+        let incoming_cert = certified_key.incoming_cert();
+
+        let crl_uri = incoming_cert.crl_uri();
+        let gbr_uri = incoming_cert.uri_for_name(&ObjectName::ghostbusters());
+        let ca_issuer = incoming_cert.uri().clone();
+
+        let mut object_builder = SignedObjectBuilder::new(
+            signer.random_serial()?,
+            validity,
+            crl_uri,
+            ca_issuer,
+            gbr_uri,
+        );
+        object_builder.set_issuer(Some(incoming_cert.subject().clone()));
+        object_builder.set_signing_time(Some(Time::now()));
+
+        // The EE certificate of a Ghostbuster record does not certify
+        // any resources of its own, see section 4 of RFC 6493.
+        object_builder.set_v4_resources_inherit();
+        object_builder.set_v6_resources_inherit();
+        object_builder.set_as_resources_inherit();
+
+        object_builder
+    };
+
+    Ok(signer.sign_ghostbusters(
+        vcard.to_bytes(),
+        object_builder,
+        certified_key.key_id(),
+    )?)
+}
+
+//------------ GhostbusterDefinitions --------------------------------------
+
+/// This type contains the Ghostbuster records configured for a CA. A CA
+/// can have one record that applies to all its resource classes, and it
+/// can override this with a specific record for a resource class.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GhostbusterDefinitions {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    ca: Option<VCard>,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    resource_classes: HashMap<ResourceClassName, VCard>,
+}
+
+impl GhostbusterDefinitions {
+    // Add or replace a record
+    pub fn add_or_replace(&mut self, record: GhostbusterRecord) {
+        match record.unpack() {
+            (Some(rcn), vcard) => {
+                self.resource_classes.insert(rcn, vcard);
+            }
+            (None, vcard) => self.ca = Some(vcard),
+        }
+    }
+
+    // Remove a record, returns false if there was no such record
+    pub fn remove(
+        &mut self,
+        resource_class: Option<&ResourceClassName>,
+    ) -> bool {
+        match resource_class {
+            Some(rcn) => self.resource_classes.remove(rcn).is_some(),
+            None => self.ca.take().is_some(),
+        }
+    }
+
+    pub fn get(
+        &self,
+        resource_class: Option<&ResourceClassName>,
+    ) -> Option<&VCard> {
+        match resource_class {
+            Some(rcn) => self.resource_classes.get(rcn),
+            None => self.ca.as_ref(),
+        }
+    }
+
+    /// Returns the vCard that should be published in the resource class,
+    /// i.e. the one set for the resource class, or the one set for the CA.
+    pub fn vcard_for(&self, rcn: &ResourceClassName) -> Option<&VCard> {
+        self.resource_classes.get(rcn).or(self.ca.as_ref())
+    }
+
+    /// Returns all records, the record for all resource classes first.
+    pub fn records(&self) -> GhostbusterRecords {
+        let mut records: Vec<GhostbusterRecord> = self
+            .ca
+            .iter()
+            .map(|vcard| GhostbusterRecord::new(None, vcard.clone()))
+            .collect();
+
+        let mut rc_records: Vec<GhostbusterRecord> = self
+            .resource_classes
+            .iter()
+            .map(|(rcn, vcard)| {
+                GhostbusterRecord::new(Some(rcn.clone()), vcard.clone())
+            })
+            .collect();
+        rc_records.sort_by(|a, b| {
+            a.resource_class()
+                .map(|rcn| rcn.to_string())
+                .cmp(&b.resource_class().map(|rcn| rcn.to_string()))
+        });
+
+        records.append(&mut rc_records);
+        GhostbusterRecords::new(records)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ca.is_none() && self.resource_classes.is_empty()
+    }
+}
+
+//------------ GhostbusterObject -------------------------------------------
+
+/// The Ghostbuster record object held by a resource class in a CA, if any.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GhostbusterObject(Option<GhostbusterInfo>);
+
+impl GhostbusterObject {
+    fn make_ghostbuster(
+        vcard: &VCard,
+        certified_key: &CertifiedKey,
+        issuance_timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<GhostbusterInfo> {
+        let object = make_ghostbuster_object(
+            vcard,
+            certified_key,
+            issuance_timing.new_ghostbusters_validity(),
+            signer,
+        )?;
+        Ok(GhostbusterInfo::new(vcard.clone(), object))
+    }
+
+    /// Issues a new object if the vCard that applies to this resource
+    /// class changed, or removes the current object if no vCard applies.
+    ///
+    /// Note: we pass in *all* definitions for the CA, the relevant vCard
+    /// is selected based on the resource class name.
+    pub fn update(
+        &self,
+        all_ghostbusters: &GhostbusterDefinitions,
+        rcn: &ResourceClassName,
+        certified_key: &CertifiedKey,
+        issuance_timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<Option<GhostbusterObjectUpdate>> {
+        match (all_ghostbusters.vcard_for(rcn), &self.0) {
+            (Some(vcard), Some(existing)) if existing.vcard() == vcard => {
+                Ok(None)
+            }
+            (Some(vcard), _) => {
+                let info = Self::make_ghostbuster(
+                    vcard,
+                    certified_key,
+                    issuance_timing,
+                    signer,
+                )?;
+                Ok(Some(GhostbusterObjectUpdate::Issued(info)))
+            }
+            (None, Some(_)) => Ok(Some(GhostbusterObjectUpdate::Removed)),
+            (None, None) => Ok(None),
+        }
+    }
+
+    // Re-new the object, if the renew_threshold is specified, then
+    // it will only be renewed if it expires before that time.
+    pub fn renew(
+        &self,
+        certified_key: &CertifiedKey,
+        renew_threshold: Option<Time>,
+        issuance_timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<Option<GhostbusterObjectUpdate>> {
+        match &self.0 {
+            Some(existing)
+                if renew_threshold
+                    .map(|threshold| existing.expires() < threshold)
+                    .unwrap_or(true) =>
+            {
+                let info = Self::make_ghostbuster(
+                    existing.vcard(),
+                    certified_key,
+                    issuance_timing,
+                    signer,
+                )?;
+                Ok(Some(GhostbusterObjectUpdate::Issued(info)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn updated(&mut self, update: GhostbusterObjectUpdate) {
+        match update {
+            GhostbusterObjectUpdate::Issued(info) => self.0 = Some(info),
+            GhostbusterObjectUpdate::Removed => self.0 = None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+}
+
+//------------ GhostbusterInfo ---------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GhostbusterInfo {
+    // The vCard contained in the object
+    vcard: VCard,
+
+    // The validity time for this object.
+    validity: Validity,
+
+    // The serial number (needed for revocation)
+    serial: Serial,
+
+    // The URI where this object is expected to be published
+    uri: uri::Rsync,
+
+    // The actual object in base64 format.
+    base64: Base64,
+
+    // The object's hash
+    hash: Hash,
+}
+
+impl GhostbusterInfo {
+    pub fn new(vcard: VCard, object: SignedObject) -> Self {
+        let validity = object.cert().validity();
+        let serial = object.cert().serial_number();
+        let uri = object.cert().signed_object().unwrap().clone(); // safe for our own objects
+        let base64 = Base64::from_content(
+            &object.encode_ref().to_captured(Mode::Der).into_bytes(),
+        );
+        let hash = base64.to_hash();
+
+        GhostbusterInfo {
+            vcard,
+            validity,
+            serial,
+            uri,
+            base64,
+            hash,
+        }
+    }
+
+    pub fn vcard(&self) -> &VCard {
+        &self.vcard
+    }
+
+    pub fn expires(&self) -> Time {
+        self.validity.not_after()
+    }
+
+    pub fn serial(&self) -> Serial {
+        self.serial
+    }
+
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+
+    pub fn base64(&self) -> &Base64 {
+        &self.base64
+    }
+
+    pub fn hash(&self) -> Hash {
+        self.hash
+    }
+}
@@ -13,11 +13,13 @@ use rpki::{
             ResourceClassName, RevocationRequest, RevocationResponse,
         },
         publication,
-        publication::{ListReply, Publish, PublishDelta, Update, Withdraw},
+        publication::{
+            Base64, ListReply, Publish, PublishDelta, Update, Withdraw,
+        },
     },
     crypto::KeyIdentifier,
-    repository::resources::ResourceSet,
-    uri,
+    repository::{resources::ResourceSet, x509::Time},
+    rrdp, uri,
 };
 
 use crate::{
@@ -28,15 +30,17 @@ use crate::{
             rrdp::PublishElement,
             BgpSecCsrInfoList, BgpSecDefinitionUpdates, IdCertInfo,
             ParentServerInfo, PublicationServerInfo, RoaConfigurationUpdates,
-            Timestamp,
+            ScheduleRoaUpdatesRequest, Timestamp,
         },
         api::{
             AddChildRequest, AspaDefinitionList, AspaDefinitionUpdates,
             AspaProvidersUpdate, CaCommandDetails, CertAuthList,
-            CertAuthSummary, ChildCaInfo, CommandHistory,
-            CommandHistoryCriteria, CustomerAsn, ParentCaContact,
-            ParentCaReq, ReceivedCert, RepositoryContact, RtaName,
-            UpdateChildRequest,
+            CertAuthSummary, ChangeRequest, ChangeRequestList,
+            ChangeRequestUpdates, ChildCaInfo, CommandHistory,
+            CommandHistoryCriteria, CustomerAsn, GhostbusterRecordUpdates,
+            GhostbusterRecords, ParentCaContact, ParentCaReq, ReceivedCert,
+            RepoValidationIssue, RepoValidationStatus, RepositoryContact,
+            RscName, RtaName, UpdateChildRequest,
         },
         crypto::KrillSigner,
         error::Error,
@@ -45,7 +49,8 @@ use crate::{
         KrillResult,
     },
     constants::{
-        CASERVER_NS, STATUS_NS, TA_PROXY_SERVER_NS, TA_SIGNER_SERVER_NS,
+        ACTOR_DEF_KRILL, CASERVER_NS, STATUS_NS, TA_PROXY_SERVER_NS,
+        TA_SIGNER_SERVER_NS,
     },
     daemon::{
         auth::common::permissions::Permission,
@@ -53,18 +58,20 @@ use crate::{
         ca::{
             CaObjectsStore, CaStatus, CertAuth, CertAuthCommand,
             CertAuthCommandDetails, DeprecatedRepository,
-            ResourceTaggedAttestation, RtaContentRequest, RtaPrepareRequest,
+            RepoContentValidator, ResourceTaggedAttestation,
+            RscContentRequest, RtaContentRequest, RtaPrepareRequest,
             StatusStore,
         },
         config::Config,
         mq::{now, Task, TaskQueue},
+        webhooks::WebhookNotifier,
     },
     pubd::RepositoryManager,
     ta::{
-        self, ta_handle, TrustAnchorProxy, TrustAnchorProxyCommand,
-        TrustAnchorProxyInitCommand, TrustAnchorSignedRequest,
-        TrustAnchorSignedResponse, TrustAnchorSigner,
-        TrustAnchorSignerCommand, TrustAnchorSignerInfo,
+        self, ta_handle, TaKeyRollRequest, TaKeyRollStatus, TrustAnchorProxy,
+        TrustAnchorProxyCommand, TrustAnchorProxyInitCommand,
+        TrustAnchorSignedRequest, TrustAnchorSignedResponse,
+        TrustAnchorSigner, TrustAnchorSignerCommand, TrustAnchorSignerInfo,
         TrustAnchorSignerInitCommand, TrustAnchorSignerInitCommandDetails,
         TA_NAME,
     },
@@ -189,6 +196,19 @@ impl CaManager {
         // RFC 6492).
         ca_store.add_post_save_listener(tasks.clone());
 
+        // Notify configured webhooks about selected CA events, after they
+        // have been saved.
+        let webhooks = if config.webhooks.is_empty() {
+            None
+        } else {
+            let webhooks = Arc::new(WebhookNotifier::new(
+                config.webhooks.clone(),
+                tasks.clone(),
+            ));
+            ca_store.add_post_save_listener(webhooks.clone());
+            Some(webhooks)
+        };
+
         // Create TA proxy store if we need it.
         let ta_proxy_store = if config.ta_proxy_enabled() {
             let mut store = AggregateStore::<TrustAnchorProxy>::create(
@@ -226,8 +246,11 @@ impl CaManager {
         // Create the status store which will maintain the last known
         // connection status between each CA and their parent(s) and
         // repository.
+        //
+        // If webhooks are configured, then they are notified when a CA
+        // starts failing to synchronise with a parent or its repository.
         let status_store =
-            StatusStore::create(&config.storage_uri, STATUS_NS)?;
+            StatusStore::create(&config.storage_uri, STATUS_NS, webhooks)?;
 
         Ok(CaManager {
             ca_store,
@@ -510,6 +533,96 @@ impl CaManager {
         Ok(())
     }
 
+    /// Plan a key roll step, to be included in the next signer request.
+    ///
+    /// Errors if:
+    /// - there is no proxy, or it has no signer
+    /// - the proxy has an open signer request
+    /// - the step does not fit the current key roll state
+    pub async fn ta_proxy_key_roll_plan(
+        &self,
+        key_roll: TaKeyRollRequest,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let cmd = TrustAnchorProxyCommand::plan_key_roll(
+            &ta_handle(),
+            key_roll,
+            actor,
+        );
+        self.send_ta_proxy_command(cmd).await?;
+        Ok(())
+    }
+
+    /// Returns the key roll status of the proxy.
+    pub async fn ta_proxy_key_roll_status(
+        &self,
+    ) -> KrillResult<TaKeyRollStatus> {
+        self.get_trust_anchor_proxy().await?.get_key_roll_status()
+    }
+
+    /// Update a child under the TA proxy. Only the resource entitlements
+    /// can be changed. The child is expected to request a new certificate,
+    /// which will be signed in the next signer request.
+    ///
+    /// Errors if:
+    /// - there is no proxy
+    /// - the child is unknown
+    /// - the proxy has an open signer request
+    /// - the update includes anything other than resources
+    pub async fn ta_proxy_children_update(
+        &self,
+        child: ChildHandle,
+        req: UpdateChildRequest,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let (id_opt, resources_opt, suspend_opt, mapping_opt) = req.unpack();
+
+        if id_opt.is_some() || suspend_opt.is_some() || mapping_opt.is_some()
+        {
+            return Err(Error::custom(
+                "Only resources can be updated for TA children",
+            ));
+        }
+
+        if let Some(resources) = resources_opt {
+            let cmd = TrustAnchorProxyCommand::update_child_resources(
+                &ta_handle(),
+                child,
+                resources,
+                actor,
+            );
+            self.send_ta_proxy_command(cmd).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove a child from the TA proxy. Its certificates will be revoked
+    /// in the next signer request.
+    ///
+    /// Errors if:
+    /// - there is no proxy
+    /// - the child is unknown
+    /// - the proxy has an open signer request
+    pub async fn ta_proxy_children_remove(
+        &self,
+        child: ChildHandle,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let ta_handle = ta_handle();
+        let cmd = TrustAnchorProxyCommand::remove_child(
+            &ta_handle,
+            child.clone(),
+            actor,
+        );
+        self.send_ta_proxy_command(cmd).await?;
+
+        // Only remove the status once the child is gone, so that it is kept
+        // for a child which could not be removed.
+        self.status_store.remove_child(&ta_handle, &child)?;
+        Ok(())
+    }
+
     /// Initializes an embedded trust anchor with all resources.
     pub async fn ta_init_fully_embedded(
         &self,
@@ -2519,8 +2632,7 @@ impl CaManager {
                 let repo = proxy
                     .repository()
                     .ok_or(Error::TaProxyHasNoRepository)?;
-                let objects =
-                    proxy.get_trust_anchor_objects()?.publish_elements()?;
+                let objects = proxy.get_publish_elements()?;
 
                 self.ca_repo_sync(repo_manager, ca_handle, id, repo, objects)
                     .await?;
@@ -2668,6 +2780,136 @@ impl CaManager {
         Ok(self.ca_objects_store.ca_objects(ca)?.repo_elements_map())
     }
 
+    /// Fetches the content that a CA published in its current repository
+    /// and validates it, as a relying party would. The outcome is saved
+    /// in the CA status, so that problems are reported as CA issues.
+    pub async fn ca_repo_validate(
+        &self,
+        repo_manager: &RepositoryManager,
+        ca_handle: &CaHandle,
+    ) -> KrillResult<()> {
+        let objects = self.ca_objects_store.ca_objects(ca_handle)?;
+        let repo_contact = match objects.repo() {
+            Some(repo_contact) => repo_contact.clone(),
+            None => return Ok(()), // nothing published yet
+        };
+
+        let certs = objects.current_repo_signing_certs();
+        let expected = objects
+            .repo_elements_map()
+            .remove(&repo_contact)
+            .unwrap_or_default();
+
+        let status = match self
+            .ca_repo_content(repo_manager, ca_handle, &repo_contact)
+            .await
+        {
+            Ok(published) => RepoContentValidator::validate(
+                &certs,
+                &expected,
+                &published,
+                Time::now(),
+            ),
+            Err(e) => {
+                let uri = match repo_contact.repo_info().rpki_notify() {
+                    Some(notify) => notify.to_string(),
+                    None => {
+                        repo_contact.server_info().service_uri().to_string()
+                    }
+                };
+                RepoValidationStatus::new(
+                    0,
+                    vec![RepoValidationIssue::new(
+                        uri,
+                        format!("cannot retrieve published content: {}", e),
+                    )],
+                )
+            }
+        };
+
+        if !status.issues().is_empty() {
+            warn!(
+                "Found {} issue(s) in the published content of CA '{}'",
+                status.issues().len(),
+                ca_handle
+            );
+        }
+
+        self.status_store
+            .set_status_repo_validation(ca_handle, status)
+    }
+
+    /// Returns the content published by the CA in its repository. If the
+    /// repository is this Krill instance then the content is taken from
+    /// the publication server directly, otherwise it is taken from the
+    /// RRDP snapshot.
+    async fn ca_repo_content(
+        &self,
+        repo_manager: &RepositoryManager,
+        ca_handle: &CaHandle,
+        repo_contact: &RepositoryContact,
+    ) -> KrillResult<Vec<PublishElement>> {
+        if repo_contact
+            .server_info()
+            .service_uri()
+            .as_str()
+            .starts_with(self.config.service_uri().as_str())
+        {
+            return repo_manager.get_publisher_elements(&ca_handle.convert());
+        }
+
+        let notify_uri =
+            repo_contact.repo_info().rpki_notify().ok_or_else(|| {
+                Error::Custom("repository does not support RRDP".to_string())
+            })?;
+
+        let notification_xml =
+            httpclient::get_text(notify_uri.as_str(), None)
+                .await
+                .map_err(Error::HttpClientError)?;
+        let notification =
+            rrdp::NotificationFile::parse(notification_xml.as_bytes())
+                .map_err(|e| {
+                    Error::Custom(format!(
+                        "invalid RRDP notification file: {}",
+                        e
+                    ))
+                })?;
+
+        let snapshot_uri = notification.snapshot().uri();
+        let snapshot_xml = httpclient::get_text(snapshot_uri.as_str(), None)
+            .await
+            .map_err(Error::HttpClientError)?;
+        if !notification
+            .snapshot()
+            .hash()
+            .matches(snapshot_xml.as_bytes())
+        {
+            return Err(Error::Custom(format!(
+                "hash of RRDP snapshot at {} does not match notification file",
+                snapshot_uri
+            )));
+        }
+
+        let snapshot = rrdp::Snapshot::parse(snapshot_xml.as_bytes())
+            .map_err(|e| {
+                Error::Custom(format!("invalid RRDP snapshot: {}", e))
+            })?;
+
+        let base_uri = repo_contact.repo_info().base_uri();
+        Ok(snapshot
+            .into_elements()
+            .into_iter()
+            .filter(|element| base_uri.is_parent_of(element.uri()))
+            .map(|element| {
+                PublishElement::new(
+                    Base64::from_content(element.data()),
+                    element.uri().clone(),
+                )
+            })
+            .collect())
+    }
+
     /// Get deprecated repositories so that they can be cleaned.
     pub fn ca_deprecated_repos(
         &self,
@@ -2945,13 +3187,25 @@ impl CaManager {
     }
 
     /// Add a new ASPA definition for this CA and the customer ASN in the
-    /// update.
+    /// update. If approval is required, then the update is submitted as
+    /// a change request instead.
     pub async fn ca_aspas_definitions_update(
         &self,
         ca: CaHandle,
         updates: AspaDefinitionUpdates,
         actor: &Actor,
-    ) -> KrillResult<()> {
+    ) -> KrillResult<Option<ChangeRequest>> {
+        if self.change_approval_required(actor) {
+            return self
+                .ca_change_request_submit(
+                    ca,
+                    ChangeRequestUpdates::Aspas(updates),
+                    actor,
+                )
+                .await
+                .map(Some);
+        }
+
         self.send_ca_command(
             CertAuthCommandDetails::aspas_definitions_update(
                 &ca,
@@ -2962,18 +3216,30 @@ impl CaManager {
             ),
         )
         .await?;
-        Ok(())
+        Ok(None)
     }
 
     /// Update the ASPA definition for this CA and the customer ASN in the
-    /// update.
+    /// update. If approval is required, then the update is submitted as
+    /// a change request instead.
     pub async fn ca_aspas_update_aspa(
         &self,
         ca: CaHandle,
         customer: CustomerAsn,
         update: AspaProvidersUpdate,
         actor: &Actor,
-    ) -> KrillResult<()> {
+    ) -> KrillResult<Option<ChangeRequest>> {
+        if self.change_approval_required(actor) {
+            return self
+                .ca_change_request_submit(
+                    ca,
+                    ChangeRequestUpdates::AspaProviders { customer, update },
+                    actor,
+                )
+                .await
+                .map(Some);
+        }
+
         self.send_ca_command(CertAuthCommandDetails::aspas_update_aspa(
             &ca,
             customer,
@@ -2983,6 +3249,113 @@ impl CaManager {
             actor,
         ))
         .await?;
+        Ok(None)
+    }
+}
+
+/// # Ghostbuster record functions
+impl CaManager {
+    /// Show the current Ghostbuster records for this CA.
+    pub async fn ca_ghostbusters_show(
+        &self,
+        ca: CaHandle,
+    ) -> KrillResult<GhostbusterRecords> {
+        let ca = self.get_ca(&ca).await?;
+        Ok(ca.ghostbusters_show())
+    }
+
+    /// Add, replace or remove Ghostbuster records for this CA.
+    pub async fn ca_ghostbusters_update(
+        &self,
+        ca: CaHandle,
+        updates: GhostbusterRecordUpdates,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_ca_command(CertAuthCommandDetails::ghostbusters_update(
+            &ca,
+            updates,
+            self.config.clone(),
+            self.signer.clone(),
+            actor,
+        ))
+        .await?;
+        Ok(())
+    }
+}
+
+/// # Change Request functions
+impl CaManager {
+    /// Returns whether route and ASPA updates by the given actor need to
+    /// be approved. Updates done by Krill itself, e.g. when ASPA
+    /// definitions are migrated on upgrade, never need approval.
+    fn change_approval_required(&self, actor: &Actor) -> bool {
+        self.config.change_approval_required && actor != &ACTOR_DEF_KRILL
+    }
+
+    /// Show the route and ASPA change requests which are pending approval
+    /// for this CA.
+    pub async fn ca_change_requests_show(
+        &self,
+        ca: &CaHandle,
+    ) -> KrillResult<ChangeRequestList> {
+        let ca = self.get_ca(ca).await?;
+        Ok(ca.change_requests())
+    }
+
+    /// Submit route or ASPA updates, which will only be applied when they
+    /// are approved by a different user. Returns the new change request.
+    pub async fn ca_change_request_submit(
+        &self,
+        ca: CaHandle,
+        updates: ChangeRequestUpdates,
+        actor: &Actor,
+    ) -> KrillResult<ChangeRequest> {
+        let ca = self
+            .send_ca_command(CertAuthCommandDetails::change_request_submit(
+                &ca,
+                updates,
+                self.config.clone(),
+                self.signer.clone(),
+                actor,
+            ))
+            .await?;
+
+        // Requests are added in order, and commands for a CA are processed
+        // one at a time, so the new request is the last one.
+        ca.change_requests().iter().last().cloned().ok_or_else(|| {
+            Error::custom("change request was not added to the CA")
+        })
+    }
+
+    /// Approve a change request, and apply its updates.
+    pub async fn ca_change_request_approve(
+        &self,
+        ca: CaHandle,
+        id: u64,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_ca_command(CertAuthCommandDetails::change_request_approve(
+            &ca,
+            id,
+            self.config.clone(),
+            self.signer.clone(),
+            actor,
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Reject a change request.
+    pub async fn ca_change_request_reject(
+        &self,
+        ca: CaHandle,
+        id: u64,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_ca_command(CertAuthCommandDetails::change_request_reject(
+            &ca, id, actor,
+        ))
+        .await?;
         Ok(())
     }
 }
@@ -3028,12 +3401,26 @@ impl CaManager {
     /// will be triggered. Finally note that ROAs may be issues on a per
     /// prefix basis, or aggregated by ASN based on the defaults or values
     /// configured.
+    ///
+    /// If approval is required, then the update is submitted as a change
+    /// request instead.
     pub async fn ca_routes_update(
         &self,
         ca: CaHandle,
         updates: RoaConfigurationUpdates,
         actor: &Actor,
-    ) -> KrillResult<()> {
+    ) -> KrillResult<Option<ChangeRequest>> {
+        if self.change_approval_required(actor) {
+            return self
+                .ca_change_request_submit(
+                    ca,
+                    ChangeRequestUpdates::Routes(updates),
+                    actor,
+                )
+                .await
+                .map(Some);
+        }
+
         self.send_ca_command(
             CertAuthCommandDetails::route_authorizations_update(
                 &ca,
@@ -3044,6 +3431,93 @@ impl CaManager {
             ),
         )
         .await?;
+        Ok(None)
+    }
+
+    /// Remove time-limited routes authorized by a CA which have expired,
+    /// and update ROAs accordingly. This is a no-op if no routes expired.
+    pub async fn ca_routes_remove_expired(
+        &self,
+        ca: &CaHandle,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_ca_command(CertAuthCommand::new(
+            ca,
+            None,
+            CertAuthCommandDetails::RouteAuthorizationsRemoveExpired(
+                self.config.clone(),
+                self.signer.clone(),
+            ),
+            actor,
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Schedule updates to the routes authorized by a CA. The updates will
+    /// be applied by the scheduler once their 'not before' time has passed.
+    ///
+    /// If approval is required, then the schedule is submitted as a change
+    /// request instead, and the updates are only scheduled once approved.
+    pub async fn ca_routes_schedule(
+        &self,
+        ca: CaHandle,
+        request: ScheduleRoaUpdatesRequest,
+        actor: &Actor,
+    ) -> KrillResult<Option<ChangeRequest>> {
+        if self.change_approval_required(actor) {
+            return self
+                .ca_change_request_submit(
+                    ca,
+                    ChangeRequestUpdates::ScheduledRoutes(request),
+                    actor,
+                )
+                .await
+                .map(Some);
+        }
+
+        self.send_ca_command(
+            CertAuthCommandDetails::route_authorizations_schedule(
+                &ca, request, actor,
+            ),
+        )
+        .await?;
+        Ok(None)
+    }
+
+    /// Cancel scheduled updates to the routes authorized by a CA.
+    pub async fn ca_routes_schedule_cancel(
+        &self,
+        ca: CaHandle,
+        id: u64,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_ca_command(
+            CertAuthCommandDetails::route_authorizations_schedule_cancel(
+                &ca, id, actor,
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Apply scheduled updates to the routes authorized by a CA.
+    pub async fn ca_routes_scheduled_activate(
+        &self,
+        ca: &CaHandle,
+        id: u64,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_ca_command(
+            CertAuthCommandDetails::route_authorizations_schedule_activate(
+                ca,
+                id,
+                self.config.clone(),
+                self.signer.clone(),
+                actor,
+            ),
+        )
+        .await?;
         Ok(())
     }
 
@@ -3105,6 +3579,20 @@ impl CaManager {
             if let Err(e) = self.send_ca_command(cmd).await {
                 error!("Renewing BGPSec certificates for CA '{}' failed with error: {}", ca, e);
             }
+
+            let cmd = CertAuthCommand::new(
+                &ca,
+                None,
+                CertAuthCommandDetails::GhostbustersRenew(
+                    self.config.clone(),
+                    self.signer.clone(),
+                ),
+                actor,
+            );
+
+            if let Err(e) = self.send_ca_command(cmd).await {
+                error!("Renewing Ghostbuster records for CA '{}' failed with error: {}", ca, e);
+            }
         }
         Ok(())
     }
@@ -3199,6 +3687,28 @@ impl CaManager {
     }
 }
 
+/// # RPKI Signed Checklist functions
+impl CaManager {
+    /// Sign an RPKI Signed Checklist (RFC 9323)
+    pub async fn rsc_sign(
+        &self,
+        ca: CaHandle,
+        name: RscName,
+        request: RscContentRequest,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let cmd = CertAuthCommandDetails::rsc_sign(
+            &ca,
+            name,
+            request,
+            self.signer.clone(),
+            actor,
+        );
+        self.send_ca_command(cmd).await?;
+        Ok(())
+    }
+}
+
 /// CA Key Roll functions
 impl CaManager {
     /// Initiate an RFC 6489 key roll for all active keys in a CA older than
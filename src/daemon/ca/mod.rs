@@ -15,6 +15,9 @@ pub use self::certauth::CertAuth;
 mod child;
 pub use self::child::*;
 
+mod ghostbusters;
+pub use self::ghostbusters::*;
+
 mod rc;
 pub use self::rc::ResourceClass;
 
@@ -37,12 +40,18 @@ pub use self::events::*;
 mod manager;
 pub use self::manager::CaManager;
 
+mod rsc;
+pub use self::rsc::*;
+
 mod rta;
 pub use self::rta::*;
 
 mod status;
 pub use self::status::*;
 
+mod validation;
+pub use self::validation::*;
+
 pub const TESTBED_CA_NAME: &str = "testbed"; // reserved for testbed mode
 
 pub fn testbed_ca_handle() -> CaHandle {
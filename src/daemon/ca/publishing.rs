@@ -46,7 +46,7 @@ use crate::{
 
 use super::{
     AspaInfo, AspaObjectsUpdates, BgpSecCertInfo, BgpSecCertificateUpdates,
-    RoaInfo,
+    GhostbusterInfo, GhostbusterObjectUpdate, RoaInfo,
 };
 
 //------------ CaObjectsStore ----------------------------------------------
@@ -119,6 +119,16 @@ impl PreSaveEventListener<CertAuth> for CaObjectsStore {
                         objects.update_aspas(resource_class_name, updates)?;
                         force_reissue = true;
                     }
+                    super::CertAuthEvent::GhostbusterObjectUpdated {
+                        resource_class_name,
+                        update,
+                    } => {
+                        objects.update_ghostbuster(
+                            resource_class_name,
+                            update,
+                        )?;
+                        force_reissue = true;
+                    }
                     super::CertAuthEvent::BgpSecCertificatesUpdated {
                         resource_class_name,
                         updates,
@@ -375,6 +385,22 @@ impl CaObjects {
         }
     }
 
+    /// Returns the current repository of the CA, if it has one.
+    pub fn repo(&self) -> Option<&RepositoryContact> {
+        self.repo.as_ref()
+    }
+
+    /// Returns the certificates of all keys which publish in the current
+    /// repository. Keys which still publish in an old repository, as part
+    /// of a repository migration, are excluded.
+    pub fn current_repo_signing_certs(&self) -> Vec<&ReceivedCert> {
+        let mut certs = vec![];
+        for resource_class_objects in self.classes.values() {
+            resource_class_objects.add_current_repo_signing_certs(&mut certs);
+        }
+        certs
+    }
+
     #[allow(clippy::mutable_key_type)]
     /// Returns all PublishedElements mapped to each RepositoryContact.
     /// There could be more than one repository - although usually there
@@ -529,6 +555,16 @@ impl CaObjects {
         self.get_class_mut(rcn).map(|rco| rco.update_aspas(updates))
     }
 
+    // Update the Ghostbuster record in the current set
+    fn update_ghostbuster(
+        &mut self,
+        rcn: &ResourceClassName,
+        update: &GhostbusterObjectUpdate,
+    ) -> KrillResult<()> {
+        self.get_class_mut(rcn)
+            .map(|rco| rco.update_ghostbuster(update))
+    }
+
     // Update the BGPSec certificates in the current set
     fn update_bgpsec_certs(
         &mut self,
@@ -649,6 +685,31 @@ impl ResourceClassObjects {
         }
     }
 
+    /// Adds the certificates of the keys for this resource class which
+    /// publish in the current repository.
+    fn add_current_repo_signing_certs<'a>(
+        &'a self,
+        certs: &mut Vec<&'a ReceivedCert>,
+    ) {
+        let sets = match &self.keys {
+            ResourceClassKeyState::Current(state) => {
+                vec![&state.current_set]
+            }
+            ResourceClassKeyState::Staging(state) => {
+                vec![&state.current_set, &state.staging_set]
+            }
+            ResourceClassKeyState::Old(state) => {
+                vec![&state.current_set, &state.old_set]
+            }
+        };
+
+        for set in sets {
+            if set.old_repo.is_none() {
+                certs.push(&set.signing_cert);
+            }
+        }
+    }
+
     fn create(
         key: &CertifiedKey,
         timing: &IssuanceTimingConfig,
@@ -759,6 +820,20 @@ impl ResourceClassObjects {
         }
     }
 
+    fn update_ghostbuster(&mut self, update: &GhostbusterObjectUpdate) {
+        match self.keys.borrow_mut() {
+            ResourceClassKeyState::Current(state) => {
+                state.current_set.update_ghostbuster(update)
+            }
+            ResourceClassKeyState::Staging(state) => {
+                state.current_set.update_ghostbuster(update)
+            }
+            ResourceClassKeyState::Old(state) => {
+                state.current_set.update_ghostbuster(update)
+            }
+        }
+    }
+
     fn update_bgpsec_certs(&mut self, updates: &BgpSecCertificateUpdates) {
         match self.keys.borrow_mut() {
             ResourceClassKeyState::Current(state) => {
@@ -1191,6 +1266,23 @@ impl KeyObjectSet {
         }
     }
 
+    fn update_ghostbuster(&mut self, update: &GhostbusterObjectUpdate) {
+        let name = ObjectName::ghostbusters();
+        let old = match update {
+            GhostbusterObjectUpdate::Issued(info) => {
+                let published_object =
+                    PublishedObject::for_ghostbuster(name.clone(), info);
+                self.published_objects.insert(name, published_object)
+            }
+            GhostbusterObjectUpdate::Removed => {
+                self.published_objects.remove(&name)
+            }
+        };
+        if let Some(old) = old {
+            self.revocations.add(old.revoke());
+        }
+    }
+
     fn update_bgpsec_certs(&mut self, updates: &BgpSecCertificateUpdates) {
         for bgpsec_cert_info in updates.updated() {
             let published_object =
@@ -1474,6 +1566,15 @@ impl PublishedObject {
         )
     }
 
+    pub fn for_ghostbuster(name: ObjectName, info: &GhostbusterInfo) -> Self {
+        PublishedObject::new(
+            name,
+            info.base64().clone(),
+            info.serial(),
+            info.expires(),
+        )
+    }
+
     pub fn for_cert_info<T>(cert: &CertInfo<T>) -> Self {
         PublishedObject::new(
             cert.name().clone(),
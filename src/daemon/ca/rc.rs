@@ -31,8 +31,10 @@ use crate::{
         ca::events::RoaUpdates,
         ca::{
             self, AspaObjects, AspaObjectsUpdates, CertAuthEvent,
-            CertifiedKey, ChildCertificates, CurrentKey, KeyState, NewKey,
-            OldKey, PendingKey, Roas, Routes,
+            CertifiedKey, ChildCertificates, CurrentKey,
+            GhostbusterDefinitions, GhostbusterObject,
+            GhostbusterObjectUpdate, KeyState, NewKey, OldKey, PendingKey,
+            Roas, Routes,
         },
         config::{Config, IssuanceTimingConfig},
     },
@@ -74,6 +76,9 @@ pub struct ResourceClass {
     #[serde(skip_serializing_if = "BgpSecCertificates::is_empty", default)]
     bgpsec_certificates: BgpSecCertificates,
 
+    #[serde(skip_serializing_if = "GhostbusterObject::is_empty", default)]
+    ghostbuster: GhostbusterObject,
+
     #[serde(skip_serializing_if = "ChildCertificates::is_empty", default)]
     certificates: ChildCertificates,
 
@@ -100,6 +105,7 @@ impl ResourceClass {
             aspas: AspaObjects::default(),
             certificates: ChildCertificates::default(),
             bgpsec_certificates: BgpSecCertificates::default(),
+            ghostbuster: GhostbusterObject::default(),
             last_key_change: Time::now(),
             key_state: KeyState::create(pending_key),
         }
@@ -118,6 +124,7 @@ impl ResourceClass {
             aspas: AspaObjects::default(),
             certificates: ChildCertificates::default(),
             bgpsec_certificates: BgpSecCertificates::default(),
+            ghostbuster: GhostbusterObject::default(),
             last_key_change: Time::now(),
             key_state: KeyState::create(pending_key),
         }
@@ -216,6 +223,7 @@ impl ResourceClass {
         all_routes: &Routes,
         all_aspas: &AspaDefinitions,
         all_bgpsecs: &BgpSecDefinitions,
+        all_ghostbusters: &GhostbusterDefinitions,
         config: &Config,
         signer: &KrillSigner,
     ) -> KrillResult<Vec<CertAuthEvent>> {
@@ -256,6 +264,13 @@ impl ResourceClass {
                         config,
                         signer,
                     )?;
+                    let ghostbuster_update = self.ghostbuster.update(
+                        all_ghostbusters,
+                        &self.name,
+                        &current_key,
+                        &config.issuance_timing,
+                        signer,
+                    )?;
 
                     let mut events =
                         vec![CertAuthEvent::KeyPendingToActive {
@@ -286,6 +301,13 @@ impl ResourceClass {
                         )
                     }
 
+                    if let Some(update) = ghostbuster_update {
+                        events.push(CertAuthEvent::GhostbusterObjectUpdated {
+                            resource_class_name: self.name.clone(),
+                            update,
+                        })
+                    }
+
                     Ok(events)
                 }
             }
@@ -705,6 +727,18 @@ impl ResourceClass {
                     events.push(aspas_updated);
                 }
 
+                if let Some(update) = self.ghostbuster.renew(
+                    new_key,
+                    None,
+                    issuance_timing,
+                    signer,
+                )? {
+                    events.push(CertAuthEvent::GhostbusterObjectUpdated {
+                        resource_class_name: self.name.clone(),
+                        update,
+                    });
+                }
+
                 let cert_updates = self.certificates.activate_key(
                     new_key.incoming_cert(),
                     issuance_timing,
@@ -916,6 +950,58 @@ impl ResourceClass {
     }
 }
 
+/// # Ghostbuster records
+impl ResourceClass {
+    /// Renew the Ghostbuster record object under the current key if its
+    /// not-after time is closer than the configured number of weeks.
+    pub fn renew_ghostbuster(
+        &self,
+        issuance_timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<Option<GhostbusterObjectUpdate>> {
+        if let Ok(key) = self.get_current_key() {
+            let renew_threshold =
+                Some(issuance_timing.new_ghostbusters_issuance_threshold());
+            self.ghostbuster.renew(
+                key,
+                renew_threshold,
+                issuance_timing,
+                signer,
+            )
+        } else {
+            debug!("no Ghostbuster record to renew - resource class has no current key");
+            Ok(None)
+        }
+    }
+
+    /// Updates the Ghostbuster record object in accordance with the
+    /// supplied definitions
+    pub fn update_ghostbuster(
+        &self,
+        all_ghostbusters: &GhostbusterDefinitions,
+        config: &Config,
+        signer: &KrillSigner,
+    ) -> KrillResult<Option<GhostbusterObjectUpdate>> {
+        if let Ok(key) = self.get_current_key() {
+            self.ghostbuster.update(
+                all_ghostbusters,
+                &self.name,
+                key,
+                &config.issuance_timing,
+                signer,
+            )
+        } else {
+            debug!("no Ghostbuster record to update - resource class has no current key");
+            Ok(None)
+        }
+    }
+
+    /// Apply Ghostbuster record object changes from events
+    pub fn ghostbuster_updated(&mut self, update: GhostbusterObjectUpdate) {
+        self.ghostbuster.updated(update)
+    }
+}
+
 /// # BGPSec
 impl ResourceClass {
     /// Updates the BGPSec certificates in accordance with the supplied
@@ -18,7 +18,7 @@ use crate::{
     commons::{
         api::{
             ObjectName, Revocation, RoaAggregateKey, RoaConfiguration,
-            RoaPayload,
+            RoaPayload, Timestamp,
         },
         crypto::KrillSigner,
         error::Error,
@@ -136,10 +136,26 @@ impl Routes {
                     payload_key.0,
                     route_info.comment().cloned(),
                 )
+                .with_expires_at(route_info.expires_at())
             })
             .collect()
     }
 
+    /// Returns the time-limited authorizations which have expired.
+    pub fn expired(&self, now: Timestamp) -> Vec<RoaPayloadJsonMapKey> {
+        self.map
+            .iter()
+            .filter(|(_, info)| info.is_expired(now))
+            .map(|(auth, _)| *auth)
+            .collect()
+    }
+
+    /// Returns the time when the first time-limited authorization expires,
+    /// if there is any.
+    pub fn next_expiry(&self) -> Option<Timestamp> {
+        self.map.values().filter_map(|info| info.expires_at()).min()
+    }
+
     pub fn roa_payload_keys(
         &self,
     ) -> impl Iterator<Item = &RoaPayloadJsonMapKey> {
@@ -200,6 +216,17 @@ impl Routes {
         }
     }
 
+    /// Updates the expiry time for an authorization
+    pub fn expires(
+        &mut self,
+        auth: &RoaPayloadJsonMapKey,
+        expires_at: Option<Timestamp>,
+    ) {
+        if let Some(info) = self.map.get_mut(auth) {
+            info.set_expires_at(expires_at)
+        }
+    }
+
     /// Removes an authorization
     pub fn remove(&mut self, auth: &RoaPayloadJsonMapKey) -> bool {
         self.map.remove(auth).is_some()
@@ -218,6 +245,9 @@ pub struct RouteInfo {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     group: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<Timestamp>, // withdrawn automatically after this
 }
 
 impl RouteInfo {
@@ -233,6 +263,18 @@ impl RouteInfo {
         self.comment = comment;
     }
 
+    pub fn expires_at(&self) -> Option<Timestamp> {
+        self.expires_at
+    }
+
+    pub fn set_expires_at(&mut self, expires_at: Option<Timestamp>) {
+        self.expires_at = expires_at;
+    }
+
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        self.expires_at.map(|t| t <= now).unwrap_or(false)
+    }
+
     /// The idea was to allow grouping of specific payloads.
     /// But perhaps we should deprecate this as it's not used.
     pub fn group(&self) -> Option<u32> {
@@ -246,6 +288,7 @@ impl Default for RouteInfo {
             since: Time::now(),
             comment: None,
             group: None,
+            expires_at: None,
         }
     }
 }
@@ -792,6 +835,36 @@ mod tests {
         parse_encode_authorization("2001:db8::/32-48 => 64496");
     }
 
+    #[test]
+    fn routes_expired() {
+        let mut routes = Routes::default();
+        let permanent = authorization("192.168.0.0/16 => 64496");
+        let early = authorization("192.168.1.0/24 => 64497");
+        let late = authorization("192.168.2.0/24 => 64497");
+        routes.add(permanent);
+        routes.add(early);
+        routes.add(late);
+        assert_eq!(routes.next_expiry(), None);
+
+        routes.expires(&early, Some(Timestamp::new(1000)));
+        routes.expires(&late, Some(Timestamp::new(2000)));
+        assert_eq!(routes.next_expiry(), Some(Timestamp::new(1000)));
+
+        assert!(routes.expired(Timestamp::new(999)).is_empty());
+        assert_eq!(routes.expired(Timestamp::new(1000)), vec![early]);
+
+        routes.remove(&early);
+        assert_eq!(routes.next_expiry(), Some(Timestamp::new(2000)));
+
+        // The expiry is reported in the configuration.
+        let config = routes
+            .roa_configurations()
+            .into_iter()
+            .find(|c| c.payload() == *late)
+            .unwrap();
+        assert_eq!(config.expires_at(), Some(Timestamp::new(2000)));
+    }
+
     #[test]
     fn routes_as_aggregates() {
         let mut routes = Routes::default();
@@ -0,0 +1,897 @@
+//! RPKI Signed Checklists
+//!
+//! See: https://www.rfc-editor.org/rfc/rfc9323.html
+
+use std::{collections::HashMap, fmt};
+
+use bcder::{
+    decode::{self, DecodeError},
+    encode::{self, PrimitiveContent, Values},
+    Captured, Ia5String, Mode, OctetString, Oid, Tag,
+};
+use bytes::Bytes;
+
+use rpki::{
+    ca::{provisioning::ResourceClassName, publication::Base64},
+    crypto::{
+        DigestAlgorithm, KeyIdentifier, RpkiSignature,
+        RpkiSignatureAlgorithm, Signer, SigningError,
+    },
+    oid,
+    repository::{
+        resources::{AddressFamily, AsBlocks, IpBlocks, ResourceSet},
+        x509::{Time, Validity},
+        Cert,
+    },
+    rrdp::Hash,
+};
+
+use crate::commons::{
+    api::{ReceivedCert, Revocation, RscList, RscName},
+    error::Error,
+    util::ext_serde,
+    KrillResult,
+};
+
+/// The content type of RPKI Signed Checklists: 1.2.840.113549.1.9.16.1.48
+const CT_RPKI_SIGNED_CHECKLIST: Oid<&[u8]> =
+    Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 48]);
+
+//------------ Rscs ---------------------------------------------------------
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Rscs {
+    map: HashMap<RscName, SignedRsc>,
+}
+
+impl Rscs {
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn list(&self) -> RscList {
+        RscList::new(self.map.keys().cloned().collect())
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.map.contains_key(name)
+    }
+
+    pub fn signed_rsc(&self, name: &str) -> KrillResult<RpkiSignedChecklist> {
+        self.map
+            .get(name)
+            .map(|signed| signed.rsc.clone())
+            .ok_or_else(|| Error::custom("Unknown RSC"))
+    }
+
+    pub fn add_signed(&mut self, name: RscName, signed: SignedRsc) {
+        self.map.insert(name, signed);
+    }
+}
+
+//------------ SignedRsc -----------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SignedRsc {
+    resources: ResourceSet,
+    resource_class_name: ResourceClassName,
+    revocation: Revocation,
+    rsc: RpkiSignedChecklist,
+}
+
+impl SignedRsc {
+    pub fn new(
+        resources: ResourceSet,
+        resource_class_name: ResourceClassName,
+        revocation: Revocation,
+        rsc: RpkiSignedChecklist,
+    ) -> Self {
+        SignedRsc {
+            resources,
+            resource_class_name,
+            revocation,
+            rsc,
+        }
+    }
+
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+}
+
+//------------ ChecklistEntry -----------------------------------------------
+
+/// An entry on a checklist: the SHA-256 hash of a file, and optionally
+/// its name.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChecklistEntry {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    file_name: Option<String>,
+    hash: Hash,
+}
+
+impl ChecklistEntry {
+    pub fn new(file_name: Option<String>, hash: Hash) -> Self {
+        ChecklistEntry { file_name, hash }
+    }
+
+    /// Creates an entry for a file with the given name and content.
+    pub fn for_file(file_name: Option<String>, content: &[u8]) -> Self {
+        ChecklistEntry {
+            file_name,
+            hash: Hash::from_data(content),
+        }
+    }
+
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    pub fn hash(&self) -> Hash {
+        self.hash
+    }
+
+    /// Returns an error unless the file name is a portable file name, i.e.
+    /// it only uses the characters allowed in section 4 of RFC 9323.
+    pub fn validate(&self) -> KrillResult<()> {
+        match &self.file_name {
+            Some(name) if !Self::is_portable_file_name(name) => {
+                Err(Error::RscInvalid(format!(
+                    "file name '{}' may only contain letters, digits, \
+                     '.', '_' and '-'",
+                    name
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn is_portable_file_name(name: &str) -> bool {
+        !name.is_empty()
+            && name.chars().all(|c| {
+                c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-'
+            })
+    }
+
+    /// Returns whether the entry matches the given file, i.e. the hashes
+    /// are equal, and so are the names if both entries have one.
+    fn matches(&self, file: &ChecklistEntry) -> bool {
+        self.hash == file.hash
+            && match (&self.file_name, &file.file_name) {
+                (Some(name), Some(other)) => name == other,
+                _ => true,
+            }
+    }
+
+    fn encode_ref(&self) -> impl encode::Values + '_ {
+        encode::sequence((
+            self.file_name.as_ref().map(|name| {
+                OctetString::encode_slice_as(name.as_bytes(), Tag::IA5_STRING)
+            }),
+            OctetString::encode_slice(self.hash.as_slice()),
+        ))
+    }
+
+    fn take_opt_from<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+    ) -> Result<Option<Self>, DecodeError<S::Error>> {
+        cons.take_opt_sequence(|cons| {
+            let file_name = cons
+                .take_opt_value_if(Tag::IA5_STRING, Ia5String::from_content)?
+                .map(|name| name.to_string());
+            let hash = OctetString::take_from(cons)?;
+            let hash = Hash::try_from(hash.to_bytes().as_ref())
+                .map_err(|_| cons.content_err("invalid SHA-256 hash"))?;
+            Ok(ChecklistEntry { file_name, hash })
+        })
+    }
+}
+
+impl fmt::Display for ChecklistEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file_name {
+            Some(name) => write!(f, "{}  {}", self.hash, name),
+            None => write!(f, "{}", self.hash),
+        }
+    }
+}
+
+//------------ RscContentRequest --------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RscContentRequest {
+    resources: ResourceSet,
+    validity: Validity,
+    checklist: Vec<ChecklistEntry>,
+}
+
+impl RscContentRequest {
+    pub fn new(
+        resources: ResourceSet,
+        validity: Validity,
+        checklist: Vec<ChecklistEntry>,
+    ) -> Self {
+        RscContentRequest {
+            resources,
+            validity,
+            checklist,
+        }
+    }
+
+    pub fn unpack(self) -> (ResourceSet, Validity, Vec<ChecklistEntry>) {
+        (self.resources, self.validity, self.checklist)
+    }
+}
+
+impl fmt::Display for RscContentRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "resources: {}", &self.resources)?;
+        writeln!(
+            f,
+            "validity, {}-{}",
+            self.validity.not_before().to_rfc3339(),
+            self.validity.not_after().to_rfc3339()
+        )?;
+        writeln!(f, "checklist:")?;
+        for entry in self.checklist.iter() {
+            writeln!(f, "  {}", entry)?;
+        }
+
+        Ok(())
+    }
+}
+
+//------------ RscVerifyRequest ---------------------------------------------
+
+/// A request to verify an RSC, and that the given files are on its
+/// checklist.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RscVerifyRequest {
+    rsc: RpkiSignedChecklist,
+    files: Vec<ChecklistEntry>,
+}
+
+impl RscVerifyRequest {
+    pub fn new(rsc: RpkiSignedChecklist, files: Vec<ChecklistEntry>) -> Self {
+        RscVerifyRequest { rsc, files }
+    }
+
+    pub fn unpack(self) -> (RpkiSignedChecklist, Vec<ChecklistEntry>) {
+        (self.rsc, self.files)
+    }
+}
+
+//------------ RscVerification ----------------------------------------------
+
+/// The result of a successful verification of an RSC.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RscVerification {
+    resource_class_name: ResourceClassName,
+    signing_key: KeyIdentifier,
+    resources: ResourceSet,
+    validity: Validity,
+    checklist: Vec<ChecklistEntry>,
+    files: Vec<ChecklistEntry>,
+}
+
+impl RscVerification {
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+
+    pub fn checklist(&self) -> &[ChecklistEntry] {
+        &self.checklist
+    }
+
+    pub fn files(&self) -> &[ChecklistEntry] {
+        &self.files
+    }
+}
+
+impl fmt::Display for RscVerification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "RSC was signed in resource class '{}' with key '{}'",
+            self.resource_class_name, self.signing_key
+        )?;
+        writeln!(f, "resources: {}", self.resources)?;
+        writeln!(
+            f,
+            "valid until: {}",
+            self.validity.not_after().to_rfc3339()
+        )?;
+        writeln!(f, "checklist:")?;
+        for entry in self.checklist.iter() {
+            writeln!(f, "  {}", entry)?;
+        }
+        if !self.files.is_empty() {
+            writeln!(f, "verified files:")?;
+            for file in self.files.iter() {
+                writeln!(f, "  {}", file)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//------------ RpkiSignedChecklist ------------------------------------------
+
+/// RPKI Signed Checklist
+///
+/// See: https://www.rfc-editor.org/rfc/rfc9323.html
+#[derive(Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
+pub struct RpkiSignedChecklist {
+    #[serde(
+        deserialize_with = "ext_serde::de_bytes",
+        serialize_with = "ext_serde::ser_bytes"
+    )]
+    bytes: Bytes,
+}
+
+impl AsRef<Bytes> for RpkiSignedChecklist {
+    fn as_ref(&self) -> &Bytes {
+        &self.bytes
+    }
+}
+
+impl RpkiSignedChecklist {
+    pub fn new(bytes: Bytes) -> Self {
+        RpkiSignedChecklist { bytes }
+    }
+
+    pub fn rsc_builder(
+        resources: &ResourceSet,
+        checklist: Vec<ChecklistEntry>,
+    ) -> KrillResult<RscBuilder> {
+        if checklist.is_empty() {
+            return Err(Error::RscInvalid(
+                "the checklist must contain at least one file".to_string(),
+            ));
+        }
+        for entry in checklist.iter() {
+            entry.validate()?;
+        }
+
+        let content = RscContent {
+            resources: resources.clone(),
+            digest_algorithm: DigestAlgorithm::default(),
+            checklist,
+        };
+        let encoded_content = content.to_bytes()?;
+
+        Ok(RscBuilder { encoded_content })
+    }
+
+    /// Decodes the RSC and verifies its signature.
+    ///
+    /// Note that this does not verify that the RSC was issued under a
+    /// trusted certificate, see [`RscObject::validate`] for that.
+    pub fn decode(&self) -> KrillResult<RscObject> {
+        let object = RscObject::decode(self.bytes.clone()).map_err(|e| {
+            Error::RscInvalid(format!("cannot decode: {}", e))
+        })?;
+        object.verify_signature()?;
+        Ok(object)
+    }
+}
+
+impl fmt::Display for RpkiSignedChecklist {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Base64::from_content(self.as_ref()))
+    }
+}
+
+//------------ RscBuilder ---------------------------------------------------
+
+/// The encoded content of an RSC which is yet to be signed.
+#[derive(Clone, Debug)]
+pub struct RscBuilder {
+    encoded_content: Bytes,
+}
+
+impl RscBuilder {
+    /// Signs the content with the key of the given EE certificate, and
+    /// returns the CMS encoded RSC which includes the certificate.
+    pub fn sign<S: Signer>(
+        self,
+        signer: &S,
+        key: &S::KeyId,
+        ee: Cert,
+    ) -> Result<RpkiSignedChecklist, SigningError<S::Error>> {
+        let digest_algorithm = DigestAlgorithm::default();
+        let message_digest = digest_algorithm.digest(&self.encoded_content);
+        let signed_attrs =
+            Self::signed_attrs(message_digest.as_ref(), Time::now());
+
+        // The signature is calculated over the DER encoded SET OF the
+        // signed attributes, see section 5.4 of RFC 5652.
+        let signature: RpkiSignature = signer.sign(
+            key,
+            RpkiSignatureAlgorithm::default(),
+            encode::set(&signed_attrs).to_captured(Mode::Der).as_slice(),
+        )?;
+
+        let bytes = encode::sequence((
+            oid::SIGNED_DATA.encode(), // contentType
+            encode::sequence_as(
+                Tag::CTX_0, // content
+                encode::sequence((
+                    3u8.encode(),                  // version
+                    digest_algorithm.encode_set(), // digestAlgorithms
+                    encode::sequence((
+                        // encapContentInfo
+                        CT_RPKI_SIGNED_CHECKLIST.encode(),
+                        encode::sequence_as(
+                            Tag::CTX_0,
+                            OctetString::encode_slice(
+                                self.encoded_content.as_ref(),
+                            ),
+                        ),
+                    )),
+                    encode::sequence_as(Tag::CTX_0, ee.encode_ref()), // certificates
+                    encode::set(encode::sequence((
+                        // SignerInfo
+                        3u8.encode(), // version
+                        ee.subject_key_identifier().encode_ref_as(Tag::CTX_0),
+                        digest_algorithm.encode(),
+                        encode::sequence_as(Tag::CTX_0, &signed_attrs),
+                        signature.algorithm().cms_encode(),
+                        OctetString::encode_slice(signature.value().as_ref()),
+                    ))),
+                )),
+            ),
+        ))
+        .to_captured(Mode::Der)
+        .into_bytes();
+
+        Ok(RpkiSignedChecklist { bytes })
+    }
+
+    /// Returns the DER encoded content of the signed attributes.
+    ///
+    /// DER requires that the attributes are sorted by their encoding.
+    fn signed_attrs(message_digest: &[u8], signing_time: Time) -> Captured {
+        let mut attrs = vec![
+            encode::sequence((
+                oid::CONTENT_TYPE.encode(),
+                encode::set(CT_RPKI_SIGNED_CHECKLIST.encode()),
+            ))
+            .to_captured(Mode::Der),
+            encode::sequence((
+                oid::SIGNING_TIME.encode(),
+                encode::set(signing_time.encode_varied()),
+            ))
+            .to_captured(Mode::Der),
+            encode::sequence((
+                oid::MESSAGE_DIGEST.encode(),
+                encode::set(OctetString::encode_slice(message_digest)),
+            ))
+            .to_captured(Mode::Der),
+        ];
+        attrs.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+
+        let mut res = Captured::builder(Mode::Der);
+        for attr in attrs {
+            res.extend(attr);
+        }
+        res.freeze()
+    }
+}
+
+//------------ RscContent ---------------------------------------------------
+
+/// The eContent of an RSC.
+#[derive(Clone, Debug)]
+struct RscContent {
+    resources: ResourceSet,
+    digest_algorithm: DigestAlgorithm,
+    checklist: Vec<ChecklistEntry>,
+}
+
+impl RscContent {
+    fn to_bytes(&self) -> KrillResult<Bytes> {
+        let asns = self.resources.asn();
+        let v4 = self
+            .resources
+            .to_ip_resources_v4()
+            .to_blocks()
+            .map_err(|_| Error::custom("Cannot inherit IPv4 on RSC"))?;
+        let v6 = self
+            .resources
+            .to_ip_resources_v6()
+            .to_blocks()
+            .map_err(|_| Error::custom("Cannot inherit IPv6 on RSC"))?;
+
+        if asns.is_empty() && v4.is_empty() && v6.is_empty() {
+            return Err(Error::RscInvalid(
+                "an RSC must contain resources".to_string(),
+            ));
+        }
+
+        // The ResourceBlock uses explicit tags. The constrained AS
+        // identifiers are a sequence with an explicitly tagged 'asnum'.
+        let as_id = if asns.is_empty() {
+            None
+        } else {
+            Some(encode::sequence_as(
+                Tag::CTX_0,
+                encode::sequence(encode::sequence_as(
+                    Tag::CTX_0,
+                    encode::sequence(asns.encode_ref()),
+                )),
+            ))
+        };
+
+        let v4_family = if v4.is_empty() {
+            None
+        } else {
+            Some(v4.encode_family(AddressFamily::Ipv4))
+        };
+        let v6_family = if v6.is_empty() {
+            None
+        } else {
+            Some(v6.encode_family(AddressFamily::Ipv6))
+        };
+        let ip_addr_blocks = if v4.is_empty() && v6.is_empty() {
+            None
+        } else {
+            Some(encode::sequence_as(
+                Tag::CTX_1,
+                encode::sequence((v4_family, v6_family)),
+            ))
+        };
+
+        let bytes = encode::sequence((
+            // version is DEFAULT
+            encode::sequence((as_id, ip_addr_blocks)),
+            self.digest_algorithm.encode(),
+            encode::sequence(encode::iter(
+                self.checklist.iter().map(|entry| entry.encode_ref()),
+            )),
+        ))
+        .to_captured(Mode::Der)
+        .into_bytes();
+
+        Ok(bytes)
+    }
+
+    fn take_from<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+    ) -> Result<Self, DecodeError<S::Error>> {
+        cons.take_sequence(|cons| {
+            cons.take_opt_constructed_if(Tag::CTX_0, |c| c.skip_u8_if(0))?;
+            let resources = Self::take_resources_from(cons)?;
+            let digest_algorithm = DigestAlgorithm::take_from(cons)?;
+            let checklist = cons.take_sequence(|cons| {
+                let mut checklist = vec![];
+                while let Some(entry) = ChecklistEntry::take_opt_from(cons)? {
+                    checklist.push(entry);
+                }
+                Ok(checklist)
+            })?;
+            if checklist.is_empty() {
+                return Err(cons.content_err("empty checklist"));
+            }
+            Ok(RscContent {
+                resources,
+                digest_algorithm,
+                checklist,
+            })
+        })
+    }
+
+    fn take_resources_from<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+    ) -> Result<ResourceSet, DecodeError<S::Error>> {
+        cons.take_sequence(|cons| {
+            let asns = cons.take_opt_constructed_if(Tag::CTX_0, |cons| {
+                cons.take_sequence(|cons| {
+                    cons.take_constructed_if(Tag::CTX_0, AsBlocks::take_from)
+                })
+            })?;
+
+            let mut v4 = None;
+            let mut v6 = None;
+            cons.take_opt_constructed_if(Tag::CTX_1, |cons| {
+                cons.take_sequence(|cons| {
+                    while let Some(()) = cons.take_opt_sequence(|cons| {
+                        match AddressFamily::take_from(cons)? {
+                            AddressFamily::Ipv4 if v4.is_none() => {
+                                v4 = Some(IpBlocks::take_from_with_family(
+                                    cons,
+                                    AddressFamily::Ipv4,
+                                )?);
+                            }
+                            AddressFamily::Ipv6 if v6.is_none() => {
+                                v6 = Some(IpBlocks::take_from_with_family(
+                                    cons,
+                                    AddressFamily::Ipv6,
+                                )?);
+                            }
+                            _ => {
+                                return Err(cons.content_err(
+                                    "duplicate address family in RSC",
+                                ))
+                            }
+                        }
+                        Ok(())
+                    })? {}
+                    Ok(())
+                })
+            })?;
+
+            if asns.is_none() && v4.is_none() && v6.is_none() {
+                return Err(cons.content_err("no resources in RSC"));
+            }
+
+            Ok(ResourceSet::new(
+                asns.unwrap_or_default(),
+                v4.unwrap_or_default().into(),
+                v6.unwrap_or_default().into(),
+            ))
+        })
+    }
+}
+
+//------------ RscObject ----------------------------------------------------
+
+/// A decoded RSC.
+///
+/// The generic signed object in the rpki crate cannot be used for RSCs,
+/// because it insists on a signed object SIA in the EE certificate, which
+/// RFC 9323 forbids. So, we decode the CMS structure here.
+#[derive(Clone, Debug)]
+pub struct RscObject {
+    digest_algorithm: DigestAlgorithm,
+    content: OctetString,
+    cert: Cert,
+    sid: KeyIdentifier,
+    signed_attrs: Captured,
+    signature: RpkiSignature,
+}
+
+impl RscObject {
+    fn decode(
+        bytes: Bytes,
+    ) -> Result<Self, DecodeError<std::convert::Infallible>> {
+        Mode::Der.decode(bytes, |cons| {
+            cons.take_sequence(|cons| {
+                // ContentInfo
+                oid::SIGNED_DATA.skip_if(cons)?; // contentType
+                cons.take_constructed_if(Tag::CTX_0, |cons| {
+                    cons.take_sequence(|cons| {
+                        // SignedData
+                        cons.skip_u8_if(3)?; // version
+                        let digest_algorithm =
+                            DigestAlgorithm::take_set_from(cons)?;
+                        let content = cons.take_sequence(|cons| {
+                            // encapContentInfo
+                            CT_RPKI_SIGNED_CHECKLIST.skip_if(cons)?;
+                            cons.take_constructed_if(
+                                Tag::CTX_0,
+                                OctetString::take_from,
+                            )
+                        })?;
+                        let cert = cons.take_constructed_if(
+                            Tag::CTX_0,
+                            Cert::take_from,
+                        )?;
+                        let (sid, signed_attrs, signature) =
+                            cons.take_set(|cons| {
+                                cons.take_sequence(|cons| {
+                                    // SignerInfo
+                                    cons.skip_u8_if(3)?;
+                                    let sid = cons.take_value_if(
+                                        Tag::CTX_0,
+                                        KeyIdentifier::from_content,
+                                    )?;
+                                    let alg =
+                                        DigestAlgorithm::take_from(cons)?;
+                                    if alg != digest_algorithm {
+                                        return Err(cons.content_err(
+                                            "digest algorithm mismatch",
+                                        ));
+                                    }
+                                    let signed_attrs = cons
+                                        .take_constructed_if(
+                                            Tag::CTX_0,
+                                            |cons| cons.capture_all(),
+                                        )?;
+                                    let signature = RpkiSignature::new(
+                                        RpkiSignatureAlgorithm::cms_take_from(
+                                            cons,
+                                        )?,
+                                        OctetString::take_from(cons)?
+                                            .into_bytes(),
+                                    );
+                                    Ok((sid, signed_attrs, signature))
+                                })
+                            })?;
+                        Ok(RscObject {
+                            digest_algorithm,
+                            content,
+                            cert,
+                            sid,
+                            signed_attrs,
+                            signature,
+                        })
+                    })
+                })
+            })
+        })
+    }
+
+    /// Verifies that the content is signed by the key of the included EE
+    /// certificate, and that this certificate is a detached EE certificate
+    /// as required by section 3 of RFC 9323.
+    fn verify_signature(&self) -> KrillResult<()> {
+        self.cert.inspect_detached_ee(true).map_err(|e| {
+            Error::RscInvalid(format!("invalid EE certificate: {}", e))
+        })?;
+        if self.cert.signed_object().is_some() {
+            return Err(Error::RscInvalid(
+                "EE certificate must not have a signed object SIA"
+                    .to_string(),
+            ));
+        }
+        if self.sid != self.cert.subject_key_identifier() {
+            return Err(Error::RscInvalid(
+                "subject key identifier mismatch".to_string(),
+            ));
+        }
+
+        let (content_type, message_digest) = Mode::Der
+            .decode(self.signed_attrs.clone().into_bytes(), |cons| {
+                let mut content_type = None;
+                let mut message_digest = None;
+                while let Some(()) = cons.take_opt_sequence(|cons| {
+                    let attr = Oid::take_from(cons)?;
+                    if attr == oid::CONTENT_TYPE {
+                        content_type = Some(cons.take_set(Oid::take_from)?);
+                    } else if attr == oid::MESSAGE_DIGEST {
+                        message_digest =
+                            Some(cons.take_set(OctetString::take_from)?);
+                    } else {
+                        cons.take_set(|cons| cons.skip_all())?;
+                    }
+                    Ok(())
+                })? {}
+                Ok((content_type, message_digest))
+            })
+            .map_err(|e| {
+                Error::RscInvalid(format!("invalid signed attributes: {}", e))
+            })?;
+
+        match content_type {
+            Some(content_type)
+                if content_type == CT_RPKI_SIGNED_CHECKLIST => {}
+            _ => {
+                return Err(Error::RscInvalid(
+                    "content type in signed attributes differs".to_string(),
+                ))
+            }
+        }
+
+        let digest = self.digest_algorithm.digest(&self.content.to_bytes());
+        if message_digest.map(|digest| digest.into_bytes())
+            != Some(Bytes::copy_from_slice(digest.as_ref()))
+        {
+            return Err(Error::RscInvalid(
+                "message digest mismatch".to_string(),
+            ));
+        }
+
+        let msg = encode::set(&self.signed_attrs).to_captured(Mode::Der);
+        self.cert
+            .subject_public_key_info()
+            .verify(msg.as_slice(), &self.signature)
+            .map_err(|_| Error::RscInvalid("invalid signature".to_string()))
+    }
+
+    /// Returns the identifier of the key which issued the EE certificate.
+    pub fn issuer_key(&self) -> KrillResult<KeyIdentifier> {
+        self.cert.authority_key_identifier().ok_or_else(|| {
+            Error::RscInvalid(
+                "EE certificate has no authority key identifier".to_string(),
+            )
+        })
+    }
+
+    /// Validates the RSC against the certificate of the key which issued
+    /// it, and checks that all files are on its checklist.
+    pub fn validate(
+        self,
+        resource_class_name: ResourceClassName,
+        issuer: &ReceivedCert,
+        files: Vec<ChecklistEntry>,
+    ) -> KrillResult<RscVerification> {
+        let issuer_cert = issuer.to_cert().map_err(|e| {
+            Error::RscInvalid(format!("cannot use issuer certificate: {}", e))
+        })?;
+        self.cert
+            .verify_signature(&issuer_cert, true)
+            .map_err(|_| {
+                Error::RscInvalid(
+                    "EE certificate was not signed by this CA".to_string(),
+                )
+            })?;
+        self.cert.verify_validity(Time::now()).map_err(|_| {
+            Error::RscInvalid("EE certificate is not valid now".to_string())
+        })?;
+
+        let ee_resources =
+            ResourceSet::try_from(&self.cert).map_err(|e| {
+                Error::RscInvalid(format!("invalid EE certificate: {}", e))
+            })?;
+        if !issuer.resources().contains(&ee_resources) {
+            return Err(Error::RscInvalid(
+                "EE certificate claims resources not held by this CA"
+                    .to_string(),
+            ));
+        }
+
+        let content = Mode::Der
+            .decode(self.content.to_bytes(), RscContent::take_from)
+            .map_err(|e| {
+                Error::RscInvalid(format!("invalid checklist: {}", e))
+            })?;
+        if !ee_resources.contains(&content.resources) {
+            return Err(Error::RscInvalid(
+                "checklist claims resources not held by its EE certificate"
+                    .to_string(),
+            ));
+        }
+
+        for file in files.iter() {
+            if !content.checklist.iter().any(|entry| entry.matches(file)) {
+                return Err(Error::RscInvalid(format!(
+                    "file '{}' is not on the checklist",
+                    file
+                )));
+            }
+        }
+
+        Ok(RscVerification {
+            resource_class_name,
+            signing_key: issuer.key_identifier(),
+            resources: content.resources,
+            validity: self.cert.validity(),
+            checklist: content.checklist,
+            files,
+        })
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn checklist_entry_portable_file_names() {
+        let hash = Hash::from_data(b"content");
+        let entry = |name: &str| ChecklistEntry::new(Some(name.into()), hash);
+
+        assert!(entry("LOA-2023_v1.pdf").validate().is_ok());
+        assert!(ChecklistEntry::new(None, hash).validate().is_ok());
+        assert!(entry("my loa.pdf").validate().is_err());
+        assert!(entry("../loa.pdf").validate().is_err());
+        assert!(entry("").validate().is_err());
+    }
+
+    #[test]
+    fn checklist_entry_matches_file() {
+        let entry = ChecklistEntry::for_file(Some("a.txt".into()), b"a");
+
+        assert!(entry.matches(&ChecklistEntry::for_file(None, b"a")));
+        assert!(entry
+            .matches(&ChecklistEntry::for_file(Some("a.txt".into()), b"a")));
+        assert!(!entry
+            .matches(&ChecklistEntry::for_file(Some("b.txt".into()), b"a")));
+        assert!(!entry.matches(&ChecklistEntry::for_file(None, b"b")));
+    }
+}
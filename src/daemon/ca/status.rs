@@ -1,4 +1,8 @@
-use std::{collections::HashMap, str::FromStr, sync::RwLock};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
 
 use kvx::Namespace;
 use rpki::ca::{
@@ -12,6 +16,7 @@ use crate::commons::{
     api::{
         ChildConnectionStats, ChildStatus, ChildrenConnectionStats,
         ErrorResponse, ParentStatus, ParentStatuses, RepoStatus,
+        RepoValidationStatus,
     },
     error::Error,
     eventsourcing::{
@@ -20,6 +25,7 @@ use crate::commons::{
     util::httpclient,
     KrillResult,
 };
+use crate::daemon::webhooks::WebhookNotifier;
 
 const PARENTS_PREFIX: &Segment = segment!("parents-");
 const CHILDREN_PREFIX: &Segment = segment!("children-");
@@ -36,6 +42,8 @@ pub struct CaStatus {
         default = "HashMap::new"
     )]
     children: HashMap<ChildHandle, ChildStatus>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    repo_validation: Option<RepoValidationStatus>,
 }
 
 impl CaStatus {
@@ -63,6 +71,12 @@ impl CaStatus {
     pub fn children(&self) -> &HashMap<ChildHandle, ChildStatus> {
         &self.children
     }
+
+    /// Returns the outcome of the last validation of the published content,
+    /// if it was validated.
+    pub fn repo_validation(&self) -> Option<&RepoValidationStatus> {
+        self.repo_validation.as_ref()
+    }
 }
 
 //------------ StatusStore ---------------------------------------------------
@@ -70,17 +84,26 @@ impl CaStatus {
 pub struct StatusStore {
     store: KeyValueStore,
     cache: RwLock<HashMap<CaHandle, CaStatus>>,
+
+    // Notified when synchronisation with a parent or repository starts
+    // failing, if webhooks are configured.
+    webhooks: Option<Arc<WebhookNotifier>>,
 }
 
 impl StatusStore {
     pub fn create(
         storage_uri: &Url,
         namespace: &Namespace,
+        webhooks: Option<Arc<WebhookNotifier>>,
     ) -> KrillResult<Self> {
         let store = KeyValueStore::create(storage_uri, namespace)?;
         let cache = RwLock::new(HashMap::new());
 
-        let store = StatusStore { store, cache };
+        let store = StatusStore {
+            store,
+            cache,
+            webhooks,
+        };
         store.warm()?;
 
         Ok(store)
@@ -112,6 +135,12 @@ impl StatusStore {
                 _ => RepoStatus::default(),
             };
 
+        let repo_validation: Option<RepoValidationStatus> = self
+            .store
+            .get(&Self::repo_validation_key(ca))
+            .ok()
+            .flatten();
+
         // We use the following mapping for keystore keys to parents/children:
         //  parents-{parent-handle}.json
         //  children-{child-handle}.json
@@ -187,6 +216,7 @@ impl StatusStore {
             repo,
             parents,
             children,
+            repo_validation,
         };
 
         // Update the cache. Note that this is what we will use at runtime.
@@ -242,6 +272,13 @@ impl StatusStore {
         )
     }
 
+    fn repo_validation_key(ca: &CaHandle) -> Key {
+        Key::new_scoped(
+            Scope::from_segment(Segment::parse_lossy(ca.as_str())), /* ca should always be a valid Segment */
+            segment!("repo-validation.json"),
+        )
+    }
+
     fn parent_status_key(ca: &CaHandle, parent: &ParentHandle) -> Key {
         Key::new_scoped(
             Scope::from_segment(Segment::parse_lossy(ca.as_str())), /* ca should always be a valid Segment */
@@ -281,9 +318,18 @@ impl StatusStore {
         error: &Error,
     ) -> KrillResult<()> {
         let error_response = Self::error_to_error_res(error);
+        let mut was_failing = false;
         self.update_ca_parent_status(ca, parent, |status| {
-            status.set_failure(uri.clone(), error_response)
-        })
+            was_failing = status.to_failure_opt().is_some();
+            status.set_failure(uri.clone(), error_response.clone())
+        })?;
+
+        if !was_failing {
+            if let Some(webhooks) = &self.webhooks {
+                webhooks.parent_sync_failed(ca, parent, &error_response);
+            }
+        }
+        Ok(())
     }
 
     pub fn set_parent_last_updated(
@@ -399,9 +445,18 @@ impl StatusStore {
         error: &Error,
     ) -> KrillResult<()> {
         let error_response = Self::error_to_error_res(error);
+        let mut was_failing = false;
         self.update_repo_status(ca, |status| {
-            status.set_failure(uri, error_response)
-        })
+            was_failing = status.to_failure_opt().is_some();
+            status.set_failure(uri, error_response.clone())
+        })?;
+
+        if !was_failing {
+            if let Some(webhooks) = &self.webhooks {
+                webhooks.repo_sync_failed(ca, &error_response);
+            }
+        }
+        Ok(())
     }
 
     pub fn set_status_repo_success(
@@ -423,6 +478,25 @@ impl StatusStore {
         })
     }
 
+    pub fn set_status_repo_validation(
+        &self,
+        ca: &CaHandle,
+        validation: RepoValidationStatus,
+    ) -> KrillResult<()> {
+        let mut cache = self.cache.write().unwrap();
+
+        if !cache.contains_key(ca) {
+            cache.insert(ca.clone(), CaStatus::default());
+        }
+
+        let ca_status = cache.get_mut(ca).unwrap(); // safe, we just set it if missing
+        self.store
+            .store(&Self::repo_validation_key(ca), &validation)?;
+        ca_status.repo_validation = Some(validation);
+
+        Ok(())
+    }
+
     fn update_repo_status<F>(&self, ca: &CaHandle, op: F) -> KrillResult<()>
     where
         F: FnOnce(&mut RepoStatus),
@@ -553,7 +627,7 @@ mod tests {
         // Initialise the StatusStore using the new (in memory) storage,
         // and migrate the data.
         let store =
-            StatusStore::create(&test_storage_uri, STATUS_NS).unwrap();
+            StatusStore::create(&test_storage_uri, STATUS_NS, None).unwrap();
         let testbed = CaHandle::from_str("testbed").unwrap();
 
         // Get the migrated status for testbed and verify that it's equivalent
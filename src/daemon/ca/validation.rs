@@ -0,0 +1,632 @@
+//! Validation of the content published by a CA
+//!
+//! Krill knows which objects a CA should have published, but relying
+//! parties only see what the repository actually serves. The content is
+//! therefore validated here the way a relying party would, starting from
+//! the certificates that the CA received from its parents.
+
+use std::collections::{HashMap, HashSet};
+
+use bytes::Bytes;
+use rpki::{
+    repository::{
+        cert::Cert, crl::Crl, manifest::Manifest, resources::ResourceSet,
+        x509::Time,
+    },
+    uri,
+};
+
+use crate::commons::api::{
+    rrdp::{DecodedObject, PublishElement},
+    ReceivedCert, RepoValidationIssue, RepoValidationStatus,
+};
+
+//------------ RepoContentValidator ------------------------------------------
+
+/// Validates the published content for the keys of a CA, and compares it
+/// to the content that the CA expects to be published.
+pub struct RepoContentValidator<'a> {
+    published: HashMap<&'a uri::Rsync, Bytes>,
+    now: Time,
+    listed: HashSet<uri::Rsync>,
+    missing: HashSet<uri::Rsync>,
+    issues: Vec<RepoValidationIssue>,
+}
+
+impl<'a> RepoContentValidator<'a> {
+    /// Validates the content published for the given signing certificates.
+    ///
+    /// The published elements may include objects of other publishers, e.g.
+    /// if they were taken from an RRDP snapshot. Only objects under the
+    /// CA repository directories of the certificates are considered.
+    pub fn validate(
+        certs: &[&ReceivedCert],
+        expected: &[PublishElement],
+        published: &'a [PublishElement],
+        now: Time,
+    ) -> RepoValidationStatus {
+        let published: HashMap<_, _> = published
+            .iter()
+            .filter(|el| {
+                certs
+                    .iter()
+                    .any(|cert| cert.ca_repository().is_parent_of(el.uri()))
+            })
+            .map(|el| (el.uri(), el.base64().to_bytes()))
+            .collect();
+
+        let mut validator = RepoContentValidator {
+            published,
+            now,
+            listed: HashSet::new(),
+            missing: HashSet::new(),
+            issues: vec![],
+        };
+
+        for cert in certs {
+            validator.validate_key(cert);
+        }
+        validator.compare(expected);
+
+        let mut unlisted: Vec<_> = validator
+            .published
+            .keys()
+            .filter(|uri| !validator.listed.contains(**uri))
+            .collect();
+        unlisted.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        for uri in unlisted {
+            validator.issues.push(RepoValidationIssue::new(
+                uri,
+                "object is not listed on any manifest",
+            ));
+        }
+
+        RepoValidationStatus::new(validator.published.len(), validator.issues)
+    }
+
+    fn issue(&mut self, uri: &uri::Rsync, issue: impl std::fmt::Display) {
+        self.issues.push(RepoValidationIssue::new(uri, issue));
+    }
+
+    /// Reports an object which is not published. It is then not reported
+    /// again when the content is compared to the expected content.
+    fn missing(&mut self, uri: &uri::Rsync, issue: &str) {
+        self.missing.insert(uri.clone());
+        self.issue(uri, issue);
+    }
+
+    /// Validates the manifest and CRL published for a key, and all objects
+    /// listed on the manifest.
+    fn validate_key(&mut self, signing_cert: &ReceivedCert) {
+        let issuer = match signing_cert.to_cert() {
+            Ok(cert) => cert,
+            Err(e) => {
+                self.issue(signing_cert.uri(), e);
+                return;
+            }
+        };
+        if issuer.verify_validity(self.now).is_err() {
+            self.issue(
+                signing_cert.uri(),
+                "certificate received from parent is not valid at this time",
+            );
+        }
+
+        let mft_uri = signing_cert.mft_uri();
+        let crl_uri = signing_cert.crl_uri();
+        self.listed.insert(mft_uri.clone());
+
+        let mft = match self.published.get(&mft_uri) {
+            None => {
+                self.missing(&mft_uri, "manifest is not published");
+                return;
+            }
+            Some(bytes) => match Manifest::decode(bytes.as_ref(), false) {
+                Ok(mft) => mft,
+                Err(e) => {
+                    self.issue(&mft_uri, format!("cannot decode: {}", e));
+                    return;
+                }
+            },
+        };
+
+        if mft.content().next_update() < self.now {
+            self.issue(&mft_uri, "manifest is stale");
+        }
+
+        let files: Vec<_> = mft
+            .content()
+            .iter_uris(signing_cert.ca_repository())
+            .collect();
+
+        if !files.iter().any(|(uri, _)| uri == &crl_uri) {
+            self.issue(&crl_uri, "CRL is not listed on the manifest");
+        }
+
+        let crl = self.validate_crl(&crl_uri, &issuer);
+
+        self.check_cert(
+            &mft_uri,
+            mft.cert(),
+            &issuer,
+            signing_cert.resources(),
+            crl.as_ref(),
+        );
+
+        for (uri, hash) in files {
+            self.listed.insert(uri.clone());
+
+            let bytes = match self.published.get(&uri) {
+                Some(bytes) => bytes.clone(),
+                None => {
+                    self.missing(
+                        &uri,
+                        "object listed on manifest is not published",
+                    );
+                    continue;
+                }
+            };
+
+            if hash.verify(&bytes).is_err() {
+                self.issue(&uri, "hash does not match the manifest");
+                continue;
+            }
+
+            if uri == crl_uri {
+                continue;
+            }
+            match DecodedObject::decode(&uri, bytes.as_ref()) {
+                Ok(object) => {
+                    if let Some(cert) = object.cert() {
+                        self.check_cert(
+                            &uri,
+                            cert,
+                            &issuer,
+                            signing_cert.resources(),
+                            crl.as_ref(),
+                        );
+                    }
+                }
+                Err(e) => self.issue(&uri, e),
+            }
+        }
+    }
+
+    /// Returns the CRL for a key if it is published and can be decoded.
+    fn validate_crl(
+        &mut self,
+        uri: &uri::Rsync,
+        issuer: &Cert,
+    ) -> Option<Crl> {
+        let mut crl = match self.published.get(uri) {
+            None => {
+                self.missing(uri, "CRL is not published");
+                return None;
+            }
+            Some(bytes) => match Crl::decode(bytes.as_ref()) {
+                Ok(crl) => crl,
+                Err(e) => {
+                    self.issue(uri, format!("cannot decode: {}", e));
+                    return None;
+                }
+            },
+        };
+
+        if crl
+            .verify_signature(issuer.subject_public_key_info())
+            .is_err()
+        {
+            self.issue(uri, "CRL is not signed by the CA key");
+        }
+        if crl.next_update() < self.now {
+            self.issue(uri, "CRL is stale");
+        }
+
+        crl.cache_serials();
+        Some(crl)
+    }
+
+    /// Checks a certificate, or the EE certificate of a signed object,
+    /// issued under the CA key.
+    fn check_cert(
+        &mut self,
+        uri: &uri::Rsync,
+        cert: &Cert,
+        issuer: &Cert,
+        resources: &ResourceSet,
+        crl: Option<&Crl>,
+    ) {
+        if cert.verify_signature(issuer, false).is_err() {
+            self.issue(uri, "certificate is not signed by the CA key");
+        }
+        if cert.verify_validity(self.now).is_err() {
+            self.issue(uri, "certificate is not valid at this time");
+        }
+        if crl.map(|crl| crl.contains(cert.serial_number())) == Some(true) {
+            self.issue(uri, "certificate is revoked");
+        }
+
+        // Inherited resources are held by definition.
+        if let Ok(cert_resources) = ResourceSet::try_from(cert) {
+            if !resources.contains(&cert_resources) {
+                self.issue(
+                    uri,
+                    format!(
+                        "resources exceed those held by the CA: {}",
+                        cert_resources
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Compares the published content to the content that the CA expects.
+    fn compare(&mut self, expected: &[PublishElement]) {
+        for element in expected {
+            match self.published.get(element.uri()) {
+                None => {
+                    if !self.missing.contains(element.uri()) {
+                        self.missing(element.uri(), "object is not published")
+                    }
+                }
+                Some(bytes) => {
+                    if bytes != &element.base64().to_bytes() {
+                        self.issue(
+                            element.uri(),
+                            "published object differs from the object issued by the CA",
+                        )
+                    }
+                }
+            }
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use rpki::{
+        ca::{provisioning::RequestResourceLimit, publication::Base64},
+        repository::{
+            cert::{KeyUsage, Overclaim, TbsCert},
+            roa::Roa,
+        },
+    };
+
+    use super::*;
+    use crate::{
+        commons::{
+            api::{ObjectName, Revocation, Revocations, RoaPayload},
+            crypto::{KrillSigner, KrillSignerBuilder, SignSupport},
+        },
+        daemon::{
+            ca::{
+                CertifiedKey, CrlBuilder, ManifestBuilder, ObjectSetRevision,
+                PublishedObject, RoaPayloadJsonMapKey, Roas,
+            },
+            config::ConfigDefaults,
+        },
+        test,
+    };
+
+    /// A CA key certified for AS65000 and 10.0.0.0/16, which publishes
+    /// under rsync://localhost/repo/ca/
+    struct TestCa {
+        signer: KrillSigner,
+        key: CertifiedKey,
+    }
+
+    impl TestCa {
+        fn create(storage_uri: &url::Url) -> Self {
+            let signer = KrillSignerBuilder::new(
+                storage_uri,
+                Duration::from_secs(1),
+                &ConfigDefaults::signers(),
+            )
+            .build()
+            .unwrap();
+
+            let key_id = signer.create_key().unwrap();
+            let pub_key = signer.get_key_info(&key_id).unwrap();
+            let resources = test::resources("AS65000", "10.0.0.0/16", "");
+            let mft_uri = test::rsync(&format!(
+                "rsync://localhost/repo/ca/{}",
+                ObjectName::mft_for_key(&key_id)
+            ));
+
+            let name = pub_key.to_subject_name();
+            let mut tbs = TbsCert::new(
+                signer.random_serial().unwrap(),
+                name.clone(),
+                SignSupport::sign_validity_years(1),
+                Some(name),
+                pub_key,
+                KeyUsage::Ca,
+                Overclaim::Refuse,
+            );
+            tbs.set_basic_ca(Some(true));
+            tbs.set_ca_repository(Some(test::rsync(
+                "rsync://localhost/repo/ca/",
+            )));
+            tbs.set_rpki_manifest(Some(mft_uri));
+            tbs.set_as_resources(resources.to_as_resources());
+            tbs.set_v4_resources(resources.to_ip_resources_v4());
+            let cert = signer.sign_cert(tbs, &key_id).unwrap();
+
+            let incoming_cert = ReceivedCert::create(
+                cert,
+                test::rsync("rsync://localhost/repo/ta/ca.cer"),
+                resources,
+                RequestResourceLimit::default(),
+            )
+            .unwrap();
+
+            TestCa {
+                signer,
+                key: CertifiedKey::create(incoming_cert),
+            }
+        }
+
+        fn cert(&self) -> &ReceivedCert {
+            self.key.incoming_cert()
+        }
+
+        fn roa(&self, payload: &str) -> (ObjectName, Roa) {
+            let auth = RoaPayloadJsonMapKey::from(
+                RoaPayload::from_str(payload).unwrap(),
+            );
+            let name = ObjectName::from(&auth);
+            let roa = Roas::make_roa(
+                &[auth],
+                &name,
+                &self.key,
+                SignSupport::sign_validity_weeks(52),
+                &self.signer,
+            )
+            .unwrap();
+            (name, roa)
+        }
+
+        fn roa_element(
+            &self,
+            (name, roa): &(ObjectName, Roa),
+        ) -> PublishElement {
+            PublishElement::new(
+                Base64::from(roa),
+                self.cert().uri_for_name(name),
+            )
+        }
+
+        /// Returns the manifest and CRL for the given ROAs and revocations,
+        /// followed by the ROAs themselves.
+        #[allow(clippy::mutable_key_type)]
+        fn publish(
+            &self,
+            roas: &[(ObjectName, Roa)],
+            revocations: &Revocations,
+            next_update: Time,
+        ) -> Vec<PublishElement> {
+            let revision = ObjectSetRevision::new(
+                1,
+                Time::five_minutes_ago(),
+                next_update,
+            );
+
+            let crl = CrlBuilder::build(
+                self.cert().key_identifier(),
+                self.cert().subject().clone(),
+                revocations,
+                revision,
+                &self.signer,
+            )
+            .unwrap();
+
+            let objects: HashMap<_, _> = roas
+                .iter()
+                .map(|(name, roa)| {
+                    let object = PublishedObject::new(
+                        name.clone(),
+                        Base64::from(roa),
+                        roa.cert().serial_number(),
+                        roa.cert().validity().not_after(),
+                    );
+                    (name.clone(), object)
+                })
+                .collect();
+
+            let mft = ManifestBuilder::new(revision)
+                .with_objects(&crl, &objects)
+                .build_new_mft(self.cert(), &self.signer)
+                .unwrap();
+
+            let mut elements = vec![
+                PublishElement::new(
+                    Base64::from(&mft),
+                    self.cert().mft_uri(),
+                ),
+                crl.publish_element(self.cert().crl_uri()),
+            ];
+            elements.extend(roas.iter().map(|roa| self.roa_element(roa)));
+            elements
+        }
+
+        fn validate(
+            &self,
+            expected: &[PublishElement],
+            published: &[PublishElement],
+            now: Time,
+        ) -> RepoValidationStatus {
+            RepoContentValidator::validate(
+                &[self.cert()],
+                expected,
+                published,
+                now,
+            )
+        }
+    }
+
+    fn next_update() -> Time {
+        Time::now() + chrono::Duration::days(1)
+    }
+
+    fn has_issue(
+        status: &RepoValidationStatus,
+        uri: &uri::Rsync,
+        issue: &str,
+    ) -> bool {
+        status
+            .issues()
+            .iter()
+            .any(|i| i.uri() == uri.as_str() && i.issue().starts_with(issue))
+    }
+
+    #[test]
+    fn valid_content() {
+        test::test_in_memory(|storage_uri| {
+            let ca = TestCa::create(storage_uri);
+            let roa = ca.roa("10.0.0.0/24 => 65000");
+            let mut elements =
+                ca.publish(&[roa], &Revocations::default(), next_update());
+            let expected = elements.clone();
+
+            // Objects of other CAs are ignored
+            elements.push(PublishElement::new(
+                Base64::from_content(b"other"),
+                test::rsync("rsync://localhost/repo/other/object.roa"),
+            ));
+
+            let status = ca.validate(&expected, &elements, Time::now());
+            assert!(status.issues().is_empty(), "{:?}", status.issues());
+            assert_eq!(status.objects(), 3);
+        });
+    }
+
+    #[test]
+    fn hash_mismatch() {
+        test::test_in_memory(|storage_uri| {
+            let ca = TestCa::create(storage_uri);
+            let roa = ca.roa("10.0.0.0/24 => 65000");
+            let reissued = ca.roa("10.0.0.0/24 => 65000");
+            let uri = ca.cert().uri_for_name(&roa.0);
+
+            let expected =
+                ca.publish(&[roa], &Revocations::default(), next_update());
+            let mut published = expected.clone();
+            published[2] = ca.roa_element(&reissued);
+
+            let status = ca.validate(&expected, &published, Time::now());
+            assert!(has_issue(&status, &uri, "hash does not match"));
+            assert!(has_issue(&status, &uri, "published object differs"));
+        });
+    }
+
+    #[test]
+    fn stale_manifest_and_crl() {
+        test::test_in_memory(|storage_uri| {
+            let ca = TestCa::create(storage_uri);
+            let roa = ca.roa("10.0.0.0/24 => 65000");
+            let elements =
+                ca.publish(&[roa], &Revocations::default(), next_update());
+
+            let later = Time::now() + chrono::Duration::days(2);
+            let status = ca.validate(&elements, &elements, later);
+            assert!(has_issue(
+                &status,
+                &ca.cert().mft_uri(),
+                "manifest is stale"
+            ));
+            assert!(has_issue(&status, &ca.cert().crl_uri(), "CRL is stale"));
+        });
+    }
+
+    #[test]
+    fn revoked_ee_certificate() {
+        test::test_in_memory(|storage_uri| {
+            let ca = TestCa::create(storage_uri);
+            let roa = ca.roa("10.0.0.0/24 => 65000");
+            let uri = ca.cert().uri_for_name(&roa.0);
+
+            let mut revocations = Revocations::default();
+            revocations.add(Revocation::from(&roa.1));
+            let elements = ca.publish(&[roa], &revocations, next_update());
+
+            let status = ca.validate(&elements, &elements, Time::now());
+            assert!(has_issue(&status, &uri, "certificate is revoked"));
+        });
+    }
+
+    #[test]
+    fn resource_over_claim() {
+        test::test_in_memory(|storage_uri| {
+            let ca = TestCa::create(storage_uri);
+            let roa = ca.roa("192.168.0.0/24 => 65000");
+            let uri = ca.cert().uri_for_name(&roa.0);
+            let elements =
+                ca.publish(&[roa], &Revocations::default(), next_update());
+
+            let status = ca.validate(&elements, &elements, Time::now());
+            assert!(has_issue(&status, &uri, "resources exceed"));
+        });
+    }
+
+    #[test]
+    fn unlisted_object() {
+        test::test_in_memory(|storage_uri| {
+            let ca = TestCa::create(storage_uri);
+            let unlisted = ca.roa("10.0.1.0/24 => 65000");
+            let uri = ca.cert().uri_for_name(&unlisted.0);
+
+            let mut published =
+                ca.publish(&[], &Revocations::default(), next_update());
+            published.push(ca.roa_element(&unlisted));
+
+            let status = ca.validate(&published, &published, Time::now());
+            assert!(has_issue(&status, &uri, "object is not listed"));
+            assert_eq!(status.issues().len(), 1);
+        });
+    }
+
+    #[test]
+    fn unexpected_object() {
+        test::test_in_memory(|storage_uri| {
+            let ca = TestCa::create(storage_uri);
+            let roa = ca.roa("10.0.0.0/24 => 65000");
+            let reissued = ca.roa("10.0.0.0/24 => 65000");
+            let uri = ca.cert().uri_for_name(&roa.0);
+
+            // The published content is valid, but it is not what the
+            // CA expects.
+            let expected =
+                ca.publish(&[roa], &Revocations::default(), next_update());
+            let published = ca.publish(
+                &[reissued],
+                &Revocations::default(),
+                next_update(),
+            );
+
+            let status = ca.validate(&expected, &published, Time::now());
+            assert!(has_issue(&status, &uri, "published object differs"));
+            assert!(!has_issue(&status, &uri, "hash does not match"));
+        });
+    }
+
+    #[test]
+    fn missing_object_is_reported_once() {
+        test::test_in_memory(|storage_uri| {
+            let ca = TestCa::create(storage_uri);
+            let roa = ca.roa("10.0.0.0/24 => 65000");
+            let uri = ca.cert().uri_for_name(&roa.0);
+
+            let expected =
+                ca.publish(&[roa], &Revocations::default(), next_update());
+            let published = expected[..2].to_vec();
+
+            let status = ca.validate(&expected, &published, Time::now());
+            assert!(has_issue(&status, &uri, "object listed on manifest"));
+            assert_eq!(status.issues().len(), 1);
+        });
+    }
+}
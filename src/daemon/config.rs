@@ -1,4 +1,5 @@
 use std::{
+    collections::{HashMap, HashSet},
     env, fmt,
     fs::File,
     io::{self, Read},
@@ -23,7 +24,7 @@ use syslog::Facility;
 
 use crate::{
     commons::{
-        api::{PublicationServerUris, Token},
+        api::{PublicationServerUris, PublisherQuota, Token},
         crypto::{OpenSslSignerConfig, SignSupport},
         error::{Error, KrillIoError},
         eventsourcing::KeyValueStore,
@@ -34,13 +35,16 @@ use crate::{
     daemon::{
         http::tls_keys::{self, HTTPS_SUB_DIR},
         mq::{in_seconds, Priority},
+        webhooks::WebhookEventKind,
     },
     ta::TaTimingConfig,
 };
 
+#[cfg(feature = "multi-user")]
+use crate::daemon::auth::client_certs::ConfigAuthClientCerts;
 #[cfg(feature = "multi-user")]
 use crate::daemon::auth::providers::{
-    config_file::config::ConfigAuthUsers,
+    config_file::config::ConfigAuthUsers, ldap::ConfigAuthLdap,
     openid_connect::ConfigAuthOpenIDConnect,
 };
 
@@ -163,6 +167,10 @@ impl ConfigDefaults {
                          // roll for nicbr and 100% uptake should be okay)
     }
 
+    fn post_limit_mirror() -> u64 {
+        256 * 1024 * 1024 // 256MB (the RRDP snapshot contains all objects)
+    }
+
     fn rfc8181_log_dir() -> Option<PathBuf> {
         None
     }
@@ -209,6 +217,26 @@ impl ConfigDefaults {
         90
     }
 
+    fn ha_lease_seconds() -> u32 {
+        30
+    }
+
+    fn webhook_events() -> Vec<WebhookEventKind> {
+        WebhookEventKind::all()
+    }
+
+    fn webhook_retries() -> u32 {
+        5
+    }
+
+    fn mirror_lag_alert_seconds() -> i64 {
+        900
+    }
+
+    fn mirror_s3_region() -> String {
+        "us-east-1".to_string()
+    }
+
     fn timing_publish_next_hours() -> u32 {
         24
     }
@@ -253,6 +281,14 @@ impl ConfigDefaults {
         4
     }
 
+    fn timing_ghostbusters_valid_weeks() -> u32 {
+        52
+    }
+
+    fn timing_ghostbusters_reissue_weeks_before() -> u32 {
+        4
+    }
+
     pub fn openssl_signer_only() -> Vec<SignerConfig> {
         let signer_config = OpenSslSignerConfig {
             keys_storage_uri: None,
@@ -568,6 +604,16 @@ pub struct Config {
     #[cfg(feature = "multi-user")]
     pub auth_openidconnect: Option<ConfigAuthOpenIDConnect>,
 
+    #[cfg(feature = "multi-user")]
+    pub auth_ldap: Option<ConfigAuthLdap>,
+
+    #[cfg(feature = "multi-user")]
+    pub auth_client_certs: Option<ConfigAuthClientCerts>,
+
+    // Four-eyes approval of route and ASPA updates
+    #[serde(default)]
+    pub change_approval_required: bool,
+
     #[serde(default, deserialize_with = "deserialize_signer_ref")]
     pub default_signer: SignerReference,
 
@@ -596,12 +642,18 @@ pub struct Config {
     suspend_child_after_inactive_seconds: Option<u32>,
     suspend_child_after_inactive_hours: Option<u32>,
 
+    // Periodically validate the content published by each CA
+    pub ca_repo_validation_interval_minutes: Option<u32>,
+
     #[serde(default = "ConfigDefaults::post_limit_api")]
     pub post_limit_api: u64,
 
     #[serde(default = "ConfigDefaults::post_limit_rfc8181")]
     pub post_limit_rfc8181: u64,
 
+    #[serde(default = "ConfigDefaults::post_limit_mirror")]
+    pub post_limit_mirror: u64,
+
     #[serde(default = "ConfigDefaults::rfc8181_log_dir")]
     pub rfc8181_log_dir: Option<PathBuf>,
 
@@ -622,6 +674,14 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::bgp_risdumps_v6_uri")]
     pub bgp_risdumps_v6_uri: String,
 
+    // Other BGP announcement sources
+    #[serde(default)]
+    pub bgp_sources: Vec<BgpSourceConfig>,
+
+    // Sources of IRR route objects to compare ROAs with
+    #[serde(default)]
+    pub irr_sources: Vec<IrrSourceConfig>,
+
     // ROA Aggregation per ASN
     #[serde(default = "ConfigDefaults::roa_aggregate_threshold")]
     pub roa_aggregate_threshold: usize,
@@ -635,15 +695,39 @@ pub struct Config {
     #[serde(flatten)]
     pub rrdp_updates_config: RrdpUpdatesConfig,
 
+    // Limits for the content of publishers in the publication server
+    #[serde(default)]
+    pub publisher_quotas: PublisherQuotasConfig,
+
+    // Reject published objects which are not valid RPKI objects
+    #[serde(default)] // false
+    pub publisher_strict_validation: bool,
+
+    // Secondary locations to which the repository content is mirrored
+    #[serde(default)]
+    pub repository_mirrors: Vec<RepositoryMirrorConfig>,
+
     #[serde(flatten)]
     pub metrics: MetricsConfig,
 
+    #[serde(flatten)]
+    pub ha: HighAvailabilityConfig,
+
+    // Outbound notifications for CA events
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+
     pub testbed: Option<TestBed>,
 
     pub benchmark: Option<Benchmark>,
 
     #[serde(default)]
     pub ta_timing: TaTimingConfig,
+
+    /// The file this config was read from, if any. Used to reload parts
+    /// of the config at runtime.
+    #[serde(skip)]
+    pub config_file: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -674,6 +758,12 @@ pub struct IssuanceTimingConfig {
     timing_bgpsec_valid_weeks: u32,
     #[serde(default = "ConfigDefaults::timing_bgpsec_reissue_weeks_before")]
     timing_bgpsec_reissue_weeks_before: u32,
+    #[serde(default = "ConfigDefaults::timing_ghostbusters_valid_weeks")]
+    timing_ghostbusters_valid_weeks: u32,
+    #[serde(
+        default = "ConfigDefaults::timing_ghostbusters_reissue_weeks_before"
+    )]
+    timing_ghostbusters_reissue_weeks_before: u32,
 }
 
 impl IssuanceTimingConfig {
@@ -779,6 +869,26 @@ impl IssuanceTimingConfig {
         Time::now()
             + Duration::weeks(self.timing_bgpsec_reissue_weeks_before.into())
     }
+
+    //-- Ghostbuster records
+
+    /// Validity period for new Ghostbuster record objects
+    pub fn new_ghostbusters_validity(&self) -> Validity {
+        SignSupport::sign_validity_weeks(
+            self.timing_ghostbusters_valid_weeks.into(),
+        )
+    }
+
+    /// Threshold time for issuing new Ghostbuster record objects
+    ///
+    /// i.e. objects with a not after time *before* this moment should be
+    /// re-issued.
+    pub fn new_ghostbusters_issuance_threshold(&self) -> Time {
+        Time::now()
+            + Duration::weeks(
+                self.timing_ghostbusters_reissue_weeks_before.into(),
+            )
+    }
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
@@ -847,6 +957,38 @@ impl RrdpUpdatesConfig {
     }
 }
 
+/// Configures the limits for the content of publishers in the publication
+/// server. The limits at the top level apply to all publishers, unless they
+/// are overridden for a specific publisher.
+///
+/// Example:
+///
+///   [publisher_quotas]
+///   max_objects = 10000
+///   max_size = 10000000
+///   max_object_size = 1000000
+///
+///   [publisher_quotas.publishers.customer-ca]
+///   max_objects = 50000
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PublisherQuotasConfig {
+    #[serde(flatten)]
+    pub default: PublisherQuota,
+
+    #[serde(default)]
+    pub publishers: HashMap<PublisherHandle, PublisherQuota>,
+}
+
+impl PublisherQuotasConfig {
+    /// Returns the limits that apply to the given publisher.
+    pub fn quota_for(&self, publisher: &PublisherHandle) -> PublisherQuota {
+        match self.publishers.get(publisher) {
+            Some(quota) => quota.or(self.default),
+            None => self.default,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct MetricsConfig {
     #[serde(default)] // false
@@ -859,6 +1001,15 @@ pub struct MetricsConfig {
     pub metrics_hide_roa_details: bool,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+pub struct HighAvailabilityConfig {
+    #[serde(default)] // false
+    pub ha_enabled: bool,
+    pub ha_node_name: Option<String>,
+    #[serde(default = "ConfigDefaults::ha_lease_seconds")]
+    pub ha_lease_seconds: u32,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct TestBed {
     ta_aia: uri::Rsync,
@@ -1122,6 +1273,10 @@ impl Config {
         let auth_users = None;
         #[cfg(feature = "multi-user")]
         let auth_openidconnect = None;
+        #[cfg(feature = "multi-user")]
+        let auth_ldap = None;
+        #[cfg(feature = "multi-user")]
+        let auth_client_certs = None;
 
         let default_signer = SignerReference::default();
         let one_off_signer = SignerReference::default();
@@ -1147,6 +1302,7 @@ impl Config {
         let ca_refresh_parents_batch_size = 10;
         let post_limit_api = ConfigDefaults::post_limit_api();
         let post_limit_rfc8181 = ConfigDefaults::post_limit_rfc8181();
+        let post_limit_mirror = ConfigDefaults::post_limit_mirror();
         let post_limit_rfc6492 = ConfigDefaults::post_limit_rfc6492();
         let post_protocol_msg_timeout_seconds =
             ConfigDefaults::post_protocol_msg_timeout_seconds();
@@ -1179,6 +1335,10 @@ impl Config {
             ConfigDefaults::timing_bgpsec_valid_weeks();
         let timing_bgpsec_reissue_weeks_before =
             ConfigDefaults::timing_bgpsec_reissue_weeks_before();
+        let timing_ghostbusters_valid_weeks =
+            ConfigDefaults::timing_ghostbusters_valid_weeks();
+        let timing_ghostbusters_reissue_weeks_before =
+            ConfigDefaults::timing_ghostbusters_reissue_weeks_before();
 
         let issuance_timing = IssuanceTimingConfig {
             timing_publish_next_hours,
@@ -1192,6 +1352,8 @@ impl Config {
             timing_aspa_reissue_weeks_before,
             timing_bgpsec_valid_weeks,
             timing_bgpsec_reissue_weeks_before,
+            timing_ghostbusters_valid_weeks,
+            timing_ghostbusters_reissue_weeks_before,
         };
 
         let rrdp_updates_config = RrdpUpdatesConfig {
@@ -1210,6 +1372,12 @@ impl Config {
             metrics_hide_roa_details: false,
         };
 
+        let ha = HighAvailabilityConfig {
+            ha_enabled: false,
+            ha_node_name: None,
+            ha_lease_seconds: ConfigDefaults::ha_lease_seconds(),
+        };
+
         let testbed = if enable_testbed {
             Some(TestBed::new(
                 test::rsync("rsync://localhost/ta/ta.cer"),
@@ -1251,6 +1419,11 @@ impl Config {
             auth_users,
             #[cfg(feature = "multi-user")]
             auth_openidconnect,
+            #[cfg(feature = "multi-user")]
+            auth_ldap,
+            #[cfg(feature = "multi-user")]
+            auth_client_certs,
+            change_approval_required: false,
             default_signer,
             one_off_signer,
             signers,
@@ -1260,8 +1433,10 @@ impl Config {
             ca_refresh_parents_batch_size,
             suspend_child_after_inactive_seconds,
             suspend_child_after_inactive_hours: None,
+            ca_repo_validation_interval_minutes: None,
             post_limit_api,
             post_limit_rfc8181,
+            post_limit_mirror,
             rfc8181_log_dir: None,
             post_limit_rfc6492,
             rfc6492_log_dir: None,
@@ -1269,14 +1444,22 @@ impl Config {
             bgp_risdumps_enabled,
             bgp_risdumps_v4_uri,
             bgp_risdumps_v6_uri,
+            bgp_sources: vec![],
+            irr_sources: vec![],
             roa_aggregate_threshold,
             roa_deaggregate_threshold,
             issuance_timing,
             rrdp_updates_config,
+            publisher_quotas: PublisherQuotasConfig::default(),
+            publisher_strict_validation: false,
+            repository_mirrors: vec![],
             metrics,
+            ha,
+            webhooks: vec![],
             testbed,
             benchmark: None,
             ta_timing: TaTimingConfig::default(),
+            config_file: None,
         }
     }
 
@@ -1320,6 +1503,7 @@ impl Config {
         upgrade_only: bool,
     ) -> Result<Self, ConfigError> {
         let mut config = Self::read_config(config_file)?;
+        config.config_file = Some(config_file.to_string());
 
         if upgrade_only {
             config.log_type = LogType::Stderr;
@@ -1476,6 +1660,22 @@ impl Config {
             return Err(ConfigError::other("Port number must be >1024"));
         }
 
+        match self.storage_uri.scheme() {
+            "local" | "memory" => {}
+            "postgres" if cfg!(feature = "postgres") => {}
+            "postgres" => {
+                return Err(ConfigError::other(
+                    "Krill was built without support for 'postgres' in storage_uri, enable the 'postgres' feature",
+                ));
+            }
+            scheme => {
+                return Err(ConfigError::other(&format!(
+                    "Unsupported scheme '{}' in storage_uri, use 'local', 'memory' or 'postgres'",
+                    scheme
+                )));
+            }
+        }
+
         if let Some(service_uri) = &self.service_uri {
             if !service_uri.as_str().ends_with('/') {
                 return Err(ConfigError::other(
@@ -1488,6 +1688,78 @@ impl Config {
             }
         }
 
+        if self.ha.ha_enabled {
+            if self.ha.ha_lease_seconds < 3 {
+                return Err(ConfigError::other(
+                    "ha_lease_seconds must be at least 3",
+                ));
+            }
+
+            if self.testbed.is_some() {
+                return Err(ConfigError::other(
+                    "ha_enabled cannot be used in combination with testbed",
+                ));
+            }
+        }
+
+        for webhook in &self.webhooks {
+            if !matches!(webhook.url.scheme(), "http" | "https") {
+                return Err(ConfigError::other(&format!(
+                    "Unsupported scheme in webhook url '{}', use 'http' or 'https'",
+                    webhook.url
+                )));
+            }
+
+            if webhook.events.is_empty() {
+                return Err(ConfigError::other(&format!(
+                    "webhook '{}' must have at least one event",
+                    webhook.url
+                )));
+            }
+        }
+
+        let mut mirror_names = HashSet::new();
+        for mirror in &self.repository_mirrors {
+            if !mirror_names.insert(mirror.name.as_str()) {
+                return Err(ConfigError::other(&format!(
+                    "Duplicate name for repository mirror '{}'",
+                    mirror.name
+                )));
+            }
+
+            let uri = match &mirror.target {
+                RepositoryMirrorTarget::Dir { .. } => None,
+                RepositoryMirrorTarget::S3 { endpoint, .. } => Some(endpoint),
+                RepositoryMirrorTarget::Krill { uri, .. } => Some(uri),
+            };
+            if let Some(uri) = uri {
+                if !matches!(uri.scheme(), "http" | "https") {
+                    return Err(ConfigError::other(&format!(
+                        "Unsupported scheme in uri for repository mirror '{}', use 'http' or 'https'",
+                        mirror.name
+                    )));
+                }
+            }
+        }
+
+        #[cfg(feature = "multi-user")]
+        if let Some(client_certs) = &self.auth_client_certs {
+            if self.https_mode.is_disable_https() {
+                return Err(ConfigError::other(
+                    "auth_client_certs cannot be used when HTTPS is disabled",
+                ));
+            }
+
+            for client in &client_certs.clients {
+                if client.subject.is_none() && client.san.is_none() {
+                    return Err(ConfigError::other(&format!(
+                        "client certificate '{}' must have a subject or san",
+                        client.name
+                    )));
+                }
+            }
+        }
+
         if self.issuance_timing.timing_publish_next_hours < 2 {
             return Err(ConfigError::other(
                 "timing_publish_next_hours must be at least 2",
@@ -1945,6 +2217,8 @@ pub enum AuthType {
     ConfigFile,
     #[cfg(feature = "multi-user")]
     OpenIDConnect,
+    #[cfg(feature = "multi-user")]
+    Ldap,
 }
 
 impl<'de> Deserialize<'de> for AuthType {
@@ -1959,6 +2233,8 @@ impl<'de> Deserialize<'de> for AuthType {
             "config-file" => Ok(AuthType::ConfigFile),
             #[cfg(feature = "multi-user")]
             "openid-connect" => Ok(AuthType::OpenIDConnect),
+            #[cfg(feature = "multi-user")]
+            "ldap" => Ok(AuthType::Ldap),
             _ => {
                 #[cfg(not(feature = "multi-user"))]
                 let msg = format!(
@@ -1967,7 +2243,7 @@ impl<'de> Deserialize<'de> for AuthType {
                 );
                 #[cfg(feature = "multi-user")]
                 let msg = format!(
-                    "expected \"config-file\", \"admin-token\", or \"openid-connect\", found: \"{}\"",
+                    "expected \"config-file\", \"admin-token\", \"openid-connect\", or \"ldap\", found: \"{}\"",
                     string
                 );
                 Err(de::Error::custom(msg))
@@ -1976,6 +2252,166 @@ impl<'de> Deserialize<'de> for AuthType {
     }
 }
 
+//------------ BgpSourceConfig -----------------------------------------------
+
+/// Configures a source of BGP announcements, in addition to the RIS dumps.
+///
+/// Example:
+///
+///   [[bgp_sources]]
+///   type = "mrt"
+///   path = "/var/lib/collector/rib.mrt.gz"
+///
+///   [[bgp_sources]]
+///   type = "bmp"
+///   listen = "0.0.0.0:11019"
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BgpSourceConfig {
+    /// A text file with an origin ASN and prefix on each line.
+    File { path: PathBuf },
+
+    /// An MRT file with a TABLE_DUMP_V2 RIB dump.
+    Mrt { path: PathBuf },
+
+    /// Accept BMP connections from routers on this address.
+    Bmp { listen: SocketAddr },
+}
+
+//------------ IrrSourceConfig -----------------------------------------------
+
+/// Configures a source of IRR route objects, which are compared with the
+/// configured ROAs.
+///
+/// Example:
+///
+///   [[irr_sources]]
+///   type = "file"
+///   path = "/var/lib/irr/ripe.db.route.gz"
+///
+///   [[irr_sources]]
+///   type = "whois"
+///   server = "whois.radb.net:43"
+///   query = "-K -T route,route6 -i mnt-by MAINT-EXAMPLE"
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IrrSourceConfig {
+    /// A file with RPSL objects, e.g. an IRR database dump.
+    File { path: PathBuf },
+
+    /// Send a query to a whois server and use the RPSL objects returned.
+    Whois { server: String, query: String },
+}
+
+//------------ WebhookConfig -------------------------------------------------
+
+/// Configures an HTTP endpoint which is notified about CA events.
+///
+/// Example:
+///
+///   [[webhooks]]
+///   url = "https://noc.example.com/krill"
+///   events = [ "roas_updated", "parent_sync_failed" ]
+///   secret = "shared secret"
+///   retries = 5
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct WebhookConfig {
+    /// The URL to POST the notifications to.
+    pub url: Url,
+
+    /// The kinds of events to notify about, all if not specified.
+    #[serde(default = "ConfigDefaults::webhook_events")]
+    pub events: Vec<WebhookEventKind>,
+
+    /// If set, notifications are signed with HMAC-SHA256 using this secret.
+    pub secret: Option<String>,
+
+    /// The number of times a failed delivery is retried.
+    #[serde(default = "ConfigDefaults::webhook_retries")]
+    pub retries: u32,
+}
+
+impl WebhookConfig {
+    pub fn wants(&self, event: WebhookEventKind) -> bool {
+        self.events.contains(&event)
+    }
+}
+
+//------------ RepositoryMirrorConfig ----------------------------------------
+
+/// Configures a secondary location to which the RRDP and rsync files of the
+/// publication server are mirrored.
+///
+/// Example:
+///
+///   [[repository_mirrors]]
+///   name = "backup"
+///   type = "dir"
+///   path = "/mnt/backup/repo"
+///
+///   [[repository_mirrors]]
+///   name = "minio"
+///   type = "s3"
+///   endpoint = "http://localhost:9000"
+///   bucket = "rpki"
+///   access_key = "krill"
+///   secret_key = "secret"
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct RepositoryMirrorConfig {
+    /// The name of the mirror, used in its status and metrics.
+    pub name: String,
+
+    /// The number of seconds that the mirror may be behind the repository
+    /// before it is reported as lagging.
+    #[serde(default = "ConfigDefaults::mirror_lag_alert_seconds")]
+    pub lag_alert_seconds: i64,
+
+    #[serde(flatten)]
+    pub target: RepositoryMirrorTarget,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RepositoryMirrorTarget {
+    /// Another directory, e.g. on a network file system.
+    Dir { path: PathBuf },
+
+    /// A bucket in an S3 compatible object store, using path-style URIs.
+    S3 {
+        endpoint: Url,
+        bucket: String,
+        #[serde(default = "ConfigDefaults::mirror_s3_region")]
+        region: String,
+        access_key: String,
+        secret_key: String,
+        #[serde(default)]
+        prefix: String,
+    },
+
+    /// Another Krill server, which serves the mirrored files from its own
+    /// repository directory.
+    Krill { uri: Url, token: Token },
+}
+
+impl fmt::Display for RepositoryMirrorTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepositoryMirrorTarget::Dir { path } => {
+                write!(f, "dir {}", path.display())
+            }
+            RepositoryMirrorTarget::S3 {
+                endpoint,
+                bucket,
+                prefix,
+                ..
+            } => write!(f, "s3 {}{}/{}", endpoint, bucket, prefix),
+            RepositoryMirrorTarget::Krill { uri, .. } => {
+                write!(f, "krill {}", uri)
+            }
+        }
+    }
+}
+
 //------------ Signers -----------------------------------------------------
 
 // Supports TOML such as:
@@ -2340,6 +2776,190 @@ mod tests {
         parse_and_process_config_str(config_str).unwrap();
     }
 
+    #[test]
+    fn parse_bgp_sources() {
+        let config_str = r#"
+            auth_token = "secret"
+            bgp_risdumps_enabled = false
+
+            [[bgp_sources]]
+            type = "file"
+            path = "/tmp/announcements.txt"
+
+            [[bgp_sources]]
+            type = "mrt"
+            path = "/tmp/rib.mrt.gz"
+
+            [[bgp_sources]]
+            type = "bmp"
+            listen = "127.0.0.1:11019"
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(
+            c.bgp_sources,
+            vec![
+                BgpSourceConfig::File {
+                    path: PathBuf::from("/tmp/announcements.txt")
+                },
+                BgpSourceConfig::Mrt {
+                    path: PathBuf::from("/tmp/rib.mrt.gz")
+                },
+                BgpSourceConfig::Bmp {
+                    listen: ([127, 0, 0, 1], 11019).into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_irr_sources() {
+        let config_str = r#"
+            auth_token = "secret"
+
+            [[irr_sources]]
+            type = "file"
+            path = "/tmp/ripe.db.route.gz"
+
+            [[irr_sources]]
+            type = "whois"
+            server = "whois.radb.net:43"
+            query = "-i mnt-by MAINT-EXAMPLE"
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(
+            c.irr_sources,
+            vec![
+                IrrSourceConfig::File {
+                    path: PathBuf::from("/tmp/ripe.db.route.gz")
+                },
+                IrrSourceConfig::Whois {
+                    server: "whois.radb.net:43".to_string(),
+                    query: "-i mnt-by MAINT-EXAMPLE".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_webhooks() {
+        let config_str = r#"
+            auth_token = "secret"
+
+            [[webhooks]]
+            url = "https://noc.example.com/krill"
+
+            [[webhooks]]
+            url = "http://localhost:8080/alerts"
+            events = [ "parent_sync_failed", "repo_sync_failed" ]
+            secret = "hmac secret"
+            retries = 0
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.webhooks.len(), 2);
+
+        let all = &c.webhooks[0];
+        assert_eq!(all.events, WebhookEventKind::all());
+        assert_eq!(all.secret, None);
+        assert_eq!(all.retries, 5);
+
+        let failures = &c.webhooks[1];
+        assert!(failures.wants(WebhookEventKind::ParentSyncFailed));
+        assert!(!failures.wants(WebhookEventKind::RoasUpdated));
+        assert_eq!(failures.secret.as_deref(), Some("hmac secret"));
+        assert_eq!(failures.retries, 0);
+
+        let config_str = r#"
+            auth_token = "secret"
+
+            [[webhooks]]
+            url = "ftp://noc.example.com/krill"
+        "#;
+        let res = parse_and_process_config_str(config_str);
+        assert_err_msg(
+            res,
+            "Unsupported scheme in webhook url 'ftp://noc.example.com/krill', use 'http' or 'https'",
+        );
+    }
+
+    #[test]
+    fn parse_repository_mirrors() {
+        let config_str = r#"
+            auth_token = "secret"
+
+            [[repository_mirrors]]
+            name = "backup"
+            type = "dir"
+            path = "/mnt/backup/repo"
+
+            [[repository_mirrors]]
+            name = "minio"
+            type = "s3"
+            endpoint = "http://localhost:9000"
+            bucket = "rpki"
+            access_key = "krill"
+            secret_key = "secret"
+            lag_alert_seconds = 60
+
+            [[repository_mirrors]]
+            name = "secondary"
+            type = "krill"
+            uri = "https://secondary.example.com:3000/"
+            token = "mirror-token"
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.repository_mirrors.len(), 3);
+
+        let backup = &c.repository_mirrors[0];
+        assert_eq!(backup.lag_alert_seconds, 900);
+        assert_eq!(
+            backup.target,
+            RepositoryMirrorTarget::Dir {
+                path: PathBuf::from("/mnt/backup/repo")
+            }
+        );
+
+        let minio = &c.repository_mirrors[1];
+        assert_eq!(minio.lag_alert_seconds, 60);
+        assert_eq!(
+            minio.target.to_string(),
+            "s3 http://localhost:9000/rpki/"
+        );
+        if let RepositoryMirrorTarget::S3 { region, prefix, .. } =
+            &minio.target
+        {
+            assert_eq!(region, "us-east-1");
+            assert_eq!(prefix, "");
+        } else {
+            panic!("Expected S3 target");
+        }
+
+        let secondary = &c.repository_mirrors[2];
+        assert_eq!(
+            secondary.target.to_string(),
+            "krill https://secondary.example.com:3000/"
+        );
+
+        let config_str = r#"
+            auth_token = "secret"
+
+            [[repository_mirrors]]
+            name = "backup"
+            type = "dir"
+            path = "/mnt/backup/repo"
+
+            [[repository_mirrors]]
+            name = "backup"
+            type = "dir"
+            path = "/mnt/other/repo"
+        "#;
+        let res = parse_and_process_config_str(config_str);
+        assert_err_msg(res, "Duplicate name for repository mirror 'backup'");
+    }
+
     #[cfg(not(feature = "hsm"))]
     #[test]
     fn should_fail_when_config_defines_signers_but_hsm_support_is_not_enabled(
@@ -2537,4 +3157,64 @@ mod tests {
         test_uri("local://data/test", "data/test");
         test_uri("local:///tmp/test", "/tmp/test");
     }
+
+    #[test]
+    fn storage_uri_scheme() {
+        let config_str = r#"
+            storage_uri = "postgres://krill@localhost/krill"
+            tls_keys_dir = "/var/lib/krill/ssl"
+            repo_dir = "/var/lib/krill/repo"
+            pid_file = "/var/run/krill.pid"
+        "#;
+        let res = parse_and_process_config_str(config_str);
+        if cfg!(feature = "postgres") {
+            assert!(res.unwrap().data_dir().is_none());
+        } else {
+            assert_err_msg(
+                res,
+                "Krill was built without support for 'postgres' in storage_uri, enable the 'postgres' feature",
+            );
+        }
+
+        let config_str = r#"
+            storage_uri = "mysql://krill@localhost/krill"
+            tls_keys_dir = "/var/lib/krill/ssl"
+            repo_dir = "/var/lib/krill/repo"
+            pid_file = "/var/run/krill.pid"
+        "#;
+        let res = parse_and_process_config_str(config_str);
+        assert_err_msg(
+            res,
+            "Unsupported scheme 'mysql' in storage_uri, use 'local', 'memory' or 'postgres'",
+        );
+    }
+
+    #[test]
+    fn high_availability() {
+        let config_str = r#"
+            auth_token = "secret"
+        "#;
+        let config = parse_and_process_config_str(config_str).unwrap();
+        assert!(!config.ha.ha_enabled);
+        assert_eq!(config.ha.ha_lease_seconds, 30);
+
+        let config_str = r#"
+            auth_token = "secret"
+            ha_enabled = true
+            ha_node_name = "krill-a"
+            ha_lease_seconds = 10
+        "#;
+        let config = parse_and_process_config_str(config_str).unwrap();
+        assert!(config.ha.ha_enabled);
+        assert_eq!(config.ha.ha_node_name.as_deref(), Some("krill-a"));
+        assert_eq!(config.ha.ha_lease_seconds, 10);
+
+        let config_str = r#"
+            auth_token = "secret"
+            ha_enabled = true
+            ha_lease_seconds = 2
+        "#;
+        let res = parse_and_process_config_str(config_str);
+        assert_err_msg(res, "ha_lease_seconds must be at least 3");
+    }
 }
@@ -234,6 +234,22 @@ impl HttpResponse {
         }
     }
 
+    /// Returns a 202 Accepted response with the given object as JSON, for
+    /// requests which were accepted but not yet applied.
+    pub fn accepted_json<O: Serialize>(object: &O) -> Self {
+        match serde_json::to_string(object) {
+            Ok(json) => Response {
+                status: StatusCode::ACCEPTED,
+                content_type: ContentType::Json,
+                max_age: None,
+                body: json.into_bytes(),
+                cause: None,
+            }
+            .finalize(),
+            Err(e) => Self::response_from_error(Error::JsonError(e)),
+        }
+    }
+
     pub fn text(body: Vec<u8>) -> Self {
         Self::ok_response(ContentType::Text, body)
     }
@@ -253,15 +269,44 @@ impl HttpResponse {
         Self::ok_response(ContentType::Xml, body)
     }
 
-    pub fn xml_with_cache(body: Vec<u8>, seconds: usize) -> Self {
-        Response {
-            status: StatusCode::OK,
-            content_type: ContentType::Xml,
-            max_age: Some(seconds),
-            body,
-            cause: None,
+    /// Returns an XML response with the given Cache-Control value and
+    /// the validators used for conditional requests.
+    pub fn xml_with_validators(
+        body: Vec<u8>,
+        cache_control: &str,
+        etag: &str,
+        last_modified: Option<&str>,
+    ) -> Self {
+        let mut builder = hyper::Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", ContentType::Xml.as_ref())
+            .header("Cache-Control", cache_control)
+            .header("ETag", etag);
+        if let Some(last_modified) = last_modified {
+            builder = builder.header("Last-Modified", last_modified);
         }
-        .finalize()
+        HttpResponse::new(
+            builder.body(Either::Right(Full::new(body.into()))).unwrap(),
+        )
+    }
+
+    /// Returns a 304 Not Modified response for a conditional request.
+    ///
+    /// This includes the same Cache-Control and validators as the full
+    /// response would, see RFC 9110 section 15.4.5.
+    pub fn not_modified(
+        cache_control: &str,
+        etag: &str,
+        last_modified: Option<&str>,
+    ) -> Self {
+        let mut builder = hyper::Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header("Cache-Control", cache_control)
+            .header("ETag", etag);
+        if let Some(last_modified) = last_modified {
+            builder = builder.header("Last-Modified", last_modified);
+        }
+        HttpResponse::new(builder.body(Either::Left(Empty::new())).unwrap())
     }
 
     pub fn rfc8181(body: Vec<u8>) -> Self {
@@ -455,6 +500,11 @@ impl Request {
         self.read_bytes(limit).await
     }
 
+    pub async fn mirror_bytes(self) -> Result<Bytes, Error> {
+        let limit = self.state().config.post_limit_mirror;
+        self.read_bytes(limit).await
+    }
+
     pub async fn read_bytes(self, limit: u64) -> Result<Bytes, Error> {
         // We’re going to cheat a bit. If we know the body is too big from
         // the Content-Length header, we return Error::PostTooBig. But if
@@ -6,16 +6,19 @@ use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::UNIX_EPOCH;
 use std::{env, process};
 
 use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
 use base64::engine::Engine as _;
 use bytes::Bytes;
-use hyper::header::HeaderName;
+use chrono::{DateTime, Utc};
+use hyper::header::{HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH};
 use hyper::http::HeaderValue;
 use hyper::service::service_fn;
-use hyper::Method;
+use hyper::{HeaderMap, Method};
 use hyper_util::rt::{TokioExecutor, TokioIo};
+use percent_encoding::percent_decode_str;
 use rpki::ca::idexchange;
 use rpki::ca::idexchange::{
     CaHandle, ChildHandle, ParentHandle, PublisherHandle,
@@ -24,19 +27,19 @@ use rpki::repository::resources::Asn;
 use serde::Serialize;
 use tokio::net::TcpListener;
 use tokio::select;
-use tokio_rustls::TlsAcceptor;
 
 use crate::{
     commons::{
         api::{
             ApiRepositoryContact, AspaDefinitionUpdates, BgpStats,
-            CommandHistoryCriteria, ParentCaReq, PublisherList,
-            RepositoryContact, RoaConfigurationUpdates, RtaName, Token,
+            ChangeRequest, CommandHistoryCriteria, ParentCaReq,
+            PublisherList, RepositoryContact, RoaConfigurationUpdates,
+            RrdpArchiveQuery, RscName, RtaName, Timestamp, Token,
         },
         bgp::BgpAnalysisAdvice,
         error::Error,
         eventsourcing::AggregateStoreError,
-        util::file,
+        util::{file, sha256},
     },
     constants::{
         KRILL_ENV_HTTP_LOG_INFO, KRILL_ENV_UPGRADE_ONLY, KRILL_VERSION_MAJOR,
@@ -151,9 +154,39 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
             })?;
     }
 
+    // Create self-signed HTTPS cert if configured and not generated earlier.
+    if config.https_mode().is_generate_https_cert() {
+        tls_keys::create_key_cert_if_needed(config.tls_keys_dir())
+            .map_err(|e| Error::HttpsSetup(format!("{}", e)))?;
+    }
+
+    // Set up TLS, unless disabled. The acceptor is shared by all listeners,
+    // so that it can be reloaded.
+    let tls = if config.https_mode().is_disable_https() {
+        None
+    } else {
+        // Ask clients for a certificate if client certificate
+        // authentication is configured.
+        #[cfg(feature = "multi-user")]
+        let client_ca_path = config
+            .auth_client_certs
+            .as_ref()
+            .map(|client_certs| client_certs.ca_certs.clone());
+        #[cfg(not(feature = "multi-user"))]
+        let client_ca_path = None;
+
+        let acceptor = tls::ReloadableTlsAcceptor::new(
+            tls_keys::key_file_path(config.tls_keys_dir()),
+            tls_keys::cert_file_path(config.tls_keys_dir()),
+            client_ca_path,
+        )
+        .map_err(|e| Error::HttpsSetup(e.to_string()))?;
+        Some(Arc::new(acceptor))
+    };
+
     // Create the server, this will create the necessary data sub-directories
     // if needed
-    let krill_server = KrillServer::build(config.clone()).await?;
+    let krill_server = KrillServer::build(config.clone(), tls).await?;
 
     // Call post-start upgrades to trigger any upgrade related runtime
     // actions, such as re-issuing ROAs because subject name strategy has
@@ -174,14 +207,17 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
     let scheduler = krill_server.build_scheduler();
     let scheduler_future = scheduler.run();
 
+    // Keep the leader lease, or try to acquire it, if this node shares its
+    // storage with other nodes.
+    let leader_election = krill_server.leader_election();
+    let leader_election_future = leader_election.run();
+
     // Start creating the server.
     let krill_server = Arc::new(krill_server);
 
-    // Create self-signed HTTPS cert if configured and not generated earlier.
-    if config.https_mode().is_generate_https_cert() {
-        tls_keys::create_key_cert_if_needed(config.tls_keys_dir())
-            .map_err(|e| Error::HttpsSetup(format!("{}", e)))?;
-    }
+    // Reload the TLS certificate and the authentication config on SIGHUP.
+    #[cfg(unix)]
+    tokio::spawn(reload_on_sighup(krill_server.clone()));
 
     // Start a hyper server for the configured socket.
     let server_futures = futures_util::future::select_all(
@@ -189,7 +225,6 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
             tokio::spawn(single_http_listener(
                 krill_server.clone(),
                 socket_addr,
-                config.clone(),
             ))
         }),
     );
@@ -197,6 +232,7 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
     select!(
         _ = server_futures => error!("http server stopped unexpectedly"),
         _ = scheduler_future => error!("scheduler stopped unexpectedly"),
+        _ = leader_election_future => error!("leader election stopped unexpectedly"),
     );
 
     Err(Error::custom("stopping krill process"))
@@ -206,7 +242,6 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
 async fn single_http_listener(
     krill_server: Arc<KrillServer>,
     addr: SocketAddr,
-    config: Arc<Config>,
 ) {
     let listener = match TcpListener::bind(addr).await {
         Ok(listener) => listener,
@@ -216,24 +251,15 @@ async fn single_http_listener(
         }
     };
 
-    let tls = if config.https_mode().is_disable_https() {
-        None
-    } else {
-        match tls::create_server_config(
-            &tls_keys::key_file_path(config.tls_keys_dir()),
-            &tls_keys::cert_file_path(config.tls_keys_dir()),
-        ) {
-            Ok(config) => Some(TlsAcceptor::from(Arc::new(config))),
-            Err(err) => {
-                error!("{}", err);
-                return;
-            }
-        }
-    };
+    #[cfg(feature = "multi-user")]
+    let client_auth = krill_server.config.auth_client_certs.is_some();
+    #[cfg(not(feature = "multi-user"))]
+    let client_auth = false;
 
     loop {
-        let stream = match listener.accept().await {
+        let mut stream = match listener.accept().await {
             Ok((stream, _addr)) => {
+                let tls = krill_server.tls_acceptor();
                 tls::MaybeTlsTcpStream::new(stream, tls.as_ref())
             }
             Err(err) => {
@@ -243,12 +269,29 @@ async fn single_http_listener(
         };
         let server = krill_server.clone();
         tokio::task::spawn(async move {
+            // The client certificate is only known once the TLS handshake
+            // has concluded, so we need to wait for that here.
+            let client_cert = if client_auth {
+                match stream.client_certificate().await {
+                    Ok(cert) => cert.map(tls::ClientCertificate::from),
+                    Err(err) => {
+                        debug!("TLS handshake failed: {}", err);
+                        return;
+                    }
+                }
+            } else {
+                None
+            };
+
             let _ = hyper_util::server::conn::auto::Builder::new(
                 TokioExecutor::new(),
             )
             .serve_connection(
                 TokioIo::new(stream),
-                service_fn(move |req| {
+                service_fn(move |mut req| {
+                    if let Some(cert) = &client_cert {
+                        req.extensions_mut().insert(cert.clone());
+                    }
                     let server = server.clone();
                     async move { map_requests(req, server).await }
                 }),
@@ -258,6 +301,28 @@ async fn single_http_listener(
     }
 }
 
+/// Reloads the TLS certificate and the authentication config whenever the
+/// process receives a SIGHUP.
+#[cfg(unix)]
+async fn reload_on_sighup(krill_server: Arc<KrillServer>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(err) => {
+            error!("Could not listen for SIGHUP: {}", err);
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        info!("Received SIGHUP, reloading");
+        if let Err(err) = krill_server.reload() {
+            error!("Reload failed: {}", err);
+        }
+    }
+}
+
 struct RequestLogger {
     req_method: hyper::Method,
     req_path: String,
@@ -348,7 +413,10 @@ async fn map_requests(
     // stack overflow. By doing it by hand like this we avoid the use of the
     // macros that cause the recursion. We could also look at putting less
     // data on the stack.
-    let mut res = api(req).await;
+    let mut res = standby(req).await;
+    if let Err(req) = res {
+        res = api(req).await;
+    }
     if let Err(req) = res {
         res = auth(req).await;
     }
@@ -425,6 +493,19 @@ pub fn render_empty_res(res: Result<(), Error>) -> RoutingResult {
     }
 }
 
+/// Renders the result of an update which may need to be approved. If the
+/// update was applied this is an empty 200 response, otherwise it is a 202
+/// response with the change request that was submitted for it.
+fn render_change_res(
+    res: Result<Option<ChangeRequest>, Error>,
+) -> RoutingResult {
+    match res {
+        Ok(None) => render_ok(),
+        Ok(Some(request)) => Ok(HttpResponse::accepted_json(&request)),
+        Err(e) => render_error(e),
+    }
+}
+
 #[allow(clippy::unnecessary_wraps)]
 fn render_error(e: Error) -> RoutingResult {
     debug!("Server Error: {}", e);
@@ -466,6 +547,23 @@ pub async fn render_not_found(_req: Request) -> RoutingResult {
     Ok(HttpResponse::not_found())
 }
 
+/// Refuse changes on a standby node. Only the leader may change the shared
+/// storage, but logging in is fine as login sessions are local.
+async fn standby(req: Request) -> RoutingResult {
+    let safe_method = matches!(*req.method(), Method::GET | Method::HEAD);
+
+    if !safe_method
+        && req.path().segment() != "auth"
+        && req.state().is_standby()
+    {
+        let leader = req.state().leader_election().leader_name();
+        Ok(HttpResponse::response_from_error(Error::HaStandby(leader))
+            .with_benign(true))
+    } else {
+        Err(req)
+    }
+}
+
 /// Returns the server health.
 pub async fn health(req: Request) -> RoutingResult {
     if req.is_get() && req.path().segment() == "health" {
@@ -547,6 +645,16 @@ pub async fn metrics(req: Request) -> RoutingResult {
             KRILL_VERSION_PATCH
         ));
 
+        if server.leader_election().is_enabled() {
+            res.push('\n');
+            res.push_str("# HELP krill_ha_leader whether this node is the leader (1) or a standby (0)\n");
+            res.push_str("# TYPE krill_ha_leader gauge\n");
+            res.push_str(&format!(
+                "krill_ha_leader {}\n",
+                i32::from(!server.is_standby())
+            ));
+        }
+
         #[cfg(feature = "multi-user")]
         {
             res.push('\n');
@@ -682,6 +790,46 @@ pub async fn metrics(req: Request) -> RoutingResult {
                             ));
                         }
                     }
+
+                    // Only shown if validation of the published content is
+                    // enabled, and only for CAs which were validated.
+                    if server
+                        .config
+                        .ca_repo_validation_interval_minutes
+                        .is_some()
+                    {
+                        res.push('\n');
+                        res.push_str("# HELP krill_ca_repo_validation_issues number of issues found in the content published by the CA\n");
+                        res.push_str(
+                            "# TYPE krill_ca_repo_validation_issues gauge\n",
+                        );
+                        for (ca, status) in ca_status_map.iter() {
+                            if let Some(validation) = status.repo_validation()
+                            {
+                                res.push_str(&format!(
+                                    "krill_ca_repo_validation_issues{{ca=\"{}\"}} {}\n",
+                                    ca,
+                                    validation.issues().len()
+                                ));
+                            }
+                        }
+
+                        res.push('\n');
+                        res.push_str("# HELP krill_ca_repo_validation_last_time unix timestamp in seconds of the last validation of the content published by the CA\n");
+                        res.push_str(
+                            "# TYPE krill_ca_repo_validation_last_time gauge\n",
+                        );
+                        for (ca, status) in ca_status_map.iter() {
+                            if let Some(validation) = status.repo_validation()
+                            {
+                                res.push_str(&format!(
+                                    "krill_ca_repo_validation_last_time{{ca=\"{}\"}} {}\n",
+                                    ca,
+                                    validation.timestamp()
+                                ));
+                            }
+                        }
+                    }
                 }
 
                 // Do not show child metrics if none of the CAs has any
@@ -989,6 +1137,33 @@ pub async fn metrics(req: Request) -> RoutingResult {
                     ));
                 }
 
+                let quotas = &server.config.publisher_quotas;
+
+                res.push('\n');
+                res.push_str("# HELP krill_repo_quota_objects maximum number of objects in repository for publisher\n");
+                res.push_str("# TYPE krill_repo_quota_objects gauge\n");
+                for publisher in publishers.keys() {
+                    if let Some(max) = quotas.quota_for(publisher).max_objects
+                    {
+                        res.push_str(&format!(
+                            "krill_repo_quota_objects{{publisher=\"{}\"}} {}\n",
+                            publisher, max
+                        ));
+                    }
+                }
+
+                res.push('\n');
+                res.push_str("# HELP krill_repo_quota_size maximum size of objects in bytes in repository for publisher\n");
+                res.push_str("# TYPE krill_repo_quota_size gauge\n");
+                for publisher in publishers.keys() {
+                    if let Some(max) = quotas.quota_for(publisher).max_size {
+                        res.push_str(&format!(
+                            "krill_repo_quota_size{{publisher=\"{}\"}} {}\n",
+                            publisher, max
+                        ));
+                    }
+                }
+
                 res.push('\n');
                 res.push_str("# HELP krill_repo_last_update unix timestamp in seconds of last update for publisher\n");
                 res.push_str("# TYPE krill_repo_last_update gauge\n");
@@ -1002,6 +1177,46 @@ pub async fn metrics(req: Request) -> RoutingResult {
                     }
                 }
             }
+
+            let mirrors = stats.mirrors();
+            if !mirrors.is_empty() {
+                res.push('\n');
+                res.push_str("# HELP krill_repo_mirror_serial RRDP serial of the content pushed to the mirror\n");
+                res.push_str("# TYPE krill_repo_mirror_serial gauge\n");
+                for mirror in mirrors {
+                    if let Some(serial) = mirror.serial() {
+                        res.push_str(&format!(
+                            "krill_repo_mirror_serial{{mirror=\"{}\"}} {}\n",
+                            mirror.name(),
+                            serial
+                        ));
+                    }
+                }
+
+                res.push('\n');
+                res.push_str("# HELP krill_repo_mirror_last_success unix timestamp in seconds of last successful push to the mirror\n");
+                res.push_str("# TYPE krill_repo_mirror_last_success gauge\n");
+                for mirror in mirrors {
+                    if let Some(last_success) = mirror.last_success() {
+                        res.push_str(&format!(
+                            "krill_repo_mirror_last_success{{mirror=\"{}\"}} {}\n",
+                            mirror.name(),
+                            i64::from(last_success)
+                        ));
+                    }
+                }
+
+                res.push('\n');
+                res.push_str("# HELP krill_repo_mirror_lagging whether the mirror is lagging behind the repository (1) or not (0)\n");
+                res.push_str("# TYPE krill_repo_mirror_lagging gauge\n");
+                for mirror in mirrors {
+                    res.push_str(&format!(
+                        "krill_repo_mirror_lagging{{mirror=\"{}\"}} {}\n",
+                        mirror.name(),
+                        i32::from(mirror.lagging())
+                    ));
+                }
+            }
         }
 
         Ok(HttpResponse::text(res.into_bytes()))
@@ -1044,6 +1259,7 @@ async fn ta(req: Request) -> RoutingResult {
             "/ta/ta.tal" => tal(req).await,
             "/testbed.tal" => tal(req).await,
             "/ta/ta.cer" => ta_cer(req).await,
+            path if path.ends_with(".cer") => ta_staged_cer(req).await,
             _ => Err(req),
         },
         _ => Err(req),
@@ -1066,6 +1282,21 @@ pub async fn ta_cer(req: Request) -> RoutingResult {
     }
 }
 
+/// Serves the certificate for a staged TA key at the path used in its
+/// TAL, so that it can be fetched before the key is activated.
+async fn ta_staged_cer(req: Request) -> RoutingResult {
+    match req.state().ta_staged_cert_details().await {
+        Some(details)
+            if details.tal().uris().iter().any(|uri| {
+                Some(uri.path()) == req.path.full().strip_prefix('/')
+            }) =>
+        {
+            Ok(HttpResponse::cert(details.cert().to_bytes().to_vec()))
+        }
+        _ => Err(req),
+    }
+}
+
 //------------ Provisioning (RFC6492) ----------------------------------------
 
 /// Process an RFC 6492 request
@@ -1245,6 +1476,11 @@ async fn api(req: Request) -> RoutingResult {
                     match restricted_endpoint {
                         Some("bulk") => api_bulk(req, &mut path).await,
                         Some("cas") => api_cas(req, &mut path).await,
+                        Some("reload") => aa!(
+                            req,
+                            Permission::CONFIG_RELOAD,
+                            api_reload(req).await
+                        ),
                         Some("pubd") => aa!(
                             req,
                             Permission::PUB_ADMIN,
@@ -1255,6 +1491,12 @@ async fn api(req: Request) -> RoutingResult {
                             Permission::CA_ADMIN,
                             api_ta(req, &mut path).await
                         ),
+                        #[cfg(feature = "multi-user")]
+                        Some("tokens") => aa!(
+                            req,
+                            Permission::TOKENS_ADMIN,
+                            api_tokens(req, &mut path).await
+                        ),
                         _ => render_unknown_method(),
                     }
                 })
@@ -1278,6 +1520,40 @@ async fn api_authorized(req: Request) -> RoutingResult {
     )
 }
 
+async fn api_reload(req: Request) -> RoutingResult {
+    match *req.method() {
+        Method::POST => render_empty_res(req.state().reload()),
+        _ => render_unknown_method(),
+    }
+}
+
+#[cfg(feature = "multi-user")]
+async fn api_tokens(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match path.path_arg::<String>() {
+        Some(name) => match *req.method() {
+            Method::DELETE => {
+                let actor = req.actor();
+                render_empty_res(req.state().api_token_revoke(name, &actor))
+            }
+            _ => render_unknown_method(),
+        },
+        None => match *req.method() {
+            Method::GET => render_json_res(req.state().api_tokens_list()),
+            Method::POST => {
+                let actor = req.actor();
+                let state = req.state().clone();
+                match req.json().await {
+                    Err(e) => render_error(e),
+                    Ok(request) => render_json_res(
+                        state.api_token_issue(request, &actor),
+                    ),
+                }
+            }
+            _ => render_unknown_method(),
+        },
+    }
+}
+
 async fn api_bulk(req: Request, path: &mut RequestPath) -> RoutingResult {
     match path.full() {
         "/api/v1/bulk/cas/import" => api_cas_import(req).await,
@@ -1304,7 +1580,11 @@ async fn api_cas(req: Request, path: &mut RequestPath) -> RoutingResult {
                 },
                 Some("aspas") => api_ca_aspas(req, path, ca).await,
                 Some("bgpsec") => api_ca_bgpsec(req, path, ca).await,
+                Some("changes") => api_ca_changes(req, path, ca).await,
                 Some("children") => api_ca_children(req, path, ca).await,
+                Some("ghostbusters") => {
+                    api_ca_ghostbusters(req, path, ca).await
+                }
                 Some("history") => api_ca_history(req, path, ca).await,
 
                 Some("id") => api_ca_id(req, path, ca).await,
@@ -1316,6 +1596,7 @@ async fn api_cas(req: Request, path: &mut RequestPath) -> RoutingResult {
                 Some("stats") => api_ca_stats(req, path, ca).await,
                 Some("sync") => api_ca_sync(req, path, ca).await,
 
+                Some("rsc") => api_ca_rsc(req, path, ca).await,
                 Some("rta") => api_ca_rta(req, path, ca).await,
 
                 _ => render_unknown_method(),
@@ -1399,10 +1680,61 @@ async fn api_ca_routes(
             _ => render_unknown_method(),
         },
         Some("analysis") => api_ca_routes_analysis(req, path, ca).await,
+        Some("scheduled") => api_ca_routes_scheduled(req, path, ca).await,
         _ => render_unknown_method(),
     }
 }
 
+async fn api_ca_routes_scheduled(
+    req: Request,
+    path: &mut RequestPath,
+    ca: CaHandle,
+) -> RoutingResult {
+    if let Some(id) = path.path_arg() {
+        match *req.method() {
+            Method::DELETE => {
+                api_ca_routes_schedule_cancel(req, ca, id).await
+            }
+            _ => render_unknown_method(),
+        }
+    } else {
+        match *req.method() {
+            Method::GET => api_ca_routes_scheduled_show(req, ca).await,
+            Method::POST => api_ca_routes_schedule(req, ca).await,
+            _ => render_unknown_method(),
+        }
+    }
+}
+
+async fn api_ca_changes(
+    req: Request,
+    path: &mut RequestPath,
+    ca: CaHandle,
+) -> RoutingResult {
+    if let Some(id) = path.path_arg() {
+        match path.next() {
+            None => match *req.method() {
+                Method::DELETE => {
+                    api_ca_change_request_reject(req, ca, id).await
+                }
+                _ => render_unknown_method(),
+            },
+            Some("approve") => match *req.method() {
+                Method::POST => {
+                    api_ca_change_request_approve(req, ca, id).await
+                }
+                _ => render_unknown_method(),
+            },
+            _ => render_unknown_method(),
+        }
+    } else {
+        match *req.method() {
+            Method::GET => api_ca_change_requests_show(req, ca).await,
+            _ => render_unknown_method(),
+        }
+    }
+}
+
 async fn api_ca_stats(
     req: Request,
     path: &mut RequestPath,
@@ -1478,6 +1810,70 @@ async fn api_publication_server(
             }
             _ => render_unknown_method(),
         },
+        Some("mirror") => api_repository_mirror(req, path).await,
+        Some("archive") => api_repository_archive(req, path).await,
+        _ => render_unknown_method(),
+    }
+}
+
+/// Returns the index of RRDP serials kept on disk, or the repository content
+/// at one of these serials, selected by serial (and optionally session) or
+/// by time in seconds since the epoch.
+async fn api_repository_archive(
+    req: Request,
+    path: &mut RequestPath,
+) -> RoutingResult {
+    if *req.method() != Method::GET {
+        return render_unknown_method();
+    }
+
+    let query = match path.next() {
+        None => {
+            return render_json_res(req.state().repository_archive_index())
+        }
+        Some("serial") => match path.path_arg() {
+            Some(serial) => RrdpArchiveQuery::Serial(serial, path.path_arg()),
+            None => return render_unknown_resource(),
+        },
+        Some("time") => match path.path_arg() {
+            Some(seconds) => RrdpArchiveQuery::Time(Timestamp::new(seconds)),
+            None => return render_error(Error::ApiInvalidSeconds),
+        },
+        _ => return render_unknown_resource(),
+    };
+
+    render_json_res(req.state().repository_archive_state(&query))
+}
+
+/// Saves or removes a file pushed by a Krill publication server which uses
+/// this server as a mirror.
+async fn api_repository_mirror(
+    req: Request,
+    path: &mut RequestPath,
+) -> RoutingResult {
+    let remaining = path.remaining().trim_start_matches('/');
+    let file_path = match percent_decode_str(remaining).decode_utf8() {
+        Ok(file_path) => file_path.to_string(),
+        Err(_) => {
+            return render_error(Error::RepositoryMirrorInvalidPath(
+                remaining.to_string(),
+            ))
+        }
+    };
+
+    match *req.method() {
+        Method::PUT => {
+            let state = req.state().clone();
+            match req.mirror_bytes().await {
+                Ok(bytes) => render_empty_res(
+                    state.repository_mirror_save(&file_path, bytes),
+                ),
+                Err(e) => render_error(e),
+            }
+        }
+        Method::DELETE => {
+            render_empty_res(req.state().repository_mirror_delete(&file_path))
+        }
         _ => render_unknown_method(),
     }
 }
@@ -1496,6 +1892,8 @@ async fn api_publishers(
                 Some("response.json") => {
                     api_repository_response_json(req, publisher).await
                 }
+                Some("objects") => api_show_pbl_objects(req, publisher).await,
+                Some("diff") => api_diff_pbl_objects(req, publisher).await,
 
                 _ => render_unknown_method(),
             },
@@ -1583,6 +1981,36 @@ pub async fn api_show_pbl(
     )
 }
 
+/// Returns the details of all objects of a publisher.
+pub async fn api_show_pbl_objects(
+    req: Request,
+    publisher: PublisherHandle,
+) -> RoutingResult {
+    aa!(
+        req,
+        Permission::PUB_READ,
+        render_json_res(req.state().get_publisher_objects(&publisher))
+    )
+}
+
+/// Returns the differences between the objects of a publisher and the
+/// objects that the CA with the same handle believes it has published.
+pub async fn api_diff_pbl_objects(
+    req: Request,
+    publisher: PublisherHandle,
+) -> RoutingResult {
+    let ca: CaHandle = publisher.convert();
+    aa!(
+        req,
+        Permission::PUB_READ,
+        aa!(req, Permission::CA_READ, Handle::from(&ca), {
+            render_json_res(
+                req.state().diff_publisher_objects(&publisher).await,
+            )
+        })
+    )
+}
+
 //------------ repository_response
 //------------ ---------------------------------------------
 
@@ -1888,8 +2316,12 @@ async fn api_ca_aspas(
             Method::POST => api_ca_aspas_definitions_update(req, ca).await,
             _ => render_unknown_method(),
         },
-        // We may need other functions in future, such as 'analyze' or 'try'.
-        // So keep the base namespace clean and use
+        Some("analysis") => match *req.method() {
+            Method::POST => api_ca_aspas_analysis(req, ca).await,
+            _ => render_unknown_method(),
+        },
+        // We may need other functions in future, such as 'try'. So keep
+        // the base namespace clean and use
         // '/api/v1/aspas/as/<asn>/..' for functions on specific ASPA
         // definitions for the given (customer) ASN.
         Some("as") => {
@@ -1915,6 +2347,21 @@ async fn api_ca_aspas(
     }
 }
 
+async fn api_ca_ghostbusters(
+    req: Request,
+    path: &mut RequestPath,
+    ca: CaHandle,
+) -> RoutingResult {
+    match path.next() {
+        None => match *req.method() {
+            Method::GET => api_ca_ghostbusters_show(req, ca).await,
+            Method::POST => api_ca_ghostbusters_update(req, ca).await,
+            _ => render_unknown_method(),
+        },
+        _ => render_unknown_method(),
+    }
+}
+
 async fn api_ca_bgpsec(
     req: Request,
     path: &mut RequestPath,
@@ -2316,6 +2763,38 @@ async fn api_ca_kr_activate(req: Request, ca: CaHandle) -> RoutingResult {
     })
 }
 
+// -- Ghostbuster record functions
+
+/// List the current Ghostbuster records for a CA
+async fn api_ca_ghostbusters_show(
+    req: Request,
+    ca: CaHandle,
+) -> RoutingResult {
+    aa!(req, Permission::CA_READ, Handle::from(&ca), {
+        let state = req.state().clone();
+        render_json_res(state.ca_ghostbusters_show(ca).await)
+    })
+}
+
+/// Add, replace or remove Ghostbuster records for a CA based on the
+/// updates in the POST
+async fn api_ca_ghostbusters_update(
+    req: Request,
+    ca: CaHandle,
+) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        let state = req.state().clone();
+
+        match req.json().await {
+            Err(e) => render_error(e),
+            Ok(updates) => render_empty_res(
+                state.ca_ghostbusters_update(ca, updates, &actor).await,
+            ),
+        }
+    })
+}
+
 // -- ASPA functions
 
 /// List the current ASPA definitions for a CA
@@ -2329,6 +2808,21 @@ async fn api_ca_aspas_definitions_show(
     })
 }
 
+/// Analyse the current ASPA definitions for a CA against the AS paths
+/// in the POST
+async fn api_ca_aspas_analysis(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(req, Permission::ASPAS_ANALYSIS, Handle::from(&ca), {
+        let state = req.state().clone();
+
+        match req.json().await {
+            Err(e) => render_error(e),
+            Ok(paths) => {
+                render_json_res(state.ca_aspas_analysis(ca, paths).await)
+            }
+        }
+    })
+}
+
 /// Add a new ASPA definition for a CA based on the update in the POST
 async fn api_ca_aspas_definitions_update(
     req: Request,
@@ -2340,7 +2834,7 @@ async fn api_ca_aspas_definitions_update(
 
         match req.json().await {
             Err(e) => render_error(e),
-            Ok(updates) => render_empty_res(
+            Ok(updates) => render_change_res(
                 state.ca_aspas_definitions_update(ca, updates, &actor).await,
             ),
         }
@@ -2360,7 +2854,7 @@ async fn api_ca_aspas_update_aspa(
 
         match req.json().await {
             Err(e) => render_error(e),
-            Ok(update) => render_empty_res(
+            Ok(update) => render_change_res(
                 state
                     .ca_aspas_update_aspa(ca, customer, update, &actor)
                     .await,
@@ -2380,7 +2874,7 @@ async fn api_ca_aspas_delete(
         let state = req.state().clone();
 
         let updates = AspaDefinitionUpdates::new(vec![], vec![customer]);
-        render_empty_res(
+        render_change_res(
             state.ca_aspas_definitions_update(ca, updates, &actor).await,
         )
     })
@@ -2394,7 +2888,7 @@ async fn api_ca_routes_update(req: Request, ca: CaHandle) -> RoutingResult {
 
         match req.json().await {
             Err(e) => render_error(e),
-            Ok(updates) => render_empty_res(
+            Ok(updates) => render_change_res(
                 state.ca_routes_update(ca, updates, &actor).await,
             ),
         }
@@ -2425,7 +2919,7 @@ async fn api_ca_routes_try_update(
                     Ok(effect) => {
                         if !effect.contains_invalids() {
                             // no issues found, apply
-                            render_empty_res(
+                            render_change_res(
                                 server
                                     .ca_routes_update(ca, updates, &actor)
                                     .await,
@@ -2467,6 +2961,83 @@ async fn api_ca_routes_show(req: Request, ca: CaHandle) -> RoutingResult {
     })
 }
 
+/// Show the scheduled route authorization updates for this CA
+async fn api_ca_routes_scheduled_show(
+    req: Request,
+    ca: CaHandle,
+) -> RoutingResult {
+    aa!(req, Permission::ROUTES_READ, Handle::from(&ca), {
+        render_json_res(req.state().ca_routes_scheduled_show(&ca).await)
+    })
+}
+
+/// Schedule updates to the route authorizations for this CA
+async fn api_ca_routes_schedule(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(req, Permission::ROUTES_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        let state = req.state().clone();
+
+        match req.json().await {
+            Err(e) => render_error(e),
+            Ok(request) => render_change_res(
+                state.ca_routes_schedule(ca, request, &actor).await,
+            ),
+        }
+    })
+}
+
+/// Cancel scheduled updates to the route authorizations for this CA
+async fn api_ca_routes_schedule_cancel(
+    req: Request,
+    ca: CaHandle,
+    id: u64,
+) -> RoutingResult {
+    aa!(req, Permission::ROUTES_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        render_empty_res(
+            req.state().ca_routes_schedule_cancel(ca, id, &actor).await,
+        )
+    })
+}
+
+/// Show the route and ASPA change requests pending approval for this CA
+async fn api_ca_change_requests_show(
+    req: Request,
+    ca: CaHandle,
+) -> RoutingResult {
+    aa!(req, Permission::ROUTES_READ, Handle::from(&ca), {
+        render_json_res(req.state().ca_change_requests_show(&ca).await)
+    })
+}
+
+/// Approve a pending change request for this CA, and apply its updates
+async fn api_ca_change_request_approve(
+    req: Request,
+    ca: CaHandle,
+    id: u64,
+) -> RoutingResult {
+    aa!(req, Permission::ROUTES_APPROVE, Handle::from(&ca), {
+        let actor = req.actor();
+        render_empty_res(
+            req.state().ca_change_request_approve(ca, id, &actor).await,
+        )
+    })
+}
+
+/// Reject a pending change request for this CA
+async fn api_ca_change_request_reject(
+    req: Request,
+    ca: CaHandle,
+    id: u64,
+) -> RoutingResult {
+    aa!(req, Permission::ROUTES_APPROVE, Handle::from(&ca), {
+        let actor = req.actor();
+        render_empty_res(
+            req.state().ca_change_request_reject(ca, id, &actor).await,
+        )
+    })
+}
+
 /// Show the state of ROAs vs BGP for this CA
 async fn api_ca_routes_analysis(
     req: Request,
@@ -2555,35 +3126,228 @@ async fn api_suspend_all(req: Request) -> RoutingResult {
 
 //------------ Serve RRDP Files ----------------------------------------------
 
+/// Cache-Control for the notification file, which changes whenever the
+/// repository is updated.
+const RRDP_NOTIFICATION_CACHE_CONTROL: &str =
+    "public, max-age=60, must-revalidate";
+
+/// Cache-Control for snapshot and delta files. These are never modified,
+/// as every update uses new URIs, but they are removed after some time.
+const RRDP_FILE_CACHE_CONTROL: &str = "public, max-age=86400, immutable";
+
 async fn rrdp(req: Request) -> RoutingResult {
     if !req.path().full().starts_with("/rrdp/") {
         Err(req) // Not for us
     } else {
         let mut full_path: PathBuf = req.state.rrdp_base_path();
         let (_, path) = req.path.remaining().split_at(1);
-        let cache_seconds = if path.ends_with("notification.xml") {
-            60
+        let is_notification = path.ends_with("notification.xml");
+        let cache_control = if is_notification {
+            RRDP_NOTIFICATION_CACHE_CONTROL
         } else {
-            86400
+            RRDP_FILE_CACHE_CONTROL
         };
         full_path.push(path);
 
-        match File::open(&full_path) {
-            Ok(mut file) if full_path.is_file() => {
+        let mut file = match File::open(&full_path) {
+            Ok(file) if full_path.is_file() => file,
+            _ => return Ok(HttpResponse::not_found()),
+        };
+        let (len, modified) = match file.metadata() {
+            Ok(meta) => (meta.len(), meta.modified().ok()),
+            Err(_) => return Ok(HttpResponse::not_found()),
+        };
+        let last_modified = modified.map(DateTime::<Utc>::from);
+        let last_modified_str =
+            last_modified.map(|time| format_http_date(&time));
+
+        let mut content = None;
+        let etag = if is_notification {
+            // The notification file is replaced whenever the repository
+            // is updated, so use a hash of its content as a strong ETag.
+            let mut buffer = Vec::new();
+            if file.read_to_end(&mut buffer).is_err() {
+                return Ok(HttpResponse::not_found());
+            }
+            let etag = format!("\"{}\"", hex::encode(sha256(&buffer)));
+            content = Some(buffer);
+            etag
+        } else {
+            // Snapshot and delta files are never modified, so their ETag
+            // is derived from their size and time of last modification.
+            // This way they only need to be read if they are served.
+            let nanos = modified
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_nanos())
+                .unwrap_or_default();
+            format!("\"{:x}-{:x}\"", len, nanos)
+        };
+
+        // The notification file can be replaced several times within the
+        // one second resolution of HTTP dates, so it is only validated
+        // using its ETag.
+        let modified_since =
+            if is_notification { None } else { last_modified };
+
+        let conditional = matches!(*req.method(), Method::GET | Method::HEAD);
+        if conditional
+            && rrdp_not_modified(req.headers(), &etag, modified_since)
+        {
+            return Ok(HttpResponse::not_modified(
+                cache_control,
+                &etag,
+                last_modified_str.as_deref(),
+            ));
+        }
+
+        let content = match content {
+            Some(content) => content,
+            None => {
                 let mut buffer = Vec::new();
-                match file.read_to_end(&mut buffer) {
-                    Ok(_) => Ok(HttpResponse::xml_with_cache(
-                        buffer,
-                        cache_seconds,
-                    )),
-                    Err(_) => Ok(HttpResponse::not_found()),
+                if file.read_to_end(&mut buffer).is_err() {
+                    return Ok(HttpResponse::not_found());
                 }
+                buffer
+            }
+        };
+
+        Ok(HttpResponse::xml_with_validators(
+            content,
+            cache_control,
+            &etag,
+            last_modified_str.as_deref(),
+        ))
+    }
+}
+
+/// Formats a time as an HTTP-date, e.g. "Sun, 06 Nov 1994 08:49:37 GMT".
+fn format_http_date(time: &DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Returns whether the client already has the current version of a file,
+/// based on the If-None-Match and If-Modified-Since request headers.
+///
+/// As per RFC 9110 section 13.2.2, If-Modified-Since is ignored if the
+/// request has an If-None-Match header. It is also ignored if no time of
+/// last modification is given.
+fn rrdp_not_modified(
+    headers: &HeaderMap,
+    etag: &str,
+    last_modified: Option<DateTime<Utc>>,
+) -> bool {
+    if let Some(if_none_match) = headers.get(IF_NONE_MATCH) {
+        let if_none_match = match if_none_match.to_str() {
+            Ok(value) => value,
+            Err(_) => return false,
+        };
+
+        // Uses the weak comparison, i.e. W/"x" matches "x".
+        return if_none_match.split(',').map(str::trim).any(|tag| {
+            tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag
+        });
+    }
+
+    let if_modified_since = headers
+        .get(IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok());
+
+    match (if_modified_since, last_modified) {
+        (Some(since), Some(modified)) => {
+            // HTTP dates have a resolution of one second.
+            modified.timestamp() <= since.timestamp()
+        }
+        _ => false,
+    }
+}
+
+//------------ Support RPKI Signed Checklists (RSC) -------------------------
+
+async fn api_ca_rsc(
+    req: Request,
+    path: &mut RequestPath,
+    ca: CaHandle,
+) -> RoutingResult {
+    // Note that 'verify' is not an RSC name, so it is matched first. An
+    // empty segment, i.e. a trailing slash, lists the RSCs.
+    let segment = path
+        .next()
+        .filter(|segment| !segment.is_empty())
+        .map(RscName::from);
+    match segment {
+        Some(segment) if segment == "verify" => {
+            match (req.method().clone(), path.next()) {
+                (Method::POST, None) => api_ca_rsc_verify(req, ca).await,
+                _ => render_unknown_method(),
             }
-            _ => Ok(HttpResponse::not_found()),
         }
+        Some(name) => match *req.method() {
+            Method::POST => match path.next() {
+                Some("sign") => api_ca_rsc_sign(req, ca, name).await,
+                _ => render_unknown_method(),
+            },
+            Method::GET => api_ca_rsc_show(req, ca, name).await,
+            _ => render_unknown_method(),
+        },
+        None => match *req.method() {
+            Method::GET => api_ca_rsc_list(req, ca).await,
+            _ => render_unknown_method(),
+        },
     }
 }
 
+async fn api_ca_rsc_list(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::RTA_LIST,
+        Handle::from(&ca),
+        render_json_res(req.state().rsc_list(ca).await)
+    )
+}
+
+async fn api_ca_rsc_show(
+    req: Request,
+    ca: CaHandle,
+    name: RscName,
+) -> RoutingResult {
+    aa!(
+        req,
+        Permission::RTA_READ,
+        Handle::from(&ca),
+        render_json_res(req.state().rsc_show(ca, name).await)
+    )
+}
+
+async fn api_ca_rsc_sign(
+    req: Request,
+    ca: CaHandle,
+    name: RscName,
+) -> RoutingResult {
+    aa!(req, Permission::RTA_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        let state = req.state().clone();
+        match req.json().await {
+            Err(e) => render_error(e),
+            Ok(request) => render_empty_res(
+                state.rsc_sign(ca, name, request, &actor).await,
+            ),
+        }
+    })
+}
+
+async fn api_ca_rsc_verify(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(req, Permission::RTA_READ, Handle::from(&ca), {
+        let state = req.state().clone();
+        match req.json().await {
+            Err(e) => render_error(e),
+            Ok(request) => {
+                render_json_res(state.rsc_verify(ca, request).await)
+            }
+        }
+    })
+}
+
 //------------ Support Resource Tagged Attestations (RTA)
 //------------ ----------------------
 
@@ -2715,6 +3479,9 @@ async fn api_ta(req: Request, path: &mut RequestPath) -> RoutingResult {
     //    GET  /proxy/repo/request.json        get RFC8181 publisher request
     //    GET  /proxy/repo                     get repository contact
     //    POST /proxy/repo                     add pub server
+    //    GET  /proxy/key_roll                 show key roll status
+    //    POST /proxy/key_roll                 plan key roll step for the
+    //                                         next sign request
     //    POST /proxy/signer/add               add initialised signer to proxy
     //    POST /proxy/signer/request           create sign request for signer
     // (returns request)    GET  /proxy/signer/request           show open
@@ -2727,8 +3494,8 @@ async fn api_ta(req: Request, path: &mut RequestPath) -> RoutingResult {
     //    GET  /proxy/children/{child}/parent_response.json    show parent
     // response for child    GET  /proxy/children/{child}/parent_response.
     // xml    show parent response for child    POST /proxy/children/
-    // {child}          future: update child    DEL  /proxy/children/
-    // {child}          future: remove child
+    // {child}          update child resources    DEL  /proxy/children/
+    // {child}          remove child and revoke its certificate
     //
     // krillta signer --dir
     //            init
@@ -2823,6 +3590,24 @@ async fn api_ta(req: Request, path: &mut RequestPath) -> RoutingResult {
                 },
                 _ => render_unknown_method(),
             },
+            Some("key_roll") => match *req.method() {
+                Method::GET => render_json_res(
+                    req.state().ta_proxy_key_roll_status().await,
+                ),
+                Method::POST => {
+                    let server = req.state().clone();
+                    let actor = req.actor.clone();
+                    match req.json().await {
+                        Ok(key_roll) => render_empty_res(
+                            server
+                                .ta_proxy_key_roll_plan(key_roll, &actor)
+                                .await,
+                        ),
+                        Err(e) => render_error(e),
+                    }
+                }
+                _ => render_unknown_method(),
+            },
             Some("children") => match path.path_arg::<ChildHandle>() {
                 Some(child) => match path.next() {
                     Some("parent_response.json") => render_json_res(
@@ -2843,12 +3628,25 @@ async fn api_ta(req: Request, path: &mut RequestPath) -> RoutingResult {
                         }
                     }
                     None => match *req.method() {
-                        Method::POST => render_error(Error::custom(
-                            "update TA child not yet supported",
-                        )),
-                        Method::DELETE => render_error(Error::custom(
-                            "remove TA child not yet supported",
-                        )),
+                        Method::POST => {
+                            let actor = req.actor();
+                            let server = req.state().clone();
+                            match req.json().await {
+                                Ok(update) => render_empty_res(
+                                    server
+                                        .ta_proxy_children_update(
+                                            child, update, &actor,
+                                        )
+                                        .await,
+                                ),
+                                Err(e) => render_error(e),
+                            }
+                        }
+                        Method::DELETE => render_empty_res(
+                            req.state()
+                                .ta_proxy_children_remove(child, &req.actor())
+                                .await,
+                        ),
                         _ => render_unknown_method(),
                     },
                     _ => render_unknown_method(),
@@ -2883,8 +3681,67 @@ async fn api_ta(req: Request, path: &mut RequestPath) -> RoutingResult {
 mod tests {
     // NOTE: This is extensively tested through the functional and e2e tests
     // found under       the $project/tests dir
+    use super::*;
     use crate::test;
 
+    #[test]
+    fn rrdp_conditional_requests() {
+        let etag = "\"abc\"";
+        let modified =
+            DateTime::parse_from_rfc2822("Sun, 06 Nov 1994 08:49:37 GMT")
+                .unwrap()
+                .with_timezone(&Utc);
+        assert_eq!(
+            format_http_date(&modified),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+
+        let not_modified = |name: HeaderName, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_str(value).unwrap());
+            rrdp_not_modified(&headers, etag, Some(modified))
+        };
+
+        assert!(!rrdp_not_modified(&HeaderMap::new(), etag, Some(modified)));
+
+        assert!(not_modified(IF_NONE_MATCH, "\"abc\""));
+        assert!(not_modified(IF_NONE_MATCH, "\"xyz\", W/\"abc\""));
+        assert!(not_modified(IF_NONE_MATCH, "*"));
+        assert!(!not_modified(IF_NONE_MATCH, "\"xyz\""));
+
+        assert!(not_modified(
+            IF_MODIFIED_SINCE,
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        ));
+        assert!(not_modified(
+            IF_MODIFIED_SINCE,
+            "Mon, 07 Nov 1994 08:49:37 GMT"
+        ));
+        assert!(!not_modified(
+            IF_MODIFIED_SINCE,
+            "Sun, 06 Nov 1994 08:49:36 GMT"
+        ));
+        assert!(!not_modified(IF_MODIFIED_SINCE, "yesterday"));
+
+        // If-Modified-Since is ignored without a time of last modification,
+        // as is the case for the notification file.
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Mon, 07 Nov 1994 08:49:37 GMT"),
+        );
+        assert!(!rrdp_not_modified(&headers, etag, None));
+
+        // If-None-Match takes precedence over If-Modified-Since
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("\"xyz\""));
+        headers.insert(
+            IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Mon, 07 Nov 1994 08:49:37 GMT"),
+        );
+        assert!(!rrdp_not_modified(&headers, etag, Some(modified)));
+    }
+
     #[tokio::test]
     async fn start_krill_daemon() {
         let cleanup = test::start_krill_with_default_test_config(
@@ -6,13 +6,14 @@ use pin_project_lite::pin_project;
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::task::{Context, Poll};
 use std::{error, fmt, io};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio::net::TcpStream;
 use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
-use tokio_rustls::rustls::KeyLogFile;
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{KeyLogFile, RootCertStore};
 use tokio_rustls::server::TlsStream;
 use tokio_rustls::{Accept, TlsAcceptor};
 
@@ -25,12 +26,36 @@ const SSLKEYLOGFILE_ENV_VAR_NAME: &str = "SSLKEYLOGFILE";
 //------------ create_server_config -----------------------------------------
 
 /// Creates the TLS server config.
+///
+/// If `client_ca_path` is given, then clients are asked for a certificate
+/// which is verified against the CA certificates in that file. Clients
+/// which do not present a certificate are still accepted, they will have
+/// to authenticate in some other way.
 pub fn create_server_config(
     key_path: &Path,
     cert_path: &Path,
+    client_ca_path: Option<&Path>,
 ) -> Result<ServerConfig, TlsConfigError> {
-    let mut config = ServerConfig::builder()
-        .with_no_client_auth()
+    let builder = match client_ca_path {
+        None => ServerConfig::builder().with_no_client_auth(),
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(path)? {
+                roots.add(cert).map_err(|err| {
+                    TlsConfigError::other(ErrorKind::Cert(path.into()), err)
+                })?;
+            }
+            let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+                .allow_unauthenticated()
+                .build()
+                .map_err(|err| {
+                    TlsConfigError::other(ErrorKind::Cert(path.into()), err)
+                })?;
+            ServerConfig::builder().with_client_cert_verifier(verifier)
+        }
+    };
+
+    let mut config = builder
         .with_single_cert(read_certs(cert_path)?, read_key(key_path)?)
         .map_err(|err| TlsConfigError::other(ErrorKind::Tls, err))?;
 
@@ -42,6 +67,59 @@ pub fn create_server_config(
     Ok(config)
 }
 
+//------------ ReloadableTlsAcceptor ----------------------------------------
+
+/// A TLS acceptor of which the server config can be reloaded.
+///
+/// Connections use the acceptor that was current when they were accepted,
+/// so reloading does not affect connections which are already established.
+pub struct ReloadableTlsAcceptor {
+    key_path: PathBuf,
+    cert_path: PathBuf,
+    client_ca_path: Option<PathBuf>,
+    acceptor: RwLock<TlsAcceptor>,
+}
+
+impl ReloadableTlsAcceptor {
+    /// Creates an acceptor using the given files, see
+    /// [`create_server_config`].
+    pub fn new(
+        key_path: PathBuf,
+        cert_path: PathBuf,
+        client_ca_path: Option<PathBuf>,
+    ) -> Result<Self, TlsConfigError> {
+        let config = create_server_config(
+            &key_path,
+            &cert_path,
+            client_ca_path.as_deref(),
+        )?;
+        Ok(ReloadableTlsAcceptor {
+            key_path,
+            cert_path,
+            client_ca_path,
+            acceptor: RwLock::new(TlsAcceptor::from(Arc::new(config))),
+        })
+    }
+
+    /// Returns the current acceptor.
+    pub fn acceptor(&self) -> TlsAcceptor {
+        self.acceptor.read().unwrap().clone()
+    }
+
+    /// Reads the files again and replaces the current acceptor.
+    ///
+    /// If the files cannot be read, then the current acceptor is kept.
+    pub fn reload(&self) -> Result<(), TlsConfigError> {
+        let config = create_server_config(
+            &self.key_path,
+            &self.cert_path,
+            self.client_ca_path.as_deref(),
+        )?;
+        *self.acceptor.write().unwrap() = TlsAcceptor::from(Arc::new(config));
+        Ok(())
+    }
+}
+
 /// Reads the certificates from the given PEM file.
 fn read_certs(
     path: &Path,
@@ -151,6 +229,21 @@ impl TlsTcpStream {
             _ => Poll::Ready(Ok(self)),
         }
     }
+
+    /// Returns the end-entity certificate presented by the client, if any.
+    ///
+    /// This is only available once the TLS handshake has concluded.
+    fn client_certificate(&self) -> Option<CertificateDer<'static>> {
+        match self {
+            TlsTcpStream::Stream { fut } => fut
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| cert.clone().into_owned()),
+            _ => None,
+        }
+    }
 }
 
 impl AsyncRead for TlsTcpStream {
@@ -239,6 +332,26 @@ impl MaybeTlsTcpStream {
             },
         }
     }
+
+    /// Concludes the TLS handshake, if any, and returns the end-entity
+    /// certificate presented by the client.
+    ///
+    /// Returns `Ok(None)` for plain TCP streams, and for clients which did
+    /// not present a certificate.
+    pub async fn client_certificate(
+        &mut self,
+    ) -> Result<Option<CertificateDer<'static>>, io::Error> {
+        match self.sock {
+            Either::Left(_) => Ok(None),
+            Either::Right(ref mut sock) => {
+                futures_util::future::poll_fn(|cx| {
+                    Pin::new(&mut *sock).poll_accept(cx).map_ok(|_| ())
+                })
+                .await?;
+                Ok(sock.client_certificate())
+            }
+        }
+    }
 }
 
 impl AsyncRead for MaybeTlsTcpStream {
@@ -311,6 +424,27 @@ impl AsyncWrite for MaybeTlsTcpStream {
     }
 }
 
+//------------ ClientCertificate --------------------------------------------
+
+/// The verified end-entity certificate presented by a TLS client.
+///
+/// This is added to the extensions of each request received on the
+/// connection, so that it can be used to authenticate the client.
+#[derive(Clone, Debug)]
+pub struct ClientCertificate(CertificateDer<'static>);
+
+impl From<CertificateDer<'static>> for ClientCertificate {
+    fn from(cert: CertificateDer<'static>) -> Self {
+        ClientCertificate(cert)
+    }
+}
+
+impl AsRef<[u8]> for ClientCertificate {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
 //------------ TlsConfigError -----------------------------------------------
 
 /// Represents errors that can occur building the TlsConfig
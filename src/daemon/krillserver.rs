@@ -5,6 +5,7 @@ use bytes::Bytes;
 use chrono::Duration;
 
 use futures_util::future::try_join_all;
+use tokio_rustls::TlsAcceptor;
 
 use rpki::{
     ca::{
@@ -21,19 +22,27 @@ use crate::{
         api::{
             self,
             import::{ExportChild, ImportChild},
-            AddChildRequest, AllCertAuthIssues, AspaDefinitionList,
-            AspaDefinitionUpdates, AspaProvidersUpdate, BgpSecCsrInfoList,
-            BgpSecDefinitionUpdates, CaCommandDetails, CaRepoDetails,
-            CertAuthInfo, CertAuthInit, CertAuthIssues, CertAuthList,
-            CertAuthStats, ChildCaInfo, ChildrenConnectionStats,
+            AddChildRequest, AllCertAuthIssues, ApiTokenIssued, ApiTokenList,
+            ApiTokenRequest, AspaDefinitionList, AspaDefinitionUpdates,
+            AspaProvidersUpdate, BgpSecCsrInfoList, BgpSecDefinitionUpdates,
+            CaCommandDetails, CaRepoDetails, CertAuthInfo, CertAuthInit,
+            CertAuthIssues, CertAuthList, CertAuthStats, ChangeRequest,
+            ChangeRequestList, ChildCaInfo, ChildrenConnectionStats,
             CommandHistory, CommandHistoryCriteria, ConfiguredRoa,
-            CustomerAsn, IdCertInfo, ParentCaContact, ParentCaReq,
-            PublicationServerUris, PublisherDetails, ReceivedCert,
-            RepoFileDeleteCriteria, RepositoryContact, RoaConfiguration,
-            RoaConfigurationUpdates, RoaPayload, RtaList, RtaName,
-            RtaPrepResponse, ServerInfo, Timestamp, UpdateChildRequest,
+            CustomerAsn, GhostbusterRecordUpdates, GhostbusterRecords,
+            IdCertInfo, ParentCaContact, ParentCaReq, PublicationServerUris,
+            PublisherDetails, PublisherObjects, PublisherObjectsDiff,
+            ReceivedCert, RepoFileDeleteCriteria, RepositoryContact,
+            RoaConfiguration, RoaConfigurationUpdates, RoaPayload,
+            RrdpArchiveIndex, RrdpArchiveQuery, RrdpArchiveState, RscList,
+            RscName, RtaList, RtaName, RtaPrepResponse,
+            ScheduleRoaUpdatesRequest, ScheduledRoaUpdatesList, ServerInfo,
+            Timestamp, UpdateChildRequest,
+        },
+        bgp::{
+            AsPaths, AspaAnalysisReport, BgpAnalyser, BgpAnalysisReport,
+            BgpAnalysisSuggestion,
         },
-        bgp::{BgpAnalyser, BgpAnalysisReport, BgpAnalysisSuggestion},
         crypto::KrillSignerBuilder,
         error::Error,
         KrillEmptyResult, KrillResult,
@@ -43,24 +52,30 @@ use crate::{
         auth::{providers::AdminTokenAuthProvider, Authorizer, LoggedInUser},
         ca::{
             self, testbed_ca_handle, CaManager, CaStatus,
-            ResourceTaggedAttestation, RtaContentRequest, RtaPrepareRequest,
+            ResourceTaggedAttestation, RpkiSignedChecklist,
+            RscContentRequest, RscVerification, RscVerifyRequest,
+            RtaContentRequest, RtaPrepareRequest,
         },
         config::{AuthType, Config},
-        http::{HttpResponse, HyperRequest},
+        http::{tls::ReloadableTlsAcceptor, HttpResponse, HyperRequest},
         mq::{now, Task, TaskQueue},
+        properties::LeaderElection,
         scheduler::Scheduler,
     },
     pubd::{RepoStats, RepositoryManager},
     ta::{
-        ta_handle, TaCertDetails, TrustAnchorSignedRequest,
-        TrustAnchorSignedResponse, TrustAnchorSignerInfo, TA_NAME,
+        ta_handle, TaCertDetails, TaKeyRollRequest, TaKeyRollStatus,
+        TrustAnchorSignedRequest, TrustAnchorSignedResponse,
+        TrustAnchorSignerInfo, TA_NAME,
     },
 };
 
 #[cfg(feature = "multi-user")]
 use crate::daemon::auth::{
     common::session::LoginSessionCache,
-    providers::{ConfigFileAuthProvider, OpenIDConnectAuthProvider},
+    providers::{
+        ConfigFileAuthProvider, LdapAuthProvider, OpenIDConnectAuthProvider,
+    },
 };
 
 //------------ KrillServer ---------------------------------------------------
@@ -86,6 +101,9 @@ pub struct KrillServer {
     // Shared message queue
     mq: Arc<TaskQueue>,
 
+    // Tells whether this node is the leader, when nodes share storage
+    leader_election: Arc<LeaderElection>,
+
     // Time this server was started
     started: Timestamp,
 
@@ -96,6 +114,9 @@ pub struct KrillServer {
     // System actor
     system_actor: Actor,
 
+    // TLS acceptor for the HTTPS listeners, unless HTTPS is disabled
+    tls: Option<Arc<ReloadableTlsAcceptor>>,
+
     pub config: Arc<Config>,
 }
 
@@ -103,7 +124,10 @@ pub struct KrillServer {
 impl KrillServer {
     /// Creates a new publication server. Note that state is preserved
     /// in the data storage.
-    pub async fn build(config: Arc<Config>) -> KrillResult<Self> {
+    pub async fn build(
+        config: Arc<Config>,
+        tls: Option<Arc<ReloadableTlsAcceptor>>,
+    ) -> KrillResult<Self> {
         let service_uri = config.service_uri();
 
         info!("Starting {} v{}", KRILL_SERVER_APP, KRILL_VERSION);
@@ -157,6 +181,15 @@ impl KrillServer {
                 )?
                 .into(),
             )?,
+            #[cfg(feature = "multi-user")]
+            AuthType::Ldap => Authorizer::new(
+                config.clone(),
+                LdapAuthProvider::new(
+                    config.clone(),
+                    login_session_cache.clone(),
+                )?
+                .into(),
+            )?,
         };
         let system_actor = authorizer.actor_from_def(ACTOR_DEF_KRILL);
 
@@ -187,14 +220,20 @@ impl KrillServer {
             config.bgp_risdumps_enabled,
             &config.bgp_risdumps_v4_uri,
             &config.bgp_risdumps_v6_uri,
+            &config.bgp_sources,
+            &config.irr_sources,
         ));
 
-        // When multi-node set ups with a shared queue are
-        // supported then we can no longer safely reschedule
-        // ALL running tests. See issue: #1112
-        mq.reschedule_tasks_at_startup()?;
+        // If this node shares its storage with other nodes, then only the
+        // leader may run tasks. A standby node will reschedule running
+        // tasks and queue start tasks when it takes over, see Scheduler.
+        let leader_election = Arc::new(LeaderElection::create(&config)?);
+        leader_election.renew()?;
 
-        mq.schedule(Task::QueueStartTasks, now())?;
+        if leader_election.is_leader() {
+            mq.reschedule_tasks_at_startup()?;
+            mq.schedule(Task::QueueStartTasks, now())?;
+        }
 
         let server = KrillServer {
             service_uri,
@@ -203,10 +242,12 @@ impl KrillServer {
             ca_manager,
             bgp_analyser,
             mq,
+            leader_election,
             started: Timestamp::now(),
             #[cfg(feature = "multi-user")]
             login_session_cache,
             system_actor,
+            tls,
             config: config.clone(),
         };
 
@@ -317,11 +358,22 @@ impl KrillServer {
             self.bgp_analyser.clone(),
             #[cfg(feature = "multi-user")]
             self.login_session_cache.clone(),
+            self.leader_election.clone(),
             self.config.clone(),
             self.system_actor.clone(),
         )
     }
 
+    pub fn leader_election(&self) -> Arc<LeaderElection> {
+        self.leader_election.clone()
+    }
+
+    /// Returns whether this node is a standby, i.e. it shares its storage
+    /// with another node which is the current leader.
+    pub fn is_standby(&self) -> bool {
+        !self.leader_election.is_leader()
+    }
+
     pub fn service_base_uri(&self) -> &uri::Https {
         &self.service_uri
     }
@@ -329,6 +381,35 @@ impl KrillServer {
     pub fn server_info(&self) -> ServerInfo {
         ServerInfo::new(KRILL_VERSION, self.started)
     }
+
+    /// Returns the current TLS acceptor, or None if HTTPS is disabled.
+    pub fn tls_acceptor(&self) -> Option<TlsAcceptor> {
+        self.tls.as_ref().map(|tls| tls.acceptor())
+    }
+
+    /// Reloads the TLS certificate and key, and the authentication users
+    /// and policies from the config file.
+    ///
+    /// Other changes in the config file require a restart. Connections
+    /// and requests which are in progress are not affected.
+    pub fn reload(&self) -> KrillResult<()> {
+        if let Some(tls) = &self.tls {
+            tls.reload().map_err(|e| Error::HttpsSetup(e.to_string()))?;
+            info!("Reloaded TLS certificate and key");
+        }
+
+        if let Some(config_file) = &self.config.config_file {
+            let mut config = Config::read_config(config_file)
+                .map_err(|e| Error::ConfigError(e.to_string()))?;
+            config
+                .process()
+                .map_err(|e| Error::ConfigError(e.to_string()))?;
+            self.authorizer.reload(Arc::new(config))?;
+            info!("Reloaded authentication config from {}", config_file);
+        }
+
+        Ok(())
+    }
 }
 
 /// # Authentication and Access
@@ -363,6 +444,26 @@ impl KrillServer {
         self.authorizer.logout(request).await
     }
 
+    pub fn api_tokens_list(&self) -> KrillResult<ApiTokenList> {
+        self.authorizer.api_tokens().list()
+    }
+
+    pub fn api_token_issue(
+        &self,
+        request: ApiTokenRequest,
+        actor: &Actor,
+    ) -> KrillResult<ApiTokenIssued> {
+        self.authorizer.api_tokens().issue(request, actor)
+    }
+
+    pub fn api_token_revoke(
+        &self,
+        name: String,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.authorizer.api_tokens().revoke(name, actor)
+    }
+
     pub fn testbed_enabled(&self) -> bool {
         self.ca_manager.testbed_enabled()
     }
@@ -421,6 +522,36 @@ impl KrillServer {
         self.repo_manager.get_publisher_details(publisher)
     }
 
+    pub fn get_publisher_objects(
+        &self,
+        publisher: &PublisherHandle,
+    ) -> KrillResult<PublisherObjects> {
+        self.repo_manager.get_publisher_objects(publisher)
+    }
+
+    /// Compares the objects of a publisher with the objects that the CA
+    /// with the same handle believes it has published.
+    pub async fn diff_publisher_objects(
+        &self,
+        publisher: &PublisherHandle,
+    ) -> KrillResult<PublisherObjectsDiff> {
+        let ca = publisher.convert();
+        if !self.ca_manager.has_ca(&ca)? {
+            return Err(Error::CaUnknown(ca));
+        }
+
+        let ca_elements = self
+            .ca_manager
+            .ca_repo_elements(&ca)
+            .await?
+            .into_values()
+            .flatten()
+            .collect();
+
+        self.repo_manager
+            .diff_publisher_objects(publisher, ca_elements)
+    }
+
     pub fn rrdp_base_path(&self) -> PathBuf {
         let mut path = self.config.repo_dir().to_path_buf();
         path.push("rrdp");
@@ -513,6 +644,22 @@ impl KrillServer {
             .await
     }
 
+    pub async fn ta_proxy_key_roll_plan(
+        &self,
+        key_roll: TaKeyRollRequest,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.ca_manager
+            .ta_proxy_key_roll_plan(key_roll, actor)
+            .await
+    }
+
+    pub async fn ta_proxy_key_roll_status(
+        &self,
+    ) -> KrillResult<TaKeyRollStatus> {
+        self.ca_manager.ta_proxy_key_roll_status().await
+    }
+
     pub async fn ta_proxy_children_add(
         &self,
         child_request: AddChildRequest,
@@ -529,11 +676,40 @@ impl KrillServer {
             .await
     }
 
+    pub async fn ta_proxy_children_update(
+        &self,
+        child: ChildHandle,
+        req: UpdateChildRequest,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.ca_manager
+            .ta_proxy_children_update(child, req, actor)
+            .await
+    }
+
+    pub async fn ta_proxy_children_remove(
+        &self,
+        child: ChildHandle,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.ca_manager.ta_proxy_children_remove(child, actor).await
+    }
+
     pub async fn ta_cert_details(&self) -> KrillResult<TaCertDetails> {
         let proxy = self.ca_manager.get_trust_anchor_proxy().await?;
         Ok(proxy.get_ta_details()?.clone())
     }
 
+    /// Returns the details of the staged TA key during a key roll.
+    pub async fn ta_staged_cert_details(&self) -> Option<TaCertDetails> {
+        self.ca_manager
+            .ta_proxy_key_roll_status()
+            .await
+            .ok()
+            .and_then(|status| status.staged_key)
+            .map(|staged| staged.ta_cert_details)
+    }
+
     pub async fn trust_anchor_cert(&self) -> Option<ReceivedCert> {
         self.ta_cert_details()
             .await
@@ -1006,6 +1182,17 @@ impl KrillServer {
             }
         }
 
+        if let Some(validation) = ca_status.repo_validation() {
+            for issue in validation.issues() {
+                let error = Error::CaRepoContentIssue(
+                    ca.clone(),
+                    issue.uri().to_string(),
+                    issue.issue().to_string(),
+                );
+                issues.add_repo_content_issue(error.to_error_response());
+            }
+        }
+
         Ok(issues)
     }
 }
@@ -1206,7 +1393,7 @@ impl KrillServer {
         ca: CaHandle,
         updates: AspaDefinitionUpdates,
         actor: &Actor,
-    ) -> KrillEmptyResult {
+    ) -> KrillResult<Option<ChangeRequest>> {
         self.ca_manager
             .ca_aspas_definitions_update(ca, updates, actor)
             .await
@@ -1218,11 +1405,76 @@ impl KrillServer {
         customer: CustomerAsn,
         update: AspaProvidersUpdate,
         actor: &Actor,
-    ) -> KrillEmptyResult {
+    ) -> KrillResult<Option<ChangeRequest>> {
         self.ca_manager
             .ca_aspas_update_aspa(ca, customer, update, actor)
             .await
     }
+
+    /// Analyses the current ASPA definitions of a CA against the given
+    /// observed AS paths.
+    pub async fn ca_aspas_analysis(
+        &self,
+        ca: CaHandle,
+        paths: AsPaths,
+    ) -> KrillResult<AspaAnalysisReport> {
+        let definitions =
+            self.ca_manager.ca_aspas_definitions_show(ca).await?;
+        Ok(AspaAnalysisReport::new(&definitions, &paths))
+    }
+}
+
+/// # Handle Ghostbuster record requests
+impl KrillServer {
+    pub async fn ca_ghostbusters_show(
+        &self,
+        ca: CaHandle,
+    ) -> KrillResult<GhostbusterRecords> {
+        self.ca_manager.ca_ghostbusters_show(ca).await
+    }
+
+    pub async fn ca_ghostbusters_update(
+        &self,
+        ca: CaHandle,
+        updates: GhostbusterRecordUpdates,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager
+            .ca_ghostbusters_update(ca, updates, actor)
+            .await
+    }
+}
+
+/// # Handle change requests
+impl KrillServer {
+    pub async fn ca_change_requests_show(
+        &self,
+        ca: &CaHandle,
+    ) -> KrillResult<ChangeRequestList> {
+        self.ca_manager.ca_change_requests_show(ca).await
+    }
+
+    pub async fn ca_change_request_approve(
+        &self,
+        ca: CaHandle,
+        id: u64,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager
+            .ca_change_request_approve(ca, id, actor)
+            .await
+    }
+
+    pub async fn ca_change_request_reject(
+        &self,
+        ca: CaHandle,
+        id: u64,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager
+            .ca_change_request_reject(ca, id, actor)
+            .await
+    }
 }
 
 /// # Handle BGPSec requests
@@ -1253,7 +1505,7 @@ impl KrillServer {
         ca: CaHandle,
         updates: RoaConfigurationUpdates,
         actor: &Actor,
-    ) -> KrillEmptyResult {
+    ) -> KrillResult<Option<ChangeRequest>> {
         self.ca_manager.ca_routes_update(ca, updates, actor).await
     }
 
@@ -1266,6 +1518,35 @@ impl KrillServer {
         Ok(ca.configured_roas())
     }
 
+    pub async fn ca_routes_scheduled_show(
+        &self,
+        handle: &CaHandle,
+    ) -> KrillResult<ScheduledRoaUpdatesList> {
+        let ca = self.ca_manager.get_ca(handle).await?;
+
+        Ok(ca.route_authorizations_scheduled())
+    }
+
+    pub async fn ca_routes_schedule(
+        &self,
+        ca: CaHandle,
+        request: ScheduleRoaUpdatesRequest,
+        actor: &Actor,
+    ) -> KrillResult<Option<ChangeRequest>> {
+        self.ca_manager.ca_routes_schedule(ca, request, actor).await
+    }
+
+    pub async fn ca_routes_schedule_cancel(
+        &self,
+        ca: CaHandle,
+        id: u64,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager
+            .ca_routes_schedule_cancel(ca, id, actor)
+            .await
+    }
+
     pub async fn ca_routes_bgp_analysis(
         &self,
         handle: &CaHandle,
@@ -1348,6 +1629,75 @@ impl KrillServer {
     pub fn repository_session_reset(&self) -> KrillResult<()> {
         self.repo_manager.rrdp_session_reset()
     }
+
+    /// Returns the RRDP serials for which files are kept on disk.
+    pub fn repository_archive_index(&self) -> KrillResult<RrdpArchiveIndex> {
+        self.repo_manager.archive_index()
+    }
+
+    /// Reconstructs the repository content at an earlier RRDP serial.
+    pub fn repository_archive_state(
+        &self,
+        query: &RrdpArchiveQuery,
+    ) -> KrillResult<RrdpArchiveState> {
+        self.repo_manager.archive_state(query)
+    }
+
+    /// Save a file pushed by a Krill publication server which uses this
+    /// server as a mirror.
+    pub fn repository_mirror_save(
+        &self,
+        path: &str,
+        content: Bytes,
+    ) -> KrillResult<()> {
+        self.repo_manager.mirror_save(path, content)
+    }
+
+    /// Remove a file removed by a Krill publication server which uses this
+    /// server as a mirror.
+    pub fn repository_mirror_delete(&self, path: &str) -> KrillResult<()> {
+        self.repo_manager.mirror_delete(path)
+    }
+}
+
+/// # Handle RPKI Signed Checklist requests
+impl KrillServer {
+    /// List all known RSCs
+    pub async fn rsc_list(&self, ca: CaHandle) -> KrillResult<RscList> {
+        let ca = self.ca_manager.get_ca(&ca).await?;
+        Ok(ca.rsc_list())
+    }
+
+    /// Show RSC
+    pub async fn rsc_show(
+        &self,
+        ca: CaHandle,
+        name: RscName,
+    ) -> KrillResult<RpkiSignedChecklist> {
+        let ca = self.ca_manager.get_ca(&ca).await?;
+        ca.rsc_show(&name)
+    }
+
+    /// Sign a new RSC
+    pub async fn rsc_sign(
+        &self,
+        ca: CaHandle,
+        name: RscName,
+        request: RscContentRequest,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.ca_manager.rsc_sign(ca, name, request, actor).await
+    }
+
+    /// Verify an RSC issued by a CA against the given files
+    pub async fn rsc_verify(
+        &self,
+        ca: CaHandle,
+        request: RscVerifyRequest,
+    ) -> KrillResult<RscVerification> {
+        let ca = self.ca_manager.get_ca(&ca).await?;
+        ca.rsc_verify(request)
+    }
 }
 
 /// # Handle Resource Tagged Attestation requests
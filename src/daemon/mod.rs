@@ -6,3 +6,4 @@ pub mod krillserver;
 pub mod mq;
 pub mod properties;
 pub mod scheduler;
+pub mod webhooks;
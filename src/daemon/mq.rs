@@ -23,10 +23,18 @@ use rpki::{
 use crate::{
     commons::api::Timestamp,
     commons::eventsourcing,
-    commons::{eventsourcing::Aggregate, Error, KrillResult},
+    commons::{
+        eventsourcing::{blocking, Aggregate, KeyValueStore},
+        Error, KrillResult,
+    },
     constants::TASK_QUEUE_NS,
-    daemon::ca::{CertAuth, CertAuthEvent},
-    ta::{ta_handle, TrustAnchorProxy, TrustAnchorProxyEvent},
+    daemon::{
+        ca::{CertAuth, CertAuthEvent},
+        webhooks::WebhookNotification,
+    },
+    ta::{
+        ta_handle, TaKeyRollResponse, TrustAnchorProxy, TrustAnchorProxyEvent,
+    },
 };
 
 //------------ Task ---------------------------------------------------------
@@ -84,6 +92,11 @@ pub enum Task {
         ca_handle: CaHandle,
     },
 
+    // Fetches and validates the content published by the CA.
+    ValidateCaRepository {
+        ca_handle: CaHandle,
+    },
+
     RenewTestbedTa,
 
     RepublishIfNeeded,
@@ -95,6 +108,31 @@ pub enum Task {
 
     RrdpUpdateIfNeeded,
 
+    // Pushes the repository content to any configured mirrors.
+    SyncRepositoryMirrors,
+
+    // Removes time-limited ROA configurations once they expire.
+    RemoveExpiredRoas {
+        ca_handle: CaHandle,
+        ca_version: u64,
+    },
+
+    // Applies scheduled ROA configuration updates once their 'not before'
+    // time has passed.
+    ActivateScheduledRoas {
+        ca_handle: CaHandle,
+        ca_version: u64,
+        id: u64,
+    },
+
+    // Delivers a notification to a configured webhook. The attempt is
+    // increased each time a failed delivery is retried.
+    NotifyWebhook {
+        url: Url,
+        notification: WebhookNotification,
+        attempt: u32,
+    },
+
     #[cfg(feature = "multi-user")]
     SweepLoginCache,
 }
@@ -119,6 +157,12 @@ impl Task {
                     ca
                 ))
             }
+            Task::ValidateCaRepository { ca_handle: ca } => {
+                SegmentBuf::from_str(&format!(
+                    "validate_ca_repository_{}",
+                    ca
+                ))
+            }
             Task::RepublishIfNeeded => {
                 Ok(segment!("all_cas_republish_if_needed").to_owned())
             }
@@ -154,6 +198,21 @@ impl Task {
             Task::RrdpUpdateIfNeeded => {
                 Ok(segment!("update_rrdp_if_needed").to_owned())
             }
+            Task::SyncRepositoryMirrors => {
+                Ok(segment!("sync_repository_mirrors").to_owned())
+            }
+            Task::RemoveExpiredRoas { ca_handle: ca, .. } => {
+                SegmentBuf::from_str(&format!("remove_expired_roas_{}", ca))
+            }
+            Task::ActivateScheduledRoas {
+                ca_handle: ca, id, ..
+            } => SegmentBuf::from_str(&format!(
+                "activate_scheduled_roas_{}_{}",
+                ca, id
+            )),
+            Task::NotifyWebhook { notification, .. } => SegmentBuf::from_str(
+                &format!("notify_webhook_{}", notification.id()),
+            ),
             #[cfg(feature = "multi-user")]
             Task::SweepLoginCache => {
                 Ok(segment!("sweep_login_cache").to_owned())
@@ -193,6 +252,9 @@ impl fmt::Display for Task {
             Task::SuspendChildrenIfNeeded { ca_handle: ca } => {
                 write!(f, "verify if CA '{}' has children to suspend", ca)
             }
+            Task::ValidateCaRepository { ca_handle: ca } => {
+                write!(f, "validate the published content of CA '{}'", ca)
+            }
             Task::RepublishIfNeeded => {
                 write!(f, "let CAs republish their mft/crls if needed")
             }
@@ -208,6 +270,32 @@ impl fmt::Display for Task {
             Task::RrdpUpdateIfNeeded => {
                 write!(f, "create new RRDP delta, if needed")
             }
+            Task::SyncRepositoryMirrors => {
+                write!(f, "push repository content to mirrors")
+            }
+            Task::RemoveExpiredRoas { ca_handle: ca, .. } => {
+                write!(f, "remove expired ROAs for '{}'", ca)
+            }
+            Task::ActivateScheduledRoas {
+                ca_handle: ca, id, ..
+            } => {
+                write!(
+                    f,
+                    "activate scheduled ROA updates '{}' for '{}'",
+                    id, ca
+                )
+            }
+            Task::NotifyWebhook {
+                url, notification, ..
+            } => {
+                write!(
+                    f,
+                    "notify webhook {} of '{}' for CA '{}'",
+                    url,
+                    notification.event(),
+                    notification.ca()
+                )
+            }
 
             #[cfg(feature = "multi-user")]
             Task::SweepLoginCache => write!(f, "sweep up expired logins"),
@@ -227,6 +315,7 @@ impl fmt::Display for Task {
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 pub enum TaskResult {
     Done,                     // finished, nothing more to do
     FollowUp(Task, Priority), // finished, follow-up should be scheduled
@@ -237,20 +326,42 @@ pub enum TaskResult {
 
 #[derive(Debug)]
 pub struct TaskQueue {
-    q: kvx::KeyValueStore,
+    // Only taken when the queue is dropped, see the Drop implementation
+    // below.
+    q: Option<kvx::KeyValueStore>,
 }
 
 impl TaskQueue {
     pub fn new(storage_uri: &Url) -> KrillResult<Self> {
-        kvx::KeyValueStore::new(storage_uri, TASK_QUEUE_NS)
-            .map(|q| TaskQueue { q })
+        // The queue uses the kvx backend directly, which expects that the
+        // database schema was already created by our own postgres backend.
+        if KeyValueStore::is_postgres(storage_uri) {
+            KeyValueStore::create(storage_uri, TASK_QUEUE_NS)?;
+        }
+
+        blocking(|| kvx::KeyValueStore::new(storage_uri, TASK_QUEUE_NS))
+            .map(|q| TaskQueue { q: Some(q) })
             .map_err(Error::from)
     }
+
+    fn q(&self) -> &kvx::KeyValueStore {
+        self.q.as_ref().expect("task queue is only taken on drop")
+    }
+}
+
+impl Drop for TaskQueue {
+    fn drop(&mut self) {
+        // Closing the connections of a postgres backend blocks, which is
+        // not allowed on a runtime worker thread.
+        if let Some(q) = self.q.take() {
+            blocking(move || drop(q))
+        }
+    }
 }
 impl TaskQueue {
     pub fn pop(&self) -> Option<RunningTask> {
         trace!("Try to get a task off the queue");
-        match self.q.claim_scheduled_pending_task() {
+        match blocking(|| self.q().claim_scheduled_pending_task()) {
             Err(e) => {
                 // Log error and return nothing.
                 // We do this, because the key value store - if in future
@@ -335,15 +446,21 @@ impl TaskQueue {
             ))
         })?;
 
-        self.q
-            .schedule_task(task_name, json, Some(priority.to_millis()), mode)
-            .map_err(Error::from)
+        blocking(|| {
+            self.q().schedule_task(
+                task_name,
+                json,
+                Some(priority.to_millis()),
+                mode,
+            )
+        })
+        .map_err(Error::from)
     }
 
     /// Finish a running task, without rescheduling it.
     pub fn finish(&self, task: &kvx::Key) -> KrillResult<()> {
         debug!("Finish task: {}", task);
-        self.q.finish_running_task(task).map_err(Error::from)
+        blocking(|| self.q().finish_running_task(task)).map_err(Error::from)
     }
 
     /// Reschedule a running task, without finishing it.
@@ -353,24 +470,29 @@ impl TaskQueue {
         priority: Priority,
     ) -> KrillResult<()> {
         debug!("Reschedule task: {} to: {}", task, priority);
-        self.q
-            .reschedule_running_task(task, Some(priority.to_millis()))
-            .map_err(Error::from)
+        blocking(|| {
+            self.q()
+                .reschedule_running_task(task, Some(priority.to_millis()))
+        })
+        .map_err(Error::from)
     }
 
     /// Reschedule all running tasks to pending. This assumes that we only
-    /// have a single active node. See issue #1112
+    /// have a single active node, i.e. that this node is the leader if
+    /// storage is shared with other nodes.
     pub fn reschedule_tasks_at_startup(&self) -> KrillResult<()> {
-        let keys = self.q.running_tasks_keys()?;
+        let keys = blocking(|| self.q().running_tasks_keys())?;
 
         let queue_started_key_name = Task::QueueStartTasks.name()?;
 
         if keys.len() > 1 {
-            warn!("Rescheduling running tasks at startup, note that multi-node Krill servers are not yet supported.");
+            warn!("Rescheduling tasks that were left running.");
             for key in keys {
                 if key.name() != queue_started_key_name.as_ref() {
                     warn!("  - rescheduling: {}", key.name());
-                    self.q.reschedule_running_task(&key, None)?;
+                    blocking(|| {
+                        self.q().reschedule_running_task(&key, None)
+                    })?;
                 }
             }
         }
@@ -397,6 +519,7 @@ impl TaskQueue {
         match event {
             CertAuthEvent::RoasUpdated { .. }
             | CertAuthEvent::AspaObjectsUpdated { .. }
+            | CertAuthEvent::GhostbusterObjectUpdated { .. }
             | CertAuthEvent::ChildCertificatesUpdated { .. }
             | CertAuthEvent::BgpSecCertificatesUpdated { .. }
             | CertAuthEvent::ChildKeyRevoked { .. }
@@ -444,6 +567,27 @@ impl TaskQueue {
                 now(),
             ),
 
+            CertAuthEvent::RouteAuthorizationExpiry {
+                expires_at: Some(expires_at),
+                ..
+            } => self.schedule(
+                Task::RemoveExpiredRoas {
+                    ca_handle,
+                    ca_version,
+                },
+                Time::from(*expires_at).into(),
+            ),
+
+            CertAuthEvent::RouteAuthorizationsScheduled { scheduled } => self
+                .schedule(
+                    Task::ActivateScheduledRoas {
+                        ca_handle,
+                        ca_version,
+                        id: scheduled.id(),
+                    },
+                    Time::from(scheduled.not_before()).into(),
+                ),
+
             CertAuthEvent::ResourceClassRemoved {
                 resource_class_name,
                 parent,
@@ -612,10 +756,9 @@ impl eventsourcing::PreSaveEventListener<TrustAnchorProxy> for TaskQueue {
         for event in events {
             trace!("Seen TrustAnchorProxy event '{}'", event);
             match event {
-                TrustAnchorProxyEvent::ChildRequestAdded(
-                    _child,
-                    _request,
-                ) => {
+                TrustAnchorProxyEvent::ChildRequestAdded(_, _)
+                | TrustAnchorProxyEvent::KeyRollPlanned(_)
+                | TrustAnchorProxyEvent::ChildRemoved(_) => {
                     // schedule proxy -> signer sync
                     self.schedule(
                         Task::SyncTrustAnchorProxySignerIfPossible,
@@ -636,7 +779,8 @@ impl eventsourcing::PreSaveEventListener<TrustAnchorProxy> for TaskQueue {
                 | TrustAnchorProxyEvent::SignerAdded(_)
                 | TrustAnchorProxyEvent::SignerRequestMade(_)
                 | TrustAnchorProxyEvent::ChildAdded(_)
-                | TrustAnchorProxyEvent::ChildResponseGiven(_, _) => {
+                | TrustAnchorProxyEvent::ChildResponseGiven(_, _)
+                | TrustAnchorProxyEvent::ChildResourcesUpdated(_, _) => {
                     // No triggered actions needed
                 }
             }
@@ -649,15 +793,30 @@ impl eventsourcing::PreSaveEventListener<TrustAnchorProxy> for TaskQueue {
 impl eventsourcing::PostSaveEventListener<TrustAnchorProxy> for TaskQueue {
     fn listen(
         &self,
-        _proxy: &TrustAnchorProxy,
+        proxy: &TrustAnchorProxy,
         events: &[TrustAnchorProxyEvent],
     ) {
         for event in events {
             match event {
                 TrustAnchorProxyEvent::SignerResponseReceived(response) => {
                     // Schedule child->ta sync(s) now that there is a
-                    // response.
-                    for ca in response.content().child_responses.keys() {
+                    // response. If the TA key was rolled, then all children
+                    // have new certificates. Responses for removed children
+                    // (i.e. revocations) are ignored.
+                    let children: Vec<_> = if matches!(
+                        response.content().key_roll,
+                        Some(TaKeyRollResponse::Activated(_))
+                    ) {
+                        proxy.children().collect()
+                    } else {
+                        response
+                            .content()
+                            .child_responses
+                            .keys()
+                            .filter(|child| proxy.get_child(child).is_ok())
+                            .collect()
+                    };
+                    for ca in children {
                         trace!(
                             "Received signed response for TA child {}",
                             ca
@@ -679,6 +838,24 @@ impl eventsourcing::PostSaveEventListener<TrustAnchorProxy> for TaskQueue {
                         }
                     }
                 }
+                TrustAnchorProxyEvent::ChildResourcesUpdated(ca, _) => {
+                    // Let the child pick up its new entitlements.
+                    if let Err(e) = self.schedule(
+                        Task::SyncParent {
+                            ca_handle: ca.convert(),
+                            ca_version: 0,
+                            parent: ta_handle().into_converted(),
+                        },
+                        now(),
+                    ) {
+                        error!(
+                            "Could not schedule sync from {} to {}. Restart Krill or run 'krillc bulk refresh'. Error was: {}",
+                            ca,
+                            ta_handle(),
+                            e
+                        );
+                    }
+                }
                 _ => {
                     // No triggered actions needed
                 }
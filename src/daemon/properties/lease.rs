@@ -0,0 +1,309 @@
+//! Leader election for active/standby set ups.
+//!
+//! Multiple Krill nodes can use the same shared storage. Only one of them,
+//! the leader, may run scheduled tasks and accept changes. The leader holds
+//! a lease which is kept in the shared properties store, and renews it well
+//! before it expires. Other nodes are standby: they serve read requests and
+//! take over if the lease is not renewed in time.
+//!
+//! Note that the lease expiry is compared to the local clock of each node,
+//! so the clocks of all nodes must be synchronised. The leader stops acting
+//! as the leader a third of the lease period before its lease expires, so
+//! that it has stopped running tasks and writing before another node can
+//! take over. The clocks must differ by less than this safety margin.
+
+use std::{
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        RwLock,
+    },
+    time::Duration,
+};
+
+use kvx::{segment, Key, Scope, Segment};
+use tokio::time::sleep;
+
+use crate::{
+    commons::{api::Timestamp, eventsourcing::KeyValueStore, KrillResult},
+    daemon::config::Config,
+};
+
+const LEADER_LEASE_KEY: &Segment = segment!("leader_lease");
+
+//------------ LeaderLease -------------------------------------------------
+
+/// The lease held by the current leader.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LeaderLease {
+    node: String,
+    expires: Timestamp,
+}
+
+impl LeaderLease {
+    pub fn node(&self) -> &str {
+        &self.node
+    }
+
+    pub fn expires(&self) -> Timestamp {
+        self.expires
+    }
+
+    fn is_expired(&self, now: Timestamp) -> bool {
+        self.expires <= now
+    }
+}
+
+//------------ LeaderElection ----------------------------------------------
+
+/// Keeps track of whether this node is the leader.
+///
+/// If high availability is not enabled then this node is always the
+/// leader and nothing is stored.
+pub struct LeaderElection {
+    // The shared store for the lease, or None if HA is disabled
+    store: Option<KeyValueStore>,
+
+    // The name of this node
+    node: String,
+
+    lease_seconds: u32,
+
+    // Seconds since the epoch until which this node acts as the leader,
+    // i.e. the expiry of its lease minus the safety margin
+    held_until: AtomicI64,
+
+    // The lease as we saw it at the last renewal
+    last_seen: RwLock<Option<LeaderLease>>,
+}
+
+impl LeaderElection {
+    pub fn create(config: &Config) -> KrillResult<Self> {
+        if config.ha.ha_enabled {
+            let node = config
+                .ha
+                .ha_node_name
+                .clone()
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+            Ok(Self::new(
+                Some(config.general_key_value_store()?),
+                node,
+                config.ha.ha_lease_seconds,
+            ))
+        } else {
+            Ok(Self::new(None, "local".to_string(), 0))
+        }
+    }
+
+    fn new(
+        store: Option<KeyValueStore>,
+        node: String,
+        lease_seconds: u32,
+    ) -> Self {
+        LeaderElection {
+            store,
+            node,
+            lease_seconds,
+            held_until: AtomicI64::new(0),
+            last_seen: RwLock::new(None),
+        }
+    }
+
+    /// Returns whether leader election is used at all.
+    pub fn is_enabled(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Returns the name of this node.
+    pub fn node(&self) -> &str {
+        &self.node
+    }
+
+    /// Returns whether this node currently is the leader. A leader that
+    /// fails to renew its lease, e.g. because the storage cannot be
+    /// reached, steps down a safety margin before the lease expires.
+    pub fn is_leader(&self) -> bool {
+        !self.is_enabled()
+            || i64::from(Timestamp::now())
+                < self.held_until.load(Ordering::SeqCst)
+    }
+
+    /// Returns the lease as it was seen at the last renewal attempt,
+    /// unless it has expired since.
+    pub fn leader(&self) -> Option<LeaderLease> {
+        self.last_seen
+            .read()
+            .unwrap()
+            .clone()
+            .filter(|lease| !lease.is_expired(Timestamp::now()))
+    }
+
+    /// Returns the name of the current leader, for reporting.
+    pub fn leader_name(&self) -> String {
+        self.leader()
+            .map(|lease| lease.node)
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// Acquires the lease if it is free or expired, or renews it if this
+    /// node already holds it. Returns whether this node is the leader.
+    pub fn renew(&self) -> KrillResult<bool> {
+        let store = match &self.store {
+            Some(store) => store,
+            None => return Ok(true),
+        };
+
+        let was_leader = self.is_leader();
+
+        let key = Key::new_global(LEADER_LEASE_KEY);
+        let now = Timestamp::now();
+        let candidate = LeaderLease {
+            node: self.node.clone(),
+            expires: now.plus_seconds(self.lease_seconds.into()),
+        };
+
+        let lease = store.execute(&Scope::global(), |kv| {
+            let current: Option<LeaderLease> = match kv.get(&key)? {
+                Some(value) => Some(serde_json::from_value(value)?),
+                None => None,
+            };
+
+            match current {
+                Some(lease)
+                    if lease.node != self.node && !lease.is_expired(now) =>
+                {
+                    Ok(lease)
+                }
+                _ => {
+                    kv.store(&key, serde_json::to_value(&candidate)?)?;
+                    Ok(candidate.clone())
+                }
+            }
+        })?;
+
+        let is_leader = lease.node == self.node;
+        if is_leader {
+            let held_until =
+                lease.expires.minus_seconds(self.safety_margin());
+            self.held_until.store(held_until.into(), Ordering::SeqCst);
+        } else {
+            self.held_until.store(0, Ordering::SeqCst);
+        }
+
+        if is_leader && !was_leader {
+            info!("Node '{}' is now the leader", self.node);
+        } else if !is_leader
+            && (was_leader || self.last_seen.read().unwrap().is_none())
+        {
+            info!(
+                "Node '{}' is standby, the leader is '{}' until {}",
+                self.node,
+                lease.node,
+                lease.expires.to_rfc3339()
+            );
+        }
+
+        *self.last_seen.write().unwrap() = Some(lease);
+
+        Ok(is_leader)
+    }
+
+    /// Keeps renewing (or trying to acquire) the lease. This never returns
+    /// if high availability is enabled, or immediately otherwise.
+    pub async fn run(&self) {
+        if !self.is_enabled() {
+            return std::future::pending().await;
+        }
+
+        loop {
+            if let Err(e) = self.renew() {
+                warn!("Could not renew the leader lease: {}", e);
+            }
+
+            sleep(self.renew_interval()).await;
+        }
+    }
+
+    /// Renew three times per lease period, so that a single slow or
+    /// failed attempt does not cost us the lease.
+    fn renew_interval(&self) -> Duration {
+        Duration::from_secs((self.lease_seconds / 3).max(1).into())
+    }
+
+    /// The leader steps down this many seconds before its lease expires,
+    /// to allow for a difference between the clocks of the nodes.
+    fn safety_margin(&self) -> i64 {
+        (self.lease_seconds / 3).into()
+    }
+}
+
+//------------ Tests -------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::{constants::PROPERTIES_NS, test};
+
+    fn node(storage_uri: &url::Url, name: &str) -> LeaderElection {
+        node_with_lease(storage_uri, name, 1)
+    }
+
+    fn node_with_lease(
+        storage_uri: &url::Url,
+        name: &str,
+        lease_seconds: u32,
+    ) -> LeaderElection {
+        let store =
+            KeyValueStore::create(storage_uri, PROPERTIES_NS).unwrap();
+        LeaderElection::new(Some(store), name.to_string(), lease_seconds)
+    }
+
+    #[test]
+    fn standby_takes_over_expired_lease() {
+        test::test_in_memory(|storage_uri| {
+            let node_a = node(storage_uri, "a");
+            let node_b = node(storage_uri, "b");
+
+            // The first node to try gets the lease, the other is standby.
+            assert!(node_a.renew().unwrap());
+            assert!(node_a.is_leader());
+            assert!(!node_b.renew().unwrap());
+            assert!(!node_b.is_leader());
+            assert_eq!(node_b.leader_name(), "a");
+
+            // The leader can renew its lease.
+            assert!(node_a.renew().unwrap());
+
+            // If the leader stops renewing, then the standby takes over
+            // after the lease expired, and the old leader steps down.
+            std::thread::sleep(Duration::from_secs(2));
+            assert!(!node_a.is_leader());
+            assert!(node_b.renew().unwrap());
+            assert!(node_b.is_leader());
+            assert!(!node_a.renew().unwrap());
+            assert_eq!(node_a.leader_name(), "b");
+        })
+    }
+
+    #[test]
+    fn leader_steps_down_before_lease_expires() {
+        test::test_in_memory(|storage_uri| {
+            let node_a = node_with_lease(storage_uri, "a", 30);
+            assert!(node_a.renew().unwrap());
+
+            let expires = i64::from(node_a.leader().unwrap().expires());
+            let held_until = node_a.held_until.load(Ordering::SeqCst);
+            assert_eq!(expires - held_until, 10);
+        })
+    }
+
+    #[test]
+    fn always_leader_if_disabled() {
+        let election = LeaderElection::new(None, "local".to_string(), 0);
+        assert!(!election.is_enabled());
+        assert!(election.is_leader());
+        assert!(election.renew().unwrap());
+    }
+}
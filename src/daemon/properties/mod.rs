@@ -14,6 +14,12 @@
 //! In future we can extend this structure to include other runtime
 //! properties that need to be shared between nodes. E.g. timing
 //! parameters used for issuing certificates and ROAs etc.
+//!
+//! The shared store is also used to hold the lease of the leader node
+//! in active/standby set ups, see [`LeaderElection`].
+
+mod lease;
+pub use self::lease::*;
 
 use std::{fmt, str::FromStr, sync::Arc};
 
@@ -3,12 +3,15 @@
 
 use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use kvx::Namespace;
+use kvx::{queue::RunningTask, Namespace};
 use tokio::time::sleep;
 
-use rpki::ca::{
-    idexchange::{CaHandle, ParentHandle},
-    provisioning::{ResourceClassName, RevocationRequest},
+use rpki::{
+    ca::{
+        idexchange::{CaHandle, ParentHandle},
+        provisioning::{ResourceClassName, RevocationRequest},
+    },
+    repository::x509::Time,
 };
 use url::Url;
 
@@ -18,23 +21,27 @@ use crate::{
         api::Timestamp,
         bgp::BgpAnalyser,
         crypto::dispatch::signerinfo::SignerInfo,
-        error::FatalError,
+        error::{Error, FatalError},
         eventsourcing::{Aggregate, AggregateStore, WalStore, WalSupport},
         util::KrillVersion,
+        KrillResult,
     },
     constants::{
-        CASERVER_NS, PROPERTIES_NS, PUBSERVER_CONTENT_NS, PUBSERVER_NS,
-        SCHEDULER_INTERVAL_RENEW_MINS, SCHEDULER_INTERVAL_REPUBLISH_MINS,
+        API_TOKENS_NS, CASERVER_NS, PROPERTIES_NS, PUBSERVER_CONTENT_NS,
+        PUBSERVER_NS, SCHEDULER_INTERVAL_RENEW_MINS,
+        SCHEDULER_INTERVAL_REPUBLISH_MINS,
         SCHEDULER_RESYNC_REPO_CAS_THRESHOLD,
         SCHEDULER_USE_JITTER_CAS_THRESHOLD, SIGNERS_NS,
     },
     daemon::{
+        auth::tokens::ApiTokens,
         ca::{CaManager, CertAuth},
         config::Config,
         mq::{
             in_hours, in_minutes, in_seconds, in_weeks, now, Task, TaskQueue,
         },
-        properties::Properties,
+        properties::{LeaderElection, Properties},
+        webhooks::{self, WebhookNotification},
     },
     pubd::{RepositoryAccess, RepositoryContent, RepositoryManager},
 };
@@ -52,12 +59,14 @@ pub struct Scheduler {
     #[cfg(feature = "multi-user")]
     // Responsible for purging expired cached login tokens
     login_session_cache: Arc<LoginSessionCache>,
+    leader_election: Arc<LeaderElection>,
     config: Arc<Config>,
     system_actor: Actor,
     started: Timestamp,
 }
 
 impl Scheduler {
+    #[allow(clippy::too_many_arguments)]
     pub fn build(
         tasks: Arc<TaskQueue>,
         ca_manager: Arc<CaManager>,
@@ -66,6 +75,7 @@ impl Scheduler {
         #[cfg(feature = "multi-user")] login_session_cache: Arc<
             LoginSessionCache,
         >,
+        leader_election: Arc<LeaderElection>,
         config: Arc<Config>,
         system_actor: Actor,
     ) -> Self {
@@ -76,6 +86,7 @@ impl Scheduler {
             bgp_analyser,
             #[cfg(feature = "multi-user")]
             login_session_cache,
+            leader_election,
             config,
             system_actor,
             started: Timestamp::now(),
@@ -84,9 +95,24 @@ impl Scheduler {
 
     /// Run the scheduler in the background. It will sweep the message queue
     /// for tasks and re-schedule new tasks as needed.
+    ///
+    /// Tasks are only picked up while this node is the leader. A standby
+    /// node that takes over will first reschedule tasks that were left
+    /// running by the previous leader.
     pub async fn run(&self) {
+        let mut was_leader = self.leader_election.is_leader();
+
         loop {
-            while let Some(running_task) = self.tasks.pop() {
+            let is_leader = self.leader_election.is_leader();
+            if is_leader && !was_leader {
+                if let Err(e) = self.take_over() {
+                    error!("Could not take over tasks from the previous leader. Krill will stop as there is no good way to recover from this. Error was: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            was_leader = is_leader;
+
+            while let Some(running_task) = self.next_task() {
                 // remember the key so we can finish or re-schedule the task.
                 let task_key = kvx::Key::from(&running_task);
 
@@ -137,6 +163,22 @@ impl Scheduler {
         }
     }
 
+    /// Returns the next task to run, if this node is the leader.
+    fn next_task(&self) -> Option<RunningTask> {
+        if self.leader_election.is_leader() {
+            self.tasks.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Picks up the work of a previous leader.
+    fn take_over(&self) -> KrillResult<()> {
+        info!("Taking over scheduled tasks as the new leader");
+        self.tasks.reschedule_tasks_at_startup()?;
+        self.tasks.schedule(Task::QueueStartTasks, now())
+    }
+
     /// Process a single task
     ///
     /// May only return fatal errors. Temporary, or suspected temporary,
@@ -170,6 +212,10 @@ impl Scheduler {
                 self.suspend_children_if_needed(ca).await
             }
 
+            Task::ValidateCaRepository { ca_handle: ca } => {
+                self.validate_ca_repository(ca).await
+            }
+
             Task::RepublishIfNeeded => self.republish_if_needed().await,
 
             Task::RenewObjectsIfNeeded => {
@@ -187,6 +233,27 @@ impl Scheduler {
 
             Task::RrdpUpdateIfNeeded => self.update_rrdp_if_needed(),
 
+            Task::SyncRepositoryMirrors => {
+                self.sync_repository_mirrors().await
+            }
+
+            Task::RemoveExpiredRoas {
+                ca_handle: ca,
+                ca_version,
+            } => self.remove_expired_roas(ca, ca_version).await,
+
+            Task::ActivateScheduledRoas {
+                ca_handle: ca,
+                ca_version,
+                id,
+            } => self.activate_scheduled_roas(ca, ca_version, id).await,
+
+            Task::NotifyWebhook {
+                url,
+                notification,
+                attempt,
+            } => Ok(self.notify_webhook(url, notification, attempt).await),
+
             Task::ResourceClassRemoved {
                 ca_handle: ca,
                 ca_version,
@@ -297,6 +364,36 @@ impl Scheduler {
                     .map_err(FatalError)?;
             }
 
+            // Make sure that time-limited ROAs are removed when they
+            // expire. These tasks are normally added when the ROAs are
+            // configured.
+            if let Some(expiry) = ca.route_authorizations_next_expiry() {
+                self.tasks
+                    .schedule_missing(
+                        Task::RemoveExpiredRoas {
+                            ca_handle: ca_handle.clone(),
+                            ca_version,
+                        },
+                        Time::from(expiry).into(),
+                    )
+                    .map_err(FatalError)?;
+            }
+
+            // Make sure that scheduled ROA updates are activated. These
+            // tasks are normally added when the updates are scheduled.
+            for scheduled in ca.route_authorizations_scheduled().iter() {
+                self.tasks
+                    .schedule_missing(
+                        Task::ActivateScheduledRoas {
+                            ca_handle: ca_handle.clone(),
+                            ca_version,
+                            id: scheduled.id(),
+                        },
+                        Time::from(scheduled.not_before()).into(),
+                    )
+                    .map_err(FatalError)?;
+            }
+
             // If suspension is enabled then plan a task for it. Since this is
             // a cheap no-op in most cases, we do not need jitter. If we do
             // not add this task then it will not be executed
@@ -313,6 +410,19 @@ impl Scheduler {
                     )
                     .map_err(FatalError)?;
             }
+
+            // If validation of the published content is enabled then plan
+            // it. The task is re-scheduled when it is done.
+            if self.config.ca_repo_validation_interval_minutes.is_some() {
+                self.tasks
+                    .schedule_missing(
+                        Task::ValidateCaRepository {
+                            ca_handle: ca_handle.clone(),
+                        },
+                        now(),
+                    )
+                    .map_err(FatalError)?;
+            }
         }
 
         self.tasks
@@ -322,10 +432,13 @@ impl Scheduler {
             .schedule_missing(Task::RenewObjectsIfNeeded, now())
             .map_err(FatalError)?;
 
-        // BGP announcement info is only kept in-memory, so it
+        // BGP announcement and IRR info is only kept in-memory, so it
         // is lost after a restart, so schedule refreshing this
         // immediately.
-        if self.config.bgp_risdumps_enabled {
+        if self.config.bgp_risdumps_enabled
+            || !self.config.bgp_sources.is_empty()
+            || !self.config.irr_sources.is_empty()
+        {
             self.tasks
                 .schedule(Task::RefreshAnnouncementsInfo, now())
                 .map_err(FatalError)?;
@@ -343,6 +456,14 @@ impl Scheduler {
             .schedule_missing(Task::UpdateSnapshots, now())
             .map_err(FatalError)?;
 
+        // The content pushed to repository mirrors is only tracked
+        // in-memory, so push everything again after a restart.
+        if !self.config.repository_mirrors.is_empty() {
+            self.tasks
+                .schedule(Task::SyncRepositoryMirrors, now())
+                .map_err(FatalError)?;
+        }
+
         if self.config.testbed().is_some() {
             self.tasks
                 .schedule_missing(Task::RenewTestbedTa, now())
@@ -374,7 +495,19 @@ impl Scheduler {
 
                 Ok(TaskResult::Reschedule(next))
             }
-            Ok(true) => Ok(TaskResult::Done),
+            Ok(true) => {
+                // Make sure that the content of CAs which published for
+                // the first time since the server started is validated.
+                if self.config.ca_repo_validation_interval_minutes.is_some() {
+                    self.tasks
+                        .schedule_missing(
+                            Task::ValidateCaRepository { ca_handle: ca },
+                            now(),
+                        )
+                        .map_err(FatalError)?;
+                }
+                Ok(TaskResult::Done)
+            }
             Ok(false) => {
                 debug!("sync was premature, reschedule");
                 let next = in_seconds(1);
@@ -492,6 +625,41 @@ impl Scheduler {
         }
     }
 
+    /// Validate the content published by a CA and plan the next validation
+    async fn validate_ca_repository(
+        &self,
+        ca_handle: CaHandle,
+    ) -> Result<TaskResult, FatalError> {
+        let interval = match self.config.ca_repo_validation_interval_minutes {
+            Some(interval) => interval,
+            None => return Ok(TaskResult::Done),
+        };
+
+        if self.ca_manager.has_ca(&ca_handle).map_err(FatalError)? {
+            debug!("Validate the published content of CA '{}'", ca_handle);
+            if let Err(e) = self
+                .ca_manager
+                .ca_repo_validate(self.repo_manager.as_ref(), &ca_handle)
+                .await
+            {
+                error!(
+                    "Could not validate the published content of CA '{}': {}",
+                    ca_handle, e
+                );
+            }
+
+            Ok(TaskResult::FollowUp(
+                Task::ValidateCaRepository { ca_handle },
+                in_minutes(interval.into()),
+            ))
+        } else {
+            debug!(
+                "Drop task to validate published content for removed CA {ca_handle}"
+            );
+            Ok(TaskResult::Done)
+        }
+    }
+
     /// Let CAs that need it republish their CRL/MFT
     async fn republish_if_needed(&self) -> Result<TaskResult, FatalError> {
         // Note that CRL/MFT re-issuance is handled by the `CaObjects`
@@ -535,7 +703,7 @@ impl Scheduler {
     /// Update announcement info
     async fn announcements_refresh(&self) -> Result<TaskResult, FatalError> {
         if let Err(e) = self.bgp_analyser.update().await {
-            error!("Failed to update BGP announcements: {}", e)
+            error!("Failed to update BGP announcements or IRR data: {}", e)
         }
 
         // check again in 10 minutes, note.. this is a no-op in case the
@@ -648,6 +816,10 @@ impl Scheduler {
             &self.config.storage_uri,
             PROPERTIES_NS,
         );
+        update_aggregate_store_snapshots::<ApiTokens>(
+            &self.config.storage_uri,
+            API_TOKENS_NS,
+        );
         update_aggregate_store_snapshots::<RepositoryAccess>(
             &self.config.storage_uri,
             PUBSERVER_NS,
@@ -683,6 +855,200 @@ impl Scheduler {
         }
     }
 
+    /// Pushes the repository content to all configured mirrors.
+    async fn sync_repository_mirrors(
+        &self,
+    ) -> Result<TaskResult, FatalError> {
+        match self.repo_manager.sync_mirrors().await {
+            Err(e) => {
+                // Errors for individual mirrors were already logged. Keep
+                // trying, the mirror may become available again.
+                debug!("Could not update all repository mirrors: {}", e);
+                Ok(TaskResult::Reschedule(in_minutes(1)))
+            }
+            Ok(()) => Ok(TaskResult::Done),
+        }
+    }
+
+    /// Removes time-limited ROAs which expired, and plans to do this again
+    /// when the next ROA expires.
+    async fn remove_expired_roas(
+        &self,
+        ca_handle: CaHandle,
+        ca_version: u64,
+    ) -> Result<TaskResult, FatalError> {
+        if !self.ca_manager.has_ca(&ca_handle).map_err(FatalError)? {
+            debug!(
+                "Dropping task to remove expired ROAs for removed CA '{}'",
+                ca_handle
+            );
+            return Ok(TaskResult::Done);
+        }
+
+        let ca = self
+            .ca_manager
+            .get_ca(&ca_handle)
+            .await
+            .map_err(FatalError)?;
+        if ca.version() < ca_version {
+            // premature, we need to wait for the CA to be committed.
+            return Ok(TaskResult::Reschedule(in_seconds(1)));
+        }
+
+        if let Err(e) = self
+            .ca_manager
+            .ca_routes_remove_expired(&ca_handle, &self.system_actor)
+            .await
+        {
+            error!(
+                "CA '{}' could not remove expired ROAs, will retry in 1 hour. Error: {}",
+                ca_handle, e
+            );
+            return Ok(TaskResult::Reschedule(in_hours(1)));
+        }
+
+        let ca = self
+            .ca_manager
+            .get_ca(&ca_handle)
+            .await
+            .map_err(FatalError)?;
+        match ca.route_authorizations_next_expiry() {
+            Some(expiry) => Ok(TaskResult::FollowUp(
+                Task::RemoveExpiredRoas {
+                    ca_handle,
+                    ca_version: ca.version(),
+                },
+                Time::from(expiry).into(),
+            )),
+            None => Ok(TaskResult::Done),
+        }
+    }
+
+    /// Applies scheduled ROA updates. If the updates can no longer be
+    /// applied, e.g. because they conflict with changes made since they
+    /// were scheduled, then they are kept so that the operator can see
+    /// them, and cancel them.
+    async fn activate_scheduled_roas(
+        &self,
+        ca_handle: CaHandle,
+        ca_version: u64,
+        id: u64,
+    ) -> Result<TaskResult, FatalError> {
+        if !self.ca_manager.has_ca(&ca_handle).map_err(FatalError)? {
+            debug!(
+                "Dropping task to activate scheduled ROA updates for removed CA '{}'",
+                ca_handle
+            );
+            return Ok(TaskResult::Done);
+        }
+
+        let ca = self
+            .ca_manager
+            .get_ca(&ca_handle)
+            .await
+            .map_err(FatalError)?;
+        if ca.version() < ca_version {
+            // premature, we need to wait for the CA to be committed.
+            return Ok(TaskResult::Reschedule(in_seconds(1)));
+        }
+
+        match self
+            .ca_manager
+            .ca_routes_scheduled_activate(&ca_handle, id, &self.system_actor)
+            .await
+        {
+            Ok(()) => {
+                info!(
+                    "CA '{}' activated scheduled ROA updates with id '{}'",
+                    ca_handle, id
+                );
+                Ok(TaskResult::Done)
+            }
+            Err(Error::CaRoaScheduledUnknown(_, _)) => {
+                debug!(
+                    "Scheduled ROA updates with id '{}' for CA '{}' were cancelled",
+                    id, ca_handle
+                );
+                Ok(TaskResult::Done)
+            }
+            Err(Error::RoaDeltaError(_, e)) => {
+                error!(
+                    "CA '{}' cannot activate scheduled ROA updates with id '{}', they will be kept until cancelled. Error: {}",
+                    ca_handle, id, e
+                );
+                Ok(TaskResult::Done)
+            }
+            Err(e) => {
+                error!(
+                    "CA '{}' could not activate scheduled ROA updates with id '{}', will retry in 1 hour. Error: {}",
+                    ca_handle, id, e
+                );
+                Ok(TaskResult::Reschedule(in_hours(1)))
+            }
+        }
+    }
+
+    /// Delivers a notification to a webhook. Failed deliveries are retried
+    /// with an exponential back-off, up to the number of retries configured
+    /// for the webhook.
+    async fn notify_webhook(
+        &self,
+        url: Url,
+        notification: WebhookNotification,
+        attempt: u32,
+    ) -> TaskResult {
+        let webhook = match self.config.webhooks.iter().find(|w| w.url == url)
+        {
+            Some(webhook) => webhook,
+            None => {
+                debug!(
+                    "Dropping notification {} for webhook {} which is no longer configured",
+                    notification.id(),
+                    url
+                );
+                return TaskResult::Done;
+            }
+        };
+
+        match webhooks::send(webhook, &notification).await {
+            Ok(()) => {
+                debug!(
+                    "Delivered notification {} to webhook {}",
+                    notification.id(),
+                    url
+                );
+                TaskResult::Done
+            }
+            Err(e) if attempt < webhook.retries => {
+                let delay = 30 << attempt.min(7);
+                warn!(
+                    "Could not deliver notification {} to webhook {}, will retry in {} seconds. Error: {}",
+                    notification.id(),
+                    url,
+                    delay,
+                    e
+                );
+                TaskResult::FollowUp(
+                    Task::NotifyWebhook {
+                        url,
+                        notification,
+                        attempt: attempt + 1,
+                    },
+                    in_seconds(delay),
+                )
+            }
+            Err(e) => {
+                error!(
+                    "Giving up on delivering notification {} to webhook {}. Error: {}",
+                    notification.id(),
+                    url,
+                    e
+                );
+                TaskResult::Done
+            }
+        }
+    }
+
     async fn resource_class_removed(
         &self,
         ca_handle: CaHandle,
@@ -0,0 +1,309 @@
+//! Outbound webhook notifications for CA events.
+//!
+//! Configured webhooks are notified about selected CA events, and when a
+//! CA starts failing to synchronise with a parent or its repository. The
+//! notifications are delivered through the task queue, so that deliveries
+//! which fail can be retried, also after a restart.
+
+use std::{fmt, sync::Arc};
+
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
+use reqwest::header::{HeaderMap, HeaderValue};
+use rpki::ca::idexchange::{CaHandle, ChildHandle, ParentHandle};
+
+use crate::{
+    commons::{
+        api::{ErrorResponse, Timestamp},
+        eventsourcing,
+        util::httpclient,
+    },
+    daemon::{
+        ca::{CertAuth, CertAuthEvent},
+        config::WebhookConfig,
+        mq::{now, Task, TaskQueue},
+    },
+};
+
+/// The header which holds the HMAC-SHA256 signature of the body, if a
+/// secret is configured for the webhook.
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-krill-signature";
+
+//------------ WebhookEventKind ----------------------------------------------
+
+/// The kinds of events that webhooks can be notified about.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventKind {
+    RoasUpdated,
+    ChildSuspended,
+    KeyRollActivated,
+    ParentRemoved,
+    RepoSyncFailed,
+    ParentSyncFailed,
+}
+
+impl WebhookEventKind {
+    pub fn all() -> Vec<Self> {
+        vec![
+            WebhookEventKind::RoasUpdated,
+            WebhookEventKind::ChildSuspended,
+            WebhookEventKind::KeyRollActivated,
+            WebhookEventKind::ParentRemoved,
+            WebhookEventKind::RepoSyncFailed,
+            WebhookEventKind::ParentSyncFailed,
+        ]
+    }
+
+    fn for_ca_event(event: &CertAuthEvent) -> Option<Self> {
+        match event {
+            CertAuthEvent::RoasUpdated { .. } => {
+                Some(WebhookEventKind::RoasUpdated)
+            }
+            CertAuthEvent::ChildSuspended { .. } => {
+                Some(WebhookEventKind::ChildSuspended)
+            }
+            CertAuthEvent::KeyRollActivated { .. } => {
+                Some(WebhookEventKind::KeyRollActivated)
+            }
+            CertAuthEvent::ParentRemoved { .. } => {
+                Some(WebhookEventKind::ParentRemoved)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for WebhookEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WebhookEventKind::RoasUpdated => write!(f, "roas_updated"),
+            WebhookEventKind::ChildSuspended => write!(f, "child_suspended"),
+            WebhookEventKind::KeyRollActivated => {
+                write!(f, "key_roll_activated")
+            }
+            WebhookEventKind::ParentRemoved => write!(f, "parent_removed"),
+            WebhookEventKind::RepoSyncFailed => write!(f, "repo_sync_failed"),
+            WebhookEventKind::ParentSyncFailed => {
+                write!(f, "parent_sync_failed")
+            }
+        }
+    }
+}
+
+//------------ WebhookNotification -------------------------------------------
+
+/// The JSON body which is posted to a webhook. The id is unique for each
+/// delivery, and is kept when a delivery is retried.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct WebhookNotification {
+    id: String,
+    event: WebhookEventKind,
+    ca: CaHandle,
+    timestamp: Timestamp,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<ParentHandle>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    child: Option<ChildHandle>,
+}
+
+impl WebhookNotification {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn event(&self) -> WebhookEventKind {
+        self.event
+    }
+
+    pub fn ca(&self) -> &CaHandle {
+        &self.ca
+    }
+}
+
+//------------ WebhookNotifier -----------------------------------------------
+
+/// Schedules notifications for all configured webhooks which are
+/// interested in an event.
+pub struct WebhookNotifier {
+    webhooks: Vec<WebhookConfig>,
+    tasks: Arc<TaskQueue>,
+}
+
+impl WebhookNotifier {
+    pub fn new(webhooks: Vec<WebhookConfig>, tasks: Arc<TaskQueue>) -> Self {
+        WebhookNotifier { webhooks, tasks }
+    }
+
+    pub fn repo_sync_failed(&self, ca: &CaHandle, error: &ErrorResponse) {
+        self.notify(
+            WebhookEventKind::RepoSyncFailed,
+            ca,
+            error.msg().to_string(),
+            None,
+            None,
+        )
+    }
+
+    pub fn parent_sync_failed(
+        &self,
+        ca: &CaHandle,
+        parent: &ParentHandle,
+        error: &ErrorResponse,
+    ) {
+        self.notify(
+            WebhookEventKind::ParentSyncFailed,
+            ca,
+            error.msg().to_string(),
+            Some(parent.clone()),
+            None,
+        )
+    }
+
+    fn notify(
+        &self,
+        event: WebhookEventKind,
+        ca: &CaHandle,
+        message: String,
+        parent: Option<ParentHandle>,
+        child: Option<ChildHandle>,
+    ) {
+        for webhook in self.webhooks.iter().filter(|w| w.wants(event)) {
+            let notification = WebhookNotification {
+                id: uuid::Uuid::new_v4().to_string(),
+                event,
+                ca: ca.clone(),
+                timestamp: Timestamp::now(),
+                message: message.clone(),
+                parent: parent.clone(),
+                child: child.clone(),
+            };
+
+            let task = Task::NotifyWebhook {
+                url: webhook.url.clone(),
+                notification,
+                attempt: 0,
+            };
+
+            if let Err(e) = self.tasks.schedule(task, now()) {
+                error!(
+                    "Could not schedule '{}' notification for CA '{}' to webhook {}. Error: {}",
+                    event, ca, webhook.url, e
+                );
+            }
+        }
+    }
+}
+
+/// Implement post-save listening for CertAuth events, so that webhooks
+/// are only notified about changes which were saved.
+impl eventsourcing::PostSaveEventListener<CertAuth> for WebhookNotifier {
+    fn listen(&self, ca: &CertAuth, events: &[CertAuthEvent]) {
+        for event in events {
+            if let Some(kind) = WebhookEventKind::for_ca_event(event) {
+                let (parent, child) = match event {
+                    CertAuthEvent::ParentRemoved { parent } => {
+                        (Some(parent.clone()), None)
+                    }
+                    CertAuthEvent::ChildSuspended { child } => {
+                        (None, Some(child.clone()))
+                    }
+                    _ => (None, None),
+                };
+
+                self.notify(
+                    kind,
+                    ca.handle(),
+                    event.to_string(),
+                    parent,
+                    child,
+                );
+            }
+        }
+    }
+}
+
+//------------ Delivery ------------------------------------------------------
+
+/// Posts the notification to the webhook, signed if a secret is configured.
+pub async fn send(
+    webhook: &WebhookConfig,
+    notification: &WebhookNotification,
+) -> Result<(), httpclient::Error> {
+    let uri = webhook.url.as_str();
+    let body = serde_json::to_string_pretty(notification)
+        .map_err(|e| httpclient::Error::request_build_json(uri, e))?;
+
+    let mut headers = HeaderMap::new();
+    if let Some(secret) = &webhook.secret {
+        let signature = signature(secret, body.as_bytes())
+            .map_err(|e| httpclient::Error::request_build(uri, e))?;
+        headers.insert(
+            WEBHOOK_SIGNATURE_HEADER,
+            HeaderValue::from_str(&format!("sha256={}", signature))
+                .map_err(|e| httpclient::Error::request_build(uri, e))?,
+        );
+    }
+
+    httpclient::post_json_body_with_headers(uri, body, headers).await
+}
+
+/// Returns the hex encoded HMAC-SHA256 of the body using the secret.
+fn signature(
+    secret: &str,
+    body: &[u8],
+) -> Result<String, openssl::error::ErrorStack> {
+    let key = PKey::hmac(secret.as_bytes())?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(body)?;
+    Ok(hex::encode(signer.sign_to_vec()?))
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn hmac_sha256_signature() {
+        // Test vector 2 from RFC 4231.
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?").unwrap(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn notification_json() {
+        let notification = WebhookNotification {
+            id: "d0d56c4e-4a3c-4a39-8d0f-6e8e40dc3e7a".to_string(),
+            event: WebhookEventKind::ParentSyncFailed,
+            ca: CaHandle::from_str("ca").unwrap(),
+            timestamp: Timestamp::new(1700000000),
+            message: "parent is unreachable".to_string(),
+            parent: Some(ParentHandle::from_str("parent").unwrap()),
+            child: None,
+        };
+
+        let json = serde_json::to_value(&notification).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "id": "d0d56c4e-4a3c-4a39-8d0f-6e8e40dc3e7a",
+                "event": "parent_sync_failed",
+                "ca": "ca",
+                "timestamp": 1700000000,
+                "message": "parent is unreachable",
+                "parent": "parent"
+            })
+        );
+
+        let parsed: WebhookNotification =
+            serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, notification);
+    }
+}
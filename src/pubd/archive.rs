@@ -0,0 +1,356 @@
+//! Reconstruction of past repository states from RRDP files.
+//!
+//! If `rrdp_files_archive` is enabled, then snapshot and delta files are
+//! moved to the archive directory when they are no longer needed, rather
+//! than deleted. The serials found in the archive and in the current RRDP
+//! directory are indexed here, and the content of the repository at any of
+//! them can be reconstructed by applying deltas to the closest preceding
+//! snapshot.
+//!
+//! The time of a serial is taken from the modification time of its delta or
+//! snapshot file, i.e. the time that it was written by the publication
+//! server. So, this time should be preserved if the archive is copied.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::UNIX_EPOCH,
+};
+
+use rpki::{
+    ca::publication::Base64,
+    rrdp::{Delta, DeltaElement, Snapshot},
+};
+use uuid::Uuid;
+
+use crate::{
+    commons::{
+        api::{
+            rrdp::{PublishElement, RrdpSession},
+            RrdpArchiveIndex, RrdpArchiveQuery, RrdpArchiveSerial,
+            RrdpArchiveState, Timestamp,
+        },
+        error::Error,
+        KrillResult,
+    },
+    constants::{REPOSITORY_RRDP_ARCHIVE_DIR, REPOSITORY_RRDP_DIR},
+    pubd::RrdpServer,
+};
+
+//------------ RrdpArchive ---------------------------------------------------
+
+/// Gives access to the RRDP files which are kept on disk.
+pub struct RrdpArchive {
+    rrdp_dir: PathBuf,
+    archive_dir: PathBuf,
+}
+
+impl RrdpArchive {
+    pub fn new(repo_dir: &Path) -> Self {
+        RrdpArchive {
+            rrdp_dir: repo_dir.join(REPOSITORY_RRDP_DIR),
+            archive_dir: repo_dir.join(REPOSITORY_RRDP_ARCHIVE_DIR),
+        }
+    }
+
+    /// Returns all serials for which files were found, ordered by time.
+    pub fn index(&self) -> KrillResult<RrdpArchiveIndex> {
+        let mut serials: Vec<RrdpArchiveSerial> = self
+            .serial_files()?
+            .into_values()
+            .map(|files| files.as_archive_serial())
+            .collect();
+        serials.sort_by_key(|s| (s.time(), s.serial()));
+
+        Ok(RrdpArchiveIndex::new(serials))
+    }
+
+    /// Reconstructs the repository content at the serial selected by the
+    /// query.
+    pub fn state(
+        &self,
+        query: &RrdpArchiveQuery,
+    ) -> KrillResult<RrdpArchiveState> {
+        let all_files = self.serial_files()?;
+
+        let target = match query {
+            RrdpArchiveQuery::Serial(serial, Some(session)) => {
+                all_files.get(&(*session, *serial))
+            }
+            RrdpArchiveQuery::Serial(serial, None) => all_files
+                .values()
+                .filter(|files| files.serial == *serial)
+                .max_by_key(|files| files.time),
+            RrdpArchiveQuery::Time(time) => all_files
+                .values()
+                .filter(|files| files.time <= *time)
+                .max_by_key(|files| (files.time, files.serial)),
+        }
+        .ok_or_else(|| {
+            Error::RepositoryArchiveUnavailable(match query {
+                RrdpArchiveQuery::Serial(serial, _) => {
+                    format!("no files found for serial {}", serial)
+                }
+                RrdpArchiveQuery::Time(time) => {
+                    format!("no serial found at {}", time.to_rfc3339())
+                }
+            })
+        })?;
+
+        let session = target.session;
+
+        let base = all_files
+            .range((session, 0)..=(session, target.serial))
+            .rev()
+            .find(|(_, files)| files.snapshot.is_some())
+            .map(|(_, files)| files)
+            .ok_or_else(|| {
+                Error::RepositoryArchiveUnavailable(format!(
+                    "no snapshot found for serial {} or before",
+                    target.serial
+                ))
+            })?;
+
+        let mut objects = HashMap::new();
+
+        let snapshot_path = base.snapshot.as_ref().unwrap(); // found above
+        let snapshot =
+            Snapshot::parse(open(snapshot_path)?).map_err(|e| {
+                Error::RepositoryArchiveUnavailable(format!(
+                    "cannot parse snapshot for serial {}: {}",
+                    base.serial, e
+                ))
+            })?;
+        check_session_serial(
+            session,
+            base.serial,
+            snapshot.session_id(),
+            snapshot.serial(),
+        )?;
+        for element in snapshot.into_elements() {
+            let (uri, data) = element.unpack();
+            objects.insert(uri, data);
+        }
+
+        for serial in base.serial + 1..=target.serial {
+            let delta_path = all_files
+                .get(&(session, serial))
+                .and_then(|files| files.delta.as_ref())
+                .ok_or_else(|| {
+                    Error::RepositoryArchiveUnavailable(format!(
+                        "missing delta for serial {}",
+                        serial
+                    ))
+                })?;
+
+            let delta = Delta::parse(open(delta_path)?).map_err(|e| {
+                Error::RepositoryArchiveUnavailable(format!(
+                    "cannot parse delta for serial {}: {}",
+                    serial, e
+                ))
+            })?;
+            check_session_serial(
+                session,
+                serial,
+                delta.session_id(),
+                delta.serial(),
+            )?;
+
+            for element in delta.into_elements() {
+                match element {
+                    DeltaElement::Publish(publish) => {
+                        let (uri, data) = publish.unpack();
+                        objects.insert(uri, data);
+                    }
+                    DeltaElement::Update(update) => {
+                        let (uri, _hash, data) = update.unpack();
+                        objects.insert(uri, data);
+                    }
+                    DeltaElement::Withdraw(withdraw) => {
+                        objects.remove(withdraw.uri());
+                    }
+                }
+            }
+        }
+
+        let mut objects: Vec<PublishElement> = objects
+            .into_iter()
+            .map(|(uri, data)| {
+                PublishElement::new(Base64::from_content(&data), uri)
+            })
+            .collect();
+        objects.sort_by_key(|object| object.uri().to_string());
+
+        Ok(RrdpArchiveState::new(
+            session,
+            target.serial,
+            target.time,
+            objects,
+        ))
+    }
+
+    /// Returns the files for all serials, found under the archive and
+    /// the RRDP directory. Files in the RRDP directory are preferred if
+    /// a serial exists in both.
+    fn serial_files(
+        &self,
+    ) -> KrillResult<BTreeMap<(RrdpSession, u64), SerialFiles>> {
+        let mut res = BTreeMap::new();
+
+        for base_dir in [&self.archive_dir, &self.rrdp_dir] {
+            for (session, session_dir) in numbered_dirs(base_dir, |name| {
+                RrdpSession::from_str(name).ok()
+            })? {
+                for (serial, _) in numbered_dirs(&session_dir, |name| {
+                    u64::from_str(name).ok()
+                })? {
+                    let snapshot = RrdpServer::session_dir_snapshot(
+                        &session_dir,
+                        serial,
+                    )?;
+                    let delta = RrdpServer::find_in_serial_dir(
+                        &session_dir,
+                        serial,
+                        "delta.xml",
+                    )?;
+
+                    let files =
+                        res.entry((session, serial)).or_insert(SerialFiles {
+                            session,
+                            serial,
+                            time: Timestamp::new(0),
+                            snapshot: None,
+                            delta: None,
+                        });
+                    if snapshot.is_some() {
+                        files.snapshot = snapshot;
+                    }
+                    if delta.is_some() {
+                        files.delta = delta;
+                    }
+                }
+            }
+        }
+
+        // Drop serials without files, e.g. an empty directory left behind
+        // if the snapshot of a serial was removed, and set the time.
+        let mut with_files = BTreeMap::new();
+        for (key, mut files) in res {
+            if let Some(path) =
+                files.delta.as_ref().or(files.snapshot.as_ref())
+            {
+                files.time = modified(path)?;
+                with_files.insert(key, files);
+            }
+        }
+
+        Ok(with_files)
+    }
+}
+
+//------------ SerialFiles ---------------------------------------------------
+
+struct SerialFiles {
+    session: RrdpSession,
+    serial: u64,
+    time: Timestamp,
+    snapshot: Option<PathBuf>,
+    delta: Option<PathBuf>,
+}
+
+impl SerialFiles {
+    fn as_archive_serial(&self) -> RrdpArchiveSerial {
+        RrdpArchiveSerial::new(
+            self.session,
+            self.serial,
+            self.time,
+            self.snapshot.is_some(),
+            self.delta.is_some(),
+        )
+    }
+}
+
+//------------ Helpers -------------------------------------------------------
+
+/// Returns the sub-directories of a directory for which the name can be
+/// parsed, e.g. as a session or serial. Returns an empty list if the
+/// directory does not exist.
+fn numbered_dirs<T>(
+    dir: &Path,
+    parse: impl Fn(&str) -> Option<T>,
+) -> KrillResult<Vec<(T, PathBuf)>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let cannot_read = |e| {
+        Error::io_error_with_context(
+            format!("Could not read directory: '{}'", dir.to_string_lossy()),
+            e,
+        )
+    };
+
+    let mut res = vec![];
+    for entry in fs::read_dir(dir).map_err(cannot_read)? {
+        let path = entry.map_err(cannot_read)?.path();
+        if path.is_dir() {
+            if let Some(parsed) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(&parse)
+            {
+                res.push((parsed, path));
+            }
+        }
+    }
+    Ok(res)
+}
+
+fn open(path: &Path) -> KrillResult<io::BufReader<File>> {
+    File::open(path).map(io::BufReader::new).map_err(|e| {
+        Error::io_error_with_context(
+            format!("Could not open: '{}'", path.to_string_lossy()),
+            e,
+        )
+    })
+}
+
+fn modified(path: &Path) -> KrillResult<Timestamp> {
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| {
+            Error::io_error_with_context(
+                format!(
+                    "Could not get modification time of: '{}'",
+                    path.to_string_lossy()
+                ),
+                e,
+            )
+        })?;
+
+    let seconds = modified
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default();
+
+    Ok(Timestamp::new(seconds))
+}
+
+fn check_session_serial(
+    session: RrdpSession,
+    serial: u64,
+    found_session: Uuid,
+    found_serial: u64,
+) -> KrillResult<()> {
+    if Uuid::from(session) != found_session || serial != found_serial {
+        Err(Error::RepositoryArchiveUnavailable(format!(
+            "file for session {} and serial {} contains session {} and serial {}",
+            session, serial, found_session, found_serial
+        )))
+    } else {
+        Ok(())
+    }
+}
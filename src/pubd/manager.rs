@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use bytes::Bytes;
 
@@ -16,18 +16,25 @@ use crate::{
     commons::{
         actor::Actor,
         api::{
-            PublicationServerUris, PublisherDetails, RepoFileDeleteCriteria,
+            rrdp::{CurrentObjectUri, CurrentObjects, PublishElement},
+            PublicationServerUris, PublisherDetails, PublisherObjects,
+            PublisherObjectsDiff, RepoFileDeleteCriteria, RrdpArchiveIndex,
+            RrdpArchiveQuery, RrdpArchiveState,
         },
         crypto::KrillSigner,
         error::Error,
-        util::cmslogger::CmsLogger,
+        util::{cmslogger::CmsLogger, file},
         KrillResult,
     },
+    constants::REPOSITORY_MIRRORS_NS,
     daemon::{
         config::Config,
         mq::{now, Task, TaskQueue},
     },
-    pubd::{RepoStats, RepositoryAccessProxy, RepositoryContentProxy},
+    pubd::{
+        is_valid_mirror_path, RepoStats, RepositoryAccessProxy,
+        RepositoryContentProxy, RepositoryMirrors, RrdpArchive,
+    },
 };
 
 use super::RrdpUpdateNeeded;
@@ -46,6 +53,9 @@ pub struct RepositoryManager {
     // updated.
     tasks: Arc<TaskQueue>,
 
+    // pushes the repository content to secondary locations, if configured
+    mirrors: RepositoryMirrors,
+
     config: Arc<Config>,
     signer: Arc<KrillSigner>,
 }
@@ -62,11 +72,17 @@ impl RepositoryManager {
         let access_proxy = Arc::new(RepositoryAccessProxy::create(&config)?);
         let content_proxy =
             Arc::new(RepositoryContentProxy::create(&config)?);
+        let mirrors = RepositoryMirrors::new(
+            config.repo_dir(),
+            &config.repository_mirrors,
+            config.key_value_store(REPOSITORY_MIRRORS_NS)?,
+        );
 
         Ok(RepositoryManager {
             access: access_proxy,
             content: content_proxy,
             tasks,
+            mirrors,
             config,
             signer,
         })
@@ -86,7 +102,7 @@ impl RepositoryManager {
         self.content
             .write_repository(self.config.rrdp_updates_config)?;
 
-        Ok(())
+        self.schedule_mirrors_sync()
     }
 
     /// Clear the publication server. Will fail if it still
@@ -184,7 +200,9 @@ impl RepositoryManager {
 
     /// Do an RRDP session reset.
     pub fn rrdp_session_reset(&self) -> KrillResult<()> {
-        self.content.session_reset(self.config.rrdp_updates_config)
+        self.content
+            .session_reset(self.config.rrdp_updates_config)?;
+        self.schedule_mirrors_sync()
     }
 
     /// Let a known publisher publish in a repository.
@@ -194,11 +212,14 @@ impl RepositoryManager {
         delta: PublishDelta,
     ) -> KrillResult<()> {
         let publisher = self.access.get_publisher(publisher_handle)?;
+        let quota = self.config.publisher_quotas.quota_for(publisher_handle);
 
         self.content.publish(
             publisher_handle.clone(),
             delta,
             publisher.base_uri(),
+            quota,
+            self.config.publisher_strict_validation,
         )?;
 
         self.tasks.schedule(Task::RrdpUpdateIfNeeded, now())
@@ -224,6 +245,7 @@ impl RepositoryManager {
         let content =
             self.content.update_rrdp(self.config.rrdp_updates_config)?;
         content.write_repository(self.config.rrdp_updates_config)?;
+        self.schedule_mirrors_sync()?;
 
         Ok(None)
     }
@@ -247,11 +269,14 @@ impl RepositoryManager {
         // Write the updated repository - NOTE: we no longer lock it.
         content.write_repository(self.config.rrdp_updates_config)?;
 
-        Ok(())
+        self.schedule_mirrors_sync()
     }
 
     pub fn repo_stats(&self) -> KrillResult<RepoStats> {
-        self.content.stats()
+        let mut stats = self.content.stats()?;
+        let mirrors = self.mirrors.status(&stats);
+        stats.set_mirrors(mirrors);
+        Ok(stats)
     }
 
     /// Returns a list reply for a known publisher in a repository.
@@ -273,6 +298,58 @@ impl RepositoryManager {
         self.access.repo_info_for(name)
     }
 
+    /// Returns the details of all objects of a publisher.
+    pub fn get_publisher_objects(
+        &self,
+        name: &PublisherHandle,
+    ) -> KrillResult<PublisherObjects> {
+        self.access.get_publisher(name)?;
+        let objects =
+            self.content.current_objects(name)?.to_publisher_objects()?;
+        Ok(PublisherObjects::new(name.clone(), objects))
+    }
+
+    /// Returns the current objects of a publisher, as they are served to
+    /// relying parties.
+    pub fn get_publisher_elements(
+        &self,
+        name: &PublisherHandle,
+    ) -> KrillResult<Vec<PublishElement>> {
+        self.access.get_publisher(name)?;
+        self.content
+            .current_objects(name)?
+            .try_into_publish_elements()
+    }
+
+    /// Returns the differences between the objects of a publisher and the
+    /// objects that the publishing CA believes it has published. Objects of
+    /// the CA outside of the base URI of the publisher are ignored, as they
+    /// are published in another repository.
+    pub fn diff_publisher_objects(
+        &self,
+        name: &PublisherHandle,
+        ca_elements: Vec<PublishElement>,
+    ) -> KrillResult<PublisherObjectsDiff> {
+        let publisher = self.access.get_publisher(name)?;
+        let current = self.content.current_objects(name)?;
+
+        let ca_objects = CurrentObjects::new(
+            ca_elements
+                .into_iter()
+                .filter(|element| {
+                    publisher.base_uri().is_parent_of(element.uri())
+                })
+                .map(|element| {
+                    let (uri, base64) = element.unpack();
+                    (CurrentObjectUri::from(&uri), base64)
+                })
+                .collect(),
+        );
+
+        let delta = current.diff(&ca_objects)?;
+        Ok(PublisherObjectsDiff::new(name.clone(), &delta))
+    }
+
     pub fn get_publisher_details(
         &self,
         name: &PublisherHandle,
@@ -286,7 +363,11 @@ impl RepositoryManager {
             .current_objects(name)?
             .try_into_publish_elements()?;
 
-        Ok(PublisherDetails::new(name, id_cert, base_uri, current))
+        let quota = self.config.publisher_quotas.quota_for(name);
+
+        Ok(PublisherDetails::new(
+            name, id_cert, base_uri, current, quota,
+        ))
     }
 
     /// Returns the RFC8183 Repository Response for the publisher.
@@ -329,7 +410,67 @@ impl RepositoryManager {
     /// Update the RRDP files and rsync content on disk.
     pub fn write_repository(&self) -> KrillResult<()> {
         self.content
-            .write_repository(self.config.rrdp_updates_config)
+            .write_repository(self.config.rrdp_updates_config)?;
+        self.schedule_mirrors_sync()
+    }
+
+    /// Push the RRDP files and rsync content on disk to all mirrors.
+    pub async fn sync_mirrors(&self) -> KrillResult<()> {
+        if self.mirrors.is_empty() || !self.initialized()? {
+            return Ok(());
+        }
+        let stats = self.content.stats()?;
+        self.mirrors.sync(&stats).await
+    }
+
+    /// Save a file pushed by another Krill publication server, for which
+    /// this server acts as a mirror.
+    pub fn mirror_save(&self, path: &str, content: Bytes) -> KrillResult<()> {
+        let full_path = self.mirror_path(path)?;
+        file::save(&content, &full_path)?;
+        Ok(())
+    }
+
+    /// Remove a file which was removed by another Krill publication server,
+    /// for which this server acts as a mirror.
+    pub fn mirror_delete(&self, path: &str) -> KrillResult<()> {
+        let full_path = self.mirror_path(path)?;
+        file::clean_file_and_path_in(self.config.repo_dir(), &full_path)?;
+        Ok(())
+    }
+
+    // Returns the full path for a mirrored file. Fails if this server has
+    // its own repository, because the files would be overwritten.
+    fn mirror_path(&self, path: &str) -> KrillResult<PathBuf> {
+        if !is_valid_mirror_path(path) {
+            return Err(Error::RepositoryMirrorInvalidPath(path.to_string()));
+        }
+        if self.initialized()? {
+            return Err(Error::RepositoryServerAlreadyInitialized);
+        }
+        Ok(self.config.repo_dir().join(path))
+    }
+
+    /// Returns the RRDP serials for which files are kept on disk.
+    pub fn archive_index(&self) -> KrillResult<RrdpArchiveIndex> {
+        RrdpArchive::new(self.config.repo_dir()).index()
+    }
+
+    /// Reconstructs the repository content at an earlier RRDP serial from
+    /// the files kept on disk.
+    pub fn archive_state(
+        &self,
+        query: &RrdpArchiveQuery,
+    ) -> KrillResult<RrdpArchiveState> {
+        RrdpArchive::new(self.config.repo_dir()).state(query)
+    }
+
+    fn schedule_mirrors_sync(&self) -> KrillResult<()> {
+        if self.mirrors.is_empty() {
+            Ok(())
+        } else {
+            self.tasks.schedule(Task::SyncRepositoryMirrors, now())
+        }
     }
 }
 
@@ -362,13 +503,13 @@ mod tests {
         commons::{
             api::{
                 rrdp::{PublicationDeltaError, RrdpSession},
-                IdCertInfo,
+                IdCertInfo, PublisherQuota, Timestamp,
             },
             crypto::{KrillSignerBuilder, OpenSslSignerConfig},
             util::file::{self, CurrentFile},
         },
         constants::*,
-        daemon::config::{SignerConfig, SignerType},
+        daemon::config::{PublisherQuotasConfig, SignerConfig, SignerType},
         pubd::{Publisher, RrdpServer},
         test::{self, https, init_config, rsync},
     };
@@ -417,6 +558,14 @@ mod tests {
     }
 
     fn make_server(storage_uri: &Url, data_dir: &Path) -> RepositoryManager {
+        make_server_with_config(storage_uri, data_dir, |_| {})
+    }
+
+    fn make_server_with_config(
+        storage_uri: &Url,
+        data_dir: &Path,
+        configure: impl FnOnce(&mut Config),
+    ) -> RepositoryManager {
         enable_test_mode();
         let mut config = Config::test(
             storage_uri,
@@ -427,6 +576,7 @@ mod tests {
             false,
         );
         init_config(&mut config);
+        configure(&mut config);
 
         let signer = KrillSignerBuilder::new(
             storage_uri,
@@ -778,6 +928,82 @@ mod tests {
         cleanup();
     }
 
+    #[test]
+    fn should_enforce_publisher_quota() {
+        let (data_dir, cleanup) = test::tmp_dir();
+        let storage_uri = test::mem_storage();
+
+        let quotas = PublisherQuotasConfig {
+            default: PublisherQuota {
+                max_objects: Some(1),
+                max_size: None,
+                max_object_size: Some(100),
+            },
+            ..Default::default()
+        };
+        let server = make_server_with_config(&storage_uri, &data_dir, |c| {
+            c.publisher_quotas = quotas
+        });
+
+        let alice = publisher_alice(&storage_uri);
+        let alice_handle = Handle::from_str("alice").unwrap();
+        let publisher_req =
+            make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+        let actor = Actor::actor_from_def(ACTOR_DEF_TEST);
+        server.create_publisher(publisher_req, &actor).unwrap();
+
+        let file1 = CurrentFile::new(
+            test::rsync("rsync://localhost/repo/alice/file.txt"),
+            &Bytes::from("example content"),
+        );
+        let file2 = CurrentFile::new(
+            test::rsync("rsync://localhost/repo/alice/file2.txt"),
+            &Bytes::from("example content 2"),
+        );
+        let big_file = CurrentFile::new(
+            test::rsync("rsync://localhost/repo/alice/file2.txt"),
+            &Bytes::from_static(include_bytes!("../../LICENSE")),
+        );
+
+        // A single small object is allowed
+        let mut delta = PublishDelta::empty();
+        delta.add_publish(file1.as_publish());
+        server.publish(&alice_handle, delta).unwrap();
+
+        // A second object exceeds the maximum number of objects
+        let mut delta = PublishDelta::empty();
+        delta.add_publish(file2.as_publish());
+        match server.publish(&alice_handle, delta) {
+            Err(Error::Rfc8181Delta(
+                PublicationDeltaError::QuotaExceeded(_),
+            )) => {}
+            _ => panic!("Expected quota exceeded error"),
+        }
+
+        // Replacing the object is fine, unless the new one is too large
+        let mut delta = PublishDelta::empty();
+        delta.add_withdraw(file1.as_withdraw());
+        delta.add_publish(file2.as_publish());
+        server.publish(&alice_handle, delta).unwrap();
+
+        let mut delta = PublishDelta::empty();
+        delta.add_update(big_file.as_update(file2.hash()));
+        match server.publish(&alice_handle, delta) {
+            Err(Error::Rfc8181Delta(
+                PublicationDeltaError::ObjectTooLarge(uri, _),
+            )) => assert_eq!(&uri, big_file.uri()),
+            _ => panic!("Expected object too large error"),
+        }
+
+        let details = server.get_publisher_details(&alice_handle).unwrap();
+        assert_eq!(details.current_files().len(), 1);
+        assert!(details.size() > 0 && details.size() <= 100);
+        assert_eq!(details.quota().max_objects, Some(1));
+
+        cleanup();
+    }
+
     #[test]
     pub fn repository_session_reset() {
         let (data_dir, cleanup) = test::tmp_dir();
@@ -880,6 +1106,139 @@ mod tests {
         cleanup();
     }
 
+    #[test]
+    fn should_reconstruct_archived_serials() {
+        let (data_dir, cleanup) = test::tmp_dir();
+        let storage_uri = test::mem_storage();
+        let server = make_server_with_config(&storage_uri, &data_dir, |c| {
+            c.rrdp_updates_config.rrdp_files_archive = true;
+            c.rrdp_updates_config.rrdp_delta_files_min_nr = 1;
+            c.rrdp_updates_config.rrdp_delta_files_max_nr = 1;
+        });
+
+        let alice = publisher_alice(&storage_uri);
+        let alice_handle = Handle::from_str("alice").unwrap();
+        let publisher_req =
+            make_publisher_req(alice_handle.as_str(), alice.id_cert());
+        let actor = Actor::actor_from_def(ACTOR_DEF_TEST);
+        server.create_publisher(publisher_req, &actor).unwrap();
+
+        let file1 = CurrentFile::new(
+            test::rsync("rsync://localhost/repo/alice/file.txt"),
+            &Bytes::from("example content"),
+        );
+        let file1_update = CurrentFile::new(
+            test::rsync("rsync://localhost/repo/alice/file.txt"),
+            &Bytes::from("example content - updated"),
+        );
+        let file2 = CurrentFile::new(
+            test::rsync("rsync://localhost/repo/alice/file2.txt"),
+            &Bytes::from("example content 2"),
+        );
+
+        // serial 2: publish file and file2
+        let mut delta = PublishDelta::empty();
+        delta.add_publish(file1.as_publish());
+        delta.add_publish(file2.as_publish());
+        server.publish(&alice_handle, delta).unwrap();
+        server.update_rrdp_if_needed().unwrap();
+
+        // serial 3: update file, withdraw file2
+        let mut delta = PublishDelta::empty();
+        delta.add_update(file1_update.as_update(file1.hash()));
+        delta.add_withdraw(file2.as_withdraw());
+        server.publish(&alice_handle, delta).unwrap();
+        server.update_rrdp_if_needed().unwrap();
+
+        // serial 4: remove everything
+        server.remove_publisher(alice_handle, &actor).unwrap();
+        server.update_rrdp_if_needed().unwrap();
+
+        let repo_dir = data_dir.join(REPOSITORY_DIR);
+        let session = server.repo_stats().unwrap().session();
+        let archived_session_dir = repo_dir
+            .join(REPOSITORY_RRDP_ARCHIVE_DIR)
+            .join(session.to_string());
+        let current_session_dir = session_dir(&data_dir);
+
+        // Serial 2 went out of scope and was archived
+        assert!(session_dir_contains_serial(&archived_session_dir, 2));
+        assert!(!session_dir_contains_serial(&current_session_dir, 2));
+
+        let index = server.archive_index().unwrap();
+        let serials: Vec<u64> =
+            index.serials().iter().map(|s| s.serial()).collect();
+        assert_eq!(serials, vec![1, 2, 3, 4]);
+
+        let uris = |state: &RrdpArchiveState| -> Vec<String> {
+            state
+                .objects()
+                .iter()
+                .map(|o| o.uri().to_string())
+                .collect()
+        };
+
+        // Remove the snapshots for serials 2 and 3, so that they can only
+        // be reconstructed by applying deltas to the snapshot for serial 1.
+        for serial in [2, 3] {
+            for dir in [&archived_session_dir, &current_session_dir] {
+                if let Some(snapshot) =
+                    RrdpServer::session_dir_snapshot(dir, serial).unwrap()
+                {
+                    fs::remove_file(snapshot).unwrap();
+                }
+            }
+        }
+
+        let state = server
+            .archive_state(&RrdpArchiveQuery::Serial(2, Some(session)))
+            .unwrap();
+        assert_eq!(
+            uris(&state),
+            vec![
+                "rsync://localhost/repo/alice/file.txt",
+                "rsync://localhost/repo/alice/file2.txt"
+            ]
+        );
+
+        let state = server
+            .archive_state(&RrdpArchiveQuery::Serial(3, None))
+            .unwrap();
+        assert_eq!(
+            uris(&state),
+            vec!["rsync://localhost/repo/alice/file.txt"]
+        );
+        assert_eq!(
+            state.objects()[0].base64().to_bytes(),
+            Bytes::from("example content - updated")
+        );
+
+        let state = server
+            .archive_state(&RrdpArchiveQuery::Time(Timestamp::now()))
+            .unwrap();
+        assert_eq!(state.serial(), 4);
+        assert!(state.objects().is_empty());
+
+        // Serial 3 can no longer be reconstructed without the delta for 2
+        let delta_2 = RrdpServer::find_in_serial_dir(
+            &archived_session_dir,
+            2,
+            "delta.xml",
+        )
+        .unwrap()
+        .unwrap();
+        fs::remove_file(delta_2).unwrap();
+
+        match server.archive_state(&RrdpArchiveQuery::Serial(3, None)) {
+            Err(Error::RepositoryArchiveUnavailable(msg)) => {
+                assert_eq!(msg, "missing delta for serial 2")
+            }
+            _ => panic!("Expected error for missing delta"),
+        }
+
+        cleanup();
+    }
+
     fn session_dir(base_dir: &Path) -> PathBuf {
         let mut rrdp_dir = base_dir.to_path_buf();
         rrdp_dir = rrdp_dir.join("repo/rrdp");
@@ -1,14 +1,20 @@
+mod archive;
 mod commands;
 mod events;
 mod manager;
+mod mirror;
 mod publishers;
 #[allow(clippy::mutable_key_type)]
 mod repository;
 
+pub use self::archive::RrdpArchive;
 pub use self::commands::{
     RepositoryAccessCommand, RepositoryAccessCommandDetails,
 };
 pub use self::events::{RepositoryAccessEvent, RepositoryAccessInitEvent};
 pub use self::manager::RepositoryManager;
+pub use self::mirror::{
+    is_valid_mirror_path, RepositoryMirrorStatus, RepositoryMirrors,
+};
 pub use self::publishers::Publisher;
 pub use self::repository::*;
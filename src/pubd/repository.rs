@@ -32,7 +32,9 @@ use crate::{
             },
             IdCertInfo,
         },
-        api::{PublicationServerUris, StorableRepositoryCommand},
+        api::{
+            PublicationServerUris, PublisherQuota, StorableRepositoryCommand,
+        },
         crypto::KrillSigner,
         error::{Error, KrillIoError},
         eventsourcing::{
@@ -54,7 +56,7 @@ use crate::{
     pubd::{
         publishers::Publisher, RepositoryAccessCommand,
         RepositoryAccessCommandDetails, RepositoryAccessEvent,
-        RepositoryAccessInitEvent,
+        RepositoryAccessInitEvent, RepositoryMirrorStatus,
     },
     ta::TA_NAME,
 };
@@ -175,12 +177,17 @@ impl RepositoryContentProxy {
     /// Publish an update for a publisher.
     ///
     /// Assumes that the RFC 8181 CMS has been verified, but will check that
-    /// all objects are within the publisher's uri space (jail).
+    /// all objects are within the publisher's uri space (jail), and that
+    /// the resulting content is within the publisher's quota. If
+    /// strict_validation is set, then all published objects must also be
+    /// valid RPKI objects.
     pub fn publish(
         &self,
         publisher: PublisherHandle,
         delta: PublishDelta,
         jail: &uri::Rsync,
+        quota: PublisherQuota,
+        strict_validation: bool,
     ) -> KrillResult<()> {
         debug!("Publish delta for {}", publisher);
         let delta = DeltaElements::from(delta);
@@ -190,6 +197,8 @@ impl RepositoryContentProxy {
             publisher,
             jail.clone(),
             delta,
+            quota,
+            strict_validation,
         );
         self.store.send_command(command)?;
 
@@ -301,6 +310,8 @@ pub enum RepositoryContentCommand {
         publisher: PublisherHandle,
         jail: uri::Rsync,
         delta: DeltaElements,
+        quota: PublisherQuota,
+        strict_validation: bool,
     },
     CreateRrdpDelta {
         handle: MyHandle,
@@ -336,12 +347,16 @@ impl RepositoryContentCommand {
         publisher: PublisherHandle,
         jail: uri::Rsync,
         delta: DeltaElements,
+        quota: PublisherQuota,
+        strict_validation: bool,
     ) -> Self {
         RepositoryContentCommand::Publish {
             handle,
             publisher,
             jail,
             delta,
+            quota,
+            strict_validation,
         }
     }
 
@@ -581,8 +596,12 @@ impl WalSupport for RepositoryContent {
                 publisher,
                 jail,
                 delta,
+                quota,
+                strict_validation,
                 ..
-            } => self.publish(publisher, jail, delta),
+            } => {
+                self.publish(publisher, jail, delta, quota, strict_validation)
+            }
         }
     }
 }
@@ -732,12 +751,26 @@ impl RepositoryContent {
         publisher: PublisherHandle,
         jail: uri::Rsync,
         delta: DeltaElements,
+        quota: PublisherQuota,
+        strict_validation: bool,
     ) -> KrillResult<Vec<RepositoryContentChange>> {
         if !delta.is_empty() {
             // Verifying the delta first.
             let current_objects = self.objects_for_publisher(&publisher);
             current_objects.verify_delta(&delta, &jail)?;
 
+            if let Err(e) = current_objects.verify_quota(&delta, quota) {
+                warn!("Rejected publication for {}: {}", publisher, e);
+                return Err(e.into());
+            }
+
+            if strict_validation {
+                if let Err(e) = current_objects.verify_objects(&delta) {
+                    warn!("Rejected publication for {}: {}", publisher, e);
+                    return Err(e.into());
+                }
+            }
+
             Ok(vec![RepositoryContentChange::RrdpDeltaStaged {
                 publisher,
                 delta,
@@ -770,6 +803,7 @@ impl RepositoryContent {
             last_update: Some(self.rrdp.last_update),
             rsync_base: self.rsync.base_uri.clone(),
             rrdp_base: self.rrdp.rrdp_base_uri.clone(),
+            mirrors: vec![],
         }
     }
 
@@ -1643,6 +1677,11 @@ impl RrdpServer {
             } else {
                 let path = entry.path();
                 if path.is_dir() {
+                    if rrdp_updates_config.rrdp_files_archive {
+                        // If archiving is enabled, then also keep the
+                        // files of old sessions
+                        self.archive_session_dir(&path);
+                    }
                     let _best_effort_rm = fs::remove_dir_all(path);
                 }
             }
@@ -1694,18 +1733,11 @@ impl RrdpServer {
                     if rrdp_updates_config.rrdp_files_archive {
                         // If archiving is enabled, then move these
                         // directories under the archive base
-
-                        let mut dest = self.rrdp_archive_dir.clone();
-                        dest.push(self.session.to_string());
-                        dest.push(format!("{}", serial));
-
-                        info!(
-                            "Archiving RRDP serial '{}' to '{}",
+                        self.archive_serial_dir(
+                            &self.session.to_string(),
                             serial,
-                            dest.to_string_lossy()
+                            path,
                         );
-                        let _ = fs::create_dir_all(&dest);
-                        let _ = fs::rename(path, dest);
                     } else if path.is_dir() {
                         let _best_effort_rm = fs::remove_dir_all(path);
                     } else {
@@ -1756,6 +1788,39 @@ impl RrdpServer {
 
         Ok(())
     }
+
+    /// Moves the serial directories of an old session to the archive.
+    fn archive_session_dir(&self, session_dir: &Path) {
+        let session = session_dir.file_name().unwrap_or_default();
+        if let Ok(entries) = fs::read_dir(session_dir) {
+            for entry in entries.flatten() {
+                if let Ok(serial) = u64::from_str(
+                    entry.file_name().to_string_lossy().as_ref(),
+                ) {
+                    self.archive_serial_dir(
+                        &session.to_string_lossy(),
+                        serial,
+                        entry.path(),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Moves a serial directory to the archive, on a best effort basis.
+    fn archive_serial_dir(&self, session: &str, serial: u64, path: PathBuf) {
+        let mut dest = self.rrdp_archive_dir.clone();
+        dest.push(session);
+        dest.push(format!("{}", serial));
+
+        info!(
+            "Archiving RRDP serial '{}' to '{}",
+            serial,
+            dest.to_string_lossy()
+        );
+        let _ = fs::create_dir_all(&dest);
+        let _ = fs::rename(path, dest);
+    }
 }
 
 /// rrdp paths and uris
@@ -2230,6 +2295,8 @@ pub struct RepoStats {
     last_update: Option<Time>,
     rsync_base: uri::Rsync,
     rrdp_base: uri::Https,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mirrors: Vec<RepositoryMirrorStatus>,
 }
 
 impl RepoStats {
@@ -2305,6 +2372,14 @@ impl RepoStats {
     pub fn session(&self) -> RrdpSession {
         self.session
     }
+
+    pub fn mirrors(&self) -> &[RepositoryMirrorStatus] {
+        &self.mirrors
+    }
+
+    pub fn set_mirrors(&mut self, mirrors: Vec<RepositoryMirrorStatus>) {
+        self.mirrors = mirrors;
+    }
 }
 
 impl fmt::Display for RepoStats {
@@ -2335,6 +2410,14 @@ impl fmt::Display for RepoStats {
             )?;
         }
 
+        if !self.mirrors.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Mirror, Target, Serial, Last Success")?;
+            for mirror in &self.mirrors {
+                writeln!(f, "{}", mirror)?;
+            }
+        }
+
         Ok(())
     }
 }
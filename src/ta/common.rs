@@ -132,8 +132,8 @@ impl TrustAnchorObjects {
         let signing_key = signing_cert.key_identifier();
 
         if signing_key != self.key_identifier {
-            // This would be a bug.. we will need to re-think this when
-            // implementing signed TALs and TA key rollovers.
+            // This would be a bug.. a key roll results in a new set of
+            // objects for the new key, rather than a change of this set.
             Err(Error::custom("TA key changed when republishing"))
         } else {
             let issuer = signing_cert.subject().clone();
@@ -197,6 +197,15 @@ impl TrustAnchorObjects {
         &self.manifest
     }
 
+    pub fn key_identifier(&self) -> KeyIdentifier {
+        self.key_identifier
+    }
+
+    /// Returns all certificates issued to children.
+    pub fn issued(&self) -> impl Iterator<Item = &IssuedCertificate> {
+        self.issued.values()
+    }
+
     pub fn revision(&self) -> &ObjectSetRevision {
         &self.revision
     }
@@ -378,6 +387,9 @@ pub struct TrustAnchorSignerInfo {
     pub objects: TrustAnchorObjects,
     // The TA certificate and TAL
     pub ta_cert_details: TaCertDetails,
+    // The staged new TA key, if a key roll is in progress
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub staged_key: Option<TaStagedKey>,
 }
 
 impl fmt::Display for TrustAnchorSignerInfo {
@@ -417,11 +429,148 @@ impl fmt::Display for TrustAnchorSignerInfo {
             f,
             "-------------------------------------------------------"
         )?;
+        if let Some(staged) = &self.staged_key {
+            writeln!(f)?;
+            writeln!(f, "{}", staged)?;
+        }
 
         Ok(())
     }
 }
 
+//------------ TaStagedKey -------------------------------------------------
+
+/// A new Trust Anchor key which is staged for a planned key roll.
+///
+/// The staged key has its own self-signed certificate and TAL. Its (empty)
+/// manifest and CRL are published alongside the objects of the current key,
+/// so that the new TAL can be distributed to relying parties before the key
+/// is activated. See RFC 6489 for the general key roll process.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TaStagedKey {
+    pub ta_cert_details: TaCertDetails,
+    pub objects: TrustAnchorObjects,
+}
+
+impl fmt::Display for TaStagedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "-------------------------------------------------------"
+        )?;
+        writeln!(f, "                 Staged Key (key roll)")?;
+        writeln!(
+            f,
+            "-------------------------------------------------------"
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Key identifier: {}", self.objects.key_identifier())?;
+        writeln!(f)?;
+        writeln!(f, "{}", self.objects)?;
+        writeln!(f)?;
+        writeln!(f, "New TAL:")?;
+        writeln!(f)?;
+        writeln!(f, "{}", self.ta_cert_details.tal())?;
+        writeln!(f)?;
+        writeln!(f, "-------------------------------------------------------")
+    }
+}
+
+//------------ TaKeyRollRequest --------------------------------------------
+
+/// A step in a planned Trust Anchor key roll, to be performed by the
+/// Trust Anchor Signer as part of the next signer request.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "step")]
+pub enum TaKeyRollRequest {
+    /// Create and stage a new key, with a new TA certificate and a TAL
+    /// using the given URIs. Note that the URIs must be different from
+    /// the ones used for the current key, as relying parties using the
+    /// current TAL will expect to find the current TA certificate there.
+    Initiate {
+        tal_https: Vec<uri::Https>,
+        tal_rsync: uri::Rsync,
+    },
+
+    /// Activate the staged key. All certificates issued to children are
+    /// re-issued under the new key, and the objects for the old key are
+    /// no longer published.
+    Activate,
+}
+
+impl fmt::Display for TaKeyRollRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaKeyRollRequest::Initiate { tal_rsync, .. } => write!(
+                f,
+                "initiate key roll, new TA certificate at: {}",
+                tal_rsync
+            ),
+            TaKeyRollRequest::Activate => write!(f, "activate staged key"),
+        }
+    }
+}
+
+//------------ TaKeyRollStatus ---------------------------------------------
+
+/// Shows the state of a Trust Anchor key roll as known to the proxy.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TaKeyRollStatus {
+    // Step to be included in the next signer request, if any
+    pub planned: Option<TaKeyRollRequest>,
+    // The staged key, if any
+    pub staged_key: Option<TaStagedKey>,
+}
+
+impl fmt::Display for TaKeyRollStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.planned {
+            Some(planned) => {
+                writeln!(f, "Planned for next signer request: {}", planned)?
+            }
+            None => writeln!(f, "No key roll step planned.")?,
+        }
+        writeln!(f)?;
+        match &self.staged_key {
+            Some(staged) => write!(f, "{}", staged),
+            None => writeln!(f, "No key is staged."),
+        }
+    }
+}
+
+//------------ TaKeyRollResponse -------------------------------------------
+
+/// The key roll state reported back by the Trust Anchor Signer.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum TaKeyRollResponse {
+    /// A new key is staged. This is included in every response as long
+    /// as the key is staged, because its objects are re-signed in every
+    /// signing session.
+    Staged(TaStagedKey),
+
+    /// The staged key was activated. The TA certificate and TAL for the
+    /// new current key are included. The objects in the response are
+    /// signed under this new key.
+    Activated(TaCertDetails),
+}
+
+impl fmt::Display for TaKeyRollResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaKeyRollResponse::Staged(staged) => {
+                write!(f, "key staged: {}", staged.objects.key_identifier())
+            }
+            TaKeyRollResponse::Activated(details) => write!(
+                f,
+                "key activated: {}",
+                details.cert().key_identifier()
+            ),
+        }
+    }
+}
+
 //------------ Nonce -------------------------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -553,6 +702,8 @@ impl fmt::Display for TrustAnchorSignedRequest {
 pub struct TrustAnchorSignerRequest {
     pub nonce: Nonce, // should be matched in response (replay protection)
     pub child_requests: Vec<TrustAnchorChildRequests>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_roll: Option<TaKeyRollRequest>,
 }
 
 impl TrustAnchorSignerRequest {
@@ -600,6 +751,10 @@ impl fmt::Display for TrustAnchorSignerRequest {
             }
             writeln!(f)?;
         }
+        if let Some(key_roll) = &self.key_roll {
+            writeln!(f, "key roll:      {}", key_roll)?;
+            writeln!(f)?;
+        }
         writeln!(f, "NOTE: Use the JSON output for the signer.")?;
 
         Ok(())
@@ -671,6 +826,8 @@ impl fmt::Display for TrustAnchorSignedResponse {
 pub struct TrustAnchorSignerResponse {
     pub nonce: Nonce, // should match the request (replay protection)
     pub objects: TrustAnchorObjects,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_roll: Option<TaKeyRollResponse>,
     pub child_responses:
         HashMap<ChildHandle, HashMap<KeyIdentifier, ProvisioningResponse>>,
 }
@@ -703,6 +860,10 @@ impl fmt::Display for TrustAnchorSignerResponse {
         writeln!(f)?;
         writeln!(f, "{}", self.objects)?;
         writeln!(f)?;
+        if let Some(key_roll) = &self.key_roll {
+            writeln!(f, "key roll:      {}", key_roll)?;
+            writeln!(f)?;
+        }
         for (child, responses) in &self.child_responses {
             writeln!(f, "-------------------------------")?;
             writeln!(f, "          child response")?;
@@ -31,19 +31,32 @@ fn ta_resource_class_name() -> rpki::ca::provisioning::ResourceClassName {
 //----------------- TESTS --------------------------------------------------------------
 #[cfg(test)]
 mod tests {
-    use rpki::ca::idexchange::{RepoInfo, ServiceUri};
+    use rpki::{
+        ca::{
+            idexchange::{ChildHandle, RepoInfo, ServiceUri},
+            provisioning::{IssuanceRequest, RequestResourceLimit},
+        },
+        repository::crl::Crl,
+    };
 
     use super::*;
 
-    use std::{sync::Arc, time::Duration};
+    use std::{str::FromStr, sync::Arc, time::Duration};
+
+    use url::Url;
 
     use crate::{
         commons::{
-            api::{PublicationServerInfo, RepositoryContact},
-            crypto::KrillSignerBuilder,
+            actor::Actor,
+            api::{
+                AddChildRequest, IdCertInfo, PublicationServerInfo,
+                RepositoryContact,
+            },
+            crypto::{KrillSigner, KrillSignerBuilder},
+            error::Error,
             eventsourcing::{namespace, AggregateStore, Namespace},
         },
-        daemon::config::ConfigDefaults,
+        daemon::{ca::Rfc8183Id, config::ConfigDefaults},
         test,
     };
 
@@ -226,4 +239,375 @@ mod tests {
             cleanup();
         })
     }
+
+    /// A proxy and signer set up with a repository and in memory stores.
+    struct TaTestSetup {
+        ta_signer_store: AggregateStore<TrustAnchorSigner>,
+        ta_proxy_store: AggregateStore<TrustAnchorProxy>,
+        signer: Arc<KrillSigner>,
+        timing: TaTimingConfig,
+        actor: Actor,
+        proxy_handle: TrustAnchorHandle,
+        signer_handle: TrustAnchorHandle,
+        repo_info: RepoInfo,
+    }
+
+    impl TaTestSetup {
+        fn new(storage_uri: &Url) -> Self {
+            let ta_signer_store: AggregateStore<TrustAnchorSigner> =
+                AggregateStore::create(
+                    storage_uri,
+                    namespace!("ta_signer"),
+                    false,
+                )
+                .unwrap();
+            let ta_proxy_store: AggregateStore<TrustAnchorProxy> =
+                AggregateStore::create(
+                    storage_uri,
+                    namespace!("ta_proxy"),
+                    false,
+                )
+                .unwrap();
+
+            let signers = ConfigDefaults::openssl_signer_only();
+            let signer = Arc::new(
+                KrillSignerBuilder::new(
+                    storage_uri,
+                    Duration::from_secs(1),
+                    &signers,
+                )
+                .build()
+                .unwrap(),
+            );
+
+            let timing = TaTimingConfig::default();
+            let actor = test::test_actor();
+
+            let proxy_handle = TrustAnchorHandle::new("proxy".into());
+            let proxy_init = TrustAnchorProxyInitCommand::make(
+                &proxy_handle,
+                signer.clone(),
+                &actor,
+            );
+            ta_proxy_store.add(proxy_init).unwrap();
+
+            let repo_info = RepoInfo::new(
+                test::rsync("rsync://example.krill.cloud/repo/"),
+                Some(test::https(
+                    "https://exmple.krill.cloud/repo/notification.xml",
+                )),
+            );
+
+            let repository = {
+                let repo_key_id = signer.create_key().unwrap();
+                let repo_key = signer.get_key_info(&repo_key_id).unwrap();
+
+                let service_uri = ServiceUri::Https(test::https(
+                    "https://example.krill.cloud/rfc8181/ta",
+                ));
+                let server_info =
+                    PublicationServerInfo::new(repo_key, service_uri);
+
+                RepositoryContact::new(repo_info.clone(), server_info)
+            };
+
+            let add_repo_cmd = TrustAnchorProxyCommand::add_repo(
+                &proxy_handle,
+                repository,
+                &actor,
+            );
+            let proxy = ta_proxy_store.command(add_repo_cmd).unwrap();
+
+            let signer_handle = TrustAnchorHandle::new("signer".into());
+            let signer_init_cmd = TrustAnchorSignerInitCommand::new(
+                &signer_handle,
+                TrustAnchorSignerInitCommandDetails {
+                    proxy_id: proxy.id().clone(),
+                    repo_info: repo_info.clone(),
+                    tal_https: vec![test::https(
+                        "https://example.krill.cloud/ta/ta.cer",
+                    )],
+                    tal_rsync: test::rsync(
+                        "rsync://example.krill.cloud/ta/ta.cer",
+                    ),
+                    private_key_pem: None,
+                    ta_mft_nr_override: None,
+                    timing,
+                    signer: signer.clone(),
+                },
+                &actor,
+            );
+
+            let ta_signer = ta_signer_store.add(signer_init_cmd).unwrap();
+            let add_signer_cmd = TrustAnchorProxyCommand::add_signer(
+                &proxy_handle,
+                ta_signer.get_signer_info(),
+                &actor,
+            );
+            ta_proxy_store.command(add_signer_cmd).unwrap();
+
+            TaTestSetup {
+                ta_signer_store,
+                ta_proxy_store,
+                signer,
+                timing,
+                actor,
+                proxy_handle,
+                signer_handle,
+                repo_info,
+            }
+        }
+
+        fn proxy_command(
+            &self,
+            cmd: TrustAnchorProxyCommand,
+        ) -> Result<Arc<TrustAnchorProxy>, Error> {
+            self.ta_proxy_store.command(cmd)
+        }
+
+        fn make_signer_request(&self) -> Arc<TrustAnchorProxy> {
+            self.proxy_command(TrustAnchorProxyCommand::make_signer_request(
+                &self.proxy_handle,
+                &self.actor,
+            ))
+            .unwrap()
+        }
+
+        /// Lets the signer process the open request of the proxy, and the
+        /// proxy process the response.
+        fn process_signer_request(&self) -> Arc<TrustAnchorProxy> {
+            let proxy =
+                self.ta_proxy_store.get_latest(&self.proxy_handle).unwrap();
+            let signed_request =
+                proxy.get_signer_request(self.timing, &self.signer).unwrap();
+            let nonce = signed_request.content().nonce.clone();
+
+            let ta_signer = self
+                .ta_signer_store
+                .command(
+                    TrustAnchorSignerCommand::make_process_request_command(
+                        &self.signer_handle,
+                        signed_request,
+                        self.timing,
+                        None,
+                        self.signer.clone(),
+                        &self.actor,
+                    ),
+                )
+                .unwrap();
+
+            let response =
+                ta_signer.get_exchange(&nonce).unwrap().response.clone();
+            self.proxy_command(
+                TrustAnchorProxyCommand::process_signer_response(
+                    &self.proxy_handle,
+                    response,
+                    &self.actor,
+                ),
+            )
+            .unwrap()
+        }
+
+        /// Performs a full proxy -> signer -> proxy exchange.
+        fn signing_session(&self) -> Arc<TrustAnchorProxy> {
+            self.make_signer_request();
+            self.process_signer_request()
+        }
+    }
+
+    #[test]
+    fn ta_key_roll() {
+        test::test_in_memory(|storage_uri| {
+            let cleanup = test::init_logging();
+            let setup = TaTestSetup::new(storage_uri);
+
+            let proxy = setup.signing_session();
+            let old_key =
+                proxy.get_ta_details().unwrap().cert().key_identifier();
+            assert_eq!(proxy.get_publish_elements().unwrap().len(), 2);
+
+            // Activation is not possible without a staged key
+            let activate = TrustAnchorProxyCommand::plan_key_roll(
+                &setup.proxy_handle,
+                TaKeyRollRequest::Activate,
+                &setup.actor,
+            );
+            assert!(setup.proxy_command(activate.clone()).is_err());
+
+            // Stage a new key, using a new TAL. The rsync URI is in the
+            // repository of the TA, so the staged certificate is published.
+            let new_tal_https =
+                vec![test::https("https://example.krill.cloud/ta/ta-2.cer")];
+            let new_tal_rsync =
+                test::rsync("rsync://example.krill.cloud/repo/ta-2.cer");
+            setup
+                .proxy_command(TrustAnchorProxyCommand::plan_key_roll(
+                    &setup.proxy_handle,
+                    TaKeyRollRequest::Initiate {
+                        tal_https: new_tal_https.clone(),
+                        tal_rsync: new_tal_rsync.clone(),
+                    },
+                    &setup.actor,
+                ))
+                .unwrap();
+
+            let proxy = setup.signing_session();
+            let status = proxy.get_key_roll_status().unwrap();
+            assert!(status.planned.is_none());
+            let staged = status.staged_key.unwrap();
+            let new_key = staged.ta_cert_details.cert().key_identifier();
+            assert_ne!(old_key, new_key);
+            assert_eq!(staged.ta_cert_details.tal().uris(), &new_tal_https);
+
+            // The current key is still used, but the staged key objects and
+            // certificate are published as well. Also in subsequent signing
+            // sessions.
+            assert_eq!(
+                proxy.get_ta_details().unwrap().cert().key_identifier(),
+                old_key
+            );
+            let elements = proxy.get_publish_elements().unwrap();
+            assert_eq!(elements.len(), 5);
+            assert!(elements
+                .iter()
+                .any(|element| element.uri() == &new_tal_rsync));
+            let proxy = setup.signing_session();
+            assert_eq!(proxy.get_publish_elements().unwrap().len(), 5);
+
+            // Activate the staged key
+            setup.proxy_command(activate).unwrap();
+            let proxy = setup.signing_session();
+
+            let ta_details = proxy.get_ta_details().unwrap();
+            assert_eq!(ta_details.cert().key_identifier(), new_key);
+            assert_eq!(ta_details.tal().rsync_uri(), &new_tal_rsync);
+            assert!(proxy
+                .get_key_roll_status()
+                .unwrap()
+                .staged_key
+                .is_none());
+            assert_eq!(proxy.get_publish_elements().unwrap().len(), 2);
+
+            let ta_signer = setup
+                .ta_signer_store
+                .get_latest(&setup.signer_handle)
+                .unwrap();
+            assert!(ta_signer.get_staged_key().is_none());
+            assert_eq!(
+                ta_signer
+                    .get_signer_info()
+                    .ta_cert_details
+                    .cert()
+                    .key_identifier(),
+                new_key
+            );
+
+            cleanup();
+        })
+    }
+
+    #[test]
+    fn ta_child_update_and_remove() {
+        test::test_in_memory(|storage_uri| {
+            let cleanup = test::init_logging();
+            let setup = TaTestSetup::new(storage_uri);
+            let proxy_handle = &setup.proxy_handle;
+            let actor = &setup.actor;
+            let signer = &setup.signer;
+
+            // Add a child and let it request a certificate
+            let child = ChildHandle::from_str("child").unwrap();
+            let child_id: IdCertInfo =
+                Rfc8183Id::generate(signer).unwrap().into();
+            let add_child_cmd = TrustAnchorProxyCommand::add_child(
+                proxy_handle,
+                AddChildRequest::new(
+                    child.clone(),
+                    test::resources("AS65000", "10.0.0.0/8", ""),
+                    (&child_id).try_into().unwrap(),
+                ),
+                actor,
+            );
+            setup.proxy_command(add_child_cmd).unwrap();
+
+            let child_key = signer.create_key().unwrap();
+            let csr = signer
+                .sign_csr(&setup.repo_info, "child", &child_key)
+                .unwrap();
+            let issuance = IssuanceRequest::new(
+                ta_resource_class_name(),
+                RequestResourceLimit::default(),
+                csr,
+            );
+            setup
+                .proxy_command(TrustAnchorProxyCommand::add_child_request(
+                    proxy_handle,
+                    child.clone(),
+                    ProvisioningRequest::Issuance(issuance),
+                    actor,
+                ))
+                .unwrap();
+
+            let proxy = setup.signing_session();
+            let serial = proxy
+                .get_trust_anchor_objects()
+                .unwrap()
+                .get_issued(&child_key)
+                .unwrap()
+                .serial();
+
+            // Update the child resources
+            let new_resources = test::resources("", "10.0.0.0/16", "");
+            let proxy = setup
+                .proxy_command(
+                    TrustAnchorProxyCommand::update_child_resources(
+                        proxy_handle,
+                        child.clone(),
+                        new_resources.clone(),
+                        actor,
+                    ),
+                )
+                .unwrap();
+            assert_eq!(
+                proxy.get_child(&child).unwrap().resources,
+                new_resources
+            );
+
+            // Children cannot be removed while there is an open request
+            let remove_child_cmd = TrustAnchorProxyCommand::remove_child(
+                proxy_handle,
+                child.clone(),
+                actor,
+            );
+            setup.make_signer_request();
+            assert!(setup.proxy_command(remove_child_cmd.clone()).is_err());
+
+            // Complete that exchange and then remove the child
+            setup.process_signer_request();
+            let proxy = setup.proxy_command(remove_child_cmd).unwrap();
+            assert!(proxy.get_child(&child).is_err());
+
+            // The certificate is revoked in the next signing session
+            let proxy = setup.signing_session();
+            let objects = proxy.get_trust_anchor_objects().unwrap();
+            assert!(objects.get_issued(&child_key).is_none());
+
+            let crl = objects
+                .publish_elements()
+                .unwrap()
+                .into_iter()
+                .find(|element| element.uri().ends_with(".crl"))
+                .unwrap();
+            let crl = Crl::decode(crl.base64().to_bytes()).unwrap();
+            assert!(crl.contains(serial));
+
+            // Nothing is left to revoke after that
+            let proxy = setup.make_signer_request();
+            let signed_request =
+                proxy.get_signer_request(setup.timing, signer).unwrap();
+            assert!(signed_request.content().child_requests.is_empty());
+
+            cleanup();
+        })
+    }
 }
@@ -11,16 +11,20 @@ use chrono::Duration;
 use rpki::{
     ca::{
         idexchange::{self, ChildHandle, MyHandle},
-        provisioning::{ResourceClassEntitlements, SigningCert},
+        provisioning::{self, ResourceClassEntitlements, SigningCert},
+        publication::Base64,
     },
     crypto::KeyIdentifier,
-    repository::x509::Time,
+    repository::{resources::ResourceSet, x509::Time},
 };
 
 use crate::{
     commons::{
         actor::Actor,
-        api::{AddChildRequest, IdCertInfo, RepositoryContact},
+        api::{
+            rrdp::PublishElement, AddChildRequest, IdCertInfo,
+            RepositoryContact,
+        },
         crypto::{CsrInfo, KrillSigner},
         error::Error,
         eventsourcing::{
@@ -89,6 +93,17 @@ pub struct TrustAnchorProxy {
     // is an open request. We first need to process the response, before we
     // can accept new requests from any child.
     open_signer_request: Option<Nonce>,
+
+    // A key roll step planned by the operator. It will be included in the
+    // next signer request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    planned_key_roll: Option<TaKeyRollRequest>,
+
+    // Keys of removed children that still need to be revoked by the signer.
+    // Revocation requests for these keys are included in the next signer
+    // request, so that the TA CRL will show them.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    removed_children: HashMap<ChildHandle, Vec<KeyIdentifier>>,
 }
 
 //------------ TrustAnchorProxy: Commands and Events -----------------------
@@ -159,11 +174,14 @@ pub enum TrustAnchorProxyEvent {
     SignerAdded(TrustAnchorSignerInfo),
     SignerRequestMade(Nonce),
     SignerResponseReceived(TrustAnchorSignedResponse),
+    KeyRollPlanned(TaKeyRollRequest),
 
     // Children
     ChildAdded(TrustAnchorChild),
     ChildRequestAdded(ChildHandle, ProvisioningRequest),
     ChildResponseGiven(ChildHandle, KeyIdentifier),
+    ChildResourcesUpdated(ChildHandle, ResourceSet),
+    ChildRemoved(ChildHandle),
 }
 
 impl Event for TrustAnchorProxyEvent {}
@@ -199,6 +217,9 @@ impl fmt::Display for TrustAnchorProxyEvent {
                     response.content().nonce
                 )
             }
+            TrustAnchorProxyEvent::KeyRollPlanned(key_roll) => {
+                write!(f, "Planned key roll step: {}", key_roll)
+            }
 
             // Children
             TrustAnchorProxyEvent::ChildAdded(child) => {
@@ -225,6 +246,19 @@ impl fmt::Display for TrustAnchorProxyEvent {
                     child_handle, key
                 )
             }
+            TrustAnchorProxyEvent::ChildResourcesUpdated(
+                child_handle,
+                resources,
+            ) => {
+                write!(
+                    f,
+                    "Updated resources for child {} to: {}",
+                    child_handle, resources
+                )
+            }
+            TrustAnchorProxyEvent::ChildRemoved(child_handle) => {
+                write!(f, "Removed child: {}", child_handle)
+            }
         }
     }
 }
@@ -244,11 +278,14 @@ pub enum TrustAnchorProxyCommandDetails {
     AddSigner(TrustAnchorSignerInfo),
     MakeSignerRequest,
     ProcessSignerResponse(TrustAnchorSignedResponse),
+    PlanKeyRoll(TaKeyRollRequest),
 
     // Children
     AddChild(AddChildRequest),
     AddChildRequest(ChildHandle, ProvisioningRequest),
     GiveChildResponse(ChildHandle, KeyIdentifier),
+    UpdateChildResources(ChildHandle, ResourceSet),
+    RemoveChild(ChildHandle),
 }
 
 impl fmt::Display for TrustAnchorProxyCommandDetails {
@@ -288,6 +325,9 @@ impl fmt::Display for TrustAnchorProxyCommandDetails {
                     response.content().objects.revision().next_update().to_rfc3339()
                 )
             }
+            TrustAnchorProxyCommandDetails::PlanKeyRoll(key_roll) => {
+                write!(f, "Plan key roll step: {}", key_roll)
+            }
 
             // Children
             TrustAnchorProxyCommandDetails::AddChild(child) => {
@@ -313,6 +353,19 @@ impl fmt::Display for TrustAnchorProxyCommandDetails {
                     child_handle, key
                 )
             }
+            TrustAnchorProxyCommandDetails::UpdateChildResources(
+                child_handle,
+                resources,
+            ) => {
+                write!(
+                    f,
+                    "Update resources for child {} to: {}",
+                    child_handle, resources
+                )
+            }
+            TrustAnchorProxyCommandDetails::RemoveChild(child_handle) => {
+                write!(f, "Remove child: {}", child_handle)
+            }
         }
     }
 }
@@ -379,6 +432,20 @@ impl eventsourcing::WithStorableDetails for TrustAnchorProxyCommandDetails {
                     .next_update()
                     .to_rfc3339(),
             ),
+            TrustAnchorProxyCommandDetails::PlanKeyRoll(key_roll) => {
+                let summary = crate::commons::api::CommandSummary::new(
+                    "cmd-ta-proxy-key-roll",
+                    self,
+                );
+                match key_roll {
+                    TaKeyRollRequest::Initiate { tal_rsync, .. } => summary
+                        .with_arg("step", "initiate")
+                        .with_arg("tal rsync uri", tal_rsync),
+                    TaKeyRollRequest::Activate => {
+                        summary.with_arg("step", "activate")
+                    }
+                }
+            }
 
             // Children
             TrustAnchorProxyCommandDetails::AddChild(child) => {
@@ -404,6 +471,22 @@ impl eventsourcing::WithStorableDetails for TrustAnchorProxyCommandDetails {
                 self,
             )
             .with_child(child_handle),
+            TrustAnchorProxyCommandDetails::UpdateChildResources(
+                child_handle,
+                resources,
+            ) => crate::commons::api::CommandSummary::new(
+                "cmd-ta-proxy-child-update-res",
+                self,
+            )
+            .with_child(child_handle)
+            .with_resources(resources),
+            TrustAnchorProxyCommandDetails::RemoveChild(child_handle) => {
+                crate::commons::api::CommandSummary::new(
+                    "cmd-ta-proxy-child-remove",
+                    self,
+                )
+                .with_child(child_handle)
+            }
         }
     }
 
@@ -464,6 +547,19 @@ impl TrustAnchorProxyCommand {
         )
     }
 
+    pub fn plan_key_roll(
+        id: &TrustAnchorHandle,
+        key_roll: TaKeyRollRequest,
+        actor: &Actor,
+    ) -> Self {
+        TrustAnchorProxyCommand::new(
+            id,
+            None,
+            TrustAnchorProxyCommandDetails::PlanKeyRoll(key_roll),
+            actor,
+        )
+    }
+
     pub fn add_child(
         id: &TrustAnchorHandle,
         child: AddChildRequest,
@@ -504,6 +600,35 @@ impl TrustAnchorProxyCommand {
             actor,
         )
     }
+
+    pub fn update_child_resources(
+        id: &TrustAnchorHandle,
+        child: ChildHandle,
+        resources: ResourceSet,
+        actor: &Actor,
+    ) -> Self {
+        TrustAnchorProxyCommand::new(
+            id,
+            None,
+            TrustAnchorProxyCommandDetails::UpdateChildResources(
+                child, resources,
+            ),
+            actor,
+        )
+    }
+
+    pub fn remove_child(
+        id: &TrustAnchorHandle,
+        child: ChildHandle,
+        actor: &Actor,
+    ) -> Self {
+        TrustAnchorProxyCommand::new(
+            id,
+            None,
+            TrustAnchorProxyCommandDetails::RemoveChild(child),
+            actor,
+        )
+    }
 }
 
 impl eventsourcing::CommandDetails for TrustAnchorProxyCommandDetails {
@@ -536,6 +661,8 @@ impl eventsourcing::Aggregate for TrustAnchorProxy {
             signer: None,
             child_details: HashMap::new(),
             open_signer_request: None,
+            planned_key_roll: None,
+            removed_children: HashMap::new(),
         }
     }
 
@@ -609,8 +736,27 @@ impl eventsourcing::Aggregate for TrustAnchorProxy {
                 }
                 // We cannot have an accepted response if we did not have a
                 // signer
-                self.signer.as_mut().unwrap().objects = content.objects;
+                let signer = self.signer.as_mut().unwrap();
+                signer.objects = content.objects;
+                match content.key_roll {
+                    None => signer.staged_key = None,
+                    Some(TaKeyRollResponse::Staged(staged)) => {
+                        signer.staged_key = Some(staged)
+                    }
+                    Some(TaKeyRollResponse::Activated(details)) => {
+                        signer.ta_cert_details = details;
+                        signer.staged_key = None;
+                    }
+                }
                 self.open_signer_request = None;
+                self.planned_key_roll = None;
+
+                // Removals are not accepted while there is an open request,
+                // so all pending revocations were included in it.
+                self.removed_children.clear();
+            }
+            TrustAnchorProxyEvent::KeyRollPlanned(key_roll) => {
+                self.planned_key_roll = Some(key_roll)
             }
 
             // Children
@@ -636,6 +782,32 @@ impl eventsourcing::Aggregate for TrustAnchorProxy {
                     .open_responses
                     .remove(&key);
             }
+            TrustAnchorProxyEvent::ChildResourcesUpdated(
+                child_handle,
+                resources,
+            ) => {
+                self.child_details
+                    .get_mut(&child_handle)
+                    .unwrap() // safe - we can only have an event for this child if it
+                    // exists
+                    .resources = resources;
+            }
+            TrustAnchorProxyEvent::ChildRemoved(child_handle) => {
+                // safe - we can only have an event for this child if it
+                // exists
+                let child = self.child_details.remove(&child_handle).unwrap();
+
+                let keys: Vec<KeyIdentifier> = child
+                    .used_keys
+                    .into_iter()
+                    .filter(|(_, state)| state != &UsedKeyState::Revoked)
+                    .map(|(key, _)| key)
+                    .collect();
+
+                if !keys.is_empty() {
+                    self.removed_children.insert(child_handle, keys);
+                }
+            }
         }
     }
 
@@ -682,6 +854,9 @@ impl eventsourcing::Aggregate for TrustAnchorProxy {
             TrustAnchorProxyCommandDetails::ProcessSignerResponse(
                 response,
             ) => self.process_signer_response(response),
+            TrustAnchorProxyCommandDetails::PlanKeyRoll(key_roll) => {
+                self.process_plan_key_roll(key_roll)
+            }
 
             // Children
             TrustAnchorProxyCommandDetails::AddChild(child) => {
@@ -695,6 +870,13 @@ impl eventsourcing::Aggregate for TrustAnchorProxy {
                 child_handle,
                 key,
             ) => self.process_give_child_response(child_handle, key),
+            TrustAnchorProxyCommandDetails::UpdateChildResources(
+                child_handle,
+                resources,
+            ) => self.process_update_child_resources(child_handle, resources),
+            TrustAnchorProxyCommandDetails::RemoveChild(child_handle) => {
+                self.process_remove_child(child_handle)
+            }
         }
     }
 }
@@ -753,7 +935,7 @@ impl TrustAnchorProxy {
             response.validate(&signer.id)?;
 
             // We accept the response as is. Since children cannot be
-            // modified, and requests cannot change as long as
+            // updated or removed, and requests cannot change as long as
             // there is an open signer request we cannot have any
             // mismatches between the children and child requests in the proxy
             // vs the children and responses received from the
@@ -778,6 +960,40 @@ impl TrustAnchorProxy {
         }
     }
 
+    fn process_plan_key_roll(
+        &self,
+        key_roll: TaKeyRollRequest,
+    ) -> KrillResult<Vec<TrustAnchorProxyEvent>> {
+        // The key roll step is included in the signer request when it is
+        // made. So, it cannot be planned if there is an open request, as it
+        // would then be silently skipped.
+        if self.open_signer_request.is_some() {
+            return Err(Error::TaProxyHasRequest);
+        }
+
+        let signer = self.signer.as_ref().ok_or(Error::TaProxyHasNoSigner)?;
+
+        match &key_roll {
+            TaKeyRollRequest::Initiate { tal_https, .. } => {
+                if signer.staged_key.is_some() {
+                    return Err(Error::TaKeyRollInProgress);
+                }
+                if tal_https.is_empty() {
+                    return Err(Error::custom(
+                        "At least one HTTPS URI is needed for the new TAL",
+                    ));
+                }
+            }
+            TaKeyRollRequest::Activate => {
+                if signer.staged_key.is_none() {
+                    return Err(Error::TaKeyRollNotStaged);
+                }
+            }
+        }
+
+        Ok(vec![TrustAnchorProxyEvent::KeyRollPlanned(key_roll)])
+    }
+
     fn process_add_child(
         &self,
         child: AddChildRequest,
@@ -787,6 +1003,11 @@ impl TrustAnchorProxy {
                 self.handle.clone(),
                 child.handle().clone(),
             ))
+        } else if self.removed_children.contains_key(child.handle()) {
+            Err(Error::Custom(format!(
+                "TA child {} was removed, but its certificate is not yet revoked. Complete a signer exchange first.",
+                child.handle()
+            )))
         } else {
             let (handle, resources, id_cert) = child.unpack();
             Ok(vec![TrustAnchorProxyEvent::ChildAdded(
@@ -872,6 +1093,54 @@ impl TrustAnchorProxy {
             )))
         }
     }
+
+    fn process_update_child_resources(
+        &self,
+        child_handle: ChildHandle,
+        resources: ResourceSet,
+    ) -> KrillResult<Vec<TrustAnchorProxyEvent>> {
+        // The resources of a child are included in the signer request, so
+        // they cannot change while there is an open request.
+        if self.open_signer_request.is_some() {
+            return Err(Error::TaProxyHasRequest);
+        }
+
+        let child = self.get_child_details(&child_handle)?;
+
+        if child.resources == resources {
+            debug!(
+                "TA update child '{}' resources has no effect, child already holds these resources",
+                child_handle
+            );
+            Ok(vec![])
+        } else {
+            info!(
+                "TA update child '{}' resources to: {}",
+                child_handle, resources
+            );
+            Ok(vec![TrustAnchorProxyEvent::ChildResourcesUpdated(
+                child_handle,
+                resources,
+            )])
+        }
+    }
+
+    fn process_remove_child(
+        &self,
+        child_handle: ChildHandle,
+    ) -> KrillResult<Vec<TrustAnchorProxyEvent>> {
+        // The certificates of the removed child are revoked in the next
+        // signer request. So, we cannot accept this while there is an open
+        // request as it would then be silently skipped.
+        if self.open_signer_request.is_some() {
+            return Err(Error::TaProxyHasRequest);
+        }
+
+        self.get_child_details(&child_handle)?;
+
+        info!("TA removed child '{}'", child_handle);
+        Ok(vec![TrustAnchorProxyEvent::ChildRemoved(child_handle)])
+    }
 }
 
 impl TrustAnchorProxy {
@@ -896,9 +1165,30 @@ impl TrustAnchorProxy {
                 }
             }
 
+            // Ask the signer to revoke all certificates of removed children.
+            for (child, keys) in &self.removed_children {
+                let requests = keys
+                    .iter()
+                    .map(|key| {
+                        let revocation = provisioning::RevocationRequest::new(
+                            ta_resource_class_name(),
+                            *key,
+                        );
+                        (*key, ProvisioningRequest::Revocation(revocation))
+                    })
+                    .collect();
+
+                child_requests.push(TrustAnchorChildRequests {
+                    child: child.clone(),
+                    resources: ResourceSet::empty(),
+                    requests,
+                });
+            }
+
             TrustAnchorSignerRequest {
                 nonce,
                 child_requests,
+                key_roll: self.planned_key_roll.clone(),
             }
             .sign(
                 self.id.public_key().key_identifier(),
@@ -926,6 +1216,42 @@ impl TrustAnchorProxy {
             .map(|signer| &signer.objects)
     }
 
+    /// Returns all objects to publish. This includes the objects for the
+    /// staged key in case of a key roll.
+    pub fn get_publish_elements(&self) -> KrillResult<Vec<PublishElement>> {
+        let signer = self.signer.as_ref().ok_or(Error::TaNotInitialized)?;
+
+        let mut elements = signer.objects.publish_elements()?;
+        if let Some(staged) = &signer.staged_key {
+            elements.append(&mut staged.objects.publish_elements()?);
+
+            // Relying parties using the new TAL must be able to fetch the
+            // staged certificate before the key is activated. We can only
+            // publish it if the TAL uses our repository, otherwise it is up
+            // to the operator to publish it.
+            let cert_uri = staged.ta_cert_details.tal().rsync_uri();
+            let in_repo = self.repository.as_ref().is_some_and(|repo| {
+                repo.repo_info().base_uri().is_parent_of(cert_uri)
+            });
+            if in_repo {
+                let cert = staged.ta_cert_details.cert();
+                elements.push(PublishElement::new(
+                    Base64::from_content(&cert.to_bytes()),
+                    cert_uri.clone(),
+                ));
+            }
+        }
+        Ok(elements)
+    }
+
+    pub fn get_key_roll_status(&self) -> KrillResult<TaKeyRollStatus> {
+        let signer = self.signer.as_ref().ok_or(Error::TaNotInitialized)?;
+        Ok(TaKeyRollStatus {
+            planned: self.planned_key_roll.clone(),
+            staged_key: signer.staged_key.clone(),
+        })
+    }
+
     pub fn id(&self) -> &IdCertInfo {
         &self.id
     }
@@ -934,6 +1260,10 @@ impl TrustAnchorProxy {
         self.repository.as_ref()
     }
 
+    pub fn children(&self) -> impl Iterator<Item = &ChildHandle> {
+        self.child_details.keys()
+    }
+
     pub fn get_child(
         &self,
         child: &ChildHandle,
@@ -62,6 +62,10 @@ pub struct TrustAnchorSigner {
     // Objects to be published under the TA certificate
     objects: TrustAnchorObjects,
 
+    // New key staged for a planned key roll, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    staged_key: Option<TaStagedKey>,
+
     // Proxy Signer Exchanges
     exchanges: TrustAnchorProxySignerExchanges,
 }
@@ -274,6 +278,7 @@ impl eventsourcing::Aggregate for TrustAnchorSigner {
             proxy_id: event.proxy_id,
             ta_cert_details: event.ta_cert_details,
             objects: event.objects,
+            staged_key: None,
             exchanges: TrustAnchorProxySignerExchanges::default(),
         }
     }
@@ -331,7 +336,18 @@ impl eventsourcing::Aggregate for TrustAnchorSigner {
 
         match event {
             TrustAnchorSignerEvent::ProxySignerExchangeDone(exchange) => {
-                self.objects = exchange.response.content().objects.clone();
+                let content = exchange.response.content();
+                self.objects = content.objects.clone();
+                match &content.key_roll {
+                    None => self.staged_key = None,
+                    Some(TaKeyRollResponse::Staged(staged)) => {
+                        self.staged_key = Some(staged.clone())
+                    }
+                    Some(TaKeyRollResponse::Activated(details)) => {
+                        self.ta_cert_details = details.clone();
+                        self.staged_key = None;
+                    }
+                }
                 self.exchanges.0.push(exchange);
             }
         }
@@ -372,12 +388,17 @@ impl TrustAnchorSigner {
             id: self.id.clone(),
             objects: self.objects.clone(),
             ta_cert_details: self.ta_cert_details.clone(),
+            staged_key: self.staged_key.clone(),
         }
     }
 
     pub fn get_associated_proxy_id(&self) -> &IdCertInfo {
         &self.proxy_id
     }
+
+    pub fn get_staged_key(&self) -> Option<&TaStagedKey> {
+        self.staged_key.as_ref()
+    }
 }
 
 impl TrustAnchorSigner {
@@ -453,6 +474,51 @@ impl TrustAnchorSigner {
         Ok(TaCertDetails::new(rcvd_cert, tal))
     }
 
+    /// Creates a new key, with its own TA certificate and TAL, to be staged
+    /// for a key roll. The new certificate uses the same repository as the
+    /// current certificate.
+    fn create_staged_key(
+        &self,
+        tal_https: Vec<uri::Https>,
+        tal_rsync: uri::Rsync,
+        ta_timing_config: TaTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<TaStagedKey> {
+        let current_tal = self.ta_cert_details.tal();
+        if tal_rsync == *current_tal.rsync_uri()
+            || tal_https.iter().any(|uri| current_tal.uris().contains(uri))
+        {
+            return Err(Error::custom(
+                "TAL URIs for the new TA key must differ from the current TAL URIs",
+            ));
+        }
+
+        let (ca_repository, _, rpki_notify, _) =
+            self.ta_cert_details.cert().csr_info().clone().unpack();
+        let repo_info = RepoInfo::new(ca_repository, rpki_notify);
+
+        let ta_cert_details = Self::create_ta_cert_details(
+            repo_info,
+            tal_https,
+            tal_rsync,
+            None,
+            ta_timing_config.certificate_validity_years,
+            signer,
+        )?;
+
+        let objects = TrustAnchorObjects::create(
+            ta_cert_details.cert(),
+            1,
+            ta_timing_config.mft_next_update_weeks,
+            signer,
+        )?;
+
+        Ok(TaStagedKey {
+            ta_cert_details,
+            objects,
+        })
+    }
+
     /// Process a request.
     fn process_signer_request(
         &self,
@@ -465,14 +531,66 @@ impl TrustAnchorSigner {
         // and the 'content' is not tampered with.
         signed_request.validate(&self.proxy_id)?;
 
+        let mut ta_cert_details = self.ta_cert_details.clone();
         let mut objects = self.objects.clone();
+        let mut staged_key = self.staged_key.clone();
+        let mut activated = false;
+
+        // Perform any key roll step first, so that child requests in this
+        // same request are signed under the new key if it is activated.
+        match &signed_request.content().key_roll {
+            None => {}
+            Some(TaKeyRollRequest::Initiate {
+                tal_https,
+                tal_rsync,
+            }) => {
+                if staged_key.is_some() {
+                    return Err(Error::TaKeyRollInProgress);
+                }
+                staged_key = Some(self.create_staged_key(
+                    tal_https.clone(),
+                    tal_rsync.clone(),
+                    ta_timing_config,
+                    signer,
+                )?);
+            }
+            Some(TaKeyRollRequest::Activate) => {
+                let staged =
+                    staged_key.take().ok_or(Error::TaKeyRollNotStaged)?;
+
+                let mut new_objects = staged.objects;
+                let new_signing_cert = staged.ta_cert_details.cert();
+                let validity = SignSupport::sign_validity_weeks(
+                    ta_timing_config.issued_certificate_validity_weeks,
+                );
+
+                // Re-issue all current child certificates under the new
+                // key. They will be published under the same names, so
+                // the old certificates are replaced.
+                for issued in objects.issued() {
+                    let reissued = SignSupport::make_issued_cert(
+                        issued.csr_info().clone(),
+                        issued.resources(),
+                        issued.limit().clone(),
+                        new_signing_cert,
+                        validity,
+                        signer,
+                    )?;
+                    new_objects.add_issued(reissued);
+                }
+
+                ta_cert_details = staged.ta_cert_details;
+                objects = new_objects;
+                activated = true;
+            }
+        }
 
         let mut child_responses: HashMap<
             ChildHandle,
             HashMap<KeyIdentifier, ProvisioningResponse>,
         > = HashMap::new();
 
-        let signing_cert = self.ta_cert_details.cert();
+        let signing_cert = ta_cert_details.cert();
         let ta_rcn = ta_resource_class_name();
 
         for child_request in &signed_request.content().child_requests {
@@ -583,9 +701,24 @@ impl TrustAnchorSigner {
             signer,
         )?;
 
+        let key_roll = if activated {
+            Some(TaKeyRollResponse::Activated(ta_cert_details))
+        } else if let Some(mut staged) = staged_key {
+            staged.objects.republish(
+                staged.ta_cert_details.cert(),
+                ta_timing_config.mft_next_update_weeks,
+                None,
+                signer,
+            )?;
+            Some(TaKeyRollResponse::Staged(staged))
+        } else {
+            None
+        };
+
         let response = TrustAnchorSignerResponse {
             nonce: signed_request.content().nonce.clone(),
             objects,
+            key_roll,
             child_responses,
         }
         .sign(
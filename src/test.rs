@@ -23,30 +23,40 @@ use rpki::{
     repository::resources::ResourceSet,
     uri,
 };
-use tokio::time::{sleep, timeout};
+use tokio::{
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
 use url::Url;
 
 use crate::{
     cli::{
         options::{
-            BulkCaCommand, CaCommand, Command, Options, PubServerCommand,
+            BulkCaCommand, CaCommand, Command, HistoryOptions, Options,
+            PubServerCommand, TokenCommand,
         },
         report::{ApiResponse, ReportFormat},
         {Error, KrillClient},
     },
     commons::{
         api::{
-            self, AddChildRequest, AspaDefinition, AspaDefinitionList,
+            self, AddChildRequest, ApiTokenIssued, ApiTokenList,
+            ApiTokenRequest, AspaDefinition, AspaDefinitionList,
             AspaProvidersUpdate, BgpSecAsnKey, BgpSecCsrInfoList,
             BgpSecDefinition, CertAuthInfo, CertAuthInit, CertifiedKeyInfo,
-            ConfiguredRoa, ConfiguredRoas, CustomerAsn, ObjectName,
-            ParentCaContact, ParentCaReq, ParentStatuses,
-            PublicationServerUris, PublisherDetails, PublisherList,
-            ResourceClassKeysInfo, RoaConfiguration, RoaConfigurationUpdates,
-            RoaPayload, RtaList, RtaName, RtaPrepResponse, TypedPrefix,
-            UpdateChildRequest,
+            ChangeRequestList, CommandHistory, ConfiguredRoa, ConfiguredRoas,
+            CustomerAsn, ObjectName, ParentCaContact, ParentCaReq,
+            ParentStatuses, PublicationServerUris, PublisherDetails,
+            PublisherList, ResourceClassKeysInfo, RoaConfiguration,
+            RoaConfigurationUpdates, RoaFileFormat, RoaImport,
+            RoaImportResult, RoaPayload, RtaList, RtaName, RtaPrepResponse,
+            ScheduleRoaUpdatesRequest, ScheduledRoaUpdatesList, Timestamp,
+            Token, TypedPrefix, UpdateChildRequest,
+        },
+        bgp::{
+            Announcement, AsPaths, AspaAnalysisReport, BgpAnalysisReport,
+            BgpAnalysisSuggestion,
         },
-        bgp::{Announcement, BgpAnalysisReport, BgpAnalysisSuggestion},
         crypto::SignSupport,
         util::httpclient,
     },
@@ -241,6 +251,19 @@ pub async fn start_krill(mut config: Config) {
     assert!(krill_server_ready().await);
 }
 
+/// Starts a krill server using the given configuration and waits until
+/// it is ready at the given URI. Returns the handle of the server task so
+/// that it can be stopped again, e.g. to let a standby node take over.
+pub async fn start_krill_node(
+    mut config: Config,
+    uri: &str,
+) -> JoinHandle<()> {
+    init_config(&mut config);
+    let handle = tokio::spawn(start_krill_with_error_trap(Arc::new(config)));
+    assert!(server_ready(uri).await);
+    handle
+}
+
 async fn start_krill_with_error_trap(config: Arc<Config>) {
     if let Err(err) = server::start_krill_daemon(config).await {
         error!("Krill failed to start: {}", err);
@@ -330,6 +353,12 @@ pub async fn krill2_admin(command: Command) -> ApiResponse {
     admin(service_uri(KRILL_SECOND_SERVER_URI), command).await
 }
 
+pub async fn krill2_admin_may_fail(
+    command: Command,
+) -> Result<ApiResponse, Error> {
+    admin_may_fail(service_uri(KRILL_SECOND_SERVER_URI), command).await
+}
+
 pub async fn krill_embedded_pubd_admin(
     command: PubServerCommand,
 ) -> ApiResponse {
@@ -369,6 +398,40 @@ pub async fn krill_admin_expect_error(command: Command) -> Error {
     }
 }
 
+/// Sends a command to Krill using the given (API) token, rather than the
+/// admin token.
+pub async fn krill_with_token_may_fail(
+    token: &Token,
+    command: Command,
+) -> Result<ApiResponse, Error> {
+    let options = Options::new(
+        service_uri(KRILL_SERVER_URI),
+        token.as_ref(),
+        ReportFormat::Json,
+        command,
+    );
+    KrillClient::process(options).await
+}
+
+pub async fn api_token_issue(request: ApiTokenRequest) -> ApiTokenIssued {
+    match krill_admin(Command::Tokens(TokenCommand::Add(request))).await {
+        ApiResponse::ApiTokenIssued(issued) => issued,
+        _ => panic!("Expected issued API token"),
+    }
+}
+
+pub async fn api_tokens_list() -> ApiTokenList {
+    match krill_admin(Command::Tokens(TokenCommand::List)).await {
+        ApiResponse::ApiTokens(list) => list,
+        _ => panic!("Expected API tokens"),
+    }
+}
+
+pub async fn api_token_revoke(name: &str) {
+    krill_admin(Command::Tokens(TokenCommand::Revoke(name.to_string())))
+        .await;
+}
+
 pub async fn cas_force_publish_all() {
     krill_admin(Command::Bulk(BulkCaCommand::ForcePublish)).await;
 }
@@ -631,6 +694,117 @@ pub async fn ca_route_authorizations_update_expect_error(
     .await;
 }
 
+pub async fn ca_route_authorizations_schedule(
+    ca: &CaHandle,
+    not_before: Timestamp,
+    updates: RoaConfigurationUpdates,
+) {
+    krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsSchedule(
+        ca.clone(),
+        ScheduleRoaUpdatesRequest::new(not_before, updates),
+    )))
+    .await;
+}
+
+pub async fn ca_route_authorizations_scheduled(
+    ca: &CaHandle,
+) -> ScheduledRoaUpdatesList {
+    match krill_admin(Command::CertAuth(
+        CaCommand::RouteAuthorizationsScheduledList(ca.clone()),
+    ))
+    .await
+    {
+        ApiResponse::RouteAuthorizationsScheduled(scheduled) => scheduled,
+        _ => panic!("Expected scheduled ROA updates"),
+    }
+}
+
+pub async fn ca_route_authorizations_schedule_cancel(ca: &CaHandle, id: u64) {
+    krill_admin(Command::CertAuth(
+        CaCommand::RouteAuthorizationsScheduleCancel(ca.clone(), id),
+    ))
+    .await;
+}
+
+pub async fn ca_route_authorizations_schedule_cancel_expect_error(
+    ca: &CaHandle,
+    id: u64,
+) {
+    krill_admin_expect_error(Command::CertAuth(
+        CaCommand::RouteAuthorizationsScheduleCancel(ca.clone(), id),
+    ))
+    .await;
+}
+
+pub async fn ca_route_authorizations_export(
+    ca: &CaHandle,
+    format: RoaFileFormat,
+) -> String {
+    match krill_admin(Command::CertAuth(
+        CaCommand::RouteAuthorizationsExport(ca.clone(), format),
+    ))
+    .await
+    {
+        ApiResponse::GenericBody(body) => body,
+        _ => panic!("Expected exported ROAs"),
+    }
+}
+
+pub async fn ca_route_authorizations_import(
+    ca: &CaHandle,
+    import: RoaImport,
+) -> RoaImportResult {
+    match krill_admin(Command::CertAuth(
+        CaCommand::RouteAuthorizationsImport(ca.clone(), import),
+    ))
+    .await
+    {
+        ApiResponse::RoaImport(result) => result,
+        _ => panic!("Expected ROA import result"),
+    }
+}
+
+pub async fn ca_change_requests(ca: &CaHandle) -> ChangeRequestList {
+    match krill_admin(Command::CertAuth(CaCommand::ChangeRequestsList(
+        ca.clone(),
+    )))
+    .await
+    {
+        ApiResponse::ChangeRequests(requests) => requests,
+        _ => panic!("Expected change requests"),
+    }
+}
+
+pub async fn ca_change_request_approve_expect_error(
+    ca: &CaHandle,
+    id: u64,
+) -> Error {
+    krill_admin_expect_error(Command::CertAuth(
+        CaCommand::ChangeRequestApprove(ca.clone(), id),
+    ))
+    .await
+}
+
+pub async fn ca_change_request_reject(ca: &CaHandle, id: u64) {
+    krill_admin(Command::CertAuth(CaCommand::ChangeRequestReject(
+        ca.clone(),
+        id,
+    )))
+    .await;
+}
+
+pub async fn ca_history(ca: &CaHandle) -> CommandHistory {
+    match krill_admin(Command::CertAuth(CaCommand::ShowHistoryCommands(
+        ca.clone(),
+        HistoryOptions::default(),
+    )))
+    .await
+    {
+        ApiResponse::CertAuthHistory(history) => history,
+        _ => panic!("Expected CA history"),
+    }
+}
+
 pub async fn ca_configured_roas(ca: &CaHandle) -> ConfiguredRoas {
     match krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsList(
         ca.clone(),
@@ -796,6 +970,22 @@ pub async fn expect_aspa_definitions(
     }
 }
 
+pub async fn ca_aspas_analysis(
+    ca: &CaHandle,
+    paths: AsPaths,
+) -> AspaAnalysisReport {
+    let res = krill_admin(Command::CertAuth(CaCommand::AspasAnalyze(
+        ca.clone(),
+        paths,
+    )))
+    .await;
+
+    match res {
+        ApiResponse::AspaAnalysis(report) => report,
+        _ => panic!("Expected ASPA analysis report"),
+    }
+}
+
 pub async fn ca_aspas_update(
     ca: &CaHandle,
     customer: CustomerAsn,
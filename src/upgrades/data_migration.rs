@@ -243,10 +243,12 @@ fn copy_data_for_migration(
     target_storage: &Url,
 ) -> UpgradeResult<()> {
     for ns in &[
+        "api_tokens",
         "ca_objects",
         "cas",
         "keys",
         "pubd",
+        "properties",
         "pubd_objects",
         "signers",
         "status",
@@ -264,6 +266,13 @@ fn copy_data_for_migration(
         if !source_kv_store.is_empty()? {
             let target_kv_store =
                 KeyValueStore::create(target_storage, namespace)?;
+            if !target_kv_store.is_empty()? {
+                return Err(UpgradeError::Custom(format!(
+                    "Target storage already contains data for '{}'",
+                    ns
+                )));
+            }
+            info!("Copy data for '{}'", ns);
             target_kv_store.import(&source_kv_store)?;
         }
     }
@@ -0,0 +1,152 @@
+//! Test that named API tokens are limited to the permissions and CAs they
+//! were issued for, and that they can be revoked.
+#![cfg(feature = "multi-user")]
+
+use krill::{
+    cli::{
+        options::{CaCommand, Command, TokenCommand},
+        report::ApiResponse,
+    },
+    commons::api::{ApiTokenRequest, RoaConfigurationUpdates, Timestamp},
+    test::*,
+};
+
+#[tokio::test]
+async fn functional_api_tokens() {
+    let cleanup =
+        start_krill_with_default_test_config(false, false, false, false)
+            .await;
+
+    let ca1 = ca_handle("ca1");
+    let ca2 = ca_handle("ca2");
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Issue a token for CA_READ and CA_UPDATE on ca1                 #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    let issued = {
+        init_ca(&ca1).await;
+        init_ca(&ca2).await;
+
+        let request = ApiTokenRequest::new(
+            "ci".to_string(),
+            vec!["CA_READ".to_string(), "CA_UPDATE".to_string()],
+            vec![ca1.clone()],
+            Timestamp::now_plus_hours(1),
+        );
+        let issued = api_token_issue(request.clone()).await;
+
+        let list = api_tokens_list().await;
+        assert_eq!(list.tokens().len(), 1);
+        assert_eq!(list.tokens()[0].name(), "ci");
+
+        // The name of a token must be unique
+        let err = krill_admin_expect_error(Command::Tokens(
+            TokenCommand::Add(request),
+        ))
+        .await;
+        assert!(err.to_string().contains("Duplicate API token"));
+
+        issued
+    };
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# The token can only be used for its permissions and CAs         #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let token = issued.token();
+
+        let res = krill_with_token_may_fail(
+            token,
+            Command::CertAuth(CaCommand::Show(ca1.clone())),
+        )
+        .await;
+        assert!(matches!(res, Ok(ApiResponse::CertAuthInfo(_))));
+
+        krill_with_token_may_fail(
+            token,
+            Command::CertAuth(CaCommand::UpdateId(ca1.clone())),
+        )
+        .await
+        .unwrap();
+
+        // The update is attributed to the token
+        let history = ca_history(&ca1).await;
+        let last = history.commands().last().unwrap();
+        assert_eq!(last.actor, "token:ci");
+
+        // Other CAs are off-limits
+        assert!(krill_with_token_may_fail(
+            token,
+            Command::CertAuth(CaCommand::Show(ca2.clone())),
+        )
+        .await
+        .is_err());
+
+        // And so are other permissions
+        assert!(krill_with_token_may_fail(
+            token,
+            Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
+                ca1.clone(),
+                RoaConfigurationUpdates::empty(),
+            )),
+        )
+        .await
+        .is_err());
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Revoked tokens are rejected                                    #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        api_token_revoke("ci").await;
+        assert!(api_tokens_list().await.tokens().is_empty());
+
+        assert!(krill_with_token_may_fail(
+            issued.token(),
+            Command::CertAuth(CaCommand::Show(ca1.clone())),
+        )
+        .await
+        .is_err());
+    }
+
+    cleanup();
+}
@@ -7,9 +7,12 @@ use rpki::{
 };
 
 use krill::{
-    commons::api::{
-        AspaDefinition, AspaDefinitionList, AspaProvidersUpdate, CustomerAsn,
-        ObjectName, ProviderAsn,
+    commons::{
+        api::{
+            AspaDefinition, AspaDefinitionList, AspaProvidersUpdate,
+            CustomerAsn, ObjectName, ProviderAsn,
+        },
+        bgp::AsPaths,
     },
     test::*,
 };
@@ -322,6 +325,40 @@ async fn functional_aspa() {
         expect_aspa_definitions(&ca, AspaDefinitionList::new(aspas)).await;
     }
 
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Analyse ASPAs against observed AS paths                        #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+
+        let paths = AsPaths::parse(
+            "64496 65002 65000\n64496 65004 65000 65000\n64496 65005\n",
+        )
+        .unwrap();
+        let report = ca_aspas_analysis(&ca, paths).await;
+        assert_eq!(report.paths(), 3);
+        assert!(report.contains_invalids());
+
+        let entry = &report.entries()[0];
+        assert_eq!(
+            entry.customer(),
+            CustomerAsn::from_str("AS65000").unwrap()
+        );
+        assert_eq!(
+            entry.suggestion().added(),
+            &vec![ProviderAsn::from_str("AS65004").unwrap()]
+        );
+        assert_eq!(
+            entry.unseen_providers(),
+            &vec![
+                ProviderAsn::from_str("AS65003").unwrap(),
+                ProviderAsn::from_str("AS65005").unwrap()
+            ]
+        );
+    }
+
     {
         info("##################################################################");
         info("#                                                                #");
@@ -0,0 +1,308 @@
+//! Test that route and ASPA updates, including ROA imports, are kept as
+//! pending change requests if approval is required, and that they cannot be
+//! approved by the user who requested them.
+use std::str::FromStr;
+
+use krill::{
+    cli::{
+        options::{CaCommand, Command},
+        report::ApiResponse,
+        Error,
+    },
+    commons::{
+        api::{
+            AspaDefinition, AspaDefinitionList, AspaProvidersUpdate,
+            ChangeRequest, ChangeRequestUpdates, CustomerAsn, ProviderAsn,
+            RoaConfigurationUpdates, RoaFileFormat, RoaImport, RoaImportMode,
+            RoaImportStatus, Timestamp,
+        },
+        util::httpclient,
+    },
+    test::*,
+};
+
+fn expect_change_request(res: ApiResponse) -> ChangeRequest {
+    match res {
+        ApiResponse::ChangeRequest(request) => request,
+        _ => panic!("Expected change request"),
+    }
+}
+
+fn expect_label(err: Error, label: &str) {
+    match err {
+        Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(
+            _,
+            _,
+            res,
+        )) => assert_eq!(res.label(), label),
+        _ => panic!("Expected error response with label: {}", label),
+    }
+}
+
+#[tokio::test]
+async fn functional_change_requests() {
+    let (data_dir, cleanup) = tmp_dir();
+    let storage_uri = mem_storage();
+    let mut config =
+        test_config(&storage_uri, Some(&data_dir), true, false, false, false);
+    config.change_approval_required = true;
+    start_krill(config).await;
+
+    let testbed = ca_handle("testbed");
+    let ca = ca_handle("CA");
+    let ca_res = resources("AS65000", "10.0.0.0/16", "");
+
+    let roa = roa_configuration("10.0.0.0/24-24 => 64496");
+    let scheduled_roa = roa_configuration("10.0.1.0/24-24 => 64496");
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Set up CA under testbed                                        #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        set_up_ca_with_repo(&ca).await;
+        set_up_ca_under_parent_with_resources(&ca, &testbed, &ca_res).await;
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Route, scheduled route and ASPA updates become pending change  #",
+    );
+    info(
+        "# requests                                                       #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        // The submitted change requests are returned, so that they can
+        // be approved or rejected.
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(roa);
+        let routes_request = expect_change_request(
+            krill_admin(Command::CertAuth(
+                CaCommand::RouteAuthorizationsUpdate(ca.clone(), updates),
+            ))
+            .await,
+        );
+
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(scheduled_roa);
+        ca_route_authorizations_schedule(
+            &ca,
+            Timestamp::now().plus_seconds(24 * 3600),
+            updates,
+        )
+        .await;
+
+        let aspa = AspaDefinition::from_str("AS65000 => AS65001").unwrap();
+        let aspas_request = expect_change_request(
+            krill_admin(Command::CertAuth(CaCommand::AspasAddOrReplace(
+                ca.clone(),
+                aspa,
+            )))
+            .await,
+        );
+
+        ca_aspas_update(
+            &ca,
+            CustomerAsn::from_str("AS65000").unwrap(),
+            AspaProvidersUpdate::new(
+                vec![ProviderAsn::from_str("AS65002").unwrap()],
+                vec![],
+            ),
+        )
+        .await;
+
+        let requests = ca_change_requests(&ca).await;
+        assert_eq!(requests.iter().count(), 4);
+
+        let mut requests = requests.iter();
+        let request = requests.next().unwrap();
+        assert_eq!(request, &routes_request);
+        assert!(matches!(request.updates(), ChangeRequestUpdates::Routes(_)));
+        assert!(matches!(
+            requests.next().unwrap().updates(),
+            ChangeRequestUpdates::ScheduledRoutes(_)
+        ));
+        let request = requests.next().unwrap();
+        assert_eq!(request, &aspas_request);
+        assert!(matches!(request.updates(), ChangeRequestUpdates::Aspas(_)));
+        assert!(matches!(
+            requests.next().unwrap().updates(),
+            ChangeRequestUpdates::AspaProviders { .. }
+        ));
+
+        expect_configured_roas(&ca, &[]).await;
+        assert!(ca_route_authorizations_scheduled(&ca).await.is_empty());
+        expect_aspa_definitions(&ca, AspaDefinitionList::new(vec![])).await;
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Updates which would be rejected are not stored                 #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(roa_configuration("192.168.0.0/24 => 64496"));
+        ca_route_authorizations_update_expect_error(&ca, updates).await;
+        assert_eq!(ca_change_requests(&ca).await.iter().count(), 4);
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# A ROA import is submitted for approval as well                 #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let configurations = RoaFileFormat::Csv
+            .parse("AS64496,10.0.2.0/24,24,\n")
+            .unwrap();
+        let import = RoaImport::new(
+            configurations,
+            RoaImportMode::Merge,
+            false,
+            false,
+        );
+        let result = ca_route_authorizations_import(&ca, import).await;
+        assert_eq!(result.status(), RoaImportStatus::Submitted);
+
+        let requests = ca_change_requests(&ca).await;
+        assert_eq!(requests.iter().count(), 5);
+        let request = requests.iter().last().unwrap();
+        assert_eq!(result.change_request(), Some(request.id()));
+        assert_eq!(
+            request.updates(),
+            &ChangeRequestUpdates::Routes(result.updates().clone())
+        );
+        expect_configured_roas(&ca, &[]).await;
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Requests cannot be approved by the same user                   #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let id = ca_change_requests(&ca).await.iter().next().unwrap().id();
+
+        let err = ca_change_request_approve_expect_error(&ca, id).await;
+        expect_label(err, "ca-change-request-self-approval");
+
+        let err = ca_change_request_approve_expect_error(&ca, 0).await;
+        expect_label(err, "ca-change-request-unknown");
+
+        assert_eq!(ca_change_requests(&ca).await.iter().count(), 5);
+        expect_configured_roas(&ca, &[]).await;
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Rejected requests are removed, and recorded in the history     #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let ids: Vec<u64> = ca_change_requests(&ca)
+            .await
+            .iter()
+            .map(|request| request.id())
+            .collect();
+
+        for id in ids {
+            ca_change_request_reject(&ca, id).await;
+        }
+
+        assert!(ca_change_requests(&ca).await.is_empty());
+        expect_configured_roas(&ca, &[]).await;
+
+        let labels: Vec<String> = ca_history(&ca)
+            .await
+            .commands()
+            .iter()
+            .map(|record| record.summary.label.clone())
+            .collect();
+        // Note that the rejected submission is also in the history.
+        assert_eq!(
+            labels
+                .iter()
+                .filter(|l| **l == "cmd-ca-change-request-submit")
+                .count(),
+            6
+        );
+        assert_eq!(
+            labels
+                .iter()
+                .filter(|l| **l == "cmd-ca-change-request-reject")
+                .count(),
+            5
+        );
+    }
+
+    cleanup();
+}
@@ -0,0 +1,159 @@
+//! Test that API clients can authenticate using TLS client certificates,
+//! and that these are mapped to actors with the configured attributes.
+#![cfg(feature = "multi-user")]
+
+use std::{collections::HashMap, path::PathBuf};
+
+use krill::{
+    daemon::auth::client_certs::{ConfigAuthClientCerts, ConfigClientCert},
+    test::*,
+};
+
+const CERTS_DIR: &str = "test-resources/client-certs";
+
+fn client(name: &str) -> reqwest::Client {
+    let cert = std::fs::read(format!("{}/{}.pem", CERTS_DIR, name)).unwrap();
+    let key = std::fs::read(format!("{}/{}.key", CERTS_DIR, name)).unwrap();
+    let identity = reqwest::Identity::from_pkcs8_pem(&cert, &key).unwrap();
+
+    reqwest::Client::builder()
+        .identity(identity)
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap()
+}
+
+/// Sends a request using the certificate of the given client, and returns
+/// the HTTP status code, or None if the TLS handshake failed.
+async fn status(
+    name: &str,
+    method: reqwest::Method,
+    path: &str,
+) -> Option<u16> {
+    let uri = format!("https://localhost:3000/api/v1/{}", path);
+    client(name)
+        .request(method, uri)
+        .send()
+        .await
+        .ok()
+        .map(|res| res.status().as_u16())
+}
+
+#[tokio::test]
+async fn functional_client_certs() {
+    let (data_dir, cleanup) = tmp_dir();
+    let storage_uri = mem_storage();
+    let mut config = test_config(
+        &storage_uri,
+        Some(&data_dir),
+        false,
+        false,
+        false,
+        false,
+    );
+    config.auth_client_certs = Some(ConfigAuthClientCerts {
+        ca_certs: PathBuf::from(format!("{}/ca.pem", CERTS_DIR)),
+        clients: vec![ConfigClientCert {
+            name: "puppet".to_string(),
+            subject: Some("CN=puppet,O=Example,C=NL".to_string()),
+            san: None,
+            attributes: HashMap::from([(
+                "role".to_string(),
+                "readwrite".to_string(),
+            )]),
+        }],
+    });
+    start_krill(config).await;
+
+    let ca = ca_handle("ca");
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Clients without a certificate can still use the admin token    #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        init_ca(&ca).await;
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# A known certificate gets the permissions of its role           #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        assert_eq!(
+            status("puppet", reqwest::Method::GET, "cas").await,
+            Some(200)
+        );
+        assert_eq!(
+            status("puppet", reqwest::Method::POST, "cas/ca/id").await,
+            Some(200)
+        );
+
+        // The update is attributed to the certificate
+        let history = ca_history(&ca).await;
+        let last = history.commands().last().unwrap();
+        assert_eq!(last.actor, "cert:puppet");
+
+        // The readwrite role cannot delete CAs
+        assert_eq!(
+            status("puppet", reqwest::Method::DELETE, "cas/ca").await,
+            Some(403)
+        );
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Unknown and untrusted certificates are rejected                #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        // Issued by the configured CA, but not mapped to a client
+        assert_eq!(
+            status("ansible", reqwest::Method::GET, "cas").await,
+            Some(401)
+        );
+
+        // Same subject as the known client, but not issued by the CA
+        assert_ne!(
+            status("rogue", reqwest::Method::GET, "cas").await,
+            Some(200)
+        );
+    }
+
+    cleanup();
+}
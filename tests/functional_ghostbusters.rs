@@ -0,0 +1,250 @@
+//! Perform functional tests for Ghostbuster records (RFC 6493)
+use std::str::FromStr;
+
+use rpki::{
+    ca::idexchange::{CaHandle, PublisherHandle},
+    repository::resources::ResourceSet,
+};
+
+use krill::{
+    cli::{
+        options::{CaCommand, Command, PubServerCommand},
+        report::ApiResponse,
+    },
+    commons::api::{
+        GhostbusterRecord, GhostbusterRecordUpdates, GhostbusterRecords,
+        ObjectName, PublisherObjectType, VCard,
+    },
+    test::*,
+};
+
+const VCARD_NOC: &str = "BEGIN:VCARD\nVERSION:4.0\nFN:NOC\n\
+                         EMAIL:noc@example.com\nEND:VCARD\n";
+
+const VCARD_LIR: &str = "BEGIN:VCARD\nVERSION:4.0\nFN:LIR\nORG:Example\n\
+                         TEL:+31 20 123 4567\nEND:VCARD\n";
+
+async fn ghostbusters_update(
+    ca: &CaHandle,
+    updates: GhostbusterRecordUpdates,
+) {
+    krill_admin(Command::CertAuth(CaCommand::GhostbustersUpdate(
+        ca.clone(),
+        updates,
+    )))
+    .await;
+}
+
+async fn ghostbusters_update_expect_error(
+    ca: &CaHandle,
+    updates: GhostbusterRecordUpdates,
+) {
+    krill_admin_expect_error(Command::CertAuth(
+        CaCommand::GhostbustersUpdate(ca.clone(), updates),
+    ))
+    .await;
+}
+
+async fn expect_ghostbuster_records(ca: &CaHandle, expected: &[&str]) {
+    let res = krill_admin(Command::CertAuth(CaCommand::GhostbustersList(
+        ca.clone(),
+    )))
+    .await;
+
+    if let ApiResponse::GhostbusterRecords(records) = res {
+        let expected = GhostbusterRecords::new(
+            expected
+                .iter()
+                .map(|vcard| {
+                    GhostbusterRecord::new(
+                        None,
+                        VCard::from_str(vcard).unwrap(),
+                    )
+                })
+                .collect(),
+        );
+        assert_eq!(expected, records);
+    } else {
+        panic!("Expected GhostbusterRecords")
+    }
+}
+
+// Expect that the CA publishes its MFT, CRL and optionally a Ghostbuster
+// record in its only resource class.
+async fn expect_ghostbuster_object(ca: &CaHandle, present: bool) {
+    let mut expected_files = expected_mft_and_crl(ca, &rcn(0)).await;
+    if present {
+        expected_files.push(ObjectName::ghostbusters().to_string());
+    }
+
+    assert!(
+        will_publish_embedded(
+            "published Ghostbuster record does not match expectations",
+            ca,
+            &expected_files
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn functional_ghostbusters() {
+    // Let the publication server verify all published objects, so that we
+    // know that the Ghostbuster records pass strict validation.
+    let (data_dir, cleanup) = tmp_dir();
+    let storage_uri = mem_storage();
+    let mut config =
+        test_config(&storage_uri, Some(&data_dir), true, false, false, false);
+    config.publisher_strict_validation = true;
+    start_krill(config).await;
+
+    let testbed = ca_handle("testbed");
+    let ca = ca_handle("CA");
+    let ca_res = resources("AS65000", "10.0.0.0/16", "");
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#                      Set up CA  under testbed                  #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        set_up_ca_with_repo(&ca).await;
+        set_up_ca_under_parent_with_resources(&ca, &testbed, &ca_res).await;
+        expect_ghostbuster_records(&ca, &[]).await;
+        expect_ghostbuster_object(&ca, false).await;
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Add a Ghostbuster record for all resource classes              #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let record =
+            GhostbusterRecord::new(None, VCard::from_str(VCARD_NOC).unwrap());
+        ghostbusters_update(
+            &ca,
+            GhostbusterRecordUpdates::new(vec![record], vec![]),
+        )
+        .await;
+
+        expect_ghostbuster_records(&ca, &[VCARD_NOC]).await;
+        expect_ghostbuster_object(&ca, true).await;
+
+        let publisher = PublisherHandle::from_str("CA").unwrap();
+        let command = PubServerCommand::PublisherObjects(publisher);
+        match krill_embedded_pubd_admin(command).await {
+            ApiResponse::PublisherObjects(objects) => {
+                let gbr = objects
+                    .objects()
+                    .iter()
+                    .find(|object| {
+                        object.object_type()
+                            == PublisherObjectType::Ghostbusters
+                    })
+                    .unwrap();
+                assert!(gbr.expires().is_some());
+            }
+            _ => panic!("Expected publisher objects"),
+        }
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Replace the record, the object is re-issued                    #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let record =
+            GhostbusterRecord::new(None, VCard::from_str(VCARD_LIR).unwrap());
+        ghostbusters_update(
+            &ca,
+            GhostbusterRecordUpdates::new(vec![record], vec![]),
+        )
+        .await;
+
+        expect_ghostbuster_records(&ca, &[VCARD_LIR]).await;
+        expect_ghostbuster_object(&ca, true).await;
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Reject invalid updates                                         #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+
+        // A vCard without contact details, sent as JSON so that it is not
+        // checked by the client.
+        let vcard: VCard = serde_json::from_str(
+            "\"BEGIN:VCARD\\r\\nVERSION:4.0\\r\\nFN:NOC\\r\\nEND:VCARD\\r\\n\"",
+        )
+        .unwrap();
+        let record = GhostbusterRecord::new(None, vcard);
+        ghostbusters_update_expect_error(
+            &ca,
+            GhostbusterRecordUpdates::new(vec![record], vec![]),
+        )
+        .await;
+
+        // A record for a resource class that the CA does not have
+        let record = GhostbusterRecord::new(
+            Some(rcn(1)),
+            VCard::from_str(VCARD_NOC).unwrap(),
+        );
+        ghostbusters_update_expect_error(
+            &ca,
+            GhostbusterRecordUpdates::new(vec![record], vec![]),
+        )
+        .await;
+
+        // Removing a record which does not exist
+        ghostbusters_update_expect_error(
+            &ca,
+            GhostbusterRecordUpdates::new(vec![], vec![Some(rcn(0))]),
+        )
+        .await;
+
+        expect_ghostbuster_records(&ca, &[VCARD_LIR]).await;
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# The record is re-issued under the new key in a key roll        #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        ca_roll_init(&ca).await;
+        assert!(state_becomes_new_key(&ca).await);
+        ca_roll_activate(&ca).await;
+        assert!(state_becomes_active(&ca).await);
+
+        expect_ghostbuster_object(&ca, true).await;
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Remove the record, the object is withdrawn                     #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        ghostbusters_update(
+            &ca,
+            GhostbusterRecordUpdates::new(vec![], vec![None]),
+        )
+        .await;
+
+        expect_ghostbuster_records(&ca, &[]).await;
+        expect_ghostbuster_object(&ca, false).await;
+    }
+
+    cleanup();
+}
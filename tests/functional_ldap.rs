@@ -0,0 +1,357 @@
+//! Test that users can log in using an LDAP directory, and that their role
+//! is determined by the groups they are a member of.
+#![cfg(feature = "multi-user")]
+
+use std::collections::HashMap;
+
+use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
+use base64::engine::Engine as _;
+use bytes::BytesMut;
+use ldap3::asn1::{parse_tag, write, StructureTag, TagClass, PL};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use krill::{
+    cli::options::{CaCommand, Command},
+    commons::{
+        api::{CertAuthInit, Token},
+        util::httpclient,
+    },
+    daemon::{
+        auth::providers::ldap::config::{
+            ConfigAuthLdap, ConfigAuthLdapGroup,
+        },
+        config::AuthType,
+    },
+    test::*,
+};
+
+const PEOPLE: &str = "ou=people,dc=example,dc=org";
+const ADMINS: &str = "cn=krill-admins,ou=groups,dc=example,dc=org";
+const READERS: &str = "cn=krill-readers,ou=groups,dc=example,dc=org";
+
+//------------ Mock LDAP server ----------------------------------------------
+
+/// A user in the mock directory: uid, password and groups.
+type MockUser = (&'static str, &'static str, Vec<&'static str>);
+
+fn mock_users() -> Vec<MockUser> {
+    vec![
+        ("alice", "alice-secret", vec![ADMINS]),
+        (
+            "bob",
+            "bob-secret",
+            vec!["CN=Krill-Readers,OU=Groups,DC=example,DC=org"],
+        ),
+        ("carol", "carol-secret", vec![]),
+    ]
+}
+
+fn primitive(class: TagClass, id: u64, value: Vec<u8>) -> StructureTag {
+    StructureTag {
+        class,
+        id,
+        payload: PL::P(value),
+    }
+}
+
+fn constructed(
+    class: TagClass,
+    id: u64,
+    inner: Vec<StructureTag>,
+) -> StructureTag {
+    StructureTag {
+        class,
+        id,
+        payload: PL::C(inner),
+    }
+}
+
+fn octets(value: &str) -> StructureTag {
+    primitive(TagClass::Universal, 4, value.as_bytes().to_vec())
+}
+
+fn integer(id: u64, value: u32) -> StructureTag {
+    let mut bytes = value.to_be_bytes().to_vec();
+    while bytes.len() > 1 && bytes[0] == 0 && bytes[1] < 0x80 {
+        bytes.remove(0);
+    }
+    primitive(TagClass::Universal, id, bytes)
+}
+
+fn ldap_result(op: u64, rc: u32) -> StructureTag {
+    constructed(
+        TagClass::Application,
+        op,
+        vec![integer(10, rc), octets(""), octets("")],
+    )
+}
+
+async fn send(stream: &mut TcpStream, msg_id: u32, op: StructureTag) {
+    let msg =
+        constructed(TagClass::Universal, 16, vec![integer(2, msg_id), op]);
+    let mut buf = BytesMut::new();
+    write::encode_into(&mut buf, msg).unwrap();
+    stream.write_all(&buf).await.unwrap();
+}
+
+async fn serve_connection(mut stream: TcpStream) {
+    let users = mock_users();
+    let mut bound: Option<MockUser> = None;
+    let mut buf: Vec<u8> = vec![];
+
+    loop {
+        let (consumed, msg) = match parse_tag(&buf) {
+            Ok((rest, msg)) => (buf.len() - rest.len(), msg),
+            Err(_) => {
+                let mut chunk = [0; 4096];
+                match stream.read(&mut chunk).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                }
+                continue;
+            }
+        };
+        buf.drain(..consumed);
+
+        let mut parts = msg.expect_constructed().unwrap().into_iter();
+        let msg_id = parts
+            .next()
+            .unwrap()
+            .expect_primitive()
+            .unwrap()
+            .iter()
+            .fold(0, |acc, b| (acc << 8) | *b as u32);
+        let op = parts.next().unwrap();
+
+        match op.id {
+            // BindRequest
+            0 => {
+                let mut bind = op.expect_constructed().unwrap().into_iter();
+                let _version = bind.next();
+                let dn = bind.next().unwrap().expect_primitive().unwrap();
+                let pw = bind.next().unwrap().expect_primitive().unwrap();
+                let dn = String::from_utf8(dn).unwrap();
+                let pw = String::from_utf8(pw).unwrap();
+
+                bound = users
+                    .iter()
+                    .find(|(uid, password, _)| {
+                        dn == format!("uid={},{}", uid, PEOPLE)
+                            && pw == *password
+                    })
+                    .cloned();
+
+                let rc = if bound.is_some() { 0 } else { 49 };
+                send(&mut stream, msg_id, ldap_result(1, rc)).await;
+            }
+            // UnbindRequest
+            2 => return,
+            // SearchRequest, only returns the entry of the bound user
+            3 => {
+                if let Some((uid, _, groups)) = &bound {
+                    let groups = groups.iter().map(|g| octets(g)).collect();
+                    let entry = constructed(
+                        TagClass::Application,
+                        4,
+                        vec![
+                            octets(&format!("uid={},{}", uid, PEOPLE)),
+                            constructed(
+                                TagClass::Universal,
+                                16,
+                                vec![constructed(
+                                    TagClass::Universal,
+                                    16,
+                                    vec![
+                                        octets("memberOf"),
+                                        constructed(
+                                            TagClass::Universal,
+                                            17,
+                                            groups,
+                                        ),
+                                    ],
+                                )],
+                            ),
+                        ],
+                    );
+                    send(&mut stream, msg_id, entry).await;
+                }
+                send(&mut stream, msg_id, ldap_result(5, 0)).await;
+            }
+            _ => return,
+        }
+    }
+}
+
+async fn start_mock_ldap_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ldap://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve_connection(stream));
+        }
+    });
+
+    url
+}
+
+//------------ Helpers -------------------------------------------------------
+
+struct LoginResponse {
+    status: u16,
+    token: Option<Token>,
+    attributes: HashMap<String, String>,
+}
+
+async fn login(username: &str, password: &str) -> LoginResponse {
+    let uri = "https://localhost:3000/auth/login";
+    let credentials =
+        BASE64_ENGINE.encode(format!("{}:{}", username, password));
+
+    let res = httpclient::client(uri)
+        .unwrap()
+        .post(uri)
+        .header("Authorization", format!("Basic {}", credentials))
+        .send()
+        .await
+        .unwrap();
+
+    let status = res.status().as_u16();
+    if status == 200 {
+        let json: serde_json::Value = res.json().await.unwrap();
+        let token = Token::from(json["token"].as_str().unwrap());
+        let attributes =
+            serde_json::from_value(json["attributes"].clone()).unwrap();
+        LoginResponse {
+            status,
+            token: Some(token),
+            attributes,
+        }
+    } else {
+        LoginResponse {
+            status,
+            token: None,
+            attributes: HashMap::new(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn functional_ldap() {
+    let url = start_mock_ldap_server().await;
+
+    let (data_dir, cleanup) = tmp_dir();
+    let storage_uri = mem_storage();
+    let mut config = test_config(
+        &storage_uri,
+        Some(&data_dir),
+        false,
+        false,
+        false,
+        false,
+    );
+    config.auth_type = AuthType::Ldap;
+    config.auth_ldap = Some(ConfigAuthLdap {
+        url,
+        starttls: false,
+        insecure: false,
+        bind_dn: format!("uid={{username}},{}", PEOPLE),
+        search_base: PEOPLE.to_string(),
+        search_filter: "(uid={username})".to_string(),
+        group_attribute: "memberOf".to_string(),
+        groups: vec![
+            ConfigAuthLdapGroup {
+                dn: ADMINS.to_string(),
+                attributes: HashMap::from([(
+                    "role".to_string(),
+                    "admin".to_string(),
+                )]),
+            },
+            ConfigAuthLdapGroup {
+                dn: READERS.to_string(),
+                attributes: HashMap::from([(
+                    "role".to_string(),
+                    "readonly".to_string(),
+                )]),
+            },
+        ],
+    });
+    start_krill(config).await;
+
+    let ca = ca_handle("ca");
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Users get the role of their group                              #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let alice = login("alice", "alice-secret").await;
+        assert_eq!(alice.status, 200);
+        assert_eq!(alice.attributes.get("role").unwrap(), "admin");
+
+        krill_with_token_may_fail(
+            alice.token.as_ref().unwrap(),
+            Command::CertAuth(CaCommand::Init(CertAuthInit::new(ca.clone()))),
+        )
+        .await
+        .unwrap();
+
+        // Group DNs are compared case-insensitively
+        let bob = login("bob", "bob-secret").await;
+        assert_eq!(bob.status, 200);
+        assert_eq!(bob.attributes.get("role").unwrap(), "readonly");
+
+        let bob = bob.token.unwrap();
+        krill_with_token_may_fail(&bob, Command::CertAuth(CaCommand::List))
+            .await
+            .unwrap();
+        assert!(krill_with_token_may_fail(
+            &bob,
+            Command::CertAuth(CaCommand::Delete(ca.clone())),
+        )
+        .await
+        .is_err());
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Wrong credentials and users without a role are rejected        #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        assert_ne!(login("alice", "wrong").await.status, 200);
+        assert_ne!(login("alice", "").await.status, 200);
+        assert_ne!(login("dave", "dave-secret").await.status, 200);
+
+        // Carol is known, but not a member of any of the configured groups
+        assert_ne!(login("carol", "carol-secret").await.status, 200);
+    }
+
+    cleanup();
+}
@@ -0,0 +1,106 @@
+//! Test that the objects published by a CA pass strict validation in the
+//! publication server.
+use std::str::FromStr;
+
+use rpki::{
+    ca::{idexchange::CaHandle, provisioning::ResourceClassName},
+    repository::resources::ResourceSet,
+};
+
+use krill::{
+    commons::api::{
+        AspaDefinition, ObjectName, RoaConfiguration, RoaConfigurationUpdates,
+    },
+    test::*,
+};
+
+/// Expects the CA to publish the given ROAs and ASPA, next to its
+/// manifest and CRL.
+async fn expect_objects(
+    ca: &CaHandle,
+    roas: &[RoaConfiguration],
+    aspa: &AspaDefinition,
+) {
+    let rcn_0 = ResourceClassName::from(0);
+
+    let mut expected_files = expected_mft_and_crl(ca, &rcn_0).await;
+    for roa in roas {
+        let payload = roa.payload().into_explicit_max_length();
+        expected_files.push(ObjectName::from(&payload).to_string());
+    }
+    expected_files.push(ObjectName::aspa(aspa.customer()).to_string());
+
+    assert!(
+        will_publish_embedded(
+            "published objects did not pass strict validation",
+            ca,
+            &expected_files
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn functional_publisher_strict_validation() {
+    let (data_dir, cleanup) = tmp_dir();
+    let storage_uri = mem_storage();
+
+    let mut config =
+        test_config(&storage_uri, Some(&data_dir), true, false, false, false);
+    config.publisher_strict_validation = true;
+    start_krill(config).await;
+
+    let testbed = ca_handle("testbed");
+    let ca = ca_handle("CA");
+    let ca_res = resources("AS65000", "10.0.0.0/16", "");
+
+    let route_1 = roa_configuration("10.0.0.0/24 => 64496");
+    let route_2 = roa_configuration("10.0.1.0/24-24 => 64496");
+    let aspa =
+        AspaDefinition::from_str("AS65000 => AS65002, AS65003").unwrap();
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#                      Set up CA  under testbed                  #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        set_up_ca_with_repo(&ca).await;
+        set_up_ca_under_parent_with_resources(&ca, &testbed, &ca_res).await;
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Publish ROAs and an ASPA, all of which pass strict validation  #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(route_1.clone());
+        updates.add(route_2.clone());
+        ca_route_authorizations_update(&ca, updates).await;
+        ca_aspas_add(&ca, aspa.clone()).await;
+
+        expect_objects(&ca, &[route_1.clone(), route_2.clone()], &aspa).await;
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Withdraw a ROA, the updated manifest and CRL must pass too     #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.remove(route_1.payload());
+        ca_route_authorizations_update(&ca, updates).await;
+
+        expect_objects(&ca, &[route_2], &aspa).await;
+    }
+
+    cleanup();
+}
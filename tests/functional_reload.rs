@@ -0,0 +1,176 @@
+//! Test that the users in the config file can be reloaded without a restart,
+//! and that an invalid config file does not affect the running server.
+#![cfg(feature = "multi-user")]
+
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
+use base64::engine::Engine as _;
+
+use krill::{
+    commons::{api::Token, util::httpclient},
+    constants::{PW_HASH_LOG_N, PW_HASH_P, PW_HASH_R},
+    daemon::config::{AuthType, Config},
+    test::*,
+};
+
+const SALT: &str =
+    "aaf0e4e1e15b7a5f3c4c2c3b1d55a0a7e2a6a4f7d0c8b4a1e1b2c3d4e5f60718";
+
+/// Returns the password hash as generated by 'krillc config user'.
+fn password_hash(id: &str, password: &str) -> String {
+    let params = scrypt::Params::new(
+        PW_HASH_LOG_N,
+        PW_HASH_R,
+        PW_HASH_P,
+        scrypt::Params::RECOMMENDED_LEN,
+    )
+    .unwrap();
+
+    let weak_salt = format!("krill-lagosta-{}", id);
+    let mut interim_hash: [u8; 32] = [0; 32];
+    scrypt::scrypt(
+        password.as_bytes(),
+        weak_salt.as_bytes(),
+        &params,
+        &mut interim_hash,
+    )
+    .unwrap();
+
+    let strong_salt = hex::decode(SALT).unwrap();
+    let mut final_hash: [u8; 32] = [0; 32];
+    scrypt::scrypt(&interim_hash, &strong_salt, &params, &mut final_hash)
+        .unwrap();
+
+    hex::encode(final_hash)
+}
+
+/// Writes a config file with the given admin users, identified by their
+/// id which is also used as their password.
+fn write_config(data_dir: &Path, users: &[&str]) {
+    let mut config = format!(
+        "admin_token = \"secret\"\n\
+         data_dir = \"{}\"\n\
+         auth_type = \"config-file\"\n\
+         \n\
+         [auth_users]\n",
+        data_dir.display()
+    );
+    for user in users {
+        config.push_str(&format!(
+            "\"{}\" = {{ attributes = {{ role = \"admin\" }}, \
+             password_hash = \"{}\", salt = \"{}\" }}\n",
+            user,
+            password_hash(user, user),
+            SALT
+        ));
+    }
+    std::fs::write(data_dir.join("krill.conf"), config).unwrap();
+}
+
+async fn login(username: &str) -> u16 {
+    let uri = "https://localhost:3000/auth/login";
+    let credentials =
+        BASE64_ENGINE.encode(format!("{}:{}", username, username));
+
+    httpclient::client(uri)
+        .unwrap()
+        .post(uri)
+        .header("Authorization", format!("Basic {}", credentials))
+        .send()
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+async fn reload() -> bool {
+    httpclient::post_empty(
+        "https://localhost:3000/api/v1/reload",
+        Some(&Token::from("secret")),
+    )
+    .await
+    .is_ok()
+}
+
+#[tokio::test]
+async fn functional_reload() {
+    let (data_dir, cleanup) = tmp_dir();
+    let storage_uri = mem_storage();
+    let config_file = data_dir.join("krill.conf");
+    write_config(&data_dir, &["alice"]);
+
+    let mut config = test_config(
+        &storage_uri,
+        Some(&data_dir),
+        false,
+        false,
+        false,
+        false,
+    );
+    config.auth_type = AuthType::ConfigFile;
+    config.auth_users = Config::read_config(config_file.to_str().unwrap())
+        .unwrap()
+        .auth_users;
+    config.config_file = Some(config_file.to_str().unwrap().to_string());
+    start_krill(config).await;
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Users added to the config file can login after a reload        #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        assert_eq!(login("alice").await, 200);
+        assert_ne!(login("bob").await, 200);
+
+        write_config(&data_dir, &["alice", "bob"]);
+        assert_ne!(login("bob").await, 200);
+
+        assert!(reload().await);
+        assert_eq!(login("alice").await, 200);
+        assert_eq!(login("bob").await, 200);
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# An invalid config file is rejected and the users are kept      #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        std::fs::write(&config_file, "auth_users = 42").unwrap();
+        assert!(!reload().await);
+        assert_eq!(login("alice").await, 200);
+        assert_eq!(login("bob").await, 200);
+
+        // Removed users can no longer login
+        write_config(&data_dir, &["bob"]);
+        assert!(reload().await);
+        assert_ne!(login("alice").await, 200);
+        assert_eq!(login("bob").await, 200);
+    }
+
+    cleanup();
+}
@@ -0,0 +1,136 @@
+//! Test that the content published by a CA is validated periodically, and
+//! that problems are reported as CA issues and in the metrics.
+use rpki::{ca::idexchange::CaHandle, repository::resources::ResourceSet};
+
+use krill::{
+    cli::{
+        options::{CaCommand, Command, PubServerCommand},
+        report::ApiResponse,
+    },
+    commons::api::{
+        CertAuthIssues, RepoFileDeleteCriteria, RoaConfigurationUpdates,
+    },
+    test::*,
+};
+
+async fn ca_issues(ca: &CaHandle) -> CertAuthIssues {
+    match krill_admin(Command::CertAuth(CaCommand::Issues(Some(ca.clone()))))
+        .await
+    {
+        ApiResponse::CertAuthIssues(issues) => issues,
+        _ => panic!("Expected CA issues"),
+    }
+}
+
+/// Returns the value of a metric for the CA, once it is available.
+async fn ca_metric(name: &str, ca: &CaHandle) -> Option<String> {
+    let prefix = format!("{}{{ca=\"{}\"}} ", name, ca);
+    krill_anon_http_get("metrics")
+        .await
+        .ok()
+        .and_then(|metrics| {
+            metrics.lines().find_map(|line| {
+                line.strip_prefix(&prefix).map(str::to_string)
+            })
+        })
+}
+
+/// Waits until the published content of the CA was validated after the
+/// given time, and returns the time of that validation.
+async fn wait_for_validation(ca: &CaHandle, after: i64) -> Option<i64> {
+    for _ in 0..90 {
+        if let Some(time) =
+            ca_metric("krill_ca_repo_validation_last_time", ca)
+                .await
+                .and_then(|time| time.parse::<i64>().ok())
+                .filter(|time| *time > after)
+        {
+            return Some(time);
+        }
+        sleep_seconds(1).await
+    }
+    None
+}
+
+#[tokio::test]
+async fn functional_repo_validation() {
+    let (data_dir, cleanup) = tmp_dir();
+    let storage_uri = mem_storage();
+
+    let mut config =
+        test_config(&storage_uri, Some(&data_dir), true, false, false, false);
+    config.ca_repo_validation_interval_minutes = Some(1);
+    start_krill(config).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let ca1_res = resources("AS65000", "10.0.0.0/16", "");
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#          Set up CA1 under testbed and publish a ROA            #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        set_up_ca_with_repo(&ca1).await;
+        set_up_ca_under_parent_with_resources(&ca1, &testbed, &ca1_res).await;
+
+        let updates = RoaConfigurationUpdates::new(
+            vec![roa_configuration("10.0.0.0/16-16 => 65000")],
+            vec![],
+        );
+        ca_route_authorizations_update(&ca1, updates).await;
+    }
+
+    let last_validation = {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#         The published content of CA1 is found valid            #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let time = wait_for_validation(&ca1, 0).await.unwrap();
+        assert_eq!(
+            ca_metric("krill_ca_repo_validation_issues", &ca1).await,
+            Some("0".to_string())
+        );
+        assert!(ca_issues(&ca1).await.is_empty());
+        time
+    };
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#   Remove the manifest of CA1 from the repository and expect    #");
+        info("#   that this is reported at the next validation                 #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let mft_uri = ca_key_for_rcn(&ca1, &rcn(0))
+            .await
+            .incoming_cert()
+            .mft_uri();
+        krill_embedded_pubd_admin(PubServerCommand::DeleteFiles(
+            RepoFileDeleteCriteria::new(mft_uri.clone()),
+        ))
+        .await;
+
+        wait_for_validation(&ca1, last_validation).await.unwrap();
+
+        let issues = ca_issues(&ca1).await;
+        assert!(!issues.is_empty());
+        assert!(issues.repo_content_issues().iter().any(|issue| {
+            issue.label() == "ca-repo-content-issue"
+                && issue.msg().contains(mft_uri.as_str())
+        }));
+        assert_ne!(
+            ca_metric("krill_ca_repo_validation_issues", &ca1).await,
+            Some("0".to_string())
+        );
+    }
+
+    cleanup();
+}
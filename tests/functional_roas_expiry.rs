@@ -0,0 +1,111 @@
+//! Test that time-limited ROAs are withdrawn automatically.
+use krill::{
+    commons::api::{RoaConfigurationUpdates, Timestamp},
+    test::*,
+};
+
+#[tokio::test]
+async fn functional_roas_expiry() {
+    let cleanup =
+        start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca = ca_handle("CA");
+    let ca_res = resources("AS65000", "10.0.0.0/16", "");
+
+    let permanent = roa_configuration("10.0.0.0/24-24 => 64496");
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Set up CA under testbed                                        #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        set_up_ca_with_repo(&ca).await;
+        set_up_ca_under_parent_with_resources(&ca, &testbed, &ca_res).await;
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Add a permanent and a time-limited ROA                         #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        // Set the expiry only now, because setting up the CA may take a
+        // while on a busy system.
+        let scrubbing =
+            roa_configuration("10.0.0.0/24-24 => 64511 # scrubbing")
+                .with_expires_at(Some(Timestamp::now().plus_seconds(10)));
+
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(permanent.clone());
+        updates.add(scrubbing.clone());
+        ca_route_authorizations_update(&ca, updates).await;
+
+        expect_configured_roas(&ca, &[permanent.clone(), scrubbing.clone()])
+            .await;
+
+        let report = ca_configured_roas(&ca).await.to_string();
+        assert!(report.contains("64511 [expires: "));
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# The time-limited ROA is removed once it expires                #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let mut removed = false;
+        for _ in 0..30 {
+            if ca_configured_roas(&ca).await.unpack().len() == 1 {
+                removed = true;
+                break;
+            }
+            sleep_seconds(1).await;
+        }
+        assert!(removed, "time-limited ROA was not removed");
+
+        expect_configured_roas(&ca, &[permanent]).await;
+
+        assert!(ca_history(&ca)
+            .await
+            .commands()
+            .iter()
+            .any(|record| record.summary.label == "cmd-ca-roas-expired"));
+    }
+
+    cleanup();
+}
@@ -0,0 +1,192 @@
+//! Test bulk export and import of ROA configurations.
+use krill::{
+    commons::api::{
+        RoaConfigurationUpdates, RoaFileFormat, RoaImport, RoaImportMode,
+        RoaImportStatus,
+    },
+    test::*,
+};
+
+#[tokio::test]
+async fn functional_roas_import() {
+    let cleanup =
+        start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca = ca_handle("CA");
+    let ca_res = resources("AS65000", "10.0.0.0/16", "");
+
+    let office = roa_configuration("10.0.0.0/24-24 => 64496 # office");
+    let lab = roa_configuration("10.0.1.0/24-24 => 64496");
+    let lab_commented = roa_configuration("10.0.1.0/24-24 => 64496 # lab");
+    let scrubbing = roa_configuration("10.0.2.0/24 => 64497");
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Set up CA under testbed with some ROAs                         #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        set_up_ca_with_repo(&ca).await;
+        set_up_ca_under_parent_with_resources(&ca, &testbed, &ca_res).await;
+
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(office.clone());
+        updates.add(lab.clone());
+        ca_route_authorizations_update(&ca, updates).await;
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Export the ROAs                                                #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let csv =
+            ca_route_authorizations_export(&ca, RoaFileFormat::Csv).await;
+        assert!(csv.contains("AS64496,10.0.0.0/24,24,office\n"));
+        assert!(csv.contains("AS64496,10.0.1.0/24,24,\n"));
+
+        let slurm =
+            ca_route_authorizations_export(&ca, RoaFileFormat::Slurm).await;
+        assert_eq!(
+            RoaFileFormat::Slurm.parse(&slurm).unwrap().len(),
+            2,
+            "expected both ROAs in the SLURM export"
+        );
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Merge a CSV file, first as a dry run                           #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let csv = "ASN,IP Prefix,Max Length,Comment\n\
+                   AS64496,10.0.0.0/24,24,\n\
+                   AS64496,10.0.1.0/24,24,lab\n\
+                   AS64497,10.0.2.0/24,,\n";
+        let configurations = RoaFileFormat::Csv.parse(csv).unwrap();
+
+        let dry_run = RoaImport::new(
+            configurations.clone(),
+            RoaImportMode::Merge,
+            true,
+            false,
+        );
+        let result = ca_route_authorizations_import(&ca, dry_run).await;
+        assert_eq!(result.status(), RoaImportStatus::DryRun);
+        assert_eq!(result.updates().added().len(), 2);
+        expect_configured_roas(&ca, &[office.clone(), lab.clone()]).await;
+
+        // The comment for the office ROA is kept, even though the file
+        // has none.
+        let merge = RoaImport::new(
+            configurations,
+            RoaImportMode::Merge,
+            false,
+            false,
+        );
+        let result = ca_route_authorizations_import(&ca, merge).await;
+        assert_eq!(result.status(), RoaImportStatus::Applied);
+        expect_configured_roas(
+            &ca,
+            &[office.clone(), lab_commented.clone(), scrubbing.clone()],
+        )
+        .await;
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Importing an export changes nothing                            #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let slurm =
+            ca_route_authorizations_export(&ca, RoaFileFormat::Slurm).await;
+        let replace = RoaImport::new(
+            RoaFileFormat::Slurm.parse(&slurm).unwrap(),
+            RoaImportMode::Replace,
+            false,
+            false,
+        );
+        let result = ca_route_authorizations_import(&ca, replace).await;
+        assert_eq!(result.status(), RoaImportStatus::Unchanged);
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Replace all ROAs using a JSON file                             #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let json = r#"[ { "asn": 64497, "prefix": "10.0.2.0/24" } ]"#;
+        let replace = RoaImport::new(
+            RoaFileFormat::detect(json).parse(json).unwrap(),
+            RoaImportMode::Replace,
+            false,
+            false,
+        );
+        let result = ca_route_authorizations_import(&ca, replace).await;
+        assert_eq!(result.status(), RoaImportStatus::Applied);
+        assert_eq!(result.updates().removed().len(), 2);
+        expect_configured_roas(&ca, &[scrubbing]).await;
+    }
+
+    cleanup();
+}
@@ -0,0 +1,152 @@
+//! Test that ROA updates can be scheduled, cancelled and are activated.
+use krill::{
+    commons::api::{RoaConfigurationUpdates, Timestamp},
+    test::*,
+};
+
+#[tokio::test]
+async fn functional_roas_scheduled() {
+    let cleanup =
+        start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca = ca_handle("CA");
+    let ca_res = resources("AS65000", "10.0.0.0/16", "");
+
+    let roa_1 = roa_configuration("10.0.0.0/24-24 => 64496");
+    let roa_2 = roa_configuration("10.0.1.0/24-24 => 64496");
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Set up CA under testbed                                        #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        set_up_ca_with_repo(&ca).await;
+        set_up_ca_under_parent_with_resources(&ca, &testbed, &ca_res).await;
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Schedule two updates, both are pending                         #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(roa_1.clone());
+        ca_route_authorizations_schedule(
+            &ca,
+            Timestamp::now().plus_seconds(5),
+            updates,
+        )
+        .await;
+
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(roa_2.clone());
+        ca_route_authorizations_schedule(
+            &ca,
+            Timestamp::now().plus_seconds(24 * 3600),
+            updates,
+        )
+        .await;
+
+        let scheduled = ca_route_authorizations_scheduled(&ca).await;
+        assert_eq!(scheduled.iter().count(), 2);
+        expect_configured_roas(&ca, &[]).await;
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Cancel the second update, it cannot be cancelled twice         #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let scheduled = ca_route_authorizations_scheduled(&ca).await;
+        let id = scheduled
+            .iter()
+            .find(|s| s.updates().added().contains(&roa_2))
+            .unwrap()
+            .id();
+
+        ca_route_authorizations_schedule_cancel(&ca, id).await;
+        ca_route_authorizations_schedule_cancel_expect_error(&ca, id).await;
+
+        let scheduled = ca_route_authorizations_scheduled(&ca).await;
+        assert_eq!(scheduled.iter().count(), 1);
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# The first update is activated once its time has passed         #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let mut activated = false;
+        for _ in 0..30 {
+            if ca_route_authorizations_scheduled(&ca).await.is_empty() {
+                activated = true;
+                break;
+            }
+            sleep_seconds(1).await;
+        }
+        assert!(activated, "scheduled ROA updates were not activated");
+
+        expect_configured_roas(&ca, &[roa_1]).await;
+
+        let labels: Vec<String> = ca_history(&ca)
+            .await
+            .commands()
+            .iter()
+            .map(|record| record.summary.label.clone())
+            .collect();
+        assert!(labels.iter().any(|l| l == "cmd-ca-roas-scheduled"));
+        assert!(labels.iter().any(|l| l == "cmd-ca-roas-scheduled-cancel"));
+        assert!(labels.iter().any(|l| l == "cmd-ca-roas-scheduled-activate"));
+    }
+
+    cleanup();
+}
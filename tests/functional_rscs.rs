@@ -0,0 +1,216 @@
+//! Perform functional tests for RPKI Signed Checklists (RFC 9323)
+use rpki::{ca::idexchange::CaHandle, repository::resources::ResourceSet};
+
+use krill::{
+    cli::{
+        options::{CaCommand, Command},
+        report::ApiResponse,
+    },
+    commons::{
+        api::{RscList, RscName},
+        crypto::SignSupport,
+    },
+    daemon::ca::{
+        ChecklistEntry, RpkiSignedChecklist, RscContentRequest,
+        RscVerification, RscVerifyRequest,
+    },
+    test::*,
+};
+
+const ROUTER_CONFIG: &[u8] = b"router bgp 65000\n neighbor 10.0.0.1\n";
+const LOA_TEXT: &[u8] = b"Letter of Authority for 10.0.0.0/24";
+
+fn rsc_sign_cmd(
+    ca: &CaHandle,
+    name: &str,
+    resources: &ResourceSet,
+    checklist: Vec<ChecklistEntry>,
+) -> Command {
+    let validity = SignSupport::sign_validity_days(14);
+    let request =
+        RscContentRequest::new(resources.clone(), validity, checklist);
+    Command::CertAuth(CaCommand::RscSign(
+        ca.clone(),
+        name.to_string(),
+        request,
+    ))
+}
+
+async fn rsc_list(ca: &CaHandle) -> RscList {
+    match krill_admin(Command::CertAuth(CaCommand::RscList(ca.clone()))).await
+    {
+        ApiResponse::RscList(list) => list,
+        _ => panic!("Expected RscList"),
+    }
+}
+
+async fn rsc_show(ca: &CaHandle, name: &RscName) -> RpkiSignedChecklist {
+    match krill_admin(Command::CertAuth(CaCommand::RscShow(
+        ca.clone(),
+        name.clone(),
+        None,
+    )))
+    .await
+    {
+        ApiResponse::Rsc(rsc) => rsc,
+        _ => panic!("Expected RSC"),
+    }
+}
+
+fn rsc_verify_cmd(
+    ca: &CaHandle,
+    rsc: &RpkiSignedChecklist,
+    files: Vec<ChecklistEntry>,
+) -> Command {
+    let request = RscVerifyRequest::new(rsc.clone(), files);
+    Command::CertAuth(CaCommand::RscVerify(ca.clone(), request))
+}
+
+async fn rsc_verify(
+    ca: &CaHandle,
+    rsc: &RpkiSignedChecklist,
+    files: Vec<ChecklistEntry>,
+) -> RscVerification {
+    match krill_admin(rsc_verify_cmd(ca, rsc, files)).await {
+        ApiResponse::RscVerification(verification) => verification,
+        _ => panic!("Expected RscVerification"),
+    }
+}
+
+#[tokio::test]
+async fn functional_rscs() {
+    let cleanup =
+        start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+
+    let ca1 = ca_handle("CA1");
+    let ca1_res = resources("AS65000", "10.0.0.0/16", "");
+
+    let ca2 = ca_handle("CA2");
+    let ca2_res = resources("", "10.1.0.0/16", "");
+
+    let router_config =
+        ChecklistEntry::for_file(Some("router.conf".into()), ROUTER_CONFIG);
+    let loa = ChecklistEntry::for_file(Some("loa.txt".into()), LOA_TEXT);
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#                 Set up CA1 and CA2 under testbed               #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        set_up_ca_with_repo(&ca1).await;
+        set_up_ca_under_parent_with_resources(&ca1, &testbed, &ca1_res).await;
+
+        set_up_ca_with_repo(&ca2).await;
+        set_up_ca_under_parent_with_resources(&ca2, &testbed, &ca2_res).await;
+    }
+
+    let rsc_name = "loa".to_string();
+    let rsc_res = resources("AS65000", "10.0.0.0/24", "");
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#                    Sign an RSC under CA1                       #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        krill_admin(rsc_sign_cmd(
+            &ca1,
+            &rsc_name,
+            &rsc_res,
+            vec![router_config.clone(), loa.clone()],
+        ))
+        .await;
+
+        assert_eq!(
+            rsc_list(&ca1).await,
+            RscList::new(vec![rsc_name.clone()])
+        );
+        assert_eq!(rsc_list(&ca2).await, RscList::new(vec![]));
+
+        // The name must be unique within the CA
+        krill_admin_expect_error(rsc_sign_cmd(
+            &ca1,
+            &rsc_name,
+            &rsc_res,
+            vec![loa.clone()],
+        ))
+        .await;
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#             CA1 cannot sign for resources it lacks             #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        krill_admin_expect_error(rsc_sign_cmd(
+            &ca1,
+            "not-held",
+            &ca2_res,
+            vec![loa.clone()],
+        ))
+        .await;
+
+        krill_admin_expect_error(rsc_sign_cmd(
+            &ca1,
+            "empty",
+            &rsc_res,
+            vec![],
+        ))
+        .await;
+
+        assert_eq!(
+            rsc_list(&ca1).await,
+            RscList::new(vec![rsc_name.clone()])
+        );
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#                     Verify the RSC of CA1                      #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let rsc = rsc_show(&ca1, &rsc_name).await;
+
+        let verification =
+            rsc_verify(&ca1, &rsc, vec![router_config.clone(), loa.clone()])
+                .await;
+        assert_eq!(verification.resources(), &rsc_res);
+        assert_eq!(
+            verification.checklist(),
+            &[router_config.clone(), loa.clone()]
+        );
+        assert_eq!(verification.files().len(), 2);
+
+        // A subset of the listed files also verifies
+        let verification = rsc_verify(&ca1, &rsc, vec![loa.clone()]).await;
+        assert_eq!(verification.files(), std::slice::from_ref(&loa));
+
+        // A file which was changed does not
+        let changed =
+            ChecklistEntry::for_file(Some("loa.txt".into()), b"changed");
+        krill_admin_expect_error(rsc_verify_cmd(&ca1, &rsc, vec![changed]))
+            .await;
+
+        // Neither does a listed file under a different name
+        let renamed =
+            ChecklistEntry::for_file(Some("other.txt".into()), LOA_TEXT);
+        krill_admin_expect_error(rsc_verify_cmd(&ca1, &rsc, vec![renamed]))
+            .await;
+
+        // And CA2 did not sign it
+        krill_admin_expect_error(rsc_verify_cmd(&ca2, &rsc, vec![loa])).await;
+    }
+
+    cleanup();
+}
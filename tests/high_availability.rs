@@ -0,0 +1,63 @@
+//! Test active/standby high availability with two nodes sharing storage.
+use rpki::ca::idexchange::CaHandle;
+
+use krill::{
+    cli::options::{CaCommand, Command},
+    commons::api::CertAuthInit,
+    test::*,
+};
+
+#[tokio::test]
+async fn standby_takes_over_from_leader() {
+    let storage_uri = mem_storage();
+
+    let (dir_1, cleanup_1) = tmp_dir();
+    let mut config_1 =
+        test_config(&storage_uri, Some(&dir_1), false, false, false, false);
+    config_1.ha.ha_enabled = true;
+    config_1.ha.ha_node_name = Some("node-1".to_string());
+    config_1.ha.ha_lease_seconds = 3;
+
+    let (dir_2, cleanup_2) = tmp_dir();
+    let mut config_2 =
+        test_config(&storage_uri, Some(&dir_2), false, false, false, false);
+    config_2.port = 3002;
+    config_2.ha.ha_enabled = true;
+    config_2.ha.ha_node_name = Some("node-2".to_string());
+    config_2.ha.ha_lease_seconds = 3;
+
+    // The first node becomes the leader, the second node is standby.
+    let node_1 = start_krill_node(config_1, KRILL_SERVER_URI).await;
+    let _node_2 = start_krill_node(config_2, KRILL_SECOND_SERVER_URI).await;
+
+    let ca = ca_handle("CA");
+    let other_ca = ca_handle("other");
+    let third_ca = ca_handle("third");
+    let init = |ca: &CaHandle| {
+        Command::CertAuth(CaCommand::Init(CertAuthInit::new(ca.clone())))
+    };
+
+    // Changes are made on the leader, the standby can read them but
+    // refuses to make changes itself.
+    init_ca(&ca).await;
+    assert_eq!(ca_details_krill2(&ca).await.handle(), &ca);
+
+    let err = krill2_admin_may_fail(init(&other_ca)).await.unwrap_err();
+    assert!(err.to_string().contains("standby"));
+
+    // Stop the scheduler and lease renewal of the first node. Its HTTP
+    // listener keeps running, so we can see that it steps down.
+    node_1.abort();
+    sleep_seconds(5).await;
+
+    // The standby took over and now accepts changes, while the old
+    // leader refuses them.
+    krill2_admin(init(&other_ca)).await;
+    assert_eq!(ca_details(&other_ca).await.handle(), &other_ca);
+
+    let err = krill_admin_may_fail(init(&third_ca)).await.unwrap_err();
+    assert!(err.to_string().contains("standby"));
+
+    cleanup_1();
+    cleanup_2();
+}
@@ -218,6 +218,52 @@ async fn migrate_repository() {
             httpclient::get_text(snapshot_uri, None).await.unwrap();
         assert!(snapshot_xml.starts_with("<snapshot"));
 
+        // Verify that conditional requests for unchanged files result in a
+        // 304 Not Modified response. The notification file is only compared
+        // using its ETag, other files can use either of the validators.
+        let notification_uri =
+            format!("{}rrdp/notification.xml", KRILL_SERVER_URI);
+        for uri in [notification_uri.as_str(), snapshot_uri] {
+            let is_notification = uri == notification_uri;
+            let client = httpclient::client(uri).unwrap();
+            let res = client.get(uri).send().await.unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+            let etag = res.headers()["ETag"].clone();
+            let last_modified = res.headers()["Last-Modified"].clone();
+            assert!(res.headers()["Cache-Control"]
+                .to_str()
+                .unwrap()
+                .starts_with("public"));
+
+            let res = client
+                .get(uri)
+                .header("If-None-Match", etag)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+            let res = client
+                .get(uri)
+                .header("If-Modified-Since", last_modified)
+                .send()
+                .await
+                .unwrap();
+            if is_notification {
+                assert_eq!(res.status(), StatusCode::OK);
+            } else {
+                assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+            }
+
+            let res = client
+                .get(uri)
+                .header("If-None-Match", "\"other\"")
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+
         // Verify that attempting to fetch a valid subdirectory results in an
         // error rather than a panic.
         let mut url = urlparse::urlparse(snapshot_uri);
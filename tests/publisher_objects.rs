@@ -0,0 +1,121 @@
+//! Test that the objects of a publisher can be listed, and compared with
+//! the objects that the publishing CA believes it has published.
+use std::str::FromStr;
+
+use rpki::{
+    ca::idexchange::PublisherHandle, repository::resources::ResourceSet,
+};
+
+use krill::{
+    cli::{options::PubServerCommand, report::ApiResponse},
+    commons::api::{
+        PublisherObjectType, PublisherObjects, PublisherObjectsDiff,
+        RepoFileDeleteCriteria,
+    },
+    test::*,
+};
+
+fn handle(publisher: &str) -> PublisherHandle {
+    PublisherHandle::from_str(publisher).unwrap()
+}
+
+async fn publisher_objects(publisher: &str) -> PublisherObjects {
+    let command = PubServerCommand::PublisherObjects(handle(publisher));
+    match krill_embedded_pubd_admin(command).await {
+        ApiResponse::PublisherObjects(objects) => objects,
+        _ => panic!("Expected publisher objects"),
+    }
+}
+
+async fn publisher_objects_diff(publisher: &str) -> PublisherObjectsDiff {
+    let command = PubServerCommand::PublisherObjectsDiff(handle(publisher));
+    match krill_embedded_pubd_admin(command).await {
+        ApiResponse::PublisherObjectsDiff(diff) => diff,
+        _ => panic!("Expected publisher objects diff"),
+    }
+}
+
+#[tokio::test]
+async fn publisher_objects_and_diff() {
+    let (data_dir, cleanup) = tmp_dir();
+    let storage_uri = mem_storage();
+
+    let config =
+        test_config(&storage_uri, Some(&data_dir), true, false, false, false);
+    start_krill(config).await;
+
+    let testbed = ca_handle("testbed");
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# List the objects of the testbed publisher                      #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    let crl = {
+        let objects = publisher_objects("testbed").await;
+        let types: Vec<_> =
+            objects.objects().iter().map(|o| o.object_type()).collect();
+        assert!(types.contains(&PublisherObjectType::Manifest));
+        assert!(types.contains(&PublisherObjectType::Crl));
+        assert!(!types.contains(&PublisherObjectType::Unknown));
+
+        let mft = objects
+            .objects()
+            .iter()
+            .find(|o| o.object_type() == PublisherObjectType::Manifest)
+            .unwrap();
+        assert!(mft.expires().is_some());
+
+        objects
+            .objects()
+            .iter()
+            .find(|o| o.object_type() == PublisherObjectType::Crl)
+            .unwrap()
+            .clone()
+    };
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# The diff is empty, until a file is removed from the repository #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        assert!(publisher_objects_diff("testbed").await.is_empty());
+
+        let criteria = RepoFileDeleteCriteria::new(crl.uri().clone());
+        krill_embedded_pubd_admin(PubServerCommand::DeleteFiles(criteria))
+            .await;
+
+        let diff = publisher_objects_diff("testbed").await;
+        assert_eq!(diff.differences().len(), 1);
+        let difference = &diff.differences()[0];
+        assert_eq!(difference.uri(), crl.uri());
+        assert_eq!(difference.repository(), None);
+        assert_eq!(difference.ca(), Some(crl.hash()));
+    }
+
+    cleanup();
+}
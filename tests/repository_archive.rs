@@ -0,0 +1,132 @@
+//! Test that archived RRDP serials can be listed, and that the repository
+//! content at an earlier serial can be exported.
+use rpki::repository::resources::ResourceSet;
+
+use krill::{
+    cli::{
+        options::{PubServerCommand, RrdpArchiveExport},
+        report::ApiResponse,
+    },
+    commons::{
+        api::{RoaConfigurationUpdates, RrdpArchiveIndex, RrdpArchiveQuery},
+        util::file,
+    },
+    test::*,
+};
+
+async fn archive_index() -> RrdpArchiveIndex {
+    match krill_embedded_pubd_admin(PubServerCommand::ArchiveIndex).await {
+        ApiResponse::RrdpArchiveIndex(index) => index,
+        _ => panic!("Expected archive index"),
+    }
+}
+
+#[tokio::test]
+async fn repository_archive() {
+    let (data_dir, cleanup) = tmp_dir();
+    let (export_dir, cleanup_export) = tmp_dir();
+    let storage_uri = mem_storage();
+
+    let mut config =
+        test_config(&storage_uri, Some(&data_dir), true, false, false, false);
+    config.rrdp_updates_config.rrdp_files_archive = true;
+    config.rrdp_updates_config.rrdp_delta_files_min_nr = 1;
+    config.rrdp_updates_config.rrdp_delta_files_max_nr = 1;
+    start_krill(config).await;
+
+    let testbed = ca_handle("testbed");
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Publish and then remove a ROA, and list the archived serials   #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    let roa_serial = {
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(roa_configuration("10.0.0.0/24 => 64496"));
+        ca_route_authorizations_update(&testbed, updates).await;
+        sleep_seconds(2).await;
+
+        let roa_serial =
+            archive_index().await.serials().last().unwrap().serial();
+
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.remove(roa_payload("10.0.0.0/24 => 64496"));
+        ca_route_authorizations_update(&testbed, updates).await;
+        sleep_seconds(2).await;
+
+        let index = archive_index().await;
+        assert!(index.serials().last().unwrap().serial() > roa_serial);
+        assert_eq!(index.serials().first().unwrap().serial(), 1);
+
+        roa_serial
+    };
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# Export the repository content at the serial with the ROA       #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        let query = RrdpArchiveQuery::Serial(roa_serial, None);
+
+        let state = match krill_embedded_pubd_admin(
+            PubServerCommand::ArchiveExport(query.clone(), None),
+        )
+        .await
+        {
+            ApiResponse::RrdpArchiveState(state) => state,
+            _ => panic!("Expected archive state"),
+        };
+        assert_eq!(state.serial(), roa_serial);
+        let roa = state
+            .objects()
+            .iter()
+            .find(|object| object.uri().to_string().ends_with(".roa"))
+            .expect("Expected a ROA at this serial");
+
+        let dir = export_dir.join("dir");
+        krill_embedded_pubd_admin(PubServerCommand::ArchiveExport(
+            query.clone(),
+            Some(RrdpArchiveExport::Dir(dir.clone())),
+        ))
+        .await;
+        let exported = file::crawl_derive_rsync_uri(&dir).unwrap();
+        assert_eq!(exported.len(), state.objects().len());
+        assert!(exported.iter().any(|f| f.uri() == roa.uri()));
+
+        let tarball = export_dir.join("repo.tar.gz");
+        krill_embedded_pubd_admin(PubServerCommand::ArchiveExport(
+            query,
+            Some(RrdpArchiveExport::Tarball(tarball.clone())),
+        ))
+        .await;
+        assert!(tarball.exists());
+    }
+
+    cleanup();
+    cleanup_export();
+}
@@ -0,0 +1,143 @@
+//! Test that the repository content is pushed to a configured mirror, and
+//! that the mirror endpoint is refused by a server with its own repository.
+use std::path::Path;
+
+use bytes::Bytes;
+use rpki::repository::resources::ResourceSet;
+
+use krill::{
+    cli::{options::PubServerCommand, report::ApiResponse},
+    commons::{
+        api::{RoaConfigurationUpdates, Token},
+        util::{file, httpclient},
+    },
+    constants::REPOSITORY_DIR,
+    daemon::config::{RepositoryMirrorConfig, RepositoryMirrorTarget},
+    pubd::RepoStats,
+    test::*,
+};
+
+async fn repo_stats() -> RepoStats {
+    match krill_embedded_pubd_admin(PubServerCommand::RepositoryStats).await {
+        ApiResponse::RepoStats(stats) => stats,
+        _ => panic!("Expected repository stats"),
+    }
+}
+
+/// Waits until the mirror has the current notification file, and all files
+/// that it refers to.
+async fn wait_for_mirror(repo_dir: &Path, mirror_dir: &Path) -> bool {
+    let path = "rrdp/notification.xml";
+    for _ in 0..30 {
+        let local = file::read(&repo_dir.join(path)).ok();
+        let mirrored = file::read(&mirror_dir.join(path)).ok();
+        if local.is_some() && local == mirrored {
+            return true;
+        }
+        sleep_seconds(1).await
+    }
+    false
+}
+
+async fn mirror_put(path: &str) -> Option<String> {
+    httpclient::put_binary_with_headers(
+        &format!("https://localhost:3000/api/v1/pubd/mirror/{}", path),
+        Bytes::from_static(b"content"),
+        Some(&Token::from("secret")),
+        Default::default(),
+    )
+    .await
+    .err()
+    .map(|e| match e {
+        httpclient::Error::ErrorResponseWithJson(_, _, res) => {
+            res.label().to_string()
+        }
+        _ => e.to_string(),
+    })
+}
+
+#[tokio::test]
+async fn repository_mirrors() {
+    let (data_dir, cleanup) = tmp_dir();
+    let (mirror_dir, cleanup_mirror) = tmp_dir();
+    let storage_uri = mem_storage();
+    let repo_dir = data_dir.join(REPOSITORY_DIR);
+
+    let mut config =
+        test_config(&storage_uri, Some(&data_dir), true, false, false, false);
+    config.repository_mirrors = vec![RepositoryMirrorConfig {
+        name: "backup".to_string(),
+        lag_alert_seconds: 900,
+        target: RepositoryMirrorTarget::Dir {
+            path: mirror_dir.clone(),
+        },
+    }];
+    start_krill(config).await;
+
+    let testbed = ca_handle("testbed");
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# The repository content is pushed to the mirror                 #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        assert!(wait_for_mirror(&repo_dir, &mirror_dir).await);
+
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(roa_configuration("10.0.0.0/24 => 64496"));
+        ca_route_authorizations_update(&testbed, updates).await;
+        sleep_seconds(2).await;
+
+        assert!(wait_for_mirror(&repo_dir, &mirror_dir).await);
+
+        let stats = repo_stats().await;
+        let mirror = &stats.mirrors()[0];
+        assert_eq!(mirror.name(), "backup");
+        assert_eq!(mirror.serial(), Some(stats.serial()));
+        assert!(mirror.last_success().is_some());
+        assert!(!mirror.lagging());
+    }
+
+    info(
+        "##################################################################",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "# A server with its own repository cannot be used as a mirror    #",
+    );
+    info(
+        "#                                                                #",
+    );
+    info(
+        "##################################################################",
+    );
+    info("");
+    {
+        assert_eq!(
+            mirror_put("rrdp/..%2F..%2Fkrill.conf").await.as_deref(),
+            Some("pub-mirror-invalid-path")
+        );
+        assert_eq!(
+            mirror_put("rrdp/notification.xml").await.as_deref(),
+            Some("pub-repo-initialized")
+        );
+    }
+
+    cleanup();
+    cleanup_mirror();
+}
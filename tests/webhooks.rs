@@ -0,0 +1,172 @@
+//! Test that configured webhooks are notified about CA events.
+use std::time::Duration;
+
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
+use rpki::repository::resources::ResourceSet;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    sync::mpsc,
+    time::timeout,
+};
+use url::Url;
+
+use krill::{
+    commons::api::RoaConfigurationUpdates,
+    daemon::{config::WebhookConfig, webhooks::WebhookEventKind},
+    test::*,
+};
+
+const SECRET: &str = "webhook secret";
+
+/// A received notification: the request head (lower case) and the body.
+type Received = (String, String);
+
+#[tokio::test]
+async fn webhooks_are_notified() {
+    // Start a minimal HTTP endpoint which accepts all notifications.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    tokio::spawn(receive_webhooks(listener, sender));
+
+    let (data_dir, cleanup) = tmp_dir();
+    let storage_uri = mem_storage();
+    let mut config =
+        test_config(&storage_uri, Some(&data_dir), true, false, false, false);
+    config.webhooks = vec![WebhookConfig {
+        url: Url::parse(&format!("http://127.0.0.1:{}/krill", port)).unwrap(),
+        events: vec![
+            WebhookEventKind::RoasUpdated,
+            WebhookEventKind::ChildSuspended,
+        ],
+        secret: Some(SECRET.to_string()),
+        retries: 0,
+    }];
+    start_krill(config).await;
+
+    let testbed = ca_handle("testbed");
+    let ca = ca_handle("CA");
+    let ca_res = resources("AS65000", "10.0.0.0/16", "");
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    // Adding a ROA results in a signed 'roas_updated' notification.
+    {
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(roa_configuration("10.0.0.0/24 => 64496"));
+        ca_route_authorizations_update(&testbed, updates).await;
+
+        let notification =
+            expect_notification(&mut receiver, "roas_updated").await;
+        assert_eq!(notification["ca"], "testbed");
+    }
+
+    // Suspending a child results in a 'child_suspended' notification.
+    {
+        set_up_ca_with_repo(&ca).await;
+        set_up_ca_under_parent_with_resources(&ca, &testbed, &ca_res).await;
+        ca_suspend_child(&testbed, &ca).await;
+
+        let notification =
+            expect_notification(&mut receiver, "child_suspended").await;
+        assert_eq!(notification["ca"], "testbed");
+        assert_eq!(notification["child"], "CA");
+    }
+
+    cleanup();
+}
+
+/// Waits for a notification for the given event, verifies its signature
+/// and returns it. Notifications for other events are skipped.
+async fn expect_notification(
+    receiver: &mut mpsc::UnboundedReceiver<Received>,
+    event: &str,
+) -> serde_json::Value {
+    loop {
+        let (head, body) = timeout(Duration::from_secs(30), receiver.recv())
+            .await
+            .expect("no webhook notification received")
+            .unwrap();
+
+        let signature = head
+            .lines()
+            .find_map(|line| line.strip_prefix("x-krill-signature: sha256="))
+            .expect("notification is not signed");
+        assert_eq!(signature.trim(), hmac_sha256(&body));
+
+        let notification: serde_json::Value =
+            serde_json::from_str(&body).unwrap();
+        if notification["event"] == event {
+            return notification;
+        }
+    }
+}
+
+fn hmac_sha256(body: &str) -> String {
+    let key = PKey::hmac(SECRET.as_bytes()).unwrap();
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+    signer.update(body.as_bytes()).unwrap();
+    hex::encode(signer.sign_to_vec().unwrap())
+}
+
+/// Accepts HTTP/1.1 POST requests, answers them with '204 No Content' and
+/// passes on the request head and body.
+async fn receive_webhooks(
+    listener: TcpListener,
+    sender: mpsc::UnboundedSender<Received>,
+) {
+    loop {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+        let mut body_start = None;
+        let mut content_length = 0;
+
+        loop {
+            let read = stream.read(&mut buf).await.unwrap();
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..read]);
+
+            if body_start.is_none() {
+                if let Some(pos) =
+                    request.windows(4).position(|w| w == b"\r\n\r\n")
+                {
+                    let head = String::from_utf8_lossy(&request[..pos])
+                        .to_lowercase();
+                    content_length = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length:"))
+                        .map(|len| len.trim().parse().unwrap())
+                        .unwrap_or(0);
+                    body_start = Some(pos + 4);
+                }
+            }
+
+            if let Some(start) = body_start {
+                if request.len() >= start + content_length {
+                    break;
+                }
+            }
+        }
+
+        let start = body_start.unwrap();
+        let head = String::from_utf8_lossy(&request[..start]).to_lowercase();
+        let body = String::from_utf8(
+            request[start..start + content_length].to_vec(),
+        )
+        .unwrap();
+
+        stream
+            .write_all(
+                b"HTTP/1.1 204 No Content\r\nconnection: close\r\n\r\n",
+            )
+            .await
+            .unwrap();
+
+        let _ = sender.send((head, body));
+    }
+}